@@ -0,0 +1,170 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::builder::parse_address;
+use crate::stall::Stall;
+use alloy::network::Network;
+use alloy::primitives::{address, keccak256, Address, B256};
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use kailua_contracts::{IENSRegistry, IENSResolver};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tracing::info;
+
+/// The ENS registry deployed at the same address on all networks that support ENS
+pub const ENS_REGISTRY_ADDRESS: Address = address!("00000000000C2E074eC69A0dFb2997BA6C7d2e1e");
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct AddressBookArgs {
+    /// Path to a JSON file mapping names to addresses, usable in place of contract addresses
+    #[clap(long, env)]
+    pub address_book: Option<PathBuf>,
+}
+
+/// An address argument given as a hex address, an address book alias or an ENS name
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AddressArg {
+    Address(Address),
+    Name(String),
+}
+
+impl FromStr for AddressArg {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("0x") {
+            Ok(Self::Address(parse_address(s)?))
+        } else if s.is_empty() {
+            bail!("Empty address");
+        } else {
+            Ok(Self::Name(s.to_string()))
+        }
+    }
+}
+
+impl Display for AddressArg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "{address}"),
+            Self::Name(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl From<Address> for AddressArg {
+    fn from(address: Address) -> Self {
+        Self::Address(address)
+    }
+}
+
+/// Returns the ENS namehash of the name
+pub fn namehash(name: &str) -> B256 {
+    let mut node = B256::ZERO;
+    if name.is_empty() {
+        return node;
+    }
+    for label in name.to_lowercase().rsplit('.') {
+        let mut preimage = [0u8; 64];
+        preimage[..32].copy_from_slice(node.as_slice());
+        preimage[32..].copy_from_slice(keccak256(label.as_bytes()).as_slice());
+        node = keccak256(preimage);
+    }
+    node
+}
+
+/// Named addresses known to the operator
+#[derive(Clone, Debug, Default)]
+pub struct AddressBook {
+    pub entries: BTreeMap<String, Address>,
+}
+
+impl AddressBook {
+    pub fn load(args: &AddressBookArgs) -> anyhow::Result<Self> {
+        let Some(path) = &args.address_book else {
+            return Ok(Self::default());
+        };
+        let data = std::fs::read(path).context(format!("Failed to read {path:?}"))?;
+        let entries: BTreeMap<String, String> =
+            serde_json::from_slice(&data).context("Failed to parse address book")?;
+        let entries = entries
+            .into_iter()
+            .map(|(name, address)| {
+                let address = parse_address(&address)
+                    .context(format!("Invalid address book entry {name}"))?;
+                Ok((name, address))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { entries })
+    }
+
+    /// Resolves the argument to an address, looking names up in the address book before ENS
+    pub async fn resolve<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &self,
+        arg: &AddressArg,
+        provider: P,
+    ) -> anyhow::Result<Address> {
+        let name = match arg {
+            AddressArg::Address(address) => return Ok(*address),
+            AddressArg::Name(name) => name,
+        };
+        if let Some(address) = self.entries.get(name) {
+            info!(
+                "Resolved {name} to {} using the address book.",
+                address.to_checksum(None)
+            );
+            return Ok(*address);
+        }
+        if !name.contains('.') {
+            bail!("Unknown address alias {name}.");
+        }
+        let node = namehash(name);
+        let resolver = IENSRegistry::new(ENS_REGISTRY_ADDRESS, &provider)
+            .resolver(node)
+            .stall()
+            .await
+            ._0;
+        if resolver.is_zero() {
+            bail!("ENS name {name} has no resolver.");
+        }
+        let address = IENSResolver::new(resolver, &provider)
+            .addr(node)
+            .stall()
+            .await
+            ._0;
+        if address.is_zero() {
+            bail!("ENS name {name} does not resolve to an address.");
+        }
+        info!(
+            "Resolved {name} to {} using ENS.",
+            address.to_checksum(None)
+        );
+        Ok(address)
+    }
+
+    /// Resolves the optional argument to an address
+    pub async fn resolve_opt<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &self,
+        arg: &Option<AddressArg>,
+        provider: P,
+    ) -> anyhow::Result<Option<Address>> {
+        match arg {
+            Some(arg) => Ok(Some(self.resolve(arg, provider).await?)),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::providers::optimism::OpNodeProvider;
+use crate::stall::Stall;
+use crate::KAILUA_GAME_TYPE;
+use alloy::network::Network;
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use kailua_contracts::*;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// The status of a dispute game that was resolved in favor of its root claim
+pub const DEFENDER_WINS: u8 = 2;
+
+/// The output used to bootstrap the treasury, and where it was sourced from
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AnchorSelection {
+    pub block_number: u64,
+    pub output_root: B256,
+    /// The game type respected by the portal at the time of the upgrade
+    pub game_type: Option<u32>,
+    /// The resolved game of the respected type whose claim covers the anchor
+    pub source_game: Option<Address>,
+    /// Whether the anchor was manually chosen instead of derived from a resolved game
+    pub manual: bool,
+}
+
+/// Finds the latest output finalized by both the op-node and a resolved game of the respected
+/// type, so that upgrading from the previous proof system does not regress the anchor.
+pub async fn find_safe_anchor<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    provider: P,
+    op_node_provider: &OpNodeProvider,
+    portal_address: Address,
+    dgf_address: Address,
+) -> anyhow::Result<Option<AnchorSelection>> {
+    let optimism_portal = OptimismPortal2::new(portal_address, &provider);
+    let game_type = optimism_portal.respectedGameType().stall().await._0;
+    if game_type == KAILUA_GAME_TYPE {
+        warn!("OptimismPortal2 already respects Kailua proposals.");
+        return Ok(None);
+    }
+    let respected_since: u64 = optimism_portal
+        .respectedGameTypeUpdatedAt()
+        .stall()
+        .await
+        ._0;
+    let sync_status = op_node_provider.sync_status().await?;
+    let finalized_l2 = sync_status["finalized_l2"]["number"]
+        .as_u64()
+        .context("finalized_l2")?;
+    info!("Searching for a resolved game of type {game_type} at or below finalized block {finalized_l2}.");
+
+    let dispute_game_factory = IDisputeGameFactory::new(dgf_address, &provider);
+    let game_count: u64 = dispute_game_factory
+        .gameCount()
+        .stall()
+        .await
+        .gameCount_
+        .to();
+    let mut best: Option<(u64, Address, B256)> = None;
+    for index in (0..game_count).rev() {
+        let game = dispute_game_factory
+            .gameAtIndex(U256::from(index))
+            .stall()
+            .await;
+        if game.gameType_ != game_type {
+            continue;
+        }
+        // Games created before the respected type was last set are not trusted by the portal
+        if game.timestamp_ < respected_since {
+            break;
+        }
+        let dispute_game = IDisputeGame::new(game.proxy_, &provider);
+        if dispute_game.status().stall().await.status_ != DEFENDER_WINS
+            || optimism_portal
+                .disputeGameBlacklist(game.proxy_)
+                .stall()
+                .await
+                ._0
+        {
+            continue;
+        }
+        let extra_data = dispute_game.extraData().stall().await.extraData_;
+        if extra_data.len() < 32 {
+            continue;
+        }
+        let block_number: u64 = U256::from_be_slice(&extra_data[..32]).saturating_to();
+        if block_number > finalized_l2 || best.is_some_and(|(b, ..)| b >= block_number) {
+            continue;
+        }
+        let root_claim = dispute_game.rootClaim().stall().await.rootClaim_;
+        best = Some((block_number, game.proxy_, root_claim));
+    }
+
+    let Some((block_number, source_game, root_claim)) = best else {
+        warn!("No resolved game of type {game_type} covers a finalized output.");
+        return Ok(None);
+    };
+    let output_root = op_node_provider.output_at_block(block_number).await?;
+    if output_root != root_claim {
+        bail!("Game {source_game} claims output {root_claim} at block {block_number} but op-node reports {output_root}.");
+    }
+    info!("Selected anchor at block {block_number} ({output_root}) from game {source_game}.");
+    Ok(Some(AnchorSelection {
+        block_number,
+        output_root,
+        game_type: Some(game_type),
+        source_game: Some(source_game),
+        manual: false,
+    }))
+}
+
+/// Selects the treasury anchor, preferring the manually provided block number if any
+pub async fn select_anchor<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    provider: P,
+    op_node_provider: &OpNodeProvider,
+    portal_address: Address,
+    dgf_address: Address,
+    starting_block_number: Option<u64>,
+) -> anyhow::Result<AnchorSelection> {
+    let safe_anchor =
+        match find_safe_anchor(provider, op_node_provider, portal_address, dgf_address).await {
+            Ok(safe_anchor) => safe_anchor,
+            Err(err) if starting_block_number.is_some() => {
+                warn!("Failed to determine a safe anchor: {err:?}");
+                None
+            }
+            Err(err) => return Err(err),
+        };
+    let Some(block_number) = starting_block_number else {
+        return safe_anchor.context(
+            "Could not determine a safe anchor. Provide a starting block number manually.",
+        );
+    };
+    if let Some(safe_anchor) = &safe_anchor {
+        if block_number < safe_anchor.block_number {
+            warn!(
+                "Starting block {block_number} regresses the anchor below block {} finalized by game {:?}.",
+                safe_anchor.block_number, safe_anchor.source_game
+            );
+        }
+    }
+    let output_root = op_node_provider.output_at_block(block_number).await?;
+    info!("Using manually selected anchor at block {block_number} ({output_root}).");
+    Ok(AnchorSelection {
+        block_number,
+        output_root,
+        game_type: safe_anchor.as_ref().and_then(|a| a.game_type),
+        source_game: None,
+        manual: true,
+    })
+}
@@ -0,0 +1,459 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::availability::DataWindow;
+use crate::db::lifecycle::{Fault, ProposalStatus};
+use crate::db::proposal::Proposal;
+use crate::db::KailuaDB;
+use crate::equivocation::Equivocation;
+use crate::latency::{unix_now, LatencyReport};
+use crate::proofs::DeadLetter;
+use crate::risk::{risk_at, risk_ranges, RiskRange};
+use crate::stats::{StatsTracker, WindowStats, DEFAULT_STATS_WINDOWS};
+use crate::workdir::DiskUsage;
+use alloy::primitives::{Address, B256};
+use anyhow::Context;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{error, info};
+
+/// The default number of proposals returned per page
+pub const DEFAULT_PAGE_LIMIT: usize = 100;
+/// The maximum number of proposals returned per page
+pub const MAX_PAGE_LIMIT: usize = 1000;
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ApiArgs {
+    /// Address to serve the read-only proposal query api on
+    #[clap(long, env)]
+    pub api_address: Option<SocketAddr>,
+}
+
+/// The publicly served view of a proposal
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProposalView {
+    pub index: u64,
+    pub contract: Address,
+    pub parent: u64,
+    pub proposer: Address,
+    pub created_at: u64,
+    pub output_root: B256,
+    pub output_block_number: u64,
+    pub l1_head: B256,
+    pub children: Vec<u64>,
+    pub survivor: Option<u64>,
+    pub contender: Option<u64>,
+    /// The validator's verdict on the correctness of the proposal, if determined
+    pub correct: Option<bool>,
+    pub correct_claim: Option<bool>,
+    pub correct_parent: Option<bool>,
+    pub incorrect_io: Vec<usize>,
+    pub canonical: Option<bool>,
+    pub status: ProposalStatus,
+}
+
+impl From<&Proposal> for ProposalView {
+    fn from(proposal: &Proposal) -> Self {
+        Self {
+            index: proposal.index,
+            contract: proposal.contract,
+            parent: proposal.parent,
+            proposer: proposal.proposer,
+            created_at: proposal.created_at,
+            output_root: proposal.output_root,
+            output_block_number: proposal.output_block_number,
+            l1_head: proposal.l1_head,
+            children: proposal.children.clone(),
+            survivor: proposal.survivor,
+            contender: proposal.contender,
+            correct: proposal.is_correct(),
+            correct_claim: proposal.assessment.agrees_at(Fault::Claim),
+            correct_parent: proposal.assessment.agrees_at(Fault::Parent),
+            incorrect_io: proposal.assessment.incorrect_io(),
+            canonical: proposal.is_canonical(),
+            status: proposal.status,
+        }
+    }
+}
+
+impl ProposalView {
+    pub fn status_name(&self) -> &'static str {
+        match self.status {
+            ProposalStatus::Unchallenged => "unchallenged",
+            ProposalStatus::Challenged { .. } => "challenged",
+            ProposalStatus::Proven { .. } => "proven",
+            ProposalStatus::Resolved { .. } => "resolved",
+        }
+    }
+}
+
+/// A summary of the validator's view of the proposal tree
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StatusView {
+    pub next_factory_index: u64,
+    pub canonical_tip_index: Option<u64>,
+    pub proposal_count: usize,
+    /// The factory index of the first incorrect proposal of each eliminated proposer
+    pub eliminations: HashMap<Address, u64>,
+    pub equivocation_count: usize,
+    /// The number of milliseconds the validator currently waits between scans
+    pub poll_interval_ms: u64,
+    /// The number of local proofs left unsubmitted because their matches were proven concurrently
+    pub redundant_proofs: usize,
+    /// The estimated gas that submitting the redundant proofs would have spent
+    pub saved_gas: u64,
+}
+
+/// The snapshot of the proposal database served by the api
+#[derive(Clone, Debug, Default)]
+pub struct ApiSnapshot {
+    pub status: StatusView,
+    pub proposals: BTreeMap<u64, ProposalView>,
+    pub equivocations: Vec<Equivocation>,
+    pub latency: LatencyReport,
+    pub dead_letters: Vec<DeadLetter>,
+    pub data_availability: Vec<DataWindow>,
+    pub stats: StatsTracker,
+    pub disk_usage: DiskUsage,
+    pub proposal_block_count: u64,
+    pub challenge_timeout: u64,
+}
+
+impl ApiSnapshot {
+    /// Returns the current risk status of all L2 blocks
+    pub fn risk(&self) -> Vec<RiskRange> {
+        risk_ranges(
+            &self.proposals,
+            self.status.canonical_tip_index,
+            self.proposal_block_count,
+            self.challenge_timeout,
+            unix_now(),
+        )
+    }
+}
+
+/// Shared handle to the snapshot served by the api, updated by the validator after every scan
+#[derive(Clone, Debug, Default)]
+pub struct ApiState(Arc<RwLock<ApiSnapshot>>);
+
+impl ApiState {
+    /// Refreshes the snapshot with the proposals modified in the database since the last update
+    pub fn update(&self, kailua_db: &mut KailuaDB) {
+        let modified = core::mem::take(&mut kailua_db.state.modified_proposals);
+        let mut snapshot = self.0.write().unwrap();
+        // forget proposals pruned from the database
+        snapshot.proposals = snapshot.proposals.split_off(&kailua_db.state.pruned_below);
+        for index in modified {
+            if let Some(proposal) = kailua_db.get_local_proposal(&index) {
+                snapshot
+                    .proposals
+                    .insert(index, ProposalView::from(&proposal));
+            }
+        }
+        snapshot.status = StatusView {
+            next_factory_index: kailua_db.state.next_factory_index,
+            canonical_tip_index: kailua_db.state.canonical_tip_index,
+            proposal_count: snapshot.proposals.len(),
+            eliminations: kailua_db.state.eliminations.clone(),
+            equivocation_count: kailua_db.state.equivocations.len(),
+            poll_interval_ms: snapshot.status.poll_interval_ms,
+            redundant_proofs: snapshot.status.redundant_proofs,
+            saved_gas: snapshot.status.saved_gas,
+        };
+        snapshot.proposal_block_count = kailua_db.config.proposal_block_count;
+        snapshot.challenge_timeout = kailua_db.config.timeout;
+        if snapshot.equivocations.len() != kailua_db.state.equivocations.len() {
+            snapshot.equivocations = kailua_db.state.equivocations.clone();
+        }
+    }
+
+    /// Replaces the served summary of dispute latencies
+    pub fn update_latency(&self, report: LatencyReport) {
+        self.0.write().unwrap().latency = report;
+    }
+
+    /// Replaces the served list of permanently failed proofs
+    pub fn update_dead_letters(&self, dead_letters: Vec<DeadLetter>) {
+        self.0.write().unwrap().dead_letters = dead_letters;
+    }
+
+    /// Replaces the proof outcomes and resolution times the served statistics are computed from
+    pub fn update_stats(&self, stats: StatsTracker) {
+        self.0.write().unwrap().stats = stats;
+    }
+
+    /// Replaces the disk usage of proving jobs reported by the health check
+    pub fn update_disk_usage(&self, disk_usage: DiskUsage) {
+        self.0.write().unwrap().disk_usage = disk_usage;
+    }
+
+    /// Replaces the adaptive polling interval reported in the status
+    pub fn update_poll_interval(&self, interval: Duration) {
+        self.0.write().unwrap().status.poll_interval_ms = interval.as_millis() as u64;
+    }
+
+    /// Replaces the redundant proof submissions and the gas they saved reported in the status
+    pub fn update_redundant_proofs(&self, redundant_proofs: usize, saved_gas: u64) {
+        let mut snapshot = self.0.write().unwrap();
+        snapshot.status.redundant_proofs = redundant_proofs;
+        snapshot.status.saved_gas = saved_gas;
+    }
+
+    /// Replaces the served data availability windows of unproven disputes
+    pub fn update_data_availability(&self, windows: Vec<DataWindow>) {
+        self.0.write().unwrap().data_availability = windows;
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ProposalFilter {
+    pub proposer: Option<Address>,
+    pub parent: Option<u64>,
+    pub correct: Option<bool>,
+    pub canonical: Option<bool>,
+    /// One of `unchallenged`, `challenged`, `proven` or `resolved`
+    pub status: Option<String>,
+    pub from_index: Option<u64>,
+    pub to_index: Option<u64>,
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+impl ProposalFilter {
+    pub fn matches(&self, proposal: &ProposalView) -> bool {
+        self.proposer.map_or(true, |p| p == proposal.proposer)
+            && self.parent.map_or(true, |p| p == proposal.parent)
+            && self.correct.map_or(true, |c| Some(c) == proposal.correct)
+            && self
+                .canonical
+                .map_or(true, |c| Some(c) == proposal.canonical)
+            && self
+                .status
+                .as_ref()
+                .map_or(true, |s| s.eq_ignore_ascii_case(proposal.status_name()))
+            && self.from_index.map_or(true, |i| i <= proposal.index)
+            && self.to_index.map_or(true, |i| proposal.index <= i)
+            && self
+                .from_block
+                .map_or(true, |b| b <= proposal.output_block_number)
+            && self
+                .to_block
+                .map_or(true, |b| proposal.output_block_number <= b)
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct StatsQuery {
+    /// Comma-separated windows (in seconds) to compute the statistics over
+    pub window_secs: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub items: Vec<T>,
+}
+
+type ApiError = (StatusCode, String);
+
+fn not_found(index: u64) -> ApiError {
+    (StatusCode::NOT_FOUND, format!("Proposal {index} not found"))
+}
+
+/// Serves the read-only query api over the given state until the process exits
+pub async fn serve(address: SocketAddr, state: ApiState) -> anyhow::Result<()> {
+    let app = Router::new()
+        .route("/health", get(get_health))
+        .route("/status", get(get_status))
+        .route("/proposals", get(get_proposals))
+        .route("/proposals/:index", get(get_proposal))
+        .route("/proposals/:index/children", get(get_children))
+        .route("/proposals/:index/ancestors", get(get_ancestors))
+        .route("/equivocations", get(get_equivocations))
+        .route("/latency", get(get_latency))
+        .route("/dead-letters", get(get_dead_letters))
+        .route("/data-availability", get(get_data_availability))
+        .route("/stats", get(get_stats))
+        .route("/risk", get(get_risk))
+        .route("/risk/:block", get(get_block_risk))
+        .with_state(state);
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .context(format!("Failed to bind api to {address}"))?;
+    info!("Serving proposal api on {address}.");
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+/// Spawns the api server in the background if an address was configured
+pub fn spawn(args: &ApiArgs) -> Option<ApiState> {
+    let address = args.api_address?;
+    let state = ApiState::default();
+    let server_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(err) = serve(address, server_state).await {
+            error!("Proposal api terminated: {err:?}");
+        }
+    });
+    Some(state)
+}
+
+/// Reports the validator as unhealthy while proving jobs run out of disk space
+async fn get_health(State(state): State<ApiState>) -> (StatusCode, String) {
+    let disk_usage = state.0.read().unwrap().disk_usage;
+    if disk_usage.under_pressure() {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!(
+                "Disk pressure: {} of {} quota bytes used",
+                disk_usage.used_bytes,
+                disk_usage.quota_bytes.unwrap_or_default()
+            ),
+        )
+    } else {
+        (StatusCode::OK, String::from("OK"))
+    }
+}
+
+async fn get_status(State(state): State<ApiState>) -> Json<StatusView> {
+    Json(state.0.read().unwrap().status.clone())
+}
+
+async fn get_proposals(
+    State(state): State<ApiState>,
+    Query(filter): Query<ProposalFilter>,
+) -> Json<Page<ProposalView>> {
+    let snapshot = state.0.read().unwrap();
+    let offset = filter.offset.unwrap_or_default();
+    let limit = filter
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .min(MAX_PAGE_LIMIT);
+    let matches = snapshot
+        .proposals
+        .values()
+        .filter(|proposal| filter.matches(proposal));
+    let total = matches.clone().count();
+    let items = matches.skip(offset).take(limit).cloned().collect();
+    Json(Page {
+        total,
+        offset,
+        limit,
+        items,
+    })
+}
+
+async fn get_proposal(
+    State(state): State<ApiState>,
+    Path(index): Path<u64>,
+) -> Result<Json<ProposalView>, ApiError> {
+    let snapshot = state.0.read().unwrap();
+    let proposal = snapshot.proposals.get(&index).ok_or(not_found(index))?;
+    Ok(Json(proposal.clone()))
+}
+
+async fn get_children(
+    State(state): State<ApiState>,
+    Path(index): Path<u64>,
+) -> Result<Json<Vec<ProposalView>>, ApiError> {
+    let snapshot = state.0.read().unwrap();
+    let proposal = snapshot.proposals.get(&index).ok_or(not_found(index))?;
+    Ok(Json(
+        proposal
+            .children
+            .iter()
+            .filter_map(|child| snapshot.proposals.get(child).cloned())
+            .collect(),
+    ))
+}
+
+/// Returns the chain of proposals from the given proposal up to its treasury instance
+async fn get_ancestors(
+    State(state): State<ApiState>,
+    Path(index): Path<u64>,
+) -> Result<Json<Vec<ProposalView>>, ApiError> {
+    let snapshot = state.0.read().unwrap();
+    let mut proposal = snapshot.proposals.get(&index).ok_or(not_found(index))?;
+    let mut ancestors = vec![proposal.clone()];
+    while proposal.parent != proposal.index {
+        let Some(parent) = snapshot.proposals.get(&proposal.parent) else {
+            break;
+        };
+        ancestors.push(parent.clone());
+        proposal = parent;
+    }
+    Ok(Json(ancestors))
+}
+
+async fn get_equivocations(State(state): State<ApiState>) -> Json<Vec<Equivocation>> {
+    Json(state.0.read().unwrap().equivocations.clone())
+}
+
+async fn get_latency(State(state): State<ApiState>) -> Json<LatencyReport> {
+    Json(state.0.read().unwrap().latency.clone())
+}
+
+async fn get_dead_letters(State(state): State<ApiState>) -> Json<Vec<DeadLetter>> {
+    Json(state.0.read().unwrap().dead_letters.clone())
+}
+
+async fn get_data_availability(State(state): State<ApiState>) -> Json<Vec<DataWindow>> {
+    Json(state.0.read().unwrap().data_availability.clone())
+}
+
+async fn get_stats(
+    State(state): State<ApiState>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<Vec<WindowStats>>, ApiError> {
+    let windows = match &query.window_secs {
+        Some(windows) => windows
+            .split(',')
+            .map(|window| window.trim().parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| (StatusCode::BAD_REQUEST, format!("Invalid window: {err}")))?,
+        None => DEFAULT_STATS_WINDOWS.to_vec(),
+    };
+    let snapshot = state.0.read().unwrap();
+    Ok(Json(
+        snapshot
+            .stats
+            .compute(snapshot.proposals.values(), &windows),
+    ))
+}
+
+async fn get_risk(State(state): State<ApiState>) -> Json<Vec<RiskRange>> {
+    Json(state.0.read().unwrap().risk())
+}
+
+/// Returns the risk status of the output of a single L2 block
+async fn get_block_risk(
+    State(state): State<ApiState>,
+    Path(block): Path<u64>,
+) -> Result<Json<RiskRange>, ApiError> {
+    let ranges = state.0.read().unwrap().risk();
+    let range = risk_at(&ranges, block)
+        .ok_or((StatusCode::NOT_FOUND, format!("Block {block} not covered")))?;
+    Ok(Json(range.clone()))
+}
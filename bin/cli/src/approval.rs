@@ -0,0 +1,272 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::primitives::{keccak256, Address, B256};
+use alloy::transports::http::reqwest;
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// The queue subdirectory holding intents awaiting approval
+pub const PENDING_DIR: &str = "pending";
+/// The queue subdirectory holding the approval tokens of approved intents
+pub const APPROVED_DIR: &str = "approved";
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ApprovalArgs {
+    /// Directory to queue challenge, proof and resolution intents in for human approval
+    #[clap(long, env)]
+    pub approval_queue_dir: Option<PathBuf>,
+    /// URL to POST intents to for human approval, and to poll for their approval tokens
+    #[clap(long, env)]
+    pub approval_webhook: Option<String>,
+    /// Secret shared with approvers to derive the approval token of each intent
+    #[clap(long, env)]
+    pub approval_secret: Option<String>,
+}
+
+/// The kind of on-chain action an intent asks approval for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntentKind {
+    /// Publishing a proposal that implicitly challenges an existing one
+    Challenge,
+    /// Submitting a proof settling a match between two proposals
+    Prove,
+    /// Resolving a game
+    Resolve,
+}
+
+/// An on-chain action that is only taken once approved
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Intent {
+    /// The identifier approvals refer to, which is stable across retries of the action
+    pub id: B256,
+    pub kind: IntentKind,
+    /// The factory index of the game the action is taken on
+    pub game_index: u64,
+    pub game_contract: Address,
+    /// A human-readable description of the action
+    pub details: String,
+    /// The unix timestamp at which the intent was first queued
+    pub created_at: u64,
+}
+
+impl Intent {
+    pub fn new(kind: IntentKind, game_index: u64, game_contract: Address, details: String) -> Self {
+        let id = keccak256(
+            serde_json::to_vec(&(kind, game_index, game_contract, &details))
+                .expect("Failed to encode intent"),
+        );
+        let created_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self {
+            id,
+            kind,
+            game_index,
+            game_contract,
+            details,
+            created_at,
+        }
+    }
+}
+
+/// Returns the token approving the intent with the given id
+pub fn approval_token(secret: &str, id: B256) -> B256 {
+    keccak256([secret.as_bytes(), id.as_slice()].concat())
+}
+
+/// Holds back on-chain actions until an approver returns their approval tokens
+#[derive(Clone, Debug)]
+pub struct ApprovalGate {
+    pub queue_dir: Option<PathBuf>,
+    pub webhook: Option<String>,
+    pub secret: String,
+    pub client: reqwest::Client,
+    /// The intents already submitted for approval
+    pub submitted: BTreeSet<B256>,
+}
+
+impl ApprovalGate {
+    /// Returns the gate configured by the arguments, if any approval queue was provided
+    pub fn new(args: &ApprovalArgs) -> anyhow::Result<Option<Self>> {
+        if args.approval_queue_dir.is_none() && args.approval_webhook.is_none() {
+            return Ok(None);
+        }
+        let Some(secret) = args.approval_secret.clone() else {
+            bail!("An approval secret is required to verify approvals.");
+        };
+        if let Some(queue_dir) = &args.approval_queue_dir {
+            std::fs::create_dir_all(queue_dir.join(PENDING_DIR))?;
+            std::fs::create_dir_all(queue_dir.join(APPROVED_DIR))?;
+        }
+        info!("Queueing challenges, proofs and resolutions for human approval.");
+        Ok(Some(Self {
+            queue_dir: args.approval_queue_dir.clone(),
+            webhook: args
+                .approval_webhook
+                .as_ref()
+                .map(|url| url.trim_end_matches('/').to_string()),
+            secret,
+            client: reqwest::Client::new(),
+            submitted: BTreeSet::new(),
+        }))
+    }
+
+    /// Queues the intent for approval if it is new, and returns whether a valid approval token
+    /// has been returned for it
+    pub async fn approve(&mut self, intent: &Intent) -> anyhow::Result<bool> {
+        if !self.submitted.contains(&intent.id) {
+            self.submit(intent).await?;
+            self.submitted.insert(intent.id);
+            info!(
+                "Queued {:?} intent {} on game {} for approval: {}",
+                intent.kind, intent.id, intent.game_index, intent.details
+            );
+        }
+        let Some(token) = self.fetch_token(intent.id).await? else {
+            return Ok(false);
+        };
+        if token != approval_token(&self.secret, intent.id) {
+            warn!("Ignoring invalid approval token for intent {}.", intent.id);
+            return Ok(false);
+        }
+        info!("{:?} intent {} was approved.", intent.kind, intent.id);
+        if let Some(queue_dir) = &self.queue_dir {
+            let _ = std::fs::remove_file(pending_path(queue_dir, intent.id));
+        }
+        Ok(true)
+    }
+
+    async fn submit(&self, intent: &Intent) -> anyhow::Result<()> {
+        if let Some(queue_dir) = &self.queue_dir {
+            let path = pending_path(queue_dir, intent.id);
+            // keep the original queueing time of intents carried over from earlier runs
+            if !path.exists() {
+                let tmp_path = path.with_extension("tmp");
+                std::fs::write(&tmp_path, serde_json::to_vec_pretty(intent)?)?;
+                std::fs::rename(&tmp_path, &path)?;
+            }
+        }
+        if let Some(webhook) = &self.webhook {
+            self.client
+                .post(webhook)
+                .json(intent)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .context("Failed to deliver intent to approval webhook")?;
+        }
+        Ok(())
+    }
+
+    async fn fetch_token(&self, id: B256) -> anyhow::Result<Option<B256>> {
+        if let Some(queue_dir) = &self.queue_dir {
+            let path = queue_dir.join(APPROVED_DIR).join(id.to_string());
+            if path.exists() {
+                let token = std::fs::read_to_string(&path)?;
+                return Ok(Some(
+                    token.trim().parse().context("Malformed approval token")?,
+                ));
+            }
+        }
+        if let Some(webhook) = &self.webhook {
+            let response = self
+                .client
+                .get(format!("{webhook}/{id}"))
+                .send()
+                .await
+                .context("Failed to poll approval webhook")?;
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            let token = response.error_for_status()?.text().await?;
+            return Ok(Some(
+                token.trim().parse().context("Malformed approval token")?,
+            ));
+        }
+        Ok(None)
+    }
+}
+
+fn pending_path(queue_dir: &Path, id: B256) -> PathBuf {
+    queue_dir.join(PENDING_DIR).join(format!("{id}.json"))
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ApproveArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Directory the agent queues its intents in
+    #[clap(long, env)]
+    pub approval_queue_dir: PathBuf,
+    /// Secret shared with the agent to derive approval tokens
+    #[clap(long, env)]
+    pub approval_secret: String,
+
+    /// Identifier of the intent to approve, or none to list the pending intents
+    pub intent: Option<B256>,
+}
+
+/// Lists the intents pending approval, or approves one of them
+pub async fn approve(args: ApproveArgs) -> anyhow::Result<()> {
+    let Some(id) = args.intent else {
+        let mut intents = vec![];
+        for entry in std::fs::read_dir(args.approval_queue_dir.join(PENDING_DIR))? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                intents.push(serde_json::from_slice::<Intent>(&std::fs::read(&path)?)?);
+            }
+        }
+        intents.sort_by_key(|intent| intent.created_at);
+        if intents.is_empty() {
+            println!("No intents pending approval.");
+        }
+        for intent in intents {
+            println!(
+                "{} {:?} game {} ({}) queued at {}: {}",
+                intent.id,
+                intent.kind,
+                intent.game_index,
+                intent.game_contract,
+                intent.created_at,
+                intent.details
+            );
+        }
+        return Ok(());
+    };
+    let pending = pending_path(&args.approval_queue_dir, id);
+    if !pending.exists() {
+        bail!("Intent {id} is not pending approval.");
+    }
+    let intent = serde_json::from_slice::<Intent>(&std::fs::read(&pending)?)?;
+    let token = approval_token(&args.approval_secret, id);
+    std::fs::write(
+        args.approval_queue_dir
+            .join(APPROVED_DIR)
+            .join(id.to_string()),
+        token.to_string(),
+    )?;
+    println!(
+        "Approved {:?} intent on game {}: {}",
+        intent.kind, intent.game_index, intent.details
+    );
+    println!("Approval token: {token}");
+    Ok(())
+}
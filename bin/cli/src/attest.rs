@@ -0,0 +1,225 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::anchor::AnchorSelection;
+use alloy::network::Network;
+use alloy::primitives::{keccak256, Address, Bytes, B256};
+use alloy::providers::Provider;
+use alloy::signers::Signer;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use kailua_contracts::artifacts::Artifact;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tracing::{error, info, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct SourceVerificationArgs {
+    /// Block explorer to submit the sources of the deployed contracts to (etherscan or blockscout)
+    #[clap(long, env)]
+    pub source_verifier: Option<String>,
+    /// Verification api url of the block explorer, if not known to forge
+    #[clap(long, env)]
+    pub source_verifier_url: Option<String>,
+    /// Api key of the block explorer
+    #[clap(long, env)]
+    pub etherscan_api_key: Option<String>,
+    /// Path to the foundry project the embedded contracts were built from
+    #[clap(long, env, default_value = "crates/contracts/foundry")]
+    pub foundry_project: PathBuf,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BytecodeAttestation {
+    pub contract: String,
+    /// The `path:name` identifier of the contract's source in the foundry project
+    pub source: String,
+    pub address: Address,
+    pub code_hash: B256,
+    pub expected_code_hash: B256,
+    pub matches: bool,
+    /// Whether the block explorer accepted the contract's source, if it was submitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_verified: Option<bool>,
+}
+
+/// A record of the deployed contracts and their bytecode checks, signed by the deployer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeploymentArtifact {
+    pub chain_id: u64,
+    pub attestations: Vec<BytecodeAttestation>,
+    /// The output the treasury was bootstrapped from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<AnchorSelection>,
+    pub attester: Address,
+    pub signature: Bytes,
+}
+
+impl DeploymentArtifact {
+    pub async fn sign<S: Signer>(
+        chain_id: u64,
+        attestations: Vec<BytecodeAttestation>,
+        anchor: Option<AnchorSelection>,
+        signer: &S,
+    ) -> anyhow::Result<Self> {
+        let message = serde_json::to_vec(&(chain_id, &attestations, &anchor))?;
+        let signature = signer
+            .sign_message(&message)
+            .await
+            .context("Failed to sign deployment attestations")?;
+        Ok(Self {
+            chain_id,
+            attestations,
+            anchor,
+            attester: signer.address(),
+            signature: Bytes::from(signature.as_bytes()),
+        })
+    }
+
+    pub async fn save(&self, path: &Path) -> anyhow::Result<()> {
+        tokio::fs::write(path, serde_json::to_vec_pretty(self)?)
+            .await
+            .context(format!("Failed to write deployment artifact to {path:?}"))?;
+        info!("Saved deployment artifact to {path:?}.");
+        Ok(())
+    }
+}
+
+/// Compares the code deployed at `address` against the expected build output and fails on a
+/// mismatch.
+pub async fn attest_bytecode<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    provider: P,
+    artifact: &Artifact,
+    address: Address,
+    expected: &Bytes,
+) -> anyhow::Result<BytecodeAttestation> {
+    let contract = artifact.name;
+    let deployed = provider
+        .get_code_at(address)
+        .await
+        .context(format!("get_code_at {contract}"))?;
+    let immutables = immutable_references(artifact)?;
+    let attestation = BytecodeAttestation {
+        contract: contract.to_string(),
+        source: artifact.identifier(),
+        address,
+        code_hash: keccak256(&deployed),
+        expected_code_hash: keccak256(expected),
+        matches: matches_ignoring_immutables(expected, &deployed, &immutables),
+        source_verified: None,
+    };
+    if !attestation.matches {
+        error!("{contract}({address}) bytecode does not match the known build.");
+        bail!("Bytecode verification failed for {contract}.");
+    }
+    info!("{contract}({address}) bytecode verified.");
+    Ok(attestation)
+}
+
+/// Returns the byte ranges of the deployed bytecode the compiler reserved for immutable variables
+pub fn immutable_references(artifact: &Artifact) -> anyhow::Result<Vec<Range<usize>>> {
+    let json: Value = serde_json::from_str(artifact.json)
+        .context(format!("Failed to parse {} artifact", artifact.name))?;
+    let mut ranges = vec![];
+    let Some(references) = json["deployedBytecode"]["immutableReferences"].as_object() else {
+        return Ok(ranges);
+    };
+    for locations in references.values() {
+        for location in locations
+            .as_array()
+            .context("Malformed immutableReferences")?
+        {
+            let (Some(start), Some(length)) =
+                (location["start"].as_u64(), location["length"].as_u64())
+            else {
+                bail!("Malformed immutable reference {location}");
+            };
+            ranges.push(start as usize..(start + length) as usize);
+        }
+    }
+    Ok(ranges)
+}
+
+/// Compares deployed bytecode against compiler output, ignoring the given immutable variable
+/// placeholders.
+pub fn matches_ignoring_immutables(
+    expected: &[u8],
+    deployed: &[u8],
+    immutables: &[Range<usize>],
+) -> bool {
+    if expected.is_empty() || expected.len() != deployed.len() {
+        return false;
+    }
+    let mut masked = deployed.to_vec();
+    for range in immutables {
+        let (Some(placeholder), Some(compiled)) =
+            (masked.get_mut(range.clone()), expected.get(range.clone()))
+        else {
+            return false;
+        };
+        placeholder.copy_from_slice(compiled);
+    }
+    masked == expected
+}
+
+/// Submits the sources of the attested contracts to a block explorer using
+/// `forge verify-contract`, recording the outcome in each attestation.
+pub async fn verify_sources(
+    args: &SourceVerificationArgs,
+    eth_rpc_url: &str,
+    chain_id: u64,
+    attestations: &mut [BytecodeAttestation],
+) -> anyhow::Result<()> {
+    let Some(verifier) = &args.source_verifier else {
+        return Ok(());
+    };
+    for attestation in attestations.iter_mut() {
+        info!(
+            "Submitting {}({}) source to {verifier}.",
+            attestation.contract, attestation.address
+        );
+        let mut command = Command::new("forge");
+        command
+            .current_dir(&args.foundry_project)
+            .args(["verify-contract", "--watch", "--guess-constructor-args"])
+            .args(["--verifier", verifier])
+            .args(["--rpc-url", eth_rpc_url])
+            .args(["--chain", &chain_id.to_string()]);
+        if let Some(url) = &args.source_verifier_url {
+            command.args(["--verifier-url", url]);
+        }
+        if let Some(api_key) = &args.etherscan_api_key {
+            command.args(["--etherscan-api-key", api_key]);
+        }
+        command
+            .arg(attestation.address.to_string())
+            .arg(&attestation.source);
+        let verified = command
+            .status()
+            .await
+            .context("Failed to run forge verify-contract")?
+            .success();
+        if !verified {
+            warn!(
+                "{}({}) source verification failed.",
+                attestation.contract, attestation.address
+            );
+        }
+        attestation.source_verified = Some(verified);
+    }
+    Ok(())
+}
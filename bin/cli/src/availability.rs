@@ -0,0 +1,175 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::config::Config;
+use crate::db::lifecycle::ProposalStatus;
+use crate::db::proposal::Proposal;
+use crate::db::KailuaDB;
+use crate::latency::unix_now;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::{error, warn};
+
+/// The seconds for which consensus clients retain blobs (4096 epochs of 32 slots of 12 seconds)
+pub const BLOB_RETENTION_SECS: u64 = 4096 * 32 * 12;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct AvailabilityArgs {
+    /// Seconds for which the consensus layer retains published blobs
+    #[clap(long, env, default_value_t = BLOB_RETENTION_SECS)]
+    pub blob_retention_secs: u64,
+    /// Seconds before the L1 data of an unproven dispute expires to start proving it
+    /// immediately regardless of the proving strategy
+    #[clap(long, env, default_value_t = 5 * 24 * 60 * 60)]
+    pub blob_expiry_warning_secs: u64,
+    /// Seconds before the L1 data of an unproven dispute expires to raise an alert
+    #[clap(long, env, default_value_t = 24 * 60 * 60)]
+    pub blob_expiry_critical_secs: u64,
+}
+
+/// How close the L1 data required to prove a dispute is to expiring
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ExpiryUrgency {
+    Nominal,
+    /// The dispute should be proven ahead of others
+    Warning,
+    /// The dispute must be proven before its data expires
+    Critical,
+    /// The data required to prove the dispute may no longer be served
+    Expired,
+}
+
+/// The window within which the L1 data required to prove a dispute remains available
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DataWindow {
+    /// The factory index of the challenging proposal
+    pub index: u64,
+    /// The factory index of the disputed proposal
+    pub contender: u64,
+    /// The earliest time at which L1 data read by the proof may have been published
+    pub published_at: u64,
+    pub expires_at: u64,
+    pub urgency: ExpiryUrgency,
+}
+
+/// Tracks the proximity of every unproven dispute to the expiry of the L1 data its proof needs
+#[derive(Clone, Debug)]
+pub struct AvailabilityMonitor {
+    pub args: AvailabilityArgs,
+    /// The data windows of unproven disputes keyed by the challenging proposal's index
+    pub windows: BTreeMap<u64, DataWindow>,
+}
+
+impl AvailabilityMonitor {
+    pub fn new(args: AvailabilityArgs) -> Self {
+        Self {
+            args,
+            windows: Default::default(),
+        }
+    }
+
+    /// Starts tracking the dispute between the contender and the challenging proposal.
+    ///
+    /// The batches of the disputed blocks could not have been posted before the first block after
+    /// the parent's output was produced, which conservatively bounds the age of the data read by
+    /// the proof, while the blobs of both proposals are younger.
+    pub fn watch(&mut self, config: &Config, parent: &Proposal, contender: &Proposal, index: u64) {
+        if self.windows.contains_key(&index) {
+            return;
+        }
+        let published_at = (config.genesis_time
+            + (parent.output_block_number + 1) * config.block_time)
+            .min(contender.created_at);
+        self.windows.insert(
+            index,
+            DataWindow {
+                index,
+                contender: contender.index,
+                published_at,
+                expires_at: published_at + self.args.blob_retention_secs,
+                urgency: ExpiryUrgency::Nominal,
+            },
+        );
+        self.escalate(index, unix_now());
+    }
+
+    fn urgency_at(&self, window: &DataWindow, now: u64) -> ExpiryUrgency {
+        let remaining = window.expires_at.saturating_sub(now);
+        if remaining == 0 {
+            ExpiryUrgency::Expired
+        } else if remaining <= self.args.blob_expiry_critical_secs {
+            ExpiryUrgency::Critical
+        } else if remaining <= self.args.blob_expiry_warning_secs {
+            ExpiryUrgency::Warning
+        } else {
+            ExpiryUrgency::Nominal
+        }
+    }
+
+    /// Returns the urgency of proving the dispute of the given proposal
+    pub fn urgency(&self, index: u64) -> ExpiryUrgency {
+        self.windows
+            .get(&index)
+            .map_or(ExpiryUrgency::Nominal, |window| window.urgency)
+    }
+
+    /// Forgets settled disputes and escalates the remaining ones as their data ages
+    pub fn check(&mut self, kailua_db: &KailuaDB) {
+        let now = unix_now();
+        self.windows = self.windows.split_off(&kailua_db.state.pruned_below);
+        self.windows.retain(|index, _| {
+            kailua_db.get_local_proposal(index).is_some_and(|proposal| {
+                !matches!(
+                    proposal.status,
+                    ProposalStatus::Proven { .. } | ProposalStatus::Resolved { .. }
+                )
+            })
+        });
+        for index in self.windows.keys().copied().collect::<Vec<_>>() {
+            self.escalate(index, now);
+        }
+    }
+
+    /// Raises the urgency of the dispute's window as its data ages, alerting on every escalation
+    fn escalate(&mut self, index: u64, now: u64) {
+        let Some(window) = self.windows.get(&index) else {
+            return;
+        };
+        let urgency = self.urgency_at(window, now);
+        if urgency <= window.urgency {
+            return;
+        }
+        let remaining = window.expires_at.saturating_sub(now);
+        match urgency {
+            ExpiryUrgency::Nominal => {}
+            ExpiryUrgency::Warning => warn!(
+                "L1 data of the dispute of proposal {index} expires in {remaining}s. Prioritizing its proof."
+            ),
+            ExpiryUrgency::Critical => error!(
+                "BLOB EXPIRY IMMINENT! L1 data of the dispute of proposal {index} expires in {remaining}s."
+            ),
+            ExpiryUrgency::Expired => error!(
+                "BLOBS EXPIRED! L1 data of the dispute of proposal {index} may no longer be available. An archival beacon node is required to prove it."
+            ),
+        }
+        self.windows.get_mut(&index).unwrap().urgency = urgency;
+    }
+
+    /// Returns the tracked windows, most urgent first
+    pub fn report(&self) -> Vec<DataWindow> {
+        let mut windows = self.windows.values().cloned().collect::<Vec<_>>();
+        windows.sort_by_key(|window| window.expires_at);
+        windows
+    }
+}
@@ -0,0 +1,286 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::config::Config;
+use crate::db::proposal::Proposal;
+use crate::providers::beacon::BlobProvider;
+use crate::providers::optimism::OpNodeProvider;
+use crate::stall::Stall;
+use crate::KAILUA_GAME_TYPE;
+use alloy::eips::eip4844::kzg_to_versioned_hash;
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy::providers::ProviderBuilder;
+use alloy::signers::local::LocalSigner;
+use alloy::signers::Signer;
+use alloy_rpc_types_beacon::sidecar::BlobData;
+use anyhow::{bail, Context};
+use kailua_contracts::{IDisputeGameFactory::gameAtIndexReturn, *};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::str::FromStr;
+use tracing::{info, warn};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct BlobReportArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the OP-NODE endpoint to recompute outputs with
+    #[clap(long, env)]
+    pub op_node_url: String,
+    /// Address of the ethereum rpc endpoint to use (eth namespace required)
+    #[clap(long, env)]
+    pub eth_rpc_url: String,
+    /// Address of the L1 Beacon API endpoint to fetch the published blobs from
+    #[clap(long, env)]
+    pub beacon_rpc_url: String,
+    /// Address of the rollup's DisputeGameFactory contract
+    #[clap(long, env)]
+    pub dispute_game_factory: Address,
+
+    /// The factory index of the proposal whose published data to check
+    #[clap(long)]
+    pub game_index: u64,
+    /// Secret key to sign the report with
+    #[clap(long, env)]
+    pub reporter_key: Option<String>,
+    /// Path to write the report to instead of stdout
+    #[clap(long, env)]
+    pub report_file: Option<PathBuf>,
+}
+
+/// The local verification of one blob referenced by a proposal
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlobCheck {
+    pub index: usize,
+    /// The versioned hash committed to on-chain by the proposal
+    pub versioned_hash: B256,
+    /// The commitment served by the beacon node
+    pub kzg_commitment: Bytes,
+    /// Whether the served commitment hashes to the committed versioned hash
+    pub versioned_hash_matches: bool,
+    /// Whether the served blob recomputes to the served commitment
+    pub commitment_matches: bool,
+    /// Whether the served blob proof verifies against the commitment
+    pub proof_valid: bool,
+}
+
+/// An intermediate output published by the proposer that differs from the local one
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputDivergence {
+    pub position: u64,
+    pub block_number: u64,
+    pub published_field_element: B256,
+    pub expected_output_root: B256,
+    pub expected_field_element: B256,
+}
+
+/// The evidence of what data a proposer published and how it compares to the local outputs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlobEquivalenceReport {
+    pub game_index: u64,
+    pub game_contract: Address,
+    pub proposer: Address,
+    pub l1_head: B256,
+    pub output_block_number: u64,
+    pub blobs: Vec<BlobCheck>,
+    pub divergences: Vec<OutputDivergence>,
+    pub root_claim: B256,
+    pub expected_root_claim: B256,
+}
+
+impl BlobEquivalenceReport {
+    pub fn is_consistent(&self) -> bool {
+        self.blobs
+            .iter()
+            .all(|b| b.versioned_hash_matches && b.commitment_matches && b.proof_valid)
+    }
+
+    pub fn diverges(&self) -> bool {
+        !self.divergences.is_empty() || self.root_claim != self.expected_root_claim
+    }
+}
+
+/// A report signed by the party that produced it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedBlobReport {
+    pub report: BlobEquivalenceReport,
+    pub reporter: Option<Address>,
+    pub signature: Option<Bytes>,
+}
+
+/// Verifies a published blob and its commitment locally
+pub fn check_blob(
+    index: usize,
+    versioned_hash: B256,
+    blob_data: &BlobData,
+) -> anyhow::Result<BlobCheck> {
+    let settings = alloy::consensus::EnvKzgSettings::default();
+    let c_kzg_blob = c_kzg::Blob::from_bytes(blob_data.blob.as_slice())?;
+    let commitment = c_kzg::Bytes48::from_bytes(blob_data.kzg_commitment.as_slice())?;
+    let proof = c_kzg::Bytes48::from_bytes(blob_data.kzg_proof.as_slice())?;
+    let computed_commitment =
+        c_kzg::KzgCommitment::blob_to_kzg_commitment(&c_kzg_blob, settings.get())?;
+    let proof_valid =
+        c_kzg::KzgProof::verify_blob_kzg_proof(&c_kzg_blob, &commitment, &proof, settings.get())
+            .unwrap_or_default();
+    Ok(BlobCheck {
+        index,
+        versioned_hash,
+        kzg_commitment: Bytes::from(blob_data.kzg_commitment.to_vec()),
+        versioned_hash_matches: kzg_to_versioned_hash(blob_data.kzg_commitment.as_slice())
+            == versioned_hash,
+        commitment_matches: computed_commitment.to_bytes().into_inner() == commitment.into_inner(),
+        proof_valid,
+    })
+}
+
+pub async fn blob_report(args: BlobReportArgs) -> anyhow::Result<()> {
+    let eth_rpc_provider = ProviderBuilder::new().on_http(args.eth_rpc_url.as_str().try_into()?);
+    let op_node_provider =
+        OpNodeProvider::new(ProviderBuilder::new().on_http(args.op_node_url.as_str().try_into()?));
+    let blob_provider = BlobProvider::new(&args.beacon_rpc_url).await?;
+    let dispute_game_factory =
+        IDisputeGameFactory::new(args.dispute_game_factory, &eth_rpc_provider);
+
+    // Load the proposal exactly as published
+    let gameAtIndexReturn {
+        gameType_: game_type,
+        proxy_: game_address,
+        ..
+    } = dispute_game_factory
+        .gameAtIndex(U256::from(args.game_index))
+        .stall()
+        .await;
+    if game_type != KAILUA_GAME_TYPE {
+        bail!(
+            "Game {} is of type {game_type} instead of {KAILUA_GAME_TYPE}.",
+            args.game_index
+        );
+    }
+    let config = Config::load(&KailuaGame::new(
+        dispute_game_factory
+            .gameImpls(KAILUA_GAME_TYPE)
+            .stall()
+            .await
+            .impl_,
+        &eth_rpc_provider,
+    ))
+    .await?;
+    let tournament = KailuaTournament::new(game_address, &eth_rpc_provider);
+    let proposal = Proposal::load(&config, &blob_provider, &tournament)
+        .await
+        .context("Failed to load published proposal data")?;
+    if !proposal.has_parent() {
+        bail!("Game {} is a treasury without blob data.", args.game_index);
+    }
+    info!(
+        "Checking {} blob(s) published by {} for game {} ({game_address}).",
+        proposal.io_blobs.len(),
+        proposal.proposer,
+        args.game_index
+    );
+
+    // Verify the published blobs against their on-chain versioned hashes
+    let blobs = proposal
+        .io_blobs
+        .iter()
+        .enumerate()
+        .map(|(i, (versioned_hash, blob_data))| check_blob(i, *versioned_hash, blob_data))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    // Compare the published intermediate outputs to the locally computed ones
+    let starting_block_number = proposal
+        .output_block_number
+        .saturating_sub(config.proposal_block_count);
+    let mut divergences = Vec::new();
+    for (position, published) in proposal.io_field_elements.iter().enumerate() {
+        let block_number = config.output_block_number(starting_block_number, position as u64);
+        let expected_output_root = op_node_provider
+            .output_at_block(block_number)
+            .await
+            .context("output_at_block")?;
+        let expected_field_element = config.field_encoding.output_to_fe(expected_output_root);
+        if expected_field_element != *published {
+            divergences.push(OutputDivergence {
+                position: position as u64,
+                block_number,
+                published_field_element: *published,
+                expected_output_root,
+                expected_field_element,
+            });
+        }
+    }
+    let expected_root_claim = op_node_provider
+        .output_at_block(proposal.output_block_number)
+        .await
+        .context("output_at_block")?;
+
+    let report = BlobEquivalenceReport {
+        game_index: args.game_index,
+        game_contract: game_address,
+        proposer: proposal.proposer,
+        l1_head: proposal.l1_head,
+        output_block_number: proposal.output_block_number,
+        blobs,
+        divergences,
+        root_claim: proposal.output_root,
+        expected_root_claim,
+    };
+    if !report.is_consistent() {
+        warn!("Published blobs do not match their on-chain commitments.");
+    }
+    match report.divergences.first() {
+        Some(first) => warn!(
+            "Published outputs diverge from block {} onwards ({} of {}).",
+            first.block_number,
+            report.divergences.len(),
+            proposal.io_field_elements.len()
+        ),
+        None => info!("All published intermediate outputs match."),
+    }
+
+    // Sign the report
+    let signed_report = match &args.reporter_key {
+        Some(reporter_key) => {
+            let signer = LocalSigner::from_str(reporter_key)?;
+            let signature = signer
+                .sign_message(&serde_json::to_vec(&report)?)
+                .await
+                .context("Failed to sign report")?;
+            SignedBlobReport {
+                report,
+                reporter: Some(signer.address()),
+                signature: Some(Bytes::from(signature.as_bytes())),
+            }
+        }
+        None => {
+            warn!("No reporter key provided. The report will be unsigned.");
+            SignedBlobReport {
+                report,
+                reporter: None,
+                signature: None,
+            }
+        }
+    };
+    let json = serde_json::to_string_pretty(&signed_report)?;
+    match &args.report_file {
+        Some(path) => {
+            std::fs::write(path, json).context(format!("Failed to write {path:?}"))?;
+            info!("Saved report to {path:?}.");
+        }
+        None => println!("{json}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,130 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::network::Network;
+use alloy::primitives::U256;
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use kailua_contracts::KailuaTreasury::KailuaTreasuryInstance;
+use tracing::{error, info, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct BondArgs {
+    /// Whether to refuse paying in additional collateral when the participation bond is raised
+    #[clap(long, env)]
+    pub disable_bond_top_up: bool,
+    /// Maximum total participation bond (in wei) to lock in the treasury
+    #[clap(long, env)]
+    pub bond_ceiling: Option<U256>,
+}
+
+/// Tracks the participation bond required by the treasury and decides how much collateral to
+/// pay in alongside the next proposal.
+#[derive(Clone, Debug)]
+pub struct BondMonitor {
+    pub args: BondArgs,
+    pub last_bond: Option<U256>,
+    /// The next L1 block to scan for participation bond updates
+    pub next_block: Option<u64>,
+}
+
+impl BondMonitor {
+    pub fn new(args: BondArgs) -> Self {
+        Self {
+            args,
+            last_bond: None,
+            next_block: None,
+        }
+    }
+
+    /// Reads the `BondUpdated` events emitted by the treasury since the last scan, returning the
+    /// latest participation bond if governance changed it.
+    pub async fn watch<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        treasury: &KailuaTreasuryInstance<T, P, N>,
+    ) -> anyhow::Result<Option<U256>> {
+        let latest_block = treasury
+            .provider()
+            .get_block_number()
+            .await
+            .context("get_block_number")?;
+        // only updates made after startup are news
+        let from_block = *self.next_block.get_or_insert(latest_block + 1);
+        if latest_block < from_block {
+            return Ok(None);
+        }
+        let events = treasury
+            .BondUpdated_filter()
+            .from_block(from_block)
+            .to_block(latest_block)
+            .query()
+            .await
+            .context("BondUpdated_filter")?;
+        self.next_block = Some(latest_block + 1);
+        let Some((event, log)) = events.last() else {
+            return Ok(None);
+        };
+        let bond_value = event.amount;
+        match self.last_bond.replace(bond_value) {
+            Some(last_bond) if last_bond == bond_value => return Ok(None),
+            Some(last_bond) => warn!(
+                "Participation bond updated from {last_bond} to {bond_value} at L1 block {:?}.",
+                log.block_number
+            ),
+            None => warn!(
+                "Participation bond updated to {bond_value} at L1 block {:?}.",
+                log.block_number
+            ),
+        }
+        if let Some(bond_ceiling) = self.args.bond_ceiling {
+            if bond_value > bond_ceiling {
+                error!("BOND CEILING EXCEEDED! Participation bond of {bond_value} exceeds the ceiling of {bond_ceiling}. Proposals are paused.");
+            }
+        }
+        Ok(Some(bond_value))
+    }
+
+    /// Returns the collateral owed for the next proposal, or an error if it should not be paid
+    pub fn owed_collateral(
+        &mut self,
+        bond_value: U256,
+        paid_in: U256,
+        balance: U256,
+    ) -> anyhow::Result<U256> {
+        match self.last_bond.replace(bond_value) {
+            Some(last_bond) if last_bond != bond_value => {
+                warn!("Participation bond changed from {last_bond} to {bond_value}.")
+            }
+            None => info!("Participation bond is {bond_value} ({paid_in} paid in)."),
+            _ => {}
+        }
+        let owed_collateral = bond_value.saturating_sub(paid_in);
+        if owed_collateral.is_zero() {
+            return Ok(owed_collateral);
+        }
+        if self.args.disable_bond_top_up && !paid_in.is_zero() {
+            bail!("Participation bond of {bond_value} exceeds the {paid_in} paid in and top-ups are disabled.");
+        }
+        if let Some(bond_ceiling) = self.args.bond_ceiling {
+            if bond_value > bond_ceiling {
+                bail!("Participation bond of {bond_value} exceeds the ceiling of {bond_ceiling}.");
+            }
+        }
+        if balance < owed_collateral {
+            bail!("INSUFFICIENT BALANCE! Need to lock in at least {owed_collateral}.");
+        }
+        Ok(owed_collateral)
+    }
+}
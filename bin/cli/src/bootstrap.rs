@@ -0,0 +1,279 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::config::Config;
+use crate::providers::optimism::OpNodeProvider;
+use crate::stall::Stall;
+use crate::{KAILUA_GAME_TYPE, SET_BUILDER_ID};
+use alloy::primitives::{Address, FixedBytes, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use anyhow::{bail, Context};
+use kailua_common::journal::PROOF_JOURNAL_VERSION;
+use kailua_contracts::{IDisputeGameFactory::gameAtIndexReturn, *};
+use kailua_host::fetch_rollup_config;
+use risc0_zkvm::sha::Digestible;
+use serde::Serialize;
+use std::str::FromStr;
+use tracing::info;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct BootstrapStatusArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the OP-NODE endpoint to use
+    #[clap(long, env)]
+    pub op_node_url: String,
+    /// Address of the OP-GETH endpoint to use (eth and debug namespace required).
+    #[clap(long, env)]
+    pub op_geth_url: String,
+    /// Address of the ethereum rpc endpoint to use (eth namespace required)
+    #[clap(long, env)]
+    pub eth_rpc_url: String,
+
+    /// Hex-encoded verifier selectors that must be routed by the verifier (defaults to the
+    /// Groth16 and Boundless set verifier selectors)
+    #[clap(long, env, value_delimiter = ',')]
+    pub verifier_selectors: Vec<String>,
+
+    /// Whether to print the checklist as JSON
+    #[clap(long, env)]
+    pub json: bool,
+}
+
+/// The outcome of a single post-deployment invariant check
+#[derive(Clone, Debug, Serialize)]
+pub struct BootstrapCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BootstrapChecklist {
+    pub checks: Vec<BootstrapCheck>,
+}
+
+impl BootstrapChecklist {
+    pub fn check(&mut self, name: &str, passed: bool, detail: impl Into<String>) -> bool {
+        self.checks.push(BootstrapCheck {
+            name: name.to_string(),
+            passed,
+            detail: detail.into(),
+        });
+        passed
+    }
+
+    pub fn failures(&self) -> usize {
+        self.checks.iter().filter(|check| !check.passed).count()
+    }
+
+    pub fn print(&self, json: bool) -> anyhow::Result<()> {
+        if json {
+            println!("{}", serde_json::to_string_pretty(self)?);
+        } else {
+            for check in &self.checks {
+                let mark = if check.passed { "PASS" } else { "FAIL" };
+                println!("[{mark}] {}: {}", check.name, check.detail);
+            }
+        }
+        Ok(())
+    }
+}
+
+pub async fn bootstrap_status(args: BootstrapStatusArgs) -> anyhow::Result<()> {
+    let op_node_provider =
+        OpNodeProvider::new(ProviderBuilder::new().on_http(args.op_node_url.as_str().try_into()?));
+    let eth_rpc_provider = ProviderBuilder::new().on_http(args.eth_rpc_url.as_str().try_into()?);
+
+    info!("Fetching rollup configuration from rpc endpoints.");
+    let config = fetch_rollup_config(&args.op_node_url, &args.op_geth_url, None)
+        .await
+        .context("fetch_rollup_config")?;
+    let system_config = SystemConfig::new(config.l1_system_config_address, &eth_rpc_provider);
+    let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
+    let dispute_game_factory = IDisputeGameFactory::new(dgf_address, &eth_rpc_provider);
+    info!("DisputeGameFactory({dgf_address:?})");
+
+    let expected_selectors = if args.verifier_selectors.is_empty() {
+        let groth16_parameters = risc0_zkvm::Groth16ReceiptVerifierParameters::default()
+            .digest::<risc0_zkvm::sha::Impl>();
+        vec![
+            FixedBytes::<4>::from_slice(&groth16_parameters.as_bytes()[..4]),
+            FixedBytes::<4>::from(kailua_client::set_verifier_selector(SET_BUILDER_ID)),
+        ]
+    } else {
+        args.verifier_selectors
+            .iter()
+            .map(|selector| FixedBytes::<4>::from_str(selector))
+            .collect::<Result<Vec<_>, _>>()
+            .context("Invalid verifier selector")?
+    };
+
+    let mut checklist = BootstrapChecklist::default();
+
+    // Factory implementation
+    let game_implementation_address = dispute_game_factory
+        .gameImpls(KAILUA_GAME_TYPE)
+        .stall()
+        .await
+        .impl_;
+    let has_code = !eth_rpc_provider
+        .get_code_at(game_implementation_address)
+        .await
+        .context("get_code_at")?
+        .is_empty();
+    if !checklist.check(
+        "factory implementation set",
+        has_code,
+        format!("gameImpls({KAILUA_GAME_TYPE}) = {game_implementation_address}"),
+    ) {
+        checklist.print(args.json)?;
+        bail!("Kailua is not installed in DisputeGameFactory {dgf_address}.");
+    }
+    let game_implementation = KailuaGame::new(game_implementation_address, &eth_rpc_provider);
+    let game_config = Config::load(&game_implementation).await?;
+    let game_type = game_implementation.gameType().stall().await.gameType_;
+    checklist.check(
+        "factory implementation game type",
+        game_type == KAILUA_GAME_TYPE && game_config.factory == dgf_address,
+        format!(
+            "KailuaGame reports game type {game_type} in factory {}",
+            game_config.factory
+        ),
+    );
+
+    checklist.check(
+        "proof journal format",
+        game_config.journal_version == PROOF_JOURNAL_VERSION
+            && game_config.l2_chain_id == config.l2_chain_id,
+        format!(
+            "KailuaGame expects journal version {} for chain {}",
+            game_config.journal_version, game_config.l2_chain_id
+        ),
+    );
+
+    // Bonds
+    let init_bond = dispute_game_factory
+        .initBonds(KAILUA_GAME_TYPE)
+        .stall()
+        .await
+        .bond_;
+    checklist.check(
+        "initialization bond",
+        init_bond.is_zero(),
+        format!("initBonds({KAILUA_GAME_TYPE}) = {init_bond} wei"),
+    );
+    let treasury_implementation = KailuaTreasury::new(game_config.treasury, &eth_rpc_provider);
+    let participation_bond = treasury_implementation.participationBond().stall().await._0;
+    checklist.check(
+        "participation bond",
+        participation_bond > U256::ZERO,
+        format!(
+            "KailuaTreasury({}) participation bond = {participation_bond} wei",
+            game_config.treasury
+        ),
+    );
+
+    // Treasury instance anchoring the proposals
+    let game_count: u64 = dispute_game_factory
+        .gameCount()
+        .stall()
+        .await
+        .gameCount_
+        .to();
+    let mut treasury_instance = None;
+    for index in (0..game_count).rev() {
+        let gameAtIndexReturn {
+            gameType_: game_type,
+            proxy_: game_address,
+            ..
+        } = dispute_game_factory
+            .gameAtIndex(U256::from(index))
+            .stall()
+            .await;
+        if game_type != KAILUA_GAME_TYPE {
+            continue;
+        }
+        let tournament = KailuaTournament::new(game_address, &eth_rpc_provider);
+        if tournament.parentGame().stall().await.parentGame_ != game_address {
+            continue;
+        }
+        if tournament.treasury().stall().await.treasury_ == game_config.treasury {
+            treasury_instance = Some((index, game_address));
+            break;
+        }
+    }
+    if let Some((index, instance_address)) = treasury_instance {
+        checklist.check(
+            "treasury instance created",
+            true,
+            format!("KailuaTreasury instance {instance_address} at factory index {index}"),
+        );
+        let instance = KailuaTreasury::new(instance_address, &eth_rpc_provider);
+        let status = instance.status().stall().await._0;
+        checklist.check(
+            "treasury instance resolved",
+            status == 2,
+            format!("status = {status}"),
+        );
+        let anchor_block: u64 = instance.l2BlockNumber().stall().await.l2BlockNumber_.to();
+        let anchor_claim = instance.rootClaim().stall().await.rootClaim_;
+        let expected_claim = op_node_provider.output_at_block(anchor_block).await?;
+        checklist.check(
+            "anchor output",
+            anchor_claim == expected_claim,
+            format!(
+                "root claim {anchor_claim} at block {anchor_block} (op-node: {expected_claim})"
+            ),
+        );
+        let anchor_config = instance.configHash().stall().await.configHash_;
+        let anchor_image = instance.imageId().stall().await.imageId_;
+        checklist.check(
+            "anchor configuration",
+            anchor_config == game_config.cfg_hash && anchor_image == game_config.image_id,
+            format!("config hash {anchor_config}, image id {anchor_image}"),
+        );
+    } else {
+        checklist.check(
+            "treasury instance created",
+            false,
+            format!(
+                "No instance of KailuaTreasury({}) found in the factory",
+                game_config.treasury
+            ),
+        );
+    }
+
+    // Verifier routing
+    let router = RiscZeroVerifierRouter::new(game_config.verifier, &eth_rpc_provider);
+    for selector in expected_selectors {
+        let verifier = router.verifiers(selector).stall().await._0;
+        checklist.check(
+            "verifier routing",
+            verifier != Address::ZERO && verifier != Address::with_last_byte(1),
+            format!(
+                "RiscZeroVerifierRouter({}) routes {selector} to {verifier}",
+                game_config.verifier
+            ),
+        );
+    }
+
+    checklist.print(args.json)?;
+    let failures = checklist.failures();
+    if failures > 0 {
+        bail!("{failures} bootstrap checks failed.");
+    }
+    Ok(())
+}
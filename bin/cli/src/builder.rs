@@ -0,0 +1,171 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::challenge::ChallengeArgs;
+use crate::propose::ProposeArgs;
+use crate::prove::ProveArgs;
+use crate::resolve::ResolveArgs;
+use crate::validate::ValidateArgs;
+use alloy::primitives::{Address, ChainId};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::transports::http::reqwest::Url;
+use anyhow::{ensure, Context};
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Parses an rpc endpoint url
+pub fn parse_url(url: &str) -> anyhow::Result<Url> {
+    let parsed = Url::parse(url).context(format!("Invalid url {url}"))?;
+    ensure!(
+        matches!(parsed.scheme(), "http" | "https" | "ws" | "wss"),
+        "Unsupported scheme in url {url}"
+    );
+    Ok(parsed)
+}
+
+/// Parses an address, enforcing its EIP-55 checksum if it is written in mixed case
+pub fn parse_address(address: &str) -> anyhow::Result<Address> {
+    let digits = address.strip_prefix("0x").unwrap_or(address);
+    let mixed_case = digits.chars().any(|c| c.is_ascii_uppercase())
+        && digits.chars().any(|c| c.is_ascii_lowercase());
+    if mixed_case {
+        Address::parse_checksummed(address, None)
+            .context(format!("Invalid address checksum {address}"))
+    } else {
+        Address::from_str(address).context(format!("Invalid address {address}"))
+    }
+}
+
+/// Parses a hex-encoded secp256k1 secret key
+pub fn parse_key(key: &str) -> anyhow::Result<PrivateKeySigner> {
+    PrivateKeySigner::from_str(key).context("Invalid secret key")
+}
+
+/// Assembles the arguments of a subcommand from typed values, for use of the subcommands as a
+/// library. Arguments left unset fall back to their environment variables and defaults, exactly
+/// as on the command line.
+#[derive(Clone, Debug)]
+pub struct ArgsBuilder<A> {
+    pub argv: Vec<String>,
+    _args: PhantomData<A>,
+}
+
+impl<A> Default for ArgsBuilder<A> {
+    fn default() -> Self {
+        Self {
+            argv: vec![],
+            _args: PhantomData,
+        }
+    }
+}
+
+impl<A: clap::Args> ArgsBuilder<A> {
+    /// Sets the argument with the given long name to the value
+    pub fn value(mut self, name: &str, value: impl Display) -> Self {
+        self.argv.push(format!("--{name}"));
+        self.argv.push(value.to_string());
+        self
+    }
+
+    /// Sets the boolean argument with the given long name
+    pub fn flag(mut self, name: &str) -> Self {
+        self.argv.push(format!("--{name}"));
+        self
+    }
+
+    pub fn url(self, name: &str, url: &Url) -> Self {
+        self.value(name, url)
+    }
+
+    pub fn address(self, name: &str, address: Address) -> Self {
+        self.value(name, address.to_checksum(None))
+    }
+
+    pub fn key(self, name: &str, signer: &PrivateKeySigner) -> Self {
+        self.value(name, signer.to_bytes())
+    }
+
+    pub fn chain_id(self, name: &str, chain_id: ChainId) -> Self {
+        self.value(name, chain_id)
+    }
+
+    pub fn path(self, name: &str, path: &Path) -> Self {
+        self.value(name, path.display())
+    }
+
+    pub fn verbosity(mut self, verbosity: u8) -> Self {
+        self.argv.extend((0..verbosity).map(|_| String::from("-v")));
+        self
+    }
+
+    pub fn op_node_url(self, url: &Url) -> Self {
+        self.url("op-node-url", url)
+    }
+
+    pub fn op_geth_url(self, url: &Url) -> Self {
+        self.url("op-geth-url", url)
+    }
+
+    pub fn eth_rpc_url(self, url: &Url) -> Self {
+        self.url("eth-rpc-url", url)
+    }
+
+    pub fn beacon_rpc_url(self, url: &Url) -> Self {
+        self.url("beacon-rpc-url", url)
+    }
+
+    pub fn data_dir(self, data_dir: &Path) -> Self {
+        self.path("data-dir", data_dir)
+    }
+
+    /// Validates the arguments as the command line parser would and returns them
+    pub fn build(self) -> anyhow::Result<A> {
+        let matches = A::augment_args(clap::Command::new("kailua-cli").no_binary_name(true))
+            .try_get_matches_from(&self.argv)
+            .context("Invalid arguments")?;
+        <A as clap::FromArgMatches>::from_arg_matches(&matches).context("Invalid arguments")
+    }
+}
+
+impl ProposeArgs {
+    pub fn builder() -> ArgsBuilder<Self> {
+        ArgsBuilder::default()
+    }
+}
+
+impl ValidateArgs {
+    pub fn builder() -> ArgsBuilder<Self> {
+        ArgsBuilder::default()
+    }
+}
+
+impl ProveArgs {
+    pub fn builder() -> ArgsBuilder<Self> {
+        ArgsBuilder::default()
+    }
+}
+
+impl ChallengeArgs {
+    pub fn builder() -> ArgsBuilder<Self> {
+        ArgsBuilder::default()
+    }
+}
+
+impl ResolveArgs {
+    pub fn builder() -> ArgsBuilder<Self> {
+        ArgsBuilder::default()
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+use tracing::info;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct CadenceArgs {
+    /// Minimum number of milliseconds to wait between scans while disputes are active
+    #[clap(long, env, default_value_t = 250)]
+    pub min_poll_interval: u64,
+    /// Maximum number of milliseconds to wait between scans while idle
+    #[clap(long, env, default_value_t = 12_000)]
+    pub max_poll_interval: u64,
+}
+
+/// An adaptive polling interval that backs off exponentially while idle
+#[derive(Clone, Debug)]
+pub struct Cadence {
+    pub min: Duration,
+    pub max: Duration,
+    pub current: Duration,
+}
+
+impl Cadence {
+    pub fn new(args: &CadenceArgs) -> Self {
+        let min = Duration::from_millis(args.min_poll_interval.max(1));
+        let max = Duration::from_millis(args.max_poll_interval).max(min);
+        Self {
+            min,
+            max,
+            current: min,
+        }
+    }
+
+    /// Applies new polling bounds, keeping the current interval within them
+    pub fn reconfigure(&mut self, args: &CadenceArgs) {
+        let current = self.current;
+        *self = Self::new(args);
+        self.current = current.clamp(self.min, self.max);
+    }
+
+    /// Returns the time to wait before the next scan
+    pub fn interval(&self) -> Duration {
+        self.current
+    }
+
+    /// Adjusts the interval after a scan depending on whether anything warrants attention
+    pub fn update(&mut self, active: bool) {
+        let next = if active {
+            self.min
+        } else {
+            (self.current * 2).min(self.max)
+        };
+        if next != self.current {
+            info!(
+                "Polling cadence changed from {}ms to {}ms.",
+                self.current.as_millis(),
+                next.as_millis()
+            );
+        }
+        self.current = next;
+    }
+}
@@ -0,0 +1,276 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::address_book::{AddressArg, AddressBook, AddressBookArgs};
+use crate::db::config::Config;
+use crate::db::proposal::Proposal;
+use crate::providers::beacon::BlobProvider;
+use crate::providers::optimism::OpNodeProvider;
+use crate::stall::Stall;
+use crate::transact::{send_private_transaction, PrivateTxnArgs};
+use crate::KAILUA_GAME_TYPE;
+use alloy::eips::{BlockId, BlockNumberOrTag};
+use alloy::network::primitives::BlockTransactionsKind;
+use alloy::network::EthereumWallet;
+use alloy::primitives::Bytes;
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::signers::local::LocalSigner;
+use alloy::sol_types::SolValue;
+use anyhow::{bail, Context};
+use kailua_contracts::*;
+use std::io::Write;
+use std::str::FromStr;
+use tracing::info;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ChallengeArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the OP-NODE endpoint to use
+    #[clap(long, env)]
+    pub op_node_url: String,
+    /// Address of the ethereum rpc endpoint to use (eth namespace required)
+    #[clap(long, env)]
+    pub eth_rpc_url: String,
+    /// Address of the L1 Beacon API endpoint to use.
+    #[clap(long, env)]
+    pub beacon_rpc_url: String,
+
+    /// Address, address book alias or ENS name of the KailuaGame instance to challenge
+    #[clap(long)]
+    pub game: AddressArg,
+    /// Secret key of L1 wallet to publish the challenging proposal with
+    #[clap(long, env)]
+    pub challenger_key: String,
+    /// Whether to submit the challenge without asking for confirmation
+    #[clap(long)]
+    pub yes: bool,
+
+    #[clap(flatten)]
+    pub address_book: AddressBookArgs,
+    #[clap(flatten)]
+    pub private_txn: PrivateTxnArgs,
+}
+
+/// Publishes the correct sibling of an incorrect proposal after checking it is worth contesting
+pub async fn challenge(args: ChallengeArgs) -> anyhow::Result<()> {
+    let op_node_provider =
+        OpNodeProvider::new(ProviderBuilder::new().on_http(args.op_node_url.as_str().try_into()?));
+    let blob_provider = BlobProvider::new(&args.beacon_rpc_url).await?;
+    let challenger_signer = LocalSigner::from_str(&args.challenger_key)?;
+    let challenger_address = challenger_signer.address();
+    let challenger_wallet = EthereumWallet::from(challenger_signer);
+    let challenger_provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(&challenger_wallet)
+        .on_http(args.eth_rpc_url.as_str().try_into()?);
+    let private_txn_provider = match &args.private_txn.private_rpc_url {
+        Some(private_rpc_url) => Some(
+            ProviderBuilder::new()
+                .with_recommended_fillers()
+                .wallet(&challenger_wallet)
+                .on_http(private_rpc_url.as_str().try_into()?),
+        ),
+        None => None,
+    };
+    let bundle_txn_provider = match &args.private_txn.private_bundle_url {
+        Some(private_bundle_url) => Some(
+            ProviderBuilder::new()
+                .with_recommended_fillers()
+                .wallet(&challenger_wallet)
+                .on_http(private_bundle_url.as_str().try_into()?),
+        ),
+        None => None,
+    };
+    let game_address = AddressBook::load(&args.address_book)?
+        .resolve(&args.game, &challenger_provider)
+        .await?;
+
+    // Verify that the game was created by the factory as a KailuaGame
+    let tournament = KailuaTournament::new(game_address, &challenger_provider);
+    let game_data = tournament.gameData().stall().await;
+    if game_data.gameType_ != KAILUA_GAME_TYPE {
+        bail!(
+            "Game {} is of type {} instead of {KAILUA_GAME_TYPE}.",
+            game_address,
+            game_data.gameType_
+        );
+    }
+    let dispute_game_factory = IDisputeGameFactory::new(
+        tournament.disputeGameFactory().stall().await.factory_,
+        &challenger_provider,
+    );
+    let factory_game = dispute_game_factory
+        .games(
+            KAILUA_GAME_TYPE,
+            game_data.rootClaim_,
+            game_data.extraData_.clone(),
+        )
+        .stall()
+        .await
+        .proxy_;
+    if factory_game != game_address {
+        bail!(
+            "Game {} was not created by its dispute game factory.",
+            game_address
+        );
+    }
+    if tournament.parentGame().stall().await.parentGame_ == game_address {
+        bail!("Game {} is a treasury instance.", game_address);
+    }
+    let config = Config::load(&KailuaGame::new(
+        dispute_game_factory
+            .gameImpls(KAILUA_GAME_TYPE)
+            .stall()
+            .await
+            .impl_,
+        &challenger_provider,
+    ))
+    .await?;
+    let proposal = Proposal::load(&config, &blob_provider, &tournament).await?;
+
+    // Compare the proposal against the op-node
+    let parent_block_number = proposal.output_block_number - config.proposal_block_count;
+    let mut io_field_elements = vec![];
+    for position in 0..config.proposal_output_count() - 1 {
+        let block_number = config.output_block_number(parent_block_number, position);
+        io_field_elements.push(
+            config
+                .field_encoding
+                .output_to_fe(op_node_provider.output_at_block(block_number).await?),
+        );
+    }
+    let output_root = op_node_provider
+        .output_at_block(proposal.output_block_number)
+        .await?;
+    let divergence = io_field_elements
+        .iter()
+        .zip(&proposal.io_field_elements)
+        .position(|(expected, published)| expected != published)
+        .map(|position| config.output_block_number(parent_block_number, position as u64))
+        .or((output_root != proposal.output_root).then_some(proposal.output_block_number));
+    let Some(divergent_block) = divergence else {
+        bail!(
+            "Game {} agrees with the op-node and should not be challenged.",
+            game_address
+        );
+    };
+    println!(
+        "Game {} ({}) first diverges from the op-node at block {divergent_block}.",
+        proposal.index, game_address
+    );
+
+    // Report the clock and bond requirements
+    let timestamp = challenger_provider
+        .get_block_by_number(BlockNumberOrTag::Latest, BlockTransactionsKind::Hashes)
+        .await?
+        .context("Latest block not found")?
+        .header
+        .timestamp;
+    let remaining_clock = tournament.remaining_challenge_clock(timestamp).await?;
+    println!("Challenge clock: {remaining_clock}s remaining.");
+    if remaining_clock == 0 {
+        bail!("Game {} can no longer be challenged.", game_address);
+    }
+    let treasury = KailuaTreasury::new(config.treasury, &challenger_provider);
+    let bond_value = treasury.participationBond().stall().await._0;
+    let paid_in = treasury.paidBonds(challenger_address).stall().await._0;
+    let owed_collateral = bond_value.saturating_sub(paid_in);
+    let balance = challenger_provider.get_balance(challenger_address).await?;
+    println!(
+        "Participation bond: {bond_value} wei ({paid_in} paid in, {owed_collateral} owed, balance {balance})."
+    );
+    if balance < owed_collateral {
+        bail!("INSUFFICIENT BALANCE! Need to lock in at least {owed_collateral}.");
+    }
+
+    // Find an unused duplication counter for the correct sibling
+    let mut dupe_counter = 0u64;
+    let extra_data = loop {
+        let extra_data = [
+            proposal.output_block_number.abi_encode_packed(),
+            proposal.parent.abi_encode_packed(),
+            dupe_counter.abi_encode_packed(),
+        ]
+        .concat();
+        let dupe_game_address = dispute_game_factory
+            .games(
+                KAILUA_GAME_TYPE,
+                output_root,
+                Bytes::from(extra_data.clone()),
+            )
+            .stall()
+            .await
+            .proxy_;
+        if dupe_game_address.is_zero() {
+            break extra_data;
+        }
+        let dupe = Proposal::load(
+            &config,
+            &blob_provider,
+            &KailuaTournament::new(dupe_game_address, &challenger_provider),
+        )
+        .await?;
+        if dupe.io_field_elements == io_field_elements {
+            bail!(
+                "Game {} is already challenged by {dupe_game_address}.",
+                game_address
+            );
+        }
+        dupe_counter += 1;
+    };
+
+    // Confirm the challenge
+    if !args.yes {
+        print!(
+            "Challenge game {} with output {output_root}? [y/N] ",
+            game_address
+        );
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            println!("Challenge aborted.");
+            return Ok(());
+        }
+    }
+
+    let propose_call = treasury
+        .propose(output_root, Bytes::from(extra_data))
+        .value(owed_collateral)
+        .sidecar(Proposal::create_sidecar(&io_field_elements)?);
+    propose_call
+        .call()
+        .block(BlockId::pending())
+        .await
+        .context("Challenge fails simulation")?;
+    info!(
+        "Challenging game {} with output {output_root}.",
+        game_address
+    );
+    let receipt = send_private_transaction(
+        propose_call.into_transaction_request(),
+        challenger_address,
+        private_txn_provider.as_ref(),
+        bundle_txn_provider.as_ref(),
+        &challenger_provider,
+        &challenger_wallet,
+        &args.private_txn,
+    )
+    .await
+    .context("propose")?;
+    println!("Challenge submitted: {}", receipt.transaction_hash);
+    Ok(())
+}
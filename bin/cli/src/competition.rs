@@ -0,0 +1,308 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::proposal::Proposal;
+use alloy::consensus::BlockHeader;
+use alloy::network::primitives::BlockTransactionsKind;
+use alloy::network::{BlockResponse, Network, TransactionResponse};
+use alloy::primitives::Address;
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProvingStrategy {
+    /// Request a proof for every unproven match as soon as it is found
+    #[default]
+    Race,
+    /// Wait for the deferral period to give other validators a chance to prove first
+    Defer,
+    /// Leave matches in tournaments where other validators have published proofs to them for the
+    /// deferral period
+    Uncontested,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct CompetitionArgs {
+    /// Strategy to use when other validators may prove the same matches
+    #[clap(long, env, value_enum, default_value_t = ProvingStrategy::Race)]
+    pub proving_strategy: ProvingStrategy,
+    /// Number of seconds to wait before requesting a proof under the `defer` strategy, or before
+    /// proving a match left to other validators under the `uncontested` strategy
+    #[clap(long, env, default_value_t = 300)]
+    pub proving_defer_secs: u64,
+    /// Number of L1 blocks to wait for after verifying a proof locally before checking that its
+    /// match is still unproven at a block with as many confirmations and submitting it
+    #[clap(long, env, default_value_t = 0)]
+    pub proof_submission_confirmations: u64,
+}
+
+/// Proposal indices whose queued or in-flight proofs are no longer needed
+pub type CancelledProofs = Arc<Mutex<HashSet<u64>>>;
+
+/// The reason an in-flight proof was abandoned
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Abort {
+    /// The proof is no longer needed
+    Cancelled,
+    /// The proof is at risk of missing its deadline on the current backend
+    Deadline,
+    /// The proving job outgrew the disk quota
+    Quota,
+}
+
+pub enum ProvingDecision {
+    /// Request the proof now
+    Prove,
+    /// Reconsider the proof on a later iteration
+    Defer,
+}
+
+/// Keeps track of the validators competing with this one to prove matches
+#[derive(Debug)]
+pub struct Competition {
+    pub args: CompetitionArgs,
+    pub validator: Address,
+    /// When each proposal's match was first found unproven
+    pub first_seen: HashMap<u64, Instant>,
+    /// Number of proofs published by other validators in each tournament
+    pub rival_proofs: HashMap<Address, HashMap<Address, usize>>,
+    /// The next L1 block to scan for `Proven` events of each tournament
+    pub rival_scans: HashMap<Address, u64>,
+    /// Matches queued for local proving, keyed by proposal index
+    pub queued: HashMap<u64, (u64, u64, u64)>,
+    pub cancelled: CancelledProofs,
+    /// Number of local proofs left unsubmitted because their matches were proven concurrently
+    pub redundant_proofs: usize,
+    /// The estimated gas that submitting the redundant proofs would have spent
+    pub saved_gas: u64,
+    /// The L1 block after which each withheld proof may be submitted, keyed by proposal index
+    pub submittable_at: HashMap<u64, u64>,
+}
+
+impl Competition {
+    pub fn new(args: CompetitionArgs, validator: Address, cancelled: CancelledProofs) -> Self {
+        info!(
+            "Validator {validator} proving with {:?} strategy.",
+            args.proving_strategy
+        );
+        Self {
+            args,
+            validator,
+            first_seen: Default::default(),
+            rival_proofs: Default::default(),
+            rival_scans: Default::default(),
+            queued: Default::default(),
+            cancelled,
+            redundant_proofs: 0,
+            saved_gas: 0,
+            submittable_at: Default::default(),
+        }
+    }
+
+    /// Reads the `Proven` events emitted by the given tournament since the last scan to learn of
+    /// other validators' proofs
+    pub async fn scan_rivals<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        parent: &Proposal,
+        provider: P,
+    ) -> anyhow::Result<usize> {
+        let from_block = match self.rival_scans.get(&parent.contract) {
+            Some(next_block) => *next_block,
+            // no proofs precede the creation of the tournament
+            None => provider
+                .get_block_by_hash(parent.l1_head, BlockTransactionsKind::Hashes)
+                .await
+                .context("get_block_by_hash")?
+                .context("tournament l1 head not found")?
+                .header()
+                .number(),
+        };
+        let to_block = provider
+            .get_block_number()
+            .await
+            .context("get_block_number")?;
+        let rivals = self.rival_proofs.entry(parent.contract).or_default();
+        if from_block > to_block {
+            return Ok(rivals.values().sum());
+        }
+        let parent_contract = parent.tournament_contract_instance(&provider);
+        let events = parent_contract
+            .Proven_filter()
+            .from_block(from_block)
+            .to_block(to_block)
+            .query()
+            .await
+            .context("Proven_filter")?;
+        let mut found = false;
+        for (_, log) in events {
+            let Some(tx_hash) = log.transaction_hash else {
+                continue;
+            };
+            let Some(txn) = provider
+                .get_transaction_by_hash(tx_hash)
+                .await
+                .context("get_transaction_by_hash")?
+            else {
+                continue;
+            };
+            let prover = txn.from();
+            if prover != self.validator {
+                *rivals.entry(prover).or_insert(0usize) += 1;
+                found = true;
+            }
+        }
+        self.rival_scans.insert(parent.contract, to_block + 1);
+        let rival_count = rivals.values().sum();
+        if found {
+            info!(
+                "Tournament {} has {rival_count} proofs from other validators: {:?}",
+                parent.index,
+                rivals.keys().collect::<Vec<_>>()
+            );
+        }
+        Ok(rival_count)
+    }
+
+    /// Returns whether the configured number of L1 blocks was built since the proof for the
+    /// given proposal was first found ready, giving proofs submitted concurrently by other
+    /// validators the chance to land and be confirmed first.
+    pub async fn is_submittable<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        proposal_index: u64,
+        provider: P,
+    ) -> anyhow::Result<bool> {
+        let confirmations = self.args.proof_submission_confirmations;
+        if confirmations == 0 {
+            return Ok(true);
+        }
+        let latest = provider
+            .get_block_number()
+            .await
+            .context("get_block_number")?;
+        let submittable_at = *self
+            .submittable_at
+            .entry(proposal_index)
+            .or_insert(latest + confirmations);
+        if latest < submittable_at {
+            info!(
+                "Withholding proof for proposal {proposal_index} until L1 block {submittable_at}."
+            );
+            return Ok(false);
+        }
+        self.submittable_at.remove(&proposal_index);
+        Ok(true)
+    }
+
+    /// Returns the latest L1 block with the configured number of confirmations
+    pub async fn confirmed_block<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &self,
+        provider: P,
+    ) -> anyhow::Result<u64> {
+        let latest = provider
+            .get_block_number()
+            .await
+            .context("get_block_number")?;
+        Ok(latest.saturating_sub(self.args.proof_submission_confirmations))
+    }
+
+    /// Accounts for a local proof that was not submitted because its match was proven meanwhile,
+    /// along with the gas its submission was estimated to spend, if known
+    pub fn record_redundant_proof(&mut self, gas_estimate: Option<u64>) {
+        self.redundant_proofs += 1;
+        match gas_estimate {
+            Some(gas_estimate) => self.saved_gas += gas_estimate,
+            None => warn!("Could not estimate the gas saved by skipping a redundant proof."),
+        }
+        info!(
+            "Skipped {} redundant proof submission(s) so far, saving an estimated {} gas.",
+            self.redundant_proofs, self.saved_gas
+        );
+    }
+
+    /// Decides whether to request a proof for the given unproven match
+    pub async fn decide<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        proposal: &Proposal,
+        parent: &Proposal,
+        provider: P,
+    ) -> anyhow::Result<ProvingDecision> {
+        let first_seen = *self
+            .first_seen
+            .entry(proposal.index)
+            .or_insert_with(Instant::now);
+        match self.args.proving_strategy {
+            ProvingStrategy::Race => Ok(ProvingDecision::Prove),
+            ProvingStrategy::Defer => {
+                let deferral = Duration::from_secs(self.args.proving_defer_secs);
+                if first_seen.elapsed() < deferral {
+                    Ok(ProvingDecision::Defer)
+                } else {
+                    Ok(ProvingDecision::Prove)
+                }
+            }
+            ProvingStrategy::Uncontested => {
+                // prove matches the other validators left unproven for too long
+                let deferral = Duration::from_secs(self.args.proving_defer_secs);
+                if first_seen.elapsed() >= deferral {
+                    return Ok(ProvingDecision::Prove);
+                }
+                if self.scan_rivals(parent, provider).await? > 0 {
+                    info!(
+                        "Leaving proposal {} to the other validators of tournament {} for now.",
+                        proposal.index, parent.index
+                    );
+                    Ok(ProvingDecision::Defer)
+                } else {
+                    Ok(ProvingDecision::Prove)
+                }
+            }
+        }
+    }
+
+    /// Records a proof request for the match `(u_index, v_index)` in the parent tournament
+    pub fn mark_queued(
+        &mut self,
+        proposal_index: u64,
+        parent_index: u64,
+        u_index: u64,
+        v_index: u64,
+    ) {
+        self.queued
+            .insert(proposal_index, (parent_index, u_index, v_index));
+    }
+
+    pub fn mark_complete(&mut self, proposal_index: u64) {
+        self.queued.remove(&proposal_index);
+        self.first_seen.remove(&proposal_index);
+        self.submittable_at.remove(&proposal_index);
+    }
+
+    /// Signals the proofs task to drop a queued or in-flight proof that is no longer needed
+    pub fn cancel(&mut self, proposal_index: u64) {
+        info!("Cancelling proof for proposal {proposal_index}.");
+        self.mark_complete(proposal_index);
+        self.cancelled.lock().unwrap().insert(proposal_index);
+    }
+
+    pub fn is_cancelled(cancelled: &CancelledProofs, proposal_index: u64) -> bool {
+        cancelled.lock().unwrap().remove(&proposal_index)
+    }
+}
@@ -13,12 +13,12 @@
 // limitations under the License.
 
 use crate::stall::Stall;
-use crate::{BN254_CONTROL_ID, CONTROL_ROOT, KAILUA_GAME_TYPE, SET_BUILDER_ID};
+use crate::{bn254_control_id, control_root, KAILUA_GAME_TYPE, SET_BUILDER_ID};
 use alloy::primitives::address;
 use alloy::providers::ProviderBuilder;
 use anyhow::Context;
 use kailua_build::KAILUA_FPVM_ID;
-use kailua_common::client::config_hash;
+use kailua_common::config::config_hash;
 use kailua_contracts::SystemConfig;
 use kailua_host::fetch_rollup_config;
 use risc0_zkvm::sha::Digest;
@@ -59,11 +59,11 @@ pub async fn config(args: ConfigArgs) -> anyhow::Result<()> {
     // Report expected Groth16 verifier parameters
     println!(
         "CONTROL_ROOT: 0x{}",
-        hex::encode_upper(CONTROL_ROOT.as_slice()),
+        hex::encode_upper(control_root().as_slice()),
     );
     println!(
         "CONTROL_ID: 0x{}",
-        hex::encode_upper(BN254_CONTROL_ID.as_slice()),
+        hex::encode_upper(bn254_control_id().as_slice()),
     );
     // Report expected Boundless verifier parameters
     println!(
@@ -123,6 +123,8 @@ pub async fn config(args: ConfigArgs) -> anyhow::Result<()> {
     );
     // report game type
     println!("KAILUA_GAME_TYPE: {}", KAILUA_GAME_TYPE);
+    // report l2 chain id
+    println!("L2_CHAIN_ID: {}", config.l2_chain_id);
 
     Ok(())
 }
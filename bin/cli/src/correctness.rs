@@ -0,0 +1,348 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::proposal::Proposal;
+use crate::providers::metered::{MeteredProvider, RpcMeter};
+use crate::providers::optimism::OpNodeProvider;
+use alloy::eips::BlockNumberOrTag;
+use alloy::network::primitives::BlockTransactionsKind;
+use alloy::primitives::{address, keccak256, Address, B256};
+use alloy::providers::Provider;
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use futures::future::{join_all, select_ok};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+/// The L2ToL1MessagePasser predeploy whose storage root is committed to in output roots
+pub const MESSAGE_PASSER_ADDRESS: Address = address!("4200000000000000000000000000000000000016");
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CorrectnessMode {
+    /// Trust the output roots reported by the op-node
+    #[default]
+    OpNode,
+    /// Require a quorum of op-nodes to agree on each output root
+    Quorum,
+    /// Recompute output roots from the blocks executed by op-geth
+    Rederive,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuorumPolicy {
+    /// Accept the output root reported by the first op-node that answers
+    FirstSuccess,
+    /// Require the threshold of op-nodes (a majority by default) to agree on each output root
+    #[default]
+    Majority,
+    /// Require all op-nodes to agree on each output root
+    AllMatch,
+}
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct CorrectnessArgs {
+    /// How to decide which output roots are correct
+    #[clap(long, env, value_enum, default_value_t = CorrectnessMode::OpNode)]
+    pub correctness_oracle: CorrectnessMode,
+    /// Addresses of additional OP-NODE endpoints to poll in quorum mode
+    #[clap(long, env, value_delimiter = ',')]
+    pub quorum_op_node_urls: Vec<String>,
+    /// How to reconcile the output roots reported by the op-nodes in quorum mode
+    #[clap(long, env, value_enum, default_value_t = QuorumPolicy::Majority)]
+    pub quorum_policy: QuorumPolicy,
+    /// Number of op-nodes that must agree on an output root under the majority policy (defaults
+    /// to a majority)
+    #[clap(long, env)]
+    pub quorum_threshold: Option<usize>,
+    /// Path to a JSON file listing game contracts to always accept or reject
+    #[clap(long, env)]
+    pub correctness_overrides: Option<PathBuf>,
+}
+
+/// Game contracts whose correctness is decided by the operator instead of the oracle
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CorrectnessOverrides {
+    #[serde(default)]
+    pub accept: HashSet<Address>,
+    #[serde(default)]
+    pub reject: HashSet<Address>,
+}
+
+/// Decides what the correct outputs of the rollup are
+#[async_trait]
+pub trait CorrectnessOracle: Send + Sync {
+    /// Returns the correct output root of the given L2 block, or None if it can not be decided
+    async fn output_at_block(&self, block_number: u64) -> anyhow::Result<Option<B256>>;
+
+    /// Returns a verdict on the outputs of the proposal that supersedes the oracle, if any
+    fn verdict_override(&self, _proposal: &Proposal) -> Option<bool> {
+        None
+    }
+
+    /// Returns the sync status of the op-node backing the oracle, if any
+    async fn sync_status(&self) -> anyhow::Result<Option<Value>> {
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl<O: CorrectnessOracle + ?Sized> CorrectnessOracle for &O {
+    async fn output_at_block(&self, block_number: u64) -> anyhow::Result<Option<B256>> {
+        (**self).output_at_block(block_number).await
+    }
+
+    fn verdict_override(&self, proposal: &Proposal) -> Option<bool> {
+        (**self).verdict_override(proposal)
+    }
+
+    async fn sync_status(&self) -> anyhow::Result<Option<Value>> {
+        (**self).sync_status().await
+    }
+}
+
+#[async_trait]
+impl CorrectnessOracle for OpNodeProvider {
+    async fn output_at_block(&self, block_number: u64) -> anyhow::Result<Option<B256>> {
+        Ok(Some(
+            OpNodeProvider::output_at_block(self, block_number).await?,
+        ))
+    }
+
+    async fn sync_status(&self) -> anyhow::Result<Option<Value>> {
+        Ok(Some(OpNodeProvider::sync_status(self).await?))
+    }
+}
+
+/// Accepts an output root only once enough op-nodes report it
+pub struct QuorumOracle<'a> {
+    pub primary: &'a OpNodeProvider,
+    pub others: Vec<OpNodeProvider>,
+    pub policy: QuorumPolicy,
+    pub threshold: usize,
+}
+
+#[async_trait]
+impl CorrectnessOracle for QuorumOracle<'_> {
+    async fn output_at_block(&self, block_number: u64) -> anyhow::Result<Option<B256>> {
+        let members = std::iter::once(self.primary).chain(self.others.iter());
+        if self.policy == QuorumPolicy::FirstSuccess {
+            // race all members and accept whichever answers first
+            let queries = members.map(|node| Box::pin(node.output_at_block(block_number)));
+            return match select_ok(queries).await {
+                Ok((output_root, _)) => Ok(Some(output_root)),
+                Err(err) => {
+                    warn!("No quorum member reported output {block_number}: {err:?}");
+                    Ok(None)
+                }
+            };
+        }
+        let mut votes = BTreeMap::<B256, usize>::new();
+        let reports = join_all(members.map(|node| node.output_at_block(block_number))).await;
+        for report in reports {
+            match report {
+                Ok(output_root) => *votes.entry(output_root).or_default() += 1,
+                Err(err) => warn!("Quorum member failed to report output {block_number}: {err:?}"),
+            }
+        }
+        if votes.len() > 1 {
+            warn!("op-nodes disagree on output {block_number}: {votes:?}");
+        }
+        // thresholds above half of the members admit at most one output root
+        Ok(votes
+            .into_iter()
+            .find_map(|(output_root, count)| (count >= self.threshold).then_some(output_root)))
+    }
+
+    async fn sync_status(&self) -> anyhow::Result<Option<Value>> {
+        Ok(Some(self.primary.sync_status().await?))
+    }
+}
+
+/// Returns the number of op-nodes that must agree on an output root under the given policy
+pub fn quorum_threshold(
+    policy: QuorumPolicy,
+    threshold: Option<usize>,
+    members: usize,
+) -> anyhow::Result<usize> {
+    let threshold = match (policy, threshold) {
+        (QuorumPolicy::Majority, threshold) => threshold.unwrap_or(members / 2 + 1),
+        (policy, Some(_)) => {
+            bail!(
+                "Quorum threshold is only configurable under the majority policy, not {policy:?}."
+            )
+        }
+        (QuorumPolicy::AllMatch, None) => members,
+        (QuorumPolicy::FirstSuccess, None) => return Ok(1),
+    };
+    // smaller thresholds could be met by two conflicting output roots at once
+    if threshold <= members / 2 || threshold > members {
+        bail!(
+            "Quorum threshold {threshold} is not between {} and {members}.",
+            members / 2 + 1
+        );
+    }
+    Ok(threshold)
+}
+
+/// Recomputes output roots from the state of the blocks executed by op-geth, without relying on
+/// the op-node's output api.
+pub struct RederivationOracle {
+    pub op_geth_provider: MeteredProvider,
+}
+
+#[async_trait]
+impl CorrectnessOracle for RederivationOracle {
+    async fn output_at_block(&self, block_number: u64) -> anyhow::Result<Option<B256>> {
+        let Some(block) = self
+            .op_geth_provider
+            .get_block_by_number(
+                BlockNumberOrTag::Number(block_number),
+                BlockTransactionsKind::Hashes,
+            )
+            .await
+            .context("get_block_by_number")?
+        else {
+            return Ok(None);
+        };
+        let message_passer = self
+            .op_geth_provider
+            .get_proof(MESSAGE_PASSER_ADDRESS, vec![])
+            .block_id(block_number.into())
+            .await
+            .context("get_proof")?;
+        // Output root v0: keccak(version ‖ state root ‖ message passer storage root ‖ block hash)
+        let mut preimage = [0u8; 128];
+        preimage[32..64].copy_from_slice(block.header.state_root.as_slice());
+        preimage[64..96].copy_from_slice(message_passer.storage_hash.as_slice());
+        preimage[96..128].copy_from_slice(block.header.hash.as_slice());
+        Ok(Some(keccak256(preimage)))
+    }
+}
+
+/// Applies the operator's overrides on top of another oracle
+pub struct OverrideOracle<'a> {
+    pub inner: Box<dyn CorrectnessOracle + 'a>,
+    pub overrides: CorrectnessOverrides,
+}
+
+#[async_trait]
+impl CorrectnessOracle for OverrideOracle<'_> {
+    async fn output_at_block(&self, block_number: u64) -> anyhow::Result<Option<B256>> {
+        self.inner.output_at_block(block_number).await
+    }
+
+    fn verdict_override(&self, proposal: &Proposal) -> Option<bool> {
+        if self.overrides.reject.contains(&proposal.contract) {
+            Some(false)
+        } else if self.overrides.accept.contains(&proposal.contract) {
+            Some(true)
+        } else {
+            self.inner.verdict_override(proposal)
+        }
+    }
+
+    async fn sync_status(&self) -> anyhow::Result<Option<Value>> {
+        self.inner.sync_status().await
+    }
+}
+
+impl CorrectnessArgs {
+    /// Instantiates the configured oracle on top of the primary op-node and op-geth connections
+    pub fn oracle<'a>(
+        &self,
+        op_node_provider: &'a OpNodeProvider,
+        op_geth_provider: MeteredProvider,
+        rpc_meter: &RpcMeter,
+    ) -> anyhow::Result<Box<dyn CorrectnessOracle + 'a>> {
+        let oracle: Box<dyn CorrectnessOracle + 'a> = match self.correctness_oracle {
+            CorrectnessMode::OpNode => Box::new(op_node_provider),
+            CorrectnessMode::Quorum => {
+                if self.quorum_op_node_urls.is_empty() {
+                    bail!("Quorum mode requires at least one additional op-node url.");
+                }
+                let others = self
+                    .quorum_op_node_urls
+                    .iter()
+                    .map(|url| {
+                        Ok(OpNodeProvider::new(
+                            rpc_meter.provider("op-node-quorum", url)?,
+                        ))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let members = others.len() + 1;
+                let threshold =
+                    quorum_threshold(self.quorum_policy, self.quorum_threshold, members)?;
+                match self.quorum_policy {
+                    QuorumPolicy::FirstSuccess => {
+                        info!("Using outputs of the first of {members} op-nodes to respond.")
+                    }
+                    _ => info!("Requiring {threshold} of {members} op-nodes to agree on outputs."),
+                }
+                Box::new(QuorumOracle {
+                    primary: op_node_provider,
+                    others,
+                    policy: self.quorum_policy,
+                    threshold,
+                })
+            }
+            CorrectnessMode::Rederive => {
+                info!("Recomputing output roots from op-geth state.");
+                Box::new(RederivationOracle { op_geth_provider })
+            }
+        };
+        let Some(overrides_path) = &self.correctness_overrides else {
+            return Ok(oracle);
+        };
+        let overrides: CorrectnessOverrides = serde_json::from_slice(
+            &std::fs::read(overrides_path).context(format!("Failed to read {overrides_path:?}"))?,
+        )?;
+        info!(
+            "Overriding correctness of {} accepted and {} rejected games.",
+            overrides.accept.len(),
+            overrides.reject.len()
+        );
+        Ok(Box::new(OverrideOracle {
+            inner: oracle,
+            overrides,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quorum_thresholds_require_a_majority() {
+        let majority = QuorumPolicy::Majority;
+        assert_eq!(quorum_threshold(majority, None, 3).unwrap(), 2);
+        assert_eq!(quorum_threshold(majority, None, 4).unwrap(), 3);
+        assert_eq!(quorum_threshold(majority, Some(3), 3).unwrap(), 3);
+        assert!(quorum_threshold(majority, Some(1), 3).is_err());
+        assert!(quorum_threshold(majority, Some(2), 4).is_err());
+        assert!(quorum_threshold(majority, Some(5), 4).is_err());
+        assert_eq!(
+            quorum_threshold(QuorumPolicy::AllMatch, None, 3).unwrap(),
+            3
+        );
+        assert_eq!(
+            quorum_threshold(QuorumPolicy::FirstSuccess, None, 3).unwrap(),
+            1
+        );
+        assert!(quorum_threshold(QuorumPolicy::AllMatch, Some(2), 3).is_err());
+    }
+}
@@ -17,6 +17,7 @@ use alloy::network::Network;
 use alloy::primitives::{Address, B256};
 use alloy::providers::Provider;
 use alloy::transports::Transport;
+use kailua_common::encoding::FieldEncoding;
 use kailua_contracts::KailuaGame::KailuaGameInstance;
 
 #[derive(Clone, Debug, Default)]
@@ -27,6 +28,7 @@ pub struct Config {
     pub image_id: B256,
     pub cfg_hash: B256,
     pub proposal_block_count: u64,
+    pub output_block_span: u64,
     pub proposal_blobs: u64,
     pub game_type: u8,
     pub factory: Address,
@@ -34,6 +36,9 @@ pub struct Config {
     pub genesis_time: u64,
     pub block_time: u64,
     pub proposal_gap: u64,
+    pub l2_chain_id: u64,
+    pub journal_version: u8,
+    pub field_encoding: FieldEncoding,
 }
 
 impl Config {
@@ -102,6 +107,34 @@ impl Config {
             .await
             .proposalTimeGap_
             .to();
+        // Implementations predating journal versioning do not expose these
+        let l2_chain_id = kailua_game_implementation
+            .l2ChainId()
+            .stall_optional()
+            .await
+            .map(|res| res.l2ChainId_)
+            .unwrap_or_default();
+        let journal_version = kailua_game_implementation
+            .JOURNAL_VERSION()
+            .stall_optional()
+            .await
+            .map(|res| res._0)
+            .unwrap_or_default();
+        // Implementations predating configurable output density commit to every block
+        let output_block_span = kailua_game_implementation
+            .outputBlockSpan()
+            .stall_optional()
+            .await
+            .map(|res| res.outputBlockSpan_.to())
+            .unwrap_or(1);
+        let field_encoding = FieldEncoding::from_version(
+            kailua_game_implementation
+                .FIELD_ENCODING_VERSION()
+                .stall_optional()
+                .await
+                .map(|res| res._0)
+                .unwrap_or_default(),
+        )?;
         Ok(Self {
             treasury,
             game,
@@ -109,6 +142,7 @@ impl Config {
             image_id,
             cfg_hash,
             proposal_block_count,
+            output_block_span,
             proposal_blobs,
             game_type,
             factory,
@@ -116,9 +150,37 @@ impl Config {
             genesis_time,
             block_time,
             proposal_gap,
+            l2_chain_id,
+            journal_version,
+            field_encoding,
         })
     }
 
+    /// Returns the number of outputs committed to by a proposal, including its root claim
+    pub fn proposal_output_count(&self) -> u64 {
+        self.proposal_block_count / self.output_block_span
+    }
+
+    /// Returns the block number of the output at the given position in a proposal
+    pub fn output_block_number(&self, parent_block_number: u64, position: u64) -> u64 {
+        parent_block_number + (position + 1) * self.output_block_span
+    }
+
+    /// Returns the position of the intermediate output committed to for the given block by a
+    /// proposal extending the given parent block, if any
+    pub fn intermediate_output_position(
+        &self,
+        parent_block_number: u64,
+        block_number: u64,
+    ) -> Option<u64> {
+        let offset = block_number.checked_sub(parent_block_number)?;
+        if offset == 0 || offset % self.output_block_span != 0 {
+            return None;
+        }
+        let position = offset / self.output_block_span - 1;
+        (position + 1 < self.proposal_output_count()).then_some(position)
+    }
+
     pub fn allows_proposal(&self, proposal_block_number: u64, proposal_time: u64) -> bool {
         proposal_time >= self.min_proposal_time(proposal_block_number)
     }
@@ -127,3 +189,31 @@ impl Config {
         self.genesis_time + proposal_block_number * self.block_time + self.proposal_gap + 1
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intermediate_positions_follow_output_span() {
+        let config = Config {
+            proposal_block_count: 12,
+            output_block_span: 3,
+            ..Default::default()
+        };
+        assert_eq!(config.intermediate_output_position(100, 103), Some(0));
+        assert_eq!(config.intermediate_output_position(100, 109), Some(2));
+        for block in [99, 100, 101, 104, 111] {
+            assert_eq!(config.intermediate_output_position(100, block), None);
+        }
+        // the last output is the root claim rather than an intermediate commitment
+        assert_eq!(config.intermediate_output_position(100, 112), None);
+        for position in 0..3 {
+            let block = config.output_block_number(100, position);
+            assert_eq!(
+                config.intermediate_output_position(100, block),
+                Some(position)
+            );
+        }
+    }
+}
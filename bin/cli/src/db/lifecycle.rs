@@ -0,0 +1,220 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+/// The on-chain dispute lifecycle of a proposal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProposalStatus {
+    /// No later sibling has contested this proposal yet
+    #[default]
+    Unchallenged,
+    /// The proposal is the standing survivor against the sibling at index `by`
+    Challenged { by: u64 },
+    /// A fault proof settled the proposal's latest match
+    Proven { valid: bool },
+    /// The proposal's game has been resolved
+    Resolved { defender_wins: bool },
+}
+
+impl ProposalStatus {
+    /// Returns whether moving from this status to `next` is a legal transition
+    pub fn allows(&self, next: &ProposalStatus) -> bool {
+        use ProposalStatus::*;
+        match (self, next) {
+            // resolution is final
+            (Resolved { .. }, _) => false,
+            // games can not revert to being unchallenged
+            (_, Unchallenged) => false,
+            // eliminated proposals can only be resolved
+            (Proven { valid: false }, Challenged { .. } | Proven { .. }) => false,
+            // a proposal proven valid can not be proven invalid by a later match
+            (Proven { valid: true }, Proven { valid }) => *valid,
+            // only later siblings can take over the challenge of a survivor
+            (Challenged { by }, Challenged { by: later }) => by < later,
+            (Unchallenged | Proven { valid: true }, Challenged { .. }) => true,
+            // a proof settles the match of both the survivor and its challenger
+            (Unchallenged | Challenged { .. }, Proven { .. }) => true,
+            (Unchallenged | Challenged { .. } | Proven { .. }, Resolved { .. }) => true,
+        }
+    }
+
+    /// Returns the next status if the transition is legal
+    pub fn transition(self, next: ProposalStatus) -> anyhow::Result<ProposalStatus> {
+        if !self.allows(&next) {
+            bail!("Illegal proposal status transition from {self:?} to {next:?}");
+        }
+        Ok(next)
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        matches!(self, ProposalStatus::Resolved { .. })
+    }
+
+    /// Whether a fault proof eliminated the proposal
+    pub fn is_eliminated(&self) -> bool {
+        matches!(self, ProposalStatus::Proven { valid: false })
+    }
+}
+
+/// A point at which a proposal disagrees with the outputs derived locally
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Fault {
+    /// The parent proposal is incorrect
+    Parent,
+    /// The intermediate output at the given position
+    Output(usize),
+    /// The output root claimed by the proposal
+    Claim,
+}
+
+/// The assessment of a proposal against the outputs derived locally
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Assessment {
+    /// Some outputs could not be compared yet, and none of the others disagree
+    #[default]
+    Pending,
+    /// The proposal and its parent agree with every output derived locally
+    Correct {
+        /// Whether the proposal extends the canonical chain
+        canonical: bool,
+    },
+    /// The proposal disagrees with the outputs derived locally at the given points
+    Incorrect { faults: Vec<Fault> },
+}
+
+impl Assessment {
+    /// Assesses a proposal from the comparison of its parent, claim and intermediate outputs
+    /// against the outputs derived locally, where `None` marks those that could not be compared.
+    pub fn from_checks(parent: Option<bool>, claim: Option<bool>, io: &[Option<bool>]) -> Self {
+        let checks = [(Fault::Parent, parent)]
+            .into_iter()
+            .chain(io.iter().enumerate().map(|(i, c)| (Fault::Output(i), *c)))
+            .chain([(Fault::Claim, claim)]);
+        let mut faults = vec![];
+        let mut pending = false;
+        for (fault, check) in checks {
+            match check {
+                Some(true) => {}
+                Some(false) => faults.push(fault),
+                None => pending = true,
+            }
+        }
+        if !faults.is_empty() {
+            Assessment::Incorrect { faults }
+        } else if pending {
+            Assessment::Pending
+        } else {
+            Assessment::Correct { canonical: false }
+        }
+    }
+
+    pub fn is_correct(&self) -> Option<bool> {
+        match self {
+            Assessment::Pending => None,
+            Assessment::Correct { .. } => Some(true),
+            Assessment::Incorrect { .. } => Some(false),
+        }
+    }
+
+    /// Returns whether the given point agrees with the outputs derived locally, if known.
+    ///
+    /// Points of incorrect proposals are only reported as faults if they were compared, so all
+    /// other points are assumed to agree.
+    pub fn agrees_at(&self, point: Fault) -> Option<bool> {
+        match self {
+            Assessment::Pending => None,
+            Assessment::Correct { .. } => Some(true),
+            Assessment::Incorrect { faults } => Some(!faults.contains(&point)),
+        }
+    }
+
+    /// Returns the positions of the intermediate outputs found to be incorrect
+    pub fn incorrect_io(&self) -> Vec<usize> {
+        match self {
+            Assessment::Incorrect { faults } => faults
+                .iter()
+                .filter_map(|fault| match fault {
+                    Fault::Output(i) => Some(*i),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUSES: [ProposalStatus; 7] = [
+        ProposalStatus::Unchallenged,
+        ProposalStatus::Challenged { by: 1 },
+        ProposalStatus::Challenged { by: 2 },
+        ProposalStatus::Proven { valid: true },
+        ProposalStatus::Proven { valid: false },
+        ProposalStatus::Resolved {
+            defender_wins: true,
+        },
+        ProposalStatus::Resolved {
+            defender_wins: false,
+        },
+    ];
+
+    #[test]
+    fn transition_table() {
+        use ProposalStatus::*;
+        let legal = |from: &ProposalStatus, to: &ProposalStatus| match (from, to) {
+            (Unchallenged, Challenged { .. } | Proven { .. } | Resolved { .. }) => true,
+            (Challenged { by: 1 }, Challenged { by: 2 }) => true,
+            (Challenged { .. }, Proven { .. } | Resolved { .. }) => true,
+            (Proven { valid: true }, Challenged { .. } | Resolved { .. }) => true,
+            (Proven { valid: true }, Proven { valid: true }) => true,
+            (Proven { valid: false }, Resolved { .. }) => true,
+            _ => false,
+        };
+        for from in &STATUSES {
+            for to in &STATUSES {
+                assert_eq!(from.allows(to), legal(from, to), "{from:?} -> {to:?}");
+                assert_eq!(from.transition(*to).is_ok(), legal(from, to));
+            }
+        }
+    }
+
+    #[test]
+    fn assessment_from_checks() {
+        assert_eq!(
+            Assessment::from_checks(Some(true), Some(true), &[Some(true), Some(true)]),
+            Assessment::Correct { canonical: false }
+        );
+        assert_eq!(
+            Assessment::from_checks(Some(true), None, &[Some(true)]),
+            Assessment::Pending
+        );
+        // known faults outweigh outputs that could not be compared
+        let assessment = Assessment::from_checks(Some(false), None, &[None, Some(false)]);
+        assert_eq!(
+            assessment,
+            Assessment::Incorrect {
+                faults: vec![Fault::Parent, Fault::Output(1)]
+            }
+        );
+        assert_eq!(assessment.is_correct(), Some(false));
+        assert_eq!(assessment.agrees_at(Fault::Output(1)), Some(false));
+        assert_eq!(assessment.agrees_at(Fault::Claim), Some(true));
+        assert_eq!(assessment.incorrect_io(), vec![1]);
+    }
+}
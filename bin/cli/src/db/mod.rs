@@ -13,12 +13,16 @@
 // limitations under the License.
 
 pub mod config;
+pub mod lifecycle;
 pub mod proposal;
+pub mod retention;
 pub mod state;
 pub mod treasury;
 
+use crate::correctness::CorrectnessOracle;
+use crate::decision::{CorrectnessDecision, RecordingOracle, DECISIONS_FILE};
+use crate::equivocation::Equivocation;
 use crate::providers::beacon::BlobProvider;
-use crate::providers::optimism::OpNodeProvider;
 use crate::stall::Stall;
 use crate::KAILUA_GAME_TYPE;
 use alloy::network::Network;
@@ -31,14 +35,16 @@ use kailua_contracts::{
     IDisputeGameFactory::{gameAtIndexReturn, IDisputeGameFactoryInstance},
     *,
 };
+use lifecycle::{Assessment, ProposalStatus};
 use proposal::Proposal;
+use retention::RetentionArgs;
 use state::State;
 use std::collections::hash_map::Entry;
 use std::path::PathBuf;
 use tracing::{error, info, warn};
 use treasury::Treasury;
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum ProofStatus {
     #[default]
     NONE,
@@ -47,12 +53,40 @@ pub enum ProofStatus {
     UWinVLose,
 }
 
+impl ProofStatus {
+    pub fn parse(proof_status: u8) -> anyhow::Result<Self> {
+        match proof_status {
+            0u8 => Ok(ProofStatus::NONE),
+            1u8 => Ok(ProofStatus::ULoseVLose),
+            2u8 => Ok(ProofStatus::ULoseVWin),
+            3u8 => Ok(ProofStatus::UWinVLose),
+            _ => bail!("Invalid proof status {proof_status}"),
+        }
+    }
+
+    /// Returns the validity of the contender (u) and the proposal (v) if proven
+    pub fn outcomes(&self) -> Option<(bool, bool)> {
+        match self {
+            ProofStatus::NONE => None,
+            ProofStatus::ULoseVLose => Some((false, false)),
+            ProofStatus::ULoseVWin => Some((false, true)),
+            ProofStatus::UWinVLose => Some((true, false)),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct KailuaDB {
     pub config: Config,
     pub treasury: Treasury,
     pub db: rocksdb::DB,
     pub state: State,
+    /// Where evidence of proposer equivocations is written to
+    pub equivocations_path: PathBuf,
+    /// Where pruned proposals are archived to by default
+    pub archive_dir: PathBuf,
+    /// Where the inputs and outcomes of correctness decisions are logged to
+    pub decisions_path: PathBuf,
 }
 
 impl Drop for KailuaDB {
@@ -85,6 +119,9 @@ impl KailuaDB {
             KailuaTreasury::new(config.treasury, dispute_game_factory.provider());
         let treasury = Treasury::init(&treasury_implementation).await?;
 
+        let equivocations_path = data_dir.join("equivocations.json");
+        let archive_dir = data_dir.join("archive");
+        let decisions_path = data_dir.join(DECISIONS_FILE);
         data_dir.push(config.cfg_hash.to_string());
         let db = rocksdb::DB::open(&Self::options(), &data_dir)?;
         Ok(Self {
@@ -92,16 +129,20 @@ impl KailuaDB {
             treasury,
             db,
             state: Default::default(),
+            equivocations_path,
+            archive_dir,
+            decisions_path,
         })
     }
 
     pub async fn load_proposals<T: Transport + Clone, P: Provider<T, N>, N: Network>(
         &mut self,
         dispute_game_factory: &IDisputeGameFactoryInstance<T, P, N>,
-        op_node_provider: &OpNodeProvider,
+        correctness_oracle: &dyn CorrectnessOracle,
         blob_provider: &BlobProvider,
     ) -> anyhow::Result<Vec<u64>> {
         let canonical_start = self.state.canonical_tip_index;
+        let equivocations_start = self.state.equivocations.len();
         let game_count: u64 = dispute_game_factory
             .gameCount()
             .stall()
@@ -109,15 +150,34 @@ impl KailuaDB {
             .gameCount_
             .to();
         let mut proposals =
-            Vec::with_capacity((game_count - self.state.next_factory_index) as usize);
+            Vec::with_capacity(game_count.saturating_sub(self.state.next_factory_index) as usize);
         while self.state.next_factory_index < game_count {
             let proposal = match self.get_local_proposal(&self.state.next_factory_index) {
                 Some(proposal) => Some(proposal),
                 None => {
+                    // Insert any ancestors missing from the database first
+                    match self
+                        .backfill_ancestors(
+                            dispute_game_factory,
+                            correctness_oracle,
+                            blob_provider,
+                            self.state.next_factory_index,
+                        )
+                        .await
+                    {
+                        Ok(backfilled) => proposals.extend(backfilled),
+                        Err(err) => {
+                            error!(
+                                "Error backfilling ancestors of game at index {}: {err:?}",
+                                self.state.next_factory_index
+                            );
+                            break;
+                        }
+                    }
                     match self
                         .load_game_at_index(
                             dispute_game_factory,
-                            op_node_provider,
+                            correctness_oracle,
                             blob_provider,
                             self.state.next_factory_index,
                         )
@@ -131,6 +191,9 @@ impl KailuaDB {
                                         .expect("Failed to load immediately processed proposal"),
                                 )
                             } else {
+                                self.state
+                                    .skipped_proposals
+                                    .insert(self.state.next_factory_index);
                                 None
                             }
                         }
@@ -147,21 +210,19 @@ impl KailuaDB {
 
             // Update state according to proposal
             if let Some(proposal) = proposal {
-                if let Some(true) = proposal.canonical {
-                    // Update canonical chain tip
-                    self.state.canonical_tip_index = Some(proposal.index);
-                } else if let Some(false) = proposal.is_correct() {
-                    // Update player eliminations
-                    if let Entry::Vacant(entry) = self.state.eliminations.entry(proposal.proposer) {
-                        entry.insert(proposal.index);
-                    }
-                }
+                self.track_proposal(&proposal);
             }
 
             // Process next game index
             self.state.next_factory_index += 1;
         }
 
+        if equivocations_start != self.state.equivocations.len() {
+            if let Err(err) = self.save_equivocations() {
+                error!("Failed to save equivocation evidence: {err:?}");
+            }
+        }
+
         if canonical_start != self.state.canonical_tip_index {
             info!(
                 "Updating canonical proposal chain tip to {:?}.",
@@ -172,10 +233,138 @@ impl KailuaDB {
         Ok(proposals)
     }
 
+    /// Updates the canonical chain tip, eliminations and equivocations according to the proposal
+    pub fn track_proposal(&mut self, proposal: &Proposal) {
+        if let Some(true) = proposal.is_canonical() {
+            // Update canonical chain tip
+            if self
+                .state
+                .canonical_tip_index
+                .map_or(true, |tip| tip < proposal.index)
+            {
+                self.state.canonical_tip_index = Some(proposal.index);
+            }
+        } else if let Some(false) = proposal.is_correct() {
+            // Update player eliminations
+            match self.state.eliminations.entry(proposal.proposer) {
+                Entry::Vacant(entry) => {
+                    entry.insert(proposal.index);
+                }
+                // backfilled proposals may precede the known elimination
+                Entry::Occupied(mut entry) => {
+                    if proposal.index < *entry.get() {
+                        entry.insert(proposal.index);
+                    }
+                }
+            }
+        }
+        // Report conflicting proposals by the same proposer
+        if let Some(equivocation) = self.detect_equivocation(proposal) {
+            error!("{equivocation}");
+        }
+    }
+
+    /// Walks up the parent pointers of the game at the given index and inserts the ancestors
+    /// missing from the database, oldest first, returning the indices of those inserted.
+    pub async fn backfill_ancestors<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        dispute_game_factory: &IDisputeGameFactoryInstance<T, P, N>,
+        correctness_oracle: &dyn CorrectnessOracle,
+        blob_provider: &BlobProvider,
+        index: u64,
+    ) -> anyhow::Result<Vec<u64>> {
+        let mut missing = Vec::new();
+        let mut game_index = index;
+        loop {
+            let gameAtIndexReturn {
+                gameType_: game_type,
+                proxy_: game_address,
+                ..
+            } = dispute_game_factory
+                .gameAtIndex(U256::from(game_index))
+                .stall()
+                .await;
+            if game_type != KAILUA_GAME_TYPE {
+                break;
+            }
+            // treasury instances have no parent
+            let tournament_instance =
+                KailuaTournament::new(game_address, dispute_game_factory.provider());
+            if tournament_instance.parentGame().stall().await.parentGame_ == game_address {
+                break;
+            }
+            let parent_index: u64 = KailuaGame::new(game_address, dispute_game_factory.provider())
+                .parentGameIndex()
+                .stall()
+                .await
+                .parentGameIndex_;
+            // stop at known, pruned or previously skipped proposals
+            if parent_index < self.state.pruned_below
+                || self.state.skipped_proposals.contains(&parent_index)
+                || self.get_local_proposal(&parent_index).is_some()
+            {
+                break;
+            }
+            missing.push(parent_index);
+            game_index = parent_index;
+        }
+
+        let mut backfilled = Vec::new();
+        while let Some(ancestor_index) = missing.pop() {
+            warn!("Backfilling ancestor {ancestor_index} of proposal {index}.");
+            if !self
+                .load_game_at_index(
+                    dispute_game_factory,
+                    correctness_oracle,
+                    blob_provider,
+                    ancestor_index,
+                )
+                .await?
+            {
+                self.state.skipped_proposals.insert(ancestor_index);
+                break;
+            }
+            let ancestor = self
+                .get_local_proposal(&ancestor_index)
+                .expect("Failed to load immediately processed ancestor");
+            self.track_proposal(&ancestor);
+            backfilled.push(ancestor_index);
+        }
+        Ok(backfilled)
+    }
+
+    /// Records the proposal as evidence of equivocation if it conflicts with an earlier proposal
+    /// by the same proposer for the same L2 block.
+    pub fn detect_equivocation(&mut self, proposal: &Proposal) -> Option<Equivocation> {
+        if !proposal.has_parent() {
+            return None;
+        }
+        let first_index = *self
+            .state
+            .first_proposals
+            .entry((proposal.proposer, proposal.output_block_number))
+            .or_insert(proposal.index);
+        if first_index == proposal.index {
+            return None;
+        }
+        let first = self.get_local_proposal(&first_index)?;
+        let equivocation = Equivocation::between(&first, proposal)?;
+        self.state.equivocations.push(equivocation.clone());
+        Some(equivocation)
+    }
+
+    pub fn save_equivocations(&self) -> anyhow::Result<()> {
+        std::fs::write(
+            &self.equivocations_path,
+            serde_json::to_vec_pretty(&self.state.equivocations)?,
+        )
+        .context(format!("Failed to write {:?}", self.equivocations_path))
+    }
+
     pub async fn load_game_at_index<T: Transport + Clone, P: Provider<T, N>, N: Network>(
         &mut self,
         dispute_game_factory: &IDisputeGameFactoryInstance<T, P, N>,
-        op_node_provider: &OpNodeProvider,
+        correctness_oracle: &dyn CorrectnessOracle,
         blob_provider: &BlobProvider,
         index: u64,
     ) -> anyhow::Result<bool> {
@@ -199,8 +388,25 @@ impl KailuaDB {
         let mut proposal =
             Proposal::load(&self.config, blob_provider, &tournament_instance).await?;
 
+        // Ignore proposals extending pruned history, which the retained resolved chain supersedes
+        if proposal.has_parent() && proposal.parent < self.state.pruned_below {
+            warn!(
+                "Ignoring proposal {} (extends pruned proposal {})",
+                proposal.index, proposal.parent
+            );
+            return Ok(false);
+        }
+        // Ignore proposals whose parent could not be inserted
+        if proposal.has_parent() && self.get_local_proposal(&proposal.parent).is_none() {
+            warn!(
+                "Ignoring proposal {} (parent {} unavailable)",
+                proposal.index, proposal.parent
+            );
+            return Ok(false);
+        }
+
         // Determine inherited correctness
-        self.determine_correctness(&mut proposal, op_node_provider)
+        self.determine_correctness(&mut proposal, correctness_oracle)
             .await
             .context("Failed to determine proposal correctness")?;
 
@@ -233,7 +439,7 @@ impl KailuaDB {
     pub async fn determine_correctness(
         &mut self,
         proposal: &mut Proposal,
-        op_node_provider: &OpNodeProvider,
+        correctness_oracle: &dyn CorrectnessOracle,
     ) -> anyhow::Result<bool> {
         // Accept correctness of treasury instance data
         if !proposal.has_parent() {
@@ -248,12 +454,22 @@ impl KailuaDB {
             .expect("Attempted to process child before registering parent.")
             .is_correct()
             .expect("Attempted to process child before deciding parent correctness");
-        let is_correct_proposal = match proposal
-            .assess_correctness(&self.config, op_node_provider, is_parent_correct)
-            .await?
-        {
+        // Record what the oracle reported for later inspection
+        let recording_oracle = RecordingOracle::new(correctness_oracle);
+        let sync_status = recording_oracle.sync_status().await;
+        let assessment = proposal
+            .assess_correctness(&self.config, &recording_oracle, is_parent_correct)
+            .await;
+        let decision =
+            CorrectnessDecision::new(&self.config, proposal, &recording_oracle, sync_status);
+        if let Err(err) = decision.save(&self.decisions_path) {
+            warn!("Failed to record correctness decision: {err:?}");
+        }
+        let is_correct_proposal = match assessment? {
             None => {
-                bail!("Failed to assess correctness. Is op-node synced far enough?");
+                bail!(
+                    "Failed to assess correctness. Is op-node synced far enough and in agreement?"
+                );
             }
             Some(correct) => {
                 if correct {
@@ -268,21 +484,16 @@ impl KailuaDB {
     }
 
     pub fn determine_if_canonical(&mut self, proposal: &mut Proposal) -> Option<bool> {
-        if proposal.is_correct()? && !self.was_proposer_eliminated_before(proposal) {
+        // Incorrect proposals are never canonical
+        if proposal.is_correct()? {
             // Consider updating canonical chain tip
-            if self
-                .canonical_tip_height()
-                .map_or(true, |h| h < proposal.output_block_number)
-            {
-                proposal.canonical = Some(true);
-            } else {
-                proposal.canonical = Some(false);
-            }
-        } else {
-            // Set as non-canonical
-            proposal.canonical = Some(false);
+            let canonical = !self.was_proposer_eliminated_before(proposal)
+                && self
+                    .canonical_tip_height()
+                    .map_or(true, |h| h < proposal.output_block_number);
+            proposal.assessment = Assessment::Correct { canonical };
         }
-        proposal.canonical
+        proposal.is_canonical()
     }
 
     pub fn determine_tournament_participation(
@@ -309,11 +520,22 @@ impl KailuaDB {
             return Ok(false);
         }
         // Skip non-canonical tournaments
-        if !parent.canonical.unwrap_or_default() {
+        if !parent.is_canonical().unwrap_or_default() {
             return Ok(false);
         }
         // Update the contender
         proposal.contender = parent.survivor;
+        if let Some(contender_index) = proposal.contender {
+            // eliminated or resolved survivors are no longer challenged by later siblings
+            let settled = self
+                .get_local_proposal(&contender_index)
+                .is_some_and(|c| c.status.is_eliminated() || c.status.is_resolved());
+            if !settled {
+                let challenged = ProposalStatus::Challenged { by: proposal.index };
+                self.transition_local_proposal(contender_index, challenged)
+                    .context("Failed to mark contender as challenged")?;
+            }
+        }
         // Append child to parent
         if !parent.append_child(proposal.index) {
             warn!(
@@ -342,16 +564,60 @@ impl KailuaDB {
     }
 
     pub fn get_local_proposal(&self, index: &u64) -> Option<Proposal> {
-        self.db
-            .get(index.to_be_bytes())
-            .ok()?
-            .and_then(|data| bincode::deserialize(&data).ok())
+        let data = match self.db.get(index.to_be_bytes()) {
+            Ok(data) => data?,
+            Err(err) => {
+                error!("Failed to read proposal {index}: {err:?}");
+                return None;
+            }
+        };
+        match Proposal::decode(&data) {
+            Ok(proposal) => Some(proposal),
+            Err(err) => {
+                error!("Failed to decode proposal {index}: {err:?}");
+                None
+            }
+        }
     }
 
     pub fn set_local_proposal(&mut self, index: u64, proposal: &Proposal) -> anyhow::Result<()> {
-        Ok(self
-            .db
-            .put(index.to_be_bytes(), bincode::serialize(proposal)?)?)
+        self.db.put(index.to_be_bytes(), proposal.encode()?)?;
+        self.state.modified_proposals.insert(index);
+        Ok(())
+    }
+
+    pub fn transition_local_proposal(
+        &mut self,
+        index: u64,
+        status: ProposalStatus,
+    ) -> anyhow::Result<()> {
+        let Some(mut proposal) = self.get_local_proposal(&index) else {
+            bail!("Proposal {index} missing from database.");
+        };
+        proposal.transition(status)?;
+        self.set_local_proposal(index, &proposal)
+    }
+
+    pub fn record_proof_status(
+        &mut self,
+        contender_index: u64,
+        proposal_index: u64,
+        proof_status: u8,
+    ) -> anyhow::Result<()> {
+        let Some((u_valid, v_valid)) = ProofStatus::parse(proof_status)?.outcomes() else {
+            return Ok(());
+        };
+        for (index, valid) in [(contender_index, u_valid), (proposal_index, v_valid)] {
+            // resolutions already settle the outcome of proven matches
+            if self
+                .get_local_proposal(&index)
+                .is_some_and(|proposal| proposal.status.is_resolved())
+            {
+                continue;
+            }
+            self.transition_local_proposal(index, ProposalStatus::Proven { valid })?;
+        }
+        Ok(())
     }
 
     pub fn is_proposer_eliminated(&self, proposer: Address) -> bool {
@@ -407,4 +673,106 @@ impl KailuaDB {
         }
         Ok(unresolved_proposal_indices)
     }
+
+    /// Marks the canonical proposals whose games were resolved since the last call as resolved,
+    /// returning the number of newly resolved proposals.
+    pub async fn record_resolutions<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        l1_node_provider: &P,
+    ) -> anyhow::Result<usize> {
+        let mut resolved_count = 0;
+        let mut next_index = self.state.canonical_tip_index;
+        while let Some(proposal_index) = next_index {
+            let Some(mut proposal) = self.get_local_proposal(&proposal_index) else {
+                break;
+            };
+            // resolved games extend each other, so their ancestors were already recorded
+            if proposal.status.is_resolved() {
+                break;
+            }
+            if let Some(defender_wins) = proposal.fetch_finality(l1_node_provider).await? {
+                proposal.transition(ProposalStatus::Resolved { defender_wins })?;
+                self.set_local_proposal(proposal_index, &proposal)?;
+                resolved_count += 1;
+            }
+            next_index = proposal.has_parent().then_some(proposal.parent);
+        }
+        Ok(resolved_count)
+    }
+
+    /// Archives and deletes all proposals older than the oldest of the configured number of
+    /// resolved canonical ancestors, returning the number of pruned proposals.
+    pub async fn prune_resolved_proposals<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        args: &RetentionArgs,
+        l1_node_provider: &P,
+    ) -> anyhow::Result<usize> {
+        let (Some(retained), Some(tip_index)) = (
+            args.retain_resolved_proposals,
+            self.state.canonical_tip_index,
+        ) else {
+            return Ok(0);
+        };
+        // Find the oldest retained resolved ancestor of the canonical tip
+        let mut cutoff = None;
+        let mut resolved_count = 0;
+        let mut proposal_index = tip_index;
+        self.record_resolutions(l1_node_provider).await?;
+        while let Some(proposal) = self.get_local_proposal(&proposal_index) {
+            if proposal.status.is_resolved() {
+                resolved_count += 1;
+                if resolved_count == retained {
+                    cutoff = Some(proposal_index);
+                    break;
+                }
+            }
+            if !proposal.has_parent() {
+                break;
+            }
+            proposal_index = proposal.parent;
+        }
+        let Some(cutoff) = cutoff else {
+            return Ok(0);
+        };
+        if cutoff <= self.state.pruned_below {
+            return Ok(0);
+        }
+        // Archive pruned proposals to cold storage before removing them
+        let pruned_range = self.state.pruned_below..cutoff;
+        let pruned = pruned_range
+            .clone()
+            .filter_map(|index| self.get_local_proposal(&index))
+            .collect::<Vec<_>>();
+        if !pruned.is_empty() {
+            let archive_dir = args
+                .proposal_archive_dir
+                .clone()
+                .unwrap_or_else(|| self.archive_dir.clone());
+            let path =
+                retention::write_archive(&archive_dir, pruned_range.start, cutoff - 1, &pruned)?;
+            info!("Archived {} proposals to {path:?}.", pruned.len());
+        }
+        let mut batch = rocksdb::WriteBatch::default();
+        for proposal in &pruned {
+            batch.delete(proposal.index.to_be_bytes());
+        }
+        self.db.write(batch)?;
+        self.db.compact_range(None::<&[u8]>, None::<&[u8]>);
+        // Drop in-memory references to pruned proposals
+        self.state.pruned_below = cutoff;
+        self.state
+            .first_proposals
+            .retain(|_, index| cutoff <= *index);
+        self.state
+            .modified_proposals
+            .retain(|index| cutoff <= *index);
+        self.state
+            .skipped_proposals
+            .retain(|index| cutoff <= *index);
+        info!(
+            "Pruned {} proposals below resolved ancestor {cutoff}.",
+            pruned.len()
+        );
+        Ok(pruned.len())
+    }
 }
@@ -1,7 +1,8 @@
+use crate::correctness::CorrectnessOracle;
 use crate::db::config::Config;
+use crate::db::lifecycle::{Assessment, Fault, ProposalStatus};
 use crate::providers::beacon::blob_fe_proof;
 use crate::providers::beacon::{blob_sidecar, BlobProvider};
-use crate::providers::optimism::OpNodeProvider;
 use crate::stall::Stall;
 use alloy::consensus::{Blob, BlobTransactionSidecar, BlockHeader};
 use alloy::eips::eip4844::FIELD_ELEMENTS_PER_BLOB;
@@ -13,20 +14,24 @@ use alloy::providers::Provider;
 use alloy::transports::Transport;
 use alloy_rpc_types_beacon::sidecar::BlobData;
 use anyhow::{bail, Context};
-use kailua_common::blobs::{hash_to_fe, intermediate_outputs};
+use kailua_common::blobs::{intermediate_outputs, io_blob_position};
 use kailua_contracts::{
     KailuaGame::KailuaGameInstance, KailuaTournament::KailuaTournamentInstance,
     KailuaTreasury::KailuaTreasuryInstance, *,
 };
 use serde::{Deserialize, Serialize};
-use std::iter::repeat;
 use tracing::{error, info, warn};
 
+/// A sequencing proposal as tracked by both the proposer and the validator, and persisted in the
+/// [crate::db::KailuaDB].
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Proposal {
     // pointers
+    /// The address of the tournament contract instance
     pub contract: Address,
+    /// The index of the proposal in the dispute game factory
     pub index: u64,
+    /// The factory index of the parent tournament
     pub parent: u64,
     pub proposer: Address,
     // claim data
@@ -41,13 +46,73 @@ pub struct Proposal {
     pub survivor: Option<u64>,
     pub contender: Option<u64>,
     // correctness
+    pub assessment: Assessment,
+    // lifecycle
+    pub status: ProposalStatus,
+}
+
+/// The prefix of proposals stored with an encoding version, which those stored before the
+/// lifecycle of proposals was tracked lack
+pub const PROPOSAL_ENCODING_MAGIC: [u8; 4] = *b"KPRP";
+
+/// The version of the encoding of proposals stored in the [crate::db::KailuaDB]
+pub const PROPOSAL_ENCODING_VERSION: u8 = 1;
+
+/// A proposal as stored before its lifecycle was tracked, with independent correctness flags
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LegacyProposal {
+    pub contract: Address,
+    pub index: u64,
+    pub parent: u64,
+    pub proposer: Address,
+    pub created_at: u64,
+    pub io_blobs: Vec<(B256, BlobData)>,
+    pub io_field_elements: Vec<B256>,
+    pub output_root: B256,
+    pub output_block_number: u64,
+    pub l1_head: B256,
+    pub children: Vec<u64>,
+    pub survivor: Option<u64>,
+    pub contender: Option<u64>,
     pub correct_io: Vec<Option<bool>>,
     pub correct_claim: Option<bool>,
     pub correct_parent: Option<bool>,
-    // resolution
     pub canonical: Option<bool>,
 }
 
+impl From<LegacyProposal> for Proposal {
+    fn from(legacy: LegacyProposal) -> Self {
+        let assessment = match Assessment::from_checks(
+            legacy.correct_parent,
+            legacy.correct_claim,
+            &legacy.correct_io,
+        ) {
+            Assessment::Correct { .. } => Assessment::Correct {
+                canonical: legacy.canonical.unwrap_or_default(),
+            },
+            assessment => assessment,
+        };
+        Self {
+            contract: legacy.contract,
+            index: legacy.index,
+            parent: legacy.parent,
+            proposer: legacy.proposer,
+            created_at: legacy.created_at,
+            io_blobs: legacy.io_blobs,
+            io_field_elements: legacy.io_field_elements,
+            output_root: legacy.output_root,
+            output_block_number: legacy.output_block_number,
+            l1_head: legacy.l1_head,
+            children: legacy.children,
+            survivor: legacy.survivor,
+            contender: legacy.contender,
+            assessment,
+            // matches and resolutions are recorded again as the validator observes them
+            status: ProposalStatus::Unchallenged,
+        }
+    }
+}
+
 impl Proposal {
     pub async fn load<T: Transport + Clone, P: Provider<T, N>, N: Network>(
         config: &Config,
@@ -108,10 +173,8 @@ impl Proposal {
             children: Default::default(),
             survivor: None,
             contender: None,
-            correct_io: vec![],
-            correct_claim: Some(true),
-            correct_parent: Some(true),
-            canonical: None,
+            assessment: Assessment::Correct { canonical: false },
+            status: ProposalStatus::Unchallenged,
         })
     }
 
@@ -142,7 +205,8 @@ impl Proposal {
                 .await
                 .context("get_blob")?;
             // save data
-            let io_remaining = config.proposal_block_count - (io_field_elements.len() as u64) - 1;
+            let io_remaining =
+                config.proposal_output_count() - (io_field_elements.len() as u64) - 1;
             let io_in_blob = io_remaining.min(FIELD_ELEMENTS_PER_BLOB);
             io_field_elements.extend(intermediate_outputs(&blob_data, io_in_blob as usize)?);
             io_blobs.push((blob_kzg_hash, blob_data));
@@ -170,12 +234,8 @@ impl Proposal {
             children: Default::default(),
             survivor: None,
             contender: None,
-            correct_io: repeat(None)
-                .take((config.proposal_block_count - 1) as usize)
-                .collect(),
-            correct_claim: None,
-            correct_parent: None,
-            canonical: None,
+            assessment: Assessment::Pending,
+            status: ProposalStatus::Unchallenged,
         })
     }
 
@@ -258,15 +318,13 @@ impl Proposal {
             )
             .await
             .context("get_block")?
-            .expect("Could not fetch latest L1 block")
+            .context("Could not fetch latest L1 block")?
             .header()
             .timestamp();
-        Ok(self
-            .tournament_contract_instance(provider)
-            .getChallengerDuration(U256::from(chain_time))
-            .stall()
+        self.tournament_contract_instance(provider)
+            .remaining_challenge_clock(chain_time)
             .await
-            .duration_)
+            .context("remaining_challenge_clock")
     }
 
     pub fn parse_finality(game_status: u8) -> anyhow::Result<Option<bool>> {
@@ -281,55 +339,97 @@ impl Proposal {
     pub async fn assess_correctness(
         &mut self,
         config: &Config,
-        op_node_provider: &OpNodeProvider,
+        oracle: &dyn CorrectnessOracle,
         is_correct_parent: bool,
     ) -> anyhow::Result<Option<bool>> {
-        // Update parent status
-        self.correct_parent = Some(is_correct_parent);
+        let mut correct_io = vec![None; self.io_field_elements.len()];
+        // Defer to the operator's verdict if overridden
+        if let Some(verdict) = oracle.verdict_override(self) {
+            warn!(
+                "Overriding correctness of proposal {} as {verdict}.",
+                self.index
+            );
+            correct_io.fill(Some(verdict));
+            self.assessment =
+                Assessment::from_checks(Some(is_correct_parent), Some(verdict), &correct_io);
+            return Ok(self.is_correct());
+        }
         // Check root claim correctness
-        let local_claim = op_node_provider
+        let local_claim = oracle
             .output_at_block(self.output_block_number)
             .await
             .context("output_at_block")?;
-        self.correct_claim = Some(local_claim == self.output_root);
+        let correct_claim = local_claim.map(|local_claim| local_claim == self.output_root);
         // Check intermediate output correctness for KailuaGame instances
         if self.has_parent() {
             let starting_block_number = self
                 .output_block_number
                 .saturating_sub(config.proposal_block_count);
             for (i, output_hash) in self.io_field_elements.iter().enumerate() {
-                let io_number = starting_block_number + (i as u64) + 1;
-                if let Ok(local_output) = op_node_provider.output_at_block(io_number).await {
-                    self.correct_io[i] = Some(&hash_to_fe(local_output) == output_hash);
-                } else {
-                    error!("Could not get output hash {io_number} from op node");
+                let io_number = config.output_block_number(starting_block_number, i as u64);
+                match oracle.output_at_block(io_number).await {
+                    Ok(Some(local_output)) => {
+                        correct_io[i] =
+                            Some(&config.field_encoding.output_to_fe(local_output) == output_hash);
+                    }
+                    Ok(None) => error!("Could not decide output hash {io_number}"),
+                    Err(_) => error!("Could not get output hash {io_number} from op node"),
                 }
             }
         }
+        self.assessment =
+            Assessment::from_checks(Some(is_correct_parent), correct_claim, &correct_io);
         // Return correctness
         Ok(self.is_correct())
     }
 
     pub fn is_correct(&self) -> Option<bool> {
-        // False case
-        if let Some(false) = self.correct_parent {
+        // A fault proof against the proposal overrides the local assessment
+        if self.status.is_eliminated() {
             return Some(false);
         }
-        if let Some(false) = self.correct_claim {
-            return Some(false);
+        self.assessment.is_correct()
+    }
+
+    /// Returns whether the proposal extends the canonical chain, if known
+    pub fn is_canonical(&self) -> Option<bool> {
+        match self.assessment {
+            _ if self.status.is_eliminated() => Some(false),
+            Assessment::Pending => None,
+            Assessment::Correct { canonical } => Some(canonical),
+            Assessment::Incorrect { .. } => Some(false),
         }
-        if self.correct_io.iter().flatten().any(|c| !c) {
-            return Some(false);
+    }
+
+    /// Encodes the proposal for storage in the database
+    pub fn encode(&self) -> anyhow::Result<Vec<u8>> {
+        let mut data = PROPOSAL_ENCODING_MAGIC.to_vec();
+        data.push(PROPOSAL_ENCODING_VERSION);
+        bincode::serialize_into(&mut data, self)?;
+        Ok(data)
+    }
+
+    /// Decodes a proposal stored in the database, migrating those stored without a version
+    pub fn decode(data: &[u8]) -> anyhow::Result<Self> {
+        match data.strip_prefix(&PROPOSAL_ENCODING_MAGIC) {
+            Some([PROPOSAL_ENCODING_VERSION, data @ ..]) => Ok(bincode::deserialize(data)?),
+            Some([version, ..]) => bail!("Unsupported proposal encoding version {version}."),
+            Some([]) => bail!("Truncated proposal encoding."),
+            None => Ok(bincode::deserialize::<LegacyProposal>(data)?.into()),
         }
-        // Unknown case
-        if self.correct_parent.is_none()
-            || self.correct_claim.is_none()
-            || self.correct_io.iter().any(|c| c.is_none())
-        {
-            return None;
+    }
+
+    /// Moves this proposal into the given lifecycle status if the transition is legal
+    pub fn transition(&mut self, status: ProposalStatus) -> anyhow::Result<()> {
+        if self.status == status {
+            return Ok(());
         }
-        // Correct!
-        Some(true)
+        self.status = self
+            .status
+            .transition(status)
+            .context(format!("Proposal {}", self.index))?;
+        info!("Proposal {} is now {:?}.", self.index, self.status);
+        Ok(())
     }
 
     pub fn tournament_contract_instance<T: Transport + Clone, P: Provider<T, N>, N: Network>(
@@ -379,11 +479,12 @@ impl Proposal {
             None => true,
             // u wins if v is wrong (even if u is wrong)
             Some(point) => {
-                if point < self.io_field_elements.len() {
-                    !proposal.correct_io[point].unwrap()
+                let fault = if point < self.io_field_elements.len() {
+                    Fault::Output(point)
                 } else {
-                    !proposal.correct_claim.unwrap()
-                }
+                    Fault::Claim
+                };
+                proposal.assessment.agrees_at(fault) == Some(false)
             }
         }
     }
@@ -415,8 +516,8 @@ impl Proposal {
     }
 
     pub fn io_blob_for(&self, position: u64) -> (B256, BlobData) {
-        let index = position / FIELD_ELEMENTS_PER_BLOB;
-        self.io_blobs[index as usize].clone()
+        let (index, _) = io_blob_position(position);
+        self.io_blobs[index].clone()
     }
 
     pub fn io_commitment_for(&self, position: u64) -> Bytes {
@@ -426,7 +527,9 @@ impl Proposal {
 
     pub fn io_proof_for(&self, position: u64) -> anyhow::Result<Bytes> {
         let io_blob = self.io_blob_for(position);
-        let (proof, _) = blob_fe_proof(&io_blob.1.blob, position as usize)?;
+        // the kzg proof opens the field element within the blob committing to it
+        let (_, fe_index) = io_blob_position(position);
+        let (proof, _) = blob_fe_proof(&io_blob.1.blob, fe_index)?;
         Ok(Bytes::from(proof.to_vec()))
     }
 
@@ -453,3 +556,156 @@ impl Proposal {
         blob_sidecar(io_blobs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::beacon::root_of_unity;
+    use alloy::consensus::EnvKzgSettings;
+    use alloy::eips::eip4844::{kzg_to_versioned_hash, BYTES_PER_BLOB};
+    use alloy::primitives::keccak256;
+    use kailua_common::blobs::hash_to_fe;
+    use serde_json::json;
+
+    fn blob_data() -> BlobData {
+        let word = |byte: u8| format!("0x{}", hex::encode([byte; 32]));
+        serde_json::from_value(json!({
+            "index": "1",
+            "blob": format!("0x{}", "ab".repeat(BYTES_PER_BLOB)),
+            "kzg_commitment": format!("0x{}", "cd".repeat(48)),
+            "kzg_proof": format!("0x{}", "ef".repeat(48)),
+            "signed_block_header": {
+                "message": {
+                    "slot": "100",
+                    "proposer_index": "7",
+                    "parent_root": word(0x01),
+                    "state_root": word(0x02),
+                    "body_root": word(0x03),
+                },
+                "signature": format!("0x{}", "04".repeat(96)),
+            },
+            "kzg_commitment_inclusion_proof": (0..17u8).map(word).collect::<Vec<_>>(),
+        }))
+        .unwrap()
+    }
+
+    fn proposal() -> Proposal {
+        Proposal {
+            contract: Address::repeat_byte(0xaa),
+            index: 42,
+            parent: 40,
+            proposer: Address::repeat_byte(0xbb),
+            created_at: 1_700_000_000,
+            io_blobs: vec![(B256::repeat_byte(0x01), blob_data())],
+            io_field_elements: vec![B256::repeat_byte(0x02), B256::repeat_byte(0x03)],
+            output_root: B256::repeat_byte(0x04),
+            output_block_number: 1800,
+            l1_head: B256::repeat_byte(0x05),
+            children: vec![43, 45],
+            survivor: Some(43),
+            contender: Some(45),
+            assessment: Assessment::Incorrect {
+                faults: vec![Fault::Claim],
+            },
+            status: ProposalStatus::Challenged { by: 45 },
+        }
+    }
+
+    #[test]
+    fn proposal_encoding_round_trip() {
+        let proposal = proposal();
+        let encoded = proposal.encode().unwrap();
+        let decoded = Proposal::decode(&encoded).unwrap();
+        assert_eq!(decoded.encode().unwrap(), encoded);
+        assert_eq!(decoded.index, proposal.index);
+        assert_eq!(decoded.io_blobs[0].1.blob, proposal.io_blobs[0].1.blob);
+        assert_eq!(decoded.assessment, proposal.assessment);
+        assert_eq!(decoded.status, proposal.status);
+        // unknown versions are rejected instead of misread
+        let mut unsupported = encoded.clone();
+        unsupported[PROPOSAL_ENCODING_MAGIC.len()] = PROPOSAL_ENCODING_VERSION + 1;
+        assert!(Proposal::decode(&unsupported).is_err());
+    }
+
+    #[test]
+    fn legacy_proposals_are_migrated() {
+        let proposal = proposal();
+        let legacy = LegacyProposal {
+            contract: proposal.contract,
+            index: proposal.index,
+            parent: proposal.parent,
+            proposer: proposal.proposer,
+            created_at: proposal.created_at,
+            io_blobs: proposal.io_blobs.clone(),
+            io_field_elements: proposal.io_field_elements.clone(),
+            output_root: proposal.output_root,
+            output_block_number: proposal.output_block_number,
+            l1_head: proposal.l1_head,
+            children: proposal.children.clone(),
+            survivor: proposal.survivor,
+            contender: proposal.contender,
+            correct_io: vec![Some(true), Some(true)],
+            correct_claim: Some(true),
+            correct_parent: Some(true),
+            canonical: Some(true),
+        };
+        let migrated = Proposal::decode(&bincode::serialize(&legacy).unwrap()).unwrap();
+        assert_eq!(migrated.index, proposal.index);
+        assert_eq!(migrated.io_blobs[0].1.blob, proposal.io_blobs[0].1.blob);
+        assert_eq!(migrated.children, proposal.children);
+        assert_eq!(migrated.assessment, Assessment::Correct { canonical: true });
+        assert_eq!(migrated.status, ProposalStatus::Unchallenged);
+
+        let legacy = LegacyProposal {
+            correct_io: vec![Some(true), None],
+            correct_claim: Some(false),
+            canonical: Some(false),
+            ..legacy
+        };
+        let migrated = Proposal::decode(&bincode::serialize(&legacy).unwrap()).unwrap();
+        assert_eq!(migrated.assessment, proposal.assessment);
+        assert_eq!(migrated.is_canonical(), Some(false));
+    }
+
+    #[test]
+    fn io_proofs_open_field_elements_within_their_blob() {
+        let io_field_elements = (0..FIELD_ELEMENTS_PER_BLOB + 2)
+            .map(|i| hash_to_fe(keccak256(i.to_be_bytes())))
+            .collect::<Vec<_>>();
+        let sidecar = Proposal::create_sidecar(&io_field_elements).unwrap();
+        let mut proposal = proposal();
+        proposal.io_field_elements = io_field_elements;
+        proposal.io_blobs = sidecar
+            .blobs
+            .iter()
+            .zip(&sidecar.commitments)
+            .map(|(blob, commitment)| {
+                let mut data = blob_data();
+                data.blob = Box::new(*blob);
+                data.kzg_commitment = *commitment;
+                (kzg_to_versioned_hash(commitment.as_slice()), data)
+            })
+            .collect();
+        let settings = EnvKzgSettings::default();
+        for position in [
+            0,
+            1,
+            FIELD_ELEMENTS_PER_BLOB - 1,
+            FIELD_ELEMENTS_PER_BLOB,
+            FIELD_ELEMENTS_PER_BLOB + 1,
+        ] {
+            let (_, fe_index) = io_blob_position(position);
+            let commitment = proposal.io_commitment_for(position);
+            let proof = proposal.io_proof_for(position).unwrap();
+            let verified = c_kzg::KzgProof::verify_kzg_proof(
+                &c_kzg::Bytes48::from_bytes(&commitment).unwrap(),
+                &c_kzg::Bytes32::new(root_of_unity(fe_index).to_be_bytes()),
+                &c_kzg::Bytes32::new(proposal.output_at(position).0),
+                &c_kzg::Bytes48::from_bytes(&proof).unwrap(),
+                settings.get(),
+            )
+            .unwrap();
+            assert!(verified, "invalid proof at position {position}");
+        }
+    }
+}
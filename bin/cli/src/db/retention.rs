@@ -0,0 +1,56 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::proposal::Proposal;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct RetentionArgs {
+    /// Number of resolved canonical ancestors to keep in the local proposal database
+    #[clap(long, env, value_parser = clap::value_parser!(u64).range(1..))]
+    pub retain_resolved_proposals: Option<u64>,
+    /// Directory to archive pruned proposals to (defaults to the archive folder of the data-dir)
+    #[clap(long, env)]
+    pub proposal_archive_dir: Option<PathBuf>,
+}
+
+/// Returns the path of the archive file holding the proposals in the given index range
+pub fn archive_path(archive_dir: &Path, from_index: u64, to_index: u64) -> PathBuf {
+    archive_dir.join(format!("proposals-{from_index}-{to_index}.bin"))
+}
+
+/// Writes the pruned proposals to a cold storage file, returning its path
+pub fn write_archive(
+    archive_dir: &Path,
+    from_index: u64,
+    to_index: u64,
+    proposals: &[Proposal],
+) -> anyhow::Result<PathBuf> {
+    std::fs::create_dir_all(archive_dir).context(format!(
+        "Failed to create archive directory {archive_dir:?}"
+    ))?;
+    let path = archive_path(archive_dir, from_index, to_index);
+    let temp_path = path.with_extension("tmp");
+    std::fs::write(&temp_path, bincode::serialize(proposals)?)
+        .context(format!("Failed to write {temp_path:?}"))?;
+    std::fs::rename(&temp_path, &path).context(format!("Failed to move archive to {path:?}"))?;
+    Ok(path)
+}
+
+/// Reads the proposals stored in an archive file
+pub fn read_archive(path: &Path) -> anyhow::Result<Vec<Proposal>> {
+    let data = std::fs::read(path).context(format!("Failed to read {path:?}"))?;
+    Ok(bincode::deserialize(&data)?)
+}
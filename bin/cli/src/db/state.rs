@@ -12,12 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::equivocation::Equivocation;
 use alloy::primitives::Address;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 #[derive(Clone, Debug, Default)]
 pub struct State {
     pub eliminations: HashMap<Address, u64>,
     pub next_factory_index: u64,
     pub canonical_tip_index: Option<u64>,
+    /// The first proposal made by each proposer for each L2 block
+    pub first_proposals: HashMap<(Address, u64), u64>,
+    pub equivocations: Vec<Equivocation>,
+    /// The indices of proposals written to the database since this set was last drained
+    pub modified_proposals: BTreeSet<u64>,
+    /// The factory index below which proposals were pruned from the database
+    pub pruned_below: u64,
+    /// The indices of proposals that were not inserted into the database
+    pub skipped_proposals: BTreeSet<u64>,
 }
@@ -0,0 +1,323 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::correctness::CorrectnessOracle;
+use crate::db::config::Config;
+use crate::db::lifecycle::Fault;
+use crate::db::proposal::Proposal;
+use alloy::primitives::{Address, B256};
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// The name of the file under the data directory that correctness decisions are appended to
+pub const DECISIONS_FILE: &str = "decisions.jsonl";
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct WhyArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the game whose correctness decisions to explain
+    #[clap(long)]
+    pub game: Address,
+    /// Data directory of the validator or proposer that made the decisions
+    #[clap(long, env)]
+    pub data_dir: PathBuf,
+    /// Whether to print the recorded decisions as JSON
+    #[clap(long, env)]
+    pub json: bool,
+}
+
+/// The answer of the correctness oracle to a query for an output root
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OracleResponse {
+    pub output_root: Option<B256>,
+    pub error: Option<String>,
+}
+
+/// Records every answer of the wrapped oracle
+pub struct RecordingOracle<'a> {
+    pub inner: &'a dyn CorrectnessOracle,
+    pub responses: Mutex<BTreeMap<u64, OracleResponse>>,
+}
+
+impl<'a> RecordingOracle<'a> {
+    pub fn new(inner: &'a dyn CorrectnessOracle) -> Self {
+        Self {
+            inner,
+            responses: Default::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl CorrectnessOracle for RecordingOracle<'_> {
+    async fn output_at_block(&self, block_number: u64) -> anyhow::Result<Option<B256>> {
+        let result = self.inner.output_at_block(block_number).await;
+        let response = match &result {
+            Ok(output_root) => OracleResponse {
+                output_root: *output_root,
+                error: None,
+            },
+            Err(err) => OracleResponse {
+                output_root: None,
+                error: Some(format!("{err:?}")),
+            },
+        };
+        self.responses
+            .lock()
+            .unwrap()
+            .insert(block_number, response);
+        result
+    }
+
+    fn verdict_override(&self, proposal: &Proposal) -> Option<bool> {
+        self.inner.verdict_override(proposal)
+    }
+
+    async fn sync_status(&self) -> anyhow::Result<Option<Value>> {
+        self.inner.sync_status().await
+    }
+}
+
+/// The comparison of a claimed output against the oracle's answer for its block
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputCheck {
+    pub block_number: u64,
+    /// The output root, or field element for intermediate outputs, committed to by the proposal
+    pub claimed: B256,
+    pub response: Option<OracleResponse>,
+    /// The value the proposal had to commit to according to the oracle
+    pub expected: Option<B256>,
+    pub correct: Option<bool>,
+}
+
+/// The inputs and outcome of a correctness decision on a proposal
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CorrectnessDecision {
+    /// The unix timestamp of the decision
+    pub decided_at: u64,
+    pub game_index: u64,
+    pub game_contract: Address,
+    pub parent_index: u64,
+    pub proposer: Address,
+    pub l1_head: B256,
+    /// The op-node sync status observed right before the decision
+    pub sync_status: Option<Value>,
+    pub sync_status_error: Option<String>,
+    pub parent_correct: Option<bool>,
+    /// The verdict of the operator's correctness overrides, if any
+    pub overridden: Option<bool>,
+    pub claim: OutputCheck,
+    pub intermediate_outputs: Vec<OutputCheck>,
+    pub correct: Option<bool>,
+}
+
+impl CorrectnessDecision {
+    /// Reconstructs the decision on the assessed proposal from the oracle's recorded answers
+    pub fn new(
+        config: &Config,
+        proposal: &Proposal,
+        oracle: &RecordingOracle,
+        sync_status: anyhow::Result<Option<Value>>,
+    ) -> Self {
+        let responses = oracle.responses.lock().unwrap();
+        let claim = OutputCheck {
+            block_number: proposal.output_block_number,
+            claimed: proposal.output_root,
+            response: responses.get(&proposal.output_block_number).cloned(),
+            expected: responses
+                .get(&proposal.output_block_number)
+                .and_then(|response| response.output_root),
+            correct: proposal.assessment.agrees_at(Fault::Claim),
+        };
+        let starting_block_number = proposal
+            .output_block_number
+            .saturating_sub(config.proposal_block_count);
+        let intermediate_outputs = proposal
+            .io_field_elements
+            .iter()
+            .enumerate()
+            .map(|(i, claimed)| {
+                let block_number = config.output_block_number(starting_block_number, i as u64);
+                let response = responses.get(&block_number).cloned();
+                OutputCheck {
+                    block_number,
+                    claimed: *claimed,
+                    expected: response
+                        .as_ref()
+                        .and_then(|response| response.output_root)
+                        .map(|output_root| config.field_encoding.output_to_fe(output_root)),
+                    response,
+                    correct: proposal.assessment.agrees_at(Fault::Output(i)),
+                }
+            })
+            .collect();
+        let (sync_status, sync_status_error) = match sync_status {
+            Ok(sync_status) => (sync_status, None),
+            Err(err) => (None, Some(format!("{err:?}"))),
+        };
+        Self {
+            decided_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            game_index: proposal.index,
+            game_contract: proposal.contract,
+            parent_index: proposal.parent,
+            proposer: proposal.proposer,
+            l1_head: proposal.l1_head,
+            sync_status,
+            sync_status_error,
+            parent_correct: proposal.assessment.agrees_at(Fault::Parent),
+            overridden: oracle.verdict_override(proposal),
+            claim,
+            intermediate_outputs,
+            correct: proposal.is_correct(),
+        }
+    }
+
+    /// Appends the decision to the decision log
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context(format!("Failed to open {path:?}"))?;
+        let mut line = serde_json::to_vec(self)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        Ok(())
+    }
+
+    /// Prints a human-readable account of the decision
+    pub fn explain(&self) {
+        println!(
+            "Game {} (index {}, parent {}, proposer {})",
+            self.game_contract, self.game_index, self.parent_index, self.proposer
+        );
+        println!(
+            "  Decided at unix time {} for L1 head {}.",
+            self.decided_at, self.l1_head
+        );
+        match (&self.sync_status, &self.sync_status_error) {
+            (Some(sync_status), _) => {
+                let number = |head: &str| {
+                    sync_status[head]["number"]
+                        .as_u64()
+                        .map_or(String::from("?"), |n| n.to_string())
+                };
+                println!(
+                    "  op-node sync status: current L1 {}, unsafe L2 {}, safe L2 {}, finalized L2 {}.",
+                    number("current_l1"),
+                    number("unsafe_l2"),
+                    number("safe_l2"),
+                    number("finalized_l2")
+                );
+            }
+            (None, Some(err)) => println!("  op-node sync status unavailable: {err}"),
+            (None, None) => println!("  The correctness oracle reports no sync status."),
+        }
+        match self.parent_correct {
+            Some(true) => println!("  Parent {} was correct.", self.parent_index),
+            Some(false) => println!(
+                "  Parent {} was incorrect, which makes this proposal incorrect.",
+                self.parent_index
+            ),
+            None => println!("  Parent {} was undecided.", self.parent_index),
+        }
+        if let Some(overridden) = self.overridden {
+            println!("  Correctness was overridden as {overridden} by the operator.");
+        }
+        let explain_check = |label: String, check: &OutputCheck| {
+            let observed = match &check.response {
+                None => String::from("was not queried"),
+                Some(OracleResponse {
+                    error: Some(err), ..
+                }) => format!("failed ({err})"),
+                Some(OracleResponse {
+                    output_root: None, ..
+                }) => String::from("was undecided"),
+                Some(OracleResponse {
+                    output_root: Some(output_root),
+                    ..
+                }) => format!("reported {output_root}"),
+            };
+            let outcome = match check.correct {
+                Some(true) => "correct",
+                Some(false) => "INCORRECT",
+                None => "undecided",
+            };
+            println!(
+                "  {label} of block {}: claimed {}, oracle {observed}{} -> {outcome}",
+                check.block_number,
+                check.claimed,
+                match (check.expected, check.response.as_ref()) {
+                    (Some(expected), Some(response)) if response.output_root != Some(expected) =>
+                        format!(" (expected {expected})"),
+                    _ => String::new(),
+                }
+            );
+        };
+        explain_check(String::from("Claim"), &self.claim);
+        for (i, check) in self.intermediate_outputs.iter().enumerate() {
+            explain_check(format!("Intermediate output {i}"), check);
+        }
+        match self.correct {
+            Some(correct) => println!("  Verdict: {correct}."),
+            None => println!("  Verdict: undecided, so the proposal was not processed."),
+        }
+    }
+}
+
+/// Loads all recorded decisions on the game, oldest first
+pub fn load_decisions(data_dir: &Path, game: Address) -> anyhow::Result<Vec<CorrectnessDecision>> {
+    let path = data_dir.join(DECISIONS_FILE);
+    let data = std::fs::read_to_string(&path)
+        .context(format!("Failed to read decisions from {path:?}"))?;
+    let mut decisions = Vec::new();
+    for line in data.lines().filter(|line| !line.trim().is_empty()) {
+        let decision: CorrectnessDecision =
+            serde_json::from_str(line).context(format!("Failed to parse {path:?}"))?;
+        if decision.game_contract == game {
+            decisions.push(decision);
+        }
+    }
+    Ok(decisions)
+}
+
+pub async fn why(args: WhyArgs) -> anyhow::Result<()> {
+    let decisions = load_decisions(&args.data_dir, args.game)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&decisions)?);
+        return Ok(());
+    }
+    if decisions.is_empty() {
+        println!(
+            "No correctness decisions on game {} were recorded in {:?}.",
+            args.game, args.data_dir
+        );
+        return Ok(());
+    }
+    for decision in &decisions {
+        decision.explain();
+    }
+    Ok(())
+}
@@ -0,0 +1,613 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::attest::{attest_bytecode, BytecodeAttestation};
+use crate::governance::Governance;
+use crate::stall::Stall;
+use crate::{bn254_control_id, control_root, SET_BUILDER_ID};
+use alloy::network::Network;
+use alloy::primitives::{Address, Bytes, Uint, B256, U256};
+use alloy::providers::Provider;
+use alloy::sol_types::SolValue;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use kailua_client::proof::Proof;
+use kailua_contracts::{artifacts, IDisputeGameFactory::IDisputeGameFactoryInstance, *};
+use risc0_zkvm::sha::{Digest, Digestible};
+use risc0_zkvm::Groth16ReceiptVerifierParameters;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct Groth16Args {
+    /// Control root of a custom Groth16 circuit to deploy the verifier for
+    #[clap(long, env)]
+    pub groth16_control_root: Option<B256>,
+    /// BN254 control id of a custom Groth16 circuit to deploy the verifier for
+    #[clap(long, env)]
+    pub groth16_bn254_control_id: Option<B256>,
+    /// Path to a known-good Groth16 proof file the deployed verifier must accept
+    #[clap(long, env)]
+    pub groth16_test_receipt: Option<PathBuf>,
+}
+
+impl Groth16Args {
+    pub fn control_root(&self) -> B256 {
+        self.groth16_control_root.unwrap_or_else(control_root)
+    }
+
+    pub fn bn254_control_id(&self) -> B256 {
+        self.groth16_bn254_control_id
+            .unwrap_or_else(bn254_control_id)
+    }
+
+    /// Returns the selector the verifier deployed with these parameters must report
+    pub fn expected_selector(&self) -> [u8; 4] {
+        let mut bn254_control_id = Digest::try_from(self.bn254_control_id().as_slice()).unwrap();
+        bn254_control_id.as_mut_bytes().reverse();
+        let params = Groth16ReceiptVerifierParameters {
+            control_root: Digest::try_from(self.control_root().as_slice()).unwrap(),
+            bn254_control_id,
+            ..Default::default()
+        };
+        params.digest().as_bytes()[..4].try_into().unwrap()
+    }
+}
+
+/// The parameters shared by the KailuaTreasury and KailuaGame implementation contracts
+#[derive(Clone, Debug)]
+pub struct GameParameters {
+    pub game_type: u32,
+    pub image_id: B256,
+    pub rollup_config_hash: B256,
+    pub proposal_block_span: u64,
+    pub output_block_span: u64,
+    pub genesis_time: u64,
+    pub block_time: u64,
+    pub proposal_time_gap: u64,
+    pub challenge_timeout: u64,
+    pub l2_chain_id: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct VerifierStack {
+    pub router: Address,
+    pub groth16: Address,
+    pub set: Address,
+    pub mock: Option<Address>,
+}
+
+/// Deploys the Kailua contracts and wires them into a `DisputeGameFactory` through its owner.
+pub struct Deployer<T, P1, P2, N: Network> {
+    pub deployer_provider: P1,
+    pub owner_provider: P2,
+    pub owner_address: Address,
+    pub dispute_game_factory: IDisputeGameFactoryInstance<T, P2, N>,
+    pub factory_owner: Governance<T, P2, N>,
+    pub attestations: Vec<BytecodeAttestation>,
+}
+
+impl<T: Transport + Clone, P1: Provider<T, N>, P2: Provider<T, N> + Clone, N: Network>
+    Deployer<T, P1, P2, N>
+{
+    /// Connects to the factory and its owner, which is either a 1-of-1 `Safe` account or a
+    /// `TimelockController` whose operations can optionally be exported instead of executed.
+    pub async fn new(
+        deployer_provider: P1,
+        owner_provider: P2,
+        owner_address: Address,
+        dispute_game_factory_address: Address,
+        export_timelock_operations: bool,
+    ) -> anyhow::Result<Self> {
+        let dispute_game_factory =
+            IDisputeGameFactory::new(dispute_game_factory_address, owner_provider.clone());
+        info!("DisputeGameFactory({:?})", dispute_game_factory.address());
+        let game_count = dispute_game_factory.gameCount().stall().await.gameCount_;
+        info!("There have been {game_count} games created using DisputeGameFactory");
+        let dispute_game_factory_ownable =
+            OwnableUpgradeable::new(dispute_game_factory_address, owner_provider.clone());
+        let factory_owner_address = dispute_game_factory_ownable.owner().stall().await._0;
+        let factory_owner = Governance::detect(
+            factory_owner_address,
+            owner_provider.clone(),
+            owner_address,
+            export_timelock_operations,
+        )
+        .await?;
+        Ok(Self {
+            deployer_provider,
+            owner_provider,
+            owner_address,
+            dispute_game_factory,
+            factory_owner,
+            attestations: vec![],
+        })
+    }
+
+    /// Deploys a `RiscZeroVerifierRouter` owned by the owner wallet, and registers a fresh
+    /// groth16 and set verifier with it.
+    pub async fn deploy_verifier_stack(
+        &mut self,
+        groth16: &Groth16Args,
+    ) -> anyhow::Result<VerifierStack> {
+        // Deploy verifier router contract
+        info!(
+            "Deploying RiscZeroVerifierRouter contract to L1 under ownership of {}.",
+            self.owner_address
+        );
+        let verifier_contract =
+            RiscZeroVerifierRouter::deploy(&self.deployer_provider, self.owner_address)
+                .await
+                .context("RiscZeroVerifierRouter contract deployment error")?;
+        let verifier_contract_address = *verifier_contract.address();
+        self.attestations.push(
+            attest_bytecode(
+                &self.deployer_provider,
+                &artifacts::RISC_ZERO_VERIFIER_ROUTER,
+                verifier_contract_address,
+                &RiscZeroVerifierRouter::DEPLOYED_BYTECODE,
+            )
+            .await?,
+        );
+        let verifier_contract =
+            RiscZeroVerifierRouter::new(verifier_contract_address, &self.owner_provider);
+
+        // Deploy RiscZeroGroth16Verifier contract
+        info!("Deploying RiscZeroGroth16Verifier contract to L1.");
+        let groth16_verifier_contract = RiscZeroGroth16Verifier::deploy(
+            &self.deployer_provider,
+            groth16.control_root(),
+            groth16.bn254_control_id(),
+        )
+        .await
+        .context("RiscZeroGroth16Verifier contract deployment error")?;
+        info!("{:?}", &groth16_verifier_contract);
+        self.attestations.push(
+            attest_bytecode(
+                &self.deployer_provider,
+                &artifacts::RISC_ZERO_GROTH16_VERIFIER,
+                *groth16_verifier_contract.address(),
+                &RiscZeroGroth16Verifier::DEPLOYED_BYTECODE,
+            )
+            .await?,
+        );
+        let selector = groth16_verifier_contract.SELECTOR().stall().await._0;
+        if selector.0 != groth16.expected_selector() {
+            bail!(
+                "RiscZeroGroth16Verifier selector {selector} does not match the expected 0x{}.",
+                hex::encode(groth16.expected_selector())
+            );
+        }
+        if let Some(path) = &groth16.groth16_test_receipt {
+            let proof: Proof = bincode::deserialize(
+                &tokio::fs::read(path)
+                    .await
+                    .context(format!("Failed to read test receipt {path:?}"))?,
+            )?;
+            let Some(receipt) = proof.as_receipt() else {
+                bail!("Test receipt {path:?} is not a zkvm receipt.");
+            };
+            let image_id = receipt.claim()?.as_value()?.pre.digest();
+            let journal_digest = proof.journal().digest();
+            groth16_verifier_contract
+                .verify(
+                    Bytes::from(proof.encoded_seal()?),
+                    B256::from_slice(image_id.as_bytes()),
+                    B256::from_slice(journal_digest.as_bytes()),
+                )
+                .call()
+                .await
+                .context("RiscZeroGroth16Verifier rejected the test receipt")?;
+            info!("RiscZeroGroth16Verifier accepted the test receipt.");
+        } else {
+            warn!("No test receipt provided to check the RiscZeroGroth16Verifier against.");
+        }
+        info!("Adding RiscZeroGroth16Verifier contract to RiscZeroVerifierRouter.");
+        verifier_contract
+            .addVerifier(selector, *groth16_verifier_contract.address())
+            .send()
+            .await
+            .context("addVerifier RiscZeroGroth16Verifier (send)")?
+            .get_receipt()
+            .await
+            .context("addVerifier RiscZeroGroth16Verifier (get_receipt)")?;
+
+        // Deploy RiscZeroSetVerifier contract
+        info!("Deploying RiscZeroSetVerifier contract to L1.");
+        let set_verifier_contract = RiscZeroSetVerifier::deploy(
+            &self.deployer_provider,
+            verifier_contract_address,
+            SET_BUILDER_ID,
+            String::default(),
+        )
+        .await
+        .context("RiscZeroSetVerifier contract deployment error")?;
+        info!("{:?}", &set_verifier_contract);
+        self.attestations.push(
+            attest_bytecode(
+                &self.deployer_provider,
+                &artifacts::RISC_ZERO_SET_VERIFIER,
+                *set_verifier_contract.address(),
+                &RiscZeroSetVerifier::DEPLOYED_BYTECODE,
+            )
+            .await?,
+        );
+        let selector = set_verifier_contract.SELECTOR().stall().await._0;
+        info!("Adding RiscZeroSetVerifier contract to RiscZeroVerifierRouter.");
+        verifier_contract
+            .addVerifier(selector, *set_verifier_contract.address())
+            .send()
+            .await
+            .context("addVerifier RiscZeroSetVerifier (send)")?
+            .get_receipt()
+            .await
+            .context("addVerifier RiscZeroSetVerifier (get_receipt)")?;
+
+        // Deploy mock verifier
+        #[allow(unused_mut)]
+        let mut mock = None;
+        #[cfg(feature = "devnet")]
+        if risc0_zkvm::is_dev_mode() {
+            // Deploy MockVerifier contract
+            tracing::warn!("Deploying RiscZeroMockVerifier contract to L1. This will accept fake proofs which are not cryptographically secure!");
+            let mock_verifier_contract =
+                RiscZeroMockVerifier::deploy(&self.deployer_provider, [0u8; 4].into())
+                    .await
+                    .context("RiscZeroMockVerifier contract deployment error")?;
+            tracing::warn!("{:?}", &mock_verifier_contract);
+            tracing::warn!("Adding RiscZeroMockVerifier contract to RiscZeroVerifierRouter.");
+            verifier_contract
+                .addVerifier([0u8; 4].into(), *mock_verifier_contract.address())
+                .send()
+                .await
+                .context("addVerifier RiscZeroMockVerifier (send)")?
+                .get_receipt()
+                .await
+                .context("addVerifier RiscZeroMockVerifier (get_receipt)")?;
+            mock = Some(*mock_verifier_contract.address());
+        }
+
+        Ok(VerifierStack {
+            router: verifier_contract_address,
+            groth16: *groth16_verifier_contract.address(),
+            set: *set_verifier_contract.address(),
+            mock,
+        })
+    }
+
+    pub async fn deploy_treasury(
+        &mut self,
+        verifier: Address,
+        params: &GameParameters,
+    ) -> anyhow::Result<Address> {
+        info!("Deploying KailuaTreasury contract to L1 rpc.");
+        let kailua_treasury_implementation = KailuaTreasury::deploy(
+            &self.deployer_provider,
+            verifier,
+            params.image_id,
+            params.rollup_config_hash,
+            Uint::from(params.proposal_block_span),
+            Uint::from(params.output_block_span),
+            params.game_type,
+            *self.dispute_game_factory.address(),
+            params.l2_chain_id,
+        )
+        .await
+        .context("KailuaTreasury implementation contract deployment error")?;
+        info!("{:?}", &kailua_treasury_implementation);
+        let treasury_address = *kailua_treasury_implementation.address();
+        self.attestations.push(
+            attest_bytecode(
+                &self.deployer_provider,
+                &artifacts::KAILUA_TREASURY,
+                treasury_address,
+                &KailuaTreasury::DEPLOYED_BYTECODE,
+            )
+            .await?,
+        );
+        Ok(treasury_address)
+    }
+
+    pub async fn deploy_game(
+        &mut self,
+        treasury_implementation: Address,
+        verifier: Address,
+        params: &GameParameters,
+    ) -> anyhow::Result<Address> {
+        info!("Deploying KailuaGame contract to L1 rpc.");
+        let kailua_game_contract = KailuaGame::deploy(
+            &self.deployer_provider,
+            treasury_implementation,
+            verifier,
+            params.image_id,
+            params.rollup_config_hash,
+            Uint::from(params.proposal_block_span),
+            Uint::from(params.output_block_span),
+            params.game_type,
+            *self.dispute_game_factory.address(),
+            params.l2_chain_id,
+            U256::from(params.genesis_time),
+            U256::from(params.block_time),
+            U256::from(params.proposal_time_gap),
+            params.challenge_timeout,
+        )
+        .await
+        .context("KailuaGame contract deployment error")?;
+        info!("{:?}", &kailua_game_contract);
+        let game_address = *kailua_game_contract.address();
+        self.attestations.push(
+            attest_bytecode(
+                &self.deployer_provider,
+                &artifacts::KAILUA_GAME,
+                game_address,
+                &KailuaGame::DEPLOYED_BYTECODE,
+            )
+            .await?,
+        );
+        Ok(game_address)
+    }
+
+    /// Zeroes the factory's initialization bond for the game type and sets the participation bond
+    /// required by the treasury.
+    pub async fn set_bonds(
+        &self,
+        game_type: u32,
+        treasury_implementation: Address,
+        participation_bond: U256,
+    ) -> anyhow::Result<()> {
+        info!("Setting KailuaTreasury initialization bond value in DisputeGameFactory to zero.");
+        let executed = self
+            .factory_owner
+            .execute(
+                vec![self
+                    .dispute_game_factory
+                    .setInitBond(game_type, U256::ZERO)
+                    .into_transaction_request()],
+                None,
+            )
+            .await
+            .context("setInitBond 0 wei")?;
+        if executed {
+            let init_bond = self
+                .dispute_game_factory
+                .initBonds(game_type)
+                .stall()
+                .await
+                .bond_;
+            if !init_bond.is_zero() {
+                bail!("DisputeGameFactory initialization bond is {init_bond} wei.");
+            }
+        }
+
+        info!("Setting KailuaTreasury participation bond value to {participation_bond} wei.");
+        let kailua_treasury = KailuaTreasury::new(treasury_implementation, &self.owner_provider);
+        let executed = self
+            .factory_owner
+            .execute(
+                vec![kailua_treasury
+                    .setParticipationBond(participation_bond)
+                    .into_transaction_request()],
+                None,
+            )
+            .await
+            .context(format!("setParticipationBond {participation_bond} wei"))?;
+        if executed {
+            let bond_value = kailua_treasury.participationBond().stall().await._0;
+            if bond_value != participation_bond {
+                bail!("KailuaTreasury participation bond is {bond_value} wei.");
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the factory's implementation contract for the given game type
+    pub async fn wire_factory(
+        &self,
+        game_type: u32,
+        implementation: Address,
+    ) -> anyhow::Result<()> {
+        info!("Setting implementation address in DisputeGameFactory to {implementation}.");
+        let executed = self
+            .factory_owner
+            .execute(
+                vec![self
+                    .dispute_game_factory
+                    .setImplementation(game_type, implementation)
+                    .into_transaction_request()],
+                None,
+            )
+            .await
+            .context("setImplementation")?;
+        if executed {
+            let current_implementation = self
+                .dispute_game_factory
+                .gameImpls(game_type)
+                .stall()
+                .await
+                .impl_;
+            if current_implementation != implementation {
+                bail!("DisputeGameFactory implementation is {current_implementation}.");
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates and resolves the KailuaTreasury instance anchoring proposals at the given output
+    pub async fn bootstrap_treasury(
+        &self,
+        game_type: u32,
+        root_claim: B256,
+        starting_block_number: u64,
+    ) -> anyhow::Result<Option<Address>> {
+        let extra_data = Bytes::from(starting_block_number.abi_encode_packed());
+        info!(
+            "Creating new KailuaTreasury game instance from {} ({}).",
+            starting_block_number, root_claim
+        );
+        let executed = self
+            .factory_owner
+            .execute(
+                vec![self
+                    .dispute_game_factory
+                    .create(game_type, root_claim, extra_data.clone())
+                    .into_transaction_request()],
+                None,
+            )
+            .await
+            .context("create KailuaTreasury")?;
+        if !executed {
+            warn!("The created KailuaTreasury instance must be resolved after the export is executed.");
+            return Ok(None);
+        }
+        let kailua_treasury_instance_address = self
+            .dispute_game_factory
+            .games(game_type, root_claim, extra_data)
+            .stall()
+            .await
+            .proxy_;
+        self.resolve_treasury(kailua_treasury_instance_address)
+            .await?;
+        Ok(Some(kailua_treasury_instance_address))
+    }
+
+    /// Performs the complete factory wiring in a single atomic governance action, through the
+    /// `MultiSendCallOnly` contract at `multisend` for safes, then resolves the created treasury
+    /// instance.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn wire_factory_atomically(
+        &self,
+        multisend: Option<Address>,
+        game_type: u32,
+        treasury_implementation: Address,
+        participation_bond: U256,
+        root_claim: B256,
+        starting_block_number: u64,
+        game_implementation: Address,
+    ) -> anyhow::Result<Option<Address>> {
+        let extra_data = Bytes::from(starting_block_number.abi_encode_packed());
+        let kailua_treasury = KailuaTreasury::new(treasury_implementation, &self.owner_provider);
+        let txns = vec![
+            self.dispute_game_factory
+                .setInitBond(game_type, U256::ZERO)
+                .into_transaction_request(),
+            kailua_treasury
+                .setParticipationBond(participation_bond)
+                .into_transaction_request(),
+            self.dispute_game_factory
+                .setImplementation(game_type, treasury_implementation)
+                .into_transaction_request(),
+            self.dispute_game_factory
+                .create(game_type, root_claim, extra_data.clone())
+                .into_transaction_request(),
+            self.dispute_game_factory
+                .setImplementation(game_type, game_implementation)
+                .into_transaction_request(),
+        ];
+        info!(
+            "Wiring DisputeGameFactory with {} calls through {}.",
+            txns.len(),
+            self.factory_owner.address()
+        );
+        let executed = self
+            .factory_owner
+            .execute(txns, multisend)
+            .await
+            .context("atomic factory wiring")?;
+        if !executed {
+            warn!("The created KailuaTreasury instance must be resolved after the export is executed.");
+            return Ok(None);
+        }
+
+        // Confirm the final factory configuration
+        let init_bond = self
+            .dispute_game_factory
+            .initBonds(game_type)
+            .stall()
+            .await
+            .bond_;
+        if !init_bond.is_zero() {
+            bail!("DisputeGameFactory initialization bond is {init_bond} wei.");
+        }
+        let bond_value = kailua_treasury.participationBond().stall().await._0;
+        if bond_value != participation_bond {
+            bail!("KailuaTreasury participation bond is {bond_value} wei.");
+        }
+        let current_implementation = self
+            .dispute_game_factory
+            .gameImpls(game_type)
+            .stall()
+            .await
+            .impl_;
+        if current_implementation != game_implementation {
+            bail!("DisputeGameFactory implementation is {current_implementation}.");
+        }
+        let kailua_treasury_instance_address = self
+            .dispute_game_factory
+            .games(game_type, root_claim, extra_data)
+            .stall()
+            .await
+            .proxy_;
+        self.resolve_treasury(kailua_treasury_instance_address)
+            .await?;
+        Ok(Some(kailua_treasury_instance_address))
+    }
+
+    /// Resolves the given KailuaTreasury instance if it is still in progress
+    pub async fn resolve_treasury(&self, treasury_instance: Address) -> anyhow::Result<()> {
+        let kailua_treasury_instance = KailuaTreasury::new(treasury_instance, &self.owner_provider);
+        info!("{:?}", &kailua_treasury_instance);
+        let status = kailua_treasury_instance.status().stall().await._0;
+        if status == 0 {
+            info!("Resolving KailuaTreasury instance");
+            self.factory_owner
+                .execute(
+                    vec![kailua_treasury_instance
+                        .resolve()
+                        .into_transaction_request()],
+                    None,
+                )
+                .await
+                .context("resolve KailuaTreasury")?;
+        } else {
+            info!("Game instance is not ongoing ({status})");
+        }
+        Ok(())
+    }
+
+    /// Sets the given game type as the one respected by the `OptimismPortal2` for withdrawals
+    pub async fn activate_portal<P3: Provider<T, N>>(
+        &self,
+        guardian_provider: P3,
+        guardian_address: Address,
+        portal_address: Address,
+        game_type: u32,
+    ) -> anyhow::Result<()> {
+        let optimism_portal = OptimismPortal2::new(portal_address, &guardian_provider);
+        let portal_guardian_address = optimism_portal.guardian().stall().await._0;
+        if portal_guardian_address != guardian_address {
+            bail!("OptimismPortal2 Guardian is {portal_guardian_address}. Provided private key has account address {guardian_address}.");
+        }
+
+        info!("Setting respectedGameType in OptimismPortal2.");
+        optimism_portal
+            .setRespectedGameType(game_type)
+            .send()
+            .await
+            .context("setRespectedGameType (send)")?
+            .get_receipt()
+            .await
+            .context("setRespectedGameType (get_receipt)")?;
+        Ok(())
+    }
+}
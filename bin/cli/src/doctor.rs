@@ -0,0 +1,752 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::competition::CancelledProofs;
+use crate::db::config::Config;
+use crate::host_service::{kailua_host_command, HostService};
+use crate::images::{find_release, local_image_id};
+use crate::providers::beacon::BlobProvider;
+use crate::providers::optimism::OpNodeProvider;
+use crate::providers::versions::{parse_version, INCOMPATIBLE_VERSIONS};
+use crate::stall::Stall;
+use crate::validate::{path_arg, ProofRequest};
+use crate::KAILUA_GAME_TYPE;
+use alloy::eips::BlockNumberOrTag;
+use alloy::network::primitives::BlockTransactionsKind;
+use alloy::network::Network;
+use alloy::primitives::{Address, B256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::signers::local::LocalSigner;
+use alloy::transports::http::reqwest;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use kailua_common::config::config_hash;
+use kailua_common::journal::PROOF_JOURNAL_VERSION;
+use kailua_contracts::*;
+use kailua_host::compat::{NodeCapabilities, PreflightStrategy};
+use kailua_host::fetch_rollup_config;
+use risc0_zkvm::is_dev_mode;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::time::timeout;
+use tracing::info;
+
+/// The seconds between consecutive L1 blocks
+const L1_BLOCK_TIME: i64 = 12;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct DoctorArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the OP-NODE endpoint to use
+    #[clap(long, env)]
+    pub op_node_url: String,
+    /// Address of the OP-GETH endpoint to use (eth and debug namespace required).
+    #[clap(long, env)]
+    pub op_geth_url: String,
+    /// Address of the ethereum rpc endpoint to use (eth namespace required)
+    #[clap(long, env)]
+    pub eth_rpc_url: String,
+    /// Address of the L1 Beacon API endpoint to use.
+    #[clap(long, env)]
+    pub beacon_rpc_url: String,
+
+    /// Directory the agent will use for caching data
+    #[clap(long, env)]
+    pub data_dir: Option<PathBuf>,
+
+    /// Secret key of the proposer wallet whose balance to check
+    #[clap(long, env)]
+    pub proposer_key: Option<String>,
+    /// Secret key of the validator wallet whose balance to check
+    #[clap(long, env)]
+    pub validator_key: Option<String>,
+
+    /// Path to the kailua host binary the validator will use, instead of the host embedded in
+    /// kailua-cli
+    #[clap(long, env)]
+    pub kailua_host: Option<PathBuf>,
+    /// Address of the `kailua-host serve` instance the validator will submit proving jobs to
+    #[clap(long, env)]
+    pub kailua_host_service: Option<String>,
+    /// Maximum number of seconds the test proof computed in dev mode may take
+    #[clap(long, env, default_value_t = 600)]
+    pub test_proof_timeout_secs: u64,
+
+    /// Maximum number of seconds by which the local clock may deviate from L1 block time
+    #[clap(long, env, default_value_t = 15)]
+    pub max_clock_skew_secs: u64,
+
+    /// Whether to print the report as JSON
+    #[clap(long, env)]
+    pub json: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// The outcome of a single pre-flight check
+#[derive(Clone, Debug, Serialize)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DoctorReport {
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    pub fn check(&mut self, name: &str, status: CheckStatus, detail: impl Into<String>) {
+        self.checks.push(DoctorCheck {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+        });
+    }
+
+    /// Records a check that passes if the result is ok, returning the result's value
+    pub fn expect<T>(&mut self, name: &str, result: anyhow::Result<(T, String)>) -> Option<T> {
+        match result {
+            Ok((value, detail)) => {
+                self.check(name, CheckStatus::Pass, detail);
+                Some(value)
+            }
+            Err(err) => {
+                self.check(name, CheckStatus::Fail, format!("{err:#}"));
+                None
+            }
+        }
+    }
+
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == status)
+            .count()
+    }
+
+    pub fn print(&self, json: bool) -> anyhow::Result<()> {
+        if json {
+            println!("{}", serde_json::to_string_pretty(self)?);
+        } else {
+            for check in &self.checks {
+                let mark = match check.status {
+                    CheckStatus::Pass => "PASS",
+                    CheckStatus::Warn => "WARN",
+                    CheckStatus::Fail => "FAIL",
+                };
+                println!("[{mark}] {}: {}", check.name, check.detail);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the pass or warn status of a client version, along with its description
+fn version_status(client: &str, version: &str) -> (CheckStatus, String) {
+    let incompatible = parse_version(version).and_then(|parsed| {
+        INCOMPATIBLE_VERSIONS
+            .iter()
+            .find(|incompatible| incompatible.client == client && parsed < incompatible.minimum)
+    });
+    match incompatible {
+        Some(incompatible) => {
+            let (major, minor, patch) = incompatible.minimum;
+            (
+                CheckStatus::Warn,
+                format!(
+                    "{version} {}, upgrade to at least v{major}.{minor}.{patch}",
+                    incompatible.reason
+                ),
+            )
+        }
+        None => (CheckStatus::Pass, version.to_string()),
+    }
+}
+
+/// Validates the configuration of an agent and reports what would keep it from operating
+pub async fn doctor(args: DoctorArgs) -> anyhow::Result<()> {
+    let mut report = DoctorReport::default();
+
+    // RPC connectivity and namespaces
+    let eth_rpc_provider = ProviderBuilder::new().on_http(args.eth_rpc_url.as_str().try_into()?);
+    let op_geth_provider = ProviderBuilder::new().on_http(args.op_geth_url.as_str().try_into()?);
+    let op_node_provider =
+        OpNodeProvider::new(ProviderBuilder::new().on_http(args.op_node_url.as_str().try_into()?));
+    let l1_chain_id = report.expect(
+        "eth-rpc connectivity",
+        eth_rpc_provider
+            .get_chain_id()
+            .await
+            .map(|chain_id| (chain_id, format!("chain id {chain_id}")))
+            .context("eth_chainId"),
+    );
+    let l2_chain_id = report.expect(
+        "op-geth connectivity",
+        op_geth_provider
+            .get_chain_id()
+            .await
+            .map(|chain_id| (chain_id, format!("chain id {chain_id}")))
+            .context("eth_chainId"),
+    );
+    for (client, provider) in [
+        ("eth-rpc", &eth_rpc_provider),
+        ("op-geth", &op_geth_provider),
+    ] {
+        match provider.get_client_version().await {
+            Ok(version) => {
+                let (status, detail) = version_status(client, &version);
+                report.check(&format!("{client} version"), status, detail);
+            }
+            Err(err) => report.check(
+                &format!("{client} version"),
+                CheckStatus::Warn,
+                format!("web3_clientVersion: {err}"),
+            ),
+        }
+    }
+    match NodeCapabilities::probe(&args.op_geth_url).await {
+        Ok(capabilities) => match capabilities.preflight_strategy() {
+            PreflightStrategy::None => report.check(
+                "op-geth debug namespace",
+                CheckStatus::Warn,
+                "No preflight method is available, so proving will fetch all state lazily",
+            ),
+            strategy => report.check(
+                "op-geth debug namespace",
+                CheckStatus::Pass,
+                format!("Preflighting with {strategy:?} strategy"),
+            ),
+        },
+        Err(err) => report.check(
+            "op-geth debug namespace",
+            CheckStatus::Fail,
+            format!("{err:#}"),
+        ),
+    }
+    let sync_status = report.expect(
+        "op-node connectivity",
+        op_node_provider.sync_status().await.map(|sync_status| {
+            let detail = format!(
+                "safe l2 head {}",
+                sync_status["safe_l2"]["number"]
+                    .as_u64()
+                    .unwrap_or_default()
+            );
+            (sync_status, detail)
+        }),
+    );
+    let op_node_version = op_node_provider
+        .provider
+        .client()
+        .request_noparams::<String>("optimism_version")
+        .await;
+    match op_node_version {
+        Ok(version) => {
+            let (status, detail) = version_status("op-node", &version);
+            report.check("op-node version", status, detail);
+        }
+        Err(err) => report.check(
+            "op-node version",
+            CheckStatus::Warn,
+            format!("optimism_version: {err}"),
+        ),
+    }
+    let blob_provider = report.expect(
+        "beacon connectivity",
+        BlobProvider::new(&args.beacon_rpc_url)
+            .await
+            .map(|provider| {
+                let detail = format!("Beacon node at {}", provider.url());
+                (provider, detail)
+            }),
+    );
+    if let Some(blob_provider) = &blob_provider {
+        match blob_provider.beacon_client.get("eth/v1/node/version").await {
+            Ok(Some(version)) => report.check(
+                "beacon version",
+                CheckStatus::Pass,
+                version["data"]["version"].as_str().unwrap_or("unknown"),
+            ),
+            Ok(None) | Err(_) => report.check(
+                "beacon version",
+                CheckStatus::Warn,
+                "eth/v1/node/version unavailable",
+            ),
+        }
+    }
+
+    // Contract addresses and game type wiring
+    let config = report.expect(
+        "rollup configuration",
+        fetch_rollup_config(&args.op_node_url, &args.op_geth_url, None)
+            .await
+            .map(|config| {
+                let detail = format!(
+                    "L1 chain {} and L2 chain {}",
+                    config.l1_chain_id, config.l2_chain_id
+                );
+                (config, detail)
+            })
+            .context("fetch_rollup_config"),
+    );
+    let mut game_config = None;
+    if let Some(config) = &config {
+        if let (Some(l1_chain_id), Some(l2_chain_id)) = (l1_chain_id, l2_chain_id) {
+            let consistent = l1_chain_id == config.l1_chain_id && l2_chain_id == config.l2_chain_id;
+            report.check(
+                "chain ids",
+                if consistent {
+                    CheckStatus::Pass
+                } else {
+                    CheckStatus::Fail
+                },
+                format!(
+                    "eth-rpc serves chain {l1_chain_id} and op-geth serves chain {l2_chain_id}"
+                ),
+            );
+        }
+        let local_cfg_hash = config_hash(config).map(B256::from).unwrap_or_default();
+        game_config = check_contracts(
+            &mut report,
+            &eth_rpc_provider,
+            config.l1_system_config_address,
+            local_cfg_hash,
+            config.l2_chain_id,
+        )
+        .await;
+    }
+
+    // Wallet balances
+    if let Some(game_config) = &game_config {
+        if let Some(proposer_key) = &args.proposer_key {
+            check_proposer_wallet(&mut report, &eth_rpc_provider, game_config, proposer_key).await;
+        }
+    }
+    if let Some(validator_key) = &args.validator_key {
+        match LocalSigner::from_str(validator_key) {
+            Ok(signer) => {
+                let address = signer.address();
+                match eth_rpc_provider.get_balance(address).await {
+                    Ok(balance) if balance.is_zero() => report.check(
+                        "validator wallet",
+                        CheckStatus::Fail,
+                        format!("{address} holds no ether to pay for proof submissions"),
+                    ),
+                    Ok(balance) => report.check(
+                        "validator wallet",
+                        CheckStatus::Pass,
+                        format!("{address} holds {balance} wei"),
+                    ),
+                    Err(err) => report.check(
+                        "validator wallet",
+                        CheckStatus::Fail,
+                        format!("eth_getBalance: {err}"),
+                    ),
+                }
+            }
+            Err(err) => report.check("validator wallet", CheckStatus::Fail, format!("{err}")),
+        }
+    }
+
+    // Prover availability
+    if is_dev_mode() {
+        match (&sync_status, &config) {
+            (Some(sync_status), Some(config)) => {
+                let started = Instant::now();
+                let result = test_proof(
+                    &args,
+                    &op_node_provider,
+                    &op_geth_provider,
+                    sync_status,
+                    config.l2_chain_id,
+                )
+                .await;
+                report.expect(
+                    "prover",
+                    result.map(|detail| {
+                        ((), format!("{detail} in {}s", started.elapsed().as_secs()))
+                    }),
+                );
+            }
+            _ => report.check(
+                "prover",
+                CheckStatus::Fail,
+                "Test proof requires op-node connectivity",
+            ),
+        }
+    } else {
+        report.expect(
+            "prover",
+            check_prover(&args).await.map(|detail| ((), detail)),
+        );
+    }
+
+    // Data directory writability
+    match &args.data_dir {
+        Some(data_dir) => {
+            report.expect(
+                "data directory",
+                check_writable(data_dir).map(|_| ((), format!("{data_dir:?} is writable"))),
+            );
+        }
+        None => report.check(
+            "data directory",
+            CheckStatus::Warn,
+            "No data-dir set, so cached data is lost on every restart",
+        ),
+    }
+
+    // Clock skew
+    match eth_rpc_provider
+        .get_block_by_number(BlockNumberOrTag::Latest, BlockTransactionsKind::Hashes)
+        .await
+    {
+        Ok(Some(block)) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as i64;
+            // the latest block may be up to one block time old
+            let skew = now - block.header.timestamp as i64;
+            let max_skew = args.max_clock_skew_secs as i64;
+            report.check(
+                "clock skew",
+                if -max_skew <= skew && skew <= L1_BLOCK_TIME + max_skew {
+                    CheckStatus::Pass
+                } else {
+                    CheckStatus::Warn
+                },
+                format!(
+                    "Local clock is {skew}s ahead of L1 block {}",
+                    block.header.number
+                ),
+            );
+        }
+        Ok(None) => report.check("clock skew", CheckStatus::Warn, "Latest L1 block not found"),
+        Err(err) => report.check("clock skew", CheckStatus::Warn, format!("{err}")),
+    }
+
+    report.print(args.json)?;
+    let (warnings, failures) = (
+        report.count(CheckStatus::Warn),
+        report.count(CheckStatus::Fail),
+    );
+    if failures > 0 {
+        bail!("{failures} checks failed and {warnings} raised warnings.");
+    }
+    info!("All checks passed with {warnings} warnings.");
+    Ok(())
+}
+
+/// Checks that Kailua is installed in the rollup's dispute game factory for this build
+async fn check_contracts<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    report: &mut DoctorReport,
+    eth_rpc_provider: P,
+    system_config_address: Address,
+    local_cfg_hash: B256,
+    l2_chain_id: u64,
+) -> Option<Config> {
+    let system_config = SystemConfig::new(system_config_address, &eth_rpc_provider);
+    let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
+    let dispute_game_factory = IDisputeGameFactory::new(dgf_address, &eth_rpc_provider);
+    let game_implementation_address = dispute_game_factory
+        .gameImpls(KAILUA_GAME_TYPE)
+        .stall()
+        .await
+        .impl_;
+    let has_code = eth_rpc_provider
+        .get_code_at(game_implementation_address)
+        .await
+        .is_ok_and(|code| !code.is_empty());
+    if !has_code {
+        report.check(
+            "game implementation",
+            CheckStatus::Fail,
+            format!("DisputeGameFactory({dgf_address}) has no implementation of game type {KAILUA_GAME_TYPE}"),
+        );
+        return None;
+    }
+    let game_implementation = KailuaGame::new(game_implementation_address, &eth_rpc_provider);
+    let game_config = match Config::load(&game_implementation).await {
+        Ok(game_config) => game_config,
+        Err(err) => {
+            report.check("game implementation", CheckStatus::Fail, format!("{err:#}"));
+            return None;
+        }
+    };
+    let game_type = game_implementation.gameType().stall().await.gameType_;
+    report.check(
+        "game implementation",
+        if game_type == KAILUA_GAME_TYPE && game_config.factory == dgf_address {
+            CheckStatus::Pass
+        } else {
+            CheckStatus::Fail
+        },
+        format!(
+            "KailuaGame({game_implementation_address}) reports game type {game_type} in factory {}",
+            game_config.factory
+        ),
+    );
+    report.check(
+        "rollup configuration hash",
+        if game_config.cfg_hash == local_cfg_hash {
+            CheckStatus::Pass
+        } else {
+            CheckStatus::Fail
+        },
+        format!(
+            "KailuaGame expects {} and the nodes serve {local_cfg_hash}",
+            game_config.cfg_hash
+        ),
+    );
+    report.check(
+        "proof journal format",
+        if game_config.journal_version == PROOF_JOURNAL_VERSION
+            && game_config.l2_chain_id == l2_chain_id
+        {
+            CheckStatus::Pass
+        } else {
+            CheckStatus::Fail
+        },
+        format!(
+            "KailuaGame expects journal version {} for chain {}",
+            game_config.journal_version, game_config.l2_chain_id
+        ),
+    );
+    let (status, detail) = if game_config.image_id == local_image_id() {
+        (
+            CheckStatus::Pass,
+            format!("Image id {} matches this build", game_config.image_id),
+        )
+    } else {
+        match find_release(game_config.image_id) {
+            Ok(Some(release)) => (
+                CheckStatus::Warn,
+                format!(
+                    "Image id {} belongs to release {} instead of this build",
+                    game_config.image_id, release.version
+                ),
+            ),
+            Ok(None) => (
+                CheckStatus::Fail,
+                format!(
+                    "Image id {} does not belong to any known release",
+                    game_config.image_id
+                ),
+            ),
+            Err(err) => (CheckStatus::Warn, format!("{err:#}")),
+        }
+    };
+    report.check("image id", status, detail);
+    Some(game_config)
+}
+
+/// Checks that the proposer can afford the participation bond
+async fn check_proposer_wallet<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    report: &mut DoctorReport,
+    eth_rpc_provider: P,
+    game_config: &Config,
+    proposer_key: &str,
+) {
+    let address = match LocalSigner::from_str(proposer_key) {
+        Ok(signer) => signer.address(),
+        Err(err) => {
+            report.check("proposer wallet", CheckStatus::Fail, format!("{err}"));
+            return;
+        }
+    };
+    let treasury = KailuaTreasury::new(game_config.treasury, &eth_rpc_provider);
+    let participation_bond = treasury.participationBond().stall().await._0;
+    let paid_bond = treasury.paidBonds(address).stall().await._0;
+    let balance = match eth_rpc_provider.get_balance(address).await {
+        Ok(balance) => balance,
+        Err(err) => {
+            report.check(
+                "proposer wallet",
+                CheckStatus::Fail,
+                format!("eth_getBalance: {err}"),
+            );
+            return;
+        }
+    };
+    let owed_bond = participation_bond.saturating_sub(paid_bond);
+    report.check(
+        "proposer wallet",
+        if balance > owed_bond {
+            CheckStatus::Pass
+        } else {
+            CheckStatus::Fail
+        },
+        format!(
+            "{address} holds {balance} wei and owes {owed_bond} wei of the {participation_bond} wei participation bond"
+        ),
+    );
+}
+
+/// Checks that the configured prover can be reached without proving anything
+async fn check_prover(args: &DoctorArgs) -> anyhow::Result<String> {
+    if let Some(url) = &args.kailua_host_service {
+        let url = format!("{}/health", url.trim_end_matches('/'));
+        reqwest::get(&url)
+            .await
+            .and_then(|response| response.error_for_status())
+            .context(format!("GET {url}"))?;
+        return Ok(format!("Proving service at {url} is healthy"));
+    }
+    let status = kailua_host_command(args.kailua_host.as_deref())?
+        .arg("--help")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .status()
+        .await
+        .context("Invoking kailua-host")?;
+    if !status.success() {
+        bail!("kailua-host exited with {status}");
+    }
+    Ok(String::from("kailua-host runs"))
+}
+
+/// Proves the latest safe L2 block with fake receipts to exercise the entire proving pipeline
+async fn test_proof<T: Transport + Clone, P: Provider<T>>(
+    args: &DoctorArgs,
+    op_node_provider: &OpNodeProvider,
+    op_geth_provider: P,
+    sync_status: &serde_json::Value,
+    l2_chain_id: u64,
+) -> anyhow::Result<String> {
+    let claimed_l2_block_number = sync_status["safe_l2"]["number"]
+        .as_u64()
+        .context("Missing safe l2 head")?;
+    let l1_head = B256::from_str(
+        sync_status["current_l1"]["hash"]
+            .as_str()
+            .context("Missing current l1 head")?,
+    )?;
+    let agreed_l2_block_number = claimed_l2_block_number.saturating_sub(1);
+    let agreed_l2_head_hash = op_geth_provider
+        .get_block_by_number(
+            BlockNumberOrTag::Number(agreed_l2_block_number),
+            BlockTransactionsKind::Hashes,
+        )
+        .await?
+        .context("Agreed l2 block not found")?
+        .header
+        .hash;
+    let request = ProofRequest {
+        index: 0,
+        precondition_validation_data: None,
+        l1_head,
+        agreed_l2_head_hash,
+        agreed_l2_output_root: op_node_provider
+            .output_at_block(agreed_l2_block_number)
+            .await?,
+        agreed_l2_block_number,
+        claimed_l2_block_number,
+        claimed_l2_output_root: op_node_provider
+            .output_at_block(claimed_l2_block_number)
+            .await?,
+    };
+    // keep the test proof out of the agent's working directory
+    let tmp_dir = tempfile::tempdir()?;
+    let proof_file = tmp_dir.path().join(request.proof_file_name());
+    let kailua_host = args
+        .kailua_host
+        .as_deref()
+        .map(std::fs::canonicalize)
+        .transpose()?;
+    let mut command = kailua_host_command(kailua_host.as_deref())?;
+    command
+        .env("RISC0_DEV_MODE", "1")
+        .current_dir(tmp_dir.path())
+        .args(request.kailua_host_args(
+            l2_chain_id,
+            &args.eth_rpc_url,
+            &args.beacon_rpc_url,
+            &args.op_geth_url,
+            &args.op_node_url,
+            tmp_dir.path(),
+        )?);
+    let deadline = Duration::from_secs(args.test_proof_timeout_secs);
+    match &args.kailua_host_service {
+        Some(url) => {
+            let outcome = HostService::new(url)
+                .prove(
+                    &command,
+                    &path_arg(&proof_file)?,
+                    &CancelledProofs::default(),
+                    request.index,
+                    Some(Instant::now() + deadline),
+                )
+                .await;
+            match outcome {
+                Some(Ok(())) => {}
+                Some(Err(err)) => bail!("Test proof failed: {err}"),
+                None => bail!("Test proof did not complete within {}s", deadline.as_secs()),
+            }
+        }
+        None => {
+            let status = timeout(
+                deadline,
+                command
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .kill_on_drop(true)
+                    .status(),
+            )
+            .await
+            .context(format!(
+                "Test proof did not complete within {}s",
+                deadline.as_secs()
+            ))?
+            .context("Invoking kailua-host")?;
+            if !status.success() {
+                bail!("Test proof failed with {status}");
+            }
+        }
+    }
+    if !proof_file.exists() {
+        bail!("Test proof file {proof_file:?} is missing");
+    }
+    Ok(format!(
+        "Proved l2 block {claimed_l2_block_number} in dev mode"
+    ))
+}
+
+/// Checks that files can be created, read back and removed in the directory
+fn check_writable(data_dir: &Path) -> anyhow::Result<()> {
+    std::fs::create_dir_all(data_dir).context(format!("Failed to create {data_dir:?}"))?;
+    let probe = data_dir.join(".doctor");
+    let contents = String::from("kailua-cli doctor");
+    std::fs::write(&probe, &contents).context(format!("Failed to write to {data_dir:?}"))?;
+    let read_back = std::fs::read_to_string(&probe);
+    std::fs::remove_file(&probe).context(format!("Failed to remove {probe:?}"))?;
+    if read_back? != contents {
+        bail!("Data read back from {probe:?} differs from what was written");
+    }
+    Ok(())
+}
@@ -0,0 +1,116 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::proposal::Proposal;
+use alloy::primitives::Address;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{error, info, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct EmergencyArgs {
+    /// Path to the operator-controlled emergency override file, re-read before every scan
+    /// (defaults to emergency.json in the data-dir)
+    #[clap(long, env)]
+    pub emergency_file: Option<PathBuf>,
+}
+
+/// The emergency measures currently requested by the operator
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmergencyOverrides {
+    /// Whether to withhold computed proofs instead of submitting them on-chain
+    #[serde(default)]
+    pub pause_submissions: bool,
+    /// Whether to stop requesting new proofs
+    #[serde(default)]
+    pub pause_proving: bool,
+    /// Game contracts the validator must not act on
+    #[serde(default)]
+    pub blacklist: HashSet<Address>,
+}
+
+/// Tracks the emergency override file and reloads it whenever it changes
+#[derive(Clone, Debug)]
+pub struct EmergencyBrake {
+    pub path: PathBuf,
+    pub modified: Option<SystemTime>,
+    pub overrides: EmergencyOverrides,
+}
+
+impl EmergencyBrake {
+    pub fn new(args: &EmergencyArgs, data_dir: &Path) -> Self {
+        let path = args
+            .emergency_file
+            .clone()
+            .unwrap_or_else(|| data_dir.join("emergency.json"));
+        info!("Watching {path:?} for emergency overrides.");
+        let mut brake = Self {
+            path,
+            modified: None,
+            overrides: Default::default(),
+        };
+        brake.refresh();
+        brake
+    }
+
+    /// Reloads the overrides if the file was created, modified or removed since the last check.
+    /// Unreadable files leave the current overrides in place.
+    pub fn refresh(&mut self) {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified == self.modified {
+            return;
+        }
+        let overrides = match modified {
+            Some(_) => match self.load() {
+                Ok(overrides) => overrides,
+                Err(err) => {
+                    error!("Ignoring malformed emergency overrides: {err:?}");
+                    return;
+                }
+            },
+            None => EmergencyOverrides::default(),
+        };
+        self.modified = modified;
+        if overrides == self.overrides {
+            return;
+        }
+        if overrides.pause_submissions != self.overrides.pause_submissions {
+            warn!("Proof submissions paused: {}.", overrides.pause_submissions);
+        }
+        if overrides.pause_proving != self.overrides.pause_proving {
+            warn!("Proving paused: {}.", overrides.pause_proving);
+        }
+        if overrides.blacklist != self.overrides.blacklist {
+            warn!("Blacklisted game contracts: {:?}.", overrides.blacklist);
+        }
+        self.overrides = overrides;
+    }
+
+    fn load(&self) -> anyhow::Result<EmergencyOverrides> {
+        let data = std::fs::read(&self.path).context(format!("Failed to read {:?}", self.path))?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Returns whether any of the given proposals is blacklisted
+    pub fn is_blacklisted<'a>(&self, proposals: impl IntoIterator<Item = &'a Proposal>) -> bool {
+        proposals
+            .into_iter()
+            .any(|proposal| self.overrides.blacklist.contains(&proposal.contract))
+    }
+}
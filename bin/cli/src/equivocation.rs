@@ -0,0 +1,183 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::proposal::Proposal;
+use crate::stall::Stall;
+use crate::KAILUA_GAME_TYPE;
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::ProviderBuilder;
+use anyhow::Context;
+use kailua_contracts::{IDisputeGameFactory::gameAtIndexReturn, *};
+use kailua_host::fetch_rollup_config;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use tracing::info;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct EquivocationsArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the OP-NODE endpoint to use
+    #[clap(long, env)]
+    pub op_node_url: String,
+    /// Address of the OP-GETH endpoint to use (eth and debug namespace required).
+    #[clap(long, env)]
+    pub op_geth_url: String,
+    /// Address of the ethereum rpc endpoint to use (eth namespace required)
+    #[clap(long, env)]
+    pub eth_rpc_url: String,
+
+    /// Factory index to start scanning from
+    #[clap(long, env, default_value_t = 0)]
+    pub start_index: u64,
+    /// Factory index to stop scanning at (defaults to the latest game)
+    #[clap(long, env)]
+    pub end_index: Option<u64>,
+
+    /// Whether to print the equivocations as JSON
+    #[clap(long, env)]
+    pub json: bool,
+}
+
+/// Evidence of two conflicting proposals made by the same proposer for the same L2 block
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Equivocation {
+    pub proposer: Address,
+    pub output_block_number: u64,
+    pub first_index: u64,
+    pub first_contract: Address,
+    pub first_output_root: B256,
+    pub second_index: u64,
+    pub second_contract: Address,
+    pub second_output_root: B256,
+}
+
+impl Equivocation {
+    /// Returns the evidence if the two proposals conflict
+    pub fn between(first: &Proposal, second: &Proposal) -> Option<Self> {
+        if first.proposer != second.proposer
+            || first.output_block_number != second.output_block_number
+            || (first.output_root == second.output_root
+                && first.io_field_elements == second.io_field_elements)
+        {
+            return None;
+        }
+        Some(Self {
+            proposer: first.proposer,
+            output_block_number: first.output_block_number,
+            first_index: first.index,
+            first_contract: first.contract,
+            first_output_root: first.output_root,
+            second_index: second.index,
+            second_contract: second.contract,
+            second_output_root: second.output_root,
+        })
+    }
+}
+
+impl Display for Equivocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Proposer {} equivocated at block {}: proposal {} ({}) claims {} while proposal {} ({}) claims {}.",
+            self.proposer,
+            self.output_block_number,
+            self.first_index,
+            self.first_contract,
+            self.first_output_root,
+            self.second_index,
+            self.second_contract,
+            self.second_output_root
+        )
+    }
+}
+
+pub async fn equivocations(args: EquivocationsArgs) -> anyhow::Result<()> {
+    let eth_rpc_provider = ProviderBuilder::new().on_http(args.eth_rpc_url.as_str().try_into()?);
+
+    info!("Fetching rollup configuration from rpc endpoints.");
+    let config = fetch_rollup_config(&args.op_node_url, &args.op_geth_url, None)
+        .await
+        .context("fetch_rollup_config")?;
+    let system_config = SystemConfig::new(config.l1_system_config_address, &eth_rpc_provider);
+    let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
+    let dispute_game_factory = IDisputeGameFactory::new(dgf_address, &eth_rpc_provider);
+    info!("DisputeGameFactory({dgf_address:?})");
+
+    let game_count: u64 = dispute_game_factory
+        .gameCount()
+        .stall()
+        .await
+        .gameCount_
+        .to();
+    let end_index = args.end_index.unwrap_or(game_count).min(game_count);
+    info!(
+        "Scanning factory indices {} to {end_index}.",
+        args.start_index
+    );
+
+    // Only root claims are compared, as intermediate commitments require blob data
+    let mut first_claims: HashMap<(Address, u64), (u64, Address, B256)> = HashMap::new();
+    let mut equivocations = Vec::new();
+    for index in args.start_index..end_index {
+        let gameAtIndexReturn {
+            gameType_: game_type,
+            proxy_: game_address,
+            ..
+        } = dispute_game_factory
+            .gameAtIndex(U256::from(index))
+            .stall()
+            .await;
+        if game_type != KAILUA_GAME_TYPE {
+            continue;
+        }
+        let tournament = KailuaTournament::new(game_address, &eth_rpc_provider);
+        if tournament.parentGame().stall().await.parentGame_ == game_address {
+            // Treasury instances anchor the proposal tree and are not proposals
+            continue;
+        }
+        let proposer = tournament.proposer().stall().await.proposer_;
+        let output_block_number: u64 = tournament.l2BlockNumber().stall().await.l2BlockNumber_.to();
+        let output_root = tournament.rootClaim().stall().await.rootClaim_;
+
+        let (first_index, first_contract, first_output_root) = *first_claims
+            .entry((proposer, output_block_number))
+            .or_insert((index, game_address, output_root));
+        if first_output_root != output_root {
+            equivocations.push(Equivocation {
+                proposer,
+                output_block_number,
+                first_index,
+                first_contract,
+                first_output_root,
+                second_index: index,
+                second_contract: game_address,
+                second_output_root: output_root,
+            });
+        }
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&equivocations)?);
+    } else if equivocations.is_empty() {
+        println!("No equivocations found.");
+    } else {
+        for equivocation in &equivocations {
+            println!("{equivocation}");
+        }
+    }
+    Ok(())
+}
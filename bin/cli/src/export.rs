@@ -0,0 +1,447 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::stall::Stall;
+use crate::KAILUA_GAME_TYPE;
+use alloy::primitives::{Address, TxHash};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::Filter;
+use alloy::sol_types::SolEvent;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use kailua_contracts::*;
+use kailua_host::fetch_rollup_config;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One JSON object per line
+    #[default]
+    Jsonl,
+    /// Comma-separated values with a header row
+    Csv,
+    /// Apache Parquet (requires the `parquet` feature)
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ExportArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the OP-NODE endpoint to use
+    #[clap(long, env)]
+    pub op_node_url: String,
+    /// Address of the OP-GETH endpoint to use (eth and debug namespace required).
+    #[clap(long, env)]
+    pub op_geth_url: String,
+    /// Address of the ethereum rpc endpoint to use (eth namespace required)
+    #[clap(long, env)]
+    pub eth_rpc_url: String,
+
+    /// Format to export the dispute history in
+    #[clap(long, env, value_enum, default_value_t = ExportFormat::Jsonl)]
+    pub format: ExportFormat,
+    /// L1 block to start exporting from
+    #[clap(long, env, default_value_t = 0)]
+    pub from_block: u64,
+    /// L1 block to stop exporting at (defaults to the latest block)
+    #[clap(long, env)]
+    pub to_block: Option<u64>,
+    /// Maximum number of L1 blocks to query logs for in a single request
+    #[clap(long, env, default_value_t = 10_000)]
+    pub log_chunk_size: u64,
+    /// Directory to write the exported tables to
+    #[clap(long, env)]
+    pub output: PathBuf,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    U64,
+    Bool,
+    Str,
+}
+
+#[derive(Clone, Debug)]
+pub enum ExportValue {
+    U64(u64),
+    Bool(bool),
+    Str(String),
+}
+
+impl ExportValue {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            ExportValue::U64(v) => serde_json::Value::from(*v),
+            ExportValue::Bool(v) => serde_json::Value::from(*v),
+            ExportValue::Str(v) => serde_json::Value::from(v.as_str()),
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        match self {
+            ExportValue::U64(v) => v.to_string(),
+            ExportValue::Bool(v) => v.to_string(),
+            ExportValue::Str(v) if v.contains([',', '"', '\n']) => {
+                format!("\"{}\"", v.replace('"', "\"\""))
+            }
+            ExportValue::Str(v) => v.clone(),
+        }
+    }
+}
+
+/// A table of exported records with a fixed schema
+#[derive(Clone, Debug)]
+pub struct ExportTable {
+    pub name: &'static str,
+    pub columns: Vec<(&'static str, ColumnType)>,
+    pub rows: Vec<Vec<ExportValue>>,
+}
+
+impl ExportTable {
+    pub fn save(&self, dir: &Path, format: ExportFormat) -> anyhow::Result<PathBuf> {
+        let path = dir.join(format!("{}.{}", self.name, format.extension()));
+        match format {
+            ExportFormat::Jsonl => {
+                let mut file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+                for row in &self.rows {
+                    let object = self
+                        .columns
+                        .iter()
+                        .zip(row)
+                        .map(|((name, _), value)| (name.to_string(), value.to_json()))
+                        .collect::<serde_json::Map<_, _>>();
+                    writeln!(file, "{}", serde_json::Value::Object(object))?;
+                }
+                file.flush()?;
+            }
+            ExportFormat::Csv => {
+                let mut file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+                let header = self.columns.iter().map(|(name, _)| *name);
+                writeln!(file, "{}", header.collect::<Vec<_>>().join(","))?;
+                for row in &self.rows {
+                    let values = row.iter().map(ExportValue::to_csv);
+                    writeln!(file, "{}", values.collect::<Vec<_>>().join(","))?;
+                }
+                file.flush()?;
+            }
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => self.save_parquet(&path)?,
+            #[cfg(not(feature = "parquet"))]
+            ExportFormat::Parquet => bail!("Parquet export requires the parquet feature."),
+        }
+        Ok(path)
+    }
+
+    #[cfg(feature = "parquet")]
+    fn save_parquet(&self, path: &Path) -> anyhow::Result<()> {
+        use arrow::array::{ArrayRef, BooleanArray, StringArray, UInt64Array};
+        use arrow::datatypes::{DataType, Field, Schema};
+        use arrow::record_batch::RecordBatch;
+        use parquet::arrow::ArrowWriter;
+        use std::sync::Arc;
+
+        let mut fields = Vec::with_capacity(self.columns.len());
+        let mut arrays: Vec<ArrayRef> = Vec::with_capacity(self.columns.len());
+        for (i, (name, column_type)) in self.columns.iter().enumerate() {
+            let values = self.rows.iter().map(|row| &row[i]);
+            let (data_type, array): (DataType, ArrayRef) = match column_type {
+                ColumnType::U64 => (
+                    DataType::UInt64,
+                    Arc::new(UInt64Array::from_iter(values.map(|v| match v {
+                        ExportValue::U64(v) => Some(*v),
+                        _ => None,
+                    }))),
+                ),
+                ColumnType::Bool => (
+                    DataType::Boolean,
+                    Arc::new(BooleanArray::from_iter(values.map(|v| match v {
+                        ExportValue::Bool(v) => Some(*v),
+                        _ => None,
+                    }))),
+                ),
+                ColumnType::Str => (
+                    DataType::Utf8,
+                    Arc::new(StringArray::from_iter(values.map(|v| match v {
+                        ExportValue::Str(v) => Some(v.clone()),
+                        _ => None,
+                    }))),
+                ),
+            };
+            fields.push(Field::new(*name, data_type, true));
+            arrays.push(array);
+        }
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?;
+        let mut writer = ArrowWriter::try_new(std::fs::File::create(path)?, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+}
+
+/// The gas spent by a transaction and its cost in wei
+async fn transaction_cost<T: Transport + Clone, P: Provider<T>>(
+    provider: &P,
+    tx_hash: TxHash,
+) -> anyhow::Result<(Address, u64, u128)> {
+    let receipt = provider
+        .get_transaction_receipt(tx_hash)
+        .await
+        .context("get_transaction_receipt")?
+        .context(format!("Missing receipt for {tx_hash}"))?;
+    Ok((
+        receipt.from,
+        receipt.gas_used,
+        receipt.gas_used as u128 * receipt.effective_gas_price,
+    ))
+}
+
+/// Queries the logs of the given event in chunks of blocks
+async fn query_logs<T: Transport + Clone, P: Provider<T>>(
+    provider: &P,
+    filter: Filter,
+    from_block: u64,
+    to_block: u64,
+    chunk_size: u64,
+) -> anyhow::Result<Vec<alloy::rpc::types::Log>> {
+    let mut logs = Vec::new();
+    let mut start = from_block;
+    while start <= to_block {
+        let end = to_block.min(start + chunk_size.max(1) - 1);
+        let chunk_filter = filter.clone().from_block(start).to_block(end);
+        logs.extend(provider.get_logs(&chunk_filter).await.context("get_logs")?);
+        start = end + 1;
+    }
+    Ok(logs)
+}
+
+pub async fn export(args: ExportArgs) -> anyhow::Result<()> {
+    #[cfg(not(feature = "parquet"))]
+    if args.format == ExportFormat::Parquet {
+        bail!("Parquet export requires the parquet feature.");
+    }
+    let eth_rpc_provider = ProviderBuilder::new().on_http(args.eth_rpc_url.as_str().try_into()?);
+
+    info!("Fetching rollup configuration from rpc endpoints.");
+    let config = fetch_rollup_config(&args.op_node_url, &args.op_geth_url, None)
+        .await
+        .context("fetch_rollup_config")?;
+    let system_config = SystemConfig::new(config.l1_system_config_address, &eth_rpc_provider);
+    let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
+    info!("DisputeGameFactory({dgf_address:?})");
+
+    let to_block = match args.to_block {
+        Some(to_block) => to_block,
+        None => eth_rpc_provider
+            .get_block_number()
+            .await
+            .context("get_block_number")?,
+    };
+    if to_block < args.from_block {
+        bail!("Block range {}..={to_block} is empty.", args.from_block);
+    }
+    info!(
+        "Exporting dispute history of L1 blocks {} to {to_block}.",
+        args.from_block
+    );
+
+    // Proposals
+    let creation_logs = query_logs(
+        &eth_rpc_provider,
+        Filter::new()
+            .address(dgf_address)
+            .event_signature(IDisputeGameFactory::DisputeGameCreated::SIGNATURE_HASH),
+        args.from_block,
+        to_block,
+        args.log_chunk_size,
+    )
+    .await?;
+    let mut proposals = ExportTable {
+        name: "proposals",
+        columns: vec![
+            ("contract", ColumnType::Str),
+            ("parent", ColumnType::Str),
+            ("proposer", ColumnType::Str),
+            ("is_treasury", ColumnType::Bool),
+            ("l2_block_number", ColumnType::U64),
+            ("output_root", ColumnType::Str),
+            ("l1_head", ColumnType::Str),
+            ("created_block", ColumnType::U64),
+            ("created_at", ColumnType::U64),
+            ("creation_tx", ColumnType::Str),
+            ("creation_gas_used", ColumnType::U64),
+            ("creation_cost_wei", ColumnType::Str),
+            ("sibling_count", ColumnType::U64),
+            ("status", ColumnType::U64),
+            ("resolved_at", ColumnType::U64),
+            ("resolution_secs", ColumnType::U64),
+        ],
+        rows: vec![],
+    };
+    let mut tournaments = HashSet::new();
+    let mut parents = Vec::new();
+    for log in creation_logs {
+        let created = log.log_decode::<IDisputeGameFactory::DisputeGameCreated>()?;
+        let event = &created.inner.data;
+        if event.gameType != KAILUA_GAME_TYPE {
+            continue;
+        }
+        let tournament = KailuaTournament::new(event.disputeProxy, &eth_rpc_provider);
+        let parent = tournament.parentGame().stall().await.parentGame_;
+        let proposer = tournament.proposer().stall().await.proposer_;
+        let l2_block_number: u64 = tournament.l2BlockNumber().stall().await.l2BlockNumber_.to();
+        let l1_head = tournament.l1Head().stall().await.l1Head_;
+        let created_at = tournament.createdAt().stall().await._0;
+        let resolved_at = tournament.resolvedAt().stall().await._0;
+        let status = tournament.status().stall().await._0;
+        let tx_hash = log.transaction_hash.unwrap_or_default();
+        let (_, gas_used, cost) = transaction_cost(&eth_rpc_provider, tx_hash).await?;
+        info!("Exporting proposal {}.", event.disputeProxy);
+        tournaments.insert(event.disputeProxy);
+        parents.push(parent);
+        proposals.rows.push(vec![
+            ExportValue::Str(event.disputeProxy.to_string()),
+            ExportValue::Str(parent.to_string()),
+            ExportValue::Str(proposer.to_string()),
+            ExportValue::Bool(parent == event.disputeProxy),
+            ExportValue::U64(l2_block_number),
+            ExportValue::Str(event.rootClaim.to_string()),
+            ExportValue::Str(l1_head.to_string()),
+            ExportValue::U64(log.block_number.unwrap_or_default()),
+            ExportValue::U64(created_at),
+            ExportValue::Str(tx_hash.to_string()),
+            ExportValue::U64(gas_used),
+            ExportValue::Str(cost.to_string()),
+            ExportValue::U64(0),
+            ExportValue::U64(status as u64),
+            ExportValue::U64(resolved_at),
+            ExportValue::U64(resolved_at.saturating_sub(created_at)),
+        ]);
+    }
+    // Proposals sharing a parent challenge each other
+    let mut sibling_counts: HashMap<Address, u64> = HashMap::new();
+    for parent in &parents {
+        *sibling_counts.entry(*parent).or_default() += 1;
+    }
+    for (row, parent) in proposals.rows.iter_mut().zip(&parents) {
+        if let ExportValue::Bool(false) = row[3] {
+            row[12] = ExportValue::U64(sibling_counts[parent] - 1);
+        }
+    }
+
+    // Proofs and resolutions
+    let mut proofs = ExportTable {
+        name: "proofs",
+        columns: vec![
+            ("tournament", ColumnType::Str),
+            ("contender_index", ColumnType::U64),
+            ("opponent_index", ColumnType::U64),
+            ("proof_status", ColumnType::U64),
+            ("block", ColumnType::U64),
+            ("tx", ColumnType::Str),
+            ("prover", ColumnType::Str),
+            ("gas_used", ColumnType::U64),
+            ("cost_wei", ColumnType::Str),
+        ],
+        rows: vec![],
+    };
+    let proven_logs = query_logs(
+        &eth_rpc_provider,
+        Filter::new().event_signature(KailuaTournament::Proven::SIGNATURE_HASH),
+        args.from_block,
+        to_block,
+        args.log_chunk_size,
+    )
+    .await?;
+    for log in proven_logs {
+        if !tournaments.contains(&log.address()) {
+            continue;
+        }
+        let proven = log.log_decode::<KailuaTournament::Proven>()?;
+        let event = &proven.inner.data;
+        let tx_hash = log.transaction_hash.unwrap_or_default();
+        let (prover, gas_used, cost) = transaction_cost(&eth_rpc_provider, tx_hash).await?;
+        proofs.rows.push(vec![
+            ExportValue::Str(log.address().to_string()),
+            ExportValue::U64(event.u),
+            ExportValue::U64(event.v),
+            ExportValue::U64(event.status as u64),
+            ExportValue::U64(log.block_number.unwrap_or_default()),
+            ExportValue::Str(tx_hash.to_string()),
+            ExportValue::Str(prover.to_string()),
+            ExportValue::U64(gas_used),
+            ExportValue::Str(cost.to_string()),
+        ]);
+    }
+
+    let mut resolutions = ExportTable {
+        name: "resolutions",
+        columns: vec![
+            ("contract", ColumnType::Str),
+            ("status", ColumnType::U64),
+            ("block", ColumnType::U64),
+            ("tx", ColumnType::Str),
+            ("resolver", ColumnType::Str),
+            ("gas_used", ColumnType::U64),
+            ("cost_wei", ColumnType::Str),
+        ],
+        rows: vec![],
+    };
+    let resolved_logs = query_logs(
+        &eth_rpc_provider,
+        Filter::new().event_signature(KailuaGame::Resolved::SIGNATURE_HASH),
+        args.from_block,
+        to_block,
+        args.log_chunk_size,
+    )
+    .await?;
+    for log in resolved_logs {
+        if !tournaments.contains(&log.address()) {
+            continue;
+        }
+        let resolved = log.log_decode::<KailuaGame::Resolved>()?;
+        let tx_hash = log.transaction_hash.unwrap_or_default();
+        let (resolver, gas_used, cost) = transaction_cost(&eth_rpc_provider, tx_hash).await?;
+        resolutions.rows.push(vec![
+            ExportValue::Str(log.address().to_string()),
+            ExportValue::U64(resolved.inner.data.status as u64),
+            ExportValue::U64(log.block_number.unwrap_or_default()),
+            ExportValue::Str(tx_hash.to_string()),
+            ExportValue::Str(resolver.to_string()),
+            ExportValue::U64(gas_used),
+            ExportValue::Str(cost.to_string()),
+        ]);
+    }
+
+    std::fs::create_dir_all(&args.output).context("Failed to create output directory")?;
+    for table in [proposals, proofs, resolutions] {
+        let path = table.save(&args.output, args.format)?;
+        println!("Exported {} {} to {path:?}", table.rows.len(), table.name);
+    }
+    Ok(())
+}
@@ -0,0 +1,276 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::stall::Stall;
+use crate::KAILUA_GAME_TYPE;
+use alloy::eips::BlockNumberOrTag;
+use alloy::network::primitives::BlockTransactionsKind;
+use alloy::primitives::{Address, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use anyhow::Context;
+use kailua_contracts::{IDisputeGameFactory::gameAtIndexReturn, *};
+use kailua_host::fetch_rollup_config;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use tracing::info;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ExposureArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the OP-NODE endpoint to use
+    #[clap(long, env)]
+    pub op_node_url: String,
+    /// Address of the OP-GETH endpoint to use (eth and debug namespace required).
+    #[clap(long, env)]
+    pub op_geth_url: String,
+    /// Address of the ethereum rpc endpoint to use (eth namespace required)
+    #[clap(long, env)]
+    pub eth_rpc_url: String,
+
+    /// Address of the proposer to report the bond exposure of
+    #[clap(long, env)]
+    pub proposer: Address,
+    /// Factory index to start scanning from
+    #[clap(long, env, default_value_t = 0)]
+    pub start_index: u64,
+    /// Factory index to stop scanning at (defaults to the latest game)
+    #[clap(long, env)]
+    pub end_index: Option<u64>,
+
+    /// Whether to print the report as JSON
+    #[clap(long, env)]
+    pub json: bool,
+}
+
+/// The bond a proposer has paid into a single treasury instance
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TreasuryExposure {
+    pub treasury: Address,
+    pub participation_bond: U256,
+    pub paid_bond: U256,
+    /// The factory index of the proposal whose elimination forfeited the bond, if any
+    pub eliminated_at: Option<u64>,
+}
+
+/// A proposal that can still be contested, keeping its proposer's bond at risk
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingProposal {
+    pub index: u64,
+    pub contract: Address,
+    pub treasury: Address,
+    pub output_block_number: u64,
+    pub created_at: u64,
+    /// The time after which the proposal can no longer be contested
+    pub challenge_window_end: u64,
+}
+
+/// A bond forfeited to the prover of an incorrect proposal
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LossEvent {
+    pub treasury: Address,
+    pub index: u64,
+    pub contract: Address,
+    pub amount: U256,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExposureReport {
+    pub proposer: Address,
+    /// The L1 time as of which the report was made
+    pub timestamp: u64,
+    pub treasuries: Vec<TreasuryExposure>,
+    pub pending: Vec<PendingProposal>,
+    pub losses: Vec<LossEvent>,
+    /// The bonds paid into treasuries that did not eliminate the proposer
+    pub total_locked: U256,
+    /// The bonds locked in treasuries with proposals still in their challenge window
+    pub total_at_risk: U256,
+    pub total_lost: U256,
+    /// The time at which the challenge windows of all pending proposals will have ended
+    pub at_risk_until: Option<u64>,
+}
+
+impl ExposureReport {
+    pub fn print(&self) {
+        println!("Bond exposure of proposer {}:", self.proposer);
+        println!("  Locked:  {} wei", self.total_locked);
+        println!("  At risk: {} wei", self.total_at_risk);
+        println!("  Lost:    {} wei", self.total_lost);
+        for treasury in &self.treasuries {
+            let status = treasury
+                .eliminated_at
+                .map_or(String::from("active"), |index| {
+                    format!("eliminated at proposal {index}")
+                });
+            println!(
+                "Treasury {}: {} wei paid ({} wei required), {status}.",
+                treasury.treasury, treasury.paid_bond, treasury.participation_bond
+            );
+        }
+        for proposal in &self.pending {
+            println!(
+                "Proposal {} ({}) for block {} can be contested for another {}s.",
+                proposal.index,
+                proposal.contract,
+                proposal.output_block_number,
+                proposal.challenge_window_end.saturating_sub(self.timestamp)
+            );
+        }
+        if let Some(at_risk_until) = self.at_risk_until {
+            println!("All pending proposals leave their challenge window at {at_risk_until}.");
+        }
+        for loss in &self.losses {
+            println!(
+                "Lost {} wei of bond in treasury {} to the elimination of proposal {} ({}).",
+                loss.amount, loss.treasury, loss.index, loss.contract
+            );
+        }
+    }
+}
+
+pub async fn exposure(args: ExposureArgs) -> anyhow::Result<()> {
+    let eth_rpc_provider = ProviderBuilder::new().on_http(args.eth_rpc_url.as_str().try_into()?);
+
+    info!("Fetching rollup configuration from rpc endpoints.");
+    let config = fetch_rollup_config(&args.op_node_url, &args.op_geth_url, None)
+        .await
+        .context("fetch_rollup_config")?;
+    let system_config = SystemConfig::new(config.l1_system_config_address, &eth_rpc_provider);
+    let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
+    let dispute_game_factory = IDisputeGameFactory::new(dgf_address, &eth_rpc_provider);
+    info!("DisputeGameFactory({dgf_address:?})");
+
+    let timestamp = eth_rpc_provider
+        .get_block_by_number(BlockNumberOrTag::Latest, BlockTransactionsKind::Hashes)
+        .await
+        .context("get_block_by_number")?
+        .context("Could not fetch latest L1 block")?
+        .header
+        .timestamp;
+    let game_count: u64 = dispute_game_factory
+        .gameCount()
+        .stall()
+        .await
+        .gameCount_
+        .to();
+    let end_index = args.end_index.unwrap_or(game_count).min(game_count);
+    info!(
+        "Scanning factory indices {} to {end_index}.",
+        args.start_index
+    );
+
+    let mut treasuries = BTreeSet::new();
+    let mut pending = Vec::new();
+    for index in args.start_index..end_index {
+        let gameAtIndexReturn {
+            gameType_: game_type,
+            proxy_: game_address,
+            ..
+        } = dispute_game_factory
+            .gameAtIndex(U256::from(index))
+            .stall()
+            .await;
+        if game_type != KAILUA_GAME_TYPE {
+            continue;
+        }
+        let tournament = KailuaTournament::new(game_address, &eth_rpc_provider);
+        if tournament.parentGame().stall().await.parentGame_ == game_address {
+            // Treasury instances anchor the proposal tree and are not proposals
+            continue;
+        }
+        if tournament.proposer().stall().await.proposer_ != args.proposer {
+            continue;
+        }
+        let treasury = tournament.treasury().stall().await.treasury_;
+        treasuries.insert(treasury);
+        let Some(challenge_window_end) = tournament.proof_deadline().await? else {
+            continue;
+        };
+        pending.push(PendingProposal {
+            index,
+            contract: game_address,
+            treasury,
+            output_block_number: tournament.l2BlockNumber().stall().await.l2BlockNumber_.to(),
+            created_at: tournament.createdAt().stall().await._0,
+            challenge_window_end,
+        });
+    }
+
+    let mut report = ExposureReport {
+        proposer: args.proposer,
+        timestamp,
+        treasuries: vec![],
+        pending: vec![],
+        losses: vec![],
+        total_locked: U256::ZERO,
+        total_at_risk: U256::ZERO,
+        total_lost: U256::ZERO,
+        at_risk_until: None,
+    };
+    for treasury in treasuries {
+        let treasury_contract = KailuaTreasury::new(treasury, &eth_rpc_provider);
+        let participation_bond = treasury_contract.participationBond().stall().await._0;
+        let paid_bond = treasury_contract.paidBonds(args.proposer).stall().await._0;
+        let elimination_round: u64 = treasury_contract
+            .eliminationRound(args.proposer)
+            .stall()
+            .await
+            ._0
+            .to();
+        let eliminated_at = (elimination_round > 0).then_some(elimination_round);
+        match eliminated_at {
+            Some(index) => {
+                // The bond is paid out to the prover but remains recorded as paid in
+                let contract = dispute_game_factory
+                    .gameAtIndex(U256::from(index))
+                    .stall()
+                    .await
+                    .proxy_;
+                report.losses.push(LossEvent {
+                    treasury,
+                    index,
+                    contract,
+                    amount: paid_bond,
+                });
+                report.total_lost += paid_bond;
+            }
+            None => {
+                report.total_locked += paid_bond;
+                if pending.iter().any(|proposal| proposal.treasury == treasury) {
+                    report.total_at_risk += paid_bond;
+                }
+            }
+        }
+        report.treasuries.push(TreasuryExposure {
+            treasury,
+            participation_bond,
+            paid_bond,
+            eliminated_at,
+        });
+    }
+    report.at_risk_until = pending
+        .iter()
+        .map(|proposal| proposal.challenge_window_end)
+        .max();
+    report.pending = pending;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        report.print();
+    }
+    Ok(())
+}
@@ -0,0 +1,249 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::bail;
+use boundless_market::storage::StorageProviderConfig;
+use kailua_client::BoundlessArgs;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tracing::{info, warn};
+
+#[derive(
+    clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ProvingBackend {
+    /// Let kailua-host pick the backend from the environment and boundless parameters
+    #[default]
+    Auto,
+    /// Prove on the local machine
+    Local,
+    /// Prove using Bonsai with the inherited `BONSAI_API_URL` and `BONSAI_API_KEY`
+    Bonsai,
+    /// Request proofs from the Boundless market
+    Boundless,
+}
+
+impl ProvingBackend {
+    /// Configures a kailua-host invocation to prove using this backend
+    pub fn configure(
+        &self,
+        command: &mut Command,
+        proving_args: &mut Vec<String>,
+        boundless_args: &Option<BoundlessArgs>,
+        boundless_storage_config: &Option<StorageProviderConfig>,
+    ) -> anyhow::Result<()> {
+        match self {
+            ProvingBackend::Auto => {
+                if let Some(boundless_args) = boundless_args {
+                    proving_args.extend(boundless_args.to_arg_vec(boundless_storage_config));
+                }
+            }
+            ProvingBackend::Local => {
+                command.env("RISC0_PROVER", "local");
+            }
+            ProvingBackend::Bonsai => {
+                command.env("RISC0_PROVER", "bonsai");
+            }
+            ProvingBackend::Boundless => {
+                let Some(boundless_args) = boundless_args else {
+                    bail!("Boundless proving backend requires boundless parameters.");
+                };
+                proving_args.extend(boundless_args.to_arg_vec(boundless_storage_config));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct FailoverArgs {
+    /// Proving backend to submit proofs to first
+    #[clap(long, env, value_enum, default_value_t = ProvingBackend::Auto)]
+    pub primary_prover: ProvingBackend,
+    /// Proving backend to resubmit proofs to when the primary backend fails
+    #[clap(long, env, value_enum)]
+    pub secondary_prover: Option<ProvingBackend>,
+    /// Number of consecutive failures after which the primary backend is bypassed
+    #[clap(long, env, default_value_t = 3)]
+    pub prover_failure_threshold: u32,
+    /// Number of seconds to wait before retrying a bypassed primary backend
+    #[clap(long, env, default_value_t = 1800)]
+    pub prover_recovery_secs: u64,
+    /// Number of seconds after which a proof still running on the primary backend is at risk and
+    /// is resubmitted to the secondary backend
+    #[clap(long, env)]
+    pub prover_deadline_secs: Option<u64>,
+}
+
+/// Recent outcomes of proving jobs on a backend
+#[derive(Clone, Debug, Default)]
+pub struct BackendHealth {
+    pub consecutive_failures: u32,
+    pub bypassed_since: Option<Instant>,
+}
+
+/// Routes proving jobs between a primary and an optional secondary backend
+#[derive(Debug)]
+pub struct ProverFailover {
+    pub args: FailoverArgs,
+    /// The health of every backend attempted, kept across reconfigurations
+    pub health: HashMap<ProvingBackend, BackendHealth>,
+}
+
+impl ProverFailover {
+    pub fn new(args: FailoverArgs, has_boundless_args: bool) -> anyhow::Result<Self> {
+        let backends = [Some(args.primary_prover), args.secondary_prover];
+        if !has_boundless_args && backends.contains(&Some(ProvingBackend::Boundless)) {
+            bail!("Boundless proving backend requires boundless parameters.");
+        }
+        match args.secondary_prover {
+            Some(secondary) => info!(
+                "Proving with {:?} backend and failing over to {secondary:?} backend.",
+                args.primary_prover
+            ),
+            None => info!("Proving with {:?} backend.", args.primary_prover),
+        }
+        Ok(Self {
+            args,
+            health: Default::default(),
+        })
+    }
+
+    /// Applies new failover settings, keeping the health and recovery periods of all backends
+    pub fn reconfigure(
+        &mut self,
+        args: FailoverArgs,
+        has_boundless_args: bool,
+    ) -> anyhow::Result<()> {
+        self.args = Self::new(args, has_boundless_args)?.args;
+        Ok(())
+    }
+
+    /// Returns the health of the given backend
+    pub fn health(&self, backend: ProvingBackend) -> BackendHealth {
+        self.health.get(&backend).cloned().unwrap_or_default()
+    }
+
+    /// Returns the backends to attempt the next job on, in order
+    pub fn backends(&mut self) -> Vec<ProvingBackend> {
+        let primary = self.args.primary_prover;
+        let Some(secondary) = self.args.secondary_prover else {
+            return vec![primary];
+        };
+        let health = self.health.entry(primary).or_default();
+        if let Some(bypassed_since) = health.bypassed_since {
+            if bypassed_since.elapsed() < Duration::from_secs(self.args.prover_recovery_secs) {
+                return vec![secondary];
+            }
+            info!("Retrying {primary:?} backend after recovery period.");
+            health.bypassed_since = None;
+        }
+        vec![primary, secondary]
+    }
+
+    /// Returns how long a job may run on the given backend before it is resubmitted elsewhere
+    pub fn deadline(&self, backend: ProvingBackend) -> Option<Duration> {
+        if backend != self.args.primary_prover || self.args.secondary_prover.is_none() {
+            return None;
+        }
+        self.args.prover_deadline_secs.map(Duration::from_secs)
+    }
+
+    /// Updates the health of a backend after a job attempt, bypassing a failing primary backend
+    pub fn record(&mut self, backend: ProvingBackend, success: bool) {
+        let health = self.health.entry(backend).or_default();
+        if success {
+            *health = Default::default();
+            return;
+        }
+        health.consecutive_failures += 1;
+        if backend == self.args.primary_prover
+            && self.args.secondary_prover.is_some()
+            && health.bypassed_since.is_none()
+            && health.consecutive_failures >= self.args.prover_failure_threshold
+        {
+            warn!(
+                "Bypassing {backend:?} backend for {}s after {} consecutive failures.",
+                self.args.prover_recovery_secs, health.consecutive_failures
+            );
+            health.bypassed_since = Some(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(primary: ProvingBackend, secondary: ProvingBackend) -> FailoverArgs {
+        FailoverArgs {
+            primary_prover: primary,
+            secondary_prover: Some(secondary),
+            prover_failure_threshold: 2,
+            prover_recovery_secs: 1800,
+            prover_deadline_secs: None,
+        }
+    }
+
+    #[test]
+    fn reconfigure_keeps_health() {
+        let mut failover =
+            ProverFailover::new(args(ProvingBackend::Bonsai, ProvingBackend::Local), false)
+                .unwrap();
+        failover.record(ProvingBackend::Bonsai, false);
+        failover.record(ProvingBackend::Bonsai, false);
+        failover.record(ProvingBackend::Local, false);
+        assert_eq!(failover.backends(), vec![ProvingBackend::Local]);
+
+        // tightening the threshold neither resets the failures nor lifts the bypass
+        let mut reconfigured = args(ProvingBackend::Bonsai, ProvingBackend::Local);
+        reconfigured.prover_failure_threshold = 1;
+        failover.reconfigure(reconfigured, false).unwrap();
+        assert_eq!(failover.backends(), vec![ProvingBackend::Local]);
+        let bonsai = failover.health(ProvingBackend::Bonsai);
+        assert_eq!(bonsai.consecutive_failures, 2);
+        assert!(bonsai.bypassed_since.is_some());
+        assert_eq!(
+            failover.health(ProvingBackend::Local).consecutive_failures,
+            1
+        );
+
+        // swapping the backends keeps the health of each backend
+        failover
+            .reconfigure(args(ProvingBackend::Local, ProvingBackend::Bonsai), false)
+            .unwrap();
+        assert_eq!(
+            failover.health(ProvingBackend::Local).consecutive_failures,
+            1
+        );
+        failover.record(ProvingBackend::Local, false);
+        assert_eq!(failover.backends(), vec![ProvingBackend::Bonsai]);
+
+        // invalid settings leave the failover unchanged
+        assert!(failover
+            .reconfigure(
+                args(ProvingBackend::Boundless, ProvingBackend::Local),
+                false
+            )
+            .is_err());
+        assert_eq!(failover.args.primary_prover, ProvingBackend::Local);
+        assert_eq!(
+            failover.health(ProvingBackend::Local).consecutive_failures,
+            2
+        );
+    }
+}
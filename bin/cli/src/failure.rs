@@ -0,0 +1,247 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::validate::ProofRequest;
+use kailua_common::errors::ErrorClass;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::task::JoinHandle;
+
+/// The number of trailing output lines of a failed kailua-host invocation to retain
+pub const OUTPUT_TAIL_LINES: usize = 64;
+
+/// The exit code of a process killed by the kernel, e.g. by the OOM killer
+pub const SIGKILL_EXIT_CODE: i32 = 137;
+
+/// The stage at which a proving attempt failed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureClass {
+    /// Chain data required for the proof could not be fetched
+    Preflight,
+    /// The client program failed or asserted during execution
+    Execution,
+    /// The prover ran out of memory
+    OutOfMemory,
+    /// The STARK proof could not be wrapped into a Groth16 proof
+    Wrap,
+    /// The proof was not computed before its deadline
+    Timeout,
+    /// The working directory of the proof outgrew the disk quota
+    DiskQuota,
+    Unknown,
+}
+
+impl FailureClass {
+    /// Whether another attempt may succeed, as opposed to deterministic execution failures
+    pub fn is_transient(&self) -> bool {
+        !matches!(self, Self::Execution)
+    }
+
+    /// Maps the error class reported by kailua-host, if it determines the failure stage
+    pub fn from_reported(class: ErrorClass) -> Option<Self> {
+        match class {
+            ErrorClass::Fetch => Some(Self::Preflight),
+            ErrorClass::Encoding | ErrorClass::Chain => Some(Self::Execution),
+            // proving failures range from exhausted memory to failed assertions
+            ErrorClass::Proving => None,
+        }
+    }
+
+    /// Classifies a failure by the exit status and output of kailua-host
+    pub fn classify(exit_status: Option<ExitStatus>, output: &[String]) -> Self {
+        // prefer the error class reported by kailua-host over output heuristics
+        if let Some(class) = exit_status
+            .and_then(|status| status.code())
+            .and_then(ErrorClass::from_exit_code)
+            .and_then(Self::from_reported)
+        {
+            return class;
+        }
+        #[cfg(unix)]
+        let killed = exit_status.is_some_and(|status| {
+            std::os::unix::process::ExitStatusExt::signal(&status) == Some(9)
+                || status.code() == Some(SIGKILL_EXIT_CODE)
+        });
+        #[cfg(not(unix))]
+        let killed = exit_status.is_some_and(|status| status.code() == Some(SIGKILL_EXIT_CODE));
+        let output = output.join("\n").to_lowercase();
+        let mentions = |markers: &[&str]| markers.iter().any(|marker| output.contains(marker));
+        if killed
+            || mentions(&[
+                "out of memory",
+                "memory allocation",
+                "cannot allocate memory",
+            ])
+        {
+            Self::OutOfMemory
+        } else if mentions(&["groth16", "stark2snark", "identity_p254", "compress"]) {
+            Self::Wrap
+        } else if mentions(&[
+            "generate_rollup_config",
+            "preflight",
+            "blob",
+            "beacon",
+            "failed to fetch",
+            "error sending request",
+        ]) {
+            Self::Preflight
+        } else if mentions(&["panicked", "assertion", "native client", "execute"]) {
+            Self::Execution
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+/// A structured record of a failed proving attempt
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProofFailure {
+    pub class: FailureClass,
+    /// The proving backend that failed
+    pub backend: String,
+    pub exit_code: Option<i32>,
+    /// The unix timestamp of the failure
+    pub failed_at: u64,
+    /// The witness archived by the failed attempt for native replays, if any
+    pub witness_archive: Option<PathBuf>,
+    /// The trailing output of the failed attempt
+    pub output: Vec<String>,
+}
+
+impl ProofFailure {
+    pub fn new(
+        backend: String,
+        exit_status: Option<ExitStatus>,
+        output: Vec<String>,
+        witness_archive: &Path,
+    ) -> Self {
+        Self {
+            class: FailureClass::classify(exit_status, &output),
+            backend,
+            exit_code: exit_status.and_then(|status| status.code()),
+            failed_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            witness_archive: witness_archive
+                .exists()
+                .then(|| witness_archive.to_path_buf()),
+            output,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ProofRetryArgs {
+    /// Number of attempts at computing a proof before it is dead-lettered
+    #[clap(long, env, default_value_t = 3)]
+    pub proof_max_attempts: usize,
+    /// Number of seconds to wait before retrying a failed proof, doubled after every failure
+    #[clap(long, env, default_value_t = 60)]
+    pub proof_retry_backoff_secs: u64,
+    /// Maximum number of seconds to wait before retrying a failed proof
+    #[clap(long, env, default_value_t = 3600)]
+    pub proof_retry_max_backoff_secs: u64,
+}
+
+impl ProofRetryArgs {
+    /// Returns how long to wait before retrying a proof after its latest failure, or None if the
+    /// proof should be dead-lettered.
+    pub fn backoff(&self, failure: &ProofFailure, attempts: usize) -> Option<Duration> {
+        if !failure.class.is_transient() || attempts >= self.proof_max_attempts {
+            return None;
+        }
+        let exponent = attempts.saturating_sub(1).min(32) as u32;
+        Some(Duration::from_secs(
+            self.proof_retry_backoff_secs
+                .saturating_mul(1 << exponent)
+                .min(self.proof_retry_max_backoff_secs),
+        ))
+    }
+}
+
+/// Failed proof requests awaiting their next attempt, keyed by requesting proposal
+#[derive(Debug, Default)]
+pub struct RetryQueue(BTreeMap<u64, (Instant, ProofRequest)>);
+
+impl RetryQueue {
+    pub fn schedule(&mut self, request: ProofRequest, backoff: Duration) {
+        self.0
+            .insert(request.index, (Instant::now() + backoff, request));
+    }
+
+    pub fn remove(&mut self, requester: u64) {
+        self.0.remove(&requester);
+    }
+
+    /// Removes and returns the requests whose backoff has elapsed
+    pub fn take_due(&mut self) -> Vec<ProofRequest> {
+        let now = Instant::now();
+        let due = self
+            .0
+            .iter()
+            .filter_map(|(requester, (retry_at, _))| (*retry_at <= now).then_some(*requester))
+            .collect::<Vec<_>>();
+        due.into_iter()
+            .filter_map(|requester| self.0.remove(&requester))
+            .map(|(_, request)| request)
+            .collect()
+    }
+}
+
+/// Returns the path that kailua-host archives the witness of a proof to
+pub fn witness_archive_path(data_dir: &Path, proof_file_name: &str) -> PathBuf {
+    data_dir.join(format!("{proof_file_name}.witness"))
+}
+
+/// The trailing lines of output of a child process, which is still forwarded as it is read
+#[derive(Clone, Debug, Default)]
+pub struct OutputTail(Arc<Mutex<VecDeque<String>>>);
+
+impl OutputTail {
+    /// Forwards the lines read from the given stream to stdout (or stderr) while retaining the
+    /// last [OUTPUT_TAIL_LINES] of them.
+    pub fn capture<R: AsyncRead + Unpin + Send + 'static>(
+        &self,
+        reader: R,
+        to_stderr: bool,
+    ) -> JoinHandle<()> {
+        let tail = self.0.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if to_stderr {
+                    eprintln!("{line}");
+                } else {
+                    println!("{line}");
+                }
+                let mut tail = tail.lock().unwrap();
+                if tail.len() == OUTPUT_TAIL_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(line);
+            }
+        })
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.0.lock().unwrap().iter().cloned().collect()
+    }
+}
@@ -12,23 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::anchor::select_anchor;
+use crate::attest::{verify_sources, DeploymentArtifact, SourceVerificationArgs};
+use crate::deploy::{Deployer, GameParameters, Groth16Args};
 use crate::providers::optimism::OpNodeProvider;
 use crate::stall::Stall;
-use crate::{BN254_CONTROL_ID, CONTROL_ROOT, KAILUA_GAME_TYPE, SET_BUILDER_ID};
-use alloy::network::{EthereumWallet, Network, TxSigner};
-use alloy::primitives::{Address, Bytes, Uint, U256};
+use crate::KAILUA_GAME_TYPE;
+use alloy::network::{EthereumWallet, TxSigner};
+use alloy::primitives::{Address, U256};
 use alloy::providers::{Provider, ProviderBuilder};
 use alloy::signers::local::LocalSigner;
-use alloy::sol_types::SolValue;
-use alloy::transports::Transport;
-use anyhow::{bail, Context};
+use anyhow::{ensure, Context};
 use kailua_build::KAILUA_FPVM_ID;
-use kailua_common::client::config_hash;
+use kailua_common::config::config_hash;
 use kailua_contracts::*;
 use kailua_host::fetch_rollup_config;
-use std::process::exit;
+use std::path::PathBuf;
 use std::str::FromStr;
-use tracing::{error, info};
+use tracing::info;
 
 #[derive(clap::Args, Debug, Clone)]
 pub struct FastTrackArgs {
@@ -45,12 +46,15 @@ pub struct FastTrackArgs {
     #[clap(long, env)]
     pub eth_rpc_url: String,
 
-    /// The l2 block number to start sequencing since
+    /// The l2 block number to start sequencing since, overriding the automatically selected anchor
     #[clap(long, env)]
-    pub starting_block_number: u64,
+    pub starting_block_number: Option<u64>,
     /// The number of blocks that a proposal must cover
     #[clap(long, env)]
     pub proposal_block_span: u64,
+    /// The number of blocks between the intermediate outputs committed to by a proposal
+    #[clap(long, env, default_value_t = 1)]
+    pub output_block_span: u64,
     /// The time gap before a proposal can be made
     #[clap(long, env)]
     pub proposal_time_gap: u64,
@@ -61,6 +65,8 @@ pub struct FastTrackArgs {
     /// Address of the existing L1 `RiscZeroVerifier` contract to use
     #[clap(long, env)]
     pub verifier_contract: Option<String>,
+    #[clap(flatten)]
+    pub groth16: Groth16Args,
     /// The timeout after which a counter-proposal can not be made
     #[clap(long, env)]
     pub challenge_timeout: u64,
@@ -75,14 +81,34 @@ pub struct FastTrackArgs {
     #[clap(long, env, required_if_eq("respect_kailua_proposals", "true"))]
     pub guardian_key: Option<String>,
 
+    /// Address of the `MultiSendCallOnly` contract to batch the factory's configuration through
+    #[clap(long, env)]
+    pub multisend_address: Option<String>,
+
+    /// Path to export timelock operations to instead of scheduling and executing them
+    #[clap(long, env)]
+    pub timelock_export: Option<PathBuf>,
+
     /// Whether to set Kailua as the OptimismPortal's respected game type
     #[clap(long, env)]
     pub respect_kailua_proposals: bool,
+
+    /// Path to write the signed bytecode attestations of the deployed contracts to
+    #[clap(long, env)]
+    pub deployment_artifact: Option<PathBuf>,
+    #[clap(flatten)]
+    pub source_verification: SourceVerificationArgs,
 }
 
 pub async fn fast_track(args: FastTrackArgs) -> anyhow::Result<()> {
+    ensure!(
+        args.output_block_span > 0 && args.proposal_block_span % args.output_block_span == 0,
+        "Proposal block span {} is not a multiple of output block span {}.",
+        args.proposal_block_span,
+        args.output_block_span
+    );
     let op_node_provider =
-        OpNodeProvider(ProviderBuilder::new().on_http(args.op_node_url.as_str().try_into()?));
+        OpNodeProvider::new(ProviderBuilder::new().on_http(args.op_node_url.as_str().try_into()?));
     let eth_rpc_provider = ProviderBuilder::new().on_http(args.eth_rpc_url.as_str().try_into()?);
 
     info!("Fetching rollup configuration from rpc endpoints.");
@@ -106,180 +132,110 @@ pub async fn fast_track(args: FastTrackArgs) -> anyhow::Result<()> {
         .with_recommended_fillers()
         .wallet(&owner_wallet)
         .on_http(args.eth_rpc_url.as_str().try_into()?);
-
-    // Init factory contract
-    let dispute_game_factory = IDisputeGameFactory::new(dgf_address, &owner_provider);
-    info!("DisputeGameFactory({:?})", dispute_game_factory.address());
-    let game_count = dispute_game_factory.gameCount().stall().await.gameCount_;
-    info!("There have been {game_count} games created using DisputeGameFactory");
-    let dispute_game_factory_ownable = OwnableUpgradeable::new(dgf_address, &owner_provider);
-    let factory_owner_address = dispute_game_factory_ownable.owner().stall().await._0;
-    let factory_owner_safe = Safe::new(factory_owner_address, &owner_provider);
-    info!("Safe({:?})", factory_owner_safe.address());
-    let safe_owners = factory_owner_safe.getOwners().stall().await._0;
-    info!("Safe::owners({:?})", &safe_owners);
     let owner_address = owner_wallet.default_signer().address();
-    if safe_owners.first().unwrap() != &owner_address {
-        error!("Incorrect owner key.");
-        exit(2);
-    } else if safe_owners.len() != 1 {
-        error!("Expected exactly one owner of safe account.");
-        exit(1);
-    }
 
     // initialize deployment wallet
     info!("Initializing deployer wallet.");
     let deployer_signer = LocalSigner::from_str(&args.deployer_key)?;
-    let deployer_wallet = EthereumWallet::from(deployer_signer);
+    let deployer_wallet = EthereumWallet::from(deployer_signer.clone());
     let deployer_provider = ProviderBuilder::new()
         .with_recommended_fillers()
         .wallet(&deployer_wallet)
         .on_http(args.eth_rpc_url.as_str().try_into()?);
 
+    // Init factory contract
+    let mut deployer = Deployer::new(
+        &deployer_provider,
+        &owner_provider,
+        owner_address,
+        dgf_address,
+        args.timelock_export.is_some(),
+    )
+    .await?;
+    let params = GameParameters {
+        game_type: KAILUA_GAME_TYPE,
+        image_id: bytemuck::cast::<[u32; 8], [u8; 32]>(KAILUA_FPVM_ID).into(),
+        rollup_config_hash: rollup_config_hash.into(),
+        proposal_block_span: args.proposal_block_span,
+        output_block_span: args.output_block_span,
+        genesis_time: config.genesis.l2_time,
+        block_time: config.block_time,
+        proposal_time_gap: args.proposal_time_gap,
+        challenge_timeout: args.challenge_timeout,
+        l2_chain_id: config.l2_chain_id,
+    };
+
     // Deploy or reuse existing RISCZeroVerifier contracts
     let verifier_contract_address = match &args.verifier_contract {
-        None => deploy_verifier(&deployer_provider, &owner_provider, owner_address)
-            .await
-            .context("deploy_verifier")?,
+        None => {
+            deployer
+                .deploy_verifier_stack(&args.groth16)
+                .await
+                .context("deploy_verifier_stack")?
+                .router
+        }
         Some(address) => Address::from_str(address)?,
     };
 
-    // Deploy KailuaTreasury contract
-    info!("Deploying KailuaTreasury contract to L1 rpc.");
-    let kailua_treasury_implementation = KailuaTreasury::deploy(
-        &deployer_provider,
-        verifier_contract_address,
-        bytemuck::cast::<[u32; 8], [u8; 32]>(KAILUA_FPVM_ID).into(),
-        rollup_config_hash.into(),
-        Uint::from(args.proposal_block_span),
-        KAILUA_GAME_TYPE,
+    // Deploy KailuaTreasury and KailuaGame contracts
+    let treasury_implementation = deployer
+        .deploy_treasury(verifier_contract_address, &params)
+        .await?;
+    let game_implementation = deployer
+        .deploy_game(treasury_implementation, verifier_contract_address, &params)
+        .await?;
+    let participation_bond = U256::from(1);
+
+    // Create new treasury instance from the latest safe anchor or the target block number
+    let anchor = select_anchor(
+        &eth_rpc_provider,
+        &op_node_provider,
+        portal_address,
         dgf_address,
+        args.starting_block_number,
     )
-    .await
-    .context("KailuaTreasury implementation contract deployment error")?;
-    info!("{:?}", &kailua_treasury_implementation);
+    .await?;
+    let root_claim = anchor.output_root;
 
-    // Update dispute factory implementation to KailuaTreasury
-    info!("Setting KailuaTreasury initialization bond value in DisputeGameFactory to zero.");
-    crate::exec_safe_txn(
-        dispute_game_factory.setInitBond(KAILUA_GAME_TYPE, U256::ZERO),
-        &factory_owner_safe,
-        owner_address,
-    )
-    .await
-    .context("setInitBond 0 wei")?;
-    assert_eq!(
-        dispute_game_factory
-            .initBonds(KAILUA_GAME_TYPE)
-            .stall()
-            .await
-            .bond_,
-        U256::ZERO
-    );
-    info!("Setting KailuaTreasury participation bond value to 1 wei.");
-    let bond_value = U256::from(1);
-    crate::exec_safe_txn(
-        kailua_treasury_implementation.setParticipationBond(bond_value),
-        &factory_owner_safe,
-        owner_address,
-    )
-    .await
-    .context("setParticipationBond 1 wei")?;
-    assert_eq!(
-        kailua_treasury_implementation
-            .participationBond()
-            .stall()
+    // Install the contracts in the factory
+    if args.multisend_address.is_some() || deployer.factory_owner.is_timelock() {
+        let multisend = args
+            .multisend_address
+            .as_ref()
+            .map(|address| Address::from_str(address))
+            .transpose()?;
+        deployer
+            .wire_factory_atomically(
+                multisend,
+                KAILUA_GAME_TYPE,
+                treasury_implementation,
+                participation_bond,
+                root_claim,
+                anchor.block_number,
+                game_implementation,
+            )
+            .await?;
+    } else {
+        deployer
+            .set_bonds(
+                KAILUA_GAME_TYPE,
+                treasury_implementation,
+                participation_bond,
+            )
+            .await?;
+        deployer
+            .wire_factory(KAILUA_GAME_TYPE, treasury_implementation)
             .await
-            ._0,
-        bond_value
-    );
-
-    info!("Setting KailuaTreasury implementation address in DisputeGameFactory.");
-    crate::exec_safe_txn(
-        dispute_game_factory
-            .setImplementation(KAILUA_GAME_TYPE, *kailua_treasury_implementation.address()),
-        &factory_owner_safe,
-        owner_address,
-    )
-    .await
-    .context("setImplementation KailuaTreasury")?;
-    assert_eq!(
-        dispute_game_factory
-            .gameImpls(KAILUA_GAME_TYPE)
-            .stall()
+            .context("wire_factory KailuaTreasury")?;
+        deployer
+            .bootstrap_treasury(KAILUA_GAME_TYPE, root_claim, anchor.block_number)
+            .await?;
+        deployer
+            .wire_factory(KAILUA_GAME_TYPE, game_implementation)
             .await
-            .impl_,
-        *kailua_treasury_implementation.address()
-    );
-
-    // Create new treasury instance from target block number
-    let root_claim = op_node_provider
-        .output_at_block(args.starting_block_number)
-        .await?;
-    let extra_data = Bytes::from(args.starting_block_number.abi_encode_packed());
-    info!(
-        "Creating new KailuaTreasury game instance from {} ({}).",
-        args.starting_block_number, root_claim
-    );
-    crate::exec_safe_txn(
-        dispute_game_factory.create(KAILUA_GAME_TYPE, root_claim, extra_data.clone()),
-        &factory_owner_safe,
-        owner_address,
-    )
-    .await
-    .context("create KailuaTreasury")?;
-    let kailua_treasury_instance_address = dispute_game_factory
-        .games(KAILUA_GAME_TYPE, root_claim, extra_data)
-        .stall()
-        .await
-        .proxy_;
-    let kailua_treasury_instance =
-        KailuaTreasury::new(kailua_treasury_instance_address, &owner_provider);
-    info!("{:?}", &kailua_treasury_instance);
-    let status = kailua_treasury_instance.status().stall().await._0;
-    if status == 0 {
-        info!("Resolving KailuaTreasury instance");
-        crate::exec_safe_txn(
-            kailua_treasury_instance.resolve(),
-            &factory_owner_safe,
-            owner_address,
-        )
-        .await
-        .context("resolve KailuaTreasury")?;
-    } else {
-        info!("Game instance is not ongoing ({status})");
+            .context("wire_factory KailuaGame")?;
     }
 
-    // Deploy KailuaGame contract
-    info!("Deploying KailuaGame contract to L1 rpc.");
-    let kailua_game_contract = KailuaGame::deploy(
-        &deployer_provider,
-        *kailua_treasury_implementation.address(),
-        verifier_contract_address,
-        bytemuck::cast::<[u32; 8], [u8; 32]>(KAILUA_FPVM_ID).into(),
-        rollup_config_hash.into(),
-        Uint::from(args.proposal_block_span),
-        KAILUA_GAME_TYPE,
-        dgf_address,
-        U256::from(config.genesis.l2_time),
-        U256::from(config.block_time),
-        U256::from(args.proposal_time_gap),
-        args.challenge_timeout,
-    )
-    .await
-    .context("KailuaGame contract deployment error")?;
-    info!("{:?}", &kailua_game_contract);
-
-    // Update implementation to KailuaGame
-    info!("Setting KailuaGame implementation address in DisputeGameFactory.");
-    crate::exec_safe_txn(
-        dispute_game_factory.setImplementation(KAILUA_GAME_TYPE, *kailua_game_contract.address()),
-        &factory_owner_safe,
-        owner_address,
-    )
-    .await
-    .context("setImplementation KailuaGame")?;
-
     // Update the respectedGameType as the guardian
     if args.respect_kailua_proposals {
         // initialize guardian wallet
@@ -291,104 +247,44 @@ pub async fn fast_track(args: FastTrackArgs) -> anyhow::Result<()> {
             .with_recommended_fillers()
             .wallet(&guardian_wallet)
             .on_http(args.eth_rpc_url.as_str().try_into()?);
-        let optimism_portal = OptimismPortal2::new(portal_address, &guardian_provider);
-        let portal_guardian_address = optimism_portal.guardian().stall().await._0;
-        if portal_guardian_address != guardian_address {
-            bail!("OptimismPortal2 Guardian is {portal_guardian_address}. Provided private key has account address {guardian_address}.");
-        }
-
-        info!("Setting respectedGameType in OptimismPortal2.");
-        optimism_portal
-            .setRespectedGameType(KAILUA_GAME_TYPE)
-            .send()
-            .await
-            .context("setImplementation KailuaGame")?
-            .get_receipt()
+        deployer
+            .activate_portal(
+                &guardian_provider,
+                guardian_address,
+                portal_address,
+                KAILUA_GAME_TYPE,
+            )
             .await?;
     }
 
-    info!("Kailua upgrade complete.");
-    Ok(())
-}
-
-pub async fn deploy_verifier<
-    T: Transport + Clone,
-    P1: Provider<T, N>,
-    P2: Provider<T, N>,
-    N: Network,
->(
-    deployer_provider: P1,
-    owner_provider: P2,
-    owner_address: Address,
-) -> anyhow::Result<Address> {
-    // Deploy verifier router contract
-    info!("Deploying RiscZeroVerifierRouter contract to L1 under ownership of {owner_address}.");
-    let verifier_contract = RiscZeroVerifierRouter::deploy(&deployer_provider, owner_address)
-        .await
-        .context("RiscZeroVerifierRouter contract deployment error")?;
-    let verifier_contract_address = *verifier_contract.address();
-    let verifier_contract = RiscZeroVerifierRouter::new(verifier_contract_address, &owner_provider);
-
-    // Deploy RiscZeroGroth16Verifier contract
-    info!("Deploying RiscZeroGroth16Verifier contract to L1.");
-    let groth16_verifier_contract =
-        RiscZeroGroth16Verifier::deploy(&deployer_provider, CONTROL_ROOT, BN254_CONTROL_ID)
-            .await
-            .context("RiscZeroGroth16Verifier contract deployment error")?;
-    info!("{:?}", &groth16_verifier_contract);
-    let selector = groth16_verifier_contract.SELECTOR().stall().await._0;
-    info!("Adding RiscZeroGroth16Verifier contract to RiscZeroVerifierRouter.");
-    verifier_contract
-        .addVerifier(selector, *groth16_verifier_contract.address())
-        .send()
-        .await
-        .context("addVerifier RiscZeroGroth16Verifier (send)")?
-        .get_receipt()
-        .await
-        .context("addVerifier RiscZeroGroth16Verifier (get_receipt)")?;
+    // Export the scheduled governance payloads
+    if let Some(path) = &args.timelock_export {
+        deployer.factory_owner.save_operations(path).await?;
+    }
 
-    // Deploy RiscZeroSetVerifier contract
-    info!("Deploying RiscZeroSetVerifier contract to L1.");
-    let set_verifier_contract = RiscZeroSetVerifier::deploy(
-        &deployer_provider,
-        verifier_contract_address,
-        SET_BUILDER_ID,
-        String::default(),
+    // Submit the deployed contracts' sources to the block explorer
+    let chain_id = eth_rpc_provider.get_chain_id().await?;
+    verify_sources(
+        &args.source_verification,
+        &args.eth_rpc_url,
+        chain_id,
+        &mut deployer.attestations,
     )
-    .await
-    .context("RiscZeroSetVerifier contract deployment error")?;
-    info!("{:?}", &set_verifier_contract);
-    let selector = set_verifier_contract.SELECTOR().stall().await._0;
-    info!("Adding RiscZeroSetVerifier contract to RiscZeroVerifierRouter.");
-    verifier_contract
-        .addVerifier(selector, *set_verifier_contract.address())
-        .send()
-        .await
-        .context("addVerifier RiscZeroSetVerifier (send)")?
-        .get_receipt()
-        .await
-        .context("addVerifier RiscZeroSetVerifier (get_receipt)")?;
+    .await?;
 
-    // Deploy mock verifier
-    #[cfg(feature = "devnet")]
-    if risc0_zkvm::is_dev_mode() {
-        // Deploy MockVerifier contract
-        tracing::warn!("Deploying RiscZeroMockVerifier contract to L1. This will accept fake proofs which are not cryptographically secure!");
-        let mock_verifier_contract =
-            RiscZeroMockVerifier::deploy(&deployer_provider, [0u8; 4].into())
-                .await
-                .context("RiscZeroMockVerifier contract deployment error")?;
-        tracing::warn!("{:?}", &mock_verifier_contract);
-        tracing::warn!("Adding RiscZeroMockVerifier contract to RiscZeroVerifierRouter.");
-        verifier_contract
-            .addVerifier([0u8; 4].into(), *mock_verifier_contract.address())
-            .send()
-            .await
-            .context("addVerifier RiscZeroMockVerifier (send)")?
-            .get_receipt()
-            .await
-            .context("addVerifier RiscZeroMockVerifier (get_receipt)")?;
+    // Publish signed bytecode attestations
+    if let Some(path) = &args.deployment_artifact {
+        DeploymentArtifact::sign(
+            chain_id,
+            deployer.attestations,
+            Some(anchor),
+            &deployer_signer,
+        )
+        .await?
+        .save(path)
+        .await?;
     }
 
-    Ok(verifier_contract_address)
+    info!("Kailua upgrade complete.");
+    Ok(())
 }
@@ -22,9 +22,9 @@ use alloy::primitives::{Bytes, B256, U256};
 use alloy::providers::ProviderBuilder;
 use alloy::signers::local::LocalSigner;
 use alloy::sol_types::SolValue;
-use anyhow::Context;
+use anyhow::{ensure, Context};
 use kailua_common::blobs::hash_to_fe;
-use kailua_common::client::config_hash;
+use kailua_common::config::config_hash;
 use kailua_contracts::*;
 use kailua_host::fetch_rollup_config;
 use std::str::FromStr;
@@ -45,7 +45,7 @@ pub struct FaultArgs {
 }
 
 pub async fn fault(args: FaultArgs) -> anyhow::Result<()> {
-    let op_node_provider = OpNodeProvider(
+    let op_node_provider = OpNodeProvider::new(
         ProviderBuilder::new().on_http(args.propose_args.core.op_node_url.as_str().try_into()?),
     );
     let eth_rpc_provider =
@@ -99,6 +99,17 @@ pub async fn fault(args: FaultArgs) -> anyhow::Result<()> {
         .await
         .proposalBlockCount_
         .to();
+    let output_block_span: u64 = kailua_game_implementation
+        .outputBlockSpan()
+        .stall()
+        .await
+        .outputBlockSpan_
+        .to();
+    ensure!(
+        args.fault_offset % output_block_span == 0,
+        "Fault offset {} is not a multiple of output block span {output_block_span}.",
+        args.fault_offset
+    );
 
     // get proposal parent
     let games_count = dispute_game_factory.gameCount().stall().await.gameCount_;
@@ -129,8 +140,8 @@ pub async fn fault(args: FaultArgs) -> anyhow::Result<()> {
 
     // Prepare intermediate outputs
     let mut io_field_elements = vec![];
-    let first_io_number = parent_block_number + 1;
-    for i in first_io_number..proposed_block_number {
+    let first_io_number = parent_block_number + output_block_span;
+    for i in (first_io_number..proposed_block_number).step_by(output_block_span as usize) {
         let output = if i == faulty_block_number {
             faulty_root_claim
         } else {
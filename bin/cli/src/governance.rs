@@ -0,0 +1,310 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::stall::Stall;
+use crate::{exec_safe_multisend, exec_safe_request};
+use alloy::network::{Network, TransactionBuilder};
+use alloy::primitives::{keccak256, Address, Bytes, B256, U256};
+use alloy::providers::Provider;
+use alloy::sol_types::SolCall;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use kailua_contracts::{ITimelockController::ITimelockControllerInstance, Safe::SafeInstance, *};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+/// A timelock operation that was scheduled or exported for an external governance process
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TimelockOperation {
+    pub timelock: Address,
+    pub id: B256,
+    pub targets: Vec<Address>,
+    pub values: Vec<U256>,
+    pub payloads: Vec<Bytes>,
+    pub predecessor: B256,
+    pub salt: B256,
+    pub delay: U256,
+    pub schedule_calldata: Bytes,
+    pub execute_calldata: Bytes,
+}
+
+/// The contract account through which governance actions are executed
+pub enum Governance<T, P, N: Network> {
+    /// A 1-of-1 `Safe` account owned by `owner`
+    Safe {
+        safe: SafeInstance<T, P, N>,
+        owner: Address,
+    },
+    /// A `TimelockController` whose operations are scheduled and executed by `proposer`, or
+    /// exported when `export` is set
+    Timelock {
+        timelock: ITimelockControllerInstance<T, P, N>,
+        proposer: Address,
+        export: bool,
+        operations: Mutex<Vec<TimelockOperation>>,
+    },
+}
+
+impl<T: Transport + Clone, P: Provider<T, N> + Clone, N: Network> Governance<T, P, N> {
+    /// Determines whether the given owner contract is a `Safe` or a `TimelockController`
+    pub async fn detect(
+        owner_contract: Address,
+        provider: P,
+        owner_address: Address,
+        export: bool,
+    ) -> anyhow::Result<Self> {
+        let safe = Safe::new(owner_contract, provider.clone());
+        if let Ok(safe_owners) = safe.getOwners().call().await {
+            info!("Safe({owner_contract:?})");
+            let safe_owners = safe_owners._0;
+            info!("Safe::owners({:?})", &safe_owners);
+            if safe_owners.first() != Some(&owner_address) {
+                bail!("Incorrect owner key.");
+            } else if safe_owners.len() != 1 {
+                bail!("Expected exactly one owner of safe account.");
+            }
+            return Ok(Self::Safe {
+                safe,
+                owner: owner_address,
+            });
+        }
+
+        let timelock = ITimelockController::new(owner_contract, provider);
+        let Ok(min_delay) = timelock.getMinDelay().call().await else {
+            bail!("Owner {owner_contract} is neither a Safe nor a TimelockController.");
+        };
+        info!(
+            "TimelockController({owner_contract:?}) with minimum delay of {}s",
+            min_delay._0
+        );
+        if !export {
+            let proposer_role = keccak256("PROPOSER_ROLE");
+            let executor_role = keccak256("EXECUTOR_ROLE");
+            if !timelock
+                .hasRole(proposer_role, owner_address)
+                .stall()
+                .await
+                ._0
+            {
+                bail!("Owner key {owner_address} lacks the timelock PROPOSER_ROLE.");
+            }
+            let can_execute = timelock
+                .hasRole(executor_role, owner_address)
+                .stall()
+                .await
+                ._0
+                || timelock
+                    .hasRole(executor_role, Address::ZERO)
+                    .stall()
+                    .await
+                    ._0;
+            if !can_execute {
+                bail!("Owner key {owner_address} lacks the timelock EXECUTOR_ROLE.");
+            }
+        }
+        Ok(Self::Timelock {
+            timelock,
+            proposer: owner_address,
+            export,
+            operations: Default::default(),
+        })
+    }
+
+    pub fn address(&self) -> Address {
+        match self {
+            Governance::Safe { safe, .. } => *safe.address(),
+            Governance::Timelock { timelock, .. } => *timelock.address(),
+        }
+    }
+
+    pub fn is_timelock(&self) -> bool {
+        matches!(self, Governance::Timelock { .. })
+    }
+
+    /// Returns whether governance actions are exported rather than executed
+    pub fn is_exporting(&self) -> bool {
+        matches!(self, Governance::Timelock { export: true, .. })
+    }
+
+    /// Atomically performs the given calls as the owner contract, returning whether they were
+    /// executed on chain.
+    pub async fn execute(
+        &self,
+        txns: Vec<N::TransactionRequest>,
+        multisend: Option<Address>,
+    ) -> anyhow::Result<bool> {
+        match self {
+            Governance::Safe { safe, owner } => {
+                if txns.len() == 1 {
+                    exec_safe_request(txns.into_iter().next().unwrap(), safe, *owner).await?;
+                } else if let Some(multisend) = multisend {
+                    exec_safe_multisend(txns, multisend, safe, *owner).await?;
+                } else {
+                    bail!("Batching Safe transactions requires a MultiSendCallOnly contract.");
+                }
+                Ok(true)
+            }
+            Governance::Timelock {
+                timelock,
+                export,
+                operations,
+                ..
+            } => {
+                let operation = self.timelock_operation(timelock, txns).await?;
+                if *export {
+                    info!("Exporting timelock operation {}.", operation.id);
+                    operations.lock().unwrap().push(operation);
+                    return Ok(false);
+                }
+                self.schedule_and_execute(timelock, &operation).await?;
+                operations.lock().unwrap().push(operation);
+                Ok(true)
+            }
+        }
+    }
+
+    async fn timelock_operation(
+        &self,
+        timelock: &ITimelockControllerInstance<T, P, N>,
+        txns: Vec<N::TransactionRequest>,
+    ) -> anyhow::Result<TimelockOperation> {
+        let mut targets = Vec::with_capacity(txns.len());
+        let mut values = Vec::with_capacity(txns.len());
+        let mut payloads = Vec::with_capacity(txns.len());
+        for req in txns {
+            let Some(to) = req.to() else {
+                bail!("Timelock operations can not create contracts.");
+            };
+            targets.push(to);
+            values.push(req.value().unwrap_or_default());
+            payloads.push(req.input().cloned().unwrap_or_default());
+        }
+        let predecessor = B256::ZERO;
+        let salt = keccak256(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)?
+                .as_nanos()
+                .to_be_bytes(),
+        );
+        let delay = timelock.getMinDelay().stall().await._0;
+        let schedule_calldata = ITimelockController::scheduleBatchCall {
+            targets: targets.clone(),
+            values: values.clone(),
+            payloads: payloads.clone(),
+            predecessor,
+            salt,
+            delay,
+        }
+        .abi_encode();
+        let execute_calldata = ITimelockController::executeBatchCall {
+            targets: targets.clone(),
+            values: values.clone(),
+            payloads: payloads.clone(),
+            predecessor,
+            salt,
+        }
+        .abi_encode();
+        let id = timelock
+            .hashOperationBatch(
+                targets.clone(),
+                values.clone(),
+                payloads.clone(),
+                predecessor,
+                salt,
+            )
+            .stall()
+            .await
+            ._0;
+        Ok(TimelockOperation {
+            timelock: *timelock.address(),
+            id,
+            targets,
+            values,
+            payloads,
+            predecessor,
+            salt,
+            delay,
+            schedule_calldata: schedule_calldata.into(),
+            execute_calldata: execute_calldata.into(),
+        })
+    }
+
+    async fn schedule_and_execute(
+        &self,
+        timelock: &ITimelockControllerInstance<T, P, N>,
+        operation: &TimelockOperation,
+    ) -> anyhow::Result<()> {
+        info!(
+            "Scheduling timelock operation {} with delay {}s.",
+            operation.id, operation.delay
+        );
+        timelock
+            .scheduleBatch(
+                operation.targets.clone(),
+                operation.values.clone(),
+                operation.payloads.clone(),
+                operation.predecessor,
+                operation.salt,
+                operation.delay,
+            )
+            .send()
+            .await
+            .context("ITimelockController::scheduleBatch (send)")?
+            .get_receipt()
+            .await
+            .context("ITimelockController::scheduleBatch (get_receipt)")?;
+        // Wait out the delay
+        let poll_interval = Duration::from_secs(operation.delay.to::<u64>().clamp(1, 12));
+        while !timelock.isOperationReady(operation.id).stall().await._0 {
+            sleep(poll_interval).await;
+        }
+        info!("Executing timelock operation {}.", operation.id);
+        timelock
+            .executeBatch(
+                operation.targets.clone(),
+                operation.values.clone(),
+                operation.payloads.clone(),
+                operation.predecessor,
+                operation.salt,
+            )
+            .send()
+            .await
+            .context("ITimelockController::executeBatch (send)")?
+            .get_receipt()
+            .await
+            .context("ITimelockController::executeBatch (get_receipt)")?;
+        if !timelock.isOperationDone(operation.id).stall().await._0 {
+            bail!("Timelock operation {} was not executed.", operation.id);
+        }
+        Ok(())
+    }
+
+    /// Writes all timelock operations performed so far to the given path
+    pub async fn save_operations(&self, path: &Path) -> anyhow::Result<()> {
+        let Governance::Timelock { operations, .. } = self else {
+            warn!("Factory owner is not a timelock. No operations to export.");
+            return Ok(());
+        };
+        let data = serde_json::to_vec_pretty(&*operations.lock().unwrap())?;
+        tokio::fs::write(path, data)
+            .await
+            .context(format!("Failed to write timelock operations to {path:?}"))?;
+        info!("Saved timelock operations to {path:?}.");
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::address_book::{AddressArg, AddressBook};
+use crate::db::proposal::Proposal;
+use crate::db::KailuaDB;
+use crate::stall::Stall;
+use alloy::network::{EthereumWallet, Network};
+use alloy::primitives::Address;
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::signers::local::LocalSigner;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use kailua_contracts::*;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use tracing::{error, info, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct GuardianArgs {
+    /// Secret key of the L1 guardian wallet to protect withdrawals from invalid resolutions with
+    #[clap(long, env)]
+    pub guardian_key: Option<String>,
+    /// Address, address book alias or ENS name of the rollup's OptimismPortal2 contract
+    #[clap(long, env)]
+    pub optimism_portal: Option<AddressArg>,
+    /// Whether to blacklist games resolved in favor of incorrect proposals in the OptimismPortal2
+    #[clap(long, env)]
+    pub guardian_blacklist_invalid: bool,
+    /// Whether to pause withdrawals once a game is resolved in favor of an incorrect proposal
+    #[clap(long, env)]
+    pub guardian_pause_invalid: bool,
+}
+
+impl GuardianArgs {
+    pub fn acts(&self) -> bool {
+        self.guardian_blacklist_invalid || self.guardian_pause_invalid
+    }
+}
+
+/// Watches incorrect proposals for resolutions in favor of their proposer, which indicate a
+/// compromised proof system, and takes the guardian actions the operator opted into.
+#[derive(Clone, Debug)]
+pub struct ResolutionGuard {
+    pub args: GuardianArgs,
+    /// The indices of unresolved proposals deemed incorrect
+    pub watched: BTreeSet<u64>,
+    pub guardian_wallet: Option<EthereumWallet>,
+    /// The resolved address of the OptimismPortal2 contract
+    pub optimism_portal: Option<Address>,
+}
+
+impl ResolutionGuard {
+    pub async fn new<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        args: GuardianArgs,
+        address_book: &AddressBook,
+        provider: P,
+    ) -> anyhow::Result<Self> {
+        if !args.acts() {
+            return Ok(Self {
+                args,
+                watched: Default::default(),
+                guardian_wallet: None,
+                optimism_portal: None,
+            });
+        }
+        let (Some(guardian_key), Some(optimism_portal)) =
+            (&args.guardian_key, &args.optimism_portal)
+        else {
+            bail!("Guardian actions require both the guardian-key and optimism-portal.");
+        };
+        let portal_address = address_book.resolve(optimism_portal, &provider).await?;
+        let guardian_signer = LocalSigner::from_str(guardian_key)?;
+        let guardian_address = guardian_signer.address();
+        let portal_guardian_address = OptimismPortal2::new(portal_address, &provider)
+            .guardian()
+            .stall()
+            .await
+            ._0;
+        if portal_guardian_address != guardian_address {
+            bail!("OptimismPortal2 Guardian is {portal_guardian_address}. Provided private key has account address {guardian_address}.");
+        }
+        info!("Guardian address: {guardian_address}");
+        Ok(Self {
+            args,
+            watched: Default::default(),
+            guardian_wallet: Some(EthereumWallet::from(guardian_signer)),
+            optimism_portal: Some(portal_address),
+        })
+    }
+
+    /// Starts watching the proposal if it is unresolved and deemed incorrect
+    pub fn watch(&mut self, proposal: &Proposal) {
+        if proposal.is_correct() == Some(false) && !proposal.status.is_resolved() {
+            self.watched.insert(proposal.index);
+        }
+    }
+
+    /// Checks the finality of all watched proposals and acts on those resolved in their favor
+    pub async fn check<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        kailua_db: &KailuaDB,
+        provider: P,
+        eth_rpc_url: &str,
+    ) -> anyhow::Result<()> {
+        for index in self.watched.clone() {
+            let Some(proposal) = kailua_db.get_local_proposal(&index) else {
+                self.watched.remove(&index);
+                continue;
+            };
+            let Some(defender_wins) = proposal.fetch_finality(&provider).await? else {
+                continue;
+            };
+            self.watched.remove(&index);
+            if !defender_wins {
+                continue;
+            }
+            error!(
+                "INVALID RESOLUTION! Game {index} ({}) was resolved in favor of incorrect output {} at block {}.",
+                proposal.contract, proposal.output_root, proposal.output_block_number
+            );
+            if let Err(err) = self.protect(proposal.contract, eth_rpc_url).await {
+                error!("Failed to take guardian action: {err:?}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Blacklists the game and pauses withdrawals as opted into by the operator
+    pub async fn protect(&self, game: Address, eth_rpc_url: &str) -> anyhow::Result<()> {
+        let (Some(guardian_wallet), Some(portal_address)) =
+            (&self.guardian_wallet, self.optimism_portal)
+        else {
+            warn!("No guardian action configured.");
+            return Ok(());
+        };
+        let guardian_provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(guardian_wallet)
+            .on_http(eth_rpc_url.try_into()?);
+        let optimism_portal = OptimismPortal2::new(portal_address, &guardian_provider);
+        if self.args.guardian_blacklist_invalid {
+            if optimism_portal.disputeGameBlacklist(game).stall().await._0 {
+                info!("Game {game} is already blacklisted.");
+            } else {
+                optimism_portal
+                    .blacklistDisputeGame(game)
+                    .send()
+                    .await
+                    .context("blacklistDisputeGame (send)")?
+                    .get_receipt()
+                    .await
+                    .context("blacklistDisputeGame (get_receipt)")?;
+                warn!("Blacklisted game {game} in OptimismPortal2.");
+            }
+        }
+        if self.args.guardian_pause_invalid {
+            let superchain_config = SuperchainConfig::new(
+                optimism_portal.superchainConfig().stall().await._0,
+                &guardian_provider,
+            );
+            if superchain_config.paused().stall().await.paused_ {
+                info!("Withdrawals are already paused.");
+            } else {
+                superchain_config
+                    .pause(format!("kailua: invalid resolution of {game}"))
+                    .send()
+                    .await
+                    .context("pause (send)")?
+                    .get_receipt()
+                    .await
+                    .context("pause (get_receipt)")?;
+                warn!("Paused withdrawals through SuperchainConfig.");
+            }
+        }
+        Ok(())
+    }
+}
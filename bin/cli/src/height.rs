@@ -0,0 +1,86 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::proposal::Proposal;
+use alloy::consensus::BlockHeader;
+use alloy::eips::BlockNumberOrTag;
+use alloy::network::primitives::BlockTransactionsKind;
+use alloy::network::{BlockResponse, Network};
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::Context;
+use serde_json::Value;
+
+/// The op-node head beyond which outputs are not proposed
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum L2Head {
+    /// Propose outputs derived from L1 data that was published, but may still be reorged
+    #[default]
+    Safe,
+    /// Only propose outputs derived from finalized L1 data
+    Finalized,
+}
+
+/// The L1 block that must include a proposal before it is extended
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum L1Inclusion {
+    #[default]
+    Latest,
+    Safe,
+    Finalized,
+}
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct HeightGuardArgs {
+    /// The op-node head that proposed outputs may not exceed
+    #[clap(long, env, value_enum, default_value_t = L2Head::Safe)]
+    pub proposal_head: L2Head,
+    /// The L1 block that must include the parent proposal and its blob data before it is extended
+    #[clap(long, env, value_enum, default_value_t = L1Inclusion::Latest)]
+    pub parent_inclusion: L1Inclusion,
+}
+
+impl HeightGuardArgs {
+    /// Returns the number of the highest L2 block whose output may be proposed
+    pub fn head_number(&self, sync_status: &Value) -> anyhow::Result<u64> {
+        let head = match self.proposal_head {
+            L2Head::Safe => "safe_l2",
+            L2Head::Finalized => "finalized_l2",
+        };
+        sync_status[head]["number"]
+            .as_u64()
+            .context(format!("optimism_syncStatus missing {head} number"))
+    }
+
+    /// Returns whether the transaction publishing the proposal was included in an L1 block at
+    /// least as safe as required.
+    pub async fn is_included<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &self,
+        provider: P,
+        proposal: &Proposal,
+    ) -> anyhow::Result<bool> {
+        let tag = match self.parent_inclusion {
+            // the proposal was loaded from the latest state
+            L1Inclusion::Latest => return Ok(true),
+            L1Inclusion::Safe => BlockNumberOrTag::Safe,
+            L1Inclusion::Finalized => BlockNumberOrTag::Finalized,
+        };
+        let block = provider
+            .get_block_by_number(tag, BlockTransactionsKind::Hashes)
+            .await
+            .context("get_block_by_number")?
+            .context(format!("{tag} L1 block not found"))?;
+        Ok(proposal.created_at <= block.header().timestamp())
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::Cli;
+use clap::CommandFactory;
+use clap_complete::Shell;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct CompletionsArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Shell to generate completions for
+    #[clap(value_enum)]
+    pub shell: Shell,
+}
+
+/// Writes the completion script for the requested shell to stdout
+pub fn completions(args: CompletionsArgs) -> anyhow::Result<()> {
+    let mut command = Cli::command();
+    let bin_name = command.get_name().to_string();
+    clap_complete::generate(args.shell, &mut command, bin_name, &mut std::io::stdout());
+    Ok(())
+}
+
+pub const CONFIG_EXAMPLES: &str = "\
+Examples:
+  # Print the rollup configuration parameters required for deployment
+  kailua-cli config --op-node-url http://127.0.0.1:7545 --op-geth-url http://127.0.0.1:8545 \\
+    --eth-rpc-url http://127.0.0.1:8546";
+
+pub const FAST_TRACK_EXAMPLES: &str = "\
+Examples:
+  # Deploy Kailua on a testnet and start respecting its proposals immediately
+  kailua-cli fast-track --op-node-url $OP_NODE_URL --op-geth-url $OP_GETH_URL --eth-rpc-url $ETH_RPC_URL \\
+    --starting-block-number 0 --proposal-block-span 60 --proposal-time-gap 30 \\
+    --collateral-amount 1 --challenge-timeout 300 \\
+    --deployer-key $DEPLOYER_KEY --owner-key $OWNER_KEY --guardian-key $GUARDIAN_KEY \\
+    --respect-kailua-proposals
+
+  # Upgrade from permissioned games, anchoring at the latest output finalized by a resolved game
+  kailua-cli fast-track [...] --proposal-block-span 1800 --proposal-time-gap 3600 \\
+    --deployment-artifact deployment.json
+
+  # Export the governance calls for a timelocked owner instead of executing them
+  kailua-cli fast-track [...] --timelock-export upgrade.json";
+
+pub const DOCTOR_EXAMPLES: &str = "\
+Examples:
+  # Check the endpoints, contracts, wallets, prover and data directory of a validator
+  kailua-cli doctor --op-node-url $OP_NODE_URL --op-geth-url $OP_GETH_URL --eth-rpc-url $ETH_RPC_URL \\
+    --beacon-rpc-url $BEACON_RPC_URL --data-dir ./validator --validator-key $VALIDATOR_KEY
+
+  # Exercise the entire proving pipeline with a fake proof of the latest safe block
+  RISC0_DEV_MODE=1 kailua-cli doctor [...] --kailua-host ./target/release/kailua-host
+
+  # Check a proposer wallet and print a machine-readable report
+  kailua-cli doctor [...] --proposer-key $PROPOSER_KEY --json";
+
+pub const PROPOSE_EXAMPLES: &str = "\
+Examples:
+  # Publish sequencing proposals, caching tracked proposals under ./proposer
+  kailua-cli propose --op-node-url $OP_NODE_URL --op-geth-url $OP_GETH_URL --eth-rpc-url $ETH_RPC_URL \\
+    --beacon-rpc-url $BEACON_RPC_URL --data-dir ./proposer --proposer-key $PROPOSER_KEY
+
+  # Refuse to lock in more than 2 ether of participation bond
+  kailua-cli propose [...] --bond-ceiling 2000000000000000000";
+
+pub const VALIDATE_EXAMPLES: &str = "\
+Examples:
+  # Validate proposals and prove faults locally
+  kailua-cli validate --op-node-url $OP_NODE_URL --op-geth-url $OP_GETH_URL --eth-rpc-url $ETH_RPC_URL \\
+    --beacon-rpc-url $BEACON_RPC_URL --kailua-host ./target/release/kailua-host \\
+    --validator-key $VALIDATOR_KEY
+
+  # Back up other validators by only proving matches left unproven for 5 minutes,
+  # while serving the validator's view of the proposal tree on port 8080
+  kailua-cli validate [...] --proving-strategy defer --proving-defer-secs 300 --api-address 0.0.0.0:8080
+
+  # Delegate proving to Bonsai, resubmitting failed proofs to the local prover
+  BONSAI_API_KEY=[...] BONSAI_API_URL=[...] kailua-cli validate [...] \\
+    --primary-prover bonsai --secondary-prover local
+
+  # Submit proving jobs to a long-lived kailua-host instance
+  kailua-host serve --serve-address 127.0.0.1:9651 &
+  kailua-cli validate [...] --kailua-host-service http://127.0.0.1:9651
+
+  # Warm-start from the hourly snapshot exported by a trusted peer validator
+  kailua-cli validate [...] --snapshot-import peer-snapshot.json --snapshot-signers $PEER_ADDRESS
+
+  # Publish signed correctness verdicts to a webhook and a local audit log
+  kailua-cli validate [...] --attestation-webhook https://example.com/verdicts --attestation-file verdicts.jsonl";
+
+pub const PROVE_EXAMPLES: &str = "\
+Examples:
+  # Prove a game against the earliest sibling it diverges from without submitting the proof
+  kailua-cli prove --op-node-url $OP_NODE_URL --op-geth-url $OP_GETH_URL --eth-rpc-url $ETH_RPC_URL \\
+    --beacon-rpc-url $BEACON_RPC_URL --kailua-host ./target/release/kailua-host --data-dir ./prove \\
+    --game $GAME_ADDRESS
+
+  # Prove a game against a specific sibling and submit the proof
+  kailua-cli prove [...] --game $GAME_ADDRESS --contender $CONTENDER_ADDRESS --prover-key $PROVER_KEY
+
+  # Study the proof obtainable for a game from an earlier L1 head without submitting it
+  kailua-cli prove [...] --game $GAME_ADDRESS --l1-head-override $L1_BLOCK_HASH";
+
+pub const CHALLENGE_EXAMPLES: &str = "\
+Examples:
+  # Inspect an incorrect game and challenge it after confirmation
+  kailua-cli challenge --op-node-url $OP_NODE_URL --eth-rpc-url $ETH_RPC_URL --beacon-rpc-url $BEACON_RPC_URL \\
+    --game $GAME_ADDRESS --challenger-key $CHALLENGER_KEY";
+
+pub const RESOLVE_EXAMPLES: &str = "\
+Examples:
+  # Resolve a game whose parent is already resolved
+  kailua-cli resolve --eth-rpc-url $ETH_RPC_URL --resolver-key $RESOLVER_KEY --game $GAME_ADDRESS
+
+  # Resolve a game along with all of its unresolved ancestors, e.g. from a cron job
+  kailua-cli resolve [...] --game $GAME_ADDRESS --recursive
+
+  # Resolve a game named in an address book
+  kailua-cli resolve [...] --address-book addresses.json --game latest-game";
+
+pub const APPROVE_EXAMPLES: &str = "\
+Examples:
+  # Run the validator in peer review mode, queueing its proofs for approval
+  kailua-cli validate [...] --approval-queue-dir ./approvals --approval-secret $APPROVAL_SECRET
+
+  # List the intents awaiting approval
+  kailua-cli approve --approval-queue-dir ./approvals --approval-secret $APPROVAL_SECRET
+
+  # Approve an intent after reviewing it
+  kailua-cli approve --approval-queue-dir ./approvals --approval-secret $APPROVAL_SECRET $INTENT_ID";
+
+pub const TEST_FAULT_EXAMPLES: &str = "\
+Examples:
+  # Publish a faulty proposal extending the proposal at factory index 1 (devnet builds only)
+  kailua-cli test-fault --op-node-url $OP_NODE_URL --op-geth-url $OP_GETH_URL --eth-rpc-url $ETH_RPC_URL \\
+    --beacon-rpc-url $BEACON_RPC_URL --proposer-key $PROPOSER_KEY --fault-offset 1 --fault-parent 1";
+
+pub const EXPOSURE_EXAMPLES: &str = "\
+Examples:
+  # Summarize the bonds locked by a proposer and the proposals that keep them at risk
+  kailua-cli exposure --op-node-url $OP_NODE_URL --op-geth-url $OP_GETH_URL --eth-rpc-url $ETH_RPC_URL \\
+    --proposer $PROPOSER_ADDRESS
+
+  # Feed a treasury dashboard with the report of the games created since factory index 1000
+  kailua-cli exposure [...] --proposer $PROPOSER_ADDRESS --start-index 1000 --json";
+
+pub const REINDEX_EXAMPLES: &str = "\
+Examples:
+  # Rebuild the proposal records of a corrupted validator data directory from L1 logs
+  kailua-cli reindex --op-node-url $OP_NODE_URL --op-geth-url $OP_GETH_URL --eth-rpc-url $ETH_RPC_URL \\
+    --beacon-rpc-url $BEACON_RPC_URL --data-dir ./validator --from-block 20000000
+
+  # Resume an interrupted run, querying fewer blocks per request for a rate-limited endpoint
+  kailua-cli reindex [...] --data-dir ./validator --log-chunk-size 2000";
+
+pub const STATS_EXAMPLES: &str = "\
+Examples:
+  # Summarize the last hour, day and week of disputes
+  kailua-cli stats --op-node-url $OP_NODE_URL --op-geth-url $OP_GETH_URL --eth-rpc-url $ETH_RPC_URL
+
+  # Compare the last 30 days against the last 90 days, scanning from a recent L1 block
+  kailua-cli stats [...] --from-block 20000000 --window-secs 2592000,7776000 --json";
+
+pub const WHY_EXAMPLES: &str = "\
+Examples:
+  # Explain how the validator running on ./validator classified a game
+  kailua-cli why --game $GAME_ADDRESS --data-dir ./validator
+
+  # Print the recorded op-node responses behind each decision on the game
+  kailua-cli why --game $GAME_ADDRESS --data-dir ./validator --json";
+
+pub const BLOB_REPORT_EXAMPLES: &str = "\
+Examples:
+  # Check the data published by the proposal at factory index 42 and save a signed report
+  kailua-cli blob-report --op-node-url $OP_NODE_URL --eth-rpc-url $ETH_RPC_URL --beacon-rpc-url $BEACON_RPC_URL \\
+    --dispute-game-factory $DGF_ADDRESS --game-index 42 --reporter-key $REPORTER_KEY --report-file report.json";
+
+pub const VERIFY_TRANSCRIPT_EXAMPLES: &str = "\
+Examples:
+  # Audit the inputs a proof attests to using the transcript kailua-host wrote alongside it
+  kailua-cli verify-transcript ./risc0-[...].zkp
+
+  # Check a proof against a transcript obtained from a third party
+  kailua-cli verify-transcript ./risc0-[...].zkp --transcript-file transcript.json";
+
+pub const SIMULATE_EXAMPLES: &str = "\
+Examples:
+  # Predict the outcome of challenging game 42 with a new proposal now
+  kailua-cli simulate --api-url http://127.0.0.1:8080 --eth-rpc-url $ETH_RPC_URL \\
+    --dispute-game-factory $DGF_ADDRESS --action challenge:42
+
+  # Predict the outcome of a fault proof eliminating game 43 landing at a given time
+  kailua-cli simulate [...] --action fault:43@1735689600";
+
+pub const GEN_TEST_RECEIPT_EXAMPLES: &str = "\
+Examples:
+  # Fake a receipt for a synthetic proposal, verifiable by a RiscZeroMockVerifier in Foundry tests
+  kailua-cli gen-test-receipt --claimed-l2-output-root 0x[...] --claimed-l2-block-number 128 \\
+    --config-hash 0x[...] --l2-chain-id 10 --fixture-file fixture.json
+
+  # Export a Groth16 proof produced by kailua-host as a fixture and check its journal
+  kailua-cli gen-test-receipt --from-proof ./risc0-[...].zkp --l2-chain-id 10 --fixture-file fixture.json";
+
+pub const REPLAY_EXAMPLES: &str = "\
+Examples:
+  # Archive the witness of a proof while proving it
+  WITNESS_ARCHIVE=./witness.bin kailua-host [...]
+
+  # Replay the archived witness natively under a debugger
+  cargo build -p kailua-cli && rust-gdb --args ./target/debug/kailua-cli replay ./witness.bin
+
+  # Find the first block whose output diverges from the op-node's
+  kailua-cli replay ./witness.bin --op-node-url $OP_NODE_URL --bisect";
+
+pub const HOST_EXAMPLES: &str = "\
+Examples:
+  # Prove with the host embedded in kailua-cli, as the validator does when --kailua-host is unset
+  kailua-cli host --op-node-address $OP_NODE_URL --l1-node-address $ETH_RPC_URL [...]
+
+  # Serve proving jobs from the same binary
+  kailua-cli host-serve --serve-address 0.0.0.0:9651";
+
+pub const COMPLETIONS_EXAMPLES: &str = "\
+Examples:
+  # Install bash completions for the current user
+  kailua-cli completions bash > ~/.local/share/bash-completion/completions/kailua-cli
+
+  # Install zsh completions
+  kailua-cli completions zsh > \"${fpath[1]}/_kailua-cli\"
+
+  # Install fish completions
+  kailua-cli completions fish > ~/.config/fish/completions/kailua-cli.fish";
@@ -0,0 +1,180 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::competition::{Abort, CancelledProofs, Competition};
+use alloy::transports::http::reqwest;
+use anyhow::{bail, Context};
+use kailua_common::errors::ErrorClass;
+use kailua_host::serve::{JobRequest, JobStatus, JobView};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Instant;
+use tokio::process::Command;
+use tracing::{error, info};
+
+/// The seconds to long-poll the status of a proving job for
+pub const JOB_STATUS_WAIT_SECS: u64 = 1;
+
+/// The kailua-cli subcommand running the embedded kailua host
+pub const EMBEDDED_HOST_SUBCOMMAND: &str = "host";
+
+/// Returns a command invoking the kailua host binary at the given path, or the kailua host
+/// embedded in this binary otherwise.
+pub fn kailua_host_command(kailua_host: Option<&Path>) -> anyhow::Result<Command> {
+    match kailua_host {
+        Some(kailua_host) => Ok(Command::new(kailua_host)),
+        None => {
+            let current_exe = std::env::current_exe().context("Failed to locate kailua-cli")?;
+            let mut command = Command::new(current_exe);
+            command.arg(EMBEDDED_HOST_SUBCOMMAND);
+            Ok(command)
+        }
+    }
+}
+
+/// A client of a long-lived `kailua-host serve` instance
+#[derive(Clone, Debug)]
+pub struct HostService {
+    pub url: String,
+    pub client: reqwest::Client,
+}
+
+impl HostService {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Submits the arguments and environment of a prepared kailua-host invocation as a job
+    pub async fn submit(&self, command: &Command) -> anyhow::Result<u64> {
+        let command = command.as_std();
+        let request = JobRequest {
+            // the service parses the arguments as those of kailua-host
+            args: command
+                .get_args()
+                .skip_while(|arg| *arg == EMBEDDED_HOST_SUBCOMMAND)
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect(),
+            env: command
+                .get_envs()
+                .filter_map(|(key, value)| {
+                    Some((
+                        key.to_string_lossy().to_string(),
+                        value?.to_string_lossy().to_string(),
+                    ))
+                })
+                .collect::<BTreeMap<_, _>>(),
+        };
+        let response = self
+            .client
+            .post(format!("{}/jobs", self.url))
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to submit proving job")?;
+        if !response.status().is_success() {
+            bail!(
+                "Proving job rejected ({}): {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            );
+        }
+        Ok(response.json::<JobView>().await?.id)
+    }
+
+    /// Waits briefly for the status of the job to change and returns it
+    pub async fn status(&self, id: u64) -> anyhow::Result<JobStatus> {
+        let job = self
+            .client
+            .get(format!(
+                "{}/jobs/{id}?wait_secs={JOB_STATUS_WAIT_SECS}",
+                self.url
+            ))
+            .send()
+            .await
+            .context("Failed to query proving job")?
+            .error_for_status()?
+            .json::<JobView>()
+            .await?;
+        Ok(job.status)
+    }
+
+    pub async fn receipt(&self, id: u64) -> anyhow::Result<Vec<u8>> {
+        Ok(self
+            .client
+            .get(format!("{}/jobs/{id}/receipt", self.url))
+            .send()
+            .await
+            .context("Failed to fetch receipt")?
+            .error_for_status()?
+            .bytes()
+            .await?
+            .to_vec())
+    }
+
+    /// Proves through the service and saves the receipt to the proof file, returning the reason
+    /// the proof was abandoned if it became unnecessary or missed its deadline first, or the
+    /// error and its reported class on failure.
+    pub async fn prove(
+        &self,
+        command: &Command,
+        proof_file_name: &str,
+        cancelled_proofs: &CancelledProofs,
+        proposal_index: u64,
+        deadline: Option<Instant>,
+    ) -> Result<Result<(), (String, Option<ErrorClass>)>, Abort> {
+        let id = match self.submit(command).await {
+            Ok(id) => id,
+            Err(err) => {
+                error!("{err:?}");
+                return Ok(Err((format!("{err:?}"), None)));
+            }
+        };
+        info!("Submitted proving job {id} to {}.", self.url);
+        loop {
+            if Competition::is_cancelled(cancelled_proofs, proposal_index) {
+                return Err(Abort::Cancelled);
+            }
+            if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                return Err(Abort::Deadline);
+            }
+            match self.status(id).await {
+                Ok(JobStatus::Succeeded { .. }) => break,
+                Ok(JobStatus::Failed { error, class }) => {
+                    error!("Proving job {id} failed: {error}");
+                    return Ok(Err((error, class)));
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    error!("Failed to query proving job {id}: {err:?}");
+                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                }
+            }
+        }
+        let receipt = match self.receipt(id).await {
+            Ok(receipt) => receipt,
+            Err(err) => {
+                error!("{err:?}");
+                return Ok(Err((format!("{err:?}"), None)));
+            }
+        };
+        if let Err(err) = tokio::fs::write(proof_file_name, receipt).await {
+            error!("Failed to write proof file {proof_file_name}: {err:?}");
+            return Ok(Err((format!("{err:?}"), None)));
+        }
+        Ok(Ok(()))
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::primitives::B256;
+use anyhow::Context;
+use kailua_build::KAILUA_FPVM_ID;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// The FPVM image ids of published releases, appended to as part of every release
+pub const RELEASED_IMAGES: &str = include_str!("../images.json");
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ImagesArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Whether to print the registry as JSON
+    #[clap(long, env)]
+    pub json: bool,
+}
+
+/// A published release of the FPVM program
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReleasedImage {
+    pub version: String,
+    pub image_id: B256,
+}
+
+/// Returns the image id of the FPVM program embedded in this build
+pub fn local_image_id() -> B256 {
+    B256::from(bytemuck::cast::<[u32; 8], [u8; 32]>(KAILUA_FPVM_ID))
+}
+
+/// Returns the registry of released images
+pub fn released_images() -> anyhow::Result<Vec<ReleasedImage>> {
+    serde_json::from_str(RELEASED_IMAGES).context("Failed to parse the released image registry")
+}
+
+/// Returns the release that built the given image id, if any
+pub fn find_release(image_id: B256) -> anyhow::Result<Option<ReleasedImage>> {
+    Ok(released_images()?
+        .into_iter()
+        .find(|release| release.image_id == image_id))
+}
+
+/// Warns if the image id expected by the game does not belong to a known release or this build
+pub fn check_image_id(image_id: B256) -> anyhow::Result<()> {
+    let local_image_id = local_image_id();
+    match find_release(image_id)? {
+        _ if image_id == local_image_id => {
+            info!("Game image id {image_id} matches this build.");
+        }
+        Some(release) => {
+            warn!(
+                "Game image id {image_id} belongs to release {} instead of this build ({local_image_id}). Local proofs will be rejected.",
+                release.version
+            );
+        }
+        None => {
+            warn!("UNKNOWN IMAGE ID! Game image id {image_id} does not belong to any known release. The deployment may be rogue.");
+        }
+    }
+    Ok(())
+}
+
+pub async fn images(args: ImagesArgs) -> anyhow::Result<()> {
+    let releases = released_images()?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&releases)?);
+        return Ok(());
+    }
+    let local_image_id = local_image_id();
+    for release in &releases {
+        let marker = if release.image_id == local_image_id {
+            " (this build)"
+        } else {
+            ""
+        };
+        println!("{:<12} {}{marker}", release.version, release.image_id);
+    }
+    if releases
+        .iter()
+        .all(|release| release.image_id != local_image_id)
+    {
+        println!(
+            "{:<12} {local_image_id} (this build, unreleased)",
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+    Ok(())
+}
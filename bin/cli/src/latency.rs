@@ -0,0 +1,314 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// The number of settled disputes whose timelines are kept for percentile summaries
+pub const MAX_SETTLED_DISPUTES: usize = 1000;
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct LatencyArgs {
+    /// Target seconds from a challenge to the submission of the fault proof settling it
+    #[clap(long, env)]
+    pub dispute_slo_secs: Option<u64>,
+    /// Path to append the timelines of resolved disputes to as json lines
+    #[clap(long, env)]
+    pub dispute_latency_log: Option<PathBuf>,
+}
+
+/// The milestones of a dispute, in the order they are expected to be reached
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum DisputeStage {
+    /// The disputed proposal was published
+    Created,
+    /// The validator loaded the disputed proposal
+    Detected,
+    /// A competing proposal was published
+    Challenged,
+    /// The validator queued a fault proof of the match
+    ProofRequested,
+    /// The fault proof was computed
+    ProofReady,
+    /// The fault proof was accepted on-chain
+    ProofSubmitted,
+    /// The challenging proposal's game was resolved
+    Resolved,
+}
+
+impl DisputeStage {
+    pub const ALL: [DisputeStage; 7] = [
+        DisputeStage::Created,
+        DisputeStage::Detected,
+        DisputeStage::Challenged,
+        DisputeStage::ProofRequested,
+        DisputeStage::ProofReady,
+        DisputeStage::ProofSubmitted,
+        DisputeStage::Resolved,
+    ];
+}
+
+/// The times (in unix seconds) at which a dispute reached each of its milestones
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DisputeTimeline {
+    /// The factory index of the challenging proposal
+    pub index: u64,
+    /// The factory index of the disputed proposal
+    pub contender: u64,
+    pub stages: BTreeMap<DisputeStage, u64>,
+}
+
+impl DisputeTimeline {
+    /// Returns the seconds elapsed between reaching the two milestones, if both were reached
+    pub fn elapsed(&self, from: DisputeStage, to: DisputeStage) -> Option<u64> {
+        Some(
+            self.stages
+                .get(&to)?
+                .saturating_sub(*self.stages.get(&from)?),
+        )
+    }
+}
+
+/// Percentiles of the seconds taken to move between two dispute milestones
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SegmentLatency {
+    pub from: DisputeStage,
+    pub to: DisputeStage,
+    pub count: usize,
+    pub p50: Option<u64>,
+    pub p90: Option<u64>,
+    pub p99: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl SegmentLatency {
+    pub fn new(from: DisputeStage, to: DisputeStage, mut samples: Vec<u64>) -> Self {
+        samples.sort_unstable();
+        let percentile = |quantile: f64| {
+            let rank = (samples.len() as f64 * quantile).ceil() as usize;
+            samples.get(rank.max(1) - 1).copied()
+        };
+        Self {
+            from,
+            to,
+            count: samples.len(),
+            p50: percentile(0.5),
+            p90: percentile(0.9),
+            p99: percentile(0.99),
+            max: samples.last().copied(),
+        }
+    }
+}
+
+/// A summary of dispute latencies served by the api
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LatencyReport {
+    pub slo_secs: Option<u64>,
+    /// The fraction of settled disputes whose proof was submitted within the slo
+    pub slo_attainment: Option<f64>,
+    pub open_disputes: Vec<DisputeTimeline>,
+    pub segments: Vec<SegmentLatency>,
+}
+
+/// Tracks the timeline of every dispute the validator takes part in
+#[derive(Clone, Debug, Default)]
+pub struct DisputeLatencyTracker {
+    pub args: LatencyArgs,
+    /// The times at which proposals were loaded, until they become disputed or pruned
+    pub detections: BTreeMap<u64, u64>,
+    /// The timelines of unresolved disputes keyed by the challenging proposal's index
+    pub open: BTreeMap<u64, DisputeTimeline>,
+    pub settled: VecDeque<DisputeTimeline>,
+    /// The indices of open disputes already reported to have breached the slo
+    pub breached: BTreeSet<u64>,
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl DisputeLatencyTracker {
+    pub fn new(args: LatencyArgs) -> Self {
+        Self {
+            args,
+            ..Default::default()
+        }
+    }
+
+    /// Remembers when the proposal was loaded
+    pub fn detect(&mut self, index: u64) {
+        self.detections.entry(index).or_insert_with(unix_now);
+    }
+
+    /// Forgets the detection times of proposals that were pruned from the database
+    pub fn prune(&mut self, pruned_below: u64) {
+        self.detections = self.detections.split_off(&pruned_below);
+        self.open = self.open.split_off(&pruned_below);
+        self.breached = self.breached.split_off(&pruned_below);
+    }
+
+    /// Starts tracking the dispute between the contender and the challenging proposal
+    pub fn open(&mut self, index: u64, contender: u64, contender_created_at: u64, created_at: u64) {
+        if self.open.contains_key(&index) {
+            return;
+        }
+        let mut timeline = DisputeTimeline {
+            index,
+            contender,
+            stages: BTreeMap::from([
+                (DisputeStage::Created, contender_created_at),
+                (DisputeStage::Challenged, created_at),
+            ]),
+        };
+        if let Some(detected_at) = self.detections.get(&contender) {
+            timeline.stages.insert(DisputeStage::Detected, *detected_at);
+        }
+        self.open.insert(index, timeline);
+    }
+
+    /// Records the first time the dispute reached the milestone
+    pub fn record(&mut self, index: u64, stage: DisputeStage) {
+        if let Some(timeline) = self.open.get_mut(&index) {
+            timeline.stages.entry(stage).or_insert_with(unix_now);
+        }
+    }
+
+    /// Warns about open disputes that have exceeded the slo without a submitted proof
+    pub fn check_slo(&mut self) {
+        let Some(slo_secs) = self.args.dispute_slo_secs else {
+            return;
+        };
+        let now = unix_now();
+        for timeline in self.open.values() {
+            if timeline.stages.contains_key(&DisputeStage::ProofSubmitted) {
+                continue;
+            }
+            let Some(challenged_at) = timeline.stages.get(&DisputeStage::Challenged) else {
+                continue;
+            };
+            let elapsed = now.saturating_sub(*challenged_at);
+            if elapsed > slo_secs && self.breached.insert(timeline.index) {
+                warn!(
+                    "DISPUTE SLO BREACHED! Proposal {} challenged {elapsed}s ago remains unproven (slo {slo_secs}s).",
+                    timeline.index
+                );
+            }
+        }
+    }
+
+    /// Settles the dispute once its challenging proposal's game was resolved at the given time
+    pub fn resolve(&mut self, index: u64, resolved_at: u64) -> anyhow::Result<()> {
+        let Some(mut timeline) = self.open.remove(&index) else {
+            return Ok(());
+        };
+        timeline.stages.insert(DisputeStage::Resolved, resolved_at);
+        self.detections.remove(&timeline.contender);
+        self.breached.remove(&index);
+        info!(
+            "Dispute of proposal {} against {} settled after {}s.",
+            timeline.index,
+            timeline.contender,
+            timeline
+                .elapsed(DisputeStage::Created, DisputeStage::Resolved)
+                .unwrap_or_default()
+        );
+        if let Some(path) = &self.args.dispute_latency_log {
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .context(format!("Failed to open dispute latency log {path:?}"))?;
+            writeln!(file, "{}", serde_json::to_string(&timeline)?)?;
+        }
+        self.settled.push_back(timeline);
+        if self.settled.len() > MAX_SETTLED_DISPUTES {
+            self.settled.pop_front();
+        }
+        self.report().log();
+        Ok(())
+    }
+
+    /// Summarizes the latencies between consecutive milestones and of the end-to-end dispute
+    pub fn report(&self) -> LatencyReport {
+        let timelines = self.settled.iter().chain(self.open.values());
+        let mut pairs = DisputeStage::ALL
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect::<Vec<_>>();
+        pairs.push((DisputeStage::Challenged, DisputeStage::ProofSubmitted));
+        pairs.push((DisputeStage::Created, DisputeStage::Resolved));
+        let segments = pairs
+            .into_iter()
+            .map(|(from, to)| {
+                let samples = timelines
+                    .clone()
+                    .filter_map(|timeline| timeline.elapsed(from, to))
+                    .collect();
+                SegmentLatency::new(from, to, samples)
+            })
+            .collect();
+        let slo_attainment = self.args.dispute_slo_secs.and_then(|slo_secs| {
+            let responses = self
+                .settled
+                .iter()
+                .filter_map(|timeline| {
+                    timeline.elapsed(DisputeStage::Challenged, DisputeStage::ProofSubmitted)
+                })
+                .collect::<Vec<_>>();
+            let met = responses
+                .iter()
+                .filter(|elapsed| **elapsed <= slo_secs)
+                .count();
+            (!responses.is_empty()).then(|| met as f64 / responses.len() as f64)
+        });
+        LatencyReport {
+            slo_secs: self.args.dispute_slo_secs,
+            slo_attainment,
+            open_disputes: self.open.values().cloned().collect(),
+            segments,
+        }
+    }
+}
+
+impl LatencyReport {
+    /// Logs the percentiles of every milestone segment observed so far
+    pub fn log(&self) {
+        for segment in self.segments.iter().filter(|s| s.count > 0) {
+            info!(
+                "Dispute latency {:?} -> {:?}: {} disputes, p50 {}s, p90 {}s, p99 {}s, max {}s.",
+                segment.from,
+                segment.to,
+                segment.count,
+                segment.p50.unwrap_or_default(),
+                segment.p90.unwrap_or_default(),
+                segment.p99.unwrap_or_default(),
+                segment.max.unwrap_or_default()
+            );
+        }
+        if let (Some(slo_secs), Some(attainment)) = (self.slo_secs, self.slo_attainment) {
+            info!(
+                "{:.1}% of settled disputes were proven within {slo_secs}s.",
+                attainment * 100.0
+            );
+        }
+    }
+}
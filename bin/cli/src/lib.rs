@@ -16,27 +16,90 @@ use alloy::contract::SolCallBuilder;
 use alloy::network::{Network, TransactionBuilder};
 use alloy::primitives::{b256, Address, Uint, B256, U256};
 use alloy::providers::Provider;
+use alloy::sol_types::SolCall;
 use alloy::transports::Transport;
-use kailua_contracts::Safe::SafeInstance;
+use anyhow::bail;
+use kailua_contracts::{IMultiSendCallOnly, Safe::SafeInstance};
 use std::path::PathBuf;
 
 // pub mod bench;
+pub mod address_book;
+pub mod anchor;
+pub mod api;
+pub mod approval;
+pub mod attest;
+pub mod availability;
+pub mod blob_report;
+pub mod bond;
+pub mod bootstrap;
+pub mod builder;
+pub mod cadence;
+pub mod challenge;
 pub mod channel;
+pub mod competition;
 pub mod config;
+pub mod correctness;
 pub mod db;
+pub mod decision;
+pub mod deploy;
+pub mod doctor;
+pub mod emergency;
+pub mod equivocation;
+pub mod export;
+pub mod exposure;
+pub mod failover;
+pub mod failure;
 pub mod fast_track;
 pub mod fault;
+pub mod governance;
+pub mod guardian;
+pub mod height;
+pub mod help;
+pub mod host_service;
+pub mod images;
+pub mod latency;
+pub mod lock;
+pub mod prefetch;
+pub mod proofs;
 pub mod propose;
+pub mod prove;
 pub mod providers;
+pub mod reindex;
+pub mod relay;
+pub mod reload;
+pub mod replay;
+pub mod resolve;
+pub mod risk;
+pub mod shedding;
+pub mod simulate;
+pub mod snapshot;
 pub mod stall;
+pub mod stats;
+pub mod test_receipt;
+pub mod transact;
+pub mod transcript;
+pub mod tune;
 pub mod validate;
+pub mod verdict;
+pub mod verify;
+pub mod workdir;
 
 pub const KAILUA_GAME_TYPE: u32 = 1337;
 
-pub const CONTROL_ROOT: B256 =
-    b256!("8cdad9242664be3112aba377c5425a4df735eb1c6966472b561d2855932c0469");
-pub const BN254_CONTROL_ID: B256 =
-    b256!("04446e66d300eb7fb45c9726bb53c793dda407a62e9601618bb43c5c14657ac0");
+/// The Groth16 control root of the linked risc0 version
+pub fn control_root() -> B256 {
+    let params = risc0_zkvm::Groth16ReceiptVerifierParameters::default();
+    B256::from_slice(params.control_root.as_bytes())
+}
+
+/// The BN254 control id of the linked risc0 version, in the byte order of the verifier contract
+pub fn bn254_control_id() -> B256 {
+    let params = risc0_zkvm::Groth16ReceiptVerifierParameters::default();
+    let mut bn254_control_id = params.bn254_control_id;
+    bn254_control_id.as_mut_bytes().reverse();
+    B256::from_slice(bn254_control_id.as_bytes())
+}
+
 pub const SET_BUILDER_ID: B256 =
     b256!("744cca56cde6933dea72752c78b4a6ca894ed620e8af6437ab05fad53bcec40a");
 
@@ -46,11 +109,83 @@ pub const SET_BUILDER_ID: B256 =
 #[command(author, version, about, long_about = None)]
 #[allow(clippy::large_enum_variant)]
 pub enum Cli {
+    /// Print the rollup configuration parameters required for deploying Kailua
+    #[command(after_long_help = help::CONFIG_EXAMPLES)]
     Config(config::ConfigArgs),
+    /// Deploy the Kailua contracts and upgrade the rollup to use them
+    #[command(after_long_help = help::FAST_TRACK_EXAMPLES)]
     FastTrack(fast_track::FastTrackArgs),
+    /// Check the status of the Kailua deployment on the rollup
+    BootstrapStatus(bootstrap::BootstrapStatusArgs),
+    /// Check the configuration of an agent for problems before going live
+    #[command(after_long_help = help::DOCTOR_EXAMPLES)]
+    Doctor(doctor::DoctorArgs),
+    /// Publish sequencing proposals and resolve them once final
+    #[command(after_long_help = help::PROPOSE_EXAMPLES)]
     Propose(propose::ProposeArgs),
+    /// Monitor proposals and publish fault proofs against incorrect ones
+    #[command(after_long_help = help::VALIDATE_EXAMPLES)]
     Validate(validate::ValidateArgs),
+    /// Prove a specific game against a sibling it diverges from, optionally submitting the proof
+    #[command(after_long_help = help::PROVE_EXAMPLES)]
+    Prove(prove::ProveArgs),
+    /// Contest a specific incorrect game by publishing its correct sibling
+    #[command(after_long_help = help::CHALLENGE_EXAMPLES)]
+    Challenge(challenge::ChallengeArgs),
+    /// Resolve a specific game, optionally resolving its unresolved ancestors first
+    #[command(after_long_help = help::RESOLVE_EXAMPLES)]
+    Resolve(resolve::ResolveArgs),
+    /// List the actions an agent queued for approval, or approve one of them
+    #[command(after_long_help = help::APPROVE_EXAMPLES)]
+    Approve(approval::ApproveArgs),
+    /// Publish a deliberately faulty proposal for testing validators
+    #[command(after_long_help = help::TEST_FAULT_EXAMPLES)]
     TestFault(fault::FaultArgs),
+    /// Recommend deployment parameters based on recent rollup activity
+    Tune(tune::TuneArgs),
+    /// Enumerate proposers that made conflicting proposals for the same block
+    Equivocations(equivocation::EquivocationsArgs),
+    /// Export the dispute history of the rollup for offline analysis
+    Export(export::ExportArgs),
+    /// Rebuild the local proposal database of a data directory from L1 logs alone
+    #[command(after_long_help = help::REINDEX_EXAMPLES)]
+    Reindex(reindex::ReindexArgs),
+    /// Summarize proposal correctness, challenge, resolution and proof rates over time windows
+    #[command(after_long_help = help::STATS_EXAMPLES)]
+    Stats(stats::StatsArgs),
+    /// Report the bonds a proposer has locked, at risk and lost across treasuries
+    #[command(after_long_help = help::EXPOSURE_EXAMPLES)]
+    Exposure(exposure::ExposureArgs),
+    /// Verify an L2 output root against the latest resolved game covering its block
+    VerifyOutput(verify::VerifyOutputArgs),
+    /// Check that a proof attests to exactly the inputs recorded in its input transcript
+    #[command(after_long_help = help::VERIFY_TRANSCRIPT_EXAMPLES)]
+    VerifyTranscript(transcript::VerifyTranscriptArgs),
+    /// Explain why the validator decided that a game was correct or incorrect
+    #[command(after_long_help = help::WHY_EXAMPLES)]
+    Why(decision::WhyArgs),
+    /// Check the blobs published by a proposal and report where they diverge from local outputs
+    #[command(after_long_help = help::BLOB_REPORT_EXAMPLES)]
+    BlobReport(blob_report::BlobReportArgs),
+    /// Play out the resolution of the proposal tree under hypothetical challenges and proofs
+    #[command(after_long_help = help::SIMULATE_EXAMPLES)]
+    Simulate(simulate::SimulateArgs),
+    /// Generate a receipt and journal fixture for testing the Kailua contracts
+    #[command(after_long_help = help::GEN_TEST_RECEIPT_EXAMPLES)]
+    GenTestReceipt(test_receipt::GenTestReceiptArgs),
+    /// Natively replay an archived proof witness to locate where it diverges
+    #[command(after_long_help = help::REPLAY_EXAMPLES)]
+    Replay(replay::ReplayArgs),
+    /// Run the embedded kailua host to prove a sequence of blocks
+    #[command(after_long_help = help::HOST_EXAMPLES)]
+    Host(kailua_host::KailuaHostCli),
+    /// Serve proving jobs using the embedded kailua host
+    HostServe(kailua_host::serve::ServeArgs),
+    /// List the FPVM image ids of known releases
+    Images(images::ImagesArgs),
+    /// Generate shell completions
+    #[command(after_long_help = help::COMPLETIONS_EXAMPLES)]
+    Completions(help::CompletionsArgs),
     // Benchmark(bench::BenchArgs),
 }
 
@@ -75,6 +210,21 @@ pub struct CoreArgs {
     /// Directory to use for caching data
     #[clap(long, env)]
     pub data_dir: Option<PathBuf>,
+    /// Maximum number of seconds a scan for new proposals may take before it is retried
+    #[clap(long, env, default_value_t = 600)]
+    pub scan_timeout: u64,
+    /// Factory index to start scanning for proposals from, backfilling their ancestors on demand
+    #[clap(long, env, default_value_t = 0)]
+    pub start_factory_index: u64,
+
+    #[clap(flatten)]
+    pub rpc_budget: providers::metered::RpcBudgetArgs,
+    #[clap(flatten)]
+    pub retention: db::retention::RetentionArgs,
+    #[clap(flatten)]
+    pub correctness: correctness::CorrectnessArgs,
+    #[clap(flatten)]
+    pub lock: lock::InstanceLockArgs,
 }
 
 impl Cli {
@@ -82,9 +232,32 @@ impl Cli {
         match self {
             Cli::Config(args) => args.v,
             Cli::FastTrack(args) => args.v,
+            Cli::BootstrapStatus(args) => args.v,
+            Cli::Doctor(args) => args.v,
             Cli::Propose(args) => args.core.v,
             Cli::Validate(args) => args.core.v,
+            Cli::Prove(args) => args.v,
+            Cli::Challenge(args) => args.v,
+            Cli::Resolve(args) => args.v,
+            Cli::Approve(args) => args.v,
             Cli::TestFault(args) => args.propose_args.core.v,
+            Cli::Tune(args) => args.v,
+            Cli::Equivocations(args) => args.v,
+            Cli::Export(args) => args.v,
+            Cli::Reindex(args) => args.v,
+            Cli::Exposure(args) => args.v,
+            Cli::Stats(args) => args.v,
+            Cli::VerifyOutput(args) => args.v,
+            Cli::VerifyTranscript(args) => args.v,
+            Cli::Why(args) => args.v,
+            Cli::BlobReport(args) => args.v,
+            Cli::Simulate(args) => args.v,
+            Cli::GenTestReceipt(args) => args.v,
+            Cli::Replay(args) => args.v,
+            Cli::Host(args) => args.kona.v,
+            Cli::HostServe(args) => args.v,
+            Cli::Images(args) => args.v,
+            Cli::Completions(args) => args.v,
             // Cli::Benchmark(args) => args.v,
         }
     }
@@ -109,9 +282,19 @@ pub async fn exec_safe_txn<
     safe: &SafeInstance<T, P2, N>,
     from: Address,
 ) -> anyhow::Result<()> {
-    let req = txn.into_transaction_request();
+    exec_safe_request(txn.into_transaction_request(), safe, from).await
+}
+
+pub async fn exec_safe_request<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    req: N::TransactionRequest,
+    safe: &SafeInstance<T, P, N>,
+    from: Address,
+) -> anyhow::Result<()> {
+    let Some(to) = req.to() else {
+        bail!("Safe transactions can not create contracts.");
+    };
     safe.execTransaction(
-        req.to().unwrap(),
+        to,
         req.value().unwrap_or_default(),
         req.input().cloned().unwrap_or_default(),
         0,
@@ -135,3 +318,53 @@ pub async fn exec_safe_txn<
     .await?;
     Ok(())
 }
+
+/// Executes the given calls atomically through a delegate call from the safe to a
+/// `MultiSendCallOnly` contract.
+pub async fn exec_safe_multisend<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    txns: Vec<N::TransactionRequest>,
+    multisend: Address,
+    safe: &SafeInstance<T, P, N>,
+    from: Address,
+) -> anyhow::Result<()> {
+    let mut transactions = Vec::new();
+    for req in &txns {
+        let Some(to) = req.to() else {
+            bail!("MultiSendCallOnly does not support contract creation.");
+        };
+        let input = req.input().cloned().unwrap_or_default();
+        transactions.push(0u8);
+        transactions.extend_from_slice(to.as_slice());
+        transactions.extend_from_slice(&req.value().unwrap_or_default().to_be_bytes::<32>());
+        transactions.extend_from_slice(&U256::from(input.len()).to_be_bytes::<32>());
+        transactions.extend_from_slice(&input);
+    }
+    let data = IMultiSendCallOnly::multiSendCall {
+        transactions: transactions.into(),
+    }
+    .abi_encode();
+    safe.execTransaction(
+        multisend,
+        U256::ZERO,
+        data.into(),
+        1,
+        U256::ZERO,
+        U256::ZERO,
+        U256::ZERO,
+        Address::ZERO,
+        Address::ZERO,
+        [
+            [0u8; 12].as_slice(),
+            from.as_slice(),
+            [0u8; 32].as_slice(),
+            [1u8].as_slice(),
+        ]
+        .concat()
+        .into(),
+    )
+    .send()
+    .await?
+    .get_receipt()
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,115 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::network::Network;
+use alloy::primitives::Address;
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct InstanceLockArgs {
+    /// Whether to start even if another instance appears to be using the same wallet
+    #[clap(long, env)]
+    pub force: bool,
+}
+
+/// An advisory lock on a wallet held by this process for as long as the lock is alive
+#[derive(Debug)]
+pub struct InstanceLock {
+    pub path: PathBuf,
+}
+
+impl InstanceLock {
+    /// Locks the wallet in the data directory, refusing to do so if another running process holds
+    /// the lock unless forced.
+    pub fn acquire(
+        data_dir: &Path,
+        wallet: Address,
+        args: &InstanceLockArgs,
+    ) -> anyhow::Result<Self> {
+        let path = data_dir.join(format!("wallet-{wallet}.lock"));
+        loop {
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => break,
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+                Err(err) => return Err(err).context(format!("Failed to create {path:?}")),
+            }
+            let holder = std::fs::read_to_string(&path).unwrap_or_default();
+            let holder = holder.trim().parse::<u32>().ok();
+            if holder.is_some_and(is_running) && !args.force {
+                bail!(
+                    "Wallet {wallet} is locked by process {} through {path:?}. Stop it or pass --force to start anyway.",
+                    holder.unwrap()
+                );
+            }
+            warn!("Taking over lock {path:?} from process {holder:?}.");
+            std::fs::remove_file(&path).context(format!("Failed to remove {path:?}"))?;
+        }
+        std::fs::write(&path, std::process::id().to_string())
+            .context(format!("Failed to write {path:?}"))?;
+        info!("Locked wallet {wallet} through {path:?}.");
+        Ok(Self { path })
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Returns whether the process with the given id is alive, assuming so where this can not be told
+fn is_running(pid: u32) -> bool {
+    if pid == std::process::id() {
+        return false;
+    }
+    let proc_dir = Path::new("/proc");
+    !proc_dir.is_dir() || proc_dir.join(pid.to_string()).exists()
+}
+
+/// Refuses to start if the wallet has pending transactions that this process did not send, which
+/// indicates that another instance is using the same wallet, unless forced.
+pub async fn check_wallet_activity<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    provider: &P,
+    wallet: Address,
+    args: &InstanceLockArgs,
+) -> anyhow::Result<()> {
+    let latest_nonce = provider
+        .get_transaction_count(wallet)
+        .latest()
+        .await
+        .context("get_transaction_count")?;
+    let pending_nonce = provider
+        .get_transaction_count(wallet)
+        .pending()
+        .await
+        .context("get_transaction_count")?;
+    if pending_nonce <= latest_nonce {
+        return Ok(());
+    }
+    let pending_count = pending_nonce - latest_nonce;
+    if args.force {
+        warn!("Wallet {wallet} has {pending_count} pending transaction(s). Starting anyway.");
+        return Ok(());
+    }
+    bail!("Wallet {wallet} has {pending_count} pending transaction(s), so another instance may be using it. Wait for them to be included or pass --force to start anyway.");
+}
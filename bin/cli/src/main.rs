@@ -14,7 +14,9 @@
 
 use clap::Parser;
 use kailua_cli::Cli;
+use kailua_common::errors::ErrorClass;
 use kona_host::init_tracing_subscriber;
+use std::env::set_var;
 use tempfile::tempdir;
 
 #[tokio::main]
@@ -28,8 +30,41 @@ async fn main() -> anyhow::Result<()> {
     match cli {
         Cli::Config(args) => kailua_cli::config::config(args).await?,
         Cli::FastTrack(args) => kailua_cli::fast_track::fast_track(args).await?,
+        Cli::BootstrapStatus(args) => kailua_cli::bootstrap::bootstrap_status(args).await?,
+        Cli::Doctor(args) => kailua_cli::doctor::doctor(args).await?,
         Cli::Propose(args) => kailua_cli::propose::propose(args, data_dir).await?,
         Cli::Validate(args) => kailua_cli::validate::validate(args, data_dir).await?,
+        Cli::Prove(args) => kailua_cli::prove::prove(args).await?,
+        Cli::Challenge(args) => kailua_cli::challenge::challenge(args).await?,
+        Cli::Resolve(args) => kailua_cli::resolve::resolve(args).await?,
+        Cli::Approve(args) => kailua_cli::approval::approve(args).await?,
+        Cli::Tune(args) => kailua_cli::tune::tune(args).await?,
+        Cli::Equivocations(args) => kailua_cli::equivocation::equivocations(args).await?,
+        Cli::Export(args) => kailua_cli::export::export(args).await?,
+        Cli::Reindex(args) => kailua_cli::reindex::reindex(args).await?,
+        Cli::Exposure(args) => kailua_cli::exposure::exposure(args).await?,
+        Cli::Stats(args) => kailua_cli::stats::stats(args).await?,
+        Cli::VerifyOutput(args) => kailua_cli::verify::verify_output(args).await?,
+        Cli::VerifyTranscript(args) => kailua_cli::transcript::verify_transcript(args).await?,
+        Cli::Why(args) => kailua_cli::decision::why(args).await?,
+        Cli::BlobReport(args) => kailua_cli::blob_report::blob_report(args).await?,
+        Cli::Simulate(args) => kailua_cli::simulate::simulate_actions(args).await?,
+        Cli::GenTestReceipt(args) => kailua_cli::test_receipt::gen_test_receipt(args).await?,
+        Cli::Replay(args) => kailua_cli::replay::replay(args).await?,
+        Cli::Host(args) => {
+            set_var("KAILUA_VERBOSITY", args.kona.v.to_string());
+            if let Err(err) = kailua_host::prove(args).await {
+                // let the invoking validator decide whether to retry
+                if let Some(class) = ErrorClass::of(&err) {
+                    eprintln!("Error: {err:?}");
+                    std::process::exit(class.exit_code());
+                }
+                return Err(err);
+            }
+        }
+        Cli::HostServe(args) => kailua_host::serve::serve(args).await?,
+        Cli::Images(args) => kailua_cli::images::images(args).await?,
+        Cli::Completions(args) => kailua_cli::help::completions(args)?,
         Cli::TestFault(_args) =>
         {
             #[cfg(feature = "devnet")]
@@ -0,0 +1,193 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::providers::beacon::BlobProvider;
+use crate::providers::metered::MeteredProvider;
+use crate::shedding::{LoadShedder, WorkClass};
+use alloy::consensus::Transaction;
+use alloy::eips::eip4844::kzg_to_versioned_hash;
+use alloy::eips::BlockNumberOrTag;
+use alloy::network::primitives::BlockTransactionsKind;
+use alloy::primitives::{Address, B256};
+use alloy::providers::{Provider, ProviderBuilder};
+use anyhow::Context;
+use kailua_host::prefetch::PrefetchCache;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct PrefetchArgs {
+    /// Whether to skip downloading blobs and L1 headers for challenged games ahead of proving
+    #[clap(long, env)]
+    pub disable_prefetch: bool,
+    /// Minimum number of milliseconds between consecutive prefetch requests
+    #[clap(long, env, default_value_t = 250)]
+    pub prefetch_interval_ms: u64,
+}
+
+/// The L1 data to download ahead of proving a single challenged output
+#[derive(Clone, Debug)]
+pub struct PrefetchJob {
+    pub proof_key: String,
+    pub l1_head: B256,
+    pub agreed_l2_block_number: u64,
+    pub claimed_l2_block_number: u64,
+}
+
+/// Returns the directory the data prefetched for the given proof is stored in
+pub fn prefetch_dir(data_dir: &Path, proof_key: &str) -> PathBuf {
+    data_dir.join("prefetch").join(proof_key)
+}
+
+/// Downloads the blob sidecars and L1 headers needed by queued proofs one request at a time,
+/// so that network I/O overlaps with ongoing proving without exceeding endpoint rate limits.
+pub struct Prefetcher {
+    pub args: PrefetchArgs,
+    pub data_dir: PathBuf,
+    pub op_node_url: String,
+    pub l1_provider: MeteredProvider,
+    pub blob_provider: BlobProvider,
+    pub batch_inbox_address: Address,
+    pub seq_window_size: u64,
+    pub shedder: LoadShedder,
+}
+
+impl Prefetcher {
+    /// Spawns the prefetcher in the background, returning the queue to submit jobs to
+    pub fn spawn(self) -> Option<UnboundedSender<PrefetchJob>> {
+        if self.args.disable_prefetch {
+            return None;
+        }
+        let (sender, receiver) = unbounded_channel();
+        tokio::spawn(self.run(receiver));
+        Some(sender)
+    }
+
+    async fn run(self, mut receiver: UnboundedReceiver<PrefetchJob>) {
+        while let Some(job) = receiver.recv().await {
+            if let Err(err) = self.prefetch(&job).await {
+                warn!(
+                    "Failed to prefetch data for proof {}: {err:?}",
+                    job.proof_key
+                );
+            }
+        }
+    }
+
+    async fn throttle(&self) {
+        sleep(Duration::from_millis(self.args.prefetch_interval_ms)).await;
+    }
+
+    /// Returns the number of the L1 origin block of the given L2 block
+    async fn l1_origin(&self, l2_block_number: u64) -> anyhow::Result<u64> {
+        let op_node_provider =
+            ProviderBuilder::new().on_http(self.op_node_url.as_str().try_into()?);
+        let output_at_block: Value = op_node_provider
+            .client()
+            .request(
+                "optimism_outputAtBlock",
+                (format!("0x{:x}", l2_block_number),),
+            )
+            .await
+            .context(format!("optimism_outputAtBlock {l2_block_number}"))?;
+        self.throttle().await;
+        output_at_block["blockRef"]["l1origin"]["number"]
+            .as_u64()
+            .context(format!("Missing l1 origin of block {l2_block_number}"))
+    }
+
+    pub async fn prefetch(&self, job: &PrefetchJob) -> anyhow::Result<()> {
+        let cache = PrefetchCache::new(&prefetch_dir(&self.data_dir, &job.proof_key))?;
+        // Derivation reads from the agreed block's origin until the claimed block's batches,
+        // which must land within the sequencing window
+        let start = self.l1_origin(job.agreed_l2_block_number).await?;
+        let claimed_origin = self.l1_origin(job.claimed_l2_block_number).await?;
+        let l1_head_number = self
+            .l1_provider
+            .get_block_by_hash(job.l1_head, BlockTransactionsKind::Hashes)
+            .await?
+            .context("l1_head not found")?
+            .header
+            .number;
+        let end = l1_head_number.min(claimed_origin + self.seq_window_size);
+        info!(
+            "Prefetching L1 blocks {start} to {end} for proof {}.",
+            job.proof_key
+        );
+        let (mut header_count, mut blob_count) = (0, 0);
+        for number in start..=end {
+            // the prover downloads the remaining data itself
+            if self.shedder.sheds(WorkClass::Prefetch) {
+                info!(
+                    "Shedding prefetch for proof {} at L1 block {number} while endpoints are degraded.",
+                    job.proof_key
+                );
+                return Ok(());
+            }
+            self.throttle().await;
+            let block = self
+                .l1_provider
+                .get_block_by_number(
+                    BlockNumberOrTag::Number(number),
+                    BlockTransactionsKind::Full,
+                )
+                .await?
+                .context(format!("L1 block {number} not found"))?;
+            if !cache.has_header(block.header.hash) {
+                cache.save_header(block.header.hash, &alloy::rlp::encode(&block.header.inner))?;
+                header_count += 1;
+            }
+            // Only batches posted to the inbox are read by the derivation pipeline
+            let blob_hashes = block
+                .transactions
+                .txns()
+                .filter(|tx| tx.to() == Some(self.batch_inbox_address))
+                .flat_map(|tx| {
+                    tx.blob_versioned_hashes()
+                        .map(|h| h.to_vec())
+                        .unwrap_or_default()
+                })
+                .filter(|hash| !cache.has_blob(*hash))
+                .collect::<Vec<_>>();
+            if blob_hashes.is_empty() {
+                continue;
+            }
+            self.throttle().await;
+            let beacon_client = &self.blob_provider.beacon_client;
+            let slot = beacon_client
+                .slot_for_timestamp(block.header.timestamp)
+                .await?;
+            let Some(sidecars) = beacon_client.get_blob_sidecars(slot).await? else {
+                warn!("No blob sidecars found for L1 block {number} at slot {slot}.");
+                continue;
+            };
+            for sidecar in sidecars {
+                let versioned_hash = kzg_to_versioned_hash(sidecar.kzg_commitment.as_slice());
+                if blob_hashes.contains(&versioned_hash) {
+                    cache.save_blob(&sidecar)?;
+                    blob_count += 1;
+                }
+            }
+        }
+        info!(
+            "Prefetched {header_count} headers and {blob_count} blobs for proof {}.",
+            job.proof_key
+        );
+        Ok(())
+    }
+}
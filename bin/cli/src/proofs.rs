@@ -0,0 +1,190 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::failure::ProofFailure;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProofEntry {
+    /// The proposal whose proof request was dispatched to the prover, if one is outstanding
+    pub requester: Option<u64>,
+    /// The proposals awaiting the proof
+    pub pending: BTreeSet<u64>,
+    /// All proposals that the proof was delivered to
+    pub proposals: BTreeSet<u64>,
+    /// The failed attempts at computing the proof
+    #[serde(default)]
+    pub failures: Vec<ProofFailure>,
+    /// Whether the proof failed permanently and is no longer attempted
+    #[serde(default)]
+    pub dead_letter: bool,
+}
+
+/// A proof that failed permanently, awaiting operator intervention
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub key: String,
+    /// The proposals that were awaiting the proof
+    pub proposals: BTreeSet<u64>,
+    pub failures: Vec<ProofFailure>,
+}
+
+/// A persistent index of proof requests keyed by their journal, so that proposals sharing the
+/// same claims are proven only once.
+#[derive(Clone, Debug, Default)]
+pub struct ProofIndex {
+    pub path: PathBuf,
+    pub entries: BTreeMap<String, ProofEntry>,
+}
+
+impl ProofIndex {
+    pub fn load(data_dir: &Path) -> anyhow::Result<Self> {
+        let path = data_dir.join("proofs_index.json");
+        let mut entries: BTreeMap<String, ProofEntry> = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data).context("Failed to parse proofs index")?,
+            Err(_) => Default::default(),
+        };
+        // Requests do not survive restarts
+        for entry in entries.values_mut() {
+            entry.requester = None;
+            entry.pending.clear();
+        }
+        Ok(Self { path, entries })
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        std::fs::write(&self.path, serde_json::to_vec_pretty(&self.entries)?)
+            .context(format!("Failed to write proofs index to {:?}", self.path))
+    }
+
+    /// Registers the proposal as awaiting the proof with the given journal key, returning whether
+    /// a new proof request must be dispatched for it.
+    pub fn register(&mut self, key: String, proposal_index: u64) -> anyhow::Result<bool> {
+        let entry = self.entries.entry(key.clone()).or_default();
+        if entry.dead_letter {
+            warn!(
+                "Proof {key} for proposal {proposal_index} was dead-lettered and is not attempted."
+            );
+            entry.proposals.insert(proposal_index);
+            self.save()?;
+            return Ok(false);
+        }
+        entry.pending.insert(proposal_index);
+        let dispatch = entry.requester.is_none();
+        if dispatch {
+            entry.requester = Some(proposal_index);
+        } else {
+            info!(
+                "Proposal {proposal_index} shares the pending proof of proposal {}.",
+                entry.requester.unwrap()
+            );
+        }
+        self.save()?;
+        Ok(dispatch)
+    }
+
+    /// Marks the proof dispatched for `requester` as complete, returning all proposals awaiting it
+    pub fn complete(&mut self, requester: u64) -> anyhow::Result<Vec<u64>> {
+        let Some(entry) = self
+            .entries
+            .values_mut()
+            .find(|entry| entry.requester == Some(requester))
+        else {
+            return Ok(vec![]);
+        };
+        entry.requester = None;
+        let pending = std::mem::take(&mut entry.pending);
+        entry.proposals.extend(pending.iter().copied());
+        self.save()?;
+        Ok(pending.into_iter().collect())
+    }
+
+    /// Records a failed attempt at computing the proof dispatched for `requester`, returning the
+    /// number of failed attempts so far, or None if the proof is no longer requested.
+    pub fn record_failure(
+        &mut self,
+        requester: u64,
+        failure: ProofFailure,
+    ) -> anyhow::Result<Option<usize>> {
+        let Some((key, entry)) = self
+            .entries
+            .iter_mut()
+            .find(|(_, entry)| entry.requester == Some(requester))
+        else {
+            return Ok(None);
+        };
+        error!(
+            "Proof {key} for proposal {requester} failed on {} backend ({:?}, exit code {:?}, witness archive {:?}).",
+            failure.backend, failure.class, failure.exit_code, failure.witness_archive
+        );
+        entry.failures.push(failure);
+        let attempts = entry.failures.len();
+        self.save()?;
+        Ok(Some(attempts))
+    }
+
+    /// Gives up on the proof dispatched for `requester`, returning the proposals awaiting it
+    pub fn dead_letter(&mut self, requester: u64) -> anyhow::Result<Vec<u64>> {
+        let Some(entry) = self
+            .entries
+            .values_mut()
+            .find(|entry| entry.requester == Some(requester))
+        else {
+            return Ok(vec![]);
+        };
+        entry.requester = None;
+        entry.dead_letter = true;
+        let pending = std::mem::take(&mut entry.pending);
+        entry.proposals.extend(pending.iter().copied());
+        self.save()?;
+        Ok(pending.into_iter().collect())
+    }
+
+    /// Returns all proofs that failed permanently
+    pub fn dead_letters(&self) -> Vec<DeadLetter> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.dead_letter)
+            .map(|(key, entry)| DeadLetter {
+                key: key.clone(),
+                proposals: entry.proposals.clone(),
+                failures: entry.failures.clone(),
+            })
+            .collect()
+    }
+
+    /// Withdraws the proposal from the proof it awaits, returning the requester of the proof to
+    /// cancel once no proposal awaits it anymore.
+    pub fn release(&mut self, proposal_index: u64) -> anyhow::Result<Option<u64>> {
+        let Some(entry) = self
+            .entries
+            .values_mut()
+            .find(|entry| entry.pending.contains(&proposal_index))
+        else {
+            return Ok(None);
+        };
+        entry.pending.remove(&proposal_index);
+        let requester = if entry.pending.is_empty() {
+            entry.requester.take()
+        } else {
+            None
+        };
+        self.save()?;
+        Ok(requester)
+    }
+}
@@ -12,11 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::approval::{ApprovalArgs, ApprovalGate, Intent, IntentKind};
+use crate::bond::{BondArgs, BondMonitor};
+use crate::db::lifecycle::ProposalStatus;
 use crate::db::proposal::Proposal;
 use crate::db::KailuaDB;
+use crate::height::HeightGuardArgs;
+use crate::lock::{check_wallet_activity, InstanceLock};
 use crate::providers::beacon::BlobProvider;
+use crate::providers::metered::RpcMeter;
 use crate::providers::optimism::OpNodeProvider;
-use crate::{stall::Stall, CoreArgs, KAILUA_GAME_TYPE};
+use crate::providers::versions::probe_node_versions;
+use crate::resolve::{resolve_proposals, ResolveBatchArgs};
+use crate::stall::{with_scan_deadline, Stall};
+use crate::transact::{send_private_transaction, PrivateTxnArgs};
+use crate::{CoreArgs, KAILUA_GAME_TYPE};
 use alloy::consensus::BlockHeader;
 use alloy::eips::{BlockId, BlockNumberOrTag};
 use alloy::network::primitives::BlockTransactionsKind;
@@ -26,8 +36,7 @@ use alloy::providers::{Provider, ProviderBuilder};
 use alloy::signers::local::LocalSigner;
 use alloy::sol_types::SolValue;
 use anyhow::Context;
-use kailua_common::blobs::hash_to_fe;
-use kailua_common::client::config_hash;
+use kailua_common::config::config_hash;
 use kailua_contracts::*;
 use kailua_host::fetch_rollup_config;
 use std::path::PathBuf;
@@ -45,15 +54,41 @@ pub struct ProposeArgs {
     /// Secret key of L1 wallet to use for proposing outputs
     #[clap(long, env)]
     pub proposer_key: String,
+
+    #[clap(flatten)]
+    pub resolve: ResolveBatchArgs,
+
+    #[clap(flatten)]
+    pub bond: BondArgs,
+
+    #[clap(flatten)]
+    pub height_guard: HeightGuardArgs,
+
+    #[clap(flatten)]
+    pub approval: ApprovalArgs,
+
+    #[clap(flatten)]
+    pub private_txn: PrivateTxnArgs,
 }
 
 pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()> {
     // initialize blockchain connections
+    let rpc_meter = RpcMeter::new(args.core.rpc_budget.clone())
+        .with_usage_file(&data_dir.join("rpc_usage.json"))?;
     let op_node_provider =
-        OpNodeProvider(ProviderBuilder::new().on_http(args.core.op_node_url.as_str().try_into()?));
-    let cl_node_provider = BlobProvider::new(args.core.beacon_rpc_url.as_str()).await?;
-    let eth_rpc_provider =
-        ProviderBuilder::new().on_http(args.core.eth_rpc_url.as_str().try_into()?);
+        OpNodeProvider::new(rpc_meter.provider("op-node", &args.core.op_node_url)?)
+            .with_cache_dir(&data_dir.join("output_cache"), &args.core.op_node_url)?;
+    let cl_node_provider = BlobProvider::new(args.core.beacon_rpc_url.as_str())
+        .await?
+        .with_slot_cache(&data_dir.join("slot_cache.json"))?;
+    let eth_rpc_provider = rpc_meter.provider("eth-rpc", &args.core.eth_rpc_url)?;
+    let correctness_oracle = args.core.correctness.oracle(
+        &op_node_provider,
+        rpc_meter.provider("op-geth", &args.core.op_geth_url)?,
+        &rpc_meter,
+    )?;
+
+    probe_node_versions(&args.core).await;
 
     info!("Fetching rollup configuration from rpc endpoints.");
     // fetch rollup config
@@ -75,8 +110,35 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
     let proposer_provider = ProviderBuilder::new()
         .with_recommended_fillers()
         .wallet(&proposer_wallet)
-        .on_http(args.core.eth_rpc_url.as_str().try_into()?);
+        .on_client(rpc_meter.client("eth-rpc", &args.core.eth_rpc_url)?);
     info!("Proposer address: {proposer_address}");
+    // refuse to share the wallet with another running proposer
+    let _instance_lock = InstanceLock::acquire(&data_dir, proposer_address, &args.core.lock)?;
+    check_wallet_activity(&proposer_provider, proposer_address, &args.core.lock).await?;
+    let private_txn_provider = match &args.private_txn.private_rpc_url {
+        Some(private_rpc_url) => {
+            info!("Submitting challenges through private rpc endpoint.");
+            Some(
+                ProviderBuilder::new()
+                    .with_recommended_fillers()
+                    .wallet(&proposer_wallet)
+                    .on_client(rpc_meter.client("private-rpc", private_rpc_url)?),
+            )
+        }
+        None => None,
+    };
+    let bundle_txn_provider = match &args.private_txn.private_bundle_url {
+        Some(private_bundle_url) => {
+            info!("Submitting challenges as bundles through block builder endpoint.");
+            Some(
+                ProviderBuilder::new()
+                    .with_recommended_fillers()
+                    .wallet(&proposer_wallet)
+                    .on_client(rpc_meter.client("private-bundle", private_bundle_url)?),
+            )
+        }
+        None => None,
+    };
 
     // Init registry and factory contracts
     let dispute_game_factory =
@@ -106,25 +168,73 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
     info!("Initializing..");
     let mut kailua_db = KailuaDB::init(data_dir, &dispute_game_factory).await?;
     info!("KailuaTreasury({:?})", kailua_db.treasury.address);
+    kailua_db.state.next_factory_index = args.core.start_factory_index;
     // Run the proposer loop to sync and post
     info!(
         "Starting from proposal at factory index {}",
         kailua_db.state.next_factory_index
     );
 
+    let mut bond_monitor = BondMonitor::new(args.bond.clone());
+    let mut approval_gate = ApprovalGate::new(&args.approval)?;
+    let mut last_canonical_tip = None;
     loop {
         // Wait for new data on every iteration
-        sleep(Duration::from_secs(1)).await;
+        sleep(rpc_meter.throttle(Duration::from_secs(1))).await;
+        rpc_meter.report();
+        // drop cached outputs invalidated since the last iteration
+        if let Err(err) = op_node_provider.refresh_cache().await {
+            warn!("Failed to refresh output cache: {err:?}");
+        }
         // fetch latest games
-        kailua_db
-            .load_proposals(&dispute_game_factory, &op_node_provider, &cl_node_provider)
+        let scan = kailua_db.load_proposals(
+            &dispute_game_factory,
+            correctness_oracle.as_ref(),
+            &cl_node_provider,
+        );
+        match with_scan_deadline(Duration::from_secs(args.core.scan_timeout), scan).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(err)) => {
+                error!("Failed to load proposals: {err:?}");
+                continue;
+            }
+            Err(err) => {
+                error!("{err:?}");
+                continue;
+            }
+        }
+        // learn of participation bond updates before they revert the next proposal
+        match bond_monitor
+            .watch(
+                &kailua_db
+                    .treasury
+                    .treasury_contract_instance(&proposer_provider),
+            )
+            .await
+        {
+            Ok(Some(bond_value)) => kailua_db.treasury.participation_bond = bond_value,
+            Ok(None) => {}
+            Err(err) => warn!("Failed to watch participation bond updates: {err:?}"),
+        }
+        // forget resolved history beyond the retention window
+        if let Err(err) = kailua_db
+            .prune_resolved_proposals(&args.core.retention, &proposer_provider)
             .await
-            .context("load_proposals")?;
+        {
+            warn!("Failed to prune resolved proposals: {err:?}");
+        }
 
         // Stack unresolved ancestors
-        let mut unresolved_proposal_indices = kailua_db
+        let mut unresolved_proposal_indices = match kailua_db
             .unresolved_canonical_proposals(&proposer_provider)
-            .await?;
+            .await
+        {
+            Ok(indices) => indices,
+            Err(err) => {
+                error!("Failed to find unresolved proposals: {err:?}");
+                continue;
+            }
+        };
         // Resolve in reverse order
         if !unresolved_proposal_indices.is_empty() {
             info!(
@@ -132,9 +242,19 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
                 unresolved_proposal_indices.len()
             );
         }
+        let mut resolvable_proposals = Vec::new();
         while let Some(proposal_index) = unresolved_proposal_indices.pop() {
-            let proposal = kailua_db.get_local_proposal(&proposal_index).unwrap();
-            let parent = kailua_db.get_local_proposal(&proposal.parent).unwrap();
+            let Some(proposal) = kailua_db.get_local_proposal(&proposal_index) else {
+                error!("Proposal {proposal_index} missing from database.");
+                continue;
+            };
+            let Some(parent) = kailua_db.get_local_proposal(&proposal.parent) else {
+                error!(
+                    "Proposal {proposal_index} parent {} missing from database.",
+                    proposal.parent
+                );
+                continue;
+            };
             let parent_contract = parent.tournament_contract_instance(&proposer_provider);
             info!("Parent Tournament Children:");
             for i in 0..u64::MAX {
@@ -149,15 +269,23 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
                 }
             }
 
-            let proposal = kailua_db.get_local_proposal(&proposal_index).unwrap();
             // Skip resolved games
-            if proposal
-                .fetch_finality(&proposer_provider)
-                .await?
-                .unwrap_or_default()
-            {
-                info!("Reached resolved ancestor proposal.");
-                continue;
+            match proposal.fetch_finality(&proposer_provider).await {
+                Ok(Some(true)) => {
+                    info!("Reached resolved ancestor proposal.");
+                    let resolved = ProposalStatus::Resolved {
+                        defender_wins: true,
+                    };
+                    kailua_db
+                        .transition_local_proposal(proposal_index, resolved)
+                        .context("Failed to mark proposal as resolved")?;
+                    continue;
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    warn!("Failed to fetch finality of proposal {proposal_index}: {err:?}");
+                    continue;
+                }
             }
 
             // Check if claim won in tournament
@@ -173,22 +301,77 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
             }
 
             // Check for timeout
-            let challenger_duration = proposal
+            let challenger_duration = match proposal
                 .fetch_current_challenger_duration(&proposer_provider)
-                .await?;
+                .await
+            {
+                Ok(challenger_duration) => challenger_duration,
+                Err(err) => {
+                    warn!(
+                        "Failed to fetch challenger duration of proposal {proposal_index}: {err:?}"
+                    );
+                    break;
+                }
+            };
             if challenger_duration > 0 {
                 info!("Waiting for {challenger_duration} more seconds before resolution.");
                 break;
             }
 
-            // resolve
-            info!(
-                "Resolving game at index {} and height {}.",
-                proposal.index, proposal.output_block_number
-            );
+            // hold back this and all dependent resolutions until a human approves them
+            if let Some(approval_gate) = approval_gate.as_mut() {
+                let intent = Intent::new(
+                    IntentKind::Resolve,
+                    proposal_index,
+                    proposal.contract,
+                    format!(
+                        "Resolve proposal {proposal_index} of output {} at l2 block {}",
+                        proposal.output_root, proposal.output_block_number
+                    ),
+                );
+                match approval_gate.approve(&intent).await {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(err) => {
+                        warn!("Failed to queue resolution intent for approval: {err:?}");
+                        break;
+                    }
+                }
+            }
 
-            if let Err(e) = proposal.resolve(&proposer_provider).await {
-                error!("Failed to resolve proposal: {e:?}");
+            // queue for resolution
+            resolvable_proposals.push(proposal);
+        }
+        // Resolve in dependency order
+        if !resolvable_proposals.is_empty() {
+            match resolve_proposals(&resolvable_proposals, &proposer_provider, &args.resolve).await
+            {
+                Ok(resolved_count) => {
+                    info!("Resolved {resolved_count} proposals.");
+                    for proposal in resolvable_proposals.iter().take(resolved_count) {
+                        // record the outcome the game actually resolved to
+                        let defender_wins = match proposal.fetch_finality(&proposer_provider).await
+                        {
+                            Ok(Some(defender_wins)) => defender_wins,
+                            Ok(None) => {
+                                warn!("Proposal {} remains unresolved.", proposal.index);
+                                continue;
+                            }
+                            Err(err) => {
+                                warn!(
+                                    "Failed to fetch finality of proposal {}: {err:?}",
+                                    proposal.index
+                                );
+                                continue;
+                            }
+                        };
+                        let resolved = ProposalStatus::Resolved { defender_wins };
+                        kailua_db
+                            .transition_local_proposal(proposal.index, resolved)
+                            .context("Failed to mark proposal as resolved")?;
+                    }
+                }
+                Err(e) => error!("Failed to resolve proposals: {e:?}"),
             }
         }
 
@@ -197,21 +380,54 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
             warn!("No canonical proposal chain to extend!");
             continue;
         };
-        // Query op-node to get latest safe l2 head
-        let sync_status = op_node_provider.sync_status().await?;
-        debug!("sync_status[safe_l2] {:?}", &sync_status["safe_l2"]);
-        let output_block_number = sync_status["safe_l2"]["number"].as_u64().unwrap();
+        if last_canonical_tip.replace(canonical_tip.index) != Some(canonical_tip.index) {
+            info!(
+                "Canonical tip is proposal {} at l2 block {}.",
+                canonical_tip.index, canonical_tip.output_block_number
+            );
+        }
+        // Wait for the blob data of the tip to be included in a sufficiently safe L1 block
+        let is_included = match args
+            .height_guard
+            .is_included(&proposer_provider, &canonical_tip)
+            .await
+        {
+            Ok(is_included) => is_included,
+            Err(err) => {
+                warn!("Failed to check inclusion of canonical tip: {err:?}");
+                continue;
+            }
+        };
+        if !is_included {
+            info!(
+                "Waiting for {:?} L1 block to include canonical tip {}.",
+                args.height_guard.parent_inclusion, canonical_tip.index
+            );
+            continue;
+        }
+        // Query op-node to get latest safe (or finalized) l2 head
+        let sync_status = match op_node_provider.sync_status().await {
+            Ok(sync_status) => sync_status,
+            Err(err) => {
+                warn!("Failed to fetch op-node sync status: {err:?}");
+                continue;
+            }
+        };
+        debug!("sync_status {:?}", &sync_status);
+        let output_block_number = args.height_guard.head_number(&sync_status)?;
         if output_block_number < canonical_tip.output_block_number {
             warn!(
-                "op-node is still {} blocks behind safe l2 head.",
-                canonical_tip.output_block_number - output_block_number
+                "op-node is still {} blocks behind {:?} l2 head.",
+                canonical_tip.output_block_number - output_block_number,
+                args.height_guard.proposal_head
             );
             continue;
         } else if output_block_number - canonical_tip.output_block_number
             < kailua_db.config.proposal_block_count
         {
             info!(
-                "Waiting for safe l2 head to advance by {} more blocks before submitting proposal.",
+                "Waiting for {:?} l2 head to advance by {} more blocks before submitting proposal.",
+                args.height_guard.proposal_head,
                 kailua_db.config.proposal_block_count
                     - (output_block_number - canonical_tip.output_block_number)
             );
@@ -220,16 +436,23 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
         // Wait for L1 timestamp to advance beyond the safety gap for proposals
         let proposed_block_number =
             canonical_tip.output_block_number + kailua_db.config.proposal_block_count;
-        let chain_time = proposer_provider
+        let chain_time = match proposer_provider
             .get_block(
                 BlockId::Number(BlockNumberOrTag::Latest),
                 BlockTransactionsKind::Hashes,
             )
             .await
-            .context("get_block")?
-            .expect("Could not fetch latest L1 block")
-            .header()
-            .timestamp();
+        {
+            Ok(Some(block)) => block.header().timestamp(),
+            Ok(None) => {
+                warn!("Could not fetch latest L1 block.");
+                continue;
+            }
+            Err(err) => {
+                warn!("Failed to fetch latest L1 block: {err:?}");
+                continue;
+            }
+        };
         if !kailua_db
             .config
             .allows_proposal(proposed_block_number, chain_time)
@@ -240,17 +463,28 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
             continue;
         }
 
-        // Prepare proposal
-        let proposed_output_root = op_node_provider
-            .output_at_block(proposed_block_number)
-            .await?;
-        // Prepare intermediate outputs
-        let mut io_field_elements = vec![];
-        let first_io_number = canonical_tip.output_block_number + 1;
-        for i in first_io_number..proposed_block_number {
-            let output = op_node_provider.output_at_block(i).await?;
-            io_field_elements.push(hash_to_fe(output));
-        }
+        // Prepare proposal along with its intermediate outputs
+        let proposal_outputs = async {
+            let proposed_output_root = op_node_provider
+                .output_at_block(proposed_block_number)
+                .await?;
+            let mut io_field_elements = vec![];
+            for i in 0..kailua_db.config.proposal_output_count() - 1 {
+                let io_number = kailua_db
+                    .config
+                    .output_block_number(canonical_tip.output_block_number, i);
+                let output = op_node_provider.output_at_block(io_number).await?;
+                io_field_elements.push(kailua_db.config.field_encoding.output_to_fe(output));
+            }
+            anyhow::Ok((proposed_output_root, io_field_elements))
+        };
+        let (proposed_output_root, io_field_elements) = match proposal_outputs.await {
+            Ok(outputs) => outputs,
+            Err(err) => {
+                warn!("Failed to fetch proposal outputs: {err:?}");
+                continue;
+            }
+        };
         let sidecar = Proposal::create_sidecar(&io_field_elements)?;
 
         // Calculate required duplication counter
@@ -309,33 +543,86 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
             .fetch_balance(&proposer_provider, proposer_address)
             .await?;
         let balance = proposer_provider.get_balance(proposer_address).await?;
-        let owed_collateral = bond_value.saturating_sub(paid_in);
-        if balance < owed_collateral {
-            error!("INSUFFICIENT BALANCE! Need to lock in at least {owed_collateral}.");
-            continue;
-        }
-        // Submit proposal
-        info!("Proposing output {proposed_output_root} at l2 block number {proposed_block_number} with {owed_collateral} additional collateral and duplication counter {dupe_counter}.");
-        match kailua_db
+        let owed_collateral = match bond_monitor.owed_collateral(bond_value, paid_in, balance) {
+            Ok(owed_collateral) => owed_collateral,
+            Err(err) => {
+                error!("{err}");
+                continue;
+            }
+        };
+        let treasury_contract = kailua_db
             .treasury
-            .treasury_contract_instance(&proposer_provider)
-            .propose(proposed_output_root, Bytes::from(extra_data))
+            .treasury_contract_instance(&proposer_provider);
+        let propose_call = treasury_contract
+            .propose(proposed_output_root, Bytes::from(extra_data.clone()))
             .value(owed_collateral)
-            .sidecar(sidecar)
-            .send()
-            .await
-            .context("propose (send)")
+            .sidecar(sidecar);
+        // Simulate the proposal to avoid submitting a transaction that will revert
+        if let Err(err) = propose_call.call().block(BlockId::pending()).await {
+            // Another submission of the same proposal may be pending inclusion
+            let pending_game_address = dispute_game_factory
+                .games(
+                    KAILUA_GAME_TYPE,
+                    proposed_output_root,
+                    Bytes::from(extra_data),
+                )
+                .block(BlockId::pending())
+                .stall()
+                .await
+                .proxy_;
+            if pending_game_address.is_zero() {
+                error!("Aborting proposal that fails simulation: {err:?}");
+            } else {
+                info!("Proposal was already made at {pending_game_address}.");
+            }
+            continue;
+        }
+        // Hold back proposals that implicitly challenge a sibling until a human approves them
+        if let (Some(approval_gate), Some(sibling)) =
+            (approval_gate.as_mut(), canonical_tip.children.last())
         {
-            Ok(txn) => match txn.get_receipt().await.context("propose (get_receipt)") {
-                Ok(receipt) => {
-                    info!("Proposal submitted: {receipt:?}")
-                }
-                Err(e) => {
-                    error!("Failed to confirm proposal txn: {e:?}");
+            let Some(sibling) = kailua_db.get_local_proposal(sibling) else {
+                error!("Proposal {sibling} missing from database.");
+                continue;
+            };
+            let intent = Intent::new(
+                IntentKind::Challenge,
+                sibling.index,
+                sibling.contract,
+                format!(
+                    "Challenge proposal {} by proposing output {proposed_output_root} at l2 block {proposed_block_number} with duplication counter {dupe_counter}",
+                    sibling.index
+                ),
+            );
+            match approval_gate.approve(&intent).await {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(err) => {
+                    warn!("Failed to queue challenge intent for approval: {err:?}");
+                    continue;
                 }
-            },
+            }
+        }
+        // Submit proposal, keeping challenges of siblings out of the public mempool
+        info!("Proposing output {proposed_output_root} at l2 block number {proposed_block_number} with {owed_collateral} additional collateral and duplication counter {dupe_counter}.");
+        let is_challenge = !canonical_tip.children.is_empty();
+        match send_private_transaction(
+            propose_call.into_transaction_request(),
+            proposer_address,
+            private_txn_provider.as_ref().filter(|_| is_challenge),
+            bundle_txn_provider.as_ref().filter(|_| is_challenge),
+            &proposer_provider,
+            &proposer_wallet,
+            &args.private_txn,
+        )
+        .await
+        .context("propose")
+        {
+            Ok(receipt) => {
+                info!("Proposal submitted: {receipt:?}")
+            }
             Err(e) => {
-                error!("Failed to send proposal txn: {e:?}");
+                error!("Failed to submit proposal txn: {e:?}");
             }
         }
     }
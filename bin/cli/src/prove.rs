@@ -0,0 +1,315 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::address_book::{AddressArg, AddressBook, AddressBookArgs};
+use crate::db::config::Config;
+use crate::db::proposal::Proposal;
+use crate::host_service::kailua_host_command;
+use crate::providers::beacon::BlobProvider;
+use crate::providers::optimism::OpNodeProvider;
+use crate::stall::Stall;
+use crate::validate::{match_kzg_proofs, proof_request};
+use crate::KAILUA_GAME_TYPE;
+use alloy::eips::BlockId;
+use alloy::network::{EthereumWallet, Network};
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::signers::local::LocalSigner;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use kailua_client::proof::Proof;
+use kailua_common::journal::ProofJournal;
+use kailua_contracts::*;
+use risc0_zkvm::is_dev_mode;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::{info, warn};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ProveArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the OP-NODE endpoint to use
+    #[clap(long, env)]
+    pub op_node_url: String,
+    /// Address of the OP-GETH endpoint to use (eth and debug namespace required).
+    #[clap(long, env)]
+    pub op_geth_url: String,
+    /// Address of the ethereum rpc endpoint to use (eth namespace required)
+    #[clap(long, env)]
+    pub eth_rpc_url: String,
+    /// Address of the L1 Beacon API endpoint to use.
+    #[clap(long, env)]
+    pub beacon_rpc_url: String,
+
+    /// Address, address book alias or ENS name of the KailuaGame instance to prove
+    #[clap(long)]
+    pub game: AddressArg,
+    /// Address of the sibling KailuaGame instance to prove the game against, instead of the
+    /// earliest sibling it diverges from
+    #[clap(long)]
+    pub contender: Option<AddressArg>,
+    /// Hash of an L1 block to derive the proven outputs from instead of the game's L1 head.
+    /// Proofs against any other head can not be submitted to the game.
+    #[clap(long)]
+    pub l1_head_override: Option<B256>,
+
+    /// Path to the kailua host binary to use for proving, instead of the host embedded in
+    /// kailua-cli
+    #[clap(long, env)]
+    pub kailua_host: Option<PathBuf>,
+    /// Directory to use for caching data
+    #[clap(long, env)]
+    pub data_dir: PathBuf,
+    /// Secret key of L1 wallet to submit the proof with, if it should be submitted
+    #[clap(long, env)]
+    pub prover_key: Option<String>,
+
+    #[clap(flatten)]
+    pub address_book: AddressBookArgs,
+}
+
+/// Returns the addresses of all children of the tournament, in order
+pub async fn fetch_children<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    tournament: &KailuaTournamentInstance<T, P, N>,
+) -> Vec<Address> {
+    let mut children = Vec::new();
+    // Reading past the end of the children array reverts
+    while let Ok(child) = tournament.children(U256::from(children.len())).call().await {
+        children.push(child._0);
+    }
+    children
+}
+
+pub async fn prove(args: ProveArgs) -> anyhow::Result<()> {
+    let eth_rpc_provider = ProviderBuilder::new().on_http(args.eth_rpc_url.as_str().try_into()?);
+    let op_geth_provider = ProviderBuilder::new().on_http(args.op_geth_url.as_str().try_into()?);
+    let op_node_provider =
+        OpNodeProvider::new(ProviderBuilder::new().on_http(args.op_node_url.as_str().try_into()?));
+    let blob_provider = BlobProvider::new(&args.beacon_rpc_url).await?;
+    let address_book = AddressBook::load(&args.address_book)?;
+    let game_address = address_book.resolve(&args.game, &eth_rpc_provider).await?;
+    let contender_address = address_book
+        .resolve_opt(&args.contender, &eth_rpc_provider)
+        .await?;
+
+    // Read the boot parameters from the game contracts
+    let tournament = KailuaTournament::new(game_address, &eth_rpc_provider);
+    let game_type = tournament.gameType().stall().await.gameType_;
+    if game_type != KAILUA_GAME_TYPE {
+        bail!(
+            "Game {} is of type {game_type} instead of {KAILUA_GAME_TYPE}.",
+            game_address
+        );
+    }
+    let parent_address = tournament.parentGame().stall().await.parentGame_;
+    if parent_address == game_address {
+        bail!("Game {} is a treasury instance.", game_address);
+    }
+    let dispute_game_factory = IDisputeGameFactory::new(
+        tournament.disputeGameFactory().stall().await.factory_,
+        &eth_rpc_provider,
+    );
+    let config = Config::load(&KailuaGame::new(
+        dispute_game_factory
+            .gameImpls(KAILUA_GAME_TYPE)
+            .stall()
+            .await
+            .impl_,
+        &eth_rpc_provider,
+    ))
+    .await?;
+    let parent = KailuaTournament::new(parent_address, &eth_rpc_provider);
+    let parent_proposal = Proposal::load(&config, &blob_provider, &parent).await?;
+    let proposal = Proposal::load(&config, &blob_provider, &tournament).await?;
+
+    // Pick the match to prove
+    let children = fetch_children(&parent).await;
+    let child_index = |address: Address| {
+        children
+            .iter()
+            .position(|child| *child == address)
+            .map(|index| index as u64)
+            .context(format!("{address} is not a child of {parent_address}"))
+    };
+    let proposal_child_index = child_index(game_address)?;
+    let contender = match contender_address {
+        Some(contender) => {
+            Proposal::load(
+                &config,
+                &blob_provider,
+                &KailuaTournament::new(contender, &eth_rpc_provider),
+            )
+            .await?
+        }
+        None => {
+            let mut diverging = None;
+            for sibling in children.iter().filter(|child| **child != game_address) {
+                let sibling = Proposal::load(
+                    &config,
+                    &blob_provider,
+                    &KailuaTournament::new(*sibling, &eth_rpc_provider),
+                )
+                .await?;
+                if sibling.divergence_point(&proposal).is_some() {
+                    diverging = Some(sibling);
+                    break;
+                }
+            }
+            diverging.context(format!("No sibling of {} diverges from it.", game_address))?
+        }
+    };
+    let contender_child_index = child_index(contender.contract)?;
+    let Some(challenge_position) = contender.divergence_point(&proposal) else {
+        bail!(
+            "Game {} does not diverge from {}.",
+            game_address,
+            contender.contract
+        );
+    };
+    // Matches are proven with the earlier child on the left
+    let (u, v) = if contender_child_index < proposal_child_index {
+        (&contender, &proposal)
+    } else {
+        (&proposal, &contender)
+    };
+    let (u_index, v_index) = (
+        contender_child_index.min(proposal_child_index),
+        contender_child_index.max(proposal_child_index),
+    );
+    let proof_status = parent
+        .proofStatus(U256::from(u_index), U256::from(v_index))
+        .stall()
+        .await
+        ._0;
+    if proof_status != 0 {
+        warn!("Match between children {u_index} and {v_index} is already proven: {proof_status}");
+    }
+    info!(
+        "Proving match between {} and {} over output {challenge_position}.",
+        u.contract, v.contract
+    );
+
+    // Prove the divergent output using kailua-host
+    let mut request = proof_request(
+        u,
+        v,
+        &eth_rpc_provider,
+        &op_geth_provider,
+        &op_node_provider,
+        config.output_block_span,
+    )
+    .await?;
+    if let Some(l1_head) = args.l1_head_override {
+        if l1_head != request.l1_head {
+            if args.prover_key.is_some() {
+                bail!(
+                    "Refusing to submit a proof derived from {l1_head} instead of the L1 head {} of the game.",
+                    request.l1_head
+                );
+            }
+            warn!(
+                "Proving against L1 head {l1_head} instead of {}. The proof will not be accepted by the game.",
+                request.l1_head
+            );
+        }
+        request.l1_head = l1_head;
+    }
+    let proof_file_name = request.proof_file_name();
+    if Path::new(&proof_file_name).exists() {
+        info!("Proving skipped. Proof file {proof_file_name} already exists.");
+    } else {
+        let mut proving_args = request.kailua_host_args(
+            config.l2_chain_id,
+            &args.eth_rpc_url,
+            &args.beacon_rpc_url,
+            &args.op_geth_url,
+            &args.op_node_url,
+            &args.data_dir,
+        )?;
+        if args.v > 0 {
+            proving_args.push(format!("-{}", "v".repeat(args.v as usize)));
+        }
+        let mut kailua_host_command = kailua_host_command(args.kailua_host.as_deref())?;
+        if is_dev_mode() {
+            kailua_host_command.env("RISC0_DEV_MODE", "1");
+        }
+        kailua_host_command.args(proving_args);
+        info!("Proving {proof_file_name} using kailua-host.");
+        let status = kailua_host_command
+            .kill_on_drop(true)
+            .status()
+            .await
+            .context("Invoking kailua-host")?;
+        if !status.success() || !Path::new(&proof_file_name).exists() {
+            bail!("Proving task failure.");
+        }
+    }
+    let proof = Proof::decode(&tokio::fs::read(&proof_file_name).await?)?;
+    let proof_journal = ProofJournal::decode_packed(proof.journal().as_ref())?;
+    info!("Proof journal: {proof_journal:?}");
+
+    // Submit the proof if a wallet was provided
+    let Some(prover_key) = &args.prover_key else {
+        info!("Proof saved to {proof_file_name}.");
+        return Ok(());
+    };
+    if proof_status != 0 {
+        bail!("Refusing to submit a proof for an already proven match.");
+    }
+    let prover_wallet = EthereumWallet::from(LocalSigner::from_str(prover_key)?);
+    let prover_provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(&prover_wallet)
+        .on_http(args.eth_rpc_url.as_str().try_into()?);
+    let (commitments, proofs) = match_kzg_proofs(
+        u,
+        v,
+        challenge_position as u64,
+        proof_journal.claimed_l2_block_number,
+    )?;
+    let prove_call = KailuaTournament::new(parent_proposal.contract, &prover_provider).prove(
+        [u_index, v_index, challenge_position as u64],
+        Bytes::from(proof.encoded_seal()?),
+        proof_journal.agreed_l2_output_root,
+        [
+            u.output_at(challenge_position as u64),
+            v.output_at(challenge_position as u64),
+        ],
+        proof_journal.claimed_l2_output_root,
+        commitments,
+        proofs,
+    );
+    prove_call
+        .call()
+        .block(BlockId::pending())
+        .await
+        .context("Proof submission fails simulation")?;
+    let receipt = prove_call
+        .send()
+        .await
+        .context("KailuaTournament::prove (send)")?
+        .get_receipt()
+        .await
+        .context("KailuaTournament::prove (get_receipt)")?;
+    info!("Proof submitted: {receipt:?}");
+    let proof_status = parent
+        .proofStatus(U256::from(u_index), U256::from(v_index))
+        .stall()
+        .await
+        ._0;
+    info!("Match between children {u_index} and {v_index} proven: {proof_status}");
+    Ok(())
+}
@@ -12,100 +12,57 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::shedding::LoadShedder;
 use alloy::consensus::{Blob, BlobTransactionSidecar};
-use alloy::eips::eip4844::{kzg_to_versioned_hash, BLS_MODULUS, FIELD_ELEMENTS_PER_BLOB};
+use alloy::eips::eip4844::{BLS_MODULUS, FIELD_ELEMENTS_PER_BLOB};
 use alloy::primitives::{B256, U256};
-use alloy::providers::{Provider, ProviderBuilder, ReqwestProvider};
-use alloy_rpc_types_beacon::sidecar::{BeaconBlobBundle, BlobData};
-use anyhow::{bail, Context};
-use serde::de::DeserializeOwned;
-use serde_json::Value;
+use alloy_rpc_types_beacon::sidecar::BlobData;
+use anyhow::bail;
+use kailua_host::beacon::BeaconClient;
 use std::ops::{Div, Sub};
-use tracing::debug;
+use std::path::Path;
+use std::time::Instant;
 
 #[derive(Clone, Debug)]
 pub struct BlobProvider {
-    pub cl_node_provider: ReqwestProvider,
-    pub genesis_time: u64,
-    pub seconds_per_slot: u64,
+    pub beacon_client: BeaconClient,
+    pub shedder: Option<LoadShedder>,
 }
 
 impl BlobProvider {
     pub async fn new(url: &str) -> anyhow::Result<Self> {
-        let cl_node_provider = ProviderBuilder::new().on_http(url.try_into()?);
-        let genesis =
-            Self::provider_get::<Value>(&cl_node_provider, "eth/v1/beacon/genesis").await?;
-        debug!("genesis {:?}", &genesis);
-        let genesis_time = genesis["data"]["genesis_time"]
-            .as_str()
-            .unwrap()
-            .parse::<u64>()?;
-        let spec = Self::provider_get::<Value>(&cl_node_provider, "eth/v1/config/spec").await?;
-        debug!("spec {:?}", &spec);
-        let seconds_per_slot = spec["data"]["SECONDS_PER_SLOT"]
-            .as_str()
-            .unwrap()
-            .parse::<u64>()?;
         Ok(Self {
-            cl_node_provider,
-            genesis_time,
-            seconds_per_slot,
+            beacon_client: BeaconClient::new(url).await?,
+            shedder: None,
         })
     }
 
-    pub fn provider_url(provider: &ReqwestProvider) -> &str {
-        provider.client().transport().url().trim_end_matches('/')
+    /// Reports the outcomes of blob requests to the given load shedder
+    pub fn with_load_shedder(mut self, shedder: LoadShedder) -> Self {
+        self.shedder = Some(shedder);
+        self
     }
 
-    pub fn url(&self) -> &str {
-        self.cl_node_provider
-            .client()
-            .transport()
-            .url()
-            .trim_end_matches('/')
-    }
-
-    pub fn slot(&self, timestamp: u64) -> u64 {
-        (timestamp - self.genesis_time) / self.seconds_per_slot
+    pub fn with_slot_cache(mut self, path: &Path) -> anyhow::Result<Self> {
+        self.beacon_client = self.beacon_client.with_slot_cache(path)?;
+        Ok(self)
     }
 
-    pub async fn provider_get<T: DeserializeOwned>(
-        provider: &ReqwestProvider,
-        path: &str,
-    ) -> anyhow::Result<T> {
-        provider
-            .client()
-            .transport()
-            .client()
-            .get(format!("{}/{}", Self::provider_url(provider), path))
-            .send()
-            .await
-            .context("get")?
-            .json::<T>()
-            .await
-            .context("json")
+    pub fn url(&self) -> &str {
+        self.beacon_client.url()
     }
 
-    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> anyhow::Result<T> {
-        Self::provider_get(&self.cl_node_provider, path).await
+    pub fn slot(&self, timestamp: u64) -> anyhow::Result<u64> {
+        self.beacon_client.slot(timestamp)
     }
 
     pub async fn get_blob(&self, timestamp: u64, blob_hash: B256) -> anyhow::Result<BlobData> {
-        let slot = self.slot(timestamp);
-        let blobs = self
-            .get::<BeaconBlobBundle>(&format!("eth/v1/beacon/blob_sidecars/{slot}"))
-            .await
-            .context(format!("blob_sidecars {slot}"))?;
-
-        let blob_count = blobs.len();
-        for blob in blobs {
-            let versioned_hash = kzg_to_versioned_hash(blob.kzg_commitment.as_slice());
-            if versioned_hash == blob_hash {
-                return Ok(blob);
-            }
+        let start = Instant::now();
+        let result = self.beacon_client.get_blob(timestamp, blob_hash).await;
+        if let Some(shedder) = &self.shedder {
+            shedder.record("beacon", start.elapsed(), result.is_ok());
         }
-
-        bail!("Blob {blob_hash} @ {timestamp} not found in slot ({blob_count} blobs found)!");
+        result
     }
 }
 
@@ -0,0 +1,295 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::shedding::LoadShedder;
+use crate::stall::report_call_latencies;
+use alloy::providers::{ProviderBuilder, RootProvider};
+use alloy::rpc::client::RpcClient;
+use alloy::rpc::json_rpc::{RequestPacket, ResponsePacket};
+use alloy::transports::http::{reqwest, Http};
+use alloy::transports::{TransportError, TransportFut};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::task::Poll;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tower::Service;
+use tracing::{info, warn};
+
+/// The largest factor by which polling is slowed down when exceeding the daily request budget
+pub const MAX_THROTTLE_FACTOR: u32 = 16;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct RpcBudgetArgs {
+    /// Estimated cost in USD of one million rpc requests, used to report spending
+    #[clap(long, env, default_value_t = 0.0)]
+    pub rpc_cost_per_million: f64,
+    /// Number of rpc requests to issue per (UTC) day before polling is slowed down
+    #[clap(long, env)]
+    pub rpc_daily_budget: Option<u64>,
+    /// Number of seconds between rpc usage summaries
+    #[clap(long, env, default_value_t = 600)]
+    pub rpc_report_interval: u64,
+}
+
+impl Default for RpcBudgetArgs {
+    fn default() -> Self {
+        Self {
+            rpc_cost_per_million: 0.0,
+            rpc_daily_budget: None,
+            rpc_report_interval: 600,
+        }
+    }
+}
+
+/// Request counters of a single rpc endpoint
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EndpointUsage {
+    /// Requests issued since the start of the current day
+    pub daily_requests: u64,
+    /// Requests issued since the process started
+    pub session_requests: u64,
+}
+
+/// Request counters of all metered endpoints, persisted to disk on every summary
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RpcUsage {
+    /// Days since the unix epoch at which the daily counters started
+    pub day: u64,
+    pub endpoints: BTreeMap<String, EndpointUsage>,
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    #[serde(skip)]
+    pub last_report: Option<Instant>,
+}
+
+impl RpcUsage {
+    pub fn daily_requests(&self) -> u64 {
+        self.endpoints.values().map(|e| e.daily_requests).sum()
+    }
+
+    /// Resets the daily counters if a new day started
+    pub fn roll_over(&mut self, day: u64) {
+        if self.day != day {
+            self.day = day;
+            self.endpoints
+                .values_mut()
+                .for_each(|usage| usage.daily_requests = 0);
+        }
+    }
+}
+
+/// Shared accounting of the rpc requests issued through [MeteredTransport] instances
+#[derive(Clone, Debug, Default)]
+pub struct RpcMeter {
+    /// The budget, shared with all transports so that reloaded settings apply to them at once
+    pub args: Arc<RwLock<RpcBudgetArgs>>,
+    pub usage: Arc<Mutex<RpcUsage>>,
+    /// Scores the health of the endpoints from the outcomes of their requests
+    pub shedder: Option<LoadShedder>,
+}
+
+impl RpcMeter {
+    pub fn new(args: RpcBudgetArgs) -> Self {
+        Self {
+            args: Arc::new(RwLock::new(args)),
+            usage: Default::default(),
+            shedder: None,
+        }
+    }
+
+    /// Replaces the budget of this meter and all transports created from it
+    pub fn set_budget(&self, args: RpcBudgetArgs) {
+        *self.args.write().unwrap() = args;
+    }
+
+    /// Persists request counters to the given file so that the daily budget survives restarts
+    pub fn with_usage_file(self, path: &Path) -> anyhow::Result<Self> {
+        {
+            let mut usage = self.usage.lock().unwrap();
+            if let Ok(data) = std::fs::read(path) {
+                match serde_json::from_slice::<RpcUsage>(&data) {
+                    Ok(mut persisted) => {
+                        persisted
+                            .endpoints
+                            .values_mut()
+                            .for_each(|usage| usage.session_requests = 0);
+                        *usage = persisted;
+                    }
+                    Err(err) => warn!("Discarding unreadable rpc usage file {path:?}: {err:?}"),
+                }
+            }
+            usage.roll_over(today());
+            usage.path = Some(path.to_path_buf());
+        }
+        Ok(self)
+    }
+
+    /// Reports the outcomes of all requests issued through clients created from this meter to
+    /// the given load shedder
+    pub fn with_load_shedder(mut self, shedder: LoadShedder) -> Self {
+        self.shedder = Some(shedder);
+        self
+    }
+
+    /// Creates an rpc client for the given url whose requests are accounted under `endpoint`
+    pub fn client(&self, endpoint: &str, url: &str) -> anyhow::Result<RpcClient<MeteredTransport>> {
+        let transport = Http::new(url.try_into().context(format!("Invalid url {endpoint}"))?);
+        let is_local = transport.guess_local();
+        Ok(RpcClient::new(
+            MeteredTransport {
+                inner: transport,
+                endpoint: endpoint.to_string(),
+                meter: self.clone(),
+            },
+            is_local,
+        ))
+    }
+
+    /// Creates a provider for the given url whose requests are accounted under `endpoint`
+    pub fn provider(&self, endpoint: &str, url: &str) -> anyhow::Result<MeteredProvider> {
+        Ok(ProviderBuilder::new().on_client(self.client(endpoint, url)?))
+    }
+
+    pub fn record(&self, endpoint: &str, requests: u64) {
+        let mut usage = self.usage.lock().unwrap();
+        usage.roll_over(today());
+        let counters = usage.endpoints.entry(endpoint.to_string()).or_default();
+        counters.daily_requests += requests;
+        counters.session_requests += requests;
+    }
+
+    pub fn estimated_cost(&self, requests: u64) -> f64 {
+        requests as f64 * self.args.read().unwrap().rpc_cost_per_million / 1_000_000.0
+    }
+
+    /// Returns the factor by which polling should be slowed down to stay within the daily budget.
+    ///
+    /// The budget is spread evenly over the day, and polling slows down proportionally to how far
+    /// the requests issued so far exceed the share of the budget that has elapsed.
+    pub fn throttle_factor(&self) -> u32 {
+        let Some(budget) = self.args.read().unwrap().rpc_daily_budget else {
+            return 1;
+        };
+        let daily_requests = self.usage.lock().unwrap().daily_requests();
+        if daily_requests >= budget {
+            return MAX_THROTTLE_FACTOR;
+        }
+        // Allow the first hour's share upfront so that restarts do not immediately throttle
+        let elapsed = (now_secs() % SECONDS_PER_DAY).max(SECONDS_PER_DAY / 24);
+        let allowance = (budget as u128 * elapsed as u128 / SECONDS_PER_DAY as u128).max(1) as u64;
+        (daily_requests.div_ceil(allowance) as u32).clamp(1, MAX_THROTTLE_FACTOR)
+    }
+
+    /// Scales the given polling interval according to the daily request budget
+    pub fn throttle(&self, interval: Duration) -> Duration {
+        interval * self.throttle_factor()
+    }
+
+    /// Logs a summary of rpc usage and persists the counters if the report interval elapsed
+    pub fn report(&self) {
+        let mut usage = self.usage.lock().unwrap();
+        let report_interval = Duration::from_secs(self.args.read().unwrap().rpc_report_interval);
+        if usage
+            .last_report
+            .is_some_and(|last| last.elapsed() < report_interval)
+        {
+            return;
+        }
+        usage.last_report = Some(Instant::now());
+        usage.roll_over(today());
+
+        for (endpoint, counters) in &usage.endpoints {
+            info!(
+                "RPC usage of {endpoint}: {} requests today (${:.2}), {} requests this session (${:.2}).",
+                counters.daily_requests,
+                self.estimated_cost(counters.daily_requests),
+                counters.session_requests,
+                self.estimated_cost(counters.session_requests)
+            );
+        }
+        let daily_requests = usage.daily_requests();
+        let rpc_daily_budget = self.args.read().unwrap().rpc_daily_budget;
+        if let Some(budget) = rpc_daily_budget {
+            let utilization = 100.0 * daily_requests as f64 / budget.max(1) as f64;
+            if daily_requests >= budget {
+                warn!("Daily rpc budget of {budget} requests exhausted ({utilization:.1}%). Polling at minimum frequency.");
+            } else {
+                info!("Daily rpc budget utilization at {utilization:.1}% of {budget} requests.");
+            }
+        }
+
+        report_call_latencies();
+
+        if let Some(path) = &usage.path {
+            if let Err(err) = serde_json::to_vec(&*usage)
+                .map_err(anyhow::Error::from)
+                .and_then(|data| std::fs::write(path, data).map_err(anyhow::Error::from))
+            {
+                warn!("Failed to persist rpc usage to {path:?}: {err:?}");
+            }
+        }
+    }
+}
+
+pub type MeteredProvider = RootProvider<MeteredTransport>;
+
+/// An http transport that accounts every request it issues in an [RpcMeter]
+#[derive(Clone, Debug)]
+pub struct MeteredTransport {
+    pub inner: Http<reqwest::Client>,
+    pub endpoint: String,
+    pub meter: RpcMeter,
+}
+
+impl Service<RequestPacket> for MeteredTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: RequestPacket) -> Self::Future {
+        // Managed providers bill batched calls individually
+        self.meter.record(&self.endpoint, req.len() as u64);
+        let Some(shedder) = self.meter.shedder.clone() else {
+            return self.inner.call(req);
+        };
+        let endpoint = self.endpoint.clone();
+        let response = self.inner.call(req);
+        Box::pin(async move {
+            let start = Instant::now();
+            let result = response.await;
+            shedder.record(&endpoint, start.elapsed(), result.is_ok());
+            result
+        })
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn today() -> u64 {
+    now_secs() / SECONDS_PER_DAY
+}
@@ -13,4 +13,6 @@
 // limitations under the License.
 
 pub mod beacon;
+pub mod metered;
 pub mod optimism;
+pub mod versions;
@@ -12,19 +12,56 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use alloy::primitives::B256;
-use alloy::providers::{Provider, ReqwestProvider};
-use anyhow::Context;
+use alloy::primitives::{keccak256, B256};
+use alloy::providers::{Provider, RootProvider};
+use alloy::transports::{BoxTransport, Transport};
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
 use std::str::FromStr;
-use tracing::debug;
+use std::sync::Mutex;
+use tracing::{debug, warn};
 
-pub struct OpNodeProvider(pub ReqwestProvider);
+pub struct OpNodeProvider {
+    pub provider: RootProvider<BoxTransport>,
+    pub cache: OutputCache,
+}
 
 impl OpNodeProvider {
+    pub fn new<T: Transport + Clone>(provider: RootProvider<T>) -> Self {
+        Self {
+            provider: provider.boxed(),
+            cache: Default::default(),
+        }
+    }
+
+    /// Persists cached output roots to a database under the given directory, keeping them apart
+    /// from those of other op-nodes sharing it
+    pub fn with_cache_dir(mut self, cache_dir: &Path, op_node_url: &str) -> anyhow::Result<Self> {
+        let db = rocksdb::DB::open(&OutputCache::options(), cache_dir)
+            .context("Failed to open output root cache")?;
+        self.cache.disk = Some(db);
+        self.cache.identity = keccak256(op_node_url);
+        Ok(self)
+    }
+
     pub async fn output_at_block(&self, output_block_number: u64) -> anyhow::Result<B256> {
+        let chain_id = match self.cache.chain_id() {
+            Some(chain_id) => chain_id,
+            None => self.refresh_chain_id().await?,
+        };
+        // only outputs at or below the finalized head are cached
+        if self.cache.finalized().is_none() {
+            self.sync_status().await?;
+        }
+        if let Some(cached) = self.cache.get(chain_id, output_block_number)? {
+            return Ok(cached.output_root);
+        }
+
         let output_at_block: serde_json::Value = self
-            .0
+            .provider
             .client()
             .request(
                 "optimism_outputAtBlock",
@@ -33,24 +70,193 @@ impl OpNodeProvider {
             .await
             .context(format!("optimism_outputAtBlock {output_block_number}"))?;
         debug!("optimism_outputAtBlock {:?}", &output_at_block);
-        Ok(B256::from_str(
-            output_at_block["outputRoot"].as_str().unwrap(),
-        )?)
+        let output_root = output_at_block["outputRoot"]
+            .as_str()
+            .context("optimism_outputAtBlock response lacks outputRoot")?;
+        let output_root = B256::from_str(output_root)?;
+        if !self
+            .cache
+            .finalized()
+            .is_some_and(|finalized| output_block_number <= finalized)
+        {
+            return Ok(output_root);
+        }
+        match output_at_block["blockRef"]["hash"].as_str() {
+            Some(block_hash) => self.cache.insert(
+                chain_id,
+                output_block_number,
+                CachedOutput {
+                    block_hash: B256::from_str(block_hash)?,
+                    output_root,
+                },
+            )?,
+            None => warn!("optimism_outputAtBlock {output_block_number} missing block hash"),
+        }
+        Ok(output_root)
     }
 
     pub async fn sync_status(&self) -> anyhow::Result<Value> {
-        Ok(self
-            .0
+        let sync_status: Value = self
+            .provider
             .client()
             .request_noparams("optimism_syncStatus")
-            .await?)
+            .await?;
+        // Track the finalized head and evict cached outputs invalidated by a reorg
+        if let Some(chain_id) = self.cache.chain_id() {
+            self.cache.check_reorg(chain_id, &sync_status)?;
+        }
+        Ok(sync_status)
     }
 
     pub async fn rollup_config(&self) -> anyhow::Result<Value> {
         Ok(self
-            .0
+            .provider
             .client()
             .request_noparams("optimism_rollupConfig")
             .await?)
     }
+
+    /// Re-reads the op-node's sync status to invalidate stale cached outputs. The chain id is only
+    /// read from the rollup configuration once.
+    pub async fn refresh_cache(&self) -> anyhow::Result<()> {
+        if self.cache.chain_id().is_none() {
+            self.refresh_chain_id().await?;
+        }
+        self.sync_status().await?;
+        Ok(())
+    }
+
+    async fn refresh_chain_id(&self) -> anyhow::Result<u64> {
+        let rollup_config = self.rollup_config().await?;
+        let Some(chain_id) = rollup_config["l2_chain_id"].as_u64() else {
+            bail!("optimism_rollupConfig missing l2_chain_id");
+        };
+        self.cache.set_chain_id(chain_id);
+        Ok(chain_id)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedOutput {
+    pub block_hash: B256,
+    pub output_root: B256,
+}
+
+/// Memoized output roots of finalized blocks keyed by the identity of the op-node, its chain id
+/// and the l2 block number
+#[derive(Debug, Default)]
+pub struct OutputCache {
+    /// The hash of the op-node's endpoint, under which its outputs are persisted
+    pub identity: B256,
+    pub chain_id: Mutex<Option<u64>>,
+    /// The latest finalized l2 block number reported by the op-node
+    pub finalized: Mutex<Option<u64>>,
+    pub memory: Mutex<BTreeMap<(u64, u64), CachedOutput>>,
+    pub disk: Option<rocksdb::DB>,
+}
+
+impl OutputCache {
+    pub fn options() -> rocksdb::Options {
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options
+    }
+
+    pub fn key(&self, chain_id: u64, block_number: u64) -> [u8; 48] {
+        let mut key = [0u8; 48];
+        key[..32].copy_from_slice(self.identity.as_slice());
+        key[32..40].copy_from_slice(&chain_id.to_be_bytes());
+        key[40..].copy_from_slice(&block_number.to_be_bytes());
+        key
+    }
+
+    pub fn chain_id(&self) -> Option<u64> {
+        *self.chain_id.lock().unwrap()
+    }
+
+    pub fn set_chain_id(&self, chain_id: u64) {
+        let mut current = self.chain_id.lock().unwrap();
+        if current.is_some_and(|id| id != chain_id) {
+            warn!(
+                "op-node chain id changed from {} to {chain_id}. Invalidating output cache.",
+                current.unwrap()
+            );
+            self.memory.lock().unwrap().clear();
+            self.finalized.lock().unwrap().take();
+        }
+        current.replace(chain_id);
+    }
+
+    pub fn finalized(&self) -> Option<u64> {
+        *self.finalized.lock().unwrap()
+    }
+
+    pub fn get(&self, chain_id: u64, block_number: u64) -> anyhow::Result<Option<CachedOutput>> {
+        let mut memory = self.memory.lock().unwrap();
+        if let Some(cached) = memory.get(&(chain_id, block_number)) {
+            return Ok(Some(*cached));
+        }
+        let Some(db) = &self.disk else {
+            return Ok(None);
+        };
+        let Some(data) = db.get(self.key(chain_id, block_number))? else {
+            return Ok(None);
+        };
+        let cached: CachedOutput = bincode::deserialize(&data)?;
+        memory.insert((chain_id, block_number), cached);
+        Ok(Some(cached))
+    }
+
+    pub fn insert(
+        &self,
+        chain_id: u64,
+        block_number: u64,
+        cached: CachedOutput,
+    ) -> anyhow::Result<()> {
+        if let Some(db) = &self.disk {
+            db.put(
+                self.key(chain_id, block_number),
+                bincode::serialize(&cached)?,
+            )?;
+        }
+        self.memory
+            .lock()
+            .unwrap()
+            .insert((chain_id, block_number), cached);
+        Ok(())
+    }
+
+    /// Removes all cached outputs for blocks at or after the given block number
+    pub fn evict_from(&self, chain_id: u64, block_number: u64) -> anyhow::Result<()> {
+        self.memory
+            .lock()
+            .unwrap()
+            .retain(|(id, number), _| *id != chain_id || *number < block_number);
+        if let Some(db) = &self.disk {
+            db.delete_range(
+                self.key(chain_id, block_number),
+                self.key(chain_id, u64::MAX).as_slice(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Tracks the finalized head reported by the op-node, invalidating the cache if the finalized
+    /// chain no longer matches it
+    pub fn check_reorg(&self, chain_id: u64, sync_status: &Value) -> anyhow::Result<()> {
+        let (Some(number), Some(hash)) = (
+            sync_status["finalized_l2"]["number"].as_u64(),
+            sync_status["finalized_l2"]["hash"].as_str(),
+        ) else {
+            return Ok(());
+        };
+        if let Some(cached) = self.get(chain_id, number)? {
+            if cached.block_hash != B256::from_str(hash)? {
+                warn!("Finalized block {number} differs from the cached output. Invalidating output cache.");
+                self.evict_from(chain_id, 0)?;
+            }
+        }
+        self.finalized.lock().unwrap().replace(number);
+        Ok(())
+    }
 }
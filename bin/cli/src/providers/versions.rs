@@ -0,0 +1,99 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::CoreArgs;
+use alloy::providers::{Provider, ProviderBuilder};
+use tracing::{debug, info, warn};
+
+/// A range of node client versions that disagree with the derivation rules kona implements
+pub struct IncompatibleVersions {
+    /// The endpoint the client serves
+    pub client: &'static str,
+    /// The first compatible version
+    pub minimum: (u64, u64, u64),
+    pub reason: &'static str,
+}
+
+/// Known client versions that derive a different chain than the bundled version of kona
+pub const INCOMPATIBLE_VERSIONS: [IncompatibleVersions; 2] = [
+    IncompatibleVersions {
+        client: "op-node",
+        minimum: (1, 10, 0),
+        reason: "predates holocene derivation",
+    },
+    IncompatibleVersions {
+        client: "op-geth",
+        minimum: (1, 101411, 0),
+        reason: "predates holocene execution",
+    },
+];
+
+/// Extracts the first `major.minor.patch` version triple following a `v` in a version string
+pub fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    version.split(['v', '/', '-']).find_map(|part| {
+        let mut numbers = part.splitn(3, '.').map(|n| n.parse::<u64>().ok());
+        Some((numbers.next()??, numbers.next()??, numbers.next()??))
+    })
+}
+
+/// Warns if the given client version is known to disagree with honest proposers
+pub fn check_version(client: &str, version: &str) {
+    info!("Connected to {client} version {version}.");
+    let Some(parsed) = parse_version(version) else {
+        debug!("Could not parse {client} version {version}.");
+        return;
+    };
+    for incompatible in &INCOMPATIBLE_VERSIONS {
+        if incompatible.client == client && parsed < incompatible.minimum {
+            let (major, minor, patch) = incompatible.minimum;
+            warn!(
+                "INCOMPATIBLE NODE! {client} version {version} {}. Upgrade to at least v{major}.{minor}.{patch} to avoid disagreeing with honest proposers.",
+                incompatible.reason
+            );
+        }
+    }
+}
+
+/// Queries the client versions of all connected nodes and warns about known incompatibilities
+pub async fn probe_node_versions(core: &CoreArgs) {
+    let endpoints = [
+        ("eth-rpc", core.eth_rpc_url.as_str()),
+        ("op-geth", core.op_geth_url.as_str()),
+    ];
+    for (client, url) in endpoints {
+        let Ok(url) = url.try_into() else {
+            continue;
+        };
+        match ProviderBuilder::new()
+            .on_http(url)
+            .get_client_version()
+            .await
+        {
+            Ok(version) => check_version(client, &version),
+            Err(err) => warn!("Failed to query {client} client version: {err:?}"),
+        }
+    }
+    let Ok(url) = core.op_node_url.as_str().try_into() else {
+        return;
+    };
+    let op_node_provider = ProviderBuilder::new().on_http(url);
+    let op_node_version = op_node_provider
+        .client()
+        .request_noparams::<String>("optimism_version")
+        .await;
+    match op_node_version {
+        Ok(version) => check_version("op-node", &version),
+        Err(err) => warn!("Failed to query op-node version: {err:?}"),
+    }
+}
@@ -0,0 +1,497 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::config::Config;
+use crate::db::lifecycle::ProposalStatus;
+use crate::db::proposal::Proposal;
+use crate::db::ProofStatus;
+use crate::equivocation::Equivocation;
+use crate::providers::beacon::BlobProvider;
+use crate::stall::Stall;
+use crate::KAILUA_GAME_TYPE;
+use alloy::primitives::{Address, TxHash, B256, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::Filter;
+use alloy::sol_types::SolEvent;
+use anyhow::{bail, Context};
+use kailua_contracts::*;
+use kailua_host::fetch_rollup_config;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// The directory under the data directory that reindexed records are written to
+pub const REINDEX_DIR: &str = "reindex";
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ReindexArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the OP-NODE endpoint to use
+    #[clap(long, env)]
+    pub op_node_url: String,
+    /// Address of the OP-GETH endpoint to use (eth and debug namespace required).
+    #[clap(long, env)]
+    pub op_geth_url: String,
+    /// Address of the ethereum rpc endpoint to use (eth namespace required)
+    #[clap(long, env)]
+    pub eth_rpc_url: String,
+    /// Address of the L1 Beacon API endpoint to use.
+    #[clap(long, env)]
+    pub beacon_rpc_url: String,
+
+    /// Directory to rebuild the local database in
+    #[clap(long, env)]
+    pub data_dir: PathBuf,
+    /// L1 block to start reindexing from
+    #[clap(long, env, default_value_t = 0)]
+    pub from_block: u64,
+    /// L1 block to stop reindexing at (defaults to the latest block)
+    #[clap(long, env)]
+    pub to_block: Option<u64>,
+    /// Maximum number of L1 blocks to query logs for in a single request
+    #[clap(long, env, default_value_t = 10_000)]
+    pub log_chunk_size: u64,
+    /// Discard any previous reindexing progress and start over from `--from-block`
+    #[clap(long, env)]
+    pub restart: bool,
+}
+
+/// A record reconstructed from an L1 log, stored as one JSON line per record
+pub trait IndexedRecord: Serialize + DeserializeOwned {
+    /// The name of the file under [REINDEX_DIR] that records of this kind are appended to
+    const FILE_NAME: &'static str;
+
+    /// The L1 block in which the log was emitted
+    fn block(&self) -> u64;
+}
+
+/// A proposal as loaded from the chain when its creation was logged
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IndexedProposal {
+    pub block: u64,
+    pub tx: TxHash,
+    pub proposal: Proposal,
+}
+
+impl IndexedRecord for IndexedProposal {
+    const FILE_NAME: &'static str = "proposals.jsonl";
+
+    fn block(&self) -> u64 {
+        self.block
+    }
+}
+
+/// A proof settling a match between two children of a tournament
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IndexedProof {
+    pub block: u64,
+    pub tx: TxHash,
+    /// The factory index of the parent tournament
+    pub parent: u64,
+    /// The factory index of the contender (u)
+    pub contender: u64,
+    /// The factory index of the opponent proposal (v)
+    pub proposal: u64,
+    pub status: u8,
+}
+
+impl IndexedRecord for IndexedProof {
+    const FILE_NAME: &'static str = "proofs.jsonl";
+
+    fn block(&self) -> u64 {
+        self.block
+    }
+}
+
+/// The resolution of a game
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IndexedResolution {
+    pub block: u64,
+    pub tx: TxHash,
+    pub index: u64,
+    pub defender_wins: bool,
+}
+
+impl IndexedRecord for IndexedResolution {
+    const FILE_NAME: &'static str = "resolutions.jsonl";
+
+    fn block(&self) -> u64 {
+        self.block
+    }
+}
+
+/// The extent of the L1 history that was reindexed so far
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReindexProgress {
+    pub dispute_game_factory: Address,
+    pub cfg_hash: B256,
+    pub from_block: u64,
+    /// The first L1 block whose logs were not yet fully indexed
+    pub next_block: u64,
+}
+
+/// The append-only record files of a reindexing run and the progress made through them
+#[derive(Clone, Debug)]
+pub struct ReindexStore {
+    pub dir: PathBuf,
+}
+
+impl ReindexStore {
+    pub fn open(data_dir: &Path) -> anyhow::Result<Self> {
+        let dir = data_dir.join(REINDEX_DIR);
+        std::fs::create_dir_all(&dir).context(format!("Failed to create {dir:?}"))?;
+        Ok(Self { dir })
+    }
+
+    fn progress_path(&self) -> PathBuf {
+        self.dir.join("progress.json")
+    }
+
+    pub fn load_progress(&self) -> anyhow::Result<Option<ReindexProgress>> {
+        match std::fs::read(self.progress_path()) {
+            Ok(data) => Ok(Some(
+                serde_json::from_slice(&data).context("Failed to parse reindexing progress")?,
+            )),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn save_progress(&self, progress: &ReindexProgress) -> anyhow::Result<()> {
+        // write to a temporary file first so that an interrupted save does not lose progress
+        let path = self.progress_path();
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_vec_pretty(progress)?)?;
+        std::fs::rename(&tmp_path, &path).context(format!("Failed to write {path:?}"))
+    }
+
+    pub fn load<R: IndexedRecord>(&self) -> anyhow::Result<Vec<R>> {
+        let path = self.dir.join(R::FILE_NAME);
+        let data = match std::fs::read_to_string(&path) {
+            Ok(data) => data,
+            Err(_) => return Ok(vec![]),
+        };
+        data.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).context(format!("Failed to parse {path:?}")))
+            .collect()
+    }
+
+    pub fn append<R: IndexedRecord>(&self, records: &[R]) -> anyhow::Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let path = self.dir.join(R::FILE_NAME);
+        let mut file = std::io::BufWriter::new(
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .context(format!("Failed to open {path:?}"))?,
+        );
+        for record in records {
+            serde_json::to_writer(&mut file, record)?;
+            writeln!(file)?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Drops the records of an interrupted chunk, returning those that were fully indexed
+    pub fn truncate<R: IndexedRecord>(&self, next_block: u64) -> anyhow::Result<Vec<R>> {
+        let records = self.load::<R>()?;
+        let count = records.len();
+        let records = records
+            .into_iter()
+            .filter(|record| record.block() < next_block)
+            .collect::<Vec<R>>();
+        if records.len() != count {
+            warn!(
+                "Discarding {} {} record(s) of an interrupted run.",
+                count - records.len(),
+                R::FILE_NAME
+            );
+            self.clear::<R>()?;
+            self.append(&records)?;
+        }
+        Ok(records)
+    }
+
+    pub fn clear<R: IndexedRecord>(&self) -> anyhow::Result<()> {
+        match std::fs::remove_file(self.dir.join(R::FILE_NAME)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Applies the indexed proofs and resolutions to the indexed proposals
+    pub fn replay(&self) -> anyhow::Result<BTreeMap<u64, Proposal>> {
+        let mut proposals: BTreeMap<u64, Proposal> = BTreeMap::new();
+        for IndexedProposal { proposal, .. } in self.load::<IndexedProposal>()? {
+            if proposal.has_parent() {
+                if let Some(parent) = proposals.get_mut(&proposal.parent) {
+                    parent.children.push(proposal.index);
+                }
+            }
+            proposals.insert(proposal.index, proposal);
+        }
+        for proof in self.load::<IndexedProof>()? {
+            let Some((contender_wins, proposal_wins)) =
+                ProofStatus::parse(proof.status)?.outcomes()
+            else {
+                continue;
+            };
+            for (index, valid) in [
+                (proof.contender, contender_wins),
+                (proof.proposal, proposal_wins),
+            ] {
+                if let Some(proposal) = proposals.get_mut(&index) {
+                    proposal.status = ProposalStatus::Proven { valid };
+                }
+            }
+        }
+        for resolution in self.load::<IndexedResolution>()? {
+            if let Some(proposal) = proposals.get_mut(&resolution.index) {
+                proposal.status = ProposalStatus::Resolved {
+                    defender_wins: resolution.defender_wins,
+                };
+            }
+        }
+        Ok(proposals)
+    }
+}
+
+/// Returns the evidence of all equivocations among the proposals, in factory order
+pub fn find_equivocations(proposals: &BTreeMap<u64, Proposal>) -> Vec<Equivocation> {
+    let mut first_proposals = HashMap::new();
+    let mut equivocations = Vec::new();
+    for proposal in proposals.values().filter(|p| p.has_parent()) {
+        let first_index = *first_proposals
+            .entry((proposal.proposer, proposal.output_block_number))
+            .or_insert(proposal.index);
+        if let Some(equivocation) = Equivocation::between(&proposals[&first_index], proposal) {
+            equivocations.push(equivocation);
+        }
+    }
+    equivocations
+}
+
+pub async fn reindex(args: ReindexArgs) -> anyhow::Result<()> {
+    let eth_rpc_provider = ProviderBuilder::new().on_http(args.eth_rpc_url.as_str().try_into()?);
+    let blob_provider = BlobProvider::new(args.beacon_rpc_url.as_str()).await?;
+
+    info!("Fetching rollup configuration from rpc endpoints.");
+    let rollup_config = fetch_rollup_config(&args.op_node_url, &args.op_geth_url, None)
+        .await
+        .context("fetch_rollup_config")?;
+    let system_config =
+        SystemConfig::new(rollup_config.l1_system_config_address, &eth_rpc_provider);
+    let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
+    let dispute_game_factory = IDisputeGameFactory::new(dgf_address, &eth_rpc_provider);
+    let game_implementation = KailuaGame::new(
+        dispute_game_factory
+            .gameImpls(KAILUA_GAME_TYPE)
+            .stall()
+            .await
+            .impl_,
+        &eth_rpc_provider,
+    );
+    let config = Config::load(&game_implementation).await?;
+    info!("DisputeGameFactory({dgf_address:?})");
+
+    // Resume from the last fully indexed chunk of a previous run
+    let store = ReindexStore::open(&args.data_dir)?;
+    let mut progress = match store.load_progress()? {
+        Some(progress) if !args.restart => {
+            if progress.dispute_game_factory != dgf_address || progress.cfg_hash != config.cfg_hash
+            {
+                bail!(
+                    "Previous reindexing run targeted DisputeGameFactory({}) with config {}. Use --restart to discard it.",
+                    progress.dispute_game_factory,
+                    progress.cfg_hash
+                );
+            }
+            info!(
+                "Resuming reindexing run started at block {} from block {}.",
+                progress.from_block, progress.next_block
+            );
+            progress
+        }
+        _ => {
+            store.clear::<IndexedProposal>()?;
+            store.clear::<IndexedProof>()?;
+            store.clear::<IndexedResolution>()?;
+            ReindexProgress {
+                dispute_game_factory: dgf_address,
+                cfg_hash: config.cfg_hash,
+                from_block: args.from_block,
+                next_block: args.from_block,
+            }
+        }
+    };
+    let to_block = match args.to_block {
+        Some(to_block) => to_block,
+        None => eth_rpc_provider
+            .get_block_number()
+            .await
+            .context("get_block_number")?,
+    };
+
+    // Map tournament contracts to their factory index
+    let mut tournaments: HashMap<Address, u64> = store
+        .truncate::<IndexedProposal>(progress.next_block)?
+        .into_iter()
+        .map(|record| (record.proposal.contract, record.proposal.index))
+        .collect();
+    store.truncate::<IndexedProof>(progress.next_block)?;
+    store.truncate::<IndexedResolution>(progress.next_block)?;
+
+    let chunk_size = args.log_chunk_size.max(1);
+    while progress.next_block <= to_block {
+        let start = progress.next_block;
+        let end = to_block.min(start + chunk_size - 1);
+
+        // Proposals
+        let creation_logs = eth_rpc_provider
+            .get_logs(
+                &Filter::new()
+                    .address(dgf_address)
+                    .event_signature(IDisputeGameFactory::DisputeGameCreated::SIGNATURE_HASH)
+                    .from_block(start)
+                    .to_block(end),
+            )
+            .await
+            .context("get_logs")?;
+        let mut proposals = Vec::new();
+        for log in creation_logs {
+            let created = log.log_decode::<IDisputeGameFactory::DisputeGameCreated>()?;
+            let event = &created.inner.data;
+            if event.gameType != KAILUA_GAME_TYPE {
+                continue;
+            }
+            let tournament = KailuaTournament::new(event.disputeProxy, &eth_rpc_provider);
+            let proposal = Proposal::load(&config, &blob_provider, &tournament)
+                .await
+                .context(format!("Failed to load proposal {}", event.disputeProxy))?;
+            tournaments.insert(proposal.contract, proposal.index);
+            proposals.push(IndexedProposal {
+                block: log.block_number.unwrap_or_default(),
+                tx: log.transaction_hash.unwrap_or_default(),
+                proposal,
+            });
+        }
+
+        // Proofs
+        let proven_logs = eth_rpc_provider
+            .get_logs(
+                &Filter::new()
+                    .event_signature(KailuaTournament::Proven::SIGNATURE_HASH)
+                    .from_block(start)
+                    .to_block(end),
+            )
+            .await
+            .context("get_logs")?;
+        let mut proofs = Vec::new();
+        for log in proven_logs {
+            let Some(parent) = tournaments.get(&log.address()).copied() else {
+                continue;
+            };
+            let proven = log.log_decode::<KailuaTournament::Proven>()?;
+            let event = &proven.inner.data;
+            let tournament = KailuaTournament::new(log.address(), &eth_rpc_provider);
+            let mut children = [0u64; 2];
+            for (child, position) in children.iter_mut().zip([event.u, event.v]) {
+                let address = tournament.children(U256::from(position)).stall().await._0;
+                *child = *tournaments
+                    .get(&address)
+                    .context(format!("Proof references unindexed game {address}"))?;
+            }
+            proofs.push(IndexedProof {
+                block: log.block_number.unwrap_or_default(),
+                tx: log.transaction_hash.unwrap_or_default(),
+                parent,
+                contender: children[0],
+                proposal: children[1],
+                status: event.status as u8,
+            });
+        }
+
+        // Resolutions
+        let resolved_logs = eth_rpc_provider
+            .get_logs(
+                &Filter::new()
+                    .event_signature(KailuaGame::Resolved::SIGNATURE_HASH)
+                    .from_block(start)
+                    .to_block(end),
+            )
+            .await
+            .context("get_logs")?;
+        let mut resolutions = Vec::new();
+        for log in resolved_logs {
+            let Some(index) = tournaments.get(&log.address()).copied() else {
+                continue;
+            };
+            let resolved = log.log_decode::<KailuaGame::Resolved>()?;
+            resolutions.push(IndexedResolution {
+                block: log.block_number.unwrap_or_default(),
+                tx: log.transaction_hash.unwrap_or_default(),
+                index,
+                // GameStatus.DEFENDER_WINS
+                defender_wins: resolved.inner.data.status as u8 == 2,
+            });
+        }
+
+        // Persist the chunk before recording it as indexed
+        store.append(&proposals)?;
+        store.append(&proofs)?;
+        store.append(&resolutions)?;
+        progress.next_block = end + 1;
+        store.save_progress(&progress)?;
+
+        let total = (to_block + 1).saturating_sub(progress.from_block).max(1);
+        let done = progress.next_block.saturating_sub(progress.from_block);
+        info!(
+            "Indexed L1 blocks {start} to {end} ({:.1}%): {} proposals, {} proofs, {} resolutions.",
+            100.0 * done as f64 / total as f64,
+            proposals.len(),
+            proofs.len(),
+            resolutions.len()
+        );
+    }
+
+    // Rebuild the derived audit records
+    let proposals = store.replay()?;
+    let equivocations = find_equivocations(&proposals);
+    for equivocation in &equivocations {
+        warn!("{equivocation}");
+    }
+    let equivocations_path = args.data_dir.join("equivocations.json");
+    std::fs::write(
+        &equivocations_path,
+        serde_json::to_vec_pretty(&equivocations)?,
+    )
+    .context(format!("Failed to write {equivocations_path:?}"))?;
+
+    println!(
+        "Reindexed {} proposals up to L1 block {to_block} into {:?} ({} equivocations).",
+        proposals.len(),
+        store.dir,
+        equivocations.len()
+    );
+    Ok(())
+}
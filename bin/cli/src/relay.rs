@@ -0,0 +1,224 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::network::{Network, TransactionBuilder};
+use alloy::primitives::{keccak256, Address, Bytes};
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use tracing::info;
+
+/// The longest run of zero or 0xff bytes encoded by a single token
+pub const MAX_RUN_LENGTH: usize = 128;
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ProofRelayArgs {
+    /// Address of a KailuaProofRelay owned by the validator wallet to submit proofs through with
+    /// compressed calldata whenever that is estimated to cost less gas. Games record the relay as
+    /// the prover of relayed proofs, so their payouts must be swept from the relay.
+    #[clap(long, env)]
+    pub proof_relay: Option<Address>,
+}
+
+/// Compresses calldata into the format decompressed by the KailuaProofRelay contract, where runs
+/// of zero or 0xff bytes are replaced by a zero byte followed by the run length minus one, with
+/// the top bit set for 0xff runs.
+pub fn compress_calldata(data: &[u8]) -> Vec<u8> {
+    let mut compressed = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        let byte = data[i];
+        let run = data[i..]
+            .iter()
+            .take(MAX_RUN_LENGTH)
+            .take_while(|b| **b == byte)
+            .count();
+        match byte {
+            // zero bytes always start a run, as they escape runs
+            0x00 => compressed.extend([0x00, (run - 1) as u8]),
+            0xff if run > 2 => compressed.extend([0x00, 0x80 | (run - 1) as u8]),
+            _ => {
+                compressed.push(byte);
+                i += 1;
+                continue;
+            }
+        }
+        i += run;
+    }
+    compressed
+}
+
+/// Reverses [compress_calldata]
+pub fn decompress_calldata(data: &[u8]) -> Vec<u8> {
+    let mut decompressed = Vec::with_capacity(data.len() * 2);
+    let mut tokens = data.iter();
+    while let Some(byte) = tokens.next() {
+        if *byte != 0x00 {
+            decompressed.push(*byte);
+            continue;
+        }
+        let run = tokens.next().copied().unwrap_or_default();
+        let fill = if run & 0x80 == 0 { 0x00 } else { 0xff };
+        decompressed.extend(std::iter::repeat(fill).take(1 + (run & 0x7f) as usize));
+    }
+    decompressed
+}
+
+/// Returns the intrinsic gas cost of the given calldata
+pub fn calldata_gas(data: &[u8]) -> u64 {
+    data.iter()
+        .map(|byte| if *byte == 0 { 4 } else { 16 })
+        .sum()
+}
+
+/// Returns the given transaction request relayed through the relay with compressed calldata if
+/// that is estimated to cost less gas than the given estimate of submitting it directly.
+pub async fn relayed_request<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    relay: Address,
+    owner: Address,
+    txn: &N::TransactionRequest,
+    provider: &P,
+    direct_gas: u64,
+) -> anyhow::Result<Option<N::TransactionRequest>> {
+    let Some(target) = txn.to() else {
+        bail!("Contract creations can not be relayed.");
+    };
+    let data = [
+        target.as_slice(),
+        txn.input().map(|input| input.as_ref()).unwrap_or_default(),
+    ]
+    .concat();
+    let compressed = compress_calldata(&data);
+    if decompress_calldata(&compressed) != data {
+        bail!("Calldata compression does not round trip.");
+    }
+    // calls starting with a selector of the relay are not forwarded
+    if compressed.starts_with(&keccak256("sweep()")[..4]) {
+        bail!("Compressed calldata collides with the relay interface.");
+    }
+    let mut relayed_txn = txn.clone();
+    relayed_txn.set_to(relay);
+    relayed_txn.set_from(owner);
+    relayed_txn.set_input(Bytes::from(compressed));
+    let relayed_gas = provider
+        .estimate_gas(&relayed_txn)
+        .await
+        .context("estimate_gas (relayed)")?;
+    info!(
+        "Relaying {} bytes of calldata ({} gas) compressed to {} bytes ({} gas) costs {relayed_gas} gas against {direct_gas} gas directly.",
+        data.len(),
+        calldata_gas(&data),
+        relayed_txn.input().map(|input| input.len()).unwrap_or_default(),
+        relayed_txn.input().map(|input| calldata_gas(input)).unwrap_or_default(),
+    );
+    Ok((relayed_gas < direct_gas).then_some(relayed_txn))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, B256, U256};
+    use alloy::sol_types::SolCall;
+    use kailua_contracts::KailuaTournament;
+
+    /// The compressed `prove` call checked against the relay in `KailuaProofRelay.t.sol`
+    const GOLDEN_COMPRESSED_PROVE: &str = concat!(
+        "4200114208ba0b29001e01001e02001d1001001d014002575a0e9e593c0000f959f8c92f12db2869",
+        "c3395a3b0502d05e2516446f71f85b0a35acfbc15ff81a39ae7d344fd709f28e860000b4aa8c65c6",
+        "b64bfe7fe36bd19b3f009e036b6384b5eca791c62761152d0c79bb0604c104a5fb6f4eb0703f3154",
+        "bb3db0001d02e0001d0460001d0174c101b42b290decd9548b62a8d60345a988386fc84ba6bc9548",
+        "4008f6362f93160ef3e563007f001f00ff008bb10effff405787fa12a823e0f2b7631cc41b3ba882",
+        "8b3321ca811111fa75cd3aa3bb5ace002a40001ee0001e01001e20001e30f652222313e28459528d",
+        "920b65115c16c04f3efc82aaedc97be59f3f377c0d3fa66cc928b5edb82af9bd49922954155a002e",
+        "01001e20001e30f3f7a9fe364faab93b216da50a3214154f22a0a2b415b23a84c8169e8b636ee36e",
+        "1540171b6c0c960b71a7020d9f6007002e40001ee0001e01001e20001e30c65a7bb8d6351c1cf70c",
+        "95a316cc6a92839c986682d98bc35f958f4883f9d2a80175b7a638427703f0dbe7bb9bbf987a002e",
+        "01001e20001e30df6966c971051c3d54ec59162606531493a51404a002842f5600009d7e5cf4a8c7",
+        "d7b6990105719101dabeb77144f2a338000f",
+    );
+
+    fn word(i: u64) -> B256 {
+        keccak256(U256::from(i).to_be_bytes::<32>())
+    }
+
+    fn fe(mut hash: B256) -> B256 {
+        hash.0[0] &= 0x3f;
+        hash
+    }
+
+    fn prove_calldata() -> Vec<u8> {
+        let seal = [
+            &hex::decode("c101b42b").unwrap()[..],
+            word(0).as_slice(),
+            &[0x00; 160][..],
+            &[0xff; 140][..],
+            &word(1)[..2],
+            &[0xff; 2][..],
+            word(2).as_slice(),
+        ]
+        .concat();
+        let blob = |i: u64| Bytes::from([word(i).as_slice(), &word(i + 1)[..16]].concat());
+        let call = KailuaTournament::proveCall {
+            uvo: [1, 2, 4097],
+            encodedSeal: Bytes::from(seal),
+            acceptedOutput: fe(word(3)),
+            proposedOutput: [fe(word(4)), fe(B256::repeat_byte(0xff))],
+            computedOutput: fe(word(5)),
+            blobCommitments: [vec![blob(6)], vec![blob(8)]],
+            kzgProofs: [vec![blob(10)], vec![blob(12)]],
+        };
+        let target = address!("4200000000000000000000000000000000000042");
+        [target.as_slice(), &call.abi_encode()].concat()
+    }
+
+    #[test]
+    fn prove_calldata_compression() {
+        let data = prove_calldata();
+        let compressed = compress_calldata(&data);
+        assert_eq!(hex::encode(&compressed), GOLDEN_COMPRESSED_PROVE);
+        assert_eq!(decompress_calldata(&compressed), data);
+        // the padding of the last kzg proof ends the calldata with a zero run token
+        assert_eq!(compressed[compressed.len() - 2], 0x00);
+        assert!(calldata_gas(&compressed) < calldata_gas(&data));
+    }
+
+    #[test]
+    fn runs_round_trip() {
+        for len in [
+            1,
+            2,
+            3,
+            MAX_RUN_LENGTH - 1,
+            MAX_RUN_LENGTH,
+            MAX_RUN_LENGTH + 1,
+            300,
+        ] {
+            for byte in [0x00, 0x01, 0xff] {
+                let data = [vec![0x42], vec![byte; len], vec![0x42]].concat();
+                assert_eq!(decompress_calldata(&compress_calldata(&data)), data);
+            }
+        }
+        assert_eq!(
+            compress_calldata(&[0x01, 0xff, 0xff, 0x00]),
+            vec![0x01, 0xff, 0xff, 0x00, 0x00]
+        );
+        assert_eq!(
+            compress_calldata(&[0x00; MAX_RUN_LENGTH + 1]),
+            vec![0x00, 0x7f, 0x00, 0x00]
+        );
+        assert_eq!(compress_calldata(&[0xff; MAX_RUN_LENGTH]), vec![0x00, 0xff]);
+        // a trailing zero byte without a run length decompresses to a single zero byte
+        assert_eq!(decompress_calldata(&[0x01, 0x00]), vec![0x01, 0x00]);
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::competition::ProvingStrategy;
+use crate::failover::ProvingBackend;
+use crate::validate::ValidateArgs;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use tracing::{error, info};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ReloadArgs {
+    /// Path to the operator-controlled settings file, applied on startup and re-read on SIGHUP
+    /// (defaults to settings.json in the data-dir)
+    #[clap(long, env)]
+    pub settings_file: Option<PathBuf>,
+}
+
+/// The arguments that may change while the validator runs, overriding those it was started with.
+///
+/// RPC endpoints are not reloadable, as the providers built from them at startup are shared by
+/// both validator tasks, so changing them requires a restart.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReloadableSettings {
+    pub min_poll_interval: Option<u64>,
+    pub max_poll_interval: Option<u64>,
+    pub proving_strategy: Option<ProvingStrategy>,
+    pub proving_defer_secs: Option<u64>,
+    pub proof_submission_confirmations: Option<u64>,
+    pub proof_max_attempts: Option<usize>,
+    pub proof_retry_backoff_secs: Option<u64>,
+    pub proof_retry_max_backoff_secs: Option<u64>,
+    pub primary_prover: Option<ProvingBackend>,
+    pub secondary_prover: Option<ProvingBackend>,
+    pub prover_failure_threshold: Option<u32>,
+    pub prover_recovery_secs: Option<u64>,
+    pub prover_deadline_secs: Option<u64>,
+    pub rpc_cost_per_million: Option<f64>,
+    pub rpc_daily_budget: Option<u64>,
+    pub rpc_report_interval: Option<u64>,
+    pub kailua_host_service: Option<String>,
+}
+
+impl ReloadableSettings {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = std::fs::read(path).context(format!("Failed to read {path:?}"))?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Returns the startup arguments with these settings applied
+    pub fn apply(&self, startup: &ValidateArgs) -> ValidateArgs {
+        let mut args = startup.clone();
+        macro_rules! set {
+            ($field:ident, $target:expr) => {
+                if let Some(value) = &self.$field {
+                    $target = value.clone().into();
+                }
+            };
+        }
+        set!(min_poll_interval, args.cadence.min_poll_interval);
+        set!(max_poll_interval, args.cadence.max_poll_interval);
+        set!(proving_strategy, args.competition.proving_strategy);
+        set!(proving_defer_secs, args.competition.proving_defer_secs);
+        set!(
+            proof_submission_confirmations,
+            args.competition.proof_submission_confirmations
+        );
+        set!(proof_max_attempts, args.proof_retry.proof_max_attempts);
+        set!(
+            proof_retry_backoff_secs,
+            args.proof_retry.proof_retry_backoff_secs
+        );
+        set!(
+            proof_retry_max_backoff_secs,
+            args.proof_retry.proof_retry_max_backoff_secs
+        );
+        set!(primary_prover, args.failover.primary_prover);
+        set!(secondary_prover, args.failover.secondary_prover);
+        set!(
+            prover_failure_threshold,
+            args.failover.prover_failure_threshold
+        );
+        set!(prover_recovery_secs, args.failover.prover_recovery_secs);
+        set!(prover_deadline_secs, args.failover.prover_deadline_secs);
+        set!(
+            rpc_cost_per_million,
+            args.core.rpc_budget.rpc_cost_per_million
+        );
+        set!(rpc_daily_budget, args.core.rpc_budget.rpc_daily_budget);
+        set!(
+            rpc_report_interval,
+            args.core.rpc_budget.rpc_report_interval
+        );
+        set!(kailua_host_service, args.kailua_host_service);
+        args
+    }
+}
+
+/// Applies the settings file to the startup arguments, and re-applies it on every SIGHUP.
+///
+/// Returns the arguments in effect along with a receiver of those applied by later reloads.
+pub fn watch_settings(
+    args: &ReloadArgs,
+    startup: ValidateArgs,
+    data_dir: &Path,
+) -> anyhow::Result<(ValidateArgs, watch::Receiver<ValidateArgs>)> {
+    let path = args
+        .settings_file
+        .clone()
+        .unwrap_or_else(|| data_dir.join("settings.json"));
+    let mut settings = ReloadableSettings::load(&path)?;
+    if settings != ReloadableSettings::default() {
+        info!("Applying settings from {path:?}: {settings:?}");
+    }
+    let effective = settings.apply(&startup);
+    let (sender, receiver) = watch::channel(effective.clone());
+    let mut hangup = signal(SignalKind::hangup()).context("Failed to listen for SIGHUP")?;
+    info!("Send SIGHUP to reload settings from {path:?}.");
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            // malformed files leave the current settings in place
+            let reloaded = match ReloadableSettings::load(&path) {
+                Ok(reloaded) => reloaded,
+                Err(err) => {
+                    error!("Ignoring malformed settings: {err:?}");
+                    continue;
+                }
+            };
+            if reloaded == settings {
+                info!("Settings in {path:?} are unchanged.");
+                continue;
+            }
+            info!("Reloaded settings from {path:?}: {reloaded:?}");
+            sender.send_replace(reloaded.apply(&startup));
+            settings = reloaded;
+        }
+    });
+    Ok((effective, receiver))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_json_round_trip() {
+        let settings = ReloadableSettings {
+            min_poll_interval: Some(500),
+            proving_strategy: Some(ProvingStrategy::Uncontested),
+            primary_prover: Some(ProvingBackend::Bonsai),
+            rpc_cost_per_million: Some(2.5),
+            kailua_host_service: Some(String::from("http://localhost:8080")),
+            ..Default::default()
+        };
+        let encoded = serde_json::to_string(&settings).unwrap();
+        let decoded: ReloadableSettings = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded, settings);
+    }
+
+    #[test]
+    fn settings_reject_unknown_fields() {
+        let decoded = serde_json::from_str::<ReloadableSettings>(r#"{"eth_rpc_url":"x"}"#);
+        assert!(decoded.is_err());
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::providers::optimism::OpNodeProvider;
+use alloy::primitives::B256;
+use alloy::providers::ProviderBuilder;
+use anyhow::{bail, Context};
+use kailua_client::replay::{load_witness_archive, replay_safe_head, replay_witness};
+use serde_json::Value;
+use std::path::PathBuf;
+use tracing::{error, info};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ReplayArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Path to the witness archive written by kailua-host
+    pub witness_archive: PathBuf,
+    /// Address of the OP-NODE endpoint to compare intermediate outputs against
+    #[clap(long, env)]
+    pub op_node_url: Option<String>,
+    /// Whether to bisect the proven range for the first block whose output diverges
+    #[clap(long, requires = "op_node_url")]
+    pub bisect: bool,
+}
+
+pub async fn replay(args: ReplayArgs) -> anyhow::Result<()> {
+    let witness = load_witness_archive(&args.witness_archive).await?;
+    info!(
+        "Replaying witness with {} preimages and {} blobs.",
+        witness.oracle_witness.keys.len(),
+        witness.blobs_witness.blobs.len()
+    );
+
+    // Replay the full claim as the guest would
+    let outcome = replay_witness(witness.clone(), None).await?;
+    let claimed_block = outcome.boot.claimed_l2_block_number;
+    let claimed_output = outcome.boot.claimed_l2_output_root;
+    println!("Precondition hash: {}", outcome.precondition_hash);
+    match outcome.output_root {
+        Some(output_root) if output_root == claimed_output => {
+            println!("Block {claimed_block}: computed output {output_root} matches the claim.");
+        }
+        Some(output_root) => {
+            println!("Block {claimed_block}: computed output {output_root} contradicts the claimed {claimed_output}.");
+        }
+        None if claimed_output.is_zero() => {
+            println!("Block {claimed_block}: insufficient L1 data, as claimed.");
+        }
+        None => {
+            println!("Block {claimed_block}: insufficient L1 data to compute the claimed {claimed_output}.");
+        }
+    }
+    if !args.bisect {
+        return Ok(());
+    }
+
+    // Bisect for the first block whose output differs from the op-node's
+    let op_node_provider = OpNodeProvider::new(
+        ProviderBuilder::new().on_http(args.op_node_url.unwrap().as_str().try_into()?),
+    );
+    let (_, safe_head) = replay_safe_head(witness.clone()).await?;
+    info!("Bisecting blocks {} to {claimed_block}.", safe_head + 1);
+    let (mut low, mut high) = (safe_head, claimed_block);
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        let expected = op_node_provider.output_at_block(mid).await?;
+        let computed = match replay_witness(witness.clone(), Some(mid)).await {
+            Ok(outcome) => outcome.output_root,
+            Err(err) => {
+                error!("Replay to block {mid} failed: {err:?}");
+                None
+            }
+        };
+        if computed == Some(expected) {
+            info!("Block {mid} matches.");
+            low = mid;
+        } else {
+            info!("Block {mid} diverges.");
+            high = mid - 1;
+        }
+    }
+    if low == claimed_block {
+        println!("No divergence from the op-node found up to block {claimed_block}.");
+        return Ok(());
+    }
+    // The first diverging block lies directly after the last matching one
+    let block = low + 1;
+    let computed = replay_witness(witness, Some(block)).await?.output_root;
+    let expected: Value = op_node_provider
+        .provider
+        .client()
+        .request("optimism_outputAtBlock", (format!("0x{:x}", block),))
+        .await
+        .context(format!("optimism_outputAtBlock {block}"))?;
+    let field = |path: &[&str]| {
+        path.iter()
+            .fold(&expected, |value, key| &value[*key])
+            .as_str()
+            .unwrap_or_default()
+            .to_string()
+    };
+    println!("First divergent block: {block}");
+    println!(
+        "Computed output root: {}",
+        computed.map_or(String::from("none"), |root: B256| root.to_string())
+    );
+    println!("Expected output root: {}", field(&["outputRoot"]));
+    println!("Expected state root: {}", field(&["stateRoot"]));
+    println!(
+        "Expected withdrawal storage root: {}",
+        field(&["withdrawalStorageRoot"])
+    );
+    println!("Expected block hash: {}", field(&["blockRef", "hash"]));
+    if computed.is_none() {
+        bail!("Replay could not derive block {block}.");
+    }
+    Ok(())
+}
@@ -0,0 +1,363 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::address_book::{AddressArg, AddressBook, AddressBookArgs};
+use crate::db::proposal::Proposal;
+use crate::prove::fetch_children;
+use crate::stall::Stall;
+use crate::KAILUA_GAME_TYPE;
+use alloy::eips::{BlockId, BlockNumberOrTag};
+use alloy::network::primitives::BlockTransactionsKind;
+use alloy::network::{EthereumWallet, Network, ReceiptResponse};
+use alloy::primitives::{Address, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::signers::local::LocalSigner;
+use alloy::sol_types::SolCall;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use kailua_contracts::introspection::GAME_IN_PROGRESS;
+use kailua_contracts::*;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use tracing::info;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ResolveBatchArgs {
+    /// Maximum number of resolution transactions to submit before awaiting their receipts
+    #[clap(long, env, default_value_t = 1)]
+    pub max_pending_resolves: usize,
+    /// Address of a `Multicall3` contract to batch resolution transactions through
+    #[clap(long, env)]
+    pub resolve_multicall_address: Option<String>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ResolveArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the ethereum rpc endpoint to use (eth namespace required)
+    #[clap(long, env)]
+    pub eth_rpc_url: String,
+    /// Secret key of L1 wallet to send resolution transactions with
+    #[clap(long, env)]
+    pub resolver_key: String,
+
+    /// Address, address book alias or ENS name of the KailuaGame instance to resolve
+    #[clap(long)]
+    pub game: AddressArg,
+    /// Whether to first resolve any unresolved ancestors of the game, parent-first
+    #[clap(long)]
+    pub recursive: bool,
+
+    #[clap(flatten)]
+    pub address_book: AddressBookArgs,
+}
+
+/// Resolves a game, and optionally its unresolved ancestors, reporting the bonds moved
+pub async fn resolve(args: ResolveArgs) -> anyhow::Result<()> {
+    let resolver_provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(EthereumWallet::from(LocalSigner::from_str(
+            &args.resolver_key,
+        )?))
+        .on_http(args.eth_rpc_url.as_str().try_into()?);
+    let game_address = AddressBook::load(&args.address_book)?
+        .resolve(&args.game, &resolver_provider)
+        .await?;
+    let tournament = KailuaTournament::new(game_address, &resolver_provider);
+    let game_type = tournament.gameType().stall().await.gameType_;
+    if game_type != KAILUA_GAME_TYPE {
+        bail!(
+            "Game {} is of type {game_type} instead of {KAILUA_GAME_TYPE}.",
+            game_address
+        );
+    }
+
+    // Collect the unresolved games up to the first resolved ancestor, parent-first
+    let mut unresolved = vec![];
+    let mut game = game_address;
+    loop {
+        let instance = KailuaTournament::new(game, &resolver_provider);
+        if instance.status().stall().await._0 != GAME_IN_PROGRESS {
+            break;
+        }
+        let parent = instance.parentGame().stall().await.parentGame_;
+        if parent == game {
+            bail!("Treasury instance {game} can only be resolved by the factory owner.");
+        }
+        unresolved.insert(0, game);
+        game = parent;
+    }
+    if unresolved.is_empty() {
+        println!("Game {} is already resolved.", game_address);
+        return Ok(());
+    }
+    if unresolved.len() > 1 && !args.recursive {
+        bail!(
+            "Game {} has {} unresolved ancestor(s) starting with {}. Pass --recursive to resolve them first.",
+            game_address,
+            unresolved.len() - 1,
+            unresolved[0]
+        );
+    }
+
+    let treasury = KailuaTreasury::new(
+        tournament.treasury().stall().await.treasury_,
+        &resolver_provider,
+    );
+    let treasury_balance = resolver_provider.get_balance(*treasury.address()).await?;
+    for game in unresolved {
+        let instance = KailuaTournament::new(game, &resolver_provider);
+        let timestamp = resolver_provider
+            .get_block_by_number(BlockNumberOrTag::Latest, BlockTransactionsKind::Hashes)
+            .await?
+            .context("Latest block not found")?
+            .header
+            .timestamp;
+        let resolvability = instance.resolvability(timestamp).await?;
+        if !resolvability.is_resolvable() {
+            bail!("Game {game} is not resolvable yet: {resolvability:?}");
+        }
+        // Track the proposers of competing siblings, who are eliminated during resolution
+        let parent = KailuaTournament::new(
+            instance.parentGame().stall().await.parentGame_,
+            &resolver_provider,
+        );
+        let mut sibling_bonds = BTreeMap::new();
+        for sibling in fetch_children(&parent).await {
+            let proposer = treasury.proposerOf(sibling).stall().await._0;
+            if !proposer.is_zero()
+                && treasury.eliminationRound(proposer).stall().await._0 == U256::ZERO
+            {
+                sibling_bonds.insert(proposer, treasury.paidBonds(proposer).stall().await._0);
+            }
+        }
+        info!("Resolving game {game}.");
+        let receipt = instance
+            .resolve()
+            .send()
+            .await
+            .context("KailuaTournament::resolve (send)")?
+            .get_receipt()
+            .await
+            .context("KailuaTournament::resolve (get_receipt)")?;
+        println!("Resolved game {game} in {}.", receipt.transaction_hash);
+        for (proposer, bond) in sibling_bonds {
+            if treasury.eliminationRound(proposer).stall().await._0 != U256::ZERO {
+                println!(
+                    "  Eliminated proposer {proposer}, paying its {bond} wei bond to the prover."
+                );
+            }
+        }
+    }
+    let remaining_balance = resolver_provider.get_balance(*treasury.address()).await?;
+    println!(
+        "Treasury balance moved from {treasury_balance} to {remaining_balance} wei ({} wei paid out).",
+        treasury_balance.saturating_sub(remaining_balance)
+    );
+    Ok(())
+}
+
+/// Splits the proposals into batches of at most `batch_size` games, keeping every game in a later
+/// batch than its parent so that no batch depends on the resolution of its own games.
+pub fn independent_batches(proposals: &[Proposal], batch_size: usize) -> Vec<Vec<&Proposal>> {
+    let mut batches: Vec<Vec<&Proposal>> = vec![];
+    for proposal in proposals {
+        let depends_on_batch = batches.last().is_some_and(|batch| {
+            batch.len() >= batch_size
+                || (proposal.has_parent() && batch.iter().any(|p| p.index == proposal.parent))
+        });
+        match batches.last_mut() {
+            Some(batch) if !depends_on_batch => batch.push(proposal),
+            _ => batches.push(vec![proposal]),
+        }
+    }
+    batches
+}
+
+/// Resolves the given proposals in the provided (dependency) order, returning how many were
+/// resolved.
+pub async fn resolve_proposals<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    proposals: &[Proposal],
+    provider: P,
+    args: &ResolveBatchArgs,
+) -> anyhow::Result<usize> {
+    let batch_size = args.max_pending_resolves.max(1);
+    let mut resolved_count = 0;
+    for batch in independent_batches(proposals, batch_size) {
+        // Games resolved by others would revert the entire batch
+        let mut unresolved = Vec::with_capacity(batch.len());
+        for proposal in batch {
+            if is_resolved(proposal, &provider).await {
+                info!("Game at index {} is already resolved.", proposal.index);
+                resolved_count += 1;
+            } else {
+                unresolved.push(proposal);
+            }
+        }
+        if unresolved.is_empty() {
+            continue;
+        }
+        let succeeded = match &args.resolve_multicall_address {
+            Some(multicall_address) => {
+                // Resolve the independent games of the batch within a single transaction
+                let multicall = IMulticall3::new(Address::from_str(multicall_address)?, &provider);
+                let calls = unresolved
+                    .iter()
+                    .map(|proposal| IMulticall3::Call3 {
+                        target: proposal.contract,
+                        allowFailure: false,
+                        callData: KailuaTournament::resolveCall {}.abi_encode().into(),
+                    })
+                    .collect::<Vec<_>>();
+                info!(
+                    "Resolving games at indices {:?} through multicall.",
+                    unresolved.iter().map(|p| p.index).collect::<Vec<_>>()
+                );
+                let receipt = multicall
+                    .aggregate3(calls)
+                    .send()
+                    .await
+                    .context("IMulticall3::aggregate3 (send)")?
+                    .get_receipt()
+                    .await
+                    .context("IMulticall3::aggregate3 (get_receipt)")?;
+                vec![receipt.status(); unresolved.len()]
+            }
+            None => {
+                // Pipeline the independent games of the batch before awaiting their receipts
+                let mut pending_txns = Vec::with_capacity(unresolved.len());
+                for proposal in &unresolved {
+                    info!(
+                        "Resolving game at index {} and height {}.",
+                        proposal.index, proposal.output_block_number
+                    );
+                    let tournament_instance = proposal.tournament_contract_instance(&provider);
+                    let resolve_call = tournament_instance.resolve();
+                    let gas = resolve_call
+                        .estimate_gas()
+                        .await
+                        .context("KailuaTournament::resolve (estimate_gas)")?;
+                    pending_txns.push(
+                        resolve_call
+                            .gas(gas + gas / 2)
+                            .send()
+                            .await
+                            .context("KailuaTournament::resolve (send)")?,
+                    );
+                }
+                let mut succeeded = Vec::with_capacity(pending_txns.len());
+                for pending_txn in pending_txns {
+                    let receipt = pending_txn
+                        .get_receipt()
+                        .await
+                        .context("KailuaTournament::resolve (get_receipt)")?;
+                    succeeded.push(receipt.status());
+                }
+                succeeded
+            }
+        };
+        for (proposal, succeeded) in unresolved.into_iter().zip(succeeded) {
+            // Tolerate the game being resolved concurrently by others
+            if !succeeded && !is_resolved(proposal, &provider).await {
+                bail!(
+                    "Resolution of game at index {} reverted after resolving {resolved_count} games.",
+                    proposal.index
+                );
+            }
+            resolved_count += 1;
+        }
+    }
+    Ok(resolved_count)
+}
+
+/// Returns whether the game of the proposal is resolved as of the latest block
+async fn is_resolved<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    proposal: &Proposal,
+    provider: P,
+) -> bool {
+    proposal
+        .tournament_contract_instance(provider)
+        .status()
+        .block(BlockId::latest())
+        .stall()
+        .await
+        ._0
+        != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::lifecycle::{Assessment, ProposalStatus};
+    use alloy::primitives::B256;
+
+    fn proposal(index: u64, parent: u64) -> Proposal {
+        Proposal {
+            contract: Address::ZERO,
+            index,
+            parent,
+            proposer: Address::ZERO,
+            created_at: 0,
+            io_blobs: vec![],
+            io_field_elements: vec![],
+            output_root: B256::ZERO,
+            output_block_number: index,
+            l1_head: B256::ZERO,
+            children: vec![],
+            survivor: None,
+            contender: None,
+            assessment: Assessment::Pending,
+            status: ProposalStatus::Unchallenged,
+        }
+    }
+
+    fn indices(batches: Vec<Vec<&Proposal>>) -> Vec<Vec<u64>> {
+        batches
+            .into_iter()
+            .map(|batch| batch.into_iter().map(|p| p.index).collect())
+            .collect()
+    }
+
+    #[test]
+    fn batches_exclude_dependent_games() {
+        // a chain of successive proposals is resolved one game at a time
+        let chain = [proposal(1, 0), proposal(2, 1), proposal(3, 2)];
+        assert_eq!(
+            indices(independent_batches(&chain, 4)),
+            vec![vec![1], vec![2], vec![3]]
+        );
+        // games of independent branches share a batch up to its size
+        let branches = [
+            proposal(1, 0),
+            proposal(2, 0),
+            proposal(3, 0),
+            proposal(4, 1),
+        ];
+        assert_eq!(
+            indices(independent_batches(&branches, 2)),
+            vec![vec![1, 2], vec![3, 4]]
+        );
+        assert_eq!(
+            indices(independent_batches(&branches, 4)),
+            vec![vec![1, 2, 3], vec![4]]
+        );
+        // the treasury is its own parent
+        assert_eq!(
+            indices(independent_batches(&[proposal(0, 0), proposal(5, 5)], 4)),
+            vec![vec![0, 5]]
+        );
+    }
+}
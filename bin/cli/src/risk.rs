@@ -0,0 +1,134 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::api::ProposalView;
+use crate::db::lifecycle::ProposalStatus;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The assurance the dispute system currently provides for the output of an L2 block
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskStatus {
+    /// The block is covered by a canonical game that was resolved in favor of its proposer
+    Resolved,
+    /// The block is covered by a canonical proposal that no sibling has contested
+    Unchallenged,
+    /// The block is covered by a canonical proposal awaiting the outcome of a dispute
+    Challenged,
+    /// No canonical proposal covers the block yet
+    Uncovered,
+}
+
+/// A contiguous range of L2 blocks sharing the same risk status
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RiskRange {
+    /// The first L2 block covered by the range
+    pub from_block: u64,
+    /// The last L2 block covered by the range, if bounded
+    pub to_block: Option<u64>,
+    pub status: RiskStatus,
+    /// The factory index of the canonical proposal covering the range
+    pub game_index: Option<u64>,
+    /// The time after which the covering proposal can no longer be challenged
+    pub window_ends_at: Option<u64>,
+    /// Whether the challenge window of an unchallenged proposal is still open
+    pub in_window: Option<bool>,
+}
+
+impl RiskRange {
+    pub fn contains(&self, block: u64) -> bool {
+        self.from_block <= block && self.to_block.map_or(true, |to_block| block <= to_block)
+    }
+}
+
+/// Derives the risk status of every L2 block from the canonical chain of proposals ending at
+/// the given tip, earliest blocks first, such that the returned ranges cover all blocks.
+pub fn risk_ranges(
+    proposals: &BTreeMap<u64, ProposalView>,
+    canonical_tip_index: Option<u64>,
+    proposal_block_count: u64,
+    challenge_timeout: u64,
+    now: u64,
+) -> Vec<RiskRange> {
+    // collect the canonical chain from the tip up to the earliest tracked ancestor
+    let mut chain = vec![];
+    let mut next = canonical_tip_index.and_then(|index| proposals.get(&index));
+    while let Some(proposal) = next {
+        chain.push(proposal);
+        if proposal.parent == proposal.index {
+            break;
+        }
+        next = proposals.get(&proposal.parent);
+    }
+    chain.reverse();
+
+    let mut ranges = vec![];
+    let mut from_block = 0;
+    for proposal in chain {
+        let is_anchor = proposal.parent == proposal.index;
+        if ranges.is_empty() && !is_anchor {
+            // proposals are only forgotten after their games are resolved
+            from_block = (proposal.output_block_number + 1).saturating_sub(proposal_block_count);
+            if from_block > 0 {
+                ranges.push(RiskRange {
+                    from_block: 0,
+                    to_block: Some(from_block - 1),
+                    status: RiskStatus::Resolved,
+                    game_index: None,
+                    window_ends_at: None,
+                    in_window: None,
+                });
+            }
+        }
+        let window_ends_at = proposal.created_at + challenge_timeout;
+        let (status, in_window) = match proposal.status {
+            // the treasury instance anchors the rollup at a finalized output
+            _ if is_anchor => (RiskStatus::Resolved, None),
+            ProposalStatus::Resolved {
+                defender_wins: true,
+            } => (RiskStatus::Resolved, None),
+            ProposalStatus::Resolved {
+                defender_wins: false,
+            } => (RiskStatus::Uncovered, None),
+            ProposalStatus::Unchallenged => (RiskStatus::Unchallenged, Some(now < window_ends_at)),
+            ProposalStatus::Challenged { .. } | ProposalStatus::Proven { .. } => {
+                (RiskStatus::Challenged, None)
+            }
+        };
+        ranges.push(RiskRange {
+            from_block,
+            to_block: Some(proposal.output_block_number),
+            status,
+            game_index: Some(proposal.index),
+            window_ends_at: (!is_anchor).then_some(window_ends_at),
+            in_window,
+        });
+        from_block = proposal.output_block_number + 1;
+    }
+    ranges.push(RiskRange {
+        from_block,
+        to_block: None,
+        status: RiskStatus::Uncovered,
+        game_index: None,
+        window_ends_at: None,
+        in_window: None,
+    });
+    ranges
+}
+
+/// Returns the risk range containing the given L2 block
+pub fn risk_at(ranges: &[RiskRange], block: u64) -> Option<&RiskRange> {
+    ranges.iter().find(|range| range.contains(block))
+}
@@ -0,0 +1,185 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct SheddingArgs {
+    /// Number of recent requests to each endpoint its health is scored on
+    #[clap(long, env, default_value_t = 100)]
+    pub health_window: usize,
+    /// Number of milliseconds after which a request counts against the health of its endpoint
+    #[clap(long, env, default_value_t = 5_000)]
+    pub slow_request_ms: u64,
+    /// Health score (0-100) of the weakest endpoint below which prefetching and statistics are
+    /// shed
+    #[clap(long, env, default_value_t = 80)]
+    pub shed_health_threshold: u8,
+    /// Health score (0-100) of the weakest endpoint below which proofs of matches that do not
+    /// involve the validator's own proposals are deferred
+    #[clap(long, env, default_value_t = 50)]
+    pub shed_proving_threshold: u8,
+}
+
+impl Default for SheddingArgs {
+    fn default() -> Self {
+        Self {
+            health_window: 100,
+            slow_request_ms: 5_000,
+            shed_health_threshold: 80,
+            shed_proving_threshold: 50,
+        }
+    }
+}
+
+/// The kinds of work the validator sheds, in the order they are given up as endpoints degrade
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorkClass {
+    /// Downloading L1 data ahead of proving
+    Prefetch,
+    /// Settling dispute timelines for latency and statistics reports
+    Stats,
+    /// Proving matches that do not involve the validator's own proposals
+    SpeculativeProof,
+    /// Proving matches that involve the validator's own proposals or expiring data, and
+    /// submitting proofs, which are never shed
+    Critical,
+}
+
+impl WorkClass {
+    /// Returns the health score below which work of this class is shed
+    pub fn min_health(&self, args: &SheddingArgs) -> u8 {
+        match self {
+            WorkClass::Prefetch | WorkClass::Stats => args.shed_health_threshold,
+            WorkClass::SpeculativeProof => args.shed_proving_threshold,
+            WorkClass::Critical => 0,
+        }
+    }
+}
+
+/// The outcomes of the latest requests to an endpoint, where timeouts, errors and slow responses
+/// count as unhealthy
+#[derive(Clone, Debug, Default)]
+pub struct EndpointHealth {
+    pub outcomes: VecDeque<bool>,
+}
+
+impl EndpointHealth {
+    pub fn record(&mut self, healthy: bool, window: usize) {
+        self.outcomes.push_back(healthy);
+        while self.outcomes.len() > window.max(1) {
+            self.outcomes.pop_front();
+        }
+    }
+
+    /// Returns the percentage of healthy outcomes, or 100 without any requests
+    pub fn score(&self) -> u8 {
+        if self.outcomes.is_empty() {
+            return 100;
+        }
+        let healthy = self.outcomes.iter().filter(|healthy| **healthy).count();
+        (100 * healthy / self.outcomes.len()) as u8
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct HealthState {
+    pub endpoints: BTreeMap<String, EndpointHealth>,
+    /// The weakest health score at the time of the last report
+    pub reported_health: Option<u8>,
+}
+
+/// Scores the health of rpc endpoints and decides which work to shed while they degrade
+#[derive(Clone, Debug, Default)]
+pub struct LoadShedder {
+    pub args: SheddingArgs,
+    pub state: Arc<Mutex<HealthState>>,
+}
+
+impl LoadShedder {
+    pub fn new(args: SheddingArgs) -> Self {
+        Self {
+            args,
+            state: Default::default(),
+        }
+    }
+
+    /// Records the outcome of a single request to the endpoint
+    pub fn record(&self, endpoint: &str, elapsed: Duration, success: bool) {
+        let healthy = success && elapsed <= Duration::from_millis(self.args.slow_request_ms);
+        self.state
+            .lock()
+            .unwrap()
+            .endpoints
+            .entry(endpoint.to_string())
+            .or_default()
+            .record(healthy, self.args.health_window);
+    }
+
+    /// Returns the health score of every endpoint requested so far
+    pub fn scores(&self) -> BTreeMap<String, u8> {
+        self.state
+            .lock()
+            .unwrap()
+            .endpoints
+            .iter()
+            .map(|(endpoint, health)| (endpoint.clone(), health.score()))
+            .collect()
+    }
+
+    /// Returns the health score of the weakest endpoint
+    pub fn health(&self) -> u8 {
+        self.scores().into_values().min().unwrap_or(100)
+    }
+
+    /// Returns whether work of the given class should be shed
+    pub fn sheds(&self, class: WorkClass) -> bool {
+        self.health() < class.min_health(&self.args)
+    }
+
+    /// Logs the endpoint health scores whenever the set of shed work classes changes
+    pub fn report(&self) {
+        let health = self.health();
+        let shed_classes = |health: u8| {
+            [
+                WorkClass::Prefetch,
+                WorkClass::Stats,
+                WorkClass::SpeculativeProof,
+            ]
+            .into_iter()
+            .filter(|class| health < class.min_health(&self.args))
+            .collect::<Vec<_>>()
+        };
+        let reported_health = self.state.lock().unwrap().reported_health.replace(health);
+        let previously_shed = shed_classes(reported_health.unwrap_or(100));
+        let shed = shed_classes(health);
+        if shed == previously_shed {
+            return;
+        }
+        if shed.is_empty() {
+            info!(
+                "Endpoints recovered ({:?}). Resuming all work.",
+                self.scores()
+            );
+        } else {
+            warn!(
+                "Endpoints degraded ({:?}). Shedding {shed:?} work.",
+                self.scores()
+            );
+        }
+    }
+}
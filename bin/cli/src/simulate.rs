@@ -0,0 +1,478 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::api::{Page, ProposalView, MAX_PAGE_LIMIT};
+use crate::db::lifecycle::ProposalStatus;
+use crate::stall::Stall;
+use crate::KAILUA_GAME_TYPE;
+use alloy::consensus::BlockHeader;
+use alloy::eips::{BlockId, BlockNumberOrTag};
+use alloy::network::primitives::BlockTransactionsKind;
+use alloy::network::BlockResponse;
+use alloy::primitives::{Address, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::transports::http::reqwest;
+use anyhow::{bail, Context};
+use kailua_contracts::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use tracing::info;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct SimulateArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of a validator's query api to read the proposal tree from
+    #[clap(long, env)]
+    pub api_url: String,
+    /// Address of the ethereum rpc endpoint to use (eth namespace required)
+    #[clap(long, env)]
+    pub eth_rpc_url: String,
+    /// Address of the rollup's DisputeGameFactory contract
+    #[clap(long, env)]
+    pub dispute_game_factory: Address,
+
+    /// Hypothetical actions to apply, as `challenge:INDEX[@TIME]` to contest a game with a new
+    /// proposal or `fault:INDEX[@TIME]` to land a fault proof eliminating a game
+    #[clap(long = "action", value_parser = parse_action)]
+    pub actions: Vec<HypotheticalAction>,
+    /// Seconds after their creation at which proposals deemed incorrect by the validator are
+    /// assumed to be eliminated by a fault proof
+    #[clap(long, env, default_value_t = 0)]
+    pub assumed_proving_secs: u64,
+    /// Unix time to start the simulation at (defaults to the latest L1 block time)
+    #[clap(long)]
+    pub at: Option<u64>,
+
+    /// Whether to print the simulation outcome as JSON
+    #[clap(long, env)]
+    pub json: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HypotheticalAction {
+    /// A new proposal contradicting the game at `index` is published
+    Challenge { index: u64, at: Option<u64> },
+    /// A fault proof eliminating the game at `index` lands
+    Fault { index: u64, at: Option<u64> },
+}
+
+pub fn parse_action(s: &str) -> Result<HypotheticalAction, String> {
+    let (kind, target) = s
+        .split_once(':')
+        .ok_or(format!("Invalid action {s}, expected KIND:INDEX[@TIME]"))?;
+    let (index, at) = match target.split_once('@') {
+        Some((index, at)) => (index, Some(at)),
+        None => (target, None),
+    };
+    let index = u64::from_str(index).map_err(|e| format!("Invalid game index {index}: {e}"))?;
+    let at = at
+        .map(u64::from_str)
+        .transpose()
+        .map_err(|e| format!("Invalid time in {s}: {e}"))?;
+    match kind {
+        "challenge" => Ok(HypotheticalAction::Challenge { index, at }),
+        "fault" => Ok(HypotheticalAction::Fault { index, at }),
+        _ => Err(format!(
+            "Unknown action {kind}, expected challenge or fault"
+        )),
+    }
+}
+
+/// A game as tracked by the simulation
+#[derive(Clone, Debug, Serialize)]
+pub struct SimulatedGame {
+    pub index: u64,
+    pub parent: u64,
+    pub proposer: Address,
+    pub created_at: u64,
+    pub output_block_number: u64,
+    pub correct: Option<bool>,
+    /// Whether the game only exists in the simulation
+    pub hypothetical: bool,
+    pub eliminated_at: Option<u64>,
+    pub resolved_at: Option<u64>,
+}
+
+impl SimulatedGame {
+    pub fn is_open(&self) -> bool {
+        self.eliminated_at.is_none() && self.resolved_at.is_none()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event")]
+pub enum SimulatedEvent {
+    /// The game was eliminated, forfeiting its proposer's bond if not forfeited already
+    Elimination {
+        time: u64,
+        index: u64,
+        proposer: Address,
+        forfeited_bond: U256,
+    },
+    /// The game was resolved in favor of its proposer
+    Resolution { time: u64, index: u64 },
+}
+
+impl SimulatedEvent {
+    pub fn time(&self) -> u64 {
+        match self {
+            SimulatedEvent::Elimination { time, .. } => *time,
+            SimulatedEvent::Resolution { time, .. } => *time,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SimulationReport {
+    pub start_time: u64,
+    pub challenge_timeout: u64,
+    pub participation_bond: U256,
+    pub events: Vec<SimulatedEvent>,
+    /// Games that can not be resolved, with the reason
+    pub stuck: BTreeMap<u64, String>,
+    /// The time after which each surviving proposer may withdraw its bond
+    pub bond_returns: BTreeMap<Address, Option<u64>>,
+    /// The latest game resolved in favor of its proposer before and after the simulation
+    pub anchor_before: Option<u64>,
+    pub anchor_after: Option<u64>,
+    pub anchor_block_before: Option<u64>,
+    pub anchor_block_after: Option<u64>,
+}
+
+/// Reads every proposal known to the validator from its query api
+pub async fn fetch_proposal_tree(api_url: &str) -> anyhow::Result<Vec<ProposalView>> {
+    let client = reqwest::Client::new();
+    let mut proposals = Vec::new();
+    loop {
+        let page = client
+            .get(format!(
+                "{}/proposals?offset={}&limit={MAX_PAGE_LIMIT}",
+                api_url.trim_end_matches('/'),
+                proposals.len()
+            ))
+            .send()
+            .await
+            .context("Failed to query proposals")?
+            .error_for_status()?
+            .json::<Page<ProposalView>>()
+            .await?;
+        let done = page.items.is_empty() || proposals.len() + page.items.len() >= page.total;
+        proposals.extend(page.items);
+        if done {
+            break;
+        }
+    }
+    Ok(proposals)
+}
+
+/// Plays out eliminations and resolutions over the proposal tree after applying the actions
+pub fn simulate(
+    proposals: &[ProposalView],
+    actions: &[HypotheticalAction],
+    start_time: u64,
+    challenge_timeout: u64,
+    participation_bond: U256,
+    assumed_proving_secs: u64,
+) -> anyhow::Result<SimulationReport> {
+    let mut games = BTreeMap::new();
+    for proposal in proposals {
+        let (eliminated_at, resolved_at) = match proposal.status {
+            ProposalStatus::Resolved {
+                defender_wins: true,
+            } => (None, Some(0)),
+            ProposalStatus::Resolved {
+                defender_wins: false,
+            }
+            | ProposalStatus::Proven { valid: false } => (Some(0), None),
+            _ => (None, None),
+        };
+        games.insert(
+            proposal.index,
+            SimulatedGame {
+                index: proposal.index,
+                parent: proposal.parent,
+                proposer: proposal.proposer,
+                created_at: proposal.created_at,
+                output_block_number: proposal.output_block_number,
+                correct: proposal.correct,
+                hypothetical: false,
+                eliminated_at,
+                resolved_at,
+            },
+        );
+    }
+    let anchor = |games: &BTreeMap<u64, SimulatedGame>| {
+        games
+            .values()
+            .filter(|game| game.resolved_at.is_some())
+            .max_by_key(|game| (game.output_block_number, game.index))
+            .map(|game| (game.index, game.output_block_number))
+    };
+    let anchor_before = anchor(&games);
+
+    // Add hypothetical challenges and schedule the fault proofs that settle each dispute
+    let mut faults = Vec::new();
+    for action in actions {
+        match *action {
+            HypotheticalAction::Challenge { index, at } => {
+                let Some(target) = games.get(&index).cloned() else {
+                    bail!("Game {index} is not part of the proposal tree.");
+                };
+                if target.parent == target.index {
+                    bail!("Game {index} is a treasury instance and can not be challenged.");
+                }
+                let challenger_index = games.keys().last().copied().unwrap_or_default() + 1;
+                games.insert(
+                    challenger_index,
+                    SimulatedGame {
+                        index: challenger_index,
+                        parent: target.parent,
+                        proposer: Address::ZERO,
+                        created_at: at.unwrap_or(start_time),
+                        output_block_number: target.output_block_number,
+                        correct: target.correct.map(|correct| !correct),
+                        hypothetical: true,
+                        eliminated_at: None,
+                        resolved_at: None,
+                    },
+                );
+            }
+            HypotheticalAction::Fault { index, at } => {
+                if !games.contains_key(&index) {
+                    bail!("Game {index} is not part of the proposal tree.");
+                }
+                faults.push((at.unwrap_or(start_time), index));
+            }
+        }
+    }
+    for game in games.values() {
+        if game.is_open() && game.correct == Some(false) {
+            let proven_at = (game.created_at + assumed_proving_secs).max(start_time);
+            faults.push((proven_at, game.index));
+        }
+    }
+    faults.sort();
+
+    // Eliminate faulty games along with all other open games of their proposers
+    let mut events = Vec::new();
+    let mut eliminated_proposers = HashMap::new();
+    for (time, index) in faults {
+        if !games[&index].is_open() {
+            continue;
+        }
+        let proposer = games[&index].proposer;
+        let forfeited_bond = if eliminated_proposers.insert(proposer, time).is_none() {
+            participation_bond
+        } else {
+            U256::ZERO
+        };
+        for game in games.values_mut() {
+            if game.proposer == proposer && game.is_open() {
+                game.eliminated_at = Some(time);
+                events.push(SimulatedEvent::Elimination {
+                    time,
+                    index: game.index,
+                    proposer,
+                    forfeited_bond: if game.index == index {
+                        forfeited_bond
+                    } else {
+                        U256::ZERO
+                    },
+                });
+            }
+        }
+    }
+
+    // Resolve games in factory order, which guarantees parents are visited before children
+    let mut stuck = BTreeMap::new();
+    let indices = games.keys().copied().collect::<Vec<_>>();
+    for index in indices {
+        let game = &games[&index];
+        if !game.is_open() {
+            continue;
+        }
+        if game.index == game.parent {
+            stuck.insert(
+                index,
+                String::from("Treasury instances are resolved by the factory owner."),
+            );
+            continue;
+        }
+        let parent = &games.get(&game.parent).context(format!(
+            "Parent of game {index} is not part of the proposal tree."
+        ))?;
+        let Some(parent_resolved_at) = parent.resolved_at else {
+            let reason = if parent.eliminated_at.is_some() {
+                format!("Parent {} was eliminated.", parent.index)
+            } else {
+                format!("Parent {} can not be resolved.", parent.index)
+            };
+            stuck.insert(index, reason);
+            continue;
+        };
+        let siblings = games
+            .values()
+            .filter(|other| {
+                other.parent == game.parent && other.index != index && other.index != other.parent
+            })
+            .collect::<Vec<_>>();
+        if let Some(sibling) = siblings
+            .iter()
+            .find(|sibling| sibling.eliminated_at.is_none())
+        {
+            stuck.insert(
+                index,
+                format!("Awaiting a proof against sibling {}.", sibling.index),
+            );
+            continue;
+        }
+        let last_elimination = siblings
+            .iter()
+            .filter_map(|sibling| sibling.eliminated_at)
+            .max()
+            .unwrap_or_default();
+        let resolved_at = [
+            parent_resolved_at,
+            game.created_at + challenge_timeout,
+            last_elimination,
+            start_time,
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or_default();
+        games.get_mut(&index).unwrap().resolved_at = Some(resolved_at);
+        events.push(SimulatedEvent::Resolution {
+            time: resolved_at,
+            index,
+        });
+    }
+    events.sort_by_key(|event| event.time());
+
+    // Surviving proposers may withdraw their bond once all their games are resolved
+    let mut bond_returns = BTreeMap::new();
+    for game in games.values() {
+        if eliminated_proposers.contains_key(&game.proposer) || game.index == game.parent {
+            continue;
+        }
+        let entry = bond_returns.entry(game.proposer).or_insert(Some(0));
+        *entry = match (*entry, game.resolved_at) {
+            (Some(latest), Some(resolved_at)) => Some(latest.max(resolved_at)),
+            _ => None,
+        };
+    }
+
+    let anchor_after = anchor(&games);
+    Ok(SimulationReport {
+        start_time,
+        challenge_timeout,
+        participation_bond,
+        events,
+        stuck,
+        bond_returns,
+        anchor_before: anchor_before.map(|(index, _)| index),
+        anchor_after: anchor_after.map(|(index, _)| index),
+        anchor_block_before: anchor_before.map(|(_, block)| block),
+        anchor_block_after: anchor_after.map(|(_, block)| block),
+    })
+}
+
+pub async fn simulate_actions(args: SimulateArgs) -> anyhow::Result<()> {
+    let eth_rpc_provider = ProviderBuilder::new().on_http(args.eth_rpc_url.as_str().try_into()?);
+    let dispute_game_factory =
+        IDisputeGameFactory::new(args.dispute_game_factory, &eth_rpc_provider);
+    let kailua_game = KailuaGame::new(
+        dispute_game_factory
+            .gameImpls(KAILUA_GAME_TYPE)
+            .stall()
+            .await
+            .impl_,
+        &eth_rpc_provider,
+    );
+    let challenge_timeout = kailua_game
+        .maxClockDuration()
+        .stall()
+        .await
+        .maxClockDuration_;
+    let kailua_treasury = KailuaTreasury::new(
+        kailua_game.treasury().stall().await.treasury_,
+        &eth_rpc_provider,
+    );
+    let participation_bond = kailua_treasury.participationBond().stall().await._0;
+    let start_time = match args.at {
+        Some(at) => at,
+        None => eth_rpc_provider
+            .get_block(
+                BlockId::Number(BlockNumberOrTag::Latest),
+                BlockTransactionsKind::Hashes,
+            )
+            .await
+            .context("get_block")?
+            .context("Could not fetch latest L1 block")?
+            .header()
+            .timestamp(),
+    };
+
+    let proposals = fetch_proposal_tree(&args.api_url).await?;
+    info!("Simulating over {} proposals.", proposals.len());
+    let report = simulate(
+        &proposals,
+        &args.actions,
+        start_time,
+        challenge_timeout,
+        participation_bond,
+        args.assumed_proving_secs,
+    )?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    for event in &report.events {
+        match event {
+            SimulatedEvent::Elimination {
+                time,
+                index,
+                proposer,
+                forfeited_bond,
+            } => println!(
+                "[{time}] Game {index} by {proposer} is eliminated, forfeiting {forfeited_bond} wei to the prover."
+            ),
+            SimulatedEvent::Resolution { time, index } => {
+                println!("[{time}] Game {index} is resolved in favor of its proposer.")
+            }
+        }
+    }
+    for (index, reason) in &report.stuck {
+        println!("Game {index} remains unresolved: {reason}");
+    }
+    for (proposer, returned_at) in &report.bond_returns {
+        match returned_at {
+            Some(time) => println!("Proposer {proposer} may withdraw its bond after {time}."),
+            None => println!("Proposer {proposer} keeps its bond locked in unresolved games."),
+        }
+    }
+    match (report.anchor_before, report.anchor_after) {
+        (before, after) if before == after => {
+            println!("The anchor remains at game {before:?}.")
+        }
+        (before, after) => println!(
+            "The anchor moves from game {before:?} (block {:?}) to game {after:?} (block {:?}).",
+            report.anchor_block_before, report.anchor_block_after
+        ),
+    }
+    Ok(())
+}
@@ -0,0 +1,352 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::blob_report::check_blob;
+use crate::db::proposal::Proposal;
+use crate::db::KailuaDB;
+use crate::stall::Stall;
+use crate::KAILUA_GAME_TYPE;
+use alloy::eips::eip4844::FIELD_ELEMENTS_PER_BLOB;
+use alloy::network::Network;
+use alloy::primitives::{keccak256, Address, Bytes, PrimitiveSignature, B256, U256};
+use alloy::providers::Provider;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::Signer;
+use alloy::transports::Transport;
+use anyhow::{bail, ensure, Context};
+use kailua_common::blobs::intermediate_outputs;
+use kailua_contracts::{
+    IDisputeGameFactory::{gameAtIndexReturn, IDisputeGameFactoryInstance},
+    *,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct SnapshotArgs {
+    /// Path to periodically write a signed snapshot of the proposal database to
+    #[clap(long, env)]
+    pub snapshot_export: Option<PathBuf>,
+    /// Secret key to sign exported snapshots with instead of the validator key
+    #[clap(long, env)]
+    pub snapshot_key: Option<String>,
+    /// Minimum number of seconds between two snapshot exports
+    #[clap(long, env, default_value_t = 3600)]
+    pub snapshot_interval_secs: u64,
+
+    /// Path of a signed snapshot of a peer's proposal database to warm-start from
+    #[clap(long, env)]
+    pub snapshot_import: Option<PathBuf>,
+    /// Comma-separated addresses whose snapshots are trusted for warm-starting
+    #[clap(long, env, value_delimiter = ',')]
+    pub snapshot_signers: Vec<Address>,
+}
+
+/// The proposals tracked by a validator up to a factory index
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProposalSnapshot {
+    pub l2_chain_id: u64,
+    pub dispute_game_factory: Address,
+    pub cfg_hash: B256,
+    /// The unix timestamp of the snapshot
+    pub created_at: u64,
+    /// The first factory index that was not yet scanned
+    pub next_factory_index: u64,
+    /// The factory indices below `next_factory_index` that are not Kailua proposals
+    pub skipped_proposals: Vec<u64>,
+    pub proposals: Vec<Proposal>,
+}
+
+impl ProposalSnapshot {
+    /// Captures the proposals currently held in the database
+    pub fn capture(kailua_db: &KailuaDB) -> Self {
+        let proposals = (kailua_db.state.pruned_below..kailua_db.state.next_factory_index)
+            .filter_map(|index| kailua_db.get_local_proposal(&index))
+            .collect();
+        Self {
+            l2_chain_id: kailua_db.config.l2_chain_id,
+            dispute_game_factory: kailua_db.config.factory,
+            cfg_hash: kailua_db.config.cfg_hash,
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            next_factory_index: kailua_db.state.next_factory_index,
+            skipped_proposals: kailua_db.state.skipped_proposals.iter().copied().collect(),
+            proposals,
+        }
+    }
+
+    /// The digest signed by the snapshot's signer
+    pub fn digest(&self) -> anyhow::Result<B256> {
+        Ok(keccak256(serde_json::to_vec(self)?))
+    }
+}
+
+/// A snapshot signed by the operator of the validator that captured it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedSnapshot {
+    pub snapshot: ProposalSnapshot,
+    pub signer: Address,
+    /// The EIP-191 signature of the snapshot's digest
+    pub signature: Bytes,
+}
+
+impl SignedSnapshot {
+    pub async fn sign(
+        snapshot: ProposalSnapshot,
+        signer: &PrivateKeySigner,
+    ) -> anyhow::Result<Self> {
+        let signature = signer
+            .sign_message(snapshot.digest()?.as_slice())
+            .await
+            .context("Failed to sign snapshot")?;
+        Ok(Self {
+            snapshot,
+            signer: signer.address(),
+            signature: Bytes::from(signature.as_bytes()),
+        })
+    }
+
+    /// Returns the snapshot if it was signed by one of the trusted signers
+    pub fn verify(self, trusted_signers: &[Address]) -> anyhow::Result<ProposalSnapshot> {
+        let signature = PrimitiveSignature::try_from(self.signature.as_ref())
+            .context("Malformed snapshot signature")?;
+        let recovered = signature
+            .recover_address_from_msg(self.snapshot.digest()?.as_slice())
+            .context("Failed to recover snapshot signer")?;
+        ensure!(
+            recovered == self.signer,
+            "Snapshot signature was made by {recovered} instead of {}.",
+            self.signer
+        );
+        ensure!(
+            trusted_signers.contains(&self.signer),
+            "Snapshot signer {} is not trusted.",
+            self.signer
+        );
+        Ok(self.snapshot)
+    }
+}
+
+/// Periodically exports signed snapshots of the validator's proposal database
+#[derive(Clone, Debug)]
+pub struct SnapshotExporter {
+    pub path: PathBuf,
+    pub signer: PrivateKeySigner,
+    pub interval: Duration,
+    pub last_export: Option<Instant>,
+}
+
+impl SnapshotExporter {
+    /// Returns the exporter configured by the arguments, if an export path was provided
+    pub fn new(args: &SnapshotArgs, validator_key: &str) -> anyhow::Result<Option<Self>> {
+        let Some(path) = args.snapshot_export.clone() else {
+            return Ok(None);
+        };
+        let signer =
+            PrivateKeySigner::from_str(args.snapshot_key.as_deref().unwrap_or(validator_key))
+                .context("Invalid snapshot key")?;
+        info!(
+            "Exporting snapshots signed by {} to {path:?}.",
+            signer.address()
+        );
+        Ok(Some(Self {
+            path,
+            signer,
+            interval: Duration::from_secs(args.snapshot_interval_secs),
+            last_export: None,
+        }))
+    }
+
+    /// Writes a snapshot of the database if the export interval elapsed since the last one
+    pub async fn export(&mut self, kailua_db: &KailuaDB) -> anyhow::Result<()> {
+        if self
+            .last_export
+            .is_some_and(|last_export| last_export.elapsed() < self.interval)
+        {
+            return Ok(());
+        }
+        let snapshot = ProposalSnapshot::capture(kailua_db);
+        let proposal_count = snapshot.proposals.len();
+        let signed_snapshot = SignedSnapshot::sign(snapshot, &self.signer).await?;
+        // replace the previous snapshot atomically so that peers never read a partial one
+        let tmp_path = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_vec(&signed_snapshot)?).await?;
+        tokio::fs::rename(&tmp_path, &self.path)
+            .await
+            .context(format!("Failed to write snapshot to {:?}", self.path))?;
+        self.last_export = Some(Instant::now());
+        info!(
+            "Exported snapshot of {proposal_count} proposals up to factory index {}.",
+            kailua_db.state.next_factory_index
+        );
+        Ok(())
+    }
+}
+
+/// Checks that the proposal matches the game at its factory index and the blobs it published
+pub async fn verify_proposal<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    kailua_db: &KailuaDB,
+    dispute_game_factory: &IDisputeGameFactoryInstance<T, P, N>,
+    proposal: &Proposal,
+) -> anyhow::Result<()> {
+    let gameAtIndexReturn {
+        gameType_: game_type,
+        proxy_: game_address,
+        ..
+    } = dispute_game_factory
+        .gameAtIndex(U256::from(proposal.index))
+        .stall()
+        .await;
+    ensure!(
+        game_type == KAILUA_GAME_TYPE && game_address == proposal.contract,
+        "Game at index {} is {game_address} instead of {}.",
+        proposal.index,
+        proposal.contract
+    );
+    let provider = dispute_game_factory.provider();
+    let tournament = KailuaTournament::new(proposal.contract, provider);
+    let output_root: B256 = tournament.rootClaim().stall().await.rootClaim_.0.into();
+    let output_block_number: u64 = tournament.l2BlockNumber().stall().await.l2BlockNumber_.to();
+    let l1_head: B256 = tournament.l1Head().stall().await.l1Head_.0.into();
+    ensure!(
+        (output_root, output_block_number, l1_head)
+            == (
+                proposal.output_root,
+                proposal.output_block_number,
+                proposal.l1_head
+            ),
+        "Claim of proposal {} does not match its game.",
+        proposal.index
+    );
+    if !proposal.has_parent() {
+        return Ok(());
+    }
+
+    let game = KailuaGame::new(proposal.contract, provider);
+    let parent = game.parentGameIndex().stall().await.parentGameIndex_;
+    let proposer = game.proposer().stall().await.proposer_;
+    let created_at = game.createdAt().stall().await._0;
+    ensure!(
+        (parent, proposer, created_at) == (proposal.parent, proposal.proposer, proposal.created_at),
+        "Origin of proposal {} does not match its game.",
+        proposal.index
+    );
+    let config = &kailua_db.config;
+    ensure!(
+        proposal.io_blobs.len() as u64 == config.proposal_blobs
+            && proposal.io_field_elements.len() as u64 + 1 == config.proposal_output_count(),
+        "Proposal {} has an unexpected number of intermediate outputs.",
+        proposal.index
+    );
+    let io_chunks = proposal
+        .io_field_elements
+        .chunks(FIELD_ELEMENTS_PER_BLOB as usize);
+    for (i, ((blob_hash, blob_data), io_chunk)) in
+        proposal.io_blobs.iter().zip(io_chunks).enumerate()
+    {
+        let published_hash = game.proposalBlobHashes(U256::from(i)).stall().await._0;
+        let blob_check = check_blob(i, published_hash, blob_data)?;
+        ensure!(
+            *blob_hash == published_hash
+                && blob_check.versioned_hash_matches
+                && blob_check.commitment_matches,
+            "Blob {i} of proposal {} does not match its published commitment.",
+            proposal.index
+        );
+        ensure!(
+            intermediate_outputs(blob_data, io_chunk.len())? == io_chunk,
+            "Intermediate outputs of proposal {} do not match blob {i}.",
+            proposal.index
+        );
+    }
+    Ok(())
+}
+
+/// Loads the proposals of a trusted snapshot into the database, stopping at the first proposal
+/// that is inconsistent with the chain so that it is scanned from scratch instead. Returns the
+/// indices of the imported proposals that are not yet resolved.
+pub async fn import_snapshot<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    args: &SnapshotArgs,
+    kailua_db: &mut KailuaDB,
+    dispute_game_factory: &IDisputeGameFactoryInstance<T, P, N>,
+) -> anyhow::Result<Vec<u64>> {
+    let Some(path) = &args.snapshot_import else {
+        return Ok(vec![]);
+    };
+    if args.snapshot_signers.is_empty() {
+        bail!("Importing a snapshot requires at least one trusted --snapshot-signers address.");
+    }
+    let data = std::fs::read(path).context(format!("Failed to read snapshot {path:?}"))?;
+    let signed_snapshot: SignedSnapshot =
+        serde_json::from_slice(&data).context("Failed to parse snapshot")?;
+    let signer = signed_snapshot.signer;
+    let snapshot = signed_snapshot.verify(&args.snapshot_signers)?;
+    ensure!(
+        snapshot.l2_chain_id == kailua_db.config.l2_chain_id
+            && snapshot.dispute_game_factory == kailua_db.config.factory
+            && snapshot.cfg_hash == kailua_db.config.cfg_hash,
+        "Snapshot was captured for another rollup deployment."
+    );
+    if snapshot.next_factory_index <= kailua_db.state.next_factory_index {
+        info!(
+            "Snapshot ends at factory index {} and provides no warm start.",
+            snapshot.next_factory_index
+        );
+        return Ok(vec![]);
+    }
+    info!(
+        "Importing {} proposals up to factory index {} from snapshot by {signer}.",
+        snapshot.proposals.len(),
+        snapshot.next_factory_index
+    );
+
+    let start = kailua_db.state.next_factory_index;
+    let mut next_factory_index = snapshot.next_factory_index;
+    let mut imported = 0;
+    let mut unresolved = Vec::new();
+    for proposal in snapshot
+        .proposals
+        .iter()
+        .filter(|proposal| proposal.index >= start)
+    {
+        if let Err(err) = verify_proposal(kailua_db, dispute_game_factory, proposal).await {
+            warn!("Stopping snapshot import: {err:?}");
+            next_factory_index = proposal.index;
+            break;
+        }
+        kailua_db.set_local_proposal(proposal.index, proposal)?;
+        kailua_db.track_proposal(proposal);
+        if !proposal.status.is_resolved() {
+            unresolved.push(proposal.index);
+        }
+        imported += 1;
+    }
+    kailua_db.state.skipped_proposals.extend(
+        snapshot
+            .skipped_proposals
+            .into_iter()
+            .filter(|index| (start..next_factory_index).contains(index)),
+    );
+    kailua_db.state.next_factory_index = next_factory_index;
+    if !kailua_db.state.equivocations.is_empty() {
+        kailua_db.save_equivocations()?;
+    }
+    info!("Imported {imported} proposals. Scanning from factory index {next_factory_index}.");
+    Ok(unresolved)
+}
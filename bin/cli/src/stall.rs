@@ -17,16 +17,127 @@ use alloy::network::Network;
 use alloy::providers::Provider;
 use alloy::sol_types::SolCall;
 use alloy::transports::Transport;
+use anyhow::bail;
 use async_trait::async_trait;
-use std::future::IntoFuture;
+use std::collections::BTreeMap;
+use std::future::{Future, IntoFuture};
 use std::marker::PhantomData;
+use std::sync::Mutex;
 use std::time::Duration;
-use tokio::time::sleep;
-use tracing::error;
+use tokio::time::{sleep, timeout_at, Instant};
+use tracing::{error, info};
+
+/// The upper bounds (in milliseconds) of the call latency histogram buckets
+pub const LATENCY_BUCKETS_MS: [u64; 8] = [10, 50, 100, 250, 500, 1_000, 5_000, 30_000];
+
+tokio::task_local! {
+    /// The instant by which the current scan must complete
+    static SCAN_DEADLINE: Instant;
+}
+
+/// The latency histogram of a single contract call
+#[derive(Clone, Debug, Default)]
+pub struct CallLatency {
+    pub count: u64,
+    pub retries: u64,
+    pub total: Duration,
+    pub max: Duration,
+    /// Call counts per [LATENCY_BUCKETS_MS] bucket, with a final bucket for slower calls
+    pub buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+}
+
+impl CallLatency {
+    pub fn record(&mut self, elapsed: Duration, retries: u64) {
+        self.count += 1;
+        self.retries += retries;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+        let elapsed_ms = elapsed.as_millis() as u64;
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| elapsed_ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+    }
+
+    /// Returns the upper bound of the bucket containing the given quantile of calls
+    pub fn quantile_ms(&self, quantile: f64) -> Option<u64> {
+        let target = (self.count as f64 * quantile).ceil() as u64;
+        let mut seen = 0;
+        for (i, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target.max(1) {
+                return LATENCY_BUCKETS_MS.get(i).copied();
+            }
+        }
+        None
+    }
+}
+
+/// Latency histograms of all contract reads, keyed by call signature
+static CALL_LATENCIES: Mutex<BTreeMap<&'static str, CallLatency>> = Mutex::new(BTreeMap::new());
+
+/// Returns a snapshot of the latency histograms of all contract reads made so far
+pub fn call_latencies() -> BTreeMap<&'static str, CallLatency> {
+    CALL_LATENCIES.lock().unwrap().clone()
+}
+
+/// Logs a latency summary of every contract read made so far
+pub fn report_call_latencies() {
+    for (name, latency) in call_latencies() {
+        let mean = latency.total / latency.count.max(1) as u32;
+        let percentile = |q| {
+            latency
+                .quantile_ms(q)
+                .map_or(String::from("slow"), |ms| format!("<={ms}ms"))
+        };
+        info!(
+            "Contract call {name}: {} calls, {} retries, mean {}ms, p50 {}, p99 {}, max {}ms.",
+            latency.count,
+            latency.retries,
+            mean.as_millis(),
+            percentile(0.5),
+            percentile(0.99),
+            latency.max.as_millis()
+        );
+    }
+}
+
+/// Runs a scan that must complete within the given duration, failing instead of stalling on a
+/// hung contract read. The deadline is visible to all contract reads made within the scan.
+pub async fn with_scan_deadline<F: Future>(
+    duration: Duration,
+    scan: F,
+) -> anyhow::Result<F::Output> {
+    let deadline = Instant::now() + duration;
+    match SCAN_DEADLINE
+        .scope(deadline, timeout_at(deadline, scan))
+        .await
+    {
+        Ok(output) => Ok(output),
+        Err(_) => bail!("Scan did not complete within {}s.", duration.as_secs()),
+    }
+}
 
 #[async_trait]
 pub trait Stall<R> {
     async fn stall(&self) -> R;
+
+    /// Reads a getter that older contracts may lack, returning None if the call reverts or
+    /// returns no data, and retrying any other error like [Stall::stall].
+    async fn stall_optional(&self) -> Option<R>;
+}
+
+/// Returns whether a failed contract call was rejected by the contract itself, rather than lost
+/// to the transport or the node
+pub fn is_contract_rejection(error: &alloy::contract::Error) -> bool {
+    match error {
+        alloy::contract::Error::TransportError(error) => error
+            .as_error_resp()
+            .is_some_and(|payload| payload.message.to_lowercase().contains("revert")),
+        // the call succeeded but returned no or malformed data
+        _ => true,
+    }
 }
 
 #[async_trait]
@@ -43,7 +154,9 @@ where
     C::Return: Send,
 {
     async fn stall(&self) -> C::Return {
-        loop {
+        let start = Instant::now();
+        let mut retries = 0;
+        let result = loop {
             match self
                 .call_raw()
                 .await
@@ -51,7 +164,46 @@ where
             {
                 Ok(res) => break res,
                 Err(error) => {
-                    error!("Stall Error: {:?}", error);
+                    let remaining = SCAN_DEADLINE
+                        .try_with(|deadline| deadline.saturating_duration_since(Instant::now()));
+                    match remaining {
+                        Ok(remaining) => error!(
+                            "Stall Error ({}, {}s left in scan): {:?}",
+                            C::SIGNATURE,
+                            remaining.as_secs(),
+                            error
+                        ),
+                        Err(_) => error!("Stall Error ({}): {:?}", C::SIGNATURE, error),
+                    }
+                    retries += 1;
+                    // Wait before retrying
+                    sleep(Duration::from_millis(250)).await;
+                }
+            }
+        };
+        CALL_LATENCIES
+            .lock()
+            .unwrap()
+            .entry(C::SIGNATURE)
+            .or_default()
+            .record(start.elapsed(), retries);
+        result
+    }
+
+    async fn stall_optional(&self) -> Option<C::Return> {
+        loop {
+            match self
+                .call_raw()
+                .await
+                .and_then(|raw_result| self.decode_output(raw_result, true))
+            {
+                Ok(res) => break Some(res),
+                Err(error) if is_contract_rejection(&error) => {
+                    info!("Contract call {} rejected: {error}", C::SIGNATURE);
+                    break None;
+                }
+                Err(error) => {
+                    error!("Stall Error ({}): {:?}", C::SIGNATURE, error);
                     // Wait before retrying
                     sleep(Duration::from_millis(250)).await;
                 }
@@ -0,0 +1,360 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::api::ProposalView;
+use crate::db::proposal::Proposal;
+use crate::db::ProofStatus;
+use crate::latency::unix_now;
+use crate::stall::Stall;
+use crate::KAILUA_GAME_TYPE;
+use alloy::eips::BlockNumberOrTag;
+use alloy::network::primitives::BlockTransactionsKind;
+use alloy::primitives::Address;
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::Filter;
+use alloy::sol_types::SolEvent;
+use anyhow::Context;
+use kailua_contracts::*;
+use kailua_host::fetch_rollup_config;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use tracing::info;
+
+/// The number of local proof outcomes kept for computing success rates
+pub const MAX_PROOF_SAMPLES: usize = 10_000;
+
+/// The default windows (in seconds) to compute statistics over: an hour, a day and a week
+pub const DEFAULT_STATS_WINDOWS: [u64; 3] = [3_600, 86_400, 604_800];
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct StatsArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the OP-NODE endpoint to use
+    #[clap(long, env)]
+    pub op_node_url: String,
+    /// Address of the OP-GETH endpoint to use (eth and debug namespace required).
+    #[clap(long, env)]
+    pub op_geth_url: String,
+    /// Address of the ethereum rpc endpoint to use (eth namespace required)
+    #[clap(long, env)]
+    pub eth_rpc_url: String,
+
+    /// L1 block to start scanning for games from
+    #[clap(long, env, default_value_t = 0)]
+    pub from_block: u64,
+    /// Maximum number of L1 blocks to query logs for in a single request
+    #[clap(long, env, default_value_t = 10_000)]
+    pub log_chunk_size: u64,
+    /// Windows (in seconds) to compute the statistics over
+    #[clap(long, env, value_delimiter = ',', default_values_t = DEFAULT_STATS_WINDOWS)]
+    pub window_secs: Vec<u64>,
+
+    /// Whether to print the statistics as JSON
+    #[clap(long, env)]
+    pub json: bool,
+}
+
+/// The outcome of a single proposal counted towards the statistics
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct ProposalSample {
+    pub created_at: u64,
+    /// Whether the proposal contradicts an earlier sibling
+    pub challenge: bool,
+    pub correct: Option<bool>,
+    pub resolved_at: Option<u64>,
+}
+
+/// The outcome of a single proof attempt counted towards the statistics
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct ProofSample {
+    pub at: u64,
+    pub succeeded: bool,
+}
+
+/// Statistics of the proposals created and proofs attempted within a time window
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WindowStats {
+    pub window_secs: u64,
+    pub proposals: usize,
+    /// The proposals whose correctness is known
+    pub assessed: usize,
+    pub incorrect: usize,
+    pub incorrect_rate: Option<f64>,
+    pub challenges: usize,
+    pub challenge_rate: Option<f64>,
+    pub resolved: usize,
+    pub mean_resolution_secs: Option<u64>,
+    pub proof_attempts: usize,
+    pub proofs_succeeded: usize,
+    pub proof_success_rate: Option<f64>,
+}
+
+fn rate(count: usize, total: usize) -> Option<f64> {
+    (total > 0).then(|| count as f64 / total as f64)
+}
+
+impl WindowStats {
+    /// Aggregates the samples that fall within the window ending at `now`
+    pub fn compute(
+        window_secs: u64,
+        now: u64,
+        proposals: &[ProposalSample],
+        proofs: &[ProofSample],
+    ) -> Self {
+        let since = now.saturating_sub(window_secs);
+        let proposals = proposals
+            .iter()
+            .filter(|sample| sample.created_at >= since)
+            .collect::<Vec<_>>();
+        let proofs = proofs
+            .iter()
+            .filter(|sample| sample.at >= since)
+            .collect::<Vec<_>>();
+        let assessed = proposals.iter().filter(|p| p.correct.is_some()).count();
+        let incorrect = proposals
+            .iter()
+            .filter(|p| p.correct == Some(false))
+            .count();
+        let challenges = proposals.iter().filter(|p| p.challenge).count();
+        let resolution_secs = proposals
+            .iter()
+            .filter_map(|p| Some(p.resolved_at?.saturating_sub(p.created_at)))
+            .collect::<Vec<_>>();
+        let proofs_succeeded = proofs.iter().filter(|p| p.succeeded).count();
+        Self {
+            window_secs,
+            proposals: proposals.len(),
+            assessed,
+            incorrect,
+            incorrect_rate: rate(incorrect, assessed),
+            challenges,
+            challenge_rate: rate(challenges, proposals.len()),
+            resolved: resolution_secs.len(),
+            mean_resolution_secs: (!resolution_secs.is_empty())
+                .then(|| resolution_secs.iter().sum::<u64>() / resolution_secs.len() as u64),
+            proof_attempts: proofs.len(),
+            proofs_succeeded,
+            proof_success_rate: rate(proofs_succeeded, proofs.len()),
+        }
+    }
+
+    pub fn print(&self) {
+        let percent = |rate: Option<f64>| {
+            rate.map_or(String::from("n/a"), |rate| format!("{:.2}%", rate * 100.0))
+        };
+        println!("Last {}s:", self.window_secs);
+        println!(
+            "  Proposals: {} ({} incorrect of {} assessed, {})",
+            self.proposals,
+            self.incorrect,
+            self.assessed,
+            percent(self.incorrect_rate)
+        );
+        println!(
+            "  Challenges: {} ({} of proposals)",
+            self.challenges,
+            percent(self.challenge_rate)
+        );
+        println!(
+            "  Resolved: {} (mean {} to resolution)",
+            self.resolved,
+            self.mean_resolution_secs
+                .map_or(String::from("n/a"), |secs| format!("{secs}s"))
+        );
+        println!(
+            "  Proofs: {} succeeded of {} attempted ({})",
+            self.proofs_succeeded,
+            self.proof_attempts,
+            percent(self.proof_success_rate)
+        );
+    }
+}
+
+/// Collects the validator's proof outcomes and resolution times for the query api
+#[derive(Clone, Debug, Default)]
+pub struct StatsTracker {
+    pub proofs: VecDeque<ProofSample>,
+    /// The resolution times of disputed proposals keyed by factory index
+    pub resolutions: BTreeMap<u64, u64>,
+}
+
+impl StatsTracker {
+    pub fn record_proof(&mut self, succeeded: bool) {
+        self.proofs.push_back(ProofSample {
+            at: unix_now(),
+            succeeded,
+        });
+        if self.proofs.len() > MAX_PROOF_SAMPLES {
+            self.proofs.pop_front();
+        }
+    }
+
+    pub fn record_resolution(&mut self, index: u64, resolved_at: u64) {
+        self.resolutions.insert(index, resolved_at);
+    }
+
+    /// Forgets the resolutions of proposals that were pruned from the database
+    pub fn prune(&mut self, pruned_below: u64) {
+        self.resolutions = self.resolutions.split_off(&pruned_below);
+    }
+
+    /// Computes the statistics of the given proposals over each window
+    pub fn compute<'a>(
+        &self,
+        proposals: impl Iterator<Item = &'a ProposalView>,
+        windows: &[u64],
+    ) -> Vec<WindowStats> {
+        let proposals = proposals
+            .filter(|proposal| proposal.parent != proposal.index)
+            .map(|proposal| ProposalSample {
+                created_at: proposal.created_at,
+                challenge: proposal.contender.is_some(),
+                correct: proposal.correct,
+                resolved_at: self.resolutions.get(&proposal.index).copied(),
+            })
+            .collect::<Vec<_>>();
+        let proofs = self.proofs.iter().copied().collect::<Vec<_>>();
+        let now = unix_now();
+        windows
+            .iter()
+            .map(|window| WindowStats::compute(*window, now, &proposals, &proofs))
+            .collect()
+    }
+}
+
+pub async fn stats(args: StatsArgs) -> anyhow::Result<()> {
+    let eth_rpc_provider = ProviderBuilder::new().on_http(args.eth_rpc_url.as_str().try_into()?);
+
+    info!("Fetching rollup configuration from rpc endpoints.");
+    let config = fetch_rollup_config(&args.op_node_url, &args.op_geth_url, None)
+        .await
+        .context("fetch_rollup_config")?;
+    let system_config = SystemConfig::new(config.l1_system_config_address, &eth_rpc_provider);
+    let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
+    info!("DisputeGameFactory({dgf_address:?})");
+
+    let latest_block = eth_rpc_provider
+        .get_block_by_number(BlockNumberOrTag::Latest, BlockTransactionsKind::Hashes)
+        .await
+        .context("get_block_by_number")?
+        .context("Could not fetch latest L1 block")?;
+    let now = latest_block.header.timestamp;
+    let to_block = latest_block.header.number;
+
+    // Proposals, in creation order
+    let mut proposals = Vec::new();
+    let mut addresses = Vec::new();
+    let mut children: HashMap<Address, Vec<usize>> = HashMap::new();
+    let mut start = args.from_block;
+    while start <= to_block {
+        let end = to_block.min(start + args.log_chunk_size.max(1) - 1);
+        let filter = Filter::new()
+            .address(dgf_address)
+            .event_signature(IDisputeGameFactory::DisputeGameCreated::SIGNATURE_HASH)
+            .from_block(start)
+            .to_block(end);
+        for log in eth_rpc_provider
+            .get_logs(&filter)
+            .await
+            .context("get_logs")?
+        {
+            let created = log.log_decode::<IDisputeGameFactory::DisputeGameCreated>()?;
+            let event = &created.inner.data;
+            if event.gameType != KAILUA_GAME_TYPE {
+                continue;
+            }
+            let tournament = KailuaTournament::new(event.disputeProxy, &eth_rpc_provider);
+            let parent = tournament.parentGame().stall().await.parentGame_;
+            if parent == event.disputeProxy {
+                // Treasury instances anchor the proposal tree and are not proposals
+                continue;
+            }
+            let status = tournament.status().stall().await._0;
+            let siblings = children.entry(parent).or_default();
+            siblings.push(proposals.len());
+            proposals.push(ProposalSample {
+                created_at: tournament.createdAt().stall().await._0,
+                challenge: siblings.len() > 1,
+                correct: Proposal::parse_finality(status)?,
+                resolved_at: match status {
+                    0 => None,
+                    _ => Some(tournament.resolvedAt().stall().await._0),
+                },
+            });
+            addresses.push(event.disputeProxy);
+        }
+        start = end + 1;
+    }
+    info!("Found {} proposals.", proposals.len());
+
+    // Proofs settle the correctness of both children of a match ahead of resolution
+    let mut proven = vec![false; proposals.len()];
+    let mut start = args.from_block;
+    while start <= to_block {
+        let end = to_block.min(start + args.log_chunk_size.max(1) - 1);
+        let filter = Filter::new()
+            .event_signature(KailuaTournament::Proven::SIGNATURE_HASH)
+            .from_block(start)
+            .to_block(end);
+        for log in eth_rpc_provider
+            .get_logs(&filter)
+            .await
+            .context("get_logs")?
+        {
+            let Some(siblings) = children.get(&log.address()) else {
+                continue;
+            };
+            let proven_log = log.log_decode::<KailuaTournament::Proven>()?;
+            let event = &proven_log.inner.data;
+            let Some((u_valid, v_valid)) = ProofStatus::parse(event.status)?.outcomes() else {
+                continue;
+            };
+            for (child, valid) in [(event.u, u_valid), (event.v, v_valid)] {
+                if let Some(position) = siblings.get(child as usize) {
+                    proposals[*position].correct = Some(valid);
+                }
+            }
+            if let Some(position) = siblings.get(event.v as usize) {
+                proven[*position] = true;
+            }
+        }
+        start = end + 1;
+    }
+    // Every challenge demands a proof, which succeeded if its match was proven
+    let proofs = proposals
+        .iter()
+        .zip(&proven)
+        .filter(|(proposal, _)| proposal.challenge)
+        .map(|(proposal, proven)| ProofSample {
+            at: proposal.created_at,
+            succeeded: *proven,
+        })
+        .collect::<Vec<_>>();
+
+    let stats = args
+        .window_secs
+        .iter()
+        .map(|window| WindowStats::compute(*window, now, &proposals, &proofs))
+        .collect::<Vec<_>>();
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        for window in &stats {
+            window.print();
+        }
+    }
+    Ok(())
+}
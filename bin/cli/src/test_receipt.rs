@@ -0,0 +1,211 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::primitives::{Bytes, B256};
+use anyhow::{bail, Context};
+use kailua_build::KAILUA_FPVM_ID;
+use kailua_client::proof::Proof;
+use kailua_common::journal::{ProofJournal, PROOF_JOURNAL_VERSION};
+use risc0_zkvm::sha::{Digest, Digestible};
+use risc0_zkvm::{FakeReceipt, InnerReceipt, Receipt, ReceiptClaim};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct GenTestReceiptArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Path to a proof produced by kailua-host to export instead of faking a receipt
+    #[clap(long)]
+    pub from_proof: Option<PathBuf>,
+
+    /// The image id to fake a receipt for (defaults to the fault proof program)
+    #[clap(long)]
+    pub image_id: Option<B256>,
+    /// The output of the proposal preconditions committed to by the journal
+    #[clap(long)]
+    pub precondition_output: Option<B256>,
+    /// The l1 head hash committed to by the journal
+    #[clap(long)]
+    pub l1_head: Option<B256>,
+    /// The agreed l2 output root committed to by the journal
+    #[clap(long)]
+    pub agreed_l2_output_root: Option<B256>,
+    /// The claimed l2 output root committed to by the journal
+    #[clap(long)]
+    pub claimed_l2_output_root: Option<B256>,
+    /// The claimed l2 block number committed to by the journal
+    #[clap(long)]
+    pub claimed_l2_block_number: Option<u64>,
+    /// The rollup configuration hash committed to by the journal
+    #[clap(long)]
+    pub config_hash: Option<B256>,
+    /// The l2 chain id committed to by the journal
+    #[clap(long)]
+    pub l2_chain_id: Option<u64>,
+
+    /// Path to write the json fixture to
+    #[clap(long)]
+    pub fixture_file: PathBuf,
+    /// Path to write the proof itself to, in the format read by kailua-cli
+    #[clap(long)]
+    pub proof_file: Option<PathBuf>,
+}
+
+/// A receipt and journal in the form consumed by the Kailua contracts
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestReceiptFixture {
+    /// Whether the seal is only accepted by a `RiscZeroMockVerifier`
+    pub fake: bool,
+    pub image_id: B256,
+    pub journal: Bytes,
+    pub journal_digest: B256,
+    pub seal: Bytes,
+    pub precondition_output: B256,
+    pub l1_head: B256,
+    pub agreed_l2_output_root: B256,
+    pub claimed_l2_output_root: B256,
+    pub claimed_l2_block_number: u64,
+    pub config_hash: B256,
+    pub l2_chain_id: u64,
+}
+
+impl GenTestReceiptArgs {
+    /// Builds the journal from the provided fields, defaulting the rest to zero
+    pub fn journal(&self) -> ProofJournal {
+        ProofJournal {
+            version: PROOF_JOURNAL_VERSION,
+            precondition_output: self.precondition_output.unwrap_or_default(),
+            l1_head: self.l1_head.unwrap_or_default(),
+            agreed_l2_output_root: self.agreed_l2_output_root.unwrap_or_default(),
+            claimed_l2_output_root: self.claimed_l2_output_root.unwrap_or_default(),
+            claimed_l2_block_number: self.claimed_l2_block_number.unwrap_or_default(),
+            config_hash: self.config_hash.unwrap_or_default(),
+            l2_chain_id: self.l2_chain_id.unwrap_or_default(),
+        }
+    }
+
+    /// Fails if any provided journal field differs from the given journal
+    pub fn check_journal(&self, journal: &ProofJournal) -> anyhow::Result<()> {
+        let mismatches = [
+            (
+                "precondition-output",
+                self.precondition_output
+                    .map(|v| v != journal.precondition_output),
+            ),
+            ("l1-head", self.l1_head.map(|v| v != journal.l1_head)),
+            (
+                "agreed-l2-output-root",
+                self.agreed_l2_output_root
+                    .map(|v| v != journal.agreed_l2_output_root),
+            ),
+            (
+                "claimed-l2-output-root",
+                self.claimed_l2_output_root
+                    .map(|v| v != journal.claimed_l2_output_root),
+            ),
+            (
+                "claimed-l2-block-number",
+                self.claimed_l2_block_number
+                    .map(|v| v != journal.claimed_l2_block_number),
+            ),
+            (
+                "config-hash",
+                self.config_hash.map(|v| v != journal.config_hash),
+            ),
+            (
+                "l2-chain-id",
+                self.l2_chain_id.map(|v| v != journal.l2_chain_id),
+            ),
+        ];
+        for (name, mismatch) in mismatches {
+            if mismatch == Some(true) {
+                bail!("Proof journal does not match the provided {name}.");
+            }
+        }
+        Ok(())
+    }
+}
+
+pub async fn gen_test_receipt(args: GenTestReceiptArgs) -> anyhow::Result<()> {
+    let (proof, image_id) = match &args.from_proof {
+        Some(path) => {
+            let proof: Proof = bincode::deserialize(
+                &tokio::fs::read(path)
+                    .await
+                    .context(format!("Failed to read proof {path:?}"))?,
+            )?;
+            let Some(receipt) = proof.as_receipt() else {
+                bail!("Proof {path:?} is not a zkvm receipt.");
+            };
+            let image_id = receipt.claim()?.as_value()?.pre.digest();
+            receipt
+                .verify(image_id)
+                .context("Proof receipt does not verify")?;
+            args.check_journal(&ProofJournal::decode_packed(&proof.journal().bytes)?)?;
+            (proof, image_id)
+        }
+        None => {
+            let image_id = args
+                .image_id
+                .map(|id| Digest::from(id.0))
+                .unwrap_or_else(|| Digest::new(KAILUA_FPVM_ID));
+            let journal = args.journal().encode_packed();
+            warn!("Faking a receipt which is only accepted by a RiscZeroMockVerifier.");
+            let receipt = Receipt::new(
+                InnerReceipt::Fake(FakeReceipt::new(ReceiptClaim::ok(
+                    image_id,
+                    journal.clone(),
+                ))),
+                journal,
+            );
+            (Proof::ZKVMReceipt(Box::new(receipt)), image_id)
+        }
+    };
+
+    let journal = ProofJournal::decode_packed(&proof.journal().bytes)?;
+    let fixture = TestReceiptFixture {
+        fake: proof
+            .as_receipt()
+            .is_some_and(|receipt| matches!(receipt.inner, InnerReceipt::Fake(_))),
+        image_id: B256::from_slice(image_id.as_bytes()),
+        journal: Bytes::from(proof.journal().bytes.clone()),
+        journal_digest: B256::from_slice(proof.journal().digest().as_bytes()),
+        seal: Bytes::from(proof.encoded_seal()?),
+        precondition_output: journal.precondition_output,
+        l1_head: journal.l1_head,
+        agreed_l2_output_root: journal.agreed_l2_output_root,
+        claimed_l2_output_root: journal.claimed_l2_output_root,
+        claimed_l2_block_number: journal.claimed_l2_block_number,
+        config_hash: journal.config_hash,
+        l2_chain_id: journal.l2_chain_id,
+    };
+    tokio::fs::write(&args.fixture_file, serde_json::to_vec_pretty(&fixture)?)
+        .await
+        .context(format!(
+            "Failed to write fixture to {:?}",
+            args.fixture_file
+        ))?;
+    info!("Saved test receipt fixture to {:?}.", args.fixture_file);
+    if let Some(path) = &args.proof_file {
+        tokio::fs::write(path, bincode::serialize(&proof)?)
+            .await
+            .context(format!("Failed to write proof to {path:?}"))?;
+        info!("Saved test proof to {path:?}.");
+    }
+    Ok(())
+}
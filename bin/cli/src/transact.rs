@@ -0,0 +1,293 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::eips::eip2718::Encodable2718;
+use alloy::network::{Network, NetworkWallet, TransactionBuilder, TransactionBuilder4844};
+use alloy::primitives::{Address, Bytes, TxHash};
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+/// The percentage by which the fees of a pending transaction are bumped to replace it
+pub const FEE_BUMP_PERCENT: u128 = 25;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct PrivateTxnArgs {
+    /// Address of a private transaction rpc endpoint (e.g. Flashbots Protect) to submit through
+    #[clap(long, env)]
+    pub private_rpc_url: Option<String>,
+    /// Seconds to wait for private inclusion before falling back to the public mempool
+    #[clap(long, env, default_value_t = 120)]
+    pub private_rpc_timeout: u64,
+    /// Address of a block builder rpc endpoint accepting bundles (eth_sendBundle) to target
+    /// before falling back to the public mempool
+    #[clap(long, env)]
+    pub private_bundle_url: Option<String>,
+    /// Number of blocks to target with bundles before falling back to the public mempool
+    #[clap(long, env, default_value_t = 5)]
+    pub private_bundle_blocks: u64,
+}
+
+/// Returns the fee increased by the given percentage
+fn bump(fee: u128, percent: u128) -> u128 {
+    fee + (fee * percent).div_ceil(100)
+}
+
+/// Submits a transaction through the private endpoint and then as a bundle to the builder
+/// endpoint (if any), falling back to the public mempool if it is not included before the
+/// deadline. All submissions share the same nonce, so that at most one of them is included, and
+/// the fallback bumps the fees to replace any private transaction that is still pending.
+/// Blob transactions are always submitted publicly, as private endpoints generally reject them.
+#[allow(clippy::too_many_arguments)]
+pub async fn send_private_transaction<
+    T: Transport + Clone,
+    P1: Provider<T, N>,
+    P2: Provider<T, N>,
+    N: Network,
+    W: NetworkWallet<N>,
+>(
+    mut txn: N::TransactionRequest,
+    from: Address,
+    private_provider: Option<P1>,
+    bundle_provider: Option<P1>,
+    public_provider: P2,
+    wallet: &W,
+    args: &PrivateTxnArgs,
+) -> anyhow::Result<N::ReceiptResponse>
+where
+    N::TransactionRequest: TransactionBuilder4844,
+{
+    let is_blob_txn = txn.blob_sidecar().is_some();
+    if is_blob_txn && (private_provider.is_some() || bundle_provider.is_some()) {
+        info!("Submitting blob transaction through public mempool.");
+    }
+    let private_provider = private_provider.filter(|_| !is_blob_txn);
+    let bundle_provider = bundle_provider.filter(|_| !is_blob_txn);
+    if private_provider.is_none() && bundle_provider.is_none() {
+        return public_provider
+            .send_transaction(txn)
+            .await
+            .context("send_transaction")?
+            .get_receipt()
+            .await
+            .context("get_receipt");
+    }
+
+    // pin the nonce and fees the fallback has to replace
+    txn.set_from(from);
+    let nonce = match txn.nonce() {
+        Some(nonce) => nonce,
+        None => {
+            let nonce = public_provider
+                .get_transaction_count(from)
+                .pending()
+                .await
+                .context("get_transaction_count")?;
+            txn.set_nonce(nonce);
+            nonce
+        }
+    };
+    if txn.max_fee_per_gas().is_none() || txn.max_priority_fee_per_gas().is_none() {
+        let fees = public_provider
+            .estimate_eip1559_fees(None)
+            .await
+            .context("estimate_eip1559_fees")?;
+        txn.set_max_fee_per_gas(fees.max_fee_per_gas);
+        txn.set_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+    }
+    let mut txn_hashes = vec![];
+
+    if let Some(private_provider) = private_provider {
+        info!("Submitting transaction through private rpc endpoint.");
+        match private_provider
+            .send_transaction(txn.clone())
+            .await
+            .context("send_transaction (private)")
+        {
+            Ok(pending_txn) => {
+                let txn_hash = *pending_txn.tx_hash();
+                txn_hashes.push(txn_hash);
+                match pending_txn
+                    .with_timeout(Some(Duration::from_secs(args.private_rpc_timeout)))
+                    .get_receipt()
+                    .await
+                    .context("get_receipt (private)")
+                {
+                    Ok(receipt) => return Ok(receipt),
+                    Err(e) => {
+                        warn!("Private transaction {txn_hash} was not included in time: {e:?}")
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Failed to submit private transaction: {e:?}");
+            }
+        }
+        // the private transaction may have been included right after the deadline
+        if let Some(receipt) = find_receipt(&public_provider, from, nonce, &txn_hashes).await? {
+            return Ok(receipt);
+        }
+    }
+
+    if let Some(bundle_provider) = bundle_provider {
+        match send_bundles(
+            txn.clone(),
+            bundle_provider,
+            &public_provider,
+            wallet,
+            args.private_bundle_blocks,
+            &mut txn_hashes,
+        )
+        .await
+        {
+            Ok(Some(receipt)) => return Ok(receipt),
+            Ok(None) => warn!(
+                "Bundle was not included in the next {} blocks.",
+                args.private_bundle_blocks
+            ),
+            Err(e) => warn!("Failed to submit bundle: {e:?}"),
+        }
+        if let Some(receipt) = find_receipt(&public_provider, from, nonce, &txn_hashes).await? {
+            return Ok(receipt);
+        }
+    }
+
+    // replace the private transaction in case it is still pending
+    if let Some(fee) = txn.max_fee_per_gas() {
+        txn.set_max_fee_per_gas(bump(fee, FEE_BUMP_PERCENT));
+    }
+    if let Some(fee) = txn.max_priority_fee_per_gas() {
+        txn.set_max_priority_fee_per_gas(bump(fee, FEE_BUMP_PERCENT));
+    }
+    warn!("Falling back to public mempool submission with nonce {nonce} and fees bumped by {FEE_BUMP_PERCENT}%.");
+    let pending_txn = match public_provider
+        .send_transaction(txn)
+        .await
+        .context("send_transaction")
+    {
+        Ok(pending_txn) => pending_txn,
+        Err(e) => {
+            // a private submission may have been included in the meantime
+            if let Some(receipt) = find_receipt(&public_provider, from, nonce, &txn_hashes).await? {
+                return Ok(receipt);
+            }
+            return Err(e);
+        }
+    };
+    pending_txn.get_receipt().await.context("get_receipt")
+}
+
+/// Returns the receipt of whichever of the given transactions consumed the nonce, or None if
+/// the nonce is still unused. Fails if the nonce was consumed by another transaction.
+async fn find_receipt<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    provider: &P,
+    from: Address,
+    nonce: u64,
+    txn_hashes: &[TxHash],
+) -> anyhow::Result<Option<N::ReceiptResponse>> {
+    let next_nonce = provider
+        .get_transaction_count(from)
+        .latest()
+        .await
+        .context("get_transaction_count")?;
+    if next_nonce <= nonce {
+        return Ok(None);
+    }
+    for txn_hash in txn_hashes {
+        if let Some(receipt) = provider
+            .get_transaction_receipt(*txn_hash)
+            .await
+            .context("get_transaction_receipt")?
+        {
+            info!("Transaction {txn_hash} was included.");
+            return Ok(Some(receipt));
+        }
+    }
+    bail!("Nonce {nonce} of {from} was consumed by another transaction.");
+}
+
+/// Signs the transaction and submits it as a single-transaction bundle targeting each of the
+/// given number of upcoming blocks in turn, returning its receipt once included.
+async fn send_bundles<
+    T: Transport + Clone,
+    P1: Provider<T, N>,
+    P2: Provider<T, N>,
+    N: Network,
+    W: NetworkWallet<N>,
+>(
+    mut txn: N::TransactionRequest,
+    bundle_provider: P1,
+    public_provider: &P2,
+    wallet: &W,
+    blocks: u64,
+    txn_hashes: &mut Vec<TxHash>,
+) -> anyhow::Result<Option<N::ReceiptResponse>> {
+    if txn.chain_id().is_none() {
+        let chain_id = public_provider
+            .get_chain_id()
+            .await
+            .context("get_chain_id")?;
+        txn.set_chain_id(chain_id);
+    }
+    if txn.gas_limit().is_none() {
+        let gas = public_provider
+            .estimate_gas(&txn)
+            .await
+            .context("estimate_gas")?;
+        txn.set_gas_limit(gas);
+    }
+    let envelope = txn.build(wallet).await.context("build")?;
+    let txn_hash = envelope.trie_hash();
+    txn_hashes.push(txn_hash);
+    let raw_txn = Bytes::from(envelope.encoded_2718());
+
+    let first_block = public_provider
+        .get_block_number()
+        .await
+        .context("get_block_number")?
+        + 1;
+    info!("Submitting transaction {txn_hash} as a bundle from block {first_block}.");
+    for target_block in first_block..first_block + blocks {
+        let _: serde_json::Value = bundle_provider
+            .raw_request(
+                "eth_sendBundle".into(),
+                [serde_json::json!({
+                    "txs": [raw_txn],
+                    "blockNumber": format!("{target_block:#x}"),
+                })],
+            )
+            .await
+            .context("eth_sendBundle")?;
+        // wait for the targeted block before checking for inclusion
+        while public_provider
+            .get_block_number()
+            .await
+            .context("get_block_number")?
+            < target_block
+        {
+            sleep(Duration::from_secs(1)).await;
+        }
+        if let Some(receipt) = public_provider
+            .get_transaction_receipt(txn_hash)
+            .await
+            .context("get_transaction_receipt")?
+        {
+            return Ok(Some(receipt));
+        }
+    }
+    Ok(None)
+}
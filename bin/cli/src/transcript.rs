@@ -0,0 +1,74 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use kailua_build::KAILUA_FPVM_ID;
+use kailua_client::proof::{transcript_file_name, Proof};
+use kailua_common::journal::ProofJournal;
+use kailua_common::transcript::InputTranscript;
+use risc0_zkvm::sha::Digest;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct VerifyTranscriptArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Path to the proof produced by kailua-host
+    pub proof_file: PathBuf,
+    /// Path to the input transcript to check the proof against, instead of the one written
+    /// alongside the proof
+    #[clap(long)]
+    pub transcript_file: Option<PathBuf>,
+}
+
+pub async fn verify_transcript(args: VerifyTranscriptArgs) -> anyhow::Result<()> {
+    let proof = Proof::decode(
+        &tokio::fs::read(&args.proof_file)
+            .await
+            .context(format!("Failed to read proof {:?}", args.proof_file))?,
+    )?;
+    let transcript_file = args
+        .transcript_file
+        .unwrap_or_else(|| PathBuf::from(transcript_file_name(&args.proof_file.to_string_lossy())));
+    let transcript: InputTranscript = serde_json::from_slice(
+        &tokio::fs::read(&transcript_file)
+            .await
+            .context(format!("Failed to read transcript {transcript_file:?}"))?,
+    )?;
+    // the transcript must be intact before it can vouch for the receipt
+    transcript.check()?;
+    match proof.as_receipt() {
+        Some(receipt) => receipt
+            .verify(Digest::new(KAILUA_FPVM_ID))
+            .context("Proof receipt does not verify")?,
+        None => {
+            warn!("Skipping verification of a boundless seal, which is only verified on-chain.")
+        }
+    }
+    let journal = ProofJournal::decode_packed(proof.journal().as_ref())?;
+    transcript.check_journal(&journal)?;
+    info!(
+        "Transcript {} covers {} preimages and {} blobs.",
+        transcript.digest,
+        transcript.preimages.len(),
+        transcript.blob_commitments.len()
+    );
+    println!(
+        "Proof {:?} attests to the inputs of transcript {}: {journal:?}",
+        args.proof_file, transcript.digest
+    );
+    Ok(())
+}
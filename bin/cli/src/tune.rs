@@ -0,0 +1,217 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::providers::optimism::OpNodeProvider;
+use alloy::eips::eip4844::FIELD_ELEMENTS_PER_BLOB;
+use alloy::eips::BlockNumberOrTag;
+use alloy::network::primitives::BlockTransactionsKind;
+use alloy::providers::{Provider, ProviderBuilder};
+use anyhow::{bail, Context};
+use kailua_host::fetch_rollup_config;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct TuneArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the OP-NODE endpoint to use
+    #[clap(long, env)]
+    pub op_node_url: String,
+    /// Address of the OP-GETH endpoint to use (eth and debug namespace required).
+    #[clap(long, env)]
+    pub op_geth_url: String,
+
+    /// The number of most recent safe L2 blocks to sample from
+    #[clap(long, env, default_value_t = 10_000)]
+    pub sample_range: u64,
+    /// The number of L2 blocks to sample within the range
+    #[clap(long, env, default_value_t = 100)]
+    pub sample_count: u64,
+
+    /// Estimated fixed number of zkVM cycles spent proving a single L2 block
+    #[clap(long, env, default_value_t = 100_000_000)]
+    pub cycles_per_block: u64,
+    /// Estimated number of zkVM cycles spent per unit of L2 gas used
+    #[clap(long, env, default_value_t = 40)]
+    pub cycles_per_gas: u64,
+    /// Number of zkVM cycles per second that the proving hardware sustains
+    #[clap(long, env, default_value_t = 1_000_000)]
+    pub prover_cycles_per_sec: u64,
+
+    /// Target number of seconds between an L2 block and the finalization of its proposal
+    #[clap(long, env, default_value_t = 86_400)]
+    pub finality_budget_secs: u64,
+    /// Factor by which the estimated proving and posting delays are padded
+    #[clap(long, env, default_value_t = 2)]
+    pub safety_factor: u64,
+    /// Maximum number of blobs a single proposal may occupy
+    #[clap(long, env, default_value_t = 1)]
+    pub max_proposal_blobs: u64,
+    /// The number of blocks between the intermediate outputs committed to by a proposal
+    #[clap(long, env, default_value_t = 1)]
+    pub output_block_span: u64,
+
+    /// Path of the deployment environment file to write the recommended parameters to
+    #[clap(long, env)]
+    pub output: Option<PathBuf>,
+}
+
+/// Recommended deployment parameters for `fast-track`
+#[derive(Clone, Debug)]
+pub struct TuningRecommendation {
+    pub proposal_block_span: u64,
+    pub output_block_span: u64,
+    pub proposal_time_gap: u64,
+    pub challenge_timeout: u64,
+    pub proposal_blobs: u64,
+}
+
+impl TuningRecommendation {
+    /// Returns the recommendation as the environment variables read by `fast-track`
+    pub fn env_vars(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("PROPOSAL_BLOCK_SPAN", self.proposal_block_span.to_string()),
+            ("OUTPUT_BLOCK_SPAN", self.output_block_span.to_string()),
+            ("PROPOSAL_TIME_GAP", self.proposal_time_gap.to_string()),
+            ("CHALLENGE_TIMEOUT", self.challenge_timeout.to_string()),
+        ]
+    }
+
+    /// Writes the recommended parameters into the environment file at `path`, preserving its
+    /// other entries.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let existing = std::fs::read_to_string(path).unwrap_or_default();
+        let vars = self.env_vars();
+        let mut lines = existing
+            .lines()
+            .filter(|line| {
+                !vars
+                    .iter()
+                    .any(|(key, _)| line.trim_start().starts_with(&format!("{key}=")))
+            })
+            .map(String::from)
+            .collect::<Vec<_>>();
+        lines.extend(vars.iter().map(|(key, value)| format!("{key}={value}")));
+        std::fs::write(path, lines.join("\n") + "\n")
+            .context(format!("Failed to write deployment config to {path:?}"))
+    }
+}
+
+pub async fn tune(args: TuneArgs) -> anyhow::Result<()> {
+    if args.sample_count == 0
+        || args.prover_cycles_per_sec == 0
+        || args.max_proposal_blobs == 0
+        || args.output_block_span == 0
+    {
+        bail!("Sample count, prover speed, maximum proposal blobs and output block span must be non-zero.");
+    }
+    let op_node_provider =
+        OpNodeProvider::new(ProviderBuilder::new().on_http(args.op_node_url.as_str().try_into()?));
+    let l2_node_provider = ProviderBuilder::new().on_http(args.op_geth_url.as_str().try_into()?);
+
+    info!("Fetching rollup configuration from rpc endpoints.");
+    let config = fetch_rollup_config(&args.op_node_url, &args.op_geth_url, None)
+        .await
+        .context("fetch_rollup_config")?;
+    let block_time = config.block_time;
+
+    // Measure how far the safe head trails behind the unsafe head
+    let sync_status = op_node_provider.sync_status().await?;
+    let safe_head = sync_status["safe_l2"]["number"]
+        .as_u64()
+        .unwrap_or_default();
+    let unsafe_head = sync_status["unsafe_l2"]["number"]
+        .as_u64()
+        .unwrap_or(safe_head);
+    let safe_lag_secs = unsafe_head.saturating_sub(safe_head) * block_time;
+    info!("Safe head at {safe_head} trails unsafe head at {unsafe_head} by {safe_lag_secs}s.");
+
+    // Sample historical blocks to estimate proving effort
+    let sample_start = safe_head.saturating_sub(args.sample_range);
+    let stride = (args.sample_range / args.sample_count).max(1);
+    let mut sampled = 0u64;
+    let mut total_cycles = 0u64;
+    let mut max_cycles = 0u64;
+    let mut block_number = sample_start;
+    while block_number <= safe_head && sampled < args.sample_count {
+        let Some(block) = l2_node_provider
+            .get_block_by_number(
+                BlockNumberOrTag::Number(block_number),
+                BlockTransactionsKind::Hashes,
+            )
+            .await
+            .context("get_block_by_number")?
+        else {
+            warn!("Failed to fetch block #{block_number}");
+            break;
+        };
+        let cycles = args.cycles_per_block + block.header.gas_used * args.cycles_per_gas;
+        total_cycles += cycles;
+        max_cycles = max_cycles.max(cycles);
+        sampled += 1;
+        block_number += stride;
+    }
+    if sampled == 0 {
+        bail!("Failed to sample any L2 blocks.");
+    }
+    let mean_cycles = total_cycles / sampled;
+    let max_proving_secs = max_cycles.div_ceil(args.prover_cycles_per_sec);
+    info!(
+        "Sampled {sampled} blocks from {sample_start} to {safe_head}: {mean_cycles} mean cycles, {max_cycles} max cycles ({max_proving_secs}s to prove)."
+    );
+
+    // A fault proof covers a single output span, so the challenge timeout must accommodate the
+    // slowest blocks throughout it
+    let challenge_timeout =
+        (max_proving_secs * args.output_block_span * args.safety_factor).max(block_time);
+    // Proposals must only be made once their data is safe
+    let proposal_time_gap = (safe_lag_secs * args.safety_factor).max(block_time);
+    // Spend the remaining budget on the proposal span
+    let Some(span_budget) = args
+        .finality_budget_secs
+        .checked_sub(challenge_timeout + proposal_time_gap)
+        .filter(|budget| *budget >= block_time * args.output_block_span)
+    else {
+        bail!(
+            "A finality budget of {}s can not accommodate a challenge timeout of {challenge_timeout}s and time gap of {proposal_time_gap}s.",
+            args.finality_budget_secs
+        );
+    };
+    let blob_capacity = args.max_proposal_blobs * FIELD_ELEMENTS_PER_BLOB;
+    let proposal_output_count =
+        (span_budget / block_time / args.output_block_span).min(blob_capacity);
+    let proposal_block_span = proposal_output_count * args.output_block_span;
+    let proposal_blobs = proposal_output_count.div_ceil(FIELD_ELEMENTS_PER_BLOB);
+    let blob_usage =
+        100 * proposal_output_count as f64 / (proposal_blobs * FIELD_ELEMENTS_PER_BLOB) as f64;
+    info!("Proposals will occupy {proposal_blobs} blobs at {blob_usage:.1}% capacity.");
+
+    let recommendation = TuningRecommendation {
+        proposal_block_span,
+        output_block_span: args.output_block_span,
+        proposal_time_gap,
+        challenge_timeout,
+        proposal_blobs,
+    };
+    for (key, value) in recommendation.env_vars() {
+        println!("{key}: {value}");
+    }
+    if let Some(path) = &args.output {
+        recommendation.save(path)?;
+        info!("Saved recommended parameters to {path:?}.");
+    }
+    Ok(())
+}
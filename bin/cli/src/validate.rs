@@ -12,41 +12,74 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::address_book::{AddressBook, AddressBookArgs};
+use crate::api::{self, ApiArgs};
+use crate::approval::{ApprovalArgs, ApprovalGate, Intent, IntentKind};
+use crate::availability::{AvailabilityArgs, AvailabilityMonitor, ExpiryUrgency};
+use crate::cadence::{Cadence, CadenceArgs};
 use crate::channel::DuplexChannel;
+use crate::competition::{Abort, CancelledProofs, Competition, CompetitionArgs, ProvingDecision};
 use crate::db::proposal::Proposal;
 use crate::db::KailuaDB;
+use crate::emergency::{EmergencyArgs, EmergencyBrake};
+use crate::failover::{FailoverArgs, ProverFailover};
+use crate::failure::{
+    witness_archive_path, FailureClass, OutputTail, ProofFailure, ProofRetryArgs, RetryQueue,
+};
+use crate::guardian::{GuardianArgs, ResolutionGuard};
+use crate::host_service::{kailua_host_command, HostService};
+use crate::images::check_image_id;
+use crate::latency::{DisputeLatencyTracker, DisputeStage, LatencyArgs};
+use crate::lock::{check_wallet_activity, InstanceLock};
+use crate::prefetch::{prefetch_dir, PrefetchArgs, PrefetchJob, Prefetcher};
+use crate::proofs::ProofIndex;
 use crate::providers::beacon::BlobProvider;
+use crate::providers::metered::{MeteredProvider, RpcMeter};
 use crate::providers::optimism::OpNodeProvider;
-use crate::{stall::Stall, CoreArgs, KAILUA_GAME_TYPE};
+use crate::providers::versions::probe_node_versions;
+use crate::relay::{relayed_request, ProofRelayArgs};
+use crate::reload::{watch_settings, ReloadArgs};
+use crate::shedding::{LoadShedder, SheddingArgs, WorkClass};
+use crate::snapshot::{import_snapshot, SnapshotArgs, SnapshotExporter};
+use crate::stall::{with_scan_deadline, Stall};
+use crate::stats::StatsTracker;
+use crate::transact::{send_private_transaction, PrivateTxnArgs};
+use crate::verdict::{Verdict, VerdictFeed, VerdictFeedArgs};
+use crate::workdir::{JobDirs, SharedDiskUsage, WorkDirArgs, DISK_USAGE_INTERVAL_SECS};
+use crate::{CoreArgs, KAILUA_GAME_TYPE};
 use alloy::eips::eip4844::IndexedBlobHash;
-use alloy::eips::BlockNumberOrTag;
+use alloy::eips::{BlockId, BlockNumberOrTag};
 use alloy::network::primitives::BlockTransactionsKind;
 use alloy::network::EthereumWallet;
-use alloy::primitives::{Bytes, FixedBytes, U256};
-use alloy::providers::{Provider, ProviderBuilder, ReqwestProvider};
+use alloy::primitives::{Bytes, FixedBytes, B256, U256};
+use alloy::providers::{Provider, ProviderBuilder};
 use alloy::signers::local::LocalSigner;
+use alloy::transports::Transport;
 use anyhow::{anyhow, bail, Context};
 use boundless_market::storage::StorageProviderConfig;
 use kailua_client::proof::{fpvm_proof_file_name, Proof};
+use kailua_client::storage::ReceiptStorageArgs;
 use kailua_client::BoundlessArgs;
-use kailua_common::blobs::hash_to_fe;
 use kailua_common::blobs::BlobFetchRequest;
-use kailua_common::client::config_hash;
-use kailua_common::journal::ProofJournal;
+use kailua_common::config::config_hash;
+use kailua_common::journal::{ProofJournal, PROOF_JOURNAL_VERSION};
 use kailua_common::precondition::{precondition_hash, PreconditionValidationData};
 use kailua_contracts::*;
 use kailua_host::fetch_rollup_config;
+use kailua_host::hardforks::{check_hardfork_support, unsupported_hardforks};
 use op_alloy_protocol::BlockInfo;
-use risc0_zkvm::is_dev_mode;
+use risc0_zkvm::{is_dev_mode, Journal};
 use std::path::{Path, PathBuf};
-use std::process::exit;
+use std::process::{exit, Stdio};
 use std::str::FromStr;
-use std::time::Duration;
+use std::sync::PoisonError;
+use std::time::{Duration, Instant};
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
-use tokio::process::Command;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::watch;
 use tokio::time::sleep;
-use tokio::{spawn, try_join};
+use tokio::{select, spawn, try_join};
 use tracing::{debug, error, info, warn};
 
 #[derive(clap::Args, Debug, Clone)]
@@ -54,14 +87,70 @@ pub struct ValidateArgs {
     #[clap(flatten)]
     pub core: CoreArgs,
 
-    /// Path to the kailua host binary to use for proving
+    /// Path to the kailua host binary to use for proving, instead of the host embedded in
+    /// kailua-cli
     #[clap(long, env)]
-    pub kailua_host: PathBuf,
+    pub kailua_host: Option<PathBuf>,
+    /// Address of a `kailua-host serve` instance to submit proving jobs to instead of spawning
+    /// the kailua host binary for every proof
+    #[clap(long, env)]
+    pub kailua_host_service: Option<String>,
 
     /// Secret key of L1 wallet to use for challenging and proving outputs
     #[clap(long, env)]
     pub validator_key: String,
 
+    #[clap(flatten)]
+    pub private_txn: PrivateTxnArgs,
+
+    #[clap(flatten)]
+    pub cadence: CadenceArgs,
+
+    #[clap(flatten)]
+    pub competition: CompetitionArgs,
+
+    #[clap(flatten)]
+    pub failover: FailoverArgs,
+    #[clap(flatten)]
+    pub proof_retry: ProofRetryArgs,
+
+    #[clap(flatten)]
+    pub api: ApiArgs,
+
+    #[clap(flatten)]
+    pub verdict_feed: VerdictFeedArgs,
+
+    #[clap(flatten)]
+    pub proof_relay: ProofRelayArgs,
+
+    #[clap(flatten)]
+    pub snapshot: SnapshotArgs,
+
+    #[clap(flatten)]
+    pub prefetch: PrefetchArgs,
+    #[clap(flatten)]
+    pub workdir: WorkDirArgs,
+
+    #[clap(flatten)]
+    pub emergency: EmergencyArgs,
+    #[clap(flatten)]
+    pub reload: ReloadArgs,
+    #[clap(flatten)]
+    pub latency: LatencyArgs,
+    #[clap(flatten)]
+    pub availability: AvailabilityArgs,
+    #[clap(flatten)]
+    pub shedding: SheddingArgs,
+    #[clap(flatten)]
+    pub guardian: GuardianArgs,
+    #[clap(flatten)]
+    pub address_book: AddressBookArgs,
+    #[clap(flatten)]
+    pub approval: ApprovalArgs,
+
+    #[clap(flatten)]
+    pub receipt_storage: ReceiptStorageArgs,
+
     #[clap(flatten)]
     pub boundless_args: Option<BoundlessArgs>,
     /// Storage provider to use for elf and input
@@ -73,13 +162,29 @@ pub async fn validate(args: ValidateArgs, data_dir: PathBuf) -> anyhow::Result<(
     // We run two concurrent tasks, one for the chain, and one for the prover.
     // Both tasks communicate using the duplex channel
     let channel_pair = DuplexChannel::new_pair(4096);
+    // Proofs that became unnecessary are signalled to the prover through a shared set
+    let cancelled_proofs = CancelledProofs::default();
+    // The disk usage of proving jobs is measured by the prover and served by the api
+    let disk_usage = SharedDiskUsage::default();
+    // Settings reloaded on SIGHUP are applied by both tasks without losing their state
+    let (args, settings) = watch_settings(&args.reload, args.clone(), &data_dir)?;
 
     let handle_proposals = spawn(handle_proposals(
         channel_pair.0,
         args.clone(),
+        settings.clone(),
         data_dir.clone(),
+        cancelled_proofs.clone(),
+        disk_usage.clone(),
+    ));
+    let handle_proofs = spawn(handle_proofs(
+        channel_pair.1,
+        args,
+        settings,
+        data_dir,
+        cancelled_proofs,
+        disk_usage,
     ));
-    let handle_proofs = spawn(handle_proofs(channel_pair.1, args, data_dir));
 
     let (proposals_task, proofs_task) = try_join!(handle_proposals, handle_proofs)?;
     proposals_task.context("handle_proposals")?;
@@ -92,32 +197,144 @@ pub async fn validate(args: ValidateArgs, data_dir: PathBuf) -> anyhow::Result<(
 #[allow(clippy::large_enum_variant)]
 pub enum Message {
     // The proposal and its parent
-    Proposal {
-        index: u64,
-        precondition_validation_data: Option<PreconditionValidationData>,
-        l1_head: FixedBytes<32>,
-        agreed_l2_head_hash: FixedBytes<32>,
-        agreed_l2_output_root: FixedBytes<32>,
-        claimed_l2_block_number: u64,
-        claimed_l2_output_root: FixedBytes<32>,
-    },
+    Proposal(ProofRequest),
     Proof(u64, Proof),
+    ProofFailure(ProofRequest, ProofFailure),
+}
+
+/// The inputs to prove the output at which a proposal diverges from its contender
+#[derive(Clone, Debug)]
+pub struct ProofRequest {
+    pub index: u64,
+    pub precondition_validation_data: Option<PreconditionValidationData>,
+    pub l1_head: FixedBytes<32>,
+    pub agreed_l2_head_hash: FixedBytes<32>,
+    pub agreed_l2_output_root: FixedBytes<32>,
+    pub agreed_l2_block_number: u64,
+    pub claimed_l2_block_number: u64,
+    pub claimed_l2_output_root: FixedBytes<32>,
+}
+
+impl ProofRequest {
+    pub fn precondition_hash(&self) -> B256 {
+        self.precondition_validation_data
+            .as_ref()
+            .map(|d| d.precondition_hash())
+            .unwrap_or_default()
+    }
+
+    pub fn proof_file_name(&self) -> String {
+        fpvm_proof_file_name(
+            self.precondition_hash(),
+            self.l1_head,
+            self.claimed_l2_output_root,
+            self.claimed_l2_block_number,
+            self.agreed_l2_output_root,
+        )
+    }
+
+    /// Returns the arguments for kailua-host to natively prove this request
+    pub fn kailua_host_args(
+        &self,
+        l2_chain_id: u64,
+        eth_rpc_url: &str,
+        beacon_rpc_url: &str,
+        op_geth_url: &str,
+        op_node_url: &str,
+        data_dir: &Path,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut proving_args = vec![
+            String::from("--l1-head"), // l1 head from on-chain proposal
+            self.l1_head.to_string(),
+            String::from("--agreed-l2-head-hash"), // l2 starting block hash from on-chain proposal
+            self.agreed_l2_head_hash.to_string(),
+            String::from("--agreed-l2-output-root"), // l2 starting output root
+            self.agreed_l2_output_root.to_string(),
+            String::from("--claimed-l2-output-root"), // proposed output root
+            self.claimed_l2_output_root.to_string(),
+            String::from("--claimed-l2-block-number"), // proposed block number
+            self.claimed_l2_block_number.to_string(),
+            String::from("--block-count"), // blocks between the agreed and claimed outputs
+            (self.claimed_l2_block_number - self.agreed_l2_block_number).to_string(),
+            String::from("--l2-chain-id"), // rollup chain id
+            l2_chain_id.to_string(),
+            String::from("--l1-node-address"), // l1 el node
+            eth_rpc_url.to_string(),
+            String::from("--l1-beacon-address"), // l1 cl node
+            beacon_rpc_url.to_string(),
+            String::from("--l2-node-address"), // l2 el node
+            op_geth_url.to_string(),
+            String::from("--op-node-address"), // l2 cl node
+            op_node_url.to_string(),
+            String::from("--data-dir"), // path to cache
+            path_arg(data_dir)?,
+            String::from("--native"), // run the client natively
+        ];
+        // precondition data
+        if let Some(precondition_data) = &self.precondition_validation_data {
+            proving_args.extend(vec![
+                String::from("--u-block-hash"),
+                precondition_data.validated_blobs[0]
+                    .block_ref
+                    .hash
+                    .to_string(),
+                String::from("--u-blob-kzg-hash"),
+                precondition_data.validated_blobs[0]
+                    .blob_hash
+                    .hash
+                    .to_string(),
+                String::from("--v-block-hash"),
+                precondition_data.validated_blobs[1]
+                    .block_ref
+                    .hash
+                    .to_string(),
+                String::from("--v-blob-kzg-hash"),
+                precondition_data.validated_blobs[1]
+                    .blob_hash
+                    .hash
+                    .to_string(),
+            ]);
+        }
+        Ok(proving_args)
+    }
+}
+
+/// Renders a path as a command line argument, failing on paths that are not valid unicode
+pub fn path_arg(path: &Path) -> anyhow::Result<String> {
+    path.to_str()
+        .map(String::from)
+        .context(format!("Path {path:?} is not valid unicode."))
 }
 
 pub async fn handle_proposals(
     mut channel: DuplexChannel<Message>,
-    args: ValidateArgs,
+    mut args: ValidateArgs,
+    mut settings: watch::Receiver<ValidateArgs>,
     data_dir: PathBuf,
+    cancelled_proofs: CancelledProofs,
+    disk_usage: SharedDiskUsage,
 ) -> anyhow::Result<()> {
     // initialize blockchain connections
     info!("Initializing rpc connections.");
+    let load_shedder = LoadShedder::new(args.shedding.clone());
+    let rpc_meter = RpcMeter::new(args.core.rpc_budget.clone())
+        .with_usage_file(&data_dir.join("rpc_usage.json"))?
+        .with_load_shedder(load_shedder.clone());
     let op_node_provider =
-        OpNodeProvider(ProviderBuilder::new().on_http(args.core.op_node_url.as_str().try_into()?));
-    let eth_rpc_provider =
-        ProviderBuilder::new().on_http(args.core.eth_rpc_url.as_str().try_into()?);
-    let op_geth_provider =
-        ProviderBuilder::new().on_http(args.core.op_geth_url.as_str().try_into()?);
-    let cl_node_provider = BlobProvider::new(args.core.beacon_rpc_url.as_str()).await?;
+        OpNodeProvider::new(rpc_meter.provider("op-node", &args.core.op_node_url)?)
+            .with_cache_dir(&data_dir.join("output_cache"), &args.core.op_node_url)?;
+    let eth_rpc_provider = rpc_meter.provider("eth-rpc", &args.core.eth_rpc_url)?;
+    let op_geth_provider = rpc_meter.provider("op-geth", &args.core.op_geth_url)?;
+    let correctness_oracle =
+        args.core
+            .correctness
+            .oracle(&op_node_provider, op_geth_provider.clone(), &rpc_meter)?;
+    let cl_node_provider = BlobProvider::new(args.core.beacon_rpc_url.as_str())
+        .await?
+        .with_slot_cache(&data_dir.join("slot_cache.json"))?
+        .with_load_shedder(load_shedder.clone());
+
+    probe_node_versions(&args.core).await;
 
     info!("Fetching rollup configuration from rpc endpoints.");
     // fetch rollup config
@@ -131,6 +348,19 @@ pub async fn handle_proposals(
     let system_config = SystemConfig::new(config.l1_system_config_address, &eth_rpc_provider);
     let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
 
+    // start downloading data for challenged proposals as soon as proofs are requested
+    let prefetch_queue = Prefetcher {
+        args: args.prefetch.clone(),
+        data_dir: data_dir.clone(),
+        op_node_url: args.core.op_node_url.clone(),
+        l1_provider: eth_rpc_provider.clone(),
+        blob_provider: cl_node_provider.clone(),
+        batch_inbox_address: config.batch_inbox_address,
+        seq_window_size: config.seq_window_size,
+        shedder: load_shedder.clone(),
+    }
+    .spawn();
+
     // initialize validator wallet
     info!("Initializing validator wallet.");
     let validator_signer = LocalSigner::from_str(&args.validator_key)?;
@@ -138,9 +368,45 @@ pub async fn handle_proposals(
     let validator_wallet = EthereumWallet::from(validator_signer);
     let validator_provider = ProviderBuilder::new()
         .with_recommended_fillers()
-        .wallet(validator_wallet)
-        .on_http(args.core.eth_rpc_url.as_str().try_into()?);
+        .wallet(&validator_wallet)
+        .on_client(rpc_meter.client("eth-rpc", &args.core.eth_rpc_url)?);
     info!("Validator address: {validator_address}");
+    let verdict_feed =
+        VerdictFeed::new(&args.verdict_feed, &args.validator_key, config.l2_chain_id)?;
+    let mut approval_gate = ApprovalGate::new(&args.approval)?;
+    let mut snapshot_exporter = SnapshotExporter::new(&args.snapshot, &args.validator_key)?;
+    // refuse to share the wallet with another running validator
+    let _instance_lock = InstanceLock::acquire(&data_dir, validator_address, &args.core.lock)?;
+    check_wallet_activity(&validator_provider, validator_address, &args.core.lock).await?;
+    let mut competition = Competition::new(
+        args.competition.clone(),
+        validator_address,
+        cancelled_proofs,
+    );
+    let private_txn_provider = match &args.private_txn.private_rpc_url {
+        Some(private_rpc_url) => {
+            info!("Submitting proofs through private rpc endpoint.");
+            Some(
+                ProviderBuilder::new()
+                    .with_recommended_fillers()
+                    .wallet(&validator_wallet)
+                    .on_client(rpc_meter.client("private-rpc", private_rpc_url)?),
+            )
+        }
+        None => None,
+    };
+    let bundle_txn_provider = match &args.private_txn.private_bundle_url {
+        Some(private_bundle_url) => {
+            info!("Submitting proofs as bundles through block builder endpoint.");
+            Some(
+                ProviderBuilder::new()
+                    .with_recommended_fillers()
+                    .wallet(&validator_wallet)
+                    .on_client(rpc_meter.client("private-bundle", private_bundle_url)?),
+            )
+        }
+        None => None,
+    };
 
     // Init factory contract
     let dispute_game_factory = IDisputeGameFactory::new(dgf_address, &validator_provider);
@@ -167,24 +433,166 @@ pub async fn handle_proposals(
     }
     // Initialize empty DB
     info!("Initializing..");
-    let mut kailua_db = KailuaDB::init(data_dir, &dispute_game_factory).await?;
+    let mut kailua_db = KailuaDB::init(data_dir.clone(), &dispute_game_factory).await?;
     info!("KailuaTreasury({:?})", kailua_db.treasury.address);
+    kailua_db.state.next_factory_index = args.core.start_factory_index;
+    if kailua_db.config.journal_version != PROOF_JOURNAL_VERSION {
+        bail!(
+            "KailuaGame({}) expects proof journal version {} instead of {PROOF_JOURNAL_VERSION}. Deploy a game implementation using the current FPVM image ID to migrate.",
+            kailua_db.config.game,
+            kailua_db.config.journal_version
+        );
+    }
+    check_image_id(kailua_db.config.image_id)?;
+    // Warm-start from a peer's snapshot instead of replaying its history, revisiting the
+    // unresolved proposals it contains in the first iteration
+    let mut deferred_proposals =
+        import_snapshot(&args.snapshot, &mut kailua_db, &dispute_game_factory).await?;
     // Run the validator loop
     info!(
         "Starting from proposal at factory index {}",
         kailua_db.state.next_factory_index
     );
+    let mut cadence = Cadence::new(&args.cadence);
+    let mut withheld_proofs = Vec::new();
+    let mut proof_index = ProofIndex::load(&data_dir)?;
+    let mut retry_queue = RetryQueue::default();
+    let mut emergency_brake = EmergencyBrake::new(&args.emergency, &data_dir);
+    let mut latency_tracker = DisputeLatencyTracker::new(args.latency.clone());
+    let mut availability_monitor = AvailabilityMonitor::new(args.availability.clone());
+    let mut stats_tracker = StatsTracker::default();
+    let address_book = AddressBook::load(&args.address_book)?;
+    let mut resolution_guard =
+        ResolutionGuard::new(args.guardian.clone(), &address_book, &validator_provider).await?;
+    let api_state = api::spawn(&args.api);
     loop {
+        // Publish the latest view of the proposal tree
+        match &api_state {
+            Some(api_state) => api_state.update(&mut kailua_db),
+            None => kailua_db.state.modified_proposals.clear(),
+        }
+        // Apply any emergency measures requested by the operator
+        emergency_brake.refresh();
+        // Apply any settings reloaded by the operator
+        if settings.has_changed().unwrap_or_default() {
+            let reloaded = settings.borrow_and_update().clone();
+            cadence.reconfigure(&reloaded.cadence);
+            competition.args = reloaded.competition;
+            args.proof_retry = reloaded.proof_retry;
+            rpc_meter.set_budget(reloaded.core.rpc_budget);
+        }
         // Wait for new data on every iteration
-        sleep(Duration::from_secs(1)).await;
+        sleep(rpc_meter.throttle(cadence.interval())).await;
+        rpc_meter.report();
+        load_shedder.report();
+        // drop cached outputs invalidated since the last iteration
+        if let Err(err) = op_node_provider.refresh_cache().await {
+            warn!("Failed to refresh output cache: {err:?}");
+        }
         // fetch latest games
-        let loaded_proposals = kailua_db
-            .load_proposals(&dispute_game_factory, &op_node_provider, &cl_node_provider)
+        let scan = kailua_db.load_proposals(
+            &dispute_game_factory,
+            correctness_oracle.as_ref(),
+            &cl_node_provider,
+        );
+        let loaded_proposals =
+            match with_scan_deadline(Duration::from_secs(args.core.scan_timeout), scan).await {
+                Ok(Ok(result)) => result,
+                Ok(Err(err)) => {
+                    error!("Failed to load proposals: {err:?}");
+                    continue;
+                }
+                Err(err) => {
+                    error!("{err:?}");
+                    continue;
+                }
+            };
+        // remember which canonical games resolved to report settled outputs
+        if let Err(err) = kailua_db.record_resolutions(&validator_provider).await {
+            warn!("Failed to record resolutions: {err:?}");
+        }
+        // forget resolved history beyond the retention window
+        if let Err(err) = kailua_db
+            .prune_resolved_proposals(&args.core.retention, &validator_provider)
             .await
-            .context("load_proposals")?;
+        {
+            warn!("Failed to prune resolved proposals: {err:?}");
+        }
+        if let Some(snapshot_exporter) = &mut snapshot_exporter {
+            if let Err(err) = snapshot_exporter.export(&kailua_db).await {
+                warn!("Failed to export snapshot: {err:?}");
+            }
+        }
+        latency_tracker.prune(kailua_db.state.pruned_below);
+        stats_tracker.prune(kailua_db.state.pruned_below);
+        for proposal_index in &loaded_proposals {
+            latency_tracker.detect(*proposal_index);
+            if let Some(proposal) = kailua_db.get_local_proposal(proposal_index) {
+                resolution_guard.watch(&proposal);
+                // attest to the correctness of every newly assessed proposal
+                if let (Some(verdict_feed), Some(verdict)) =
+                    (&verdict_feed, Verdict::correctness(&proposal))
+                {
+                    verdict_feed.publish(verdict).await;
+                }
+            }
+        }
+        // look out for games resolved in favor of incorrect proposals
+        if let Err(err) = resolution_guard
+            .check(&kailua_db, &validator_provider, &args.core.eth_rpc_url)
+            .await
+        {
+            warn!("Failed to check resolutions: {err:?}");
+        }
+
+        // poll faster while new games appear or disputes remain unsettled
+        let found_new_games = !loaded_proposals.is_empty();
 
-        // check new proposals for fault and queue potential responses
-        for proposal_index in loaded_proposals {
+        // cancel queued or in-flight proofs for matches that were proven or resolved meanwhile
+        for (proposal_index, (parent_index, u_index, v_index)) in competition.queued.clone() {
+            let (Some(parent), Some(proposal)) = (
+                kailua_db.get_local_proposal(&parent_index),
+                kailua_db.get_local_proposal(&proposal_index),
+            ) else {
+                continue;
+            };
+            let proof_status = parent
+                .tournament_contract_instance(&validator_provider)
+                .proofStatus(U256::from(u_index), U256::from(v_index))
+                .stall()
+                .await
+                ._0;
+            if proof_status != 0 {
+                info!("Match for proposal {proposal_index} was proven by another validator.");
+            } else if proposal
+                .fetch_finality(&validator_provider)
+                .await
+                .is_ok_and(|finality| finality.is_some())
+            {
+                info!("Proposal {proposal_index} was resolved before its match was proven.");
+            } else {
+                continue;
+            }
+            competition.mark_complete(proposal_index);
+            // only cancel the proof once no other proposal awaits it
+            if let Some(requester) = proof_index.release(proposal_index)? {
+                competition.cancel(requester);
+                retry_queue.remove(requester);
+            }
+        }
+
+        // retry failed proofs whose backoff elapsed
+        for request in retry_queue.take_due() {
+            info!("Retrying proof for local index {}.", request.index);
+            channel.sender.send(Message::Proposal(request)).await?;
+        }
+
+        // check new and deferred proposals for fault and queue potential responses
+        let candidate_proposals = deferred_proposals
+            .drain(..)
+            .chain(loaded_proposals)
+            .collect::<Vec<_>>();
+        for proposal_index in candidate_proposals {
             let Some(proposal) = kailua_db.get_local_proposal(&proposal_index) else {
                 error!("Proposal {proposal_index} missing from database.");
                 continue;
@@ -198,6 +606,12 @@ pub async fn handle_proposals(
                 error!("Contender {contender} missing from database.");
                 continue;
             };
+            latency_tracker.open(
+                proposal.index,
+                contender.index,
+                contender.created_at,
+                proposal.created_at,
+            );
             // Look up parent proposal
             let Some(proposal_parent) = kailua_db.get_local_proposal(&proposal.parent) else {
                 error!(
@@ -206,6 +620,18 @@ pub async fn handle_proposals(
                 );
                 continue;
             };
+            availability_monitor.watch(
+                &kailua_db.config,
+                &proposal_parent,
+                &contender,
+                proposal.index,
+            );
+            // Refrain from touching blacklisted games
+            if emergency_brake.is_blacklisted([&proposal, &contender, &proposal_parent]) {
+                warn!("Ignoring match of blacklisted proposal {}.", proposal.index);
+                deferred_proposals.push(proposal.index);
+                continue;
+            }
             let proposal_parent_contract =
                 proposal_parent.tournament_contract_instance(&validator_provider);
             // Look up indices of children in parent
@@ -231,50 +657,238 @@ pub async fn handle_proposals(
                 ._0;
             // Prove if unproven
             if proof_status == 0 {
-                request_proof(
+                if emergency_brake.overrides.pause_proving {
+                    deferred_proposals.push(proposal.index);
+                    continue;
+                }
+                // prove disputes whose l1 data nears expiry regardless of the strategy
+                let is_expiring =
+                    availability_monitor.urgency(proposal.index) >= ExpiryUrgency::Warning;
+                // protect proofs of the validator's own proposals while endpoints degrade
+                let is_own = [proposal.proposer, contender.proposer].contains(&validator_address);
+                if !is_expiring && !is_own && load_shedder.sheds(WorkClass::SpeculativeProof) {
+                    info!(
+                        "Deferring proof for proposal {} while endpoints are degraded.",
+                        proposal.index
+                    );
+                    deferred_proposals.push(proposal.index);
+                    continue;
+                }
+                let decision = if is_expiring {
+                    Ok(ProvingDecision::Prove)
+                } else {
+                    competition
+                        .decide(&proposal, &proposal_parent, &validator_provider)
+                        .await
+                };
+                match decision {
+                    Ok(ProvingDecision::Prove) => {}
+                    Ok(ProvingDecision::Defer) => {
+                        deferred_proposals.push(proposal.index);
+                        continue;
+                    }
+                    Err(err) => {
+                        warn!("Failed to assess competing validators: {err:?}");
+                    }
+                }
+                if let Err(err) = request_proof(
                     &mut channel,
+                    &mut proof_index,
                     &contender,
                     &proposal,
                     &eth_rpc_provider,
                     &op_geth_provider,
                     &op_node_provider,
+                    &prefetch_queue,
+                    kailua_db.config.output_block_span,
                 )
-                .await?;
+                .await
+                {
+                    error!(
+                        "Failed to request proof for proposal {}: {err:?}",
+                        proposal.index
+                    );
+                    deferred_proposals.push(proposal.index);
+                    continue;
+                }
+                competition.mark_queued(proposal.index, proposal_parent.index, u_index, v_index);
+                latency_tracker.record(proposal.index, DisputeStage::ProofRequested);
             } else {
                 info!(
                     "Match between children {u_index} and {v_index} already proven {proof_status}"
                 );
+                kailua_db
+                    .record_proof_status(contender.index, proposal.index, proof_status)
+                    .context("Failed to record proof status")?;
             }
         }
 
         // publish computed proofs and resolve proven challenges
+        let mut computed_proofs = withheld_proofs.drain(..).collect::<Vec<_>>();
         while !channel.receiver.is_empty() {
-            let Message::Proof(proposal_index, proof) = channel
+            let (requester, proof) = match channel
                 .receiver
                 .recv()
                 .await
                 .ok_or(anyhow!("proposals receiver channel closed"))?
-            else {
-                bail!("Unexpected message type.");
+            {
+                Message::Proof(requester, proof) => (requester, proof),
+                Message::ProofFailure(request, failure) => {
+                    let requester = request.index;
+                    stats_tracker.record_proof(false);
+                    let Some(attempts) = proof_index.record_failure(requester, failure.clone())?
+                    else {
+                        continue;
+                    };
+                    match args.proof_retry.backoff(&failure, attempts) {
+                        Some(backoff) => {
+                            warn!(
+                                "Retrying proof for proposal {requester} in {}s (attempt {attempts}/{} failed).",
+                                backoff.as_secs(),
+                                args.proof_retry.proof_max_attempts
+                            );
+                            retry_queue.schedule(request, backoff);
+                        }
+                        None => {
+                            let abandoned = proof_index.dead_letter(requester)?;
+                            error!(
+                                "DEAD LETTER! Abandoning proof for proposals {abandoned:?} after {attempts} failed attempt(s) ({:?}).",
+                                failure.class
+                            );
+                        }
+                    }
+                    continue;
+                }
+                Message::Proposal(request) => {
+                    error!("Unexpected proof request for proposal {}.", request.index);
+                    continue;
+                }
+            };
+            stats_tracker.record_proof(true);
+            // fan the proof out to all proposals awaiting it
+            let recipients = proof_index.complete(requester)?;
+            if recipients.is_empty() {
+                info!("Discarding proof for proposal {requester} that is no longer needed.");
+            }
+            for proposal_index in recipients {
+                latency_tracker.record(proposal_index, DisputeStage::ProofReady);
+                computed_proofs.push((proposal_index, proof.clone()));
+            }
+        }
+        for (proposal_index, proof) in computed_proofs {
+            if !competition.queued.contains_key(&proposal_index) {
+                info!("Discarding proof for proposal {proposal_index} that is no longer needed.");
+                continue;
+            }
+            // withhold proofs while submissions are paused or the games are blacklisted
+            if emergency_brake.overrides.pause_submissions {
+                withheld_proofs.push((proposal_index, proof));
+                continue;
+            }
+            if let Some(proposal) = kailua_db.get_local_proposal(&proposal_index) {
+                let related = [
+                    proposal.parent,
+                    proposal.contender.unwrap_or(proposal.index),
+                ]
+                .iter()
+                .filter_map(|index| kailua_db.get_local_proposal(index))
+                .collect::<Vec<_>>();
+                if emergency_brake.is_blacklisted(related.iter().chain([&proposal])) {
+                    warn!("Withholding proof for blacklisted proposal {proposal_index}.");
+                    withheld_proofs.push((proposal_index, proof));
+                    continue;
+                }
+                // withhold proofs until a human approves their submission
+                if let Some(approval_gate) = approval_gate.as_mut() {
+                    let intent = Intent::new(
+                        IntentKind::Prove,
+                        proposal_index,
+                        proposal.contract,
+                        format!(
+                            "Prove match between proposal {proposal_index} and contender {} under parent {}",
+                            proposal.contender.unwrap_or(proposal.index),
+                            proposal.parent
+                        ),
+                    );
+                    match approval_gate.approve(&intent).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            withheld_proofs.push((proposal_index, proof));
+                            continue;
+                        }
+                        Err(err) => {
+                            warn!("Failed to queue proof intent for approval: {err:?}");
+                            withheld_proofs.push((proposal_index, proof));
+                            continue;
+                        }
+                    }
+                }
+            }
+            // withhold proofs for the configured confirmations without stalling other proposals
+            match competition
+                .is_submittable(proposal_index, &validator_provider)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => {
+                    withheld_proofs.push((proposal_index, proof));
+                    continue;
+                }
+                Err(err) => warn!("Failed to check submission confirmations: {err:?}"),
+            }
+            competition.mark_complete(proposal_index);
+            let Some(proposal) = kailua_db.get_local_proposal(&proposal_index) else {
+                error!("Proposal {proposal_index} missing from database.");
+                continue;
+            };
+            let Some(proposal_parent) = kailua_db.get_local_proposal(&proposal.parent) else {
+                error!(
+                    "Proposal {proposal_index} parent {} missing from database.",
+                    proposal.parent
+                );
+                continue;
             };
-            let proposal = kailua_db.get_local_proposal(&proposal_index).unwrap();
-            let proposal_parent = kailua_db.get_local_proposal(&proposal.parent).unwrap();
             let proposal_parent_contract =
                 proposal_parent.tournament_contract_instance(&validator_provider);
-            let proof_journal = ProofJournal::decode_packed(proof.journal().as_ref())?;
+            let proof_journal = match ProofJournal::decode_packed(proof.journal().as_ref()) {
+                Ok(proof_journal) => proof_journal,
+                Err(err) => {
+                    error!("Discarding proof for proposal {proposal_index} with malformed journal: {err:?}");
+                    continue;
+                }
+            };
             info!("Proof journal: {:?}", proof_journal);
-            let contender_index = proposal.contender.unwrap();
-            let contender = kailua_db.get_local_proposal(&contender_index).unwrap();
+            let Some(contender) = proposal
+                .contender
+                .and_then(|index| kailua_db.get_local_proposal(&index))
+            else {
+                error!("Contender of proposal {proposal_index} missing from database.");
+                continue;
+            };
+            let contender_index = contender.index;
 
-            let u_index = proposal_parent
-                .child_index(contender_index)
-                .expect("Could not look up contender's index in parent tournament");
-            let v_index = proposal_parent
-                .child_index(proposal.index)
-                .expect("Could not look up contender's index in parent tournament");
+            let (Some(u_index), Some(v_index)) = (
+                proposal_parent.child_index(contender_index),
+                proposal_parent.child_index(proposal.index),
+            ) else {
+                error!(
+                    "Could not look up the indices of proposal {proposal_index} and contender {contender_index} in parent tournament {}",
+                    proposal_parent.index
+                );
+                continue;
+            };
 
-            let challenge_position =
-                proof_journal.claimed_l2_block_number - proposal_parent.output_block_number - 1;
+            let Some(challenge_position) = proof_journal
+                .claimed_l2_block_number
+                .checked_sub(proposal_parent.output_block_number)
+                .and_then(|span| (span / kailua_db.config.output_block_span).checked_sub(1))
+            else {
+                error!(
+                    "Discarding proof for proposal {proposal_index} of block {} preceding its parent.",
+                    proof_journal.claimed_l2_block_number
+                );
+                continue;
+            };
 
             let expected_image_id = proposal_parent_contract.imageId().stall().await.imageId_.0;
 
@@ -378,70 +992,80 @@ pub async fn handle_proposals(
                 }
             }
 
+            let claimed_output_fe = kailua_db
+                .config
+                .field_encoding
+                .output_to_fe(proof_journal.claimed_l2_output_root);
             let contender_output = contender.output_at(challenge_position);
-            if contender_output != hash_to_fe(proof_journal.claimed_l2_output_root) {
+            if contender_output != claimed_output_fe {
                 warn!(
-                    "Contender output fe {contender_output} doesn't match proof fe {}",
-                    hash_to_fe(proof_journal.claimed_l2_output_root)
+                    "Contender output fe {contender_output} doesn't match proof fe {claimed_output_fe}"
                 );
             }
             let proposal_output = proposal.output_at(challenge_position);
-            if proposal_output != hash_to_fe(proof_journal.claimed_l2_output_root) {
+            if proposal_output != claimed_output_fe {
                 warn!(
-                    "Proposal output fe {proposal_output} doesn't match proof fe {}",
-                    hash_to_fe(proof_journal.claimed_l2_output_root)
+                    "Proposal output fe {proposal_output} doesn't match proof fe {claimed_output_fe}"
                 );
             }
-            let op_node_output = op_node_provider
+            match op_node_provider
                 .output_at_block(proof_journal.claimed_l2_block_number)
-                .await?;
-            if op_node_output != proof_journal.claimed_l2_output_root {
-                error!(
-                    "Local op node output {op_node_output} doesn't match proof {}",
-                    proof_journal.claimed_l2_output_root
-                );
-            } else {
-                info!(
-                    "Proven output matches local op node output {}:{op_node_output}.",
-                    proof_journal.claimed_l2_block_number
-                );
+                .await
+            {
+                Ok(op_node_output) if op_node_output != proof_journal.claimed_l2_output_root => {
+                    error!(
+                        "Local op node output {op_node_output} doesn't match proof {}",
+                        proof_journal.claimed_l2_output_root
+                    );
+                }
+                Ok(op_node_output) => {
+                    info!(
+                        "Proven output matches local op node output {}:{op_node_output}.",
+                        proof_journal.claimed_l2_block_number
+                    );
+                }
+                Err(err) => warn!("Failed to fetch local op node output: {err:?}"),
             }
 
-            // only prove unproven games
+            // only prove unproven games, including proofs still pending inclusion
             let proof_status = proposal_parent_contract
                 .proofStatus(U256::from(u_index), U256::from(v_index))
+                .block(BlockId::pending())
                 .stall()
                 .await
                 ._0;
             if proof_status != 0 {
                 warn!("Skipping proof submission for already proven game at local index {proposal_index}.");
+                kailua_db
+                    .record_proof_status(contender_index, proposal.index, proof_status)
+                    .context("Failed to record proof status")?;
                 continue;
             } else {
                 info!("Proof status: {proof_status}");
             }
 
-            let encoded_seal = Bytes::from(proof.encoded_seal()?);
-
-            // create kzg proofs
-            let mut proofs = [vec![], vec![]];
-            let mut commitments = [vec![], vec![]];
-
-            // kzg proofs for agreed output hashes
-            if challenge_position > 0 {
-                commitments[0].push(contender.io_commitment_for(challenge_position - 1));
-                proofs[0].push(contender.io_proof_for(challenge_position - 1)?);
-
-                commitments[1].push(proposal.io_commitment_for(challenge_position - 1));
-                proofs[1].push(proposal.io_proof_for(challenge_position - 1)?);
-            }
-            // kzg proofs for claimed output hashes
-            if proof_journal.claimed_l2_block_number < proposal.output_block_number {
-                commitments[0].push(contender.io_commitment_for(challenge_position));
-                proofs[0].push(contender.io_proof_for(challenge_position)?);
+            let encoded_seal = match proof.encoded_seal() {
+                Ok(encoded_seal) => Bytes::from(encoded_seal),
+                Err(err) => {
+                    error!("Discarding proof for proposal {proposal_index} with malformed seal: {err:?}");
+                    continue;
+                }
+            };
 
-                commitments[1].push(proposal.io_commitment_for(challenge_position));
-                proofs[1].push(proposal.io_proof_for(challenge_position)?);
-            }
+            // create kzg proofs, retrying the match on failure
+            let (commitments, proofs) = match match_kzg_proofs(
+                &contender,
+                &proposal,
+                challenge_position,
+                proof_journal.claimed_l2_block_number,
+            ) {
+                Ok(kzg_proofs) => kzg_proofs,
+                Err(err) => {
+                    error!("Failed to compute kzg proofs for proposal {proposal_index}: {err:?}");
+                    deferred_proposals.push(proposal_index);
+                    continue;
+                }
+            };
 
             info!(
                 "Submitting proof to tournament at index {} for match between children {u_index} and {v_index} over output {challenge_position} with {} kzg proof(s).",
@@ -589,8 +1213,9 @@ pub async fn handle_proposals(
                 info!("Proof L1 head confirmed.");
             }
 
-            let expected_block_number =
-                proposal_parent.output_block_number + challenge_position + 1;
+            let expected_block_number = kailua_db
+                .config
+                .output_block_number(proposal_parent.output_block_number, challenge_position);
             if expected_block_number != proof_journal.claimed_l2_block_number {
                 warn!(
                     "Claimed l2 block number mismatch. Found {}, expected {expected_block_number}.",
@@ -600,65 +1225,262 @@ pub async fn handle_proposals(
                 info!("Claimed l2 block number confirmed.");
             }
 
-            match proposal_parent_contract
-                .prove(
-                    [u_index, v_index, challenge_position],
-                    encoded_seal.clone(),
-                    proof_journal.agreed_l2_output_root,
-                    [
-                        contender.output_at(challenge_position),
-                        proposal.output_at(challenge_position),
-                    ],
-                    proof_journal.claimed_l2_output_root,
-                    commitments,
-                    proofs,
+            let prove_call = proposal_parent_contract.prove(
+                [u_index, v_index, challenge_position],
+                encoded_seal.clone(),
+                proof_journal.agreed_l2_output_root,
+                [
+                    contender.output_at(challenge_position),
+                    proposal.output_at(challenge_position),
+                ],
+                proof_journal.claimed_l2_output_root,
+                commitments,
+                proofs,
+            );
+            // Estimate the cost of submission before concurrent proofs make it revert
+            let gas_estimate = match prove_call.estimate_gas().await {
+                Ok(gas_estimate) => Some(gas_estimate),
+                Err(err) => {
+                    warn!("Failed to estimate proof submission gas: {err:?}");
+                    None
+                }
+            };
+            // Skip matches already proven at a confirmed L1 block
+            match competition.confirmed_block(&validator_provider).await {
+                Ok(confirmed_block) => {
+                    let proof_status = proposal_parent_contract
+                        .proofStatus(U256::from(u_index), U256::from(v_index))
+                        .block(BlockId::number(confirmed_block))
+                        .stall()
+                        .await
+                        ._0;
+                    if proof_status != 0 {
+                        info!(
+                            "Match between {contender_index} and {} was proven concurrently as of L1 block {confirmed_block}.",
+                            proposal.index
+                        );
+                        competition.record_redundant_proof(gas_estimate);
+                        kailua_db
+                            .record_proof_status(contender_index, proposal.index, proof_status)
+                            .context("Failed to record proof status")?;
+                        continue;
+                    }
+                }
+                Err(err) => warn!("Failed to fetch confirmed L1 block: {err:?}"),
+            }
+            // Re-simulate the proof against the pending state to catch unconfirmed submissions
+            if let Err(err) = prove_call.call().block(BlockId::pending()).await {
+                let proof_status = proposal_parent_contract
+                    .proofStatus(U256::from(u_index), U256::from(v_index))
+                    .block(BlockId::pending())
+                    .stall()
+                    .await
+                    ._0;
+                if proof_status != 0 {
+                    info!(
+                        "Match between {contender_index} and {} was proven concurrently.",
+                        proposal.index
+                    );
+                    competition.record_redundant_proof(gas_estimate);
+                    kailua_db
+                        .record_proof_status(contender_index, proposal.index, proof_status)
+                        .context("Failed to record proof status")?;
+                } else {
+                    error!("Aborting proof submission that fails simulation: {err:?}");
+                }
+                continue;
+            }
+            let mut prove_txn = prove_call.into_transaction_request();
+            // save on calldata costs by relaying compressed calldata where possible
+            if let (Some(relay), Some(gas_estimate)) = (args.proof_relay.proof_relay, gas_estimate)
+            {
+                match relayed_request(
+                    relay,
+                    validator_address,
+                    &prove_txn,
+                    &validator_provider,
+                    gas_estimate,
                 )
-                .send()
                 .await
-                .context("prove (send)")
+                {
+                    Ok(Some(relayed_txn)) => prove_txn = relayed_txn,
+                    Ok(None) => info!("Submitting proof directly, as relaying it saves no gas."),
+                    Err(err) => warn!("Failed to relay proof submission: {err:?}"),
+                }
+            }
+            match send_private_transaction(
+                prove_txn,
+                validator_address,
+                private_txn_provider.as_ref(),
+                bundle_txn_provider.as_ref(),
+                &validator_provider,
+                &validator_wallet,
+                &args.private_txn,
+            )
+            .await
+            .context("prove")
             {
-                Ok(txn) => match txn.get_receipt().await.context("prove (get_receipt)") {
-                    Ok(receipt) => {
-                        info!("Proof submitted: {receipt:?}");
-                        let proof_status = proposal_parent_contract
-                            .proofStatus(U256::from(u_index), U256::from(v_index))
-                            .stall()
-                            .await
-                            ._0;
+                Ok(receipt) => {
+                    info!("Proof submitted: {receipt:?}");
+                    latency_tracker.record(proposal.index, DisputeStage::ProofSubmitted);
+                    let proof_status = proposal_parent_contract
+                        .proofStatus(U256::from(u_index), U256::from(v_index))
+                        .stall()
+                        .await
+                        ._0;
+                    info!(
+                        "Match between {contender_index} and {} proven: {proof_status}",
+                        proposal.index
+                    );
+                    if let Some(verdict_feed) = &verdict_feed {
+                        verdict_feed
+                            .publish(Verdict::ProofSubmission {
+                                parent_index: proposal_parent.index,
+                                contender_index,
+                                proposal_index: proposal.index,
+                                challenge_position,
+                                claimed_l2_block_number: proof_journal.claimed_l2_block_number,
+                                claimed_l2_output_root: proof_journal.claimed_l2_output_root,
+                                proof_status,
+                                transaction_hash: receipt.transaction_hash,
+                            })
+                            .await;
+                    }
+                    kailua_db
+                        .record_proof_status(contender_index, proposal.index, proof_status)
+                        .context("Failed to record proof status")?;
+                }
+                Err(e) => {
+                    // A revert caused by a concurrent proof still settles the match
+                    let proof_status = proposal_parent_contract
+                        .proofStatus(U256::from(u_index), U256::from(v_index))
+                        .block(BlockId::pending())
+                        .stall()
+                        .await
+                        ._0;
+                    if proof_status != 0 {
                         info!(
-                            "Match between {contender_index} and {} proven: {proof_status}",
+                            "Match between {contender_index} and {} was proven concurrently.",
                             proposal.index
                         );
+                        kailua_db
+                            .record_proof_status(contender_index, proposal.index, proof_status)
+                            .context("Failed to record proof status")?;
+                    } else {
+                        error!("Failed to submit proof txn: {e:?}");
                     }
-                    Err(e) => {
-                        error!("Failed to confirm proof txn: {e:?}");
-                    }
-                },
-                Err(e) => {
-                    error!("Failed to send proof txn: {e:?}");
                 }
             }
         }
+
+        // settle the timelines of disputes whose games were resolved, unless shedding load
+        let open_disputes = if load_shedder.sheds(WorkClass::Stats) {
+            vec![]
+        } else {
+            latency_tracker.open.keys().copied().collect::<Vec<_>>()
+        };
+        for proposal_index in open_disputes {
+            let Some(proposal) = kailua_db.get_local_proposal(&proposal_index) else {
+                continue;
+            };
+            match proposal.fetch_finality(&validator_provider).await {
+                Ok(Some(_)) => {}
+                Ok(None) => continue,
+                Err(err) => {
+                    warn!("Failed to fetch finality of proposal {proposal_index}: {err:?}");
+                    continue;
+                }
+            }
+            let resolved_at = proposal
+                .tournament_contract_instance(&validator_provider)
+                .resolvedAt()
+                .stall()
+                .await
+                ._0;
+            stats_tracker.record_resolution(proposal_index, resolved_at);
+            if let Err(err) = latency_tracker.resolve(proposal_index, resolved_at) {
+                warn!("Failed to record dispute latency: {err:?}");
+            }
+        }
+        latency_tracker.check_slo();
+        availability_monitor.check(&kailua_db);
+        if let Some(api_state) = &api_state {
+            api_state.update_latency(latency_tracker.report());
+            api_state.update_data_availability(availability_monitor.report());
+            api_state.update_stats(stats_tracker.clone());
+            api_state.update_dead_letters(proof_index.dead_letters());
+            api_state.update_redundant_proofs(competition.redundant_proofs, competition.saved_gas);
+            // a panicked writer can only have left a stale measurement behind
+            let usage = *disk_usage.lock().unwrap_or_else(PoisonError::into_inner);
+            api_state.update_disk_usage(usage);
+        }
+
+        cadence.update(found_new_games || !latency_tracker.open.is_empty());
+        if let Some(api_state) = &api_state {
+            api_state.update_poll_interval(cadence.interval());
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn request_proof(
     channel: &mut DuplexChannel<Message>,
+    proof_index: &mut ProofIndex,
     contender: &Proposal,
     proposal: &Proposal,
-    l1_node_provider: &ReqwestProvider,
-    l2_node_provider: &ReqwestProvider,
+    l1_node_provider: &MeteredProvider,
+    l2_node_provider: &MeteredProvider,
     op_node_provider: &OpNodeProvider,
+    prefetch_queue: &Option<UnboundedSender<PrefetchJob>>,
+    output_block_span: u64,
 ) -> anyhow::Result<()> {
+    let request = proof_request(
+        contender,
+        proposal,
+        l1_node_provider,
+        l2_node_provider,
+        op_node_provider,
+        output_block_span,
+    )
+    .await?;
+    // Skip dispatching proofs that are already pending for another proposal
+    let proof_key = request.proof_file_name();
+    if !proof_index.register(proof_key.clone(), proposal.index)? {
+        return Ok(());
+    }
+    // Download the L1 data needed for proving while earlier proofs are still being computed
+    if let Some(prefetch_queue) = prefetch_queue {
+        prefetch_queue.send(PrefetchJob {
+            proof_key,
+            l1_head: proposal.l1_head,
+            agreed_l2_block_number: request.agreed_l2_block_number,
+            claimed_l2_block_number: request.claimed_l2_block_number,
+        })?;
+    }
+    // Message proving task
+    channel.sender.send(Message::Proposal(request)).await?;
+    Ok(())
+}
+
+/// Fetches the data needed to prove the first output at which the proposal diverges from its
+/// contender.
+pub async fn proof_request<T: Transport + Clone, P1: Provider<T>, P2: Provider<T>>(
+    contender: &Proposal,
+    proposal: &Proposal,
+    l1_node_provider: P1,
+    l2_node_provider: P2,
+    op_node_provider: &OpNodeProvider,
+    output_block_span: u64,
+) -> anyhow::Result<ProofRequest> {
     let challenge_point = contender
         .divergence_point(proposal)
-        .expect("Contender does not diverge from proposal.") as u64;
+        .context("Contender does not diverge from proposal.")? as u64;
 
     // Read additional data for Kona invocation
     info!("Requesting proof for proposal {}.", proposal.index);
-    let agreed_l2_head_number =
-        proposal.output_block_number - proposal.io_field_elements.len() as u64 - 1
-            + challenge_point; // the challenge point is zero indexed, so it cancels out
+    let parent_block_number = proposal.output_block_number
+        - (proposal.io_field_elements.len() as u64 + 1) * output_block_span;
+    let agreed_l2_head_number = parent_block_number + challenge_point * output_block_span;
     debug!("l2_head_number {:?}", &agreed_l2_head_number);
     let agreed_l2_head_hash = l2_node_provider
         .get_block_by_number(
@@ -667,7 +1489,7 @@ async fn request_proof(
         )
         .await
         .context("agreed_l2_head_hash")?
-        .expect("Agreed l2 head not found")
+        .context("Agreed l2 head not found")?
         .header
         .hash;
     debug!("l2_head {:?}", &agreed_l2_head_hash);
@@ -675,7 +1497,7 @@ async fn request_proof(
         .output_at_block(agreed_l2_head_number)
         .await
         .context("output_at_block")?;
-    let claimed_l2_block_number = agreed_l2_head_number + 1;
+    let claimed_l2_block_number = agreed_l2_head_number + output_block_span;
     let claimed_l2_output_root = op_node_provider
         .output_at_block(claimed_l2_block_number)
         .await
@@ -688,7 +1510,7 @@ async fn request_proof(
             .get_block_by_hash(contender.l1_head, BlockTransactionsKind::Hashes)
             .await
             .context("u_blob_block_parent get_block_by_hash")?
-            .expect("u_blob_block_parent not found");
+            .context("u_blob_block_parent not found")?;
         let u_blob_block = l1_node_provider
             .get_block_by_number(
                 BlockNumberOrTag::Number(u_blob_block_parent.header.number + 1),
@@ -696,14 +1518,14 @@ async fn request_proof(
             )
             .await
             .context("u_blob_block get_block_by_number")?
-            .expect("u_blob_block not found");
+            .context("u_blob_block not found")?;
 
         let (v_blob_hash, v_blob) = proposal.io_blob_for(challenge_point);
         let v_blob_block_parent = l1_node_provider
             .get_block_by_hash(proposal.l1_head, BlockTransactionsKind::Hashes)
             .await
             .context("v_blob_block_parent get_block_by_hash")?
-            .expect("v_blob_block_parent not found");
+            .context("v_blob_block_parent not found")?;
         let v_blob_block = l1_node_provider
             .get_block_by_number(
                 BlockNumberOrTag::Number(v_blob_block_parent.header.number + 1),
@@ -711,7 +1533,7 @@ async fn request_proof(
             )
             .await
             .context("v_blob_block get_block_by_number")?
-            .expect("v_blob_block not found");
+            .context("v_blob_block not found")?;
 
         info!(
             "Fetched blobs {}:{u_blob_hash} and {}:{v_blob_hash} for challenge point {challenge_point}",
@@ -752,161 +1574,359 @@ async fn request_proof(
     } else {
         None
     };
-    // Message proving task
-    channel
-        .sender
-        .send(Message::Proposal {
-            index: proposal.index,
-            precondition_validation_data,
-            l1_head: proposal.l1_head,
-            agreed_l2_head_hash,
-            agreed_l2_output_root,
-            claimed_l2_block_number,
-            claimed_l2_output_root,
-        })
-        .await?;
-    Ok(())
+    Ok(ProofRequest {
+        index: proposal.index,
+        precondition_validation_data,
+        l1_head: proposal.l1_head,
+        agreed_l2_head_hash,
+        agreed_l2_output_root,
+        agreed_l2_block_number: agreed_l2_head_number,
+        claimed_l2_block_number,
+        claimed_l2_output_root,
+    })
 }
 
 pub async fn handle_proofs(
     mut channel: DuplexChannel<Message>,
     args: ValidateArgs,
+    mut settings: watch::Receiver<ValidateArgs>,
     data_dir: PathBuf,
+    cancelled_proofs: CancelledProofs,
+    disk_usage: SharedDiskUsage,
 ) -> anyhow::Result<()> {
     // Fetch rollup configuration
-    let l2_chain_id = fetch_rollup_config(&args.core.op_node_url, &args.core.op_geth_url, None)
-        .await?
-        .l2_chain_id
-        .to_string();
+    let rollup_config =
+        fetch_rollup_config(&args.core.op_node_url, &args.core.op_geth_url, None).await?;
+    let l2_chain_id = rollup_config.l2_chain_id;
+    let rollup_config_hash = B256::from(config_hash(&rollup_config)?);
+    let rollup_config = serde_json::to_value(&rollup_config)?;
+    for (hardfork, activation) in unsupported_hardforks(&rollup_config) {
+        warn!("PROVER OUTDATED! Hardfork {hardfork} activating at {activation} is unsupported.");
+    }
+    let mut failover = ProverFailover::new(args.failover.clone(), args.boundless_args.is_some())?;
+    let mut host_service = args.kailua_host_service.as_deref().map(HostService::new);
+    let receipt_storage = args.receipt_storage.storage()?;
+    let job_dirs = JobDirs::new(&data_dir, &args.workdir, disk_usage)?;
     // Run proof generator loop
-    loop {
+    'proofs: loop {
         // Dequeue messages
-        let Message::Proposal {
-            index: proposal_index,
-            precondition_validation_data,
-            l1_head,
-            agreed_l2_head_hash,
-            agreed_l2_output_root,
-            claimed_l2_block_number,
-            claimed_l2_output_root,
-        } = channel
+        let Message::Proposal(request) = channel
             .receiver
             .recv()
             .await
             .ok_or(anyhow!("proof receiver channel closed"))?
         else {
-            bail!("Unexpected message type.");
+            error!("Unexpected message in proof receiver channel.");
+            continue;
         };
+        // Apply any settings reloaded by the operator
+        if settings.has_changed().unwrap_or_default() {
+            let reloaded = settings.borrow_and_update().clone();
+            if let Err(err) =
+                failover.reconfigure(reloaded.failover, reloaded.boundless_args.is_some())
+            {
+                error!("Ignoring reloaded prover settings: {err:?}");
+            }
+            host_service = reloaded
+                .kailua_host_service
+                .as_deref()
+                .map(HostService::new);
+        }
+        let proposal_index = request.index;
+        if Competition::is_cancelled(&cancelled_proofs, proposal_index) {
+            info!("Skipping cancelled proof for local index {proposal_index}.");
+            continue;
+        }
         info!("Processing proof for local index {proposal_index}.");
+        if let Err(err) = check_hardfork_support(&rollup_config, request.claimed_l2_block_number) {
+            error!("{err:?}");
+            continue;
+        }
         // Prepare kailua-host parameters
-        let precondition_hash = precondition_validation_data
-            .as_ref()
-            .map(|d| d.precondition_hash())
-            .unwrap_or_default();
-        let proof_file_name = fpvm_proof_file_name(
-            precondition_hash,
-            l1_head,
-            claimed_l2_output_root,
-            claimed_l2_block_number,
-            agreed_l2_output_root,
-        );
-        let l1_head = l1_head.to_string();
-        let agreed_l2_head_hash = agreed_l2_head_hash.to_string();
-        let agreed_l2_output_root = agreed_l2_output_root.to_string();
-        let claimed_l2_output_root = claimed_l2_output_root.to_string();
-        let claimed_l2_block_number = claimed_l2_block_number.to_string();
+        let proof_file_name = request.proof_file_name();
+        // Fetch receipts computed elsewhere instead of proving them again
+        if let Some(receipt_storage) = &receipt_storage {
+            if !Path::new(&proof_file_name).exists() {
+                let journal = Journal::new(
+                    ProofJournal {
+                        version: PROOF_JOURNAL_VERSION,
+                        precondition_output: request.precondition_hash(),
+                        l1_head: request.l1_head,
+                        agreed_l2_output_root: request.agreed_l2_output_root,
+                        claimed_l2_output_root: request.claimed_l2_output_root,
+                        claimed_l2_block_number: request.claimed_l2_block_number,
+                        config_hash: rollup_config_hash,
+                        l2_chain_id,
+                    }
+                    .encode_packed(),
+                );
+                match receipt_storage.download(&journal).await {
+                    Ok(Some(data)) => {
+                        info!("Fetched stored receipt for local index {proposal_index}.");
+                        if let Err(e) = tokio::fs::write(&proof_file_name, data).await {
+                            error!("Failed to write proof file {proof_file_name}: {e:?}");
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => error!("Failed to fetch stored receipt: {e:?}"),
+                }
+            }
+        }
         let verbosity = [
             String::from("-"),
             (0..args.core.v).map(|_| 'v').collect::<String>(),
         ]
         .concat();
-        let mut proving_args = vec![
-            String::from("--l1-head"), // l1 head from on-chain proposal
-            l1_head,
-            String::from("--agreed-l2-head-hash"), // l2 starting block hash from on-chain proposal
-            agreed_l2_head_hash,
-            String::from("--agreed-l2-output-root"), // l2 starting output root
-            agreed_l2_output_root,
-            String::from("--claimed-l2-output-root"), // proposed output root
-            claimed_l2_output_root,
-            String::from("--claimed-l2-block-number"), // proposed block number
-            claimed_l2_block_number,
-            String::from("--l2-chain-id"), // rollup chain id
-            l2_chain_id.clone(),
-            String::from("--l1-node-address"), // l1 el node
-            args.core.eth_rpc_url.clone(),
-            String::from("--l1-beacon-address"), // l1 cl node
-            args.core.beacon_rpc_url.clone(),
-            String::from("--l2-node-address"), // l2 el node
-            args.core.op_geth_url.clone(),
-            String::from("--op-node-address"), // l2 cl node
-            args.core.op_node_url.clone(),
-            String::from("--data-dir"), // path to cache
-            data_dir.to_str().unwrap().to_string(),
-            String::from("--native"), // run the client natively
-        ];
-        // precondition data
-        if let Some(precondition_data) = precondition_validation_data {
+        // isolate the files of this job from those of concurrent ones
+        let job_dir = match job_dirs.create(&proof_file_name) {
+            Ok(job_dir) => job_dir,
+            Err(err) => {
+                error!("{err:?}");
+                let mut failure = ProofFailure::new(
+                    String::from("workdir"),
+                    None,
+                    vec![format!("{err:?}")],
+                    &witness_archive_path(&data_dir, &proof_file_name),
+                );
+                failure.class = FailureClass::DiskQuota;
+                channel
+                    .sender
+                    .send(Message::ProofFailure(request, failure))
+                    .await?;
+                continue;
+            }
+        };
+        let mut proving_args = request.kailua_host_args(
+            l2_chain_id,
+            &args.core.eth_rpc_url,
+            &args.core.beacon_rpc_url,
+            &args.core.op_geth_url,
+            &args.core.op_node_url,
+            &job_dir,
+        )?;
+        // serve the data downloaded ahead of proving
+        let prefetched_data = prefetch_dir(&data_dir, &proof_file_name);
+        if prefetched_data.exists() {
             proving_args.extend(vec![
-                String::from("--u-block-hash"),
-                precondition_data.validated_blobs[0]
-                    .block_ref
-                    .hash
-                    .to_string(),
-                String::from("--u-blob-kzg-hash"),
-                precondition_data.validated_blobs[0]
-                    .blob_hash
-                    .hash
-                    .to_string(),
-                String::from("--v-block-hash"),
-                precondition_data.validated_blobs[1]
-                    .block_ref
-                    .hash
-                    .to_string(),
-                String::from("--v-blob-kzg-hash"),
-                precondition_data.validated_blobs[1]
-                    .blob_hash
-                    .hash
-                    .to_string(),
+                String::from("--prefetch-dir"),
+                path_arg(&prefetched_data)?,
             ]);
         }
-        // boundless args
-        if let Some(boundless_args) = &args.boundless_args {
-            proving_args.extend(boundless_args.to_arg_vec(&args.boundless_storage_config));
-        }
+        // share computed receipts through the same storage backend
+        proving_args.extend(args.receipt_storage.to_arg_vec());
+        // archive the witness for diagnosing failures
+        let witness_archive = witness_archive_path(&data_dir, &proof_file_name);
+        proving_args.extend(vec![
+            String::from("--witness-archive"),
+            path_arg(&witness_archive)?,
+        ]);
         // verbosity level
         if args.core.v > 0 {
             proving_args.push(verbosity);
         }
         // Prove via kailua-host (re dev mode/bonsai: env vars inherited!)
-        let mut kailua_host_command = Command::new(&args.kailua_host);
-        // get fake receipts when building under devnet
-        if is_dev_mode() {
-            kailua_host_command.env("RISC0_DEV_MODE", "1");
-        }
-        // pass arguments to point at target block
-        kailua_host_command.args(proving_args);
-        debug!("kailua_host_command {:?}", &kailua_host_command);
-        {
-            match kailua_host_command
-                .kill_on_drop(true)
-                .spawn()
-                .context("Invoking kailua-host")?
-                .wait()
-                .await
-            {
-                Ok(proving_task) => {
-                    if !proving_task.success() {
-                        error!("Proving task failure.");
-                    } else {
-                        info!("Proving task successful.");
+        let had_proof_file = Path::new(&proof_file_name).exists();
+        let mut last_failure = None;
+        for backend in failover.backends() {
+            if had_proof_file {
+                info!("Proving skipped. Proof file {proof_file_name} already exists.");
+                break;
+            }
+            let mut backend_args = proving_args.clone();
+            let mut kailua_host_command = kailua_host_command(args.kailua_host.as_deref())?;
+            // get fake receipts when building under devnet
+            if is_dev_mode() {
+                kailua_host_command.env("RISC0_DEV_MODE", "1");
+            }
+            backend.configure(
+                &mut kailua_host_command,
+                &mut backend_args,
+                &args.boundless_args,
+                &args.boundless_storage_config,
+            )?;
+            // pass arguments to point at target block
+            kailua_host_command.args(backend_args);
+            debug!("kailua_host_command {:?}", &kailua_host_command);
+            info!("Proving local index {proposal_index} using {backend:?} backend.");
+            let deadline = failover
+                .deadline(backend)
+                .map(|deadline| Instant::now() + deadline);
+            let outcome = match &host_service {
+                Some(host_service) => host_service
+                    .prove(
+                        &kailua_host_command,
+                        &proof_file_name,
+                        &cancelled_proofs,
+                        proposal_index,
+                        deadline,
+                    )
+                    .await
+                    .map(|result| {
+                        result.map_err(|(error, class)| {
+                            let mut failure = ProofFailure::new(
+                                format!("{backend:?}"),
+                                None,
+                                error.lines().map(String::from).collect(),
+                                &witness_archive,
+                            );
+                            if let Some(class) = class.and_then(FailureClass::from_reported) {
+                                failure.class = class;
+                            }
+                            failure
+                        })
+                    }),
+                None => {
+                    let mut proving_task = kailua_host_command
+                        .stdout(Stdio::piped())
+                        .stderr(Stdio::piped())
+                        .kill_on_drop(true)
+                        .spawn()
+                        .context("Invoking kailua-host")?;
+                    // Forward the output of kailua-host while retaining its tail for diagnostics
+                    let output_tail = OutputTail::default();
+                    let output_readers = [
+                        proving_task
+                            .stdout
+                            .take()
+                            .map(|stdout| output_tail.capture(stdout, false)),
+                        proving_task
+                            .stderr
+                            .take()
+                            .map(|stderr| output_tail.capture(stderr, true)),
+                    ];
+                    // Abort proving if the proof becomes unnecessary, is at risk of missing its
+                    // deadline, or outgrows the disk quota
+                    let mut ticks = 0u64;
+                    let proving_result = loop {
+                        select! {
+                            result = proving_task.wait() => break Ok(result),
+                            _ = sleep(Duration::from_secs(1)) => {
+                                if Competition::is_cancelled(&cancelled_proofs, proposal_index) {
+                                    break Err(Abort::Cancelled);
+                                }
+                                if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                                    break Err(Abort::Deadline);
+                                }
+                                ticks += 1;
+                                if ticks % DISK_USAGE_INTERVAL_SECS == 0
+                                    && job_dirs.measure().exceeds_quota()
+                                {
+                                    break Err(Abort::Quota);
+                                }
+                            }
+                        }
+                    };
+                    if proving_result.is_err() {
+                        if let Err(e) = proving_task.kill().await {
+                            error!("Failed to kill kailua-host: {e:?}");
+                        }
+                    }
+                    for reader in output_readers.into_iter().flatten() {
+                        let _ = reader.await;
                     }
+                    proving_result.map(|proving_result| match proving_result {
+                        Ok(exit_status) if exit_status.success() => {
+                            info!("Proving task successful.");
+                            Ok(())
+                        }
+                        Ok(exit_status) => {
+                            let failure = ProofFailure::new(
+                                format!("{backend:?}"),
+                                Some(exit_status),
+                                output_tail.lines(),
+                                &witness_archive,
+                            );
+                            error!("Proving task failure ({:?}, {exit_status}).", failure.class);
+                            Err(failure)
+                        }
+                        Err(e) => {
+                            error!("Failed to invoke kailua-host: {e:?}");
+                            Err(ProofFailure::new(
+                                format!("{backend:?}"),
+                                None,
+                                vec![format!("{e:?}")],
+                                &witness_archive,
+                            ))
+                        }
+                    })
                 }
-                Err(e) => {
-                    error!("Failed to invoke kailua-host: {e:?}");
+            };
+            let outcome = match outcome {
+                Ok(outcome) => outcome,
+                Err(abort) => {
+                    if !had_proof_file && Path::new(&proof_file_name).exists() {
+                        if let Err(e) = tokio::fs::remove_file(&proof_file_name).await {
+                            error!("Failed to remove partial proof file {proof_file_name}: {e:?}");
+                        }
+                    }
+                    match abort {
+                        Abort::Cancelled => {
+                            warn!("Aborting unnecessary proof generation for local index {proposal_index}.");
+                            job_dirs.remove(&proof_file_name).await;
+                            continue 'proofs;
+                        }
+                        Abort::Quota => {
+                            error!("Aborting proof generation for local index {proposal_index} that exceeds the disk quota.");
+                            let mut failure = ProofFailure::new(
+                                format!("{backend:?}"),
+                                None,
+                                vec![String::from("Proving disk quota exceeded.")],
+                                &witness_archive,
+                            );
+                            failure.class = FailureClass::DiskQuota;
+                            last_failure = Some(failure);
+                            break;
+                        }
+                        Abort::Deadline => {
+                            warn!("Proving deadline exceeded on {backend:?} backend for local index {proposal_index}.");
+                            failover.record(backend, false);
+                            let mut failure = ProofFailure::new(
+                                format!("{backend:?}"),
+                                None,
+                                vec![String::from("Proving deadline exceeded.")],
+                                &witness_archive,
+                            );
+                            failure.class = FailureClass::Timeout;
+                            last_failure = Some(failure);
+                            continue;
+                        }
+                    }
+                }
+            };
+            // Reconcile the outcome with the proof file shared by all backends
+            let success = outcome.is_ok() && Path::new(&proof_file_name).exists();
+            failover.record(backend, success);
+            if success {
+                break;
+            }
+            last_failure = Some(outcome.err().unwrap_or_else(|| {
+                ProofFailure::new(
+                    format!("{backend:?}"),
+                    None,
+                    vec![format!("Proof file {proof_file_name} not found.")],
+                    &witness_archive,
+                )
+            }));
+        }
+        if Path::new(&proof_file_name).exists() {
+            // the witness is only retained for diagnosing failures
+            if witness_archive.exists() {
+                if let Err(e) = tokio::fs::remove_file(&witness_archive).await {
+                    warn!("Failed to remove witness archive {witness_archive:?}: {e:?}");
                 }
             }
+        } else if let Some(failure) = last_failure {
+            channel
+                .sender
+                .send(Message::ProofFailure(request, failure))
+                .await?;
+        }
+        if prefetched_data.exists() {
+            if let Err(e) = tokio::fs::remove_dir_all(&prefetched_data).await {
+                warn!("Failed to remove prefetched data {prefetched_data:?}: {e:?}");
+            }
         }
+        job_dirs.remove(&proof_file_name).await;
         sleep(Duration::from_secs(1)).await;
         // Read receipt file
         if !Path::new(&proof_file_name).exists() {
@@ -928,7 +1948,7 @@ pub async fn handle_proofs(
             continue;
         }
         info!("Read entire proof file.");
-        match bincode::deserialize::<Proof>(&proof_data) {
+        match Proof::decode(&proof_data) {
             Ok(proof) => {
                 // Send proof via the channel
                 channel
@@ -944,6 +1964,36 @@ pub async fn handle_proofs(
     }
 }
 
+/// Returns the kzg commitments and proofs of the agreed and claimed outputs published by the
+/// contender and the proposal that a match proof must reference.
+pub fn match_kzg_proofs(
+    contender: &Proposal,
+    proposal: &Proposal,
+    challenge_position: u64,
+    claimed_l2_block_number: u64,
+) -> anyhow::Result<([Vec<Bytes>; 2], [Vec<Bytes>; 2])> {
+    let mut proofs = [vec![], vec![]];
+    let mut commitments = [vec![], vec![]];
+
+    // kzg proofs for agreed output hashes
+    if challenge_position > 0 {
+        commitments[0].push(contender.io_commitment_for(challenge_position - 1));
+        proofs[0].push(contender.io_proof_for(challenge_position - 1)?);
+
+        commitments[1].push(proposal.io_commitment_for(challenge_position - 1));
+        proofs[1].push(proposal.io_proof_for(challenge_position - 1)?);
+    }
+    // kzg proofs for claimed output hashes
+    if claimed_l2_block_number < proposal.output_block_number {
+        commitments[0].push(contender.io_commitment_for(challenge_position));
+        proofs[0].push(contender.io_proof_for(challenge_position)?);
+
+        commitments[1].push(proposal.io_commitment_for(challenge_position));
+        proofs[1].push(proposal.io_proof_for(challenge_position)?);
+    }
+    Ok((commitments, proofs))
+}
+
 #[cfg(feature = "devnet")]
 fn needs_selector_patch(proof: &Proof) -> bool {
     match proof {
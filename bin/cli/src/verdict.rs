@@ -0,0 +1,191 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::proposal::Proposal;
+use alloy::primitives::{Address, Bytes, B256};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::Signer;
+use alloy::transports::http::reqwest;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct VerdictFeedArgs {
+    /// Secret key to sign published verdicts with instead of the validator key
+    #[clap(long, env)]
+    pub attestation_key: Option<String>,
+    /// Path of the JSON lines file to append signed verdicts to
+    #[clap(long, env)]
+    pub attestation_file: Option<PathBuf>,
+    /// URL to POST every signed verdict to
+    #[clap(long, env)]
+    pub attestation_webhook: Option<String>,
+}
+
+/// A judgement of the validator that downstream consumers may hold it accountable for
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Verdict {
+    /// The validator's assessment of the correctness of a proposal
+    Correctness {
+        game_index: u64,
+        game_contract: Address,
+        parent_index: u64,
+        proposer: Address,
+        output_root: B256,
+        output_block_number: u64,
+        l1_head: B256,
+        correct: bool,
+        /// The positions of the intermediate outputs found to be incorrect
+        incorrect_io: Vec<usize>,
+    },
+    /// A proof submitted by the validator to settle a match between two proposals
+    ProofSubmission {
+        parent_index: u64,
+        contender_index: u64,
+        proposal_index: u64,
+        challenge_position: u64,
+        claimed_l2_block_number: u64,
+        claimed_l2_output_root: B256,
+        proof_status: u8,
+        transaction_hash: B256,
+    },
+}
+
+impl Verdict {
+    /// Returns the correctness verdict on the proposal, if one was reached
+    pub fn correctness(proposal: &Proposal) -> Option<Self> {
+        Some(Self::Correctness {
+            game_index: proposal.index,
+            game_contract: proposal.contract,
+            parent_index: proposal.parent,
+            proposer: proposal.proposer,
+            output_root: proposal.output_root,
+            output_block_number: proposal.output_block_number,
+            l1_head: proposal.l1_head,
+            correct: proposal.is_correct()?,
+            incorrect_io: proposal.assessment.incorrect_io(),
+        })
+    }
+}
+
+/// A verdict signed by the validator's operator
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedVerdict {
+    pub l2_chain_id: u64,
+    /// The unix timestamp of the attestation
+    pub attested_at: u64,
+    pub verdict: Verdict,
+    pub attester: Address,
+    /// The EIP-191 signature of the JSON encoding of `(l2_chain_id, attested_at, verdict)`
+    pub signature: Bytes,
+}
+
+/// Signs the validator's verdicts and publishes them to a local feed and/or a webhook
+#[derive(Clone, Debug)]
+pub struct VerdictFeed {
+    pub signer: PrivateKeySigner,
+    pub l2_chain_id: u64,
+    pub file: Option<PathBuf>,
+    pub webhook: Option<String>,
+    pub client: reqwest::Client,
+}
+
+impl VerdictFeed {
+    /// Returns the feed configured by the arguments, if any destination was provided
+    pub fn new(
+        args: &VerdictFeedArgs,
+        validator_key: &str,
+        l2_chain_id: u64,
+    ) -> anyhow::Result<Option<Self>> {
+        if args.attestation_file.is_none() && args.attestation_webhook.is_none() {
+            return Ok(None);
+        }
+        let signer =
+            PrivateKeySigner::from_str(args.attestation_key.as_deref().unwrap_or(validator_key))
+                .context("Invalid attestation key")?;
+        info!("Publishing verdicts attested by {}.", signer.address());
+        Ok(Some(Self {
+            signer,
+            l2_chain_id,
+            file: args.attestation_file.clone(),
+            webhook: args.attestation_webhook.clone(),
+            client: reqwest::Client::new(),
+        }))
+    }
+
+    pub async fn sign(&self, verdict: Verdict) -> anyhow::Result<SignedVerdict> {
+        let attested_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let message = serde_json::to_vec(&(self.l2_chain_id, attested_at, &verdict))?;
+        let signature = self
+            .signer
+            .sign_message(&message)
+            .await
+            .context("Failed to sign verdict")?;
+        Ok(SignedVerdict {
+            l2_chain_id: self.l2_chain_id,
+            attested_at,
+            verdict,
+            attester: self.signer.address(),
+            signature: Bytes::from(signature.as_bytes()),
+        })
+    }
+
+    /// Signs and publishes the verdict, logging instead of failing on delivery errors
+    pub async fn publish(&self, verdict: Verdict) {
+        let signed_verdict = match self.sign(verdict).await {
+            Ok(signed_verdict) => signed_verdict,
+            Err(err) => {
+                warn!("Failed to attest verdict: {err:?}");
+                return;
+            }
+        };
+        if let Some(path) = &self.file {
+            if let Err(err) = append_line(path, &signed_verdict).await {
+                warn!("Failed to append verdict to {path:?}: {err:?}");
+            }
+        }
+        if let Some(webhook) = &self.webhook {
+            let response = self
+                .client
+                .post(webhook)
+                .json(&signed_verdict)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(err) = response {
+                warn!("Failed to deliver verdict to webhook: {err:?}");
+            }
+        }
+    }
+}
+
+async fn append_line(path: &Path, signed_verdict: &SignedVerdict) -> anyhow::Result<()> {
+    let mut line = serde_json::to_vec(signed_verdict)?;
+    line.push(b'\n');
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(&line).await?;
+    Ok(())
+}
@@ -0,0 +1,232 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::config::Config;
+use crate::db::proposal::Proposal;
+use crate::providers::beacon::BlobProvider;
+use crate::stall::Stall;
+use crate::KAILUA_GAME_TYPE;
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::ProviderBuilder;
+use anyhow::{bail, Context};
+use kailua_contracts::{IDisputeGameFactory::gameAtIndexReturn, *};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct VerifyOutputArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the ethereum rpc endpoint to use (eth namespace required)
+    #[clap(long, env)]
+    pub eth_rpc_url: String,
+    /// Address of the L1 Beacon API endpoint to use for verifying intermediate outputs
+    #[clap(long, env)]
+    pub beacon_rpc_url: Option<String>,
+    /// Address of the rollup's DisputeGameFactory contract
+    #[clap(long, env)]
+    pub dispute_game_factory: Address,
+
+    /// The L2 block number whose output root to verify
+    #[clap(long)]
+    pub block: u64,
+    /// The expected output root of the L2 block
+    #[clap(long)]
+    pub output_root: B256,
+
+    /// Whether to print the verification result as JSON
+    #[clap(long, env)]
+    pub json: bool,
+}
+
+/// The outcome of checking an output root against the latest resolved game covering its block
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputVerification {
+    pub block: u64,
+    pub output_root: B256,
+    pub game_index: u64,
+    pub game_contract: Address,
+    pub game_start_block: u64,
+    pub game_end_block: u64,
+    pub resolved_at: u64,
+    /// Whether the output root was checked against an intermediate commitment of the game
+    pub intermediate: bool,
+    pub verified: bool,
+}
+
+pub async fn verify_output(args: VerifyOutputArgs) -> anyhow::Result<()> {
+    let eth_rpc_provider = ProviderBuilder::new().on_http(args.eth_rpc_url.as_str().try_into()?);
+    let dispute_game_factory =
+        IDisputeGameFactory::new(args.dispute_game_factory, &eth_rpc_provider);
+    info!("DisputeGameFactory({:?})", dispute_game_factory.address());
+
+    let config = Config::load(&KailuaGame::new(
+        dispute_game_factory
+            .gameImpls(KAILUA_GAME_TYPE)
+            .stall()
+            .await
+            .impl_,
+        &eth_rpc_provider,
+    ))
+    .await?;
+
+    // Binary search for the first game ending at or after the block, as games are created in
+    // the order of the blocks they extend the chain to
+    let game_count: u64 = dispute_game_factory
+        .gameCount()
+        .stall()
+        .await
+        .gameCount_
+        .to();
+    let (mut low, mut high) = (0, game_count);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let game_address = dispute_game_factory
+            .gameAtIndex(U256::from(middle))
+            .stall()
+            .await
+            .proxy_;
+        let end_block: u64 = KailuaTournament::new(game_address, &eth_rpc_provider)
+            .l2BlockNumber()
+            .stall()
+            .await
+            .l2BlockNumber_
+            .to();
+        if end_block < args.block {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+
+    // Search the proposals that can cover the block for the resolved one
+    let mut covering_game = None;
+    for index in low..game_count {
+        let gameAtIndexReturn {
+            gameType_: game_type,
+            proxy_: game_address,
+            ..
+        } = dispute_game_factory
+            .gameAtIndex(U256::from(index))
+            .stall()
+            .await;
+        if game_type != KAILUA_GAME_TYPE {
+            continue;
+        }
+        let tournament = KailuaTournament::new(game_address, &eth_rpc_provider);
+        let end_block: u64 = tournament.l2BlockNumber().stall().await.l2BlockNumber_.to();
+        if end_block >= args.block + config.proposal_block_count {
+            // Later games only extend the chain beyond the block
+            break;
+        }
+        // Only games resolved in favor of their proposer are final
+        if Proposal::parse_finality(tournament.status().stall().await._0)? != Some(true) {
+            continue;
+        }
+        let parent_address = tournament.parentGame().stall().await.parentGame_;
+        let start_block: u64 = if parent_address == game_address {
+            end_block
+        } else {
+            KailuaTournament::new(parent_address, &eth_rpc_provider)
+                .l2BlockNumber()
+                .stall()
+                .await
+                .l2BlockNumber_
+                .to()
+        };
+        if (start_block == end_block && args.block == end_block)
+            || (start_block < args.block && args.block <= end_block)
+        {
+            covering_game = Some((index, tournament, start_block, end_block));
+            break;
+        }
+    }
+    let Some((game_index, tournament, start_block, end_block)) = covering_game else {
+        bail!(
+            "No resolved game covers block {} yet. The output can not be verified.",
+            args.block
+        );
+    };
+    let resolved_at = tournament.resolvedAt().stall().await._0;
+    info!(
+        "Game {game_index} ({}) covering blocks {start_block} to {end_block} was resolved at {resolved_at}.",
+        tournament.address()
+    );
+
+    // Compare against the root claim or the intermediate commitment for the block
+    let intermediate = args.block != end_block;
+    let verified = if intermediate {
+        let Some(beacon_rpc_url) = &args.beacon_rpc_url else {
+            bail!(
+                "Block {} is an intermediate output of game {game_index}, which requires the beacon-rpc-url to verify.",
+                args.block
+            );
+        };
+        let blob_provider = BlobProvider::new(beacon_rpc_url).await?;
+        let proposal = Proposal::load(&config, &blob_provider, &tournament)
+            .await
+            .context("Failed to load game commitments")?;
+        let Some(position) = config.intermediate_output_position(start_block, args.block) else {
+            bail!(
+                "Block {} is not committed to by game {game_index}, which only commits to every {} blocks after block {start_block}.",
+                args.block,
+                config.output_block_span
+            );
+        };
+        let Some(output_fe) = proposal.io_field_elements.get(position as usize) else {
+            bail!(
+                "Game {game_index} commits to {} intermediate outputs instead of {}.",
+                proposal.io_field_elements.len(),
+                config.proposal_output_count() - 1
+            );
+        };
+        *output_fe == config.field_encoding.output_to_fe(args.output_root)
+    } else {
+        B256::from(tournament.rootClaim().stall().await.rootClaim_.0) == args.output_root
+    };
+
+    let verification = OutputVerification {
+        block: args.block,
+        output_root: args.output_root,
+        game_index,
+        game_contract: *tournament.address(),
+        game_start_block: start_block,
+        game_end_block: end_block,
+        resolved_at,
+        intermediate,
+        verified,
+    };
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&verification)?);
+    } else if verified {
+        println!(
+            "Output root {} of block {} is finalized by game {game_index} ({}).",
+            args.output_root,
+            args.block,
+            tournament.address()
+        );
+    } else {
+        println!(
+            "Output root {} of block {} contradicts game {game_index} ({}).",
+            args.output_root,
+            args.block,
+            tournament.address()
+        );
+    }
+    if !verified {
+        bail!("Output root verification failed.");
+    }
+    Ok(())
+}
@@ -0,0 +1,156 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use tracing::{info, warn};
+
+/// The fraction of the disk quota above which the prover reports disk pressure
+pub const DISK_PRESSURE_RATIO: f64 = 0.9;
+
+/// The seconds between measurements of the disk usage of a running proving job
+pub const DISK_USAGE_INTERVAL_SECS: u64 = 10;
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct WorkDirArgs {
+    /// Number of bytes that the working directories of proving jobs may occupy in total
+    #[clap(long, env)]
+    pub proving_disk_quota: Option<u64>,
+}
+
+/// The disk space occupied by the working directories of proving jobs
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct DiskUsage {
+    pub used_bytes: u64,
+    pub quota_bytes: Option<u64>,
+}
+
+impl DiskUsage {
+    /// Whether the working directories occupy most of the disk quota
+    pub fn under_pressure(&self) -> bool {
+        self.quota_bytes
+            .is_some_and(|quota| self.used_bytes as f64 >= quota as f64 * DISK_PRESSURE_RATIO)
+    }
+
+    pub fn exceeds_quota(&self) -> bool {
+        self.quota_bytes
+            .is_some_and(|quota| self.used_bytes > quota)
+    }
+}
+
+/// Shared handle to the disk usage last measured by the prover
+pub type SharedDiskUsage = Arc<Mutex<DiskUsage>>;
+
+/// The isolated working directories of proving jobs, so that concurrent kailua-host invocations
+/// never share a key-value store or temporary files.
+#[derive(Clone, Debug)]
+pub struct JobDirs {
+    pub root: PathBuf,
+    pub quota: Option<u64>,
+    pub usage: SharedDiskUsage,
+}
+
+impl JobDirs {
+    /// Opens the working directories under the data directory, removing those left behind by
+    /// jobs interrupted in a previous run.
+    pub fn new(
+        data_dir: &Path,
+        args: &WorkDirArgs,
+        usage: SharedDiskUsage,
+    ) -> anyhow::Result<Self> {
+        let root = data_dir.join("jobs");
+        if root.exists() {
+            std::fs::remove_dir_all(&root).context("Failed to remove stale job directories")?;
+        }
+        std::fs::create_dir_all(&root).context("Failed to create job directories")?;
+        let job_dirs = Self {
+            root,
+            quota: args.proving_disk_quota,
+            usage,
+        };
+        job_dirs.measure();
+        Ok(job_dirs)
+    }
+
+    /// Returns the working directory of the job computing the given proof
+    pub fn job_dir(&self, proof_key: &str) -> PathBuf {
+        self.root.join(proof_key)
+    }
+
+    /// Creates the working directory of a job after checking that the quota allows it
+    pub fn create(&self, proof_key: &str) -> anyhow::Result<PathBuf> {
+        let usage = self.measure();
+        if usage.exceeds_quota() {
+            bail!(
+                "Job directories occupy {} bytes, exceeding the disk quota of {} bytes.",
+                usage.used_bytes,
+                self.quota.unwrap_or_default()
+            );
+        }
+        let job_dir = self.job_dir(proof_key);
+        std::fs::create_dir_all(&job_dir)
+            .context(format!("Failed to create job directory {job_dir:?}"))?;
+        Ok(job_dir)
+    }
+
+    /// Removes the working directory of a completed or cancelled job
+    pub async fn remove(&self, proof_key: &str) {
+        let job_dir = self.job_dir(proof_key);
+        if job_dir.exists() {
+            if let Err(err) = tokio::fs::remove_dir_all(&job_dir).await {
+                warn!("Failed to remove job directory {job_dir:?}: {err:?}");
+            } else {
+                info!("Removed job directory {job_dir:?}.");
+            }
+        }
+        self.measure();
+    }
+
+    /// Measures the space occupied by all job directories and publishes it
+    pub fn measure(&self) -> DiskUsage {
+        let usage = DiskUsage {
+            used_bytes: dir_size(&self.root),
+            quota_bytes: self.quota,
+        };
+        if usage.under_pressure() {
+            warn!(
+                "DISK PRESSURE! Job directories occupy {} of {} quota bytes.",
+                usage.used_bytes,
+                self.quota.unwrap_or_default()
+            );
+        }
+        *self.usage.lock().unwrap_or_else(PoisonError::into_inner) = usage;
+        usage
+    }
+}
+
+/// Returns the total size of the files under the given path
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| dir_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,326 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::network::EthereumWallet;
+use alloy::primitives::{Bytes, B256, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::sol_types::SolValue;
+use anyhow::{bail, Context};
+use kailua_cli::db::proposal::Proposal;
+use kailua_cli::providers::beacon::blob_fe_proof;
+use kailua_cli::KAILUA_GAME_TYPE;
+use kailua_common::blobs::hash_to_fe;
+use kailua_common::journal::{ProofJournal, PROOF_JOURNAL_VERSION};
+use kailua_contracts::{DisputeGameFactory, KailuaGame, KailuaTreasury, RiscZeroMockVerifier};
+use risc0_zkvm::sha::Digest;
+use risc0_zkvm::{FakeReceipt, InnerReceipt, Receipt, ReceiptClaim};
+use std::net::TcpListener;
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+/// The first three dev accounts funded by anvil
+const OWNER_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+const PROPOSER_KEY: &str = "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+const CHALLENGER_KEY: &str = "0x5de4111afa1a4b94908f83103eb1f1706367c2e68ca870fc3fb9a804cdd820a4";
+
+/// The storage slot of `OwnableUpgradeable._owner` in the `DisputeGameFactory`
+const FACTORY_OWNER_SLOT: u64 = 0x33;
+
+const L2_CHAIN_ID: u64 = 11155420;
+const PROPOSAL_BLOCK_COUNT: u64 = 2;
+const OUTPUT_BLOCK_SPAN: u64 = 1;
+const CHALLENGE_TIMEOUT: u64 = 600;
+const PARTICIPATION_BOND: u64 = 1_000_000_000;
+
+const DEFENDER_WINS: u8 = 2;
+const U_WIN_V_LOSE: u8 = 3;
+
+/// Kills the anvil process once the test is over
+struct Anvil {
+    process: Child,
+    endpoint: String,
+}
+
+impl Anvil {
+    /// Spawns anvil on a free port, returning `None` if it is not installed
+    fn spawn() -> anyhow::Result<Option<Self>> {
+        let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+        let process = match Command::new("anvil")
+            .args(["--port", &port.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(process) => process,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Some(Self {
+            process,
+            endpoint: format!("http://127.0.0.1:{port}"),
+        }))
+    }
+}
+
+impl Drop for Anvil {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// Proposes an honest and a contradicting proposal on a fresh anvil chain, proves the match
+/// between them through a mock verifier, and resolves the honest proposal.
+#[tokio::test(flavor = "multi_thread")]
+async fn propose_challenge_and_prove_on_mock_chain() -> anyhow::Result<()> {
+    let Some(anvil) = Anvil::spawn()? else {
+        eprintln!("Skipping mock chain test because anvil is not installed.");
+        return Ok(());
+    };
+
+    let owner_signer: PrivateKeySigner = OWNER_KEY.parse()?;
+    let proposer_signer: PrivateKeySigner = PROPOSER_KEY.parse()?;
+    let challenger_signer: PrivateKeySigner = CHALLENGER_KEY.parse()?;
+    let owner_address = owner_signer.address();
+    let proposer_address = proposer_signer.address();
+    let challenger_address = challenger_signer.address();
+    let url = anvil.endpoint.as_str().try_into()?;
+    let owner_provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(EthereumWallet::from(owner_signer))
+        .on_http(url);
+    let url = anvil.endpoint.as_str().try_into()?;
+    let proposer_provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(EthereumWallet::from(proposer_signer))
+        .on_http(url);
+    let url = anvil.endpoint.as_str().try_into()?;
+    let challenger_provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(EthereumWallet::from(challenger_signer))
+        .on_http(url);
+
+    // wait for anvil to accept requests
+    let mut attempts = 0;
+    while owner_provider.get_block_number().await.is_err() {
+        attempts += 1;
+        if attempts == 50 {
+            bail!("Anvil did not start at {}.", anvil.endpoint);
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    // deploy the factory and take ownership of it
+    let factory = DisputeGameFactory::deploy(&owner_provider).await?;
+    let set_owner: bool = owner_provider
+        .raw_request(
+            "anvil_setStorageAt".into(),
+            (
+                *factory.address(),
+                U256::from(FACTORY_OWNER_SLOT),
+                B256::left_padding_from(owner_address.as_slice()),
+            ),
+        )
+        .await?;
+    assert!(set_owner);
+    assert_eq!(factory.owner().call().await?._0, owner_address);
+
+    // deploy the kailua contracts against a mock verifier
+    let image_id = B256::repeat_byte(0x42);
+    let config_hash = B256::repeat_byte(0x43);
+    let verifier = RiscZeroMockVerifier::deploy(&owner_provider, [0u8; 4].into()).await?;
+    let treasury = KailuaTreasury::deploy(
+        &owner_provider,
+        *verifier.address(),
+        image_id,
+        config_hash,
+        U256::from(PROPOSAL_BLOCK_COUNT),
+        U256::from(OUTPUT_BLOCK_SPAN),
+        KAILUA_GAME_TYPE,
+        *factory.address(),
+        L2_CHAIN_ID,
+    )
+    .await?;
+    let game = KailuaGame::deploy(
+        &owner_provider,
+        *treasury.address(),
+        *verifier.address(),
+        image_id,
+        config_hash,
+        U256::from(PROPOSAL_BLOCK_COUNT),
+        U256::from(OUTPUT_BLOCK_SPAN),
+        KAILUA_GAME_TYPE,
+        *factory.address(),
+        L2_CHAIN_ID,
+        U256::ZERO,
+        U256::from(1),
+        U256::ZERO,
+        CHALLENGE_TIMEOUT,
+    )
+    .await?;
+
+    // anchor proposals at a resolved treasury instance
+    let anchor_root = B256::repeat_byte(0x01);
+    let anchor_block = 0u64;
+    let calls = [
+        factory
+            .setInitBond(KAILUA_GAME_TYPE, U256::ZERO)
+            .into_transaction_request(),
+        treasury
+            .setParticipationBond(U256::from(PARTICIPATION_BOND))
+            .into_transaction_request(),
+        factory
+            .setImplementation(KAILUA_GAME_TYPE, *treasury.address())
+            .into_transaction_request(),
+        factory
+            .create(
+                KAILUA_GAME_TYPE,
+                anchor_root,
+                Bytes::from(anchor_block.abi_encode_packed()),
+            )
+            .into_transaction_request(),
+        factory
+            .setImplementation(KAILUA_GAME_TYPE, *game.address())
+            .into_transaction_request(),
+    ];
+    for call in calls {
+        let receipt = owner_provider
+            .send_transaction(call)
+            .await?
+            .get_receipt()
+            .await?;
+        assert!(receipt.status());
+    }
+    let anchor = KailuaTreasury::new(
+        factory.gameAtIndex(U256::ZERO).call().await?.proxy_,
+        &owner_provider,
+    );
+    assert!(anchor.resolve().send().await?.get_receipt().await?.status());
+
+    // both proposals publish the same intermediate output but disagree on the root claim
+    let intermediate_output = B256::repeat_byte(0x02);
+    let honest_root = B256::repeat_byte(0x03);
+    let faulty_root = B256::repeat_byte(0x04);
+    let sidecar = Proposal::create_sidecar(&[hash_to_fe(intermediate_output)])?;
+    let extra_data = Bytes::from(
+        [
+            (anchor_block + PROPOSAL_BLOCK_COUNT).abi_encode_packed(),
+            0u64.abi_encode_packed(),
+            0u64.abi_encode_packed(),
+        ]
+        .concat(),
+    );
+    for (provider, root) in [
+        (&proposer_provider, honest_root),
+        (&challenger_provider, faulty_root),
+    ] {
+        let receipt = KailuaTreasury::new(*treasury.address(), provider)
+            .propose(root, extra_data.clone())
+            .value(U256::from(PARTICIPATION_BOND))
+            .sidecar(sidecar.clone())
+            .send()
+            .await
+            .context("propose")?
+            .get_receipt()
+            .await?;
+        assert!(receipt.status());
+    }
+    let proposal_address = factory.gameAtIndex(U256::from(1)).call().await?.proxy_;
+    let challenge_address = factory.gameAtIndex(U256::from(2)).call().await?.proxy_;
+    let proposal = KailuaGame::new(proposal_address, &owner_provider);
+    let challenge = KailuaGame::new(challenge_address, &owner_provider);
+    assert_eq!(
+        anchor.children(U256::ZERO).call().await?._0,
+        proposal_address
+    );
+    assert_eq!(
+        anchor.children(U256::from(1)).call().await?._0,
+        challenge_address
+    );
+
+    // prove that the honest root claim follows from the common intermediate output
+    let journal = ProofJournal {
+        version: PROOF_JOURNAL_VERSION,
+        precondition_output: B256::ZERO,
+        l1_head: challenge.l1Head().call().await?.l1Head_,
+        agreed_l2_output_root: intermediate_output,
+        claimed_l2_output_root: honest_root,
+        claimed_l2_block_number: anchor_block + PROPOSAL_BLOCK_COUNT,
+        config_hash,
+        l2_chain_id: L2_CHAIN_ID,
+    }
+    .encode_packed();
+    let receipt = Receipt::new(
+        InnerReceipt::Fake(FakeReceipt::new(ReceiptClaim::ok(
+            Digest::from(image_id.0),
+            journal.clone(),
+        ))),
+        journal,
+    );
+    let seal = risc0_ethereum_contracts::encode_seal(&receipt)?;
+    let commitment = Bytes::from(sidecar.commitments[0].to_vec());
+    let (kzg_proof, _) = blob_fe_proof(&sidecar.blobs[0], 0)?;
+    let kzg_proof = Bytes::from(kzg_proof.to_vec());
+    let receipt = anchor
+        .prove(
+            [0, 1, PROPOSAL_BLOCK_COUNT / OUTPUT_BLOCK_SPAN - 1],
+            Bytes::from(seal),
+            intermediate_output,
+            [honest_root, faulty_root],
+            honest_root,
+            [vec![commitment.clone()], vec![commitment]],
+            [vec![kzg_proof.clone()], vec![kzg_proof]],
+        )
+        .send()
+        .await
+        .context("prove")?
+        .get_receipt()
+        .await?;
+    assert!(receipt.status());
+    let proof_status = anchor
+        .proofStatus(U256::ZERO, U256::from(1))
+        .call()
+        .await?
+        ._0;
+    assert_eq!(proof_status, U_WIN_V_LOSE);
+
+    // the honest proposal is finalized after the timeout, eliminating the challenger
+    let _: serde_json::Value = owner_provider
+        .raw_request("evm_increaseTime".into(), (CHALLENGE_TIMEOUT,))
+        .await?;
+    let _: serde_json::Value = owner_provider.raw_request("evm_mine".into(), ()).await?;
+    assert!(proposal
+        .resolve()
+        .send()
+        .await?
+        .get_receipt()
+        .await?
+        .status());
+    assert_eq!(proposal.status().call().await?._0, DEFENDER_WINS);
+    assert_eq!(
+        treasury
+            .eliminationRound(challenger_address)
+            .call()
+            .await?
+            ._0,
+        U256::from(2)
+    );
+    assert_eq!(
+        treasury.eliminationRound(proposer_address).call().await?._0,
+        U256::ZERO
+    );
+
+    Ok(())
+}
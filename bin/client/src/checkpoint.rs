@@ -0,0 +1,127 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy_primitives::B256;
+use anyhow::Context;
+use kailua_build::{KAILUA_FPVM_ELF, KAILUA_FPVM_ID};
+use risc0_zkvm::{
+    get_prover_server, ExecutorEnv, ExecutorImpl, InnerReceipt, NullSegmentRef, ProverOpts,
+    Receipt, ReceiptClaim, SuccinctReceipt, VerifierContext,
+};
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// The proving progress of a zkVM session, persisted after every proven segment
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionCheckpoint {
+    /// The index of the last segment covered by the receipt
+    pub last_segment: u32,
+    /// The joined receipt of all segments up to and including the last one
+    pub receipt: SuccinctReceipt<ReceiptClaim>,
+}
+
+impl SessionCheckpoint {
+    pub fn load(path: &Path) -> Option<Self> {
+        let data = std::fs::read(path).ok()?;
+        match bincode::deserialize(&data) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(err) => {
+                warn!("Discarding unreadable checkpoint {path:?}: {err:?}");
+                None
+            }
+        }
+    }
+
+    /// Atomically replaces the checkpoint file at the given path
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let temp_path = path.with_extension("tmp");
+        std::fs::write(&temp_path, bincode::serialize(self)?)
+            .context(format!("Failed to write checkpoint {temp_path:?}"))?;
+        std::fs::rename(&temp_path, path).context(format!("Failed to persist checkpoint {path:?}"))
+    }
+}
+
+/// Returns the checkpoint file of the session proving the given input under the current image
+pub fn checkpoint_path(checkpoint_dir: &Path, input: &[u8]) -> PathBuf {
+    let image_id = risc0_zkvm::sha::Digest::from(KAILUA_FPVM_ID);
+    let input_hash = B256::from_slice(sha2::Sha256::digest(input).as_slice());
+    checkpoint_dir.join(format!("{image_id}-{input_hash}.ckpt"))
+}
+
+/// Proves the FPVM session segment by segment, persisting the joined receipt after every segment
+/// so that an interrupted session resumes from its last proven segment.
+///
+/// Execution is deterministic, so the session is re-executed on resumption and only the
+/// segments not covered by the checkpoint are proven.
+pub fn prove_with_checkpoints(
+    env: ExecutorEnv<'_>,
+    checkpoint_path: &Path,
+) -> anyhow::Result<Receipt> {
+    if let Some(parent) = checkpoint_path.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create checkpoint directory")?;
+    }
+    let opts = ProverOpts::groth16();
+    let prover = get_prover_server(&opts).context("get_prover_server")?;
+    let ctx = VerifierContext::default();
+
+    let mut checkpoint = SessionCheckpoint::load(checkpoint_path);
+    if let Some(checkpoint) = &checkpoint {
+        info!(
+            "Resuming proof from checkpoint covering {} segments.",
+            checkpoint.last_segment + 1
+        );
+    }
+    let mut executor = ExecutorImpl::from_elf(env, KAILUA_FPVM_ELF).context("from_elf")?;
+    let mut segment_count = 0;
+    let session = executor
+        .run_with_callback(|segment| {
+            segment_count += 1;
+            if checkpoint
+                .as_ref()
+                .is_some_and(|checkpoint| segment.index <= checkpoint.last_segment)
+            {
+                return Ok(Box::new(NullSegmentRef {}));
+            }
+            info!("Proving segment {}.", segment.index);
+            let segment_receipt = prover.prove_segment(&ctx, &segment)?;
+            let lifted = prover.lift(&segment_receipt)?;
+            let receipt = match checkpoint.take() {
+                Some(checkpoint) => prover.join(&checkpoint.receipt, &lifted)?,
+                None => lifted,
+            };
+            let next_checkpoint = SessionCheckpoint {
+                last_segment: segment.index,
+                receipt,
+            };
+            next_checkpoint.save(checkpoint_path)?;
+            checkpoint = Some(next_checkpoint);
+            Ok(Box::new(NullSegmentRef {}))
+        })
+        .context("run_with_callback")?;
+    info!("Proved all {segment_count} segments.");
+
+    let checkpoint = checkpoint.context("Session produced no segments")?;
+    let journal = session.journal.context("Session produced no journal")?;
+    let receipt = Receipt::new(InnerReceipt::Succinct(checkpoint.receipt), journal.bytes);
+    let receipt = prover
+        .compress(&opts, &receipt)
+        .context("Failed to compress receipt")?;
+    // The completed proof is persisted by the caller
+    if let Err(err) = std::fs::remove_file(checkpoint_path) {
+        warn!("Failed to remove checkpoint {checkpoint_path:?}: {err:?}");
+    }
+    Ok(receipt)
+}
@@ -0,0 +1,207 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::transports::http::reqwest;
+use alloy::transports::http::reqwest::Url;
+use anyhow::{bail, Context};
+use axum::body::Bytes;
+use axum::extract::DefaultBodyLimit;
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::Router;
+use kailua_build::KAILUA_FPVM_ELF;
+use risc0_zkvm::{
+    get_prover_server, ExecutorEnv, ExecutorImpl, InnerReceipt, NullSegmentRef, ProverOpts,
+    Receipt, ReceiptClaim, Segment, SuccinctReceipt, VerifierContext,
+};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::task::{spawn_blocking, JoinSet};
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+
+/// The number of consecutive failures after which a worker is no longer assigned segments
+pub const MAX_WORKER_FAILURES: usize = 3;
+
+/// Serves segment proving requests from a coordinator at the given address
+pub async fn serve_worker(address: SocketAddr) -> anyhow::Result<()> {
+    let app = Router::new()
+        .route("/health", get(|| async { "OK" }))
+        .route("/prove_segment", post(prove_segment))
+        .layer(DefaultBodyLimit::disable());
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .context(format!("Failed to bind worker to {address}"))?;
+    info!("Proving worker listening on {address}.");
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+/// Proves and lifts a bincode-encoded segment into a bincode-encoded succinct receipt
+async fn prove_segment(body: Bytes) -> Result<Vec<u8>, (StatusCode, String)> {
+    spawn_blocking(move || {
+        let segment: Segment = bincode::deserialize(&body)?;
+        info!("Proving segment {}.", segment.index);
+        let prover = get_prover_server(&ProverOpts::groth16())?;
+        let segment_receipt = prover.prove_segment(&VerifierContext::default(), &segment)?;
+        let lifted = prover.lift(&segment_receipt)?;
+        info!("Proved segment {}.", segment.index);
+        Ok::<_, anyhow::Error>(bincode::serialize(&lifted)?)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?
+    .map_err(|e| {
+        error!("Failed to prove segment: {e:?}");
+        (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}"))
+    })
+}
+
+/// Executes the FPVM once to produce its segments, proves them across the given workers, and
+/// joins and compresses the results locally.
+pub fn prove_distributed(
+    env: ExecutorEnv<'_>,
+    workers: &[Url],
+    segments_dir: &Path,
+) -> anyhow::Result<Receipt> {
+    std::fs::create_dir_all(segments_dir).context("Failed to create segments directory")?;
+    let mut executor = ExecutorImpl::from_elf(env, KAILUA_FPVM_ELF).context("from_elf")?;
+    // Spill segments to disk to bound memory usage
+    let mut segment_paths = Vec::new();
+    let session = executor
+        .run_with_callback(|segment| {
+            let path = segments_dir.join(format!("segment-{}.bin", segment.index));
+            std::fs::write(&path, bincode::serialize(&segment)?)?;
+            segment_paths.push(path);
+            Ok(Box::new(NullSegmentRef {}))
+        })
+        .context("run_with_callback")?;
+    info!(
+        "Distributing {} segments across {} workers.",
+        segment_paths.len(),
+        workers.len()
+    );
+    let lifted_receipts =
+        Handle::current().block_on(dispatch_segments(workers.to_vec(), segment_paths))?;
+
+    // Join and wrap locally
+    let opts = ProverOpts::groth16();
+    let prover = get_prover_server(&opts).context("get_prover_server")?;
+    let mut lifted_receipts = lifted_receipts.into_iter();
+    let mut joined = lifted_receipts
+        .next()
+        .context("Session produced no segments")?;
+    for lifted in lifted_receipts {
+        joined = prover.join(&joined, &lifted).context("join")?;
+    }
+    let journal = session.journal.context("Session produced no journal")?;
+    let receipt = Receipt::new(InnerReceipt::Succinct(joined), journal.bytes);
+    let receipt = prover
+        .compress(&opts, &receipt)
+        .context("Failed to compress receipt")?;
+    if let Err(err) = std::fs::remove_dir_all(segments_dir) {
+        warn!("Failed to remove segments directory {segments_dir:?}: {err:?}");
+    }
+    Ok(receipt)
+}
+
+/// Proves every segment on the first available worker, reassigning segments of failed requests
+pub async fn dispatch_segments(
+    workers: Vec<Url>,
+    segment_paths: Vec<PathBuf>,
+) -> anyhow::Result<Vec<SuccinctReceipt<ReceiptClaim>>> {
+    let segment_count = segment_paths.len();
+    let segment_paths = Arc::new(segment_paths);
+    let queue = Arc::new(Mutex::new((0..segment_count).collect::<VecDeque<_>>()));
+    let results = Arc::new(Mutex::new(vec![None; segment_count]));
+    let client = reqwest::Client::new();
+
+    let mut tasks = JoinSet::new();
+    for worker in workers {
+        let segment_paths = segment_paths.clone();
+        let queue = queue.clone();
+        let results = results.clone();
+        let client = client.clone();
+        tasks.spawn(async move {
+            let endpoint = worker.join("prove_segment")?;
+            let mut failures = 0;
+            while failures < MAX_WORKER_FAILURES {
+                let next = queue.lock().unwrap().pop_front();
+                let Some(index) = next else {
+                    // Segments in flight on other workers may still be reassigned
+                    if results.lock().unwrap().iter().all(Option::is_some) {
+                        break;
+                    }
+                    sleep(Duration::from_secs(1)).await;
+                    continue;
+                };
+                match prove_remote_segment(&client, &endpoint, &segment_paths[index]).await {
+                    Ok(lifted) => {
+                        info!("Worker {worker} proved segment {index}.");
+                        results.lock().unwrap()[index] = Some(lifted);
+                        failures = 0;
+                    }
+                    Err(err) => {
+                        warn!("Worker {worker} failed to prove segment {index}: {err:?}");
+                        queue.lock().unwrap().push_back(index);
+                        failures += 1;
+                    }
+                }
+            }
+            if failures >= MAX_WORKER_FAILURES {
+                error!("Dropping worker {worker} after {failures} consecutive failures.");
+            }
+            Ok::<_, anyhow::Error>(())
+        });
+    }
+    while let Some(result) = tasks.join_next().await {
+        if let Err(err) = result? {
+            error!("Worker task failed: {err:?}");
+        }
+    }
+
+    let results = core::mem::take(&mut *results.lock().unwrap());
+    let proven = results.iter().filter(|r| r.is_some()).count();
+    if proven < segment_count {
+        bail!("Only {proven}/{segment_count} segments were proven by the available workers.");
+    }
+    Ok(results.into_iter().flatten().collect())
+}
+
+async fn prove_remote_segment(
+    client: &reqwest::Client,
+    endpoint: &Url,
+    segment_path: &Path,
+) -> anyhow::Result<SuccinctReceipt<ReceiptClaim>> {
+    let segment = tokio::fs::read(segment_path)
+        .await
+        .context(format!("Failed to read segment {segment_path:?}"))?;
+    let response = client
+        .post(endpoint.clone())
+        .body(segment)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    let lifted: SuccinctReceipt<ReceiptClaim> = bincode::deserialize(&response)?;
+    // Reject receipts that do not verify before joining them
+    lifted
+        .verify_integrity()
+        .context("Worker returned an invalid receipt")?;
+    Ok(lifted)
+}
@@ -12,11 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "prove")]
+pub mod checkpoint;
+#[cfg(feature = "prove")]
+pub mod distributed;
+pub mod limits;
 pub mod oracle;
 pub mod proof;
+pub mod replay;
+pub mod storage;
 pub mod witness;
 
+use crate::limits::WitnessLimitArgs;
 use crate::proof::Proof;
+use crate::storage::ReceiptStorageArgs;
 use crate::witness::{BlobWitnessProvider, OracleWitnessProvider};
 use alloy::signers::k256::ecdsa::signature::digest::Digest;
 use alloy::sol_types::SolValue;
@@ -33,8 +42,10 @@ use boundless_market::storage::{StorageProviderConfig, StorageProviderType};
 use clap::Parser;
 use kailua_build::{KAILUA_FPVM_ELF, KAILUA_FPVM_ID};
 use kailua_common::blobs::BlobWitnessData;
+use kailua_common::errors::{ChainError, ProvingError};
 use kailua_common::journal::ProofJournal;
 use kailua_common::oracle::OracleWitnessData;
+use kailua_common::transcript::InputTranscript;
 use kailua_common::witness::Witness;
 use kona_preimage::{HintWriterClient, PreimageOracleClient};
 use kona_proof::l1::OracleBlobProvider;
@@ -42,14 +53,16 @@ use kona_proof::{BootInfo, CachingOracle};
 use risc0_zkvm::sha::Digestible;
 use risc0_zkvm::{default_executor, default_prover, is_dev_mode, ExecutorEnv, Journal, ProverOpts};
 use std::fmt::Debug;
+use std::net::SocketAddr;
 use std::ops::DerefMut;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 use tokio::task::spawn_blocking;
-use tracing::{info, warn};
+use tracing::{error, info, warn};
 
 /// The size of the LRU cache in the oracle.
 pub const ORACLE_LRU_SIZE: usize = 1024;
@@ -68,6 +81,26 @@ pub struct KailuaClientCli {
     /// Storage provider to use for elf and input
     #[clap(flatten)]
     pub boundless_storage_config: Option<StorageProviderConfig>,
+
+    /// Directory to persist local proving checkpoints to
+    #[clap(long, env)]
+    pub checkpoint_dir: Option<PathBuf>,
+    /// Comma-separated urls of the workers to distribute segment proving over
+    #[clap(long, env, value_delimiter = ',')]
+    pub prover_workers: Vec<Url>,
+    /// Address to serve segment proving requests at instead of running the client
+    #[clap(long, env)]
+    pub prover_worker_address: Option<SocketAddr>,
+    /// Path to write a pprof cycle profile of the guest execution to before proving
+    #[clap(long, env)]
+    pub cycle_profile: Option<PathBuf>,
+    /// Path to archive the witness fed to the guest to for native replays
+    #[clap(long, env)]
+    pub witness_archive: Option<PathBuf>,
+    #[clap(flatten)]
+    pub witness_limits: WitnessLimitArgs,
+    #[clap(flatten)]
+    pub receipt_storage: ReceiptStorageArgs,
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -187,12 +220,19 @@ pub fn parse_b256(s: &str) -> Result<B256, String> {
     B256::from_str(s).map_err(|_| format!("Invalid B256 value: {}", s))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run_client<P, H>(
     boundless_args: Option<BoundlessArgs>,
     boundless_storage_config: Option<StorageProviderConfig>,
     oracle_client: P,
     hint_client: H,
     precondition_validation_data_hash: B256,
+    checkpoint_dir: Option<PathBuf>,
+    prover_workers: Vec<Url>,
+    cycle_profile: Option<PathBuf>,
+    witness_archive: Option<PathBuf>,
+    witness_limits: WitnessLimitArgs,
+    receipt_storage: ReceiptStorageArgs,
 ) -> anyhow::Result<()>
 where
     P: PreimageOracleClient + Send + Sync + Debug + Clone + 'static,
@@ -206,28 +246,42 @@ where
         precondition_validation_data_hash,
     )
     .await
-    .expect("Failed to run native client.");
+    .context("Failed to run native client.")?;
+    if let Some(archive_path) = &witness_archive {
+        replay::save_witness_archive(&witness, archive_path).await?;
+    }
+    // refuse to prove witnesses that would exhaust guest memory
+    witness_limits.check(&witness.size())?;
+    // commit to everything fed to the guest for third party audits
+    let transcript = InputTranscript::from_witness(&witness, journal);
     // compute the receipt in the zkvm
     let proof = match boundless_args {
         Some(args) => run_boundless_client(args, boundless_storage_config, journal, witness)
             .await
-            .context("Failed to run boundless client.")?,
-        None => run_zkvm_client(witness)
+            .map_err(|err| ProvingError::Backend {
+                backend: "boundless",
+                message: format!("{err:#}"),
+            })?,
+        None => run_zkvm_client(witness, checkpoint_dir, prover_workers, cycle_profile)
             .await
-            .context("Failed to run zkvm client.")?,
+            .map_err(|err| ProvingError::Backend {
+                backend: "zkvm",
+                message: format!("{err:#}"),
+            })?,
     };
     // Prepare proof file
     let proof_journal = ProofJournal::decode_packed(proof.journal().as_ref())
         .expect("Failed to decode proof output");
-    let mut output_file = File::create(proof::fpvm_proof_file_name(
+    let proof_file_name = proof::fpvm_proof_file_name(
         proof_journal.precondition_output,
         proof_journal.l1_head,
         proof_journal.claimed_l2_output_root,
         proof_journal.claimed_l2_block_number,
         proof_journal.agreed_l2_output_root,
-    ))
-    .await
-    .expect("Failed to create proof output file");
+    );
+    let mut output_file = File::create(&proof_file_name)
+        .await
+        .expect("Failed to create proof output file");
     // Write proof data to file
     let proof_bytes = bincode::serialize(&proof).expect("Could not serialize proof.");
     output_file
@@ -238,6 +292,22 @@ where
         .flush()
         .await
         .expect("Failed to flush proof output file data.");
+    // Write the input transcript next to the proof
+    let transcript_file_name = proof::transcript_file_name(&proof_file_name);
+    if let Err(err) = tokio::fs::write(
+        &transcript_file_name,
+        serde_json::to_vec_pretty(&transcript)?,
+    )
+    .await
+    {
+        error!("Failed to write input transcript {transcript_file_name}: {err:?}");
+    }
+    // Share the proof with other machines through the configured backend
+    if let Some(receipt_storage) = receipt_storage.storage()? {
+        if let Err(err) = receipt_storage.upload(&proof).await {
+            error!("Failed to store receipt: {err:?}");
+        }
+    }
 
     Ok(())
 }
@@ -254,10 +324,10 @@ where
     let oracle_witness = Arc::new(Mutex::new(OracleWitnessData::default()));
     let blobs_witness = Arc::new(Mutex::new(BlobWitnessData::default()));
     info!("Preamble");
-    let oracle = Arc::new(OracleWitnessProvider {
-        oracle: CachingOracle::new(ORACLE_LRU_SIZE, oracle_client, hint_client),
-        witness: oracle_witness.clone(),
-    });
+    let oracle = Arc::new(OracleWitnessProvider::new(
+        CachingOracle::new(ORACLE_LRU_SIZE, oracle_client, hint_client),
+        oracle_witness.clone(),
+    ));
     let boot = Arc::new(
         BootInfo::load(oracle.as_ref())
             .await
@@ -275,12 +345,15 @@ where
         beacon,
     )?;
     // Check output
-    if let Some(computed_output) = real_output_hash {
-        // With sufficient data, the input l2_claim must be true
-        assert_eq!(boot.claimed_l2_output_root, computed_output);
-    } else {
-        // We use the zero claim hash to denote that the data as of l1 head is insufficient
-        assert_eq!(boot.claimed_l2_output_root, B256::ZERO);
+    // With sufficient data, the input l2_claim must be true, while we use the zero claim hash to
+    // denote that the data as of l1 head is insufficient
+    let expected_output = real_output_hash.unwrap_or(B256::ZERO);
+    if boot.claimed_l2_output_root != expected_output {
+        return Err(ChainError::InvalidClaim(format!(
+            "claimed {} but computed {expected_output}",
+            boot.claimed_l2_output_root
+        ))
+        .into());
     }
     let witness = Witness {
         oracle_witness: core::mem::take(oracle_witness.lock().unwrap().deref_mut()),
@@ -291,34 +364,87 @@ where
     Ok((journal_output, witness))
 }
 
-pub async fn run_zkvm_client(witness: Witness) -> anyhow::Result<Proof> {
+/// Executes the FPVM with the profiler enabled, writing a pprof cycle profile of the guest
+pub fn profile_zkvm_client(data: &[u8], profile_path: &Path) -> anyhow::Result<()> {
+    info!("Profiling zkvm client execution.");
+    let env = ExecutorEnv::builder()
+        // Pass in witness data
+        .write_frame(data)
+        .enable_profiler(profile_path)
+        .build()?;
+    let session_info = default_executor()
+        .execute(env, KAILUA_FPVM_ELF)
+        .context("execute")?;
+    let total_cycles = session_info
+        .segments
+        .iter()
+        .map(|segment| 1u64 << segment.po2)
+        .sum::<u64>();
+    info!(
+        "Wrote cycle profile of {total_cycles} total cycles over {} segments to {profile_path:?}.",
+        session_info.segments.len()
+    );
+    Ok(())
+}
+
+pub async fn run_zkvm_client(
+    witness: Witness,
+    checkpoint_dir: Option<PathBuf>,
+    prover_workers: Vec<Url>,
+    cycle_profile: Option<PathBuf>,
+) -> anyhow::Result<Proof> {
     info!("Running zkvm client.");
-    let prove_info = spawn_blocking(move || {
+    let receipt = spawn_blocking(move || {
         let data = rkyv::to_bytes::<rkyv::rancor::Error>(&witness)?.to_vec();
+        if let Some(profile_path) = &cycle_profile {
+            profile_zkvm_client(&data, profile_path)?;
+        }
         // Execution environment
         let env = ExecutorEnv::builder()
             // Pass in witness data
             .write_frame(&data)
             .build()?;
+        #[cfg(feature = "prove")]
+        if !prover_workers.is_empty() && !is_dev_mode() {
+            let segments_dir = checkpoint::checkpoint_path(
+                &checkpoint_dir.unwrap_or_else(std::env::temp_dir),
+                &data,
+            )
+            .with_extension("segments");
+            return distributed::prove_distributed(env, &prover_workers, &segments_dir);
+        }
+        #[cfg(not(feature = "prove"))]
+        if !prover_workers.is_empty() {
+            warn!("Distributed proving requires the prove feature.");
+        }
         let prover = default_prover();
+        // Only sessions proven in this process can be checkpointed
+        match checkpoint_dir {
+            #[cfg(feature = "prove")]
+            Some(checkpoint_dir) if prover.get_name() == "local" && !is_dev_mode() => {
+                let checkpoint_path = checkpoint::checkpoint_path(&checkpoint_dir, &data);
+                return checkpoint::prove_with_checkpoints(env, &checkpoint_path);
+            }
+            Some(_) => warn!("Proving checkpoints are only supported by the local prover."),
+            None => {}
+        }
         let prove_info = prover
             .prove_with_opts(env, KAILUA_FPVM_ELF, &ProverOpts::groth16())
             .context("prove_with_opts")?;
-        Ok::<_, anyhow::Error>(prove_info)
+        info!(
+            "Proof of {} total cycles ({} user cycles) computed.",
+            prove_info.stats.total_cycles, prove_info.stats.user_cycles
+        );
+        Ok::<_, anyhow::Error>(prove_info.receipt)
     })
     .await??;
 
-    info!(
-        "Proof of {} total cycles ({} user cycles) computed.",
-        prove_info.stats.total_cycles, prove_info.stats.user_cycles
-    );
-    prove_info
-        .receipt
+    receipt
         .verify(KAILUA_FPVM_ID)
         .context("receipt verification")?;
     info!("Receipt verified.");
 
-    Ok(Proof::ZKVMReceipt(Box::new(prove_info.receipt)))
+    Ok(Proof::ZKVMReceipt(Box::new(receipt)))
 }
 
 pub async fn run_boundless_client(
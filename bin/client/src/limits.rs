@@ -0,0 +1,62 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::Parser;
+use kailua_common::errors::{KailuaResult, ProvingError};
+use kailua_common::witness::WitnessSize;
+use tracing::{info, warn};
+
+/// The default number of witness bytes above which a warning is logged
+pub const DEFAULT_WITNESS_SOFT_LIMIT: u64 = 512 * 1024 * 1024;
+
+#[derive(Parser, Clone, Debug, Default)]
+pub struct WitnessLimitArgs {
+    /// Number of witness bytes above which to warn that the proof may exhaust guest memory
+    #[clap(long, env, default_value_t = DEFAULT_WITNESS_SOFT_LIMIT)]
+    pub witness_soft_limit: u64,
+    /// Number of witness bytes above which to refuse proving instead of failing within the zkVM
+    #[clap(long, env)]
+    pub zkvm_memory_budget: Option<u64>,
+}
+
+impl WitnessLimitArgs {
+    /// Reports the size of the witness and fails if it exceeds the configured memory budget
+    pub fn check(&self, size: &WitnessSize) -> KailuaResult<()> {
+        let total_bytes = size.total_bytes() as u64;
+        info!(
+            "Witness of {total_bytes} bytes: {} preimages ({} bytes), {} blobs ({} bytes), boot info ({} bytes).",
+            size.preimage_count,
+            size.preimage_bytes,
+            size.blob_count,
+            size.blob_bytes,
+            size.boot_info_bytes
+        );
+        if let Some(budget) = self.zkvm_memory_budget {
+            if total_bytes > budget {
+                return Err(ProvingError::WitnessTooLarge {
+                    size: total_bytes as usize,
+                    budget: budget as usize,
+                }
+                .into());
+            }
+        }
+        if total_bytes > self.witness_soft_limit {
+            warn!(
+                "Witness of {total_bytes} bytes exceeds the soft limit of {} bytes.",
+                self.witness_soft_limit
+            );
+        }
+        Ok(())
+    }
+}
@@ -20,6 +20,12 @@ use kailua_client::KailuaClientCli;
 async fn main() -> anyhow::Result<()> {
     let args = KailuaClientCli::parse();
     kona_host::init_tracing_subscriber(args.kailua_verbosity)?;
+    if let Some(address) = args.prover_worker_address {
+        #[cfg(feature = "prove")]
+        return kailua_client::distributed::serve_worker(address).await;
+        #[cfg(not(feature = "prove"))]
+        anyhow::bail!("Serving as a proving worker at {address} requires the prove feature.");
+    }
     let precondition_validation_data_hash =
         args.precondition_validation_data_hash.unwrap_or_default();
 
@@ -29,6 +35,12 @@ async fn main() -> anyhow::Result<()> {
         ORACLE_READER,
         HINT_WRITER,
         precondition_validation_data_hash,
+        args.checkpoint_dir,
+        args.prover_workers,
+        args.cycle_profile,
+        args.witness_archive,
+        args.witness_limits,
+        args.receipt_storage,
     )
     .await
 }
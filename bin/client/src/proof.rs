@@ -13,7 +13,9 @@
 // limitations under the License.
 
 use alloy_primitives::{keccak256, B256};
+use bincode::Options;
 use kailua_build::KAILUA_FPVM_ID;
+use kailua_common::errors::{EncodingError, KailuaResult, ProvingError};
 use risc0_zkvm::{Journal, Receipt};
 use serde::{Deserialize, Serialize};
 
@@ -24,6 +26,17 @@ pub enum Proof {
 }
 
 impl Proof {
+    /// Decodes a proof file, bounding allocations by its size so that corrupted length prefixes
+    /// fail cleanly
+    pub fn decode(data: &[u8]) -> KailuaResult<Self> {
+        bincode::options()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(data.len() as u64)
+            .deserialize(data)
+            .map_err(|_| EncodingError::Malformed("proof file").into())
+    }
+
     pub fn journal(&self) -> &Journal {
         match self {
             Proof::ZKVMReceipt(receipt) => &receipt.journal,
@@ -31,9 +44,17 @@ impl Proof {
         }
     }
 
-    pub fn encoded_seal(&self) -> anyhow::Result<Vec<u8>> {
+    pub fn encoded_seal(&self) -> KailuaResult<Vec<u8>> {
         match self {
-            Proof::ZKVMReceipt(receipt) => risc0_ethereum_contracts::encode_seal(receipt),
+            Proof::ZKVMReceipt(receipt) => {
+                risc0_ethereum_contracts::encode_seal(receipt).map_err(|err| {
+                    ProvingError::Backend {
+                        backend: "groth16",
+                        message: err.to_string(),
+                    }
+                    .into()
+                })
+            }
             Proof::BoundlessSeal(seal, _) => Ok(seal.clone()),
         }
     }
@@ -83,3 +104,8 @@ pub fn fpvm_proof_file_name(
     let file_name = keccak256(data);
     format!("risc0-{version}-{file_name}.{suffix}")
 }
+
+/// Returns the name of the input transcript file written alongside the given proof file
+pub fn transcript_file_name(proof_file_name: &str) -> String {
+    format!("{proof_file_name}.transcript.json")
+}
@@ -0,0 +1,112 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy_primitives::B256;
+use anyhow::{anyhow, Context};
+use kailua_common::blobs::PreloadedBlobProvider;
+use kailua_common::client::fetch_safe_head;
+use kailua_common::oracle::PreloadedOracle;
+use kailua_common::witness::{ArchivedWitness, Witness};
+use kona_proof::l2::OracleL2ChainProvider;
+use kona_proof::BootInfo;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::task::spawn_blocking;
+use tracing::info;
+
+/// The outcome of natively replaying the fault proof program over an archived witness
+#[derive(Clone, Debug)]
+pub struct ReplayOutcome {
+    pub boot: BootInfo,
+    pub precondition_hash: B256,
+    /// The output root computed at the target block, if the witness data sufficed
+    pub output_root: Option<B256>,
+}
+
+/// Writes the witness fed to the guest to the given path for native replays
+pub async fn save_witness_archive(witness: &Witness, path: &Path) -> anyhow::Result<()> {
+    let data = rkyv::to_bytes::<rkyv::rancor::Error>(witness)?;
+    tokio::fs::write(path, data.as_slice())
+        .await
+        .context(format!(
+            "Failed to write witness archive {}",
+            path.display()
+        ))?;
+    info!("Archived witness to {}.", path.display());
+    Ok(())
+}
+
+pub async fn load_witness_archive(path: &Path) -> anyhow::Result<Witness> {
+    let data = tokio::fs::read(path)
+        .await
+        .context(format!("Failed to read witness archive {}", path.display()))?;
+    // Archived data must be aligned before it can be accessed
+    let mut aligned = rkyv::util::AlignedVec::<16>::with_capacity(data.len());
+    aligned.extend_from_slice(&data);
+    let archived = rkyv::access::<ArchivedWitness, rkyv::rancor::Error>(&aligned)?;
+    Ok(rkyv::deserialize::<Witness, rkyv::rancor::Error>(archived)?)
+}
+
+/// Loads the boot info and the L2 safe head block number the witness starts from
+pub async fn replay_safe_head(witness: Witness) -> anyhow::Result<(BootInfo, u64)> {
+    spawn_blocking(move || {
+        let oracle = Arc::new(PreloadedOracle::from(witness.oracle_witness));
+        kona_proof::block_on(async {
+            let boot = Arc::new(
+                BootInfo::load(oracle.as_ref())
+                    .await
+                    .context("BootInfo::load")?,
+            );
+            let mut l2_provider = OracleL2ChainProvider::new(boot.clone(), oracle.clone());
+            let safe_head = fetch_safe_head(oracle.as_ref(), boot.as_ref(), &mut l2_provider)
+                .await
+                .map_err(|e| anyhow!("Failed to fetch safe head: {e:?}"))?;
+            Ok(((*boot).clone(), safe_head.number))
+        })
+    })
+    .await
+    .map_err(|e| anyhow!("Replay aborted: {e}"))?
+}
+
+/// Runs the fault proof program natively over the witness exactly as the guest would, optionally
+/// stopping at an earlier L2 block than claimed.
+pub async fn replay_witness(
+    witness: Witness,
+    target_block: Option<u64>,
+) -> anyhow::Result<ReplayOutcome> {
+    spawn_blocking(move || {
+        let oracle = Arc::new(PreloadedOracle::from(witness.oracle_witness));
+        let mut boot =
+            kona_proof::block_on(BootInfo::load(oracle.as_ref())).context("BootInfo::load")?;
+        if let Some(target_block) = target_block {
+            boot.claimed_l2_block_number = target_block;
+        }
+        let boot = Arc::new(boot);
+        let beacon = PreloadedBlobProvider::from(witness.blobs_witness);
+        let (precondition_hash, output_root) = kailua_common::client::run_client(
+            witness.precondition_validation_data_hash,
+            oracle,
+            boot.clone(),
+            beacon,
+        )?;
+        Ok(ReplayOutcome {
+            boot: (*boot).clone(),
+            precondition_hash,
+            output_root,
+        })
+    })
+    .await
+    // The preloaded oracle panics once the witness is exhausted
+    .map_err(|e| anyhow!("Replay aborted: {e}"))?
+}
@@ -0,0 +1,337 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::proof::Proof;
+use alloy_primitives::B256;
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use clap::Parser;
+use risc0_zkvm::sha::Digestible;
+use risc0_zkvm::Journal;
+use std::path::PathBuf;
+use tracing::info;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReceiptStorageType {
+    #[default]
+    File,
+    S3,
+    Gcs,
+}
+
+#[derive(Parser, Clone, Debug, Default)]
+pub struct ReceiptStorageArgs {
+    /// Backend to share computed receipts through
+    #[clap(long, env, value_enum, default_value_t)]
+    pub receipt_storage: ReceiptStorageType,
+    /// Directory to store receipts in when using the file backend
+    #[clap(long, env)]
+    pub receipt_dir: Option<PathBuf>,
+    /// Bucket to store receipts in when using the S3 or GCS backend
+    #[clap(long, env)]
+    pub receipt_bucket: Option<String>,
+    /// Prefix of the object keys of stored receipts
+    #[clap(long, env, default_value = "receipts/")]
+    pub receipt_prefix: String,
+    /// Endpoint of the S3-compatible object storage service
+    #[clap(long, env)]
+    pub receipt_s3_url: Option<String>,
+    /// Region of the S3 bucket
+    #[clap(long, env, default_value = "us-east-1")]
+    pub receipt_s3_region: String,
+    /// Access key of the S3 bucket
+    #[clap(long, env)]
+    pub receipt_s3_access_key: Option<String>,
+    /// Secret key of the S3 bucket
+    #[clap(long, env)]
+    pub receipt_s3_secret_key: Option<String>,
+    /// OAuth2 access token to authenticate to GCS with
+    #[clap(long, env)]
+    pub receipt_gcs_token: Option<String>,
+}
+
+impl ReceiptStorageArgs {
+    pub fn to_arg_vec(&self) -> Vec<String> {
+        let mut args = vec![
+            String::from("--receipt-storage"),
+            format!("{:?}", self.receipt_storage).to_lowercase(),
+            String::from("--receipt-prefix"),
+            self.receipt_prefix.clone(),
+            String::from("--receipt-s3-region"),
+            self.receipt_s3_region.clone(),
+        ];
+        let optional_args = [
+            ("--receipt-bucket", self.receipt_bucket.clone()),
+            ("--receipt-s3-url", self.receipt_s3_url.clone()),
+            (
+                "--receipt-s3-access-key",
+                self.receipt_s3_access_key.clone(),
+            ),
+            (
+                "--receipt-s3-secret-key",
+                self.receipt_s3_secret_key.clone(),
+            ),
+            ("--receipt-gcs-token", self.receipt_gcs_token.clone()),
+            (
+                "--receipt-dir",
+                self.receipt_dir
+                    .as_ref()
+                    .map(|dir| dir.to_str().unwrap().to_string()),
+            ),
+        ];
+        for (flag, value) in optional_args {
+            if let Some(value) = value {
+                args.extend(vec![String::from(flag), value]);
+            }
+        }
+        args
+    }
+
+    /// Returns the configured receipt storage backend, if any
+    pub fn storage(&self) -> anyhow::Result<Option<Box<dyn ReceiptStorage>>> {
+        match self.receipt_storage {
+            ReceiptStorageType::File => Ok(self
+                .receipt_dir
+                .clone()
+                .map(|dir| Box::new(FileReceiptStorage { dir }) as Box<dyn ReceiptStorage>)),
+            #[cfg(feature = "s3")]
+            ReceiptStorageType::S3 => {
+                let bucket = self
+                    .receipt_bucket
+                    .clone()
+                    .context("S3 receipt storage requires a receipt bucket")?;
+                Ok(Some(Box::new(S3ReceiptStorage::new(self, bucket))))
+            }
+            #[cfg(feature = "gcs")]
+            ReceiptStorageType::Gcs => {
+                let bucket = self
+                    .receipt_bucket
+                    .clone()
+                    .context("GCS receipt storage requires a receipt bucket")?;
+                Ok(Some(Box::new(GcsReceiptStorage {
+                    client: alloy::transports::http::reqwest::Client::new(),
+                    bucket,
+                    prefix: self.receipt_prefix.clone(),
+                    token: self.receipt_gcs_token.clone(),
+                })))
+            }
+            #[allow(unreachable_patterns)]
+            backend => bail!("Receipt storage backend {backend:?} requires the matching feature."),
+        }
+    }
+}
+
+/// Returns the content address of a receipt, which is the digest of its journal
+pub fn receipt_key(journal: &Journal) -> B256 {
+    B256::from_slice(journal.digest().as_bytes())
+}
+
+/// Returns the name of the object holding the receipt with the given journal digest
+pub fn receipt_object_name(key: B256) -> String {
+    let suffix = if risc0_zkvm::is_dev_mode() {
+        "fake"
+    } else {
+        "zkp"
+    };
+    format!("{key}.{suffix}")
+}
+
+/// A content-addressed store of serialized proofs
+#[async_trait]
+pub trait ReceiptStorage: Send + Sync {
+    /// Stores the serialized proof under the digest of its journal
+    async fn put(&self, key: B256, data: Vec<u8>) -> anyhow::Result<()>;
+
+    /// Returns the serialized proof with the given journal digest, if stored
+    async fn get(&self, key: B256) -> anyhow::Result<Option<Vec<u8>>>;
+
+    /// Stores the proof under the digest of its journal
+    async fn upload(&self, proof: &Proof) -> anyhow::Result<B256> {
+        let key = receipt_key(proof.journal());
+        self.put(key, bincode::serialize(proof)?).await?;
+        info!("Stored receipt {key}.");
+        Ok(key)
+    }
+
+    /// Returns the serialized proof committing to the given journal, if stored
+    async fn download(&self, journal: &Journal) -> anyhow::Result<Option<Vec<u8>>> {
+        let key = receipt_key(journal);
+        let Some(data) = self.get(key).await? else {
+            return Ok(None);
+        };
+        // Reject corrupted objects instead of submitting them
+        let proof = Proof::decode(&data).context("Failed to decode receipt")?;
+        if receipt_key(proof.journal()) != key {
+            bail!("Stored receipt {key} commits to a different journal.");
+        }
+        Ok(Some(data))
+    }
+}
+
+/// Stores receipts as files in a local or network-mounted directory
+#[derive(Clone, Debug)]
+pub struct FileReceiptStorage {
+    pub dir: PathBuf,
+}
+
+#[async_trait]
+impl ReceiptStorage for FileReceiptStorage {
+    async fn put(&self, key: B256, data: Vec<u8>) -> anyhow::Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.dir.join(receipt_object_name(key));
+        // Write to a temporary file first so that readers never see partial receipts
+        let tmp_path = path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, data).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: B256) -> anyhow::Result<Option<Vec<u8>>> {
+        match tokio::fs::read(self.dir.join(receipt_object_name(key))).await {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Stores receipts in an S3-compatible bucket
+#[cfg(feature = "s3")]
+#[derive(Clone, Debug)]
+pub struct S3ReceiptStorage {
+    pub client: aws_sdk_s3::Client,
+    pub bucket: String,
+    pub prefix: String,
+}
+
+#[cfg(feature = "s3")]
+impl S3ReceiptStorage {
+    pub fn new(args: &ReceiptStorageArgs, bucket: String) -> Self {
+        let mut config = aws_sdk_s3::Config::builder()
+            .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
+            .region(aws_sdk_s3::config::Region::new(
+                args.receipt_s3_region.clone(),
+            ))
+            .force_path_style(true);
+        if let Some(url) = &args.receipt_s3_url {
+            config = config.endpoint_url(url);
+        }
+        if let (Some(access_key), Some(secret_key)) =
+            (&args.receipt_s3_access_key, &args.receipt_s3_secret_key)
+        {
+            config = config.credentials_provider(aws_sdk_s3::config::Credentials::new(
+                access_key, secret_key, None, None, "kailua",
+            ));
+        }
+        Self {
+            client: aws_sdk_s3::Client::from_conf(config.build()),
+            bucket,
+            prefix: args.receipt_prefix.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "s3")]
+#[async_trait]
+impl ReceiptStorage for S3ReceiptStorage {
+    async fn put(&self, key: B256, data: Vec<u8>) -> anyhow::Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(format!("{}{}", self.prefix, receipt_object_name(key)))
+            .body(aws_sdk_s3::primitives::ByteStream::from(data))
+            .send()
+            .await
+            .context("Failed to upload receipt to S3")?;
+        Ok(())
+    }
+
+    async fn get(&self, key: B256) -> anyhow::Result<Option<Vec<u8>>> {
+        let result = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(format!("{}{}", self.prefix, receipt_object_name(key)))
+            .send()
+            .await;
+        match result {
+            Ok(output) => Ok(Some(output.body.collect().await?.into_bytes().to_vec())),
+            Err(err) if err.as_service_error().is_some_and(|e| e.is_no_such_key()) => Ok(None),
+            Err(err) => Err(anyhow::Error::new(err).context("Failed to download receipt from S3")),
+        }
+    }
+}
+
+/// Stores receipts in a GCS bucket through its JSON api
+#[cfg(feature = "gcs")]
+#[derive(Clone, Debug)]
+pub struct GcsReceiptStorage {
+    pub client: alloy::transports::http::reqwest::Client,
+    pub bucket: String,
+    pub prefix: String,
+    pub token: Option<String>,
+}
+
+#[cfg(feature = "gcs")]
+impl GcsReceiptStorage {
+    fn object_name(&self, key: B256) -> String {
+        format!("{}{}", self.prefix, receipt_object_name(key)).replace('/', "%2F")
+    }
+
+    fn authorize(
+        &self,
+        request: alloy::transports::http::reqwest::RequestBuilder,
+    ) -> alloy::transports::http::reqwest::RequestBuilder {
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+#[cfg(feature = "gcs")]
+#[async_trait]
+impl ReceiptStorage for GcsReceiptStorage {
+    async fn put(&self, key: B256, data: Vec<u8>) -> anyhow::Result<()> {
+        let url = format!(
+            "https://storage.googleapis.com/upload/storage/v1/b/{}/o?uploadType=media&name={}",
+            self.bucket,
+            self.object_name(key)
+        );
+        self.authorize(self.client.post(url))
+            .body(data)
+            .send()
+            .await
+            .context("Failed to upload receipt to GCS")?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn get(&self, key: B256) -> anyhow::Result<Option<Vec<u8>>> {
+        let url = format!(
+            "https://storage.googleapis.com/storage/v1/b/{}/o/{}?alt=media",
+            self.bucket,
+            self.object_name(key)
+        );
+        let response = self
+            .authorize(self.client.get(url))
+            .send()
+            .await
+            .context("Failed to download receipt from GCS")?;
+        if response.status() == alloy::transports::http::reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.bytes().await?.to_vec()))
+    }
+}
@@ -16,13 +16,14 @@ use alloy::consensus::Blob;
 use alloy::eips::eip4844::IndexedBlobHash;
 use async_trait::async_trait;
 use kailua_common::blobs::BlobWitnessData;
-use kailua_common::oracle::OracleWitnessData;
+use kailua_common::oracle::{witness_lru, OracleWitnessData};
 use kona_derive::prelude::BlobProvider;
 use kona_preimage::errors::PreimageOracleResult;
 use kona_preimage::{
     CommsClient, HintWriterClient, PreimageKey, PreimageKeyType, PreimageOracleClient,
 };
 use kona_proof::FlushableCache;
+use lru::LruCache;
 use op_alloy_protocol::BlockInfo;
 use std::fmt::Debug;
 use std::sync::{Arc, Mutex};
@@ -68,16 +69,32 @@ impl<T: BlobProvider + Send> BlobProvider for BlobWitnessProvider<T> {
 pub struct OracleWitnessProvider<P: CommsClient + FlushableCache + Send + Sync + Debug + Clone> {
     pub oracle: P,
     pub witness: Arc<Mutex<OracleWitnessData>>,
+    /// Mirrors the guest's cache of resolved preimages to avoid recording repeated accesses
+    pub cached_keys: Arc<Mutex<LruCache<PreimageKey, ()>>>,
 }
 
 impl<P> OracleWitnessProvider<P>
 where
     P: CommsClient + FlushableCache + Send + Sync + Debug + Clone,
 {
+    pub fn new(oracle: P, witness: Arc<Mutex<OracleWitnessData>>) -> Self {
+        Self {
+            oracle,
+            witness,
+            cached_keys: Arc::new(Mutex::new(witness_lru())),
+        }
+    }
+
     pub fn save(&self, key: PreimageKey, value: &[u8]) {
         if matches!(key.key_type(), PreimageKeyType::Blob) {
             return;
         }
+        // The guest serves this access from its cache
+        let mut cached_keys = self.cached_keys.lock().unwrap();
+        if cached_keys.get(&key).is_some() {
+            return;
+        }
+        cached_keys.put(key, ());
         let mut witness = self.witness.lock().unwrap();
         witness.keys.push(key);
         witness.data.push(value.to_vec());
@@ -121,3 +138,79 @@ where
         self.oracle.flush();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::keccak256;
+    use kailua_common::oracle::{PreloadedOracle, WITNESS_LRU_SIZE};
+    use kona_preimage::errors::PreimageOracleError;
+    use std::collections::HashMap;
+
+    /// Serves keccak preimages from memory
+    #[derive(Clone, Debug, Default)]
+    struct MemoryOracle(Arc<HashMap<PreimageKey, Vec<u8>>>);
+
+    #[async_trait]
+    impl PreimageOracleClient for MemoryOracle {
+        async fn get(&self, key: PreimageKey) -> PreimageOracleResult<Vec<u8>> {
+            self.0
+                .get(&key)
+                .cloned()
+                .ok_or(PreimageOracleError::KeyNotFound)
+        }
+
+        async fn get_exact(&self, key: PreimageKey, buf: &mut [u8]) -> PreimageOracleResult<()> {
+            buf.copy_from_slice(&self.get(key).await?);
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl HintWriterClient for MemoryOracle {
+        async fn write(&self, _hint: &str) -> PreimageOracleResult<()> {
+            Ok(())
+        }
+    }
+
+    impl FlushableCache for MemoryOracle {
+        fn flush(&self) {}
+    }
+
+    fn preimage(i: usize) -> (PreimageKey, Vec<u8>) {
+        let value = i.to_be_bytes().to_vec();
+        let key = PreimageKey::new(keccak256(&value).0, PreimageKeyType::Keccak256);
+        (key, value)
+    }
+
+    #[tokio::test]
+    async fn recorded_witness_replays_past_cache_evictions() {
+        let count = WITNESS_LRU_SIZE + 16;
+        let preimages: HashMap<_, _> = (0..count).map(preimage).collect();
+        // access every key, then a cached key, then keys evicted from the cache
+        let accesses: Vec<_> = (0..count)
+            .chain([count - 1, count - 1, 0, 1, 0])
+            .map(|i| preimage(i).0)
+            .collect();
+
+        let witness = Arc::new(Mutex::new(OracleWitnessData::default()));
+        let recorder =
+            OracleWitnessProvider::new(MemoryOracle(Arc::new(preimages.clone())), witness.clone());
+        for key in &accesses {
+            recorder.get(*key).await.unwrap();
+        }
+        let witness = witness.lock().unwrap().clone();
+        // repeated accesses are only recorded once evicted
+        assert_eq!(witness.keys.len(), count + 2);
+
+        let replay = PreloadedOracle::from(witness);
+        for key in &accesses {
+            assert_eq!(&replay.get(*key).await.unwrap(), &preimages[key]);
+        }
+        // a key missing from the exhausted witness is an error
+        assert!(matches!(
+            replay.get(preimage(count).0).await,
+            Err(PreimageOracleError::KeyNotFound)
+        ));
+    }
+}
@@ -0,0 +1,33 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Error messages with which execution clients reject requests for state they have pruned
+pub const MISSING_STATE_ERRORS: [&str; 6] = [
+    "missing trie node",
+    "historical state",
+    "state not available",
+    "is pruned",
+    "required state",
+    "header not found",
+];
+
+/// Returns whether the error was caused by the queried node no longer holding the state
+pub fn is_missing_state_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        let message = cause.to_string().to_lowercase();
+        MISSING_STATE_ERRORS
+            .iter()
+            .any(|pattern| message.contains(pattern))
+    })
+}
@@ -0,0 +1,446 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::primitives::B256;
+use alloy::providers::{Provider, ProviderBuilder, ReqwestProvider};
+use alloy_eips::eip4844::kzg_to_versioned_hash;
+use alloy_rpc_types_beacon::sidecar::BlobData;
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+
+/// Number of attempts made for each beacon API request before giving up
+pub const BEACON_REQUEST_ATTEMPTS: u32 = 4;
+
+/// The beacon node implementation behind an endpoint, used to work around its API quirks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BeaconFlavor {
+    Lighthouse,
+    Prysm,
+    Nimbus,
+    Teku,
+    Lodestar,
+    /// A hosted endpoint (e.g. Infura) which rejects large responses
+    Hosted,
+    Unknown,
+}
+
+impl BeaconFlavor {
+    pub fn detect(url: &str, version: &str) -> Self {
+        let version = version.to_lowercase();
+        if url.contains("infura.io") || url.contains("quiknode") || url.contains("alchemy.com") {
+            Self::Hosted
+        } else if version.starts_with("lighthouse") {
+            Self::Lighthouse
+        } else if version.starts_with("prysm") {
+            Self::Prysm
+        } else if version.starts_with("nimbus") {
+            Self::Nimbus
+        } else if version.starts_with("teku") {
+            Self::Teku
+        } else if version.starts_with("lodestar") {
+            Self::Lodestar
+        } else {
+            Self::Unknown
+        }
+    }
+
+    /// Whether sidecars should be requested one index at a time
+    pub fn prefers_indexed_requests(&self) -> bool {
+        matches!(self, Self::Hosted)
+    }
+}
+
+/// The consensus fork a slot belongs to, as far as blob retrieval is concerned
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BlobFork {
+    PreDeneb,
+    Deneb,
+    Electra,
+}
+
+/// A cache of the beacon slots that L1 blocks were found at, keyed by block timestamp
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SlotCache {
+    pub genesis_time: u64,
+    pub seconds_per_slot: u64,
+    pub slots: BTreeMap<u64, u64>,
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl SlotCache {
+    /// Loads the cache at `path`, discarding it if it was built for a different beacon chain
+    pub fn load(path: &Path, genesis_time: u64, seconds_per_slot: u64) -> anyhow::Result<Self> {
+        let cached = std::fs::read(path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<SlotCache>(&data).ok())
+            .filter(|cache| {
+                cache.genesis_time == genesis_time && cache.seconds_per_slot == seconds_per_slot
+            });
+        if cached.is_none() && path.exists() {
+            warn!("Discarding slot cache at {path:?} built for a different beacon chain.");
+        }
+        let mut cache = cached.unwrap_or(SlotCache {
+            genesis_time,
+            seconds_per_slot,
+            ..Default::default()
+        });
+        cache.path = Some(path.to_path_buf());
+        Ok(cache)
+    }
+
+    pub fn get(&self, timestamp: u64) -> Option<u64> {
+        self.slots.get(&timestamp).copied()
+    }
+
+    pub fn insert(&mut self, timestamp: u64, slot: u64) -> anyhow::Result<()> {
+        self.slots.insert(timestamp, slot);
+        if let Some(path) = &self.path {
+            std::fs::write(path, serde_json::to_vec(self)?)
+                .context(format!("Failed to write slot cache to {path:?}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// A beacon API client for fetching blob sidecars across consensus forks and node
+/// implementations.
+#[derive(Clone, Debug)]
+pub struct BeaconClient {
+    pub provider: ReqwestProvider,
+    pub flavor: BeaconFlavor,
+    pub genesis_time: u64,
+    pub seconds_per_slot: u64,
+    pub slots_per_epoch: u64,
+    pub deneb_fork_epoch: u64,
+    pub electra_fork_epoch: u64,
+    pub max_blobs_per_block: u64,
+    pub max_blobs_per_block_electra: u64,
+    pub slot_cache: Arc<Mutex<SlotCache>>,
+}
+
+/// Reads a spec value that nodes may encode as either a decimal string or a number
+pub fn parse_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        Value::Number(n) => n.as_u64(),
+        _ => None,
+    }
+}
+
+impl BeaconClient {
+    pub async fn new(url: &str) -> anyhow::Result<Self> {
+        let provider = ProviderBuilder::new().on_http(url.try_into()?);
+        let genesis = Self::provider_get(&provider, "eth/v1/beacon/genesis")
+            .await?
+            .context("beacon genesis not found")?;
+        debug!("genesis {:?}", &genesis);
+        let genesis_time = parse_u64(&genesis["data"]["genesis_time"]).context("genesis_time")?;
+        let spec = Self::provider_get(&provider, "eth/v1/config/spec")
+            .await?
+            .context("beacon spec not found")?;
+        debug!("spec {:?}", &spec);
+        let spec = &spec["data"];
+        let seconds_per_slot = parse_u64(&spec["SECONDS_PER_SLOT"]).context("SECONDS_PER_SLOT")?;
+        let slots_per_epoch = parse_u64(&spec["SLOTS_PER_EPOCH"]).unwrap_or(32);
+        // Unscheduled forks are either omitted or set to the maximum epoch
+        let deneb_fork_epoch = parse_u64(&spec["DENEB_FORK_EPOCH"]).unwrap_or(u64::MAX);
+        let electra_fork_epoch = parse_u64(&spec["ELECTRA_FORK_EPOCH"]).unwrap_or(u64::MAX);
+        let max_blobs_per_block = parse_u64(&spec["MAX_BLOBS_PER_BLOCK"]).unwrap_or(6);
+        let max_blobs_per_block_electra =
+            parse_u64(&spec["MAX_BLOBS_PER_BLOCK_ELECTRA"]).unwrap_or(9);
+        let version = Self::provider_get(&provider, "eth/v1/node/version")
+            .await
+            .ok()
+            .flatten()
+            .and_then(|v| v["data"]["version"].as_str().map(String::from))
+            .unwrap_or_default();
+        let flavor = BeaconFlavor::detect(url, &version);
+        info!("Connected to {flavor:?} beacon node ({version}).");
+        Ok(Self {
+            provider,
+            flavor,
+            genesis_time,
+            seconds_per_slot,
+            slots_per_epoch,
+            deneb_fork_epoch,
+            electra_fork_epoch,
+            max_blobs_per_block,
+            max_blobs_per_block_electra,
+            slot_cache: Arc::new(Mutex::new(SlotCache {
+                genesis_time,
+                seconds_per_slot,
+                ..Default::default()
+            })),
+        })
+    }
+
+    /// Persists the slots found for L1 blocks to the file at `path`
+    pub fn with_slot_cache(self, path: &Path) -> anyhow::Result<Self> {
+        let cache = SlotCache::load(path, self.genesis_time, self.seconds_per_slot)?;
+        *self.slot_cache.lock().unwrap() = cache;
+        Ok(self)
+    }
+
+    pub fn provider_url(provider: &ReqwestProvider) -> &str {
+        provider.client().transport().url().trim_end_matches('/')
+    }
+
+    pub fn url(&self) -> &str {
+        Self::provider_url(&self.provider)
+    }
+
+    /// Returns the response body of the given beacon API path, or `None` if it was not found.
+    /// Transient failures are retried with exponential backoff.
+    pub async fn provider_get(
+        provider: &ReqwestProvider,
+        path: &str,
+    ) -> anyhow::Result<Option<Value>> {
+        let url = format!("{}/{}", Self::provider_url(provider), path);
+        let mut backoff = Duration::from_millis(500);
+        let mut attempt = 1;
+        loop {
+            let result = provider
+                .client()
+                .transport()
+                .client()
+                .get(&url)
+                .header("Accept", "application/json")
+                .send()
+                .await;
+            let retry_reason = match result {
+                Ok(response) => {
+                    let status = response.status().as_u16();
+                    match status {
+                        200..=299 => {
+                            return Ok(Some(response.json::<Value>().await.context("json")?))
+                        }
+                        404 => return Ok(None),
+                        429 | 500..=599 => format!("status {status}"),
+                        _ => bail!("GET {path} failed with status {status}"),
+                    }
+                }
+                Err(err) => err.to_string(),
+            };
+            if attempt >= BEACON_REQUEST_ATTEMPTS {
+                bail!("GET {path} failed after {attempt} attempts: {retry_reason}");
+            }
+            warn!("GET {path} failed ({retry_reason}). Retrying in {backoff:?}.");
+            sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+
+    pub async fn get(&self, path: &str) -> anyhow::Result<Option<Value>> {
+        Self::provider_get(&self.provider, path).await
+    }
+
+    pub fn slot(&self, timestamp: u64) -> anyhow::Result<u64> {
+        let Some(elapsed) = timestamp.checked_sub(self.genesis_time) else {
+            bail!(
+                "Timestamp {timestamp} precedes beacon genesis at {}",
+                self.genesis_time
+            );
+        };
+        if elapsed % self.seconds_per_slot != 0 {
+            warn!("Timestamp {timestamp} is not aligned to a beacon slot.");
+        }
+        Ok(elapsed / self.seconds_per_slot)
+    }
+
+    /// Returns the execution payload timestamp of the block at the given slot, or `None` if the
+    /// slot was missed.
+    pub async fn payload_timestamp(&self, slot: u64) -> anyhow::Result<Option<u64>> {
+        let Some(block) = self.get(&format!("eth/v2/beacon/blocks/{slot}")).await? else {
+            return Ok(None);
+        };
+        let timestamp =
+            parse_u64(&block["data"]["message"]["body"]["execution_payload"]["timestamp"])
+                .context(format!("Block at slot {slot} has no execution payload"))?;
+        Ok(Some(timestamp))
+    }
+
+    /// Returns the first slot in `[from, to]` that was not missed, along with its timestamp
+    async fn next_block(&self, from: u64, to: u64) -> anyhow::Result<Option<(u64, u64)>> {
+        for slot in from..=to {
+            if let Some(timestamp) = self.payload_timestamp(slot).await? {
+                return Ok(Some((slot, timestamp)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Locates the slot of the L1 block with the given timestamp, consulting the slot cache first
+    /// and searching the beacon chain if the slot derived from the genesis configuration does not
+    /// hold the block.
+    pub async fn slot_for_timestamp(&self, timestamp: u64) -> anyhow::Result<u64> {
+        if let Some(slot) = self.slot_cache.lock().unwrap().get(timestamp) {
+            return Ok(slot);
+        }
+        let estimate = self.slot(timestamp)?;
+        let slot = if self.payload_timestamp(estimate).await? == Some(timestamp) {
+            estimate
+        } else {
+            warn!("Block @ {timestamp} not found at slot {estimate}. Searching beacon chain.");
+            let mut lo = 0;
+            let mut hi = estimate.saturating_mul(2).max(1);
+            let mut found = None;
+            while lo <= hi {
+                let mid = lo + (hi - lo) / 2;
+                let Some((slot, slot_timestamp)) = self.next_block(mid, hi).await? else {
+                    // All slots in the upper half were missed
+                    if mid == 0 {
+                        break;
+                    }
+                    hi = mid - 1;
+                    continue;
+                };
+                match slot_timestamp.cmp(&timestamp) {
+                    std::cmp::Ordering::Equal => {
+                        found = Some(slot);
+                        break;
+                    }
+                    std::cmp::Ordering::Less => lo = slot + 1,
+                    std::cmp::Ordering::Greater => {
+                        if mid == 0 {
+                            break;
+                        }
+                        hi = mid - 1;
+                    }
+                }
+            }
+            found.context(format!("No beacon block found for L1 block @ {timestamp}"))?
+        };
+        self.slot_cache.lock().unwrap().insert(timestamp, slot)?;
+        Ok(slot)
+    }
+
+    pub fn fork_at(&self, slot: u64) -> BlobFork {
+        let epoch = slot / self.slots_per_epoch;
+        if epoch >= self.electra_fork_epoch {
+            BlobFork::Electra
+        } else if epoch >= self.deneb_fork_epoch {
+            BlobFork::Deneb
+        } else {
+            BlobFork::PreDeneb
+        }
+    }
+
+    pub fn max_blobs_at(&self, slot: u64) -> u64 {
+        match self.fork_at(slot) {
+            BlobFork::PreDeneb => 0,
+            BlobFork::Deneb => self.max_blobs_per_block,
+            BlobFork::Electra => self.max_blobs_per_block_electra,
+        }
+    }
+
+    /// Parses a sidecar, normalizing the encodings used by different node implementations
+    pub fn parse_sidecar(mut sidecar: Value) -> anyhow::Result<BlobData> {
+        // Some nodes encode the index as a number instead of a decimal string
+        if let Some(index) = sidecar["index"].as_u64() {
+            sidecar["index"] = Value::String(index.to_string());
+        }
+        if sidecar.get("kzg_commitment_inclusion_proof").is_none() {
+            sidecar["kzg_commitment_inclusion_proof"] = Value::Array(vec![]);
+        }
+        serde_json::from_value(sidecar).context("Failed to parse blob sidecar")
+    }
+
+    fn parse_sidecars(response: Value) -> anyhow::Result<Vec<BlobData>> {
+        if let Some(version) = response["version"].as_str() {
+            debug!("blob_sidecars version {version}");
+        }
+        let data = match response {
+            Value::Object(mut map) => map.remove("data").unwrap_or(Value::Array(vec![])),
+            // Some proxies return the bare list
+            array @ Value::Array(_) => array,
+            _ => bail!("Unexpected blob_sidecars response"),
+        };
+        let Value::Array(sidecars) = data else {
+            bail!("Unexpected blob_sidecars data");
+        };
+        sidecars.into_iter().map(Self::parse_sidecar).collect()
+    }
+
+    /// Fetches all blob sidecars of the block at the given slot, returning `None` for missed
+    /// slots.
+    pub async fn get_blob_sidecars(&self, slot: u64) -> anyhow::Result<Option<Vec<BlobData>>> {
+        if self.fork_at(slot) == BlobFork::PreDeneb {
+            bail!("Slot {slot} precedes the Deneb fork and carries no blobs.");
+        }
+        if !self.flavor.prefers_indexed_requests() {
+            match self
+                .get(&format!("eth/v1/beacon/blob_sidecars/{slot}"))
+                .await
+            {
+                Ok(None) => return Ok(None),
+                Ok(Some(response)) => return Self::parse_sidecars(response).map(Some),
+                Err(err) => {
+                    warn!("Failed to fetch all blob sidecars at slot {slot} ({err:?}). Falling back to indexed requests.");
+                }
+            }
+        }
+        // Request each sidecar individually to stay under response size limits
+        let mut sidecars = Vec::new();
+        for index in 0..self.max_blobs_at(slot) {
+            let Some(response) = self
+                .get(&format!(
+                    "eth/v1/beacon/blob_sidecars/{slot}?indices={index}"
+                ))
+                .await?
+            else {
+                if index == 0 {
+                    return Ok(None);
+                }
+                break;
+            };
+            let mut batch = Self::parse_sidecars(response)?;
+            if batch.is_empty() {
+                break;
+            }
+            sidecars.append(&mut batch);
+        }
+        Ok(Some(sidecars))
+    }
+
+    /// Fetches the blob with the given versioned hash from the block at the given timestamp
+    pub async fn get_blob(&self, timestamp: u64, blob_hash: B256) -> anyhow::Result<BlobData> {
+        let slot = self.slot_for_timestamp(timestamp).await?;
+        let Some(blobs) = self
+            .get_blob_sidecars(slot)
+            .await
+            .context(format!("blob_sidecars {slot}"))?
+        else {
+            bail!("Blob {blob_hash} @ {timestamp} not found: slot {slot} was missed or pruned.");
+        };
+
+        let blob_count = blobs.len();
+        for blob in blobs {
+            let versioned_hash = kzg_to_versioned_hash(blob.kzg_commitment.as_slice());
+            if versioned_hash == blob_hash {
+                return Ok(blob);
+            }
+        }
+
+        bail!("Blob {blob_hash} @ {timestamp} not found in slot ({blob_count} blobs found)!");
+    }
+}
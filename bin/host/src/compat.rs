@@ -0,0 +1,236 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::witness::{execution_witness_preflight, is_unsupported_method_error};
+use crate::{zeth_execution_preflight, KailuaHostCli};
+use alloy::network::primitives::BlockTransactionsKind;
+use alloy::primitives::{address, keccak256, Address, Bytes, B256, U256};
+use alloy::providers::{Provider, ProviderBuilder, ReqwestProvider};
+use anyhow::Context;
+use kona_host::kv::SharedKeyValueStore;
+use kona_preimage::{PreimageKey, PreimageKeyType};
+use op_alloy_genesis::RollupConfig;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::{info, warn};
+
+/// The EIP-4788 beacon roots contract, which is called by the system before any transaction
+pub const BEACON_ROOTS_ADDRESS: Address = address!("000F3df6D732807Ef1319fB7B8bB8522d0Beac02");
+
+/// The length of the EIP-4788 beacon roots ring buffer
+pub const BEACON_ROOTS_HISTORY: u64 = 8191;
+
+/// The OP Stack fee vaults credited outside of transaction execution traces
+pub const FEE_VAULT_ADDRESSES: [Address; 3] = [
+    address!("4200000000000000000000000000000000000011"),
+    address!("4200000000000000000000000000000000000019"),
+    address!("420000000000000000000000000000000000001A"),
+];
+
+/// The rpc methods used for preflight that the L2 node was found to implement
+#[derive(Clone, Debug, Default)]
+pub struct NodeCapabilities {
+    pub client_version: String,
+    pub execution_witness: bool,
+    pub db_get: bool,
+    pub trace_prestate: bool,
+    pub get_proof: bool,
+}
+
+impl NodeCapabilities {
+    /// Probes the L2 node with cheap calls to each of the methods used for preflight
+    pub async fn probe(l2_node_address: &str) -> anyhow::Result<Self> {
+        let provider = ProviderBuilder::new().on_http(l2_node_address.try_into()?);
+        let client_version = provider
+            .get_client_version()
+            .await
+            .unwrap_or_else(|_| String::from("unknown"));
+        let capabilities = Self {
+            execution_witness: supports(&provider, "debug_executionWitness", json!(["0x0"])).await,
+            db_get: supports(&provider, "debug_dbGet", json!(["0x00"])).await,
+            trace_prestate: supports(
+                &provider,
+                "debug_traceBlockByNumber",
+                json!(["0x0", {"tracer": "prestateTracer"}]),
+            )
+            .await,
+            get_proof: supports(
+                &provider,
+                "eth_getProof",
+                json!([Address::ZERO, [], "latest"]),
+            )
+            .await,
+            client_version,
+        };
+        info!("L2 node capabilities: {capabilities:?}");
+        Ok(capabilities)
+    }
+
+    /// Returns the most efficient preflight strategy the node supports
+    pub fn preflight_strategy(&self) -> PreflightStrategy {
+        if self.execution_witness {
+            PreflightStrategy::ExecutionWitness
+        } else if self.db_get {
+            PreflightStrategy::Zeth
+        } else if self.trace_prestate && self.get_proof {
+            PreflightStrategy::GetProof
+        } else {
+            PreflightStrategy::None
+        }
+    }
+}
+
+/// Returns whether the node implements the method, regardless of whether the call succeeded
+async fn supports(provider: &ReqwestProvider, method: &'static str, params: Value) -> bool {
+    match provider
+        .raw_request::<_, Value>(method.into(), params)
+        .await
+    {
+        Ok(_) => true,
+        Err(err) => !is_unsupported_method_error(&err.into()),
+    }
+}
+
+/// The ways in which the state accessed by the proven blocks can be fetched ahead of proving
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreflightStrategy {
+    /// Fetch each block's complete witness through `debug_executionWitness`
+    ExecutionWitness,
+    /// Enumerate trie preimages through the geth-specific `debug` namespace
+    Zeth,
+    /// Trace the accessed accounts and fetch their `eth_getProof` merkle proofs
+    GetProof,
+    /// Leave all fetching to the kona fetcher during proving
+    None,
+}
+
+/// Fetches the state accessed by the proven blocks using the best strategy the L2 node supports
+pub async fn preflight(cfg: &KailuaHostCli, rollup_config: RollupConfig) -> anyhow::Result<()> {
+    let Some(l2_node_address) = &cfg.kona.l2_node_address else {
+        return Ok(());
+    };
+    let capabilities = NodeCapabilities::probe(l2_node_address).await?;
+    let strategy = capabilities.preflight_strategy();
+    info!(
+        "Preflighting with {strategy:?} strategy against {}.",
+        capabilities.client_version
+    );
+    match strategy {
+        PreflightStrategy::ExecutionWitness => {
+            if !execution_witness_preflight(cfg).await? {
+                zeth_execution_preflight(cfg, rollup_config).await?;
+            }
+        }
+        PreflightStrategy::Zeth => zeth_execution_preflight(cfg, rollup_config).await?,
+        PreflightStrategy::GetProof => get_proof_preflight(cfg, l2_node_address).await?,
+        PreflightStrategy::None => {
+            warn!("L2 node supports no preflight strategy. Proving will fetch all state lazily.")
+        }
+    }
+    Ok(())
+}
+
+/// Fetches the merkle proofs of every account and storage slot accessed by the proven blocks
+/// before and after their execution.
+pub async fn get_proof_preflight(cfg: &KailuaHostCli, l2_node_address: &str) -> anyhow::Result<()> {
+    let provider = ProviderBuilder::new().on_http(l2_node_address.try_into()?);
+    let kv_store = cfg.kona.construct_kv_store();
+    let preflight_start = cfg.kona.claimed_l2_block_number - cfg.block_count + 1;
+    let mut preimage_count = 0;
+    for block_number in preflight_start..=cfg.kona.claimed_l2_block_number {
+        let block = provider
+            .get_block_by_number(block_number.into(), BlockTransactionsKind::Hashes)
+            .await
+            .context("get_block_by_number")?
+            .context(format!("Block {block_number} not found"))?;
+        // The prestate of each transaction lists the accounts and slots it accessed
+        let traces: Vec<Value> = provider
+            .raw_request(
+                "debug_traceBlockByNumber".into(),
+                json!([format!("0x{block_number:x}"), {"tracer": "prestateTracer"}]),
+            )
+            .await
+            .context("debug_traceBlockByNumber")?;
+        let mut accessed = BTreeMap::<Address, BTreeSet<B256>>::new();
+        let mut codes = Vec::new();
+        for trace in &traces {
+            let prestate = trace.get("result").unwrap_or(trace);
+            for (account, state) in prestate.as_object().into_iter().flatten() {
+                let slots = accessed.entry(account.parse()?).or_default();
+                for slot in state["storage"]
+                    .as_object()
+                    .into_iter()
+                    .flat_map(|s| s.keys())
+                {
+                    slots.insert(slot.parse()?);
+                }
+                if let Some(code) = state["code"].as_str() {
+                    codes.push(code.parse::<Bytes>()?);
+                }
+            }
+        }
+        // Include the accounts updated by the system outside of any transaction
+        let timestamp_index = block.header.timestamp % BEACON_ROOTS_HISTORY;
+        accessed.entry(BEACON_ROOTS_ADDRESS).or_default().extend([
+            B256::from(U256::from(timestamp_index)),
+            B256::from(U256::from(timestamp_index + BEACON_ROOTS_HISTORY)),
+        ]);
+        accessed.entry(block.header.beneficiary).or_default();
+        for fee_vault in FEE_VAULT_ADDRESSES {
+            accessed.entry(fee_vault).or_default();
+        }
+        // Proofs against the parent state serve reads, and those against the block's own state
+        // serve the trie nodes touched by writes
+        for (account, slots) in &accessed {
+            let slots = slots.iter().copied().collect::<Vec<_>>();
+            for proof_block in [block_number - 1, block_number] {
+                let proof = provider
+                    .get_proof(*account, slots.clone())
+                    .number(proof_block)
+                    .await
+                    .context("eth_getProof")?;
+                let nodes = proof.account_proof.into_iter().chain(
+                    proof
+                        .storage_proof
+                        .into_iter()
+                        .flat_map(|storage_proof| storage_proof.proof),
+                );
+                preimage_count += store_preimages(&kv_store, nodes).await?;
+            }
+        }
+        preimage_count += store_preimages(&kv_store, codes).await?;
+    }
+    info!(
+        "Preflighted {preimage_count} preimages of {} blocks from merkle proofs.",
+        cfg.block_count
+    );
+    Ok(())
+}
+
+/// Writes the preimages to the kv-store keyed by their keccak256 hashes
+async fn store_preimages(
+    kv_store: &SharedKeyValueStore,
+    preimages: impl IntoIterator<Item = Bytes>,
+) -> anyhow::Result<usize> {
+    let mut store = kv_store.write().await;
+    let mut count = 0;
+    for preimage in preimages {
+        store.set(
+            PreimageKey::new(*keccak256(&preimage), PreimageKeyType::Keccak256).into(),
+            preimage.to_vec(),
+        )?;
+        count += 1;
+    }
+    Ok(count)
+}
@@ -0,0 +1,131 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use async_trait::async_trait;
+use kailua_common::precondition::PreconditionValidationData;
+use kona_host::kv::{
+    KeyValueStore, LocalKeyValueStore, MemoryKeyValueStore, SharedKeyValueStore, SplitKeyValueStore,
+};
+use kona_host::HostCli;
+use kona_preimage::errors::PreimageOracleResult;
+use kona_preimage::{PreimageKey, PreimageOracleClient};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::ops::DerefMut;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+use tracing::info;
+
+/// A recorded snapshot of all the L1/L2/beacon data served to the client during a proving run.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ChainFixture {
+    /// The blob equivalence precondition data used in the recorded run (if any)
+    pub precondition_validation_data: Option<PreconditionValidationData>,
+    /// Every distinct preimage served to the client
+    pub preimages: BTreeMap<PreimageKey, Vec<u8>>,
+}
+
+impl ChainFixture {
+    pub async fn load(path: &Path) -> anyhow::Result<Self> {
+        let data = tokio::fs::read(path)
+            .await
+            .context(format!("Failed to read chain fixture {}", path.display()))?;
+        let fixture: Self = bincode::deserialize(&data).context("bincode::deserialize")?;
+        info!(
+            "Loaded chain fixture with {} preimages from {}.",
+            fixture.preimages.len(),
+            path.display()
+        );
+        Ok(fixture)
+    }
+
+    pub async fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let data = bincode::serialize(self).context("bincode::serialize")?;
+        tokio::fs::write(path, data)
+            .await
+            .context(format!("Failed to write chain fixture {}", path.display()))?;
+        info!(
+            "Recorded chain fixture with {} preimages to {}.",
+            self.preimages.len(),
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// Builds an in-memory key-value store that serves the recorded chain data, along with the
+    /// boot information of the given configuration instead of the recorded one.
+    pub fn kv_store(&self, cfg: &HostCli) -> anyhow::Result<SharedKeyValueStore> {
+        let mut store = MemoryKeyValueStore::new();
+        for (key, value) in &self.preimages {
+            store.set((*key).into(), value.clone())?;
+        }
+        let local_kv_store = LocalKeyValueStore::new(cfg.clone());
+        Ok(Arc::new(RwLock::new(SplitKeyValueStore::new(
+            local_kv_store,
+            store,
+        ))))
+    }
+}
+
+/// Wraps a preimage oracle to capture every preimage it serves for fixture recording.
+#[derive(Clone, Debug)]
+pub struct RecordingOracle<P: PreimageOracleClient + Send + Sync + Debug + Clone> {
+    pub oracle: P,
+    pub preimages: Arc<Mutex<BTreeMap<PreimageKey, Vec<u8>>>>,
+}
+
+impl<P> RecordingOracle<P>
+where
+    P: PreimageOracleClient + Send + Sync + Debug + Clone,
+{
+    pub fn new(oracle: P) -> Self {
+        Self {
+            oracle,
+            preimages: Default::default(),
+        }
+    }
+
+    pub fn record(&self, key: PreimageKey, value: &[u8]) {
+        self.preimages
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| value.to_vec());
+    }
+
+    pub fn take_preimages(&self) -> BTreeMap<PreimageKey, Vec<u8>> {
+        core::mem::take(self.preimages.lock().unwrap().deref_mut())
+    }
+}
+
+#[async_trait]
+impl<P> PreimageOracleClient for RecordingOracle<P>
+where
+    P: PreimageOracleClient + Send + Sync + Debug + Clone,
+{
+    async fn get(&self, key: PreimageKey) -> PreimageOracleResult<Vec<u8>> {
+        let value = self.oracle.get(key).await?;
+        self.record(key, &value);
+        Ok(value)
+    }
+
+    async fn get_exact(&self, key: PreimageKey, buf: &mut [u8]) -> PreimageOracleResult<()> {
+        self.oracle.get_exact(key, buf).await?;
+        self.record(key, buf);
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{bail, Context};
+use kailua_common::hardforks::enabled_hardforks;
+use serde_json::Value;
+
+/// Returns the hardforks scheduled in the rollup configuration that the bundled version of kona
+/// does not support, or that the client was built without, along with their activation timestamps.
+pub fn unsupported_hardforks(rollup_config: &Value) -> Vec<(String, u64)> {
+    let Some(fields) = rollup_config.as_object() else {
+        return vec![];
+    };
+    let supported_hardforks = enabled_hardforks();
+    fields
+        .iter()
+        .filter_map(|(key, value)| {
+            let hardfork = key.strip_suffix("_time")?;
+            // The block time is the only timing parameter that is not an activation time
+            if hardfork == "block" || supported_hardforks.contains(&hardfork) {
+                return None;
+            }
+            Some((hardfork.to_string(), value.as_u64()?))
+        })
+        .collect()
+}
+
+/// Computes the timestamp of the given L2 block from the rollup's genesis
+pub fn l2_block_timestamp(rollup_config: &Value, block_number: u64) -> anyhow::Result<u64> {
+    let genesis = &rollup_config["genesis"];
+    let genesis_time = genesis["l2_time"]
+        .as_u64()
+        .context("rollup config missing genesis l2_time")?;
+    let genesis_number = genesis["l2"]["number"]
+        .as_u64()
+        .context("rollup config missing genesis l2 number")?;
+    let block_time = rollup_config["block_time"]
+        .as_u64()
+        .context("rollup config missing block_time")?;
+    Ok(genesis_time + block_number.saturating_sub(genesis_number) * block_time)
+}
+
+/// Fails if any hardfork unsupported by the bundled version of kona is active at the given block
+pub fn check_hardfork_support(rollup_config: &Value, block_number: u64) -> anyhow::Result<()> {
+    let timestamp = l2_block_timestamp(rollup_config, block_number)?;
+    let active = unsupported_hardforks(rollup_config)
+        .into_iter()
+        .filter(|(_, activation)| *activation <= timestamp)
+        .map(|(hardfork, activation)| format!("{hardfork} (active since {activation})"))
+        .collect::<Vec<_>>();
+    if !active.is_empty() {
+        bail!(
+            "Prover outdated! Block {block_number} is subject to unsupported hardforks: {}. Upgrade Kailua to prove this block.",
+            active.join(", ")
+        );
+    }
+    Ok(())
+}
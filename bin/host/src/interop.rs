@@ -0,0 +1,42 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::KailuaHostCli;
+use alloy::providers::{Provider, ProviderBuilder};
+use kailua_common::interop::{dependency_set_key, DependencySet};
+use tracing::info;
+
+/// Fetches the interop dependency set from the op-supervisor
+pub async fn fetch_dependency_set(supervisor_address: &str) -> anyhow::Result<DependencySet> {
+    let supervisor = ProviderBuilder::new().on_http(supervisor_address.try_into()?);
+    Ok(supervisor
+        .raw_request("supervisor_dependencySetV1".into(), ())
+        .await?)
+}
+
+/// Serves the dependency set to the client, which is empty unless a supervisor is configured
+pub async fn serve_dependency_set(cfg: &KailuaHostCli) -> anyhow::Result<()> {
+    let dependency_set = match &cfg.supervisor_address {
+        Some(supervisor_address) => fetch_dependency_set(supervisor_address).await?,
+        None => DependencySet::default(),
+    };
+    info!(
+        "Serving dependency set of {} chains.",
+        dependency_set.dependencies.len()
+    );
+    let kv_store = cfg.kona.construct_kv_store();
+    let mut store = kv_store.write().await;
+    store.set(dependency_set_key().into(), dependency_set.to_vec())?;
+    Ok(())
+}
@@ -12,17 +12,40 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod archive;
+pub mod beacon;
+pub mod compat;
+pub mod fixture;
+pub mod hardforks;
+#[cfg(feature = "interop")]
+pub mod interop;
+pub mod prefetch;
+pub mod serve;
+pub mod witness;
+
+use crate::archive::is_missing_state_error;
+use crate::compat::preflight;
+use crate::fixture::{ChainFixture, RecordingOracle};
+use crate::hardforks::check_hardfork_support;
+use crate::prefetch::PrefetchCache;
 use alloy::consensus::Transaction;
+use alloy::eips::BlockNumberOrTag;
 use alloy::network::primitives::BlockTransactionsKind;
 use alloy::primitives::{keccak256, B256};
 use alloy::providers::{Provider, ProviderBuilder, ReqwestProvider};
+use alloy::transports::http::reqwest::Url;
 use alloy_chains::NamedChain;
 use alloy_eips::eip4844::IndexedBlobHash;
-use anyhow::bail;
+use anyhow::{bail, Context};
 use boundless_market::storage::StorageProviderConfig;
 use clap::Parser;
+use kailua_client::limits::WitnessLimitArgs;
+use kailua_client::proof::fpvm_proof_file_name;
+use kailua_client::storage::ReceiptStorageArgs;
 use kailua_client::{parse_b256, BoundlessArgs};
 use kailua_common::blobs::BlobFetchRequest;
+use kailua_common::errors::FetchError;
+use kailua_common::kzg::load_trusted_setup;
 use kailua_common::precondition::PreconditionValidationData;
 use kona_host::fetcher::Fetcher;
 use kona_host::kv::SharedKeyValueStore;
@@ -33,9 +56,9 @@ use op_alloy_protocol::BlockInfo;
 use op_alloy_registry::Registry;
 use serde_json::{json, Value};
 use std::env::set_var;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tempfile::TempDir;
+use tempfile::{tempdir, TempDir};
 use tokio::sync::RwLock;
 use tokio::{fs, task};
 use tracing::{debug, info, warn};
@@ -55,6 +78,9 @@ pub struct KailuaHostCli {
     /// Address of OP-NODE endpoint to use
     #[clap(long, env)]
     pub op_node_address: Option<String>,
+    /// Address of an L2 archive node to preflight from when the L2 node has pruned the state
+    #[clap(long, env)]
+    pub l2_archive_address: Option<String>,
     /// Whether to skip running the zeth preflight engine
     #[clap(long, default_value_t = false, env)]
     pub skip_zeth_preflight: bool,
@@ -71,6 +97,40 @@ pub struct KailuaHostCli {
     #[clap(long, value_parser = parse_b256, env)]
     pub v_blob_kzg_hash: Option<B256>,
 
+    /// Path to a recorded chain fixture to serve all L1/L2/beacon data from
+    #[clap(long, env, conflicts_with = "record_fixture")]
+    pub mock_chain: Option<PathBuf>,
+    /// Path to record a chain fixture of all the data served during this run to
+    #[clap(long, env)]
+    pub record_fixture: Option<PathBuf>,
+
+    /// Comma-separated urls of the workers to distribute segment proving over
+    #[clap(long, env, value_delimiter = ',')]
+    pub prover_workers: Vec<Url>,
+    /// Path to write a pprof cycle profile of the guest execution to before proving
+    #[clap(long, env)]
+    pub cycle_profile: Option<PathBuf>,
+    /// Path to archive the witness fed to the guest to for native replays
+    #[clap(long, env)]
+    pub witness_archive: Option<PathBuf>,
+    /// Path to a KZG trusted setup file to use instead of the embedded Ethereum setup
+    #[clap(long, env)]
+    pub kzg_trusted_setup: Option<PathBuf>,
+    /// The keccak256 hash that the KZG trusted setup file must have
+    #[clap(long, value_parser = parse_b256, env, requires = "kzg_trusted_setup")]
+    pub kzg_trusted_setup_hash: Option<B256>,
+    /// Directory of blob sidecars and L1 headers prefetched for this run
+    #[clap(long, env)]
+    pub prefetch_dir: Option<PathBuf>,
+    /// Address of the op-supervisor endpoint to fetch the interop dependency set from
+    #[cfg(feature = "interop")]
+    #[clap(long, env)]
+    pub supervisor_address: Option<String>,
+    #[clap(flatten)]
+    pub witness_limits: WitnessLimitArgs,
+    #[clap(flatten)]
+    pub receipt_storage: ReceiptStorageArgs,
+
     #[clap(flatten)]
     pub boundless_args: Option<BoundlessArgs>,
     /// Storage provider to use for elf and input
@@ -82,7 +142,9 @@ pub struct KailuaHostCli {
 /// ran natively in this mode.
 ///
 /// ## Takes
-/// - `cfg`: The host configuration.
+/// - `args`: The host configuration.
+/// - `fixture`: The recorded chain fixture to serve chain data from in mock chain mode.
+/// - `precondition_validation_data`: The blob equivalence precondition data to validate.
 ///
 /// ## Returns
 /// - `Ok(exit_code)` if the client program exits successfully.
@@ -90,12 +152,20 @@ pub struct KailuaHostCli {
 ///   exited first.
 pub async fn start_server_and_native_client(
     args: KailuaHostCli,
-    precondition_validation_data_hash: B256,
+    fixture: Option<&ChainFixture>,
+    precondition_validation_data: Option<PreconditionValidationData>,
 ) -> anyhow::Result<i32> {
+    let precondition_validation_data_hash = precondition_validation_data
+        .as_ref()
+        .map(|data| data.hash())
+        .unwrap_or_default();
     let hint_chan = BidirectionalChannel::new()?;
     let preimage_chan = BidirectionalChannel::new()?;
-    let kv_store = args.kona.construct_kv_store();
-    let fetcher = if !args.kona.is_offline() {
+    let kv_store = match fixture {
+        Some(fixture) => fixture.kv_store(&args.kona)?,
+        None => args.kona.construct_kv_store(),
+    };
+    let fetcher = if fixture.is_none() && !args.kona.is_offline() {
         let (l1_provider, blob_provider, l2_provider) = args.kona.create_providers().await?;
         Some(Arc::new(RwLock::new(Fetcher::new(
             kv_store.clone(),
@@ -117,22 +187,128 @@ pub async fn start_server_and_native_client(
     ));
 
     // Start the client program in a separate child process.
+    let oracle_client = RecordingOracle::new(OracleReader::new(preimage_chan.client));
     let program_task = task::spawn(kailua_client::run_client(
-        args.boundless_args,
-        args.boundless_storage_config,
-        OracleReader::new(preimage_chan.client),
+        args.boundless_args.clone(),
+        args.boundless_storage_config.clone(),
+        oracle_client.clone(),
         HintWriter::new(hint_chan.client),
         precondition_validation_data_hash,
+        // Interrupted local proofs resume from checkpoints under the data directory
+        args.kona
+            .data_dir
+            .as_ref()
+            .map(|dir| dir.join("checkpoints")),
+        args.prover_workers.clone(),
+        args.cycle_profile.clone(),
+        args.witness_archive.clone(),
+        args.witness_limits.clone(),
+        args.receipt_storage.clone(),
     ));
 
     // Execute both tasks and wait for them to complete.
     info!("Starting preimage server and client program.");
-    let (_, client_result) = tokio::try_join!(server_task, program_task,)?;
+    let joined = tokio::try_join!(server_task, program_task,);
     info!(target: "kona_host", "Preimage server and client program have joined.");
 
+    // Record the served data for hermetic replays, including that of failed runs
+    if let Some(fixture_path) = &args.record_fixture {
+        ChainFixture {
+            precondition_validation_data,
+            preimages: oracle_client.take_preimages(),
+        }
+        .save(fixture_path)
+        .await?;
+    }
+
+    let (_, client_result) = joined?;
     Ok(client_result.is_err() as i32)
 }
 
+/// Computes the proof requested by the arguments unless already cached, returning the name of
+/// the proof file.
+pub async fn prove(mut args: KailuaHostCli) -> anyhow::Result<String> {
+    // load the trusted setup once for all proofs made by this process
+    if let Some(path) = &args.kzg_trusted_setup {
+        let hash = load_trusted_setup(path, args.kzg_trusted_setup_hash)?;
+        info!("Using KZG trusted setup {path:?} with hash {hash}.");
+    }
+    // serve all chain data from a single load of the fixture in mock chain mode
+    let fixture = match &args.mock_chain {
+        Some(fixture_path) => Some(ChainFixture::load(fixture_path).await?),
+        None => None,
+    };
+    // compute receipt if uncached
+    let precondition_validation_data = match &fixture {
+        Some(fixture) => fixture.precondition_validation_data.clone(),
+        None => fetch_precondition_data(&args).await?,
+    };
+    let precondition_hash = match &precondition_validation_data {
+        Some(data) => {
+            set_var("PRECONDITION_VALIDATION_DATA_HASH", data.hash().to_string());
+            data.precondition_hash()
+        }
+        None => B256::ZERO,
+    };
+    let file_name = fpvm_proof_file_name(
+        precondition_hash,
+        args.kona.l1_head,
+        args.kona.claimed_l2_output_root,
+        args.kona.claimed_l2_block_number,
+        args.kona.agreed_l2_output_root,
+    );
+    // a fixture can only be recorded by running the client
+    if args.record_fixture.is_none() && Path::new(&file_name).try_exists().unwrap_or_default() {
+        info!("Proving skipped. Proof file {file_name} already exists.");
+    } else {
+        info!("Computing uncached proof.");
+        let tmp_dir = tempdir()?;
+        // all chain data is served from the fixture in mock chain mode
+        if fixture.is_none() {
+            // refuse to derive from an l1 head that no game could have committed to
+            if !args.kona.is_offline() {
+                check_l1_head(&args).await?;
+            }
+            let rollup_config =
+                generate_rollup_config(&mut args, &tmp_dir)
+                    .await
+                    .map_err(|err| FetchError::Request {
+                        what: "rollup config",
+                        message: format!("{err:#}"),
+                    })?;
+            // refuse to prove blocks subject to rules kona does not implement
+            check_hardfork_support(
+                &serde_json::to_value(&rollup_config)?,
+                args.kona.claimed_l2_block_number,
+            )?;
+            // run preflight to fetch the necessary preimages using what the l2 node supports
+            if !args.skip_zeth_preflight {
+                preflight(&args, rollup_config)
+                    .await
+                    .map_err(|err| FetchError::Request {
+                        what: "preflight data",
+                        message: format!("{err:#}"),
+                    })?;
+            }
+            // serve data prefetched by the validator without refetching it
+            if let Some(prefetch_dir) = &args.prefetch_dir {
+                PrefetchCache::new(prefetch_dir)?
+                    .seed_kv_store(&args.kona.construct_kv_store())
+                    .await?;
+            }
+            // serve the dependency set of interop chains
+            #[cfg(feature = "interop")]
+            interop::serve_dependency_set(&args).await?;
+        }
+
+        // generate a proof using the kailua client and kona server
+        start_server_and_native_client(args, fixture.as_ref(), precondition_validation_data)
+            .await
+            .context("Proving failure")?;
+    }
+    Ok(file_name)
+}
+
 pub async fn generate_rollup_config(
     cfg: &mut KailuaHostCli,
     tmp_dir: &TempDir,
@@ -194,7 +370,8 @@ pub async fn fetch_rollup_config(
     let chain_config: Value = l2_node_provider
         .client()
         .request_noparams("debug_chainConfig")
-        .await?;
+        .await
+        .context("debug_chainConfig is unavailable on this L2 node, provide --rollup-config-path or --l2-chain-id for a registered chain instead")?;
 
     debug!("ChainConfig: {:?}", chain_config);
 
@@ -306,28 +483,34 @@ pub async fn zeth_execution_preflight(
             && OpRethCoreDriver::chain_spec(&named_chain).is_some()
         {
             info!("Performing zeth-optimism preflight.");
-            let kona_cfg = cfg.kona.clone();
-            let preflight_start = kona_cfg.claimed_l2_block_number - cfg.block_count + 1;
-            let block_count = cfg.block_count;
-            // Fetch all the initial data
-            let preflight_data: StatelessClientData<
-                <OpRethCoreDriver as CoreDriver>::Block,
-                <OpRethCoreDriver as CoreDriver>::Header,
-            > = tokio::task::spawn_blocking(move || {
-                // Prepare the cache directory
-                let cache_dir = kona_cfg.data_dir.map(|dir| dir.join("optimism"));
-                if let Some(dir) = cache_dir.as_ref() {
-                    std::fs::create_dir_all(dir).expect("Could not create directory");
-                };
-                OpRethPreflightClient::preflight(
-                    Some(rollup_config.l2_chain_id),
-                    cache_dir,
-                    kona_cfg.l2_node_address,
-                    preflight_start,
-                    block_count,
-                )
-            })
-            .await??;
+            let preflight_data = match zeth_preflight_from(
+                cfg,
+                &rollup_config,
+                cfg.kona.l2_node_address.clone(),
+            )
+            .await
+            {
+                Ok(preflight_data) => {
+                    info!("Preflight data served by L2 node.");
+                    preflight_data
+                }
+                // Fall back to the archive node only if the L2 node pruned the state
+                Err(err) if is_missing_state_error(&err) => {
+                    let Some(l2_archive_address) = &cfg.l2_archive_address else {
+                        return Err(err.context(
+                            "L2 node is missing historical state and no l2-archive-address is set",
+                        ));
+                    };
+                    warn!("L2 node is missing historical state ({err}). Retrying preflight against archive node {l2_archive_address}.");
+                    let preflight_data =
+                        zeth_preflight_from(cfg, &rollup_config, Some(l2_archive_address.clone()))
+                            .await
+                            .context("archive node preflight")?;
+                    info!("Preflight data served by archive node {l2_archive_address}.");
+                    preflight_data
+                }
+                Err(err) => return Err(err),
+            };
             // Write data to the cached Kona kv-store
             let mut kv_store = cfg.kona.construct_kv_store();
             dump_data_to_kv_store(&mut kv_store, &preflight_data).await;
@@ -336,6 +519,70 @@ pub async fn zeth_execution_preflight(
     Ok(())
 }
 
+/// Fetches the stateless execution data of the proven blocks from the given L2 node
+async fn zeth_preflight_from(
+    cfg: &KailuaHostCli,
+    rollup_config: &RollupConfig,
+    l2_node_address: Option<String>,
+) -> anyhow::Result<
+    StatelessClientData<
+        <OpRethCoreDriver as CoreDriver>::Block,
+        <OpRethCoreDriver as CoreDriver>::Header,
+    >,
+> {
+    let preflight_start = cfg.kona.claimed_l2_block_number - cfg.block_count + 1;
+    let block_count = cfg.block_count;
+    let l2_chain_id = rollup_config.l2_chain_id;
+    let data_dir = cfg.kona.data_dir.clone();
+    // Fetch all the initial data
+    tokio::task::spawn_blocking(move || {
+        // Prepare the cache directory
+        let cache_dir = data_dir.map(|dir| dir.join("optimism"));
+        if let Some(dir) = cache_dir.as_ref() {
+            std::fs::create_dir_all(dir).expect("Could not create directory");
+        };
+        OpRethPreflightClient::preflight(
+            Some(l2_chain_id),
+            cache_dir,
+            l2_node_address,
+            preflight_start,
+            block_count,
+        )
+    })
+    .await?
+}
+
+/// Checks that the l1 head exists on the canonical l1 chain, as receipts derived from an unknown
+/// or reorged head can not be verified by any game.
+pub async fn check_l1_head(cfg: &KailuaHostCli) -> anyhow::Result<()> {
+    let l1_head = cfg.kona.l1_head;
+    let (l1_provider, _, _) = cfg.kona.create_providers().await?;
+    let Some(block) = l1_provider
+        .get_block_by_hash(l1_head, BlockTransactionsKind::Hashes)
+        .await?
+    else {
+        bail!(FetchError::Request {
+            what: "l1 head",
+            message: format!("Block {l1_head} not found"),
+        });
+    };
+    let canonical_hash = l1_provider
+        .get_block_by_number(
+            BlockNumberOrTag::Number(block.header.number),
+            BlockTransactionsKind::Hashes,
+        )
+        .await?
+        .map(|block| block.header.hash);
+    if canonical_hash != Some(l1_head) {
+        bail!(
+            "L1 head {l1_head} is not canonical at height {} ({canonical_hash:?}).",
+            block.header.number
+        );
+    }
+    debug!("Using L1 head {l1_head} at height {}.", block.header.number);
+    Ok(())
+}
+
 pub async fn get_blob_fetch_request(
     l1_provider: &ReqwestProvider,
     block_hash: B256,
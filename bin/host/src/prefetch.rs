@@ -0,0 +1,140 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::primitives::{keccak256, B256};
+use alloy_eips::eip4844::{kzg_to_versioned_hash, FIELD_ELEMENTS_PER_BLOB};
+use alloy_rpc_types_beacon::sidecar::BlobData;
+use anyhow::Context;
+use kona_host::kv::SharedKeyValueStore;
+use kona_preimage::{PreimageKey, PreimageKeyType};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// A directory of blob sidecars and L1 headers downloaded ahead of a proving run
+#[derive(Clone, Debug)]
+pub struct PrefetchCache {
+    pub dir: PathBuf,
+}
+
+impl PrefetchCache {
+    pub fn new(dir: &Path) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(dir.join("blobs"))?;
+        std::fs::create_dir_all(dir.join("headers"))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    pub fn has_header(&self, hash: B256) -> bool {
+        self.dir.join("headers").join(hash.to_string()).exists()
+    }
+
+    pub fn save_header(&self, hash: B256, rlp: &[u8]) -> anyhow::Result<()> {
+        write_atomic(&self.dir.join("headers").join(hash.to_string()), rlp)
+            .context(format!("Failed to cache header {hash}"))
+    }
+
+    pub fn has_blob(&self, versioned_hash: B256) -> bool {
+        self.dir
+            .join("blobs")
+            .join(versioned_hash.to_string())
+            .exists()
+    }
+
+    pub fn save_blob(&self, sidecar: &BlobData) -> anyhow::Result<()> {
+        let versioned_hash = kzg_to_versioned_hash(sidecar.kzg_commitment.as_slice());
+        write_atomic(
+            &self.dir.join("blobs").join(versioned_hash.to_string()),
+            &serde_json::to_vec(sidecar)?,
+        )
+        .context(format!("Failed to cache blob {versioned_hash}"))
+    }
+
+    /// Writes all cached data to the kv-store in the layout the kona fetcher would have used, so
+    /// that the preimage server finds it without any network requests.
+    pub async fn seed_kv_store(&self, kv_store: &SharedKeyValueStore) -> anyhow::Result<()> {
+        let mut store = kv_store.write().await;
+        let mut header_count = 0;
+        for path in cached_files(&self.dir.join("headers"))? {
+            let rlp = std::fs::read(path)?;
+            store.set(
+                PreimageKey::new(*keccak256(&rlp), PreimageKeyType::Keccak256).into(),
+                rlp,
+            )?;
+            header_count += 1;
+        }
+        let mut blob_count = 0;
+        for path in cached_files(&self.dir.join("blobs"))? {
+            let sidecar: BlobData = match serde_json::from_slice(&std::fs::read(&path)?) {
+                Ok(sidecar) => sidecar,
+                Err(err) => {
+                    warn!("Skipping unreadable prefetched blob {path:?}: {err:?}");
+                    continue;
+                }
+            };
+            let versioned_hash = kzg_to_versioned_hash(sidecar.kzg_commitment.as_slice());
+            store.set(
+                PreimageKey::new(*versioned_hash, PreimageKeyType::Sha256).into(),
+                sidecar.kzg_commitment.to_vec(),
+            )?;
+            // Each field element is keyed by keccak256(commitment ++ uint256(i)), with the kzg
+            // proof stored as the element following the last one
+            let mut blob_key = [0u8; 80];
+            blob_key[..48].copy_from_slice(sidecar.kzg_commitment.as_slice());
+            for i in 0..=FIELD_ELEMENTS_PER_BLOB {
+                blob_key[72..].copy_from_slice(i.to_be_bytes().as_ref());
+                let blob_key_hash = keccak256(blob_key.as_ref());
+                store.set(
+                    PreimageKey::new(*blob_key_hash, PreimageKeyType::Keccak256).into(),
+                    blob_key.into(),
+                )?;
+                let value = if i < FIELD_ELEMENTS_PER_BLOB {
+                    let i = i as usize;
+                    sidecar.blob[i << 5..(i + 1) << 5].to_vec()
+                } else {
+                    sidecar.kzg_proof.to_vec()
+                };
+                store.set(
+                    PreimageKey::new(*blob_key_hash, PreimageKeyType::Blob).into(),
+                    value,
+                )?;
+            }
+            blob_count += 1;
+        }
+        info!(
+            "Seeded {header_count} prefetched headers and {blob_count} prefetched blobs from {}.",
+            self.dir.display()
+        );
+        Ok(())
+    }
+}
+
+/// Writes the file under a temporary name first so that readers never observe partial data
+fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, data)?;
+    std::fs::rename(tmp_path, path)
+}
+
+/// Lists the completely written files in the given cache directory
+fn cached_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
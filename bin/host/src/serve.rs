@@ -0,0 +1,284 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{prove, KailuaHostCli};
+use anyhow::Context;
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use clap::Parser;
+use kailua_common::errors::ErrorClass;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::sync::{mpsc, watch};
+use tracing::{error, info, warn};
+
+/// The longest a status request may wait for the status of a job to change
+pub const MAX_STATUS_WAIT_SECS: u64 = 60;
+
+/// The arguments of `kailua-host serve`
+#[derive(Parser, Clone, Debug)]
+pub struct ServeArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address to serve the proving job api on
+    #[clap(long, env, default_value = "127.0.0.1:9651")]
+    pub serve_address: SocketAddr,
+}
+
+/// A request to prove the claim described by the same arguments accepted by kailua-host
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct JobRequest {
+    pub args: Vec<String>,
+    /// Environment variables to set while proving, such as the risc0 prover selection
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JobStatus {
+    #[default]
+    Queued,
+    Running,
+    Succeeded {
+        proof_file: String,
+    },
+    Failed {
+        error: String,
+        /// The class of the error, if known
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        class: Option<ErrorClass>,
+    },
+}
+
+impl JobStatus {
+    pub fn is_final(&self) -> bool {
+        matches!(self, JobStatus::Succeeded { .. } | JobStatus::Failed { .. })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JobView {
+    pub id: u64,
+    #[serde(flatten)]
+    pub status: JobStatus,
+}
+
+/// The jobs known to the service, and the queue feeding its worker
+#[derive(Clone)]
+pub struct ServiceState {
+    pub jobs: Arc<Mutex<HashMap<u64, watch::Sender<JobStatus>>>>,
+    pub next_id: Arc<Mutex<u64>>,
+    pub queue: mpsc::UnboundedSender<(u64, KailuaHostCli, JobRequest)>,
+}
+
+type ApiError = (StatusCode, String);
+
+fn not_found(id: u64) -> ApiError {
+    (StatusCode::NOT_FOUND, format!("Job {id} not found"))
+}
+
+/// Serves the proving job api until the process exits, proving one job at a time
+pub async fn serve(args: ServeArgs) -> anyhow::Result<()> {
+    let (queue, jobs_receiver) = mpsc::unbounded_channel();
+    let state = ServiceState {
+        jobs: Default::default(),
+        next_id: Default::default(),
+        queue,
+    };
+    tokio::spawn(work(jobs_receiver, state.jobs.clone()));
+
+    let app = Router::new()
+        .route("/health", get(|| async { "OK" }))
+        .route("/jobs", post(submit_job))
+        .route("/jobs/:id", get(get_job))
+        .route("/jobs/:id/receipt", get(get_receipt))
+        .with_state(state);
+    let listener = tokio::net::TcpListener::bind(args.serve_address)
+        .await
+        .context(format!("Failed to bind to {}", args.serve_address))?;
+    info!("Serving proving jobs on {}.", args.serve_address);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+/// Proves queued jobs in submission order. Jobs run sequentially because the prover is
+/// configured through process-wide environment variables.
+async fn work(
+    mut receiver: mpsc::UnboundedReceiver<(u64, KailuaHostCli, JobRequest)>,
+    jobs: Arc<Mutex<HashMap<u64, watch::Sender<JobStatus>>>>,
+) {
+    // Rollup configurations fetched for earlier jobs, keyed by the node addresses
+    let config_dir = TempDir::new().expect("Failed to create rollup config directory");
+    let mut rollup_configs = HashMap::<(Option<String>, Option<String>), PathBuf>::new();
+    while let Some((id, mut args, request)) = receiver.recv().await {
+        let Some(status) = jobs.lock().unwrap().get(&id).cloned() else {
+            continue;
+        };
+        status.send_replace(JobStatus::Running);
+        info!("Running proving job {id}.");
+        // Reuse the rollup configuration of earlier jobs against the same nodes
+        let config_key = (
+            args.op_node_address.clone(),
+            args.kona.l2_node_address.clone(),
+        );
+        if args.kona.rollup_config_path.is_none() {
+            args.kona.rollup_config_path = rollup_configs.get(&config_key).cloned();
+        }
+        if args.kona.rollup_config_path.is_none() && args.mock_chain.is_none() {
+            let config_file = config_dir.path().join(format!("{id}.json"));
+            match crate::generate_rollup_config(&mut args, &config_dir).await {
+                Ok(rollup_config) => match serde_json::to_vec(&rollup_config) {
+                    Ok(data) if std::fs::write(&config_file, data).is_ok() => {
+                        rollup_configs.insert(config_key, config_file.clone());
+                        args.kona.rollup_config_path = Some(config_file);
+                    }
+                    _ => warn!("Failed to cache rollup config for job {id}."),
+                },
+                Err(err) => warn!("Failed to fetch rollup config for job {id}: {err:?}"),
+            }
+        }
+        // Apply the job's environment for its duration
+        let previous_env = request
+            .env
+            .iter()
+            .map(|(key, value)| {
+                let previous = std::env::var(key).ok();
+                std::env::set_var(key, value);
+                (key.clone(), previous)
+            })
+            .collect::<Vec<_>>();
+        std::env::remove_var("PRECONDITION_VALIDATION_DATA_HASH");
+        let result = tokio::spawn(prove(args)).await;
+        for (key, previous) in previous_env {
+            match previous {
+                Some(value) => std::env::set_var(key, value),
+                None => std::env::remove_var(key),
+            }
+        }
+        let outcome = match result {
+            Ok(Ok(proof_file)) if std::path::Path::new(&proof_file).exists() => {
+                JobStatus::Succeeded { proof_file }
+            }
+            Ok(Ok(proof_file)) => JobStatus::Failed {
+                error: format!("Proof file {proof_file} was not produced"),
+                class: None,
+            },
+            Ok(Err(err)) => JobStatus::Failed {
+                error: format!("{err:?}"),
+                class: ErrorClass::of(&err),
+            },
+            Err(err) => JobStatus::Failed {
+                error: format!("Proving task panicked: {err:?}"),
+                class: None,
+            },
+        };
+        match &outcome {
+            JobStatus::Failed { error, .. } => error!("Proving job {id} failed: {error}"),
+            _ => info!("Proving job {id} succeeded."),
+        }
+        status.send_replace(outcome);
+    }
+}
+
+async fn submit_job(
+    State(state): State<ServiceState>,
+    Json(request): Json<JobRequest>,
+) -> Result<Json<JobView>, ApiError> {
+    let args = KailuaHostCli::try_parse_from(
+        std::iter::once(String::from("kailua-host")).chain(request.args.iter().cloned()),
+    )
+    .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+    let id = {
+        let mut next_id = state.next_id.lock().unwrap();
+        *next_id += 1;
+        *next_id
+    };
+    let (status, _) = watch::channel(JobStatus::Queued);
+    state.jobs.lock().unwrap().insert(id, status);
+    state.queue.send((id, args, request)).map_err(|_| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            String::from("Proving worker stopped"),
+        )
+    })?;
+    info!("Queued proving job {id}.");
+    Ok(Json(JobView {
+        id,
+        status: JobStatus::Queued,
+    }))
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct StatusQuery {
+    /// Seconds to wait for the status to differ from this one before responding
+    pub wait_secs: Option<u64>,
+}
+
+/// Returns the status of a job, optionally long-polling until it changes so that clients can
+/// follow a job's progress without busy polling.
+async fn get_job(
+    State(state): State<ServiceState>,
+    Path(id): Path<u64>,
+    Query(query): Query<StatusQuery>,
+) -> Result<Json<JobView>, ApiError> {
+    let mut receiver = state
+        .jobs
+        .lock()
+        .unwrap()
+        .get(&id)
+        .ok_or(not_found(id))?
+        .subscribe();
+    if let Some(wait_secs) = query.wait_secs {
+        let wait = Duration::from_secs(wait_secs.min(MAX_STATUS_WAIT_SECS));
+        if !receiver.borrow_and_update().is_final() {
+            let _ = tokio::time::timeout(wait, receiver.changed()).await;
+        }
+    }
+    let status = receiver.borrow().clone();
+    Ok(Json(JobView { id, status }))
+}
+
+async fn get_receipt(
+    State(state): State<ServiceState>,
+    Path(id): Path<u64>,
+) -> Result<Bytes, ApiError> {
+    let status = state
+        .jobs
+        .lock()
+        .unwrap()
+        .get(&id)
+        .ok_or(not_found(id))?
+        .borrow()
+        .clone();
+    let JobStatus::Succeeded { proof_file } = status else {
+        return Err((StatusCode::CONFLICT, format!("Job {id} has no receipt")));
+    };
+    let data = tokio::fs::read(&proof_file).await.map_err(|err| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to read {proof_file}: {err}"),
+        )
+    })?;
+    Ok(Bytes::from(data))
+}
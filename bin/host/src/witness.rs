@@ -0,0 +1,128 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::archive::is_missing_state_error;
+use crate::KailuaHostCli;
+use alloy::primitives::{keccak256, Bytes, B256};
+use alloy::providers::{Provider, ProviderBuilder};
+use kona_preimage::{PreimageKey, PreimageKeyType};
+use serde::Deserialize;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+/// Error messages with which nodes reject calls to rpc methods they do not implement
+pub const UNSUPPORTED_METHOD_ERRORS: [&str; 3] =
+    ["method not found", "does not exist", "not available"];
+
+/// Trie nodes, contract codes or headers, served either as a list or keyed by their hashes
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Preimages {
+    List(Vec<Bytes>),
+    Map(HashMap<B256, Bytes>),
+}
+
+impl Default for Preimages {
+    fn default() -> Self {
+        Self::List(vec![])
+    }
+}
+
+impl Preimages {
+    pub fn into_vec(self) -> Vec<Bytes> {
+        match self {
+            Preimages::List(list) => list,
+            Preimages::Map(map) => map.into_values().collect(),
+        }
+    }
+}
+
+/// The complete witness of a block's execution as returned by `debug_executionWitness`
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ExecutionWitness {
+    #[serde(default)]
+    pub state: Preimages,
+    #[serde(default)]
+    pub codes: Preimages,
+    #[serde(default)]
+    pub headers: Preimages,
+}
+
+/// Fetches the execution witness of the given block
+pub async fn fetch_execution_witness(
+    l2_node_address: &str,
+    block_number: u64,
+) -> anyhow::Result<ExecutionWitness> {
+    let l2_node_provider = ProviderBuilder::new().on_http(l2_node_address.try_into()?);
+    Ok(l2_node_provider
+        .raw_request(
+            "debug_executionWitness".into(),
+            (format!("0x{block_number:x}"),),
+        )
+        .await?)
+}
+
+/// Returns whether the error was caused by the node not implementing the called method
+pub fn is_unsupported_method_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        let message = cause.to_string().to_lowercase();
+        UNSUPPORTED_METHOD_ERRORS
+            .iter()
+            .any(|pattern| message.contains(pattern))
+    })
+}
+
+/// Writes the execution witnesses of all proven blocks to the kv-store, returning false without
+/// writing anything if the L2 node can not serve them.
+pub async fn execution_witness_preflight(cfg: &KailuaHostCli) -> anyhow::Result<bool> {
+    let Some(l2_node_address) = &cfg.kona.l2_node_address else {
+        return Ok(false);
+    };
+    let preflight_start = cfg.kona.claimed_l2_block_number - cfg.block_count + 1;
+    let mut witnesses = Vec::with_capacity(cfg.block_count as usize);
+    for block_number in preflight_start..=cfg.kona.claimed_l2_block_number {
+        match fetch_execution_witness(l2_node_address, block_number).await {
+            Ok(witness) => witnesses.push(witness),
+            Err(err) if is_unsupported_method_error(&err) || is_missing_state_error(&err) => {
+                warn!("L2 node can not serve execution witnesses ({err}). Falling back to legacy preflight.");
+                return Ok(false);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    // Every preimage is keyed by its keccak256 hash, as the kona fetcher would have stored it
+    let kv_store = cfg.kona.construct_kv_store();
+    let mut store = kv_store.write().await;
+    let mut preimage_count = 0;
+    for witness in witnesses {
+        for preimage in witness
+            .state
+            .into_vec()
+            .into_iter()
+            .chain(witness.codes.into_vec())
+            .chain(witness.headers.into_vec())
+        {
+            store.set(
+                PreimageKey::new(*keccak256(&preimage), PreimageKeyType::Keccak256).into(),
+                preimage.to_vec(),
+            )?;
+            preimage_count += 1;
+        }
+    }
+    info!(
+        "Preflighted {preimage_count} preimages of {} blocks from execution witnesses.",
+        cfg.block_count
+    );
+    Ok(true)
+}
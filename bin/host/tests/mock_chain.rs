@@ -0,0 +1,118 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy_primitives::B256;
+use kailua_host::fixture::ChainFixture;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The offline data directory of op-sepolia block 16491249
+const DATA_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../testdata/16491249");
+const L1_HEAD: &str = "0x33a3e5721faa4dc6f25e75000d9810fd6c41320868f3befcc0c261a71da398e1";
+const AGREED_L2_HEAD_HASH: &str =
+    "0x09b298a83baf4c2e3c6a2e355bb09e27e3fdca435080e8754f8749233d7333b2";
+const AGREED_L2_OUTPUT_ROOT: &str =
+    "0xa548f22e1aa590de7ed271e3eab5b66c6c3db9b8cb0e3f91618516ea9ececde4";
+const CLAIMED_L2_OUTPUT_ROOT: &str =
+    "0x82da7204148ba4d8d59e587b6b3fdde5561dc31d9e726220f7974bf9f2158d75";
+
+/// Runs the host binary in `work_dir`, where it writes its proof files, and returns the proof
+/// files it produced.
+fn run_host(
+    work_dir: &Path,
+    claimed_l2_output_root: &str,
+    extra_args: &[&str],
+) -> anyhow::Result<Vec<PathBuf>> {
+    let status = Command::new(env!("CARGO_BIN_EXE_kailua-host"))
+        .current_dir(work_dir)
+        .env("RISC0_DEV_MODE", "1")
+        .args([
+            "--l1-head",
+            L1_HEAD,
+            "--agreed-l2-head-hash",
+            AGREED_L2_HEAD_HASH,
+            "--agreed-l2-output-root",
+            AGREED_L2_OUTPUT_ROOT,
+            "--claimed-l2-output-root",
+            claimed_l2_output_root,
+            "--claimed-l2-block-number",
+            "16491249",
+            "--l2-chain-id",
+            "11155420",
+            "--skip-zeth-preflight",
+            "--native",
+        ])
+        .args(extra_args)
+        .status()?;
+    assert!(status.success(), "kailua-host exited with {status}");
+    take_proof_files(work_dir)
+}
+
+/// Removes and returns the fake proofs in the directory so that later runs are not cached
+fn take_proof_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut proof_files = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "fake") {
+            std::fs::remove_file(&path)?;
+            proof_files.push(path);
+        }
+    }
+    Ok(proof_files)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        std::fs::copy(entry.path(), to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// Records the proposer's proof of a block, then settles a match between the proposal and a
+/// contradicting challenge using nothing but the recorded fixture.
+#[tokio::test(flavor = "multi_thread")]
+async fn replay_match_from_recorded_fixture() -> anyhow::Result<()> {
+    let tmp_dir = tempfile::tempdir()?;
+    let data_dir = tmp_dir.path().join("data");
+    copy_dir(Path::new(DATA_DIR), &data_dir)?;
+    let fixture_path = tmp_dir.path().join("fixture.bin");
+
+    // record the proof of the honest proposal
+    let proofs = run_host(
+        tmp_dir.path(),
+        CLAIMED_L2_OUTPUT_ROOT,
+        &[
+            "--data-dir",
+            data_dir.to_str().unwrap(),
+            "--record-fixture",
+            fixture_path.to_str().unwrap(),
+        ],
+    )?;
+    assert_eq!(proofs.len(), 1);
+    let fixture = ChainFixture::load(&fixture_path).await?;
+    assert!(!fixture.preimages.is_empty());
+
+    // the honest proposal remains provable without the data directory
+    let mock_chain = ["--mock-chain", fixture_path.to_str().unwrap()];
+    let replayed = run_host(tmp_dir.path(), CLAIMED_L2_OUTPUT_ROOT, &mock_chain)?;
+    assert_eq!(replayed, proofs);
+
+    // while the challenger's contradicting output can not be proven
+    let challenge = B256::repeat_byte(0x11).to_string();
+    assert!(run_host(tmp_dir.path(), &challenge, &mock_chain)?.is_empty());
+
+    Ok(())
+}
@@ -13,23 +13,37 @@
 // limitations under the License.
 
 fn main() {
+    // Forward the guest features enabled on this crate
+    let features = [
+        ("CARGO_FEATURE_HOLOCENE", "holocene"),
+        ("CARGO_FEATURE_INTEROP", "interop"),
+        ("CARGO_FEATURE_ACCELERATED_KECCAK", "accelerated-keccak"),
+    ]
+    .into_iter()
+    .filter(|(var, _)| std::env::var(var).is_ok())
+    .map(|(_, feature)| String::from(feature))
+    .collect::<Vec<_>>();
+
     // Build a reproducible ELF file using docker under the release profile
     #[cfg(not(any(feature = "debug-guest-build", debug_assertions)))]
-    let build_opts = {
+    let use_docker = {
         let cwd = std::env::current_dir().unwrap();
         let root_dir = cwd.parent().unwrap().parent().map(|d| d.to_path_buf());
-        std::collections::HashMap::from([(
-            "kailua-fpvm",
-            risc0_build::GuestOptions {
-                use_docker: Some(risc0_build::DockerOptions { root_dir }),
-                ..Default::default()
-            },
-        )])
+        Some(risc0_build::DockerOptions { root_dir })
     };
 
     // Build ELFs natively under debug
     #[cfg(any(feature = "debug-guest-build", debug_assertions))]
-    let build_opts = Default::default();
+    let use_docker = None;
+
+    let build_opts = std::collections::HashMap::from([(
+        "kailua-fpvm",
+        risc0_build::GuestOptions {
+            features,
+            use_docker,
+            ..Default::default()
+        },
+    )]);
 
     risc0_build::embed_methods_with_options(build_opts);
     println!("cargo:rerun-if-changed=src");
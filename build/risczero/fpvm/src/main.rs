@@ -23,12 +23,28 @@ use std::sync::Arc;
 use rkyv::rancor::Error;
 use kailua_common::client::log;
 
+/// Hashes through the accelerated keccak implementation, which alloy-primitives links against
+/// when its `native-keccak` feature is enabled.
+///
+/// # Safety
+/// The input must point to `len` readable bytes and the output to 32 writable bytes.
+#[cfg(feature = "accelerated-keccak")]
+#[no_mangle]
+pub unsafe extern "C" fn native_keccak256(bytes: *const u8, len: usize, output: *mut u8) {
+    use tiny_keccak_accelerated::{Hasher, Keccak};
+    let mut hasher = Keccak::v256();
+    hasher.update(core::slice::from_raw_parts(bytes, len));
+    hasher.finalize(&mut *(output as *mut [u8; 32]));
+}
+
 fn main() {
     let witness_data = env::read_frame();
     log("ACCESS");
     let witness_access = rkyv::access::<ArchivedWitness, Error>(&witness_data).expect("Failed to access witness data");
     log("DESERIALIZE");
     let witness = rkyv::deserialize::<Witness, Error>(witness_access).expect("Failed to deserialize witness");
+    // Release the serialized witness before running the client
+    drop(witness_data);
     log("RUN");
     // let witness: Witness = pot::from_slice(&witness_data).expect("Failed to parse framed witness");
     let oracle = Arc::new(PreloadedOracle::from(witness.oracle_witness));
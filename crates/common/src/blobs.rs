@@ -12,12 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use alloy_eips::eip4844::{kzg_to_versioned_hash, Blob, IndexedBlobHash, BYTES_PER_BLOB};
+use crate::errors::{EncodingError, KailuaResult};
+#[cfg(feature = "fpvm")]
+use alloy_eips::eip4844::{kzg_to_versioned_hash, Blob, BYTES_PER_BLOB};
+use alloy_eips::eip4844::{IndexedBlobHash, FIELD_ELEMENTS_PER_BLOB};
 use alloy_primitives::B256;
+#[cfg(feature = "fpvm")]
 use alloy_rpc_types_beacon::sidecar::BlobData;
+#[cfg(feature = "fpvm")]
 use async_trait::async_trait;
-use c_kzg::{ethereum_kzg_settings, Bytes48};
+#[cfg(feature = "fpvm")]
+use c_kzg::Bytes48;
+#[cfg(feature = "fpvm")]
 use kona_derive::errors::BlobProviderError;
+#[cfg(feature = "fpvm")]
 use kona_derive::traits::BlobProvider;
 use op_alloy_protocol::BlockInfo;
 use serde::{Deserialize, Serialize};
@@ -28,6 +36,7 @@ pub struct BlobFetchRequest {
     pub blob_hash: IndexedBlobHash,
 }
 
+#[cfg(feature = "fpvm")]
 #[derive(
     Clone, Debug, Default, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
 )]
@@ -40,17 +49,20 @@ pub struct BlobWitnessData {
     pub proofs: Vec<Bytes48>,
 }
 
+#[cfg(feature = "fpvm")]
 #[derive(Clone, Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 #[rkyv(remote = Blob)]
 #[rkyv(archived = ArchivedBlob)]
 struct BlobDef(pub [u8; BYTES_PER_BLOB]);
 
+#[cfg(feature = "fpvm")]
 impl From<BlobDef> for Blob {
     fn from(value: BlobDef) -> Self {
         Self(value.0)
     }
 }
 
+#[cfg(feature = "fpvm")]
 #[derive(
     rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq,
 )]
@@ -61,21 +73,25 @@ pub struct Bytes48Def {
     bytes: [u8; 48usize],
 }
 
+#[cfg(feature = "fpvm")]
 fn get_48_bytes(value: &Bytes48) -> [u8; 48] {
     value.into_inner()
 }
 
+#[cfg(feature = "fpvm")]
 impl From<Bytes48Def> for Bytes48 {
     fn from(value: Bytes48Def) -> Self {
         Self::from(value.bytes)
     }
 }
 
+#[cfg(feature = "fpvm")]
 #[derive(Clone, Debug, Default)]
 pub struct PreloadedBlobProvider {
     entries: Vec<(B256, Blob)>,
 }
 
+#[cfg(feature = "fpvm")]
 impl From<BlobWitnessData> for PreloadedBlobProvider {
     fn from(value: BlobWitnessData) -> Self {
         let blobs = value
@@ -87,7 +103,7 @@ impl From<BlobWitnessData> for PreloadedBlobProvider {
             blobs.as_slice(),
             value.commitments.as_slice(),
             value.proofs.as_slice(),
-            ethereum_kzg_settings(),
+            crate::kzg::kzg_settings(),
         )
         .expect("Failed to batch validate kzg proofs");
         let hashes = value
@@ -102,6 +118,7 @@ impl From<BlobWitnessData> for PreloadedBlobProvider {
     }
 }
 
+#[cfg(feature = "fpvm")]
 #[async_trait]
 impl BlobProvider for PreloadedBlobProvider {
     type Error = BlobProviderError;
@@ -122,16 +139,32 @@ impl BlobProvider for PreloadedBlobProvider {
     }
 }
 
-pub fn intermediate_outputs(blob_data: &BlobData, blocks: usize) -> anyhow::Result<Vec<B256>> {
-    let mut outputs = vec![];
-    for i in 0..blocks {
-        let index = 32 * i;
-        let bytes: [u8; 32] = blob_data.blob.0[index..index + 32].try_into()?;
-        outputs.push(B256::from(bytes));
-    }
-    Ok(outputs)
+#[cfg(feature = "fpvm")]
+pub fn intermediate_outputs(blob_data: &BlobData, blocks: usize) -> KailuaResult<Vec<B256>> {
+    blob_field_elements(blob_data.blob.as_slice(), blocks)
+}
+
+/// Reads the first `count` field elements packed into the bytes of a blob
+pub fn blob_field_elements(blob: &[u8], count: usize) -> KailuaResult<Vec<B256>> {
+    (0..count)
+        .map(|i| {
+            blob.get(32 * i..32 * (i + 1))
+                .map(B256::from_slice)
+                .ok_or(EncodingError::MissingOutputs(count).into())
+        })
+        .collect()
+}
+
+/// Returns the index of the blob committing to the intermediate output at the given position
+/// of a proposal, and the index of its field element within that blob.
+pub fn io_blob_position(position: u64) -> (usize, usize) {
+    (
+        (position / FIELD_ELEMENTS_PER_BLOB) as usize,
+        (position % FIELD_ELEMENTS_PER_BLOB) as usize,
+    )
 }
 
+/// Maps a hash to a BLS12-381 field element by clearing its two most significant bits
 pub fn hash_to_fe(mut hash: B256) -> B256 {
     hash.0[0] &= u8::MAX >> 2;
     hash
@@ -13,11 +13,11 @@
 // limitations under the License.
 
 use crate::blobs;
+use crate::errors::{ChainError, EncodingError, FetchError, KailuaResult, ProvingError};
 use crate::precondition::PreconditionValidationData;
 use alloy_consensus::Header;
 use alloy_eips::eip4844::FIELD_ELEMENTS_PER_BLOB;
 use alloy_primitives::{Address, Sealed, B256};
-use anyhow::{bail, Context};
 use kona_derive::traits::BlobProvider;
 use kona_driver::Driver;
 use kona_executor::TrieDBProvider;
@@ -28,8 +28,6 @@ use kona_proof::l1::{OracleL1ChainProvider, OraclePipeline};
 use kona_proof::l2::OracleL2ChainProvider;
 use kona_proof::sync::new_pipeline_cursor;
 use kona_proof::{BootInfo, FlushableCache, HintType};
-use op_alloy_genesis::RollupConfig;
-use risc0_zkvm::sha::{Impl as SHA2, Sha256};
 use std::fmt::Debug;
 use std::sync::Arc;
 
@@ -41,7 +39,7 @@ pub fn run_client<
     oracle: Arc<O>,
     boot: Arc<BootInfo>,
     mut beacon: B,
-) -> anyhow::Result<(B256, Option<B256>)>
+) -> KailuaResult<(B256, Option<B256>)>
 where
     <B as BlobProvider>::Error: Debug,
 {
@@ -71,9 +69,23 @@ where
         // invalid.
         let safe_head = fetch_safe_head(oracle.as_ref(), boot.as_ref(), &mut l2_provider).await?;
         if boot.claimed_l2_block_number < safe_head.number {
-            bail!("Invalid Claim");
+            return Err(ChainError::InvalidClaim(format!(
+                "Claimed block {} precedes safe head {}",
+                boot.claimed_l2_block_number, safe_head.number
+            ))
+            .into());
         }
 
+        // Refuse to prove blocks subject to hardforks this build does not implement
+        let claimed_timestamp = safe_head.timestamp
+            + (boot.claimed_l2_block_number - safe_head.number) * boot.rollup_config.block_time;
+        crate::hardforks::check_hardfork_activations(&boot.rollup_config, claimed_timestamp)?;
+
+        // Refuse to prove blocks whose validity depends on other chains in the dependency set
+        #[cfg(feature = "interop")]
+        crate::interop::check_dependency_set(oracle.as_ref(), boot.as_ref(), claimed_timestamp)
+            .await?;
+
         // In the case where the agreed upon L2 output root is the same as the claimed L2 output root,
         // trace extension is detected and we can skip the derivation and execution steps.
         if boot.agreed_l2_output_root == boot.claimed_l2_output_root {
@@ -104,7 +116,8 @@ where
         log("ADVANCE");
         let (number, output_root) = driver
             .advance_to_target(&boot.rollup_config, Some(boot.claimed_l2_block_number))
-            .await?;
+            .await
+            .map_err(|err| ProvingError::Derivation(format!("{err:?}")))?;
 
         // None indicates that there is insufficient L1 data available to produce an L2
         // output root at the claimed block number
@@ -123,7 +136,7 @@ where
 
 /// Fetches the safe head of the L2 chain based on the agreed upon L2 output root in the
 /// [BootInfo].
-async fn fetch_safe_head<O: CommsClient>(
+pub async fn fetch_safe_head<O: CommsClient>(
     caching_oracle: &O,
     boot_info: &BootInfo,
     l2_chain_provider: &mut OracleL2ChainProvider<O>,
@@ -156,128 +169,6 @@ pub fn log(msg: &str) {
     tracing::info!("{msg}");
 }
 
-fn safe_default<V: Debug + Eq>(opt: Option<V>, default: V) -> anyhow::Result<V> {
-    if let Some(v) = opt {
-        if v == default {
-            anyhow::bail!(format!("Unsafe value! {v:?}"))
-        }
-        Ok(v)
-    } else {
-        Ok(default)
-    }
-}
-
-pub fn config_hash(rollup_config: &RollupConfig) -> anyhow::Result<[u8; 32]> {
-    // todo: check whether we need to include this, or if it is loaded from the config address
-    let system_config_hash: [u8; 32] = rollup_config
-        .genesis
-        .system_config
-        .as_ref()
-        .map(|system_config| {
-            let fields = [
-                system_config.batcher_address.0.as_slice(),
-                system_config.overhead.to_be_bytes::<32>().as_slice(),
-                system_config.scalar.to_be_bytes::<32>().as_slice(),
-                system_config.gas_limit.to_be_bytes().as_slice(),
-                safe_default(system_config.base_fee_scalar, u64::MAX)
-                    .context("base_fee_scalar")?
-                    .to_be_bytes()
-                    .as_slice(),
-                safe_default(system_config.blob_base_fee_scalar, u64::MAX)
-                    .context("blob_base_fee_scalar")?
-                    .to_be_bytes()
-                    .as_slice(),
-            ]
-            .concat();
-            let digest = SHA2::hash_bytes(fields.as_slice());
-
-            Ok::<[u8; 32], anyhow::Error>(digest.as_bytes().try_into()?)
-        })
-        .unwrap_or(Ok([0u8; 32]))?;
-    let rollup_config_bytes = [
-        rollup_config.genesis.l1.hash.0.as_slice(),
-        rollup_config.genesis.l2.hash.0.as_slice(),
-        system_config_hash.as_slice(),
-        rollup_config.block_time.to_be_bytes().as_slice(),
-        rollup_config.max_sequencer_drift.to_be_bytes().as_slice(),
-        rollup_config.seq_window_size.to_be_bytes().as_slice(),
-        rollup_config.channel_timeout.to_be_bytes().as_slice(),
-        rollup_config
-            .granite_channel_timeout
-            .to_be_bytes()
-            .as_slice(),
-        rollup_config.l1_chain_id.to_be_bytes().as_slice(),
-        rollup_config.l2_chain_id.to_be_bytes().as_slice(),
-        rollup_config
-            .base_fee_params
-            .max_change_denominator
-            .to_be_bytes()
-            .as_slice(),
-        rollup_config
-            .base_fee_params
-            .elasticity_multiplier
-            .to_be_bytes()
-            .as_slice(),
-        rollup_config
-            .canyon_base_fee_params
-            .max_change_denominator
-            .to_be_bytes()
-            .as_slice(),
-        rollup_config
-            .canyon_base_fee_params
-            .elasticity_multiplier
-            .to_be_bytes()
-            .as_slice(),
-        safe_default(rollup_config.regolith_time, u64::MAX)
-            .context("regolith_time")?
-            .to_be_bytes()
-            .as_slice(),
-        safe_default(rollup_config.canyon_time, u64::MAX)
-            .context("canyon_time")?
-            .to_be_bytes()
-            .as_slice(),
-        safe_default(rollup_config.delta_time, u64::MAX)
-            .context("delta_time")?
-            .to_be_bytes()
-            .as_slice(),
-        safe_default(rollup_config.ecotone_time, u64::MAX)
-            .context("ecotone_time")?
-            .to_be_bytes()
-            .as_slice(),
-        safe_default(rollup_config.fjord_time, u64::MAX)
-            .context("fjord_time")?
-            .to_be_bytes()
-            .as_slice(),
-        safe_default(rollup_config.granite_time, u64::MAX)
-            .context("granite_time")?
-            .to_be_bytes()
-            .as_slice(),
-        safe_default(rollup_config.holocene_time, u64::MAX)
-            .context("holocene_time")?
-            .to_be_bytes()
-            .as_slice(),
-        safe_default(rollup_config.blobs_enabled_l1_timestamp, u64::MAX)
-            .context("blobs_enabled_timestmap")?
-            .to_be_bytes()
-            .as_slice(),
-        rollup_config.batch_inbox_address.0.as_slice(),
-        rollup_config.deposit_contract_address.0.as_slice(),
-        rollup_config.l1_system_config_address.0.as_slice(),
-        rollup_config.protocol_versions_address.0.as_slice(),
-        safe_default(rollup_config.superchain_config_address, Address::ZERO)
-            .context("superchain_config_address")?
-            .0
-            .as_slice(),
-        safe_default(rollup_config.da_challenge_address, Address::ZERO)
-            .context("da_challenge_address")?
-            .0
-            .as_slice(),
-    ]
-    .concat();
-    let digest = SHA2::hash_bytes(rollup_config_bytes.as_slice());
-    Ok::<[u8; 32], anyhow::Error>(digest.as_bytes().try_into()?)
-}
-
 pub async fn validate_precondition<
     O: CommsClient + Send + Sync + Debug,
     B: BlobProvider + Send + Sync + Debug + Clone,
@@ -286,7 +177,7 @@ pub async fn validate_precondition<
     oracle: Arc<O>,
     boot: Arc<BootInfo>,
     beacon: &mut B,
-) -> anyhow::Result<B256>
+) -> KailuaResult<B256>
 where
     <B as BlobProvider>::Error: Debug,
 {
@@ -303,28 +194,28 @@ where
             ))
             .await
             .map_err(OracleProviderError::Preimage)?,
-    )?;
+    )
+    .map_err(|_| EncodingError::Malformed("precondition validation data"))?;
     let precondition_hash = precondition_validation_data.precondition_hash();
     // Read the blobs to validate
     let mut blobs = Vec::new();
     for request in precondition_validation_data.validated_blobs {
-        #[cfg(not(target_os = "zkvm"))]
         let expected_hash = request.blob_hash.hash;
-
         let response = beacon
             .get_blobs(&request.block_ref, &[request.blob_hash])
             .await
-            .unwrap();
+            .map_err(|_| FetchError::MissingBlob(expected_hash))?;
         let blob = *response[0];
         #[cfg(not(target_os = "zkvm"))]
         {
             let blob = c_kzg::Blob::new(blob.0);
-            let commitment = c_kzg::KzgCommitment::blob_to_kzg_commitment(
-                &blob,
-                c_kzg::ethereum_kzg_settings(),
-            )?;
+            let commitment =
+                c_kzg::KzgCommitment::blob_to_kzg_commitment(&blob, crate::kzg::kzg_settings())
+                    .map_err(|err| EncodingError::Kzg(format!("{err:?}")))?;
             let hash = alloy_eips::eip4844::kzg_to_versioned_hash(commitment.as_slice());
-            assert_eq!(hash, expected_hash);
+            if hash != expected_hash {
+                return Err(FetchError::MissingBlob(expected_hash).into());
+            }
         }
 
         blobs.push(blob);
@@ -335,17 +226,22 @@ where
         if blobs[0][index..index + 32] != blobs[1][index..index + 32] {
             let agreed_l2_output_root_fe = blobs::hash_to_fe(boot.agreed_l2_output_root);
             if i == 0 {
-                bail!("Precondition validation failed at first element");
+                return Err(ProvingError::Precondition(String::from(
+                    "Blobs diverge at first element",
+                ))
+                .into());
             } else if &blobs[0][index - 32..index] != agreed_l2_output_root_fe.as_slice() {
-                bail!(
+                return Err(ProvingError::Precondition(format!(
                     "Agreed output {} not found in contender blob before sub-offset {i}",
                     boot.agreed_l2_output_root
-                );
+                ))
+                .into());
             } else if &blobs[1][index - 32..index] != agreed_l2_output_root_fe.as_slice() {
-                bail!(
+                return Err(ProvingError::Precondition(format!(
                     "Agreed output {} not found in proposal before sub-offset {i}",
                     boot.agreed_l2_output_root
-                );
+                ))
+                .into());
             }
             break;
         }
@@ -0,0 +1,174 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::errors::{EncodingError, KailuaError, KailuaResult};
+use op_alloy_genesis::RollupConfig;
+use risc0_zkvm::sha::{Impl as SHA2, Sha256};
+use std::fmt::Debug;
+
+fn safe_default<V: Debug + Eq>(opt: Option<V>, default: V, field: &'static str) -> KailuaResult<V> {
+    if let Some(v) = opt {
+        if v == default {
+            return Err(EncodingError::UnsafeValue {
+                field,
+                value: format!("{v:?}"),
+            }
+            .into());
+        }
+        Ok(v)
+    } else {
+        Ok(default)
+    }
+}
+
+pub fn config_hash(rollup_config: &RollupConfig) -> KailuaResult<[u8; 32]> {
+    // todo: check whether we need to include this, or if it is loaded from the config address
+    let system_config_hash: [u8; 32] = rollup_config
+        .genesis
+        .system_config
+        .as_ref()
+        .map(|system_config| {
+            let fields = [
+                system_config.batcher_address.0.as_slice(),
+                system_config.overhead.to_be_bytes::<32>().as_slice(),
+                system_config.scalar.to_be_bytes::<32>().as_slice(),
+                system_config.gas_limit.to_be_bytes().as_slice(),
+                safe_default(system_config.base_fee_scalar, u64::MAX, "base_fee_scalar")?
+                    .to_be_bytes()
+                    .as_slice(),
+                safe_default(
+                    system_config.blob_base_fee_scalar,
+                    u64::MAX,
+                    "blob_base_fee_scalar",
+                )?
+                .to_be_bytes()
+                .as_slice(),
+            ]
+            .concat();
+            let digest = SHA2::hash_bytes(fields.as_slice());
+
+            Ok::<[u8; 32], KailuaError>(
+                digest
+                    .as_bytes()
+                    .try_into()
+                    .map_err(|_| EncodingError::Malformed("system_config_hash"))?,
+            )
+        })
+        .unwrap_or(Ok([0u8; 32]))?;
+    let rollup_config_bytes = [
+        rollup_config.genesis.l1.hash.0.as_slice(),
+        rollup_config.genesis.l2.hash.0.as_slice(),
+        system_config_hash.as_slice(),
+        rollup_config.block_time.to_be_bytes().as_slice(),
+        rollup_config.max_sequencer_drift.to_be_bytes().as_slice(),
+        rollup_config.seq_window_size.to_be_bytes().as_slice(),
+        rollup_config.channel_timeout.to_be_bytes().as_slice(),
+        rollup_config
+            .granite_channel_timeout
+            .to_be_bytes()
+            .as_slice(),
+        rollup_config.l1_chain_id.to_be_bytes().as_slice(),
+        rollup_config.l2_chain_id.to_be_bytes().as_slice(),
+        rollup_config
+            .base_fee_params
+            .max_change_denominator
+            .to_be_bytes()
+            .as_slice(),
+        rollup_config
+            .base_fee_params
+            .elasticity_multiplier
+            .to_be_bytes()
+            .as_slice(),
+        rollup_config
+            .canyon_base_fee_params
+            .max_change_denominator
+            .to_be_bytes()
+            .as_slice(),
+        rollup_config
+            .canyon_base_fee_params
+            .elasticity_multiplier
+            .to_be_bytes()
+            .as_slice(),
+        safe_default(rollup_config.regolith_time, u64::MAX, "regolith_time")?
+            .to_be_bytes()
+            .as_slice(),
+        safe_default(rollup_config.canyon_time, u64::MAX, "canyon_time")?
+            .to_be_bytes()
+            .as_slice(),
+        safe_default(rollup_config.delta_time, u64::MAX, "delta_time")?
+            .to_be_bytes()
+            .as_slice(),
+        safe_default(rollup_config.ecotone_time, u64::MAX, "ecotone_time")?
+            .to_be_bytes()
+            .as_slice(),
+        safe_default(rollup_config.fjord_time, u64::MAX, "fjord_time")?
+            .to_be_bytes()
+            .as_slice(),
+        safe_default(rollup_config.granite_time, u64::MAX, "granite_time")?
+            .to_be_bytes()
+            .as_slice(),
+        safe_default(rollup_config.holocene_time, u64::MAX, "holocene_time")?
+            .to_be_bytes()
+            .as_slice(),
+        safe_default(
+            rollup_config.blobs_enabled_l1_timestamp,
+            u64::MAX,
+            "blobs_enabled_timestmap",
+        )?
+        .to_be_bytes()
+        .as_slice(),
+        rollup_config.batch_inbox_address.0.as_slice(),
+        rollup_config.deposit_contract_address.0.as_slice(),
+        rollup_config.l1_system_config_address.0.as_slice(),
+        rollup_config.protocol_versions_address.0.as_slice(),
+        safe_default(
+            rollup_config.superchain_config_address,
+            Address::ZERO,
+            "superchain_config_address",
+        )?
+        .0
+        .as_slice(),
+        safe_default(
+            rollup_config.da_challenge_address,
+            Address::ZERO,
+            "da_challenge_address",
+        )?
+        .0
+        .as_slice(),
+    ]
+    .concat();
+    let digest = SHA2::hash_bytes(rollup_config_bytes.as_slice());
+    Ok(digest
+        .as_bytes()
+        .try_into()
+        .map_err(|_| EncodingError::Malformed("rollup_config_hash"))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vectors::golden_rollup_config;
+
+    #[test]
+    fn rollup_config_round_trip_preserves_hash() {
+        let config = golden_rollup_config();
+        let encoded = serde_json::to_string(&config).unwrap();
+        let decoded: RollupConfig = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded, config);
+        assert_eq!(
+            config_hash(&decoded).unwrap(),
+            config_hash(&config).unwrap()
+        );
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::blobs::hash_to_fe;
+use crate::errors::{EncodingError, KailuaResult};
+use alloy_primitives::B256;
+use risc0_zkvm::sha::{Impl as SHA2, Sha256};
+use serde::{Deserialize, Serialize};
+
+/// The domain separation tag of output roots under [FieldEncoding::V1]
+pub const OUTPUT_ROOT_DOMAIN: &[u8] = b"kailua/output-root/v1";
+
+/// The scheme used to commit to output roots as blob field elements, as declared by the game
+/// contract's `FIELD_ENCODING_VERSION`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldEncoding {
+    /// Clears the two most significant bits of the output root (`KailuaLib.hashToFe`)
+    #[default]
+    V0,
+    /// Clears the two most significant bits of the sha256 digest of the output root prefixed by
+    /// [OUTPUT_ROOT_DOMAIN]
+    V1,
+}
+
+impl FieldEncoding {
+    /// Returns the encoding declared by a game contract.
+    ///
+    /// [FieldEncoding::V1] is rejected because neither the FPVM, which compares blob field
+    /// elements using [hash_to_fe], nor `KailuaTournament.prove`, which uses `KailuaLib.hashToFe`,
+    /// support it yet.
+    pub fn from_version(version: u8) -> KailuaResult<Self> {
+        match version {
+            0 => Ok(Self::V0),
+            _ => Err(EncodingError::FieldEncodingVersion(version).into()),
+        }
+    }
+
+    pub fn version(&self) -> u8 {
+        match self {
+            Self::V0 => 0,
+            Self::V1 => 1,
+        }
+    }
+
+    /// Maps an output root to the field element committed to in proposal blobs
+    pub fn output_to_fe(&self, output_root: B256) -> B256 {
+        match self {
+            Self::V0 => hash_to_fe(output_root),
+            Self::V1 => hash_to_fe_in_domain(OUTPUT_ROOT_DOMAIN, output_root),
+        }
+    }
+}
+
+/// Maps a hash to a field element bound to the given domain, so that the same hash committed to
+/// for different purposes never yields the same field element.
+pub fn hash_to_fe_in_domain(domain: &[u8], hash: B256) -> B256 {
+    let digest = *SHA2::hash_bytes(&[domain, hash.as_slice()].concat());
+    hash_to_fe(B256::from_slice(digest.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_supported_versions_are_accepted() {
+        assert_eq!(FieldEncoding::from_version(0).unwrap(), FieldEncoding::V0);
+        assert!(FieldEncoding::from_version(1).is_err());
+        assert!(FieldEncoding::from_version(2).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy_primitives::B256;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The class of an error, which determines how retrying and alerting should react to it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// Data could not be fetched from a node, oracle or storage backend
+    Fetch,
+    /// Data could not be encoded or decoded
+    Encoding,
+    /// The fault proof program or its prover failed
+    Proving,
+    /// The chain is in a state that can not be proven
+    Chain,
+}
+
+impl ErrorClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorClass::Fetch => "fetch",
+            ErrorClass::Encoding => "encoding",
+            ErrorClass::Proving => "proving",
+            ErrorClass::Chain => "chain",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Fetch, Self::Encoding, Self::Proving, Self::Chain]
+            .into_iter()
+            .find(|class| class.name() == name)
+    }
+
+    /// Whether another attempt may succeed once the cause of the error went away
+    pub fn is_transient(&self) -> bool {
+        matches!(self, ErrorClass::Fetch)
+    }
+
+    /// Returns the class of the first typed error in the chain of causes of the given error
+    pub fn of(error: &anyhow::Error) -> Option<Self> {
+        error.chain().find_map(|cause| {
+            if let Some(error) = cause.downcast_ref::<KailuaError>() {
+                Some(error.class())
+            } else if cause.is::<FetchError>() {
+                Some(ErrorClass::Fetch)
+            } else if cause.is::<EncodingError>() {
+                Some(ErrorClass::Encoding)
+            } else if cause.is::<ProvingError>() {
+                Some(ErrorClass::Proving)
+            } else if cause.is::<ChainError>() {
+                Some(ErrorClass::Chain)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns the exit code with which a binary reports this class of fatal error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorClass::Fetch => 10,
+            ErrorClass::Encoding => 11,
+            ErrorClass::Proving => 12,
+            ErrorClass::Chain => 13,
+        }
+    }
+
+    /// Parses the class reported through [ErrorClass::exit_code]
+    pub fn from_exit_code(code: i32) -> Option<Self> {
+        [Self::Fetch, Self::Encoding, Self::Proving, Self::Chain]
+            .into_iter()
+            .find(|class| class.exit_code() == code)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum FetchError {
+    #[error("Preimage {0} is unavailable")]
+    MissingPreimage(B256),
+    #[error("Blob {0} is unavailable")]
+    MissingBlob(B256),
+    #[error("Failed to fetch {what}: {message}")]
+    Request { what: &'static str, message: String },
+    #[error("Oracle error: {0}")]
+    Oracle(String),
+}
+
+#[derive(Debug, Error)]
+pub enum EncodingError {
+    #[error("Unsupported proof journal of length {0}")]
+    JournalLength(usize),
+    #[error("Malformed {0}")]
+    Malformed(&'static str),
+    #[error("Unsupported field encoding version {0}")]
+    FieldEncodingVersion(u8),
+    #[error("Unsafe value for {field}: {value}")]
+    UnsafeValue { field: &'static str, value: String },
+    #[error("Blob holds fewer than {0} intermediate outputs")]
+    MissingOutputs(usize),
+    #[error("KZG error: {0}")]
+    Kzg(String),
+    #[error("Invalid trusted setup: {0}")]
+    TrustedSetup(String),
+    #[error("Input transcript mismatch: {0}")]
+    TranscriptMismatch(String),
+}
+
+#[derive(Debug, Error)]
+pub enum ProvingError {
+    #[error("Precondition validation failed: {0}")]
+    Precondition(String),
+    #[error("Witness of {size} bytes exceeds the zkVM memory budget of {budget} bytes")]
+    WitnessTooLarge { size: usize, budget: usize },
+    #[error("Derivation failed: {0}")]
+    Derivation(String),
+    #[error("Proving backend {backend} failed: {message}")]
+    Backend {
+        backend: &'static str,
+        message: String,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum ChainError {
+    #[error("Invalid claim: {0}")]
+    InvalidClaim(String),
+    #[error("Client built without support for hardforks active at timestamp {timestamp}: {}", hardforks.join(", "))]
+    DisabledHardforks {
+        timestamp: u64,
+        hardforks: Vec<&'static str>,
+    },
+    #[error("Invalid interop dependency set: {0}")]
+    DependencySet(String),
+}
+
+/// The errors returned by the library functions of the kailua crates
+#[derive(Debug, Error)]
+pub enum KailuaError {
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+    #[error(transparent)]
+    Encoding(#[from] EncodingError),
+    #[error(transparent)]
+    Proving(#[from] ProvingError),
+    #[error(transparent)]
+    Chain(#[from] ChainError),
+}
+
+impl KailuaError {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            KailuaError::Fetch(_) => ErrorClass::Fetch,
+            KailuaError::Encoding(_) => ErrorClass::Encoding,
+            KailuaError::Proving(_) => ErrorClass::Proving,
+            KailuaError::Chain(_) => ErrorClass::Chain,
+        }
+    }
+}
+
+#[cfg(feature = "fpvm")]
+impl From<kona_proof::errors::OracleProviderError> for KailuaError {
+    fn from(error: kona_proof::errors::OracleProviderError) -> Self {
+        FetchError::Oracle(error.to_string()).into()
+    }
+}
+
+pub type KailuaResult<T> = Result<T, KailuaError>;
@@ -0,0 +1,106 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::errors::{ChainError, KailuaResult};
+use op_alloy_genesis::RollupConfig;
+
+/// The OP Stack hardforks known to the client, in activation order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hardfork {
+    Regolith,
+    Canyon,
+    Delta,
+    Ecotone,
+    Fjord,
+    Granite,
+    Holocene,
+}
+
+impl Hardfork {
+    pub const ALL: [Hardfork; 7] = [
+        Hardfork::Regolith,
+        Hardfork::Canyon,
+        Hardfork::Delta,
+        Hardfork::Ecotone,
+        Hardfork::Fjord,
+        Hardfork::Granite,
+        Hardfork::Holocene,
+    ];
+
+    /// The name of the hardfork as it appears in the `<name>_time` rollup configuration fields
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hardfork::Regolith => "regolith",
+            Hardfork::Canyon => "canyon",
+            Hardfork::Delta => "delta",
+            Hardfork::Ecotone => "ecotone",
+            Hardfork::Fjord => "fjord",
+            Hardfork::Granite => "granite",
+            Hardfork::Holocene => "holocene",
+        }
+    }
+
+    /// Whether the rules of the hardfork apply to the L2 block at the given timestamp
+    pub fn is_active(&self, rollup_config: &RollupConfig, timestamp: u64) -> bool {
+        match self {
+            Hardfork::Regolith => rollup_config.is_regolith_active(timestamp),
+            Hardfork::Canyon => rollup_config.is_canyon_active(timestamp),
+            Hardfork::Delta => rollup_config.is_delta_active(timestamp),
+            Hardfork::Ecotone => rollup_config.is_ecotone_active(timestamp),
+            Hardfork::Fjord => rollup_config.is_fjord_active(timestamp),
+            Hardfork::Granite => rollup_config.is_granite_active(timestamp),
+            Hardfork::Holocene => rollup_config.is_holocene_active(timestamp),
+        }
+    }
+
+    /// Whether this build of the client was compiled with the rules of the hardfork
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            // Steady-state derivation is only proven by builds that opt into it
+            Hardfork::Holocene => cfg!(feature = "holocene"),
+            _ => true,
+        }
+    }
+}
+
+/// Returns the names of the hardforks this build of the client can prove blocks under
+pub fn enabled_hardforks() -> Vec<&'static str> {
+    Hardfork::ALL
+        .iter()
+        .filter(|hardfork| hardfork.is_enabled())
+        .map(Hardfork::name)
+        .collect()
+}
+
+/// Fails if a hardfork this build of the client was compiled without is active at the given L2
+/// block timestamp. As activations are monotonic, checking the claimed block covers all blocks
+/// derived before it.
+pub fn check_hardfork_activations(
+    rollup_config: &RollupConfig,
+    timestamp: u64,
+) -> KailuaResult<()> {
+    let disabled = Hardfork::ALL
+        .iter()
+        .filter(|hardfork| !hardfork.is_enabled() && hardfork.is_active(rollup_config, timestamp))
+        .map(Hardfork::name)
+        .collect::<Vec<_>>();
+    if !disabled.is_empty() {
+        return Err(ChainError::DisabledHardforks {
+            timestamp,
+            hardforks: disabled,
+        }
+        .into());
+    }
+    Ok(())
+}
@@ -0,0 +1,96 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Experimental hooks for proving outputs of chains in an OP Stack interop dependency set.
+//!
+//! The dependency set is served by the host under a fixed key and is not yet committed to by the
+//! proof journal, so these hooks must not be relied upon until the interop specification
+//! stabilizes.
+
+use crate::errors::{ChainError, EncodingError, KailuaResult};
+use alloy_primitives::keccak256;
+use kona_preimage::{CommsClient, PreimageKey, PreimageKeyType};
+use kona_proof::errors::OracleProviderError;
+use kona_proof::BootInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The domain hashed into the preimage key of the dependency set
+pub const DEPENDENCY_SET_DOMAIN: &[u8] = b"kailua/dependency-set";
+
+/// The interop parameters of a chain in the dependency set, as served by the op-supervisor
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainDependency {
+    pub chain_index: u32,
+    pub activation_time: u64,
+    pub history_min_time: u64,
+}
+
+/// The chains whose messages may be executed by each other, keyed by chain id
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencySet {
+    pub dependencies: BTreeMap<u64, ChainDependency>,
+}
+
+impl DependencySet {
+    pub fn to_vec(&self) -> Vec<u8> {
+        pot::to_vec(self).unwrap()
+    }
+
+    /// Returns the ids of the other chains whose messages the chain may execute at the timestamp
+    pub fn active_dependencies(&self, chain_id: u64, timestamp: u64) -> Vec<u64> {
+        if !self.dependencies.contains_key(&chain_id) {
+            return vec![];
+        }
+        self.dependencies
+            .iter()
+            .filter(|(id, dependency)| **id != chain_id && dependency.activation_time <= timestamp)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+/// Returns the key under which the host serves the dependency set to the client
+pub fn dependency_set_key() -> PreimageKey {
+    PreimageKey::new(
+        *keccak256(DEPENDENCY_SET_DOMAIN),
+        PreimageKeyType::GlobalGeneric,
+    )
+}
+
+/// Reads the dependency set served by the host, failing if the claimed block may execute
+/// messages from other chains, as their validity can not be proven yet.
+pub async fn check_dependency_set<O: CommsClient>(
+    oracle: &O,
+    boot: &BootInfo,
+    claimed_timestamp: u64,
+) -> KailuaResult<DependencySet> {
+    let dependency_set: DependencySet = pot::from_slice(
+        &oracle
+            .get(dependency_set_key())
+            .await
+            .map_err(OracleProviderError::Preimage)?,
+    )
+    .map_err(|_| EncodingError::Malformed("dependency set"))?;
+    let dependencies =
+        dependency_set.active_dependencies(boot.rollup_config.l2_chain_id, claimed_timestamp);
+    if !dependencies.is_empty() {
+        return Err(ChainError::DependencySet(format!(
+            "Claimed block may execute messages from chains {dependencies:?}, which is not supported yet"
+        ))
+        .into());
+    }
+    Ok(dependency_set)
+}
@@ -12,13 +12,28 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::errors::{EncodingError, KailuaResult};
 use alloy_primitives::B256;
-use anyhow::Context;
+#[cfg(feature = "fpvm")]
 use kona_proof::BootInfo;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+/// The current version of the packed proof journal encoding.
+///
+/// Version 0 journals lack the version byte and chain id. Contracts verify proofs against a single
+/// FPVM image ID, so moving to a new journal version requires deploying a game implementation with
+/// the rotated image ID.
+pub const PROOF_JOURNAL_VERSION: u8 = 1;
+
+/// The length of a packed version 0 journal
+pub const PROOF_JOURNAL_V0_LEN: usize = 168;
+/// The length of a packed version 1 journal
+pub const PROOF_JOURNAL_V1_LEN: usize = 177;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ProofJournal {
+    /// The encoding version of this journal
+    pub version: u8,
     /// The last finalized L2 output
     pub precondition_output: B256,
     /// The L1 head hash containing the safe L2 chain data that may reproduce the L2 head hash.
@@ -31,24 +46,29 @@ pub struct ProofJournal {
     pub claimed_l2_block_number: u64,
     /// The configuration hash.
     pub config_hash: B256,
+    /// The L2 chain id (zero in version 0 journals).
+    pub l2_chain_id: u64,
 }
 
+#[cfg(feature = "fpvm")]
 impl ProofJournal {
     pub fn new(precondition_output: B256, boot_info: &BootInfo) -> Self {
         Self {
+            version: PROOF_JOURNAL_VERSION,
             precondition_output,
             l1_head: boot_info.l1_head,
             agreed_l2_output_root: boot_info.agreed_l2_output_root,
             claimed_l2_output_root: boot_info.claimed_l2_output_root,
             claimed_l2_block_number: boot_info.claimed_l2_block_number,
-            config_hash: B256::from(crate::client::config_hash(&boot_info.rollup_config).unwrap()),
+            config_hash: B256::from(crate::config::config_hash(&boot_info.rollup_config).unwrap()),
+            l2_chain_id: boot_info.rollup_config.l2_chain_id,
         }
     }
 }
 
 impl ProofJournal {
     pub fn encode_packed(&self) -> Vec<u8> {
-        [
+        let fields = [
             self.precondition_output.as_slice(),
             self.l1_head.as_slice(),
             self.agreed_l2_output_root.as_slice(),
@@ -56,25 +76,101 @@ impl ProofJournal {
             self.claimed_l2_block_number.to_be_bytes().as_slice(),
             self.config_hash.as_slice(),
         ]
-        .concat()
+        .concat();
+        match self.version {
+            0 => fields,
+            _ => [
+                [self.version].as_slice(),
+                fields.as_slice(),
+                self.l2_chain_id.to_be_bytes().as_slice(),
+            ]
+            .concat(),
+        }
     }
 
-    pub fn decode_packed(encoded: &[u8]) -> Result<Self, anyhow::Error> {
+    pub fn decode_packed(encoded: &[u8]) -> KailuaResult<Self> {
+        let (version, fields, l2_chain_id) = match encoded.len() {
+            PROOF_JOURNAL_V0_LEN => (0, encoded, 0),
+            PROOF_JOURNAL_V1_LEN if encoded[0] == 1 => (
+                1,
+                &encoded[1..PROOF_JOURNAL_V1_LEN - 8],
+                u64::from_be_bytes(
+                    encoded[PROOF_JOURNAL_V1_LEN - 8..]
+                        .try_into()
+                        .map_err(|_| EncodingError::Malformed("l2_chain_id"))?,
+                ),
+            ),
+            len => return Err(EncodingError::JournalLength(len).into()),
+        };
         Ok(ProofJournal {
-            precondition_output: encoded[..32].try_into().context("precondition_output")?,
-            l1_head: encoded[32..64].try_into().context("l1_head")?,
-            agreed_l2_output_root: encoded[64..96]
+            version,
+            precondition_output: fields[..32]
+                .try_into()
+                .map_err(|_| EncodingError::Malformed("precondition_output"))?,
+            l1_head: fields[32..64]
                 .try_into()
-                .context("agreed_l2_output_root")?,
-            claimed_l2_output_root: encoded[96..128]
+                .map_err(|_| EncodingError::Malformed("l1_head"))?,
+            agreed_l2_output_root: fields[64..96]
                 .try_into()
-                .context("claimed_l2_output_root")?,
+                .map_err(|_| EncodingError::Malformed("agreed_l2_output_root"))?,
+            claimed_l2_output_root: fields[96..128]
+                .try_into()
+                .map_err(|_| EncodingError::Malformed("claimed_l2_output_root"))?,
             claimed_l2_block_number: u64::from_be_bytes(
-                encoded[128..136]
+                fields[128..136]
                     .try_into()
-                    .context("claimed_l2_block_number")?,
+                    .map_err(|_| EncodingError::Malformed("claimed_l2_block_number"))?,
             ),
-            config_hash: encoded[136..168].try_into().context("config_hash")?,
+            config_hash: fields[136..168]
+                .try_into()
+                .map_err(|_| EncodingError::Malformed("config_hash"))?,
+            l2_chain_id,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn journal(version: u8) -> ProofJournal {
+        ProofJournal {
+            version,
+            precondition_output: B256::repeat_byte(0x11),
+            l1_head: B256::repeat_byte(0x22),
+            agreed_l2_output_root: B256::repeat_byte(0x33),
+            claimed_l2_output_root: B256::repeat_byte(0x44),
+            claimed_l2_block_number: 1234567,
+            config_hash: B256::repeat_byte(0x55),
+            l2_chain_id: if version == 0 { 0 } else { 10 },
+        }
+    }
+
+    #[test]
+    fn journal_bincode_round_trip() {
+        let journal = journal(PROOF_JOURNAL_VERSION);
+        let encoded = bincode::serialize(&journal).unwrap();
+        assert_eq!(
+            bincode::deserialize::<ProofJournal>(&encoded).unwrap(),
+            journal
+        );
+    }
+
+    #[test]
+    fn journal_packed_round_trip() {
+        for (version, len) in [(0, PROOF_JOURNAL_V0_LEN), (1, PROOF_JOURNAL_V1_LEN)] {
+            let journal = journal(version);
+            let encoded = journal.encode_packed();
+            assert_eq!(encoded.len(), len);
+            assert_eq!(ProofJournal::decode_packed(&encoded).unwrap(), journal);
+        }
+    }
+
+    #[test]
+    fn journal_rejects_malformed_encodings() {
+        let mut encoded = journal(1).encode_packed();
+        assert!(ProofJournal::decode_packed(&encoded[1..]).is_err());
+        encoded[0] = 2;
+        assert!(ProofJournal::decode_packed(&encoded).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::errors::{EncodingError, FetchError, KailuaResult};
+use alloy_eips::eip4844::BYTES_PER_BLOB;
+use alloy_primitives::{keccak256, B256};
+use c_kzg::{ethereum_kzg_settings, Blob, KzgCommitment, KzgSettings};
+use std::path::Path;
+
+/// The trusted setup loaded from an override file, shared by all proofs in the process
+static KZG_SETTINGS: spin::Once<KzgSettings> = spin::Once::new();
+
+/// Returns the loaded trusted setup, or the Ethereum setup embedded in c-kzg if none was loaded
+pub fn kzg_settings() -> &'static KzgSettings {
+    KZG_SETTINGS.get().unwrap_or_else(ethereum_kzg_settings)
+}
+
+/// Loads the trusted setup file at the given path for use by all later calls to [kzg_settings],
+/// returning the keccak256 hash of the file.
+///
+/// The file is rejected if its hash differs from the expected one, or if it commits to blobs
+/// differently than the embedded Ethereum setup. Only the first successful load takes effect.
+pub fn load_trusted_setup(path: &Path, expected_hash: Option<B256>) -> KailuaResult<B256> {
+    let data = std::fs::read(path).map_err(|err| FetchError::Request {
+        what: "trusted setup",
+        message: format!("{path:?}: {err}"),
+    })?;
+    let hash = keccak256(&data);
+    if let Some(expected_hash) = expected_hash {
+        if hash != expected_hash {
+            return Err(EncodingError::TrustedSetup(format!(
+                "{path:?} has hash {hash}, expected {expected_hash}"
+            ))
+            .into());
+        }
+    }
+    if KZG_SETTINGS.is_completed() {
+        return Ok(hash);
+    }
+    let settings = KzgSettings::load_trusted_setup_file(path)
+        .map_err(|err| EncodingError::TrustedSetup(format!("{path:?}: {err:?}")))?;
+    if setup_fingerprint(&settings)? != setup_fingerprint(ethereum_kzg_settings())? {
+        return Err(EncodingError::TrustedSetup(format!(
+            "{path:?} is not equivalent to the Ethereum trusted setup"
+        ))
+        .into());
+    }
+    KZG_SETTINGS.call_once(|| settings);
+    Ok(hash)
+}
+
+/// Returns the hash of the commitment to a fixed blob under the given setup, which differs
+/// between setups with different points.
+pub fn setup_fingerprint(settings: &KzgSettings) -> KailuaResult<B256> {
+    let mut blob = [0u8; BYTES_PER_BLOB];
+    // every field element must remain canonical, so its most significant byte is left zero
+    for (i, chunk) in blob.chunks_exact_mut(32).enumerate() {
+        chunk[24..].copy_from_slice(&(i as u64 + 1).to_be_bytes());
+    }
+    let commitment = KzgCommitment::blob_to_kzg_commitment(&Blob::new(blob), settings)
+        .map_err(|err| EncodingError::Kzg(format!("{err:?}")))?;
+    Ok(keccak256(commitment.as_slice()))
+}
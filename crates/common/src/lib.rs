@@ -13,8 +13,23 @@
 // limitations under the License.
 
 pub mod blobs;
+#[cfg(feature = "fpvm")]
 pub mod client;
+pub mod config;
+pub mod encoding;
+pub mod errors;
+pub mod hardforks;
+#[cfg(feature = "interop")]
+pub mod interop;
 pub mod journal;
+#[cfg(feature = "fpvm")]
+pub mod kzg;
+#[cfg(feature = "fpvm")]
 pub mod oracle;
 pub mod precondition;
+pub mod transcript;
+pub mod vectors;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "fpvm")]
 pub mod witness;
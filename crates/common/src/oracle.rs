@@ -14,13 +14,24 @@
 
 use alloy_primitives::keccak256;
 use async_trait::async_trait;
-use kona_preimage::errors::PreimageOracleResult;
+use kona_preimage::errors::{PreimageOracleError, PreimageOracleResult};
 use kona_preimage::{HintWriterClient, PreimageKey, PreimageKeyType, PreimageOracleClient};
 use kona_proof::FlushableCache;
+use lru::LruCache;
 use risc0_zkvm::sha::{Impl as SHA2, Sha256};
 use serde::{Deserialize, Serialize};
+use std::num::NonZeroUsize;
 use std::sync::{Arc, Mutex};
 
+/// The number of resolved preimages kept in memory by the guest oracle for repeated accesses
+pub const WITNESS_LRU_SIZE: usize = 1024;
+
+/// Creates the bounded cache of resolved preimages that the guest oracle keeps, and that the
+/// witness recorder mirrors to omit repeated accesses from the witness.
+pub fn witness_lru<V>() -> LruCache<PreimageKey, V> {
+    LruCache::new(NonZeroUsize::new(WITNESS_LRU_SIZE).unwrap())
+}
+
 #[derive(
     Clone, Debug, Default, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
 )]
@@ -31,9 +42,14 @@ pub struct OracleWitnessData {
 
 pub type PreimageStore = Arc<Mutex<Vec<(PreimageKey, Vec<u8>)>>>;
 
-#[derive(Clone, Debug, Default)]
+pub type PreimageCache = Arc<Mutex<LruCache<PreimageKey, Vec<u8>>>>;
+
+/// An oracle that streams preimages from the witness in access order, dropping each one once
+/// it falls out of the bounded cache of recently resolved preimages.
+#[derive(Clone, Debug)]
 pub struct PreloadedOracle {
     preimages: PreimageStore,
+    cache: PreimageCache,
 }
 
 impl From<OracleWitnessData> for PreloadedOracle {
@@ -63,6 +79,7 @@ impl From<OracleWitnessData> for PreloadedOracle {
             .collect();
         Self {
             preimages: Arc::new(Mutex::new(preimages)),
+            cache: Arc::new(Mutex::new(witness_lru())),
         }
     }
 }
@@ -74,13 +91,26 @@ impl FlushableCache for PreloadedOracle {
 #[async_trait]
 impl PreimageOracleClient for PreloadedOracle {
     async fn get(&self, key: PreimageKey) -> PreimageOracleResult<Vec<u8>> {
+        let mut cache = self.cache.lock().unwrap();
+        if let Some(value) = cache.get(&key) {
+            return Ok(value.clone());
+        }
         let mut preimages = self.preimages.lock().unwrap();
-        loop {
-            let (k, v) = preimages.pop().unwrap();
+        let value = loop {
+            // The witness does not hold the preimage if the stream is exhausted
+            let Some((k, v)) = preimages.pop() else {
+                return Err(PreimageOracleError::KeyNotFound);
+            };
             if k == key {
-                break Ok(v);
+                break v;
             }
+        };
+        // Release the memory of consumed entries as the stream is drained
+        if preimages.len() < preimages.capacity() / 2 {
+            preimages.shrink_to_fit();
         }
+        cache.put(key, value.clone());
+        Ok(value)
     }
 
     async fn get_exact(&self, key: PreimageKey, buf: &mut [u8]) -> PreimageOracleResult<()> {
@@ -0,0 +1,190 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::errors::{EncodingError, KailuaResult};
+use crate::journal::ProofJournal;
+#[cfg(feature = "fpvm")]
+use crate::witness::Witness;
+use alloy_primitives::{keccak256, FixedBytes, B256};
+use serde::{Deserialize, Serialize};
+
+/// The current version of the input transcript format
+pub const TRANSCRIPT_VERSION: u8 = 1;
+
+/// The type byte of local preimage keys, from which the guest loads its boot info
+pub const LOCAL_KEY_TYPE: u8 = 1;
+
+/// The commitment to a single preimage served to the guest
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreimageRecord {
+    /// The typed preimage key
+    pub key: B256,
+    /// The keccak256 hash of the preimage
+    pub value_hash: B256,
+    pub len: u64,
+}
+
+/// A record of everything fed to the guest to produce a receipt, which lets third parties audit
+/// what the receipt attests to without access to the preimages themselves.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InputTranscript {
+    pub version: u8,
+    /// The journal the fault proof program commits to when run over these inputs
+    pub journal: ProofJournal,
+    pub precondition_validation_data_hash: B256,
+    /// The preimages served to the guest, in access order
+    pub preimages: Vec<PreimageRecord>,
+    /// The KZG commitments of the blobs served to the guest, in access order
+    pub blob_commitments: Vec<FixedBytes<48>>,
+    /// The keccak256 digest of all of the above
+    pub digest: B256,
+}
+
+/// Returns the key of the local preimage with the given index
+pub fn local_key(index: u64) -> B256 {
+    let mut key = [0u8; 32];
+    key[0] = LOCAL_KEY_TYPE;
+    key[24..].copy_from_slice(&index.to_be_bytes());
+    B256::from(key)
+}
+
+impl InputTranscript {
+    pub fn new(
+        journal: ProofJournal,
+        precondition_validation_data_hash: B256,
+        preimages: Vec<PreimageRecord>,
+        blob_commitments: Vec<FixedBytes<48>>,
+    ) -> Self {
+        let mut transcript = Self {
+            version: TRANSCRIPT_VERSION,
+            journal,
+            precondition_validation_data_hash,
+            preimages,
+            blob_commitments,
+            digest: B256::ZERO,
+        };
+        transcript.digest = transcript.compute_digest();
+        transcript
+    }
+
+    pub fn compute_digest(&self) -> B256 {
+        let mut data = vec![self.version];
+        data.extend(self.journal.encode_packed());
+        data.extend_from_slice(self.precondition_validation_data_hash.as_slice());
+        for preimage in &self.preimages {
+            data.extend_from_slice(preimage.key.as_slice());
+            data.extend_from_slice(preimage.value_hash.as_slice());
+            data.extend_from_slice(&preimage.len.to_be_bytes());
+        }
+        for commitment in &self.blob_commitments {
+            data.extend_from_slice(commitment.as_slice());
+        }
+        keccak256(data)
+    }
+
+    /// Checks that the transcript is untampered and that the boot info preimages it records are
+    /// those of the journal it commits to.
+    pub fn check(&self) -> KailuaResult<()> {
+        if self.version != TRANSCRIPT_VERSION {
+            return Err(EncodingError::TranscriptMismatch(format!(
+                "unsupported version {}",
+                self.version
+            ))
+            .into());
+        }
+        if self.compute_digest() != self.digest {
+            return Err(EncodingError::TranscriptMismatch(String::from("digest")).into());
+        }
+        // the local preimages the guest loads its boot info from, by kona's key indices
+        let boot_info: [(u64, &str, Vec<u8>); 5] = [
+            (1, "l1 head", self.journal.l1_head.to_vec()),
+            (
+                2,
+                "agreed l2 output root",
+                self.journal.agreed_l2_output_root.to_vec(),
+            ),
+            (
+                3,
+                "claimed l2 output root",
+                self.journal.claimed_l2_output_root.to_vec(),
+            ),
+            (
+                4,
+                "claimed l2 block number",
+                self.journal.claimed_l2_block_number.to_be_bytes().to_vec(),
+            ),
+            (
+                5,
+                "l2 chain id",
+                self.journal.l2_chain_id.to_be_bytes().to_vec(),
+            ),
+        ];
+        for (index, name, value) in boot_info {
+            // version 0 journals do not commit to the chain id
+            if index == 5 && self.journal.version == 0 {
+                continue;
+            }
+            let key = local_key(index);
+            let Some(record) = self.preimages.iter().find(|record| record.key == key) else {
+                return Err(EncodingError::TranscriptMismatch(format!("missing {name}")).into());
+            };
+            if record.value_hash != keccak256(&value) {
+                return Err(EncodingError::TranscriptMismatch(format!("boot info {name}")).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the journal of a receipt commits to exactly the inputs of this transcript
+    pub fn check_journal(&self, journal: &ProofJournal) -> KailuaResult<()> {
+        if journal.encode_packed() != self.journal.encode_packed() {
+            return Err(EncodingError::TranscriptMismatch(format!(
+                "journal {journal:?} differs from {:?}",
+                self.journal
+            ))
+            .into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "fpvm")]
+impl InputTranscript {
+    /// Records the inputs of a witness that yields the given journal
+    pub fn from_witness(witness: &Witness, journal: ProofJournal) -> Self {
+        let preimages = witness
+            .oracle_witness
+            .keys
+            .iter()
+            .zip(&witness.oracle_witness.data)
+            .map(|(key, value)| PreimageRecord {
+                key: B256::from(<[u8; 32]>::from(*key)),
+                value_hash: keccak256(value),
+                len: value.len() as u64,
+            })
+            .collect();
+        let blob_commitments = witness
+            .blobs_witness
+            .commitments
+            .iter()
+            .map(|commitment| FixedBytes::<48>::from_slice(commitment.as_slice()))
+            .collect();
+        Self::new(
+            journal,
+            witness.precondition_validation_data_hash,
+            preimages,
+            blob_commitments,
+        )
+    }
+}
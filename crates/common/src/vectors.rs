@@ -0,0 +1,328 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Golden vectors for the encodings that the contracts must reproduce bit for bit.
+//!
+//! Any change to these encodings must be mirrored in the contracts, and vice versa, so the
+//! vectors below are checked by the tests of this module and by `KailuaVectors.t.sol`.
+
+use crate::errors::{EncodingError, KailuaResult};
+use crate::journal::ProofJournal;
+use alloy_eips::eip1559::BaseFeeParams;
+use alloy_eips::BlockNumHash;
+use alloy_primitives::{hex, Address, B256};
+use op_alloy_genesis::{ChainGenesis, RollupConfig};
+
+/// The journal whose packed encodings are given below
+pub const GOLDEN_JOURNAL: ProofJournal = ProofJournal {
+    version: 1,
+    precondition_output: B256::repeat_byte(0x11),
+    l1_head: B256::repeat_byte(0x22),
+    agreed_l2_output_root: B256::repeat_byte(0x33),
+    claimed_l2_output_root: B256::repeat_byte(0x44),
+    claimed_l2_block_number: 1234567,
+    config_hash: B256::repeat_byte(0x55),
+    l2_chain_id: 10,
+};
+
+/// The version 0 packed encoding of [GOLDEN_JOURNAL]
+pub const GOLDEN_JOURNAL_V0: &str = "1111111111111111111111111111111111111111111111111111111111111111222222222222222222222222222222222222222222222222222222222222222233333333333333333333333333333333333333333333333333333333333333334444444444444444444444444444444444444444444444444444444444444444000000000012d6875555555555555555555555555555555555555555555555555555555555555555";
+
+/// The version 1 packed encoding of [GOLDEN_JOURNAL], as hashed by `KailuaTournament.prove`
+pub const GOLDEN_JOURNAL_V1: &str = "011111111111111111111111111111111111111111111111111111111111111111222222222222222222222222222222222222222222222222222222222222222233333333333333333333333333333333333333333333333333333333333333334444444444444444444444444444444444444444444444444444444444444444000000000012d6875555555555555555555555555555555555555555555555555555555555555555000000000000000a";
+
+/// The sha256 precondition hash of two blobs with hashes `0x66..66` and `0x77..77`
+pub const GOLDEN_PRECONDITION_HASH: &str =
+    "d97c01a61ac2fc9039d811d6066e880b53635c2b582086fcdfbb91c4720f43cd";
+
+/// The configuration hash of [golden_rollup_config]
+pub const GOLDEN_CONFIG_HASH: &str =
+    "12d47f166b38c076588e62e4d8357f0e7d7df8bf2a39150b10aa07a1809638be";
+
+/// Pairs of hashes and the field elements `KailuaLib.hashToFe` maps them to, including the
+/// hashes just below and at the BLS12-381 scalar field modulus
+pub const GOLDEN_FIELD_ELEMENTS: [(B256, B256); 5] = [
+    (B256::ZERO, B256::ZERO),
+    (
+        B256::repeat_byte(0xff),
+        B256::new(hex!(
+            "3fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff"
+        )),
+    ),
+    (
+        B256::new(hex!(
+            "c0ffee0000000000000000000000000000000000000000000000000000c0ffee"
+        )),
+        B256::new(hex!(
+            "00ffee0000000000000000000000000000000000000000000000000000c0ffee"
+        )),
+    ),
+    (
+        B256::new(hex!(
+            "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000000"
+        )),
+        B256::new(hex!(
+            "33eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000000"
+        )),
+    ),
+    (
+        B256::new(hex!(
+            "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001"
+        )),
+        B256::new(hex!(
+            "33eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001"
+        )),
+    ),
+];
+
+/// Positions of intermediate outputs and the (blob, field element) indices committing to them
+pub const GOLDEN_BLOB_POSITIONS: [(u64, (usize, usize)); 5] = [
+    (0, (0, 0)),
+    (1, (0, 1)),
+    (4095, (0, 4095)),
+    (4096, (1, 0)),
+    (8193, (2, 1)),
+];
+
+/// The extra data of a proposal for l2 block 1800, extending the game at factory index 42 with
+/// duplication counter 3, which `KailuaGame` reads at offsets 0x54, 0x5C and 0x64 of its
+/// immutable args.
+pub const GOLDEN_EXTRA_DATA: &str = "0000000000000708000000000000002a0000000000000003";
+
+/// The length of the extra data of a proposal as read by `KailuaGame.extraData`
+pub const EXTRA_DATA_LEN: usize = 0x18;
+
+/// Returns a rollup configuration with distinct values for every hashed field
+pub fn golden_rollup_config() -> RollupConfig {
+    let address = |byte| Address::repeat_byte(byte);
+    RollupConfig {
+        genesis: ChainGenesis {
+            l1: BlockNumHash {
+                hash: B256::repeat_byte(0x01),
+                number: 0,
+            },
+            l2: BlockNumHash {
+                hash: B256::repeat_byte(0x02),
+                number: 0,
+            },
+            system_config: None,
+            ..Default::default()
+        },
+        block_time: 2,
+        max_sequencer_drift: 600,
+        seq_window_size: 3600,
+        channel_timeout: 300,
+        granite_channel_timeout: 50,
+        l1_chain_id: 1,
+        l2_chain_id: 10,
+        base_fee_params: BaseFeeParams::new(50, 6),
+        canyon_base_fee_params: BaseFeeParams::new(250, 6),
+        regolith_time: Some(0),
+        canyon_time: Some(1),
+        delta_time: Some(2),
+        ecotone_time: Some(3),
+        fjord_time: Some(4),
+        granite_time: Some(5),
+        holocene_time: Some(6),
+        blobs_enabled_l1_timestamp: Some(7),
+        batch_inbox_address: address(0xa1),
+        deposit_contract_address: address(0xa2),
+        l1_system_config_address: address(0xa3),
+        protocol_versions_address: address(0xa4),
+        superchain_config_address: Some(address(0xa5)),
+        da_challenge_address: Some(address(0xa6)),
+        ..Default::default()
+    }
+}
+
+/// Packs the extra data of a proposal as expected by `KailuaGame`
+pub fn encode_extra_data(l2_block_number: u64, parent_index: u64, dupe_counter: u64) -> Vec<u8> {
+    [
+        l2_block_number.to_be_bytes(),
+        parent_index.to_be_bytes(),
+        dupe_counter.to_be_bytes(),
+    ]
+    .concat()
+}
+
+/// Unpacks the l2 block number, parent index and duplication counter from proposal extra data
+pub fn decode_extra_data(extra_data: &[u8]) -> KailuaResult<(u64, u64, u64)> {
+    if extra_data.len() != EXTRA_DATA_LEN {
+        return Err(EncodingError::Malformed("proposal extra data").into());
+    }
+    let word = |i: usize| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&extra_data[8 * i..8 * (i + 1)]);
+        u64::from_be_bytes(bytes)
+    };
+    Ok((word(0), word(1), word(2)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blobs::{blob_field_elements, hash_to_fe, io_blob_position};
+    use crate::config::config_hash;
+    use crate::encoding::FieldEncoding;
+    use crate::journal::{PROOF_JOURNAL_V0_LEN, PROOF_JOURNAL_V1_LEN};
+    use crate::precondition::precondition_hash;
+    use alloy_eips::eip4844::{BLS_MODULUS, BYTES_PER_BLOB, FIELD_ELEMENTS_PER_BLOB};
+    use alloy_primitives::U256;
+    use proptest::prelude::*;
+
+    #[test]
+    fn journal_encodings() {
+        let mut journal_v0 = GOLDEN_JOURNAL;
+        journal_v0.version = 0;
+        journal_v0.l2_chain_id = 0;
+        for (journal, golden, len) in [
+            (journal_v0, GOLDEN_JOURNAL_V0, PROOF_JOURNAL_V0_LEN),
+            (GOLDEN_JOURNAL, GOLDEN_JOURNAL_V1, PROOF_JOURNAL_V1_LEN),
+        ] {
+            let encoded = journal.encode_packed();
+            assert_eq!(encoded.len(), len);
+            assert_eq!(hex::encode(&encoded), golden);
+            assert_eq!(ProofJournal::decode_packed(&encoded).unwrap(), journal);
+        }
+    }
+
+    #[test]
+    fn precondition_hash_encoding() {
+        let precondition = precondition_hash(&B256::repeat_byte(0x66), &B256::repeat_byte(0x77));
+        assert_eq!(hex::encode(precondition), GOLDEN_PRECONDITION_HASH);
+    }
+
+    #[test]
+    fn config_hash_encoding() {
+        let config = config_hash(&golden_rollup_config()).unwrap();
+        assert_eq!(hex::encode(config), GOLDEN_CONFIG_HASH);
+    }
+
+    #[test]
+    fn field_elements() {
+        for (hash, fe) in GOLDEN_FIELD_ELEMENTS {
+            assert_eq!(hash_to_fe(hash), fe);
+            assert_eq!(hash_to_fe(fe), fe);
+            for encoding in [FieldEncoding::V0, FieldEncoding::V1] {
+                let encoded = encoding.output_to_fe(hash);
+                assert!(U256::from_be_bytes(encoded.0) < BLS_MODULUS);
+            }
+        }
+    }
+
+    #[test]
+    fn blob_positions() {
+        for (position, indices) in GOLDEN_BLOB_POSITIONS {
+            assert_eq!(io_blob_position(position), indices);
+        }
+    }
+
+    #[test]
+    fn outputs_round_trip_through_blobs() {
+        // intermediate outputs spanning two blobs, as packed into proposal sidecars
+        let outputs = (0..=FIELD_ELEMENTS_PER_BLOB)
+            .map(|i| hash_to_fe(B256::left_padding_from(&(!i).to_be_bytes())))
+            .collect::<Vec<_>>();
+        let blobs = outputs
+            .chunks(FIELD_ELEMENTS_PER_BLOB as usize)
+            .map(|chunk| {
+                let mut blob = chunk.concat();
+                blob.resize(BYTES_PER_BLOB, 0);
+                blob
+            })
+            .collect::<Vec<_>>();
+        for (position, output) in outputs.iter().enumerate() {
+            let (blob, index) = io_blob_position(position as u64);
+            let recovered = blob_field_elements(&blobs[blob], index + 1).unwrap();
+            assert_eq!(recovered[index], *output);
+        }
+        assert!(blob_field_elements(&blobs[1], FIELD_ELEMENTS_PER_BLOB as usize + 1).is_err());
+    }
+
+    /// Values within a small distance of the BLS modulus, with arbitrary high bits set
+    fn near_modulus() -> impl Strategy<Value = B256> {
+        (0u64..1024, any::<bool>(), 0u8..4).prop_map(|(distance, above, high_bits)| {
+            let value = if above {
+                BLS_MODULUS + U256::from(distance)
+            } else {
+                BLS_MODULUS - U256::from(distance + 1)
+            };
+            let mut hash = B256::from(value);
+            hash.0[0] |= high_bits << 6;
+            hash
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn field_elements_are_canonical(
+            hash in prop_oneof![any::<[u8; 32]>().prop_map(B256::from), near_modulus()]
+        ) {
+            let fe = hash_to_fe(hash);
+            prop_assert!(U256::from_be_bytes(fe.0) < BLS_MODULUS);
+            prop_assert_eq!(hash_to_fe(fe), fe);
+            // only the two most significant bits are cleared
+            prop_assert_eq!(&fe.0[1..], &hash.0[1..]);
+            prop_assert_eq!(fe.0[0], hash.0[0] & 0x3f);
+            for encoding in [FieldEncoding::V0, FieldEncoding::V1] {
+                let encoded = encoding.output_to_fe(hash);
+                prop_assert!(U256::from_be_bytes(encoded.0) < BLS_MODULUS);
+            }
+        }
+
+        #[test]
+        fn blob_positions_partition_outputs(position in any::<u32>()) {
+            let (blob, index) = io_blob_position(position as u64);
+            prop_assert!(index < FIELD_ELEMENTS_PER_BLOB as usize);
+            prop_assert_eq!(blob as u64 * FIELD_ELEMENTS_PER_BLOB + index as u64, position as u64);
+        }
+
+        #[test]
+        fn outputs_round_trip_through_sidecars(
+            hashes in prop::collection::vec(
+                prop_oneof![any::<[u8; 32]>().prop_map(B256::from), near_modulus()],
+                1..64,
+            ),
+            offset in 0..2 * FIELD_ELEMENTS_PER_BLOB,
+        ) {
+            // place the outputs at an arbitrary position, possibly straddling two blobs
+            let mut outputs = vec![B256::ZERO; offset as usize];
+            outputs.extend(hashes.into_iter().map(hash_to_fe));
+            let blobs = outputs
+                .chunks(FIELD_ELEMENTS_PER_BLOB as usize)
+                .map(|chunk| {
+                    let mut blob = chunk.concat();
+                    blob.resize(BYTES_PER_BLOB, 0);
+                    blob
+                })
+                .collect::<Vec<_>>();
+            for (position, output) in outputs.iter().enumerate().skip(offset as usize) {
+                let (blob, index) = io_blob_position(position as u64);
+                let recovered = blob_field_elements(&blobs[blob], index + 1).unwrap();
+                prop_assert_eq!(recovered[index], *output);
+            }
+        }
+    }
+
+    #[test]
+    fn extra_data_encoding() {
+        let extra_data = encode_extra_data(1800, 42, 3);
+        assert_eq!(extra_data.len(), EXTRA_DATA_LEN);
+        assert_eq!(hex::encode(&extra_data), GOLDEN_EXTRA_DATA);
+        assert_eq!(decode_extra_data(&extra_data).unwrap(), (1800, 42, 3));
+        assert!(decode_extra_data(&extra_data[1..]).is_err());
+    }
+}
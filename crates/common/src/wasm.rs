@@ -0,0 +1,55 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bindings for verifying Kailua proofs from web frontends and browser wallets.
+
+use crate::config::config_hash;
+use crate::journal::ProofJournal;
+use op_alloy_genesis::RollupConfig;
+use risc0_zkvm::sha::Digest;
+use risc0_zkvm::Receipt;
+use wasm_bindgen::prelude::*;
+
+/// Decodes a packed proof journal of any supported version into its JSON representation
+#[wasm_bindgen(js_name = decodeJournal)]
+pub fn decode_journal(encoded: &[u8]) -> Result<String, JsError> {
+    let journal = ProofJournal::decode_packed(encoded)?;
+    Ok(serde_json::to_string(&journal)?)
+}
+
+/// Packs the JSON representation of a proof journal as committed to by the FPVM
+#[wasm_bindgen(js_name = encodeJournal)]
+pub fn encode_journal(journal: &str) -> Result<Vec<u8>, JsError> {
+    Ok(serde_json::from_str::<ProofJournal>(journal)?.encode_packed())
+}
+
+/// Computes the configuration hash of the JSON representation of a rollup configuration
+#[wasm_bindgen(js_name = rollupConfigHash)]
+pub fn rollup_config_hash(rollup_config: &str) -> Result<Vec<u8>, JsError> {
+    let rollup_config = serde_json::from_str::<RollupConfig>(rollup_config)?;
+    Ok(config_hash(&rollup_config)?.to_vec())
+}
+
+/// Verifies a bincode-encoded receipt against the FPVM image id, returning whether it commits to
+/// the expected packed journal
+#[wasm_bindgen(js_name = verifyReceiptJournal)]
+pub fn verify_receipt_journal(
+    receipt: &[u8],
+    image_id: &[u8],
+    expected_journal: &[u8],
+) -> Result<bool, JsError> {
+    let receipt = bincode::deserialize::<Receipt>(receipt)?;
+    receipt.verify(Digest::try_from(image_id)?)?;
+    Ok(receipt.journal.bytes == expected_journal)
+}
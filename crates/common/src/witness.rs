@@ -14,7 +14,9 @@
 
 use crate::blobs::BlobWitnessData;
 use crate::oracle::OracleWitnessData;
+use alloy_eips::eip4844::BYTES_PER_BLOB;
 use alloy_primitives::B256;
+use kona_preimage::PreimageKeyType;
 use serde::{Deserialize, Serialize};
 
 #[derive(
@@ -37,3 +39,42 @@ impl From<B256Def> for B256 {
         B256::new(value.0)
     }
 }
+
+/// A breakdown of the data fed to the guest by a witness
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct WitnessSize {
+    pub preimage_count: usize,
+    pub preimage_bytes: usize,
+    pub blob_count: usize,
+    pub blob_bytes: usize,
+    /// The size of the local preimages the guest loads its boot info from
+    pub boot_info_bytes: usize,
+}
+
+impl WitnessSize {
+    pub fn total_bytes(&self) -> usize {
+        self.preimage_bytes + self.blob_bytes + self.boot_info_bytes
+    }
+}
+
+impl Witness {
+    pub fn size(&self) -> WitnessSize {
+        let mut size = WitnessSize::default();
+        for (key, value) in self
+            .oracle_witness
+            .keys
+            .iter()
+            .zip(&self.oracle_witness.data)
+        {
+            if matches!(key.key_type(), PreimageKeyType::Local) {
+                size.boot_info_bytes += value.len();
+            } else {
+                size.preimage_count += 1;
+                size.preimage_bytes += value.len();
+            }
+        }
+        size.blob_count = self.blobs_witness.blobs.len();
+        size.blob_bytes = size.blob_count * (BYTES_PER_BLOB + 2 * 48);
+        size
+    }
+}
@@ -68,8 +68,10 @@ contract KailuaGame is KailuaTournament {
         bytes32 _imageId,
         bytes32 _configHash,
         uint256 _proposalBlockCount,
+        uint256 _outputBlockSpan,
         GameType _gameType,
         IDisputeGameFactory _disputeGameFactory,
+        uint64 _l2ChainId,
         uint256 _genesisTimeStamp,
         uint256 _l2BlockTime,
         uint256 _proposalTimeGap,
@@ -81,8 +83,10 @@ contract KailuaGame is KailuaTournament {
             _imageId,
             _configHash,
             _proposalBlockCount,
+            _outputBlockSpan,
             _gameType,
-            _disputeGameFactory
+            _disputeGameFactory,
+            _l2ChainId
         )
     {
         MAX_CLOCK_DURATION = _maxClockDuration;
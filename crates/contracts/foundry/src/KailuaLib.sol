@@ -167,6 +167,39 @@ library KailuaLib {
         fe = ((hash << 2) >> 2);
     }
 
+    /// @notice Returns the digest of the packed proof journal committed to by the FPVM
+    function journalDigest(
+        uint8 journalVersion,
+        bytes32 preconditionHash,
+        bytes32 l1Head,
+        bytes32 acceptedOutput,
+        bytes32 computedOutput,
+        uint64 claimBlockNumber,
+        bytes32 configHash,
+        uint64 l2ChainId
+    ) internal pure returns (bytes32 digest) {
+        digest = sha256(
+            abi.encodePacked(
+                // The journal encoding version
+                journalVersion,
+                // The parent proposal's claim hash
+                preconditionHash,
+                // The L1 head hash containing the safe L2 chain data that may reproduce the L2 head hash.
+                l1Head,
+                // The latest finalized L2 output root.
+                acceptedOutput,
+                // The L2 output root claim.
+                computedOutput,
+                // The L2 claim block number.
+                claimBlockNumber,
+                // The configuration hash for this game
+                configHash,
+                // The L2 chain id
+                l2ChainId
+            )
+        );
+    }
+
     function verifyKZGBlobProof(
         bytes32 versionedHash,
         uint32 index,
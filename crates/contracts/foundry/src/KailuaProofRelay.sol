@@ -0,0 +1,82 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.24;
+
+/// @notice Forwards calls made with compressed calldata on behalf of a single owner.
+/// @dev The calldata of a relayed call is the 20-byte target address followed by the call payload, with runs of up
+///      to 128 zero (or 0xff) bytes replaced by a zero byte followed by the run length minus one (with the top bit
+///      set for 0xff runs). As this relay becomes the caller of the target, `KailuaTournament.prove` records the
+///      relay instead of the owner in `prover[u][v]`, so the treasury pays the bonds of proposers eliminated by
+///      relayed proofs to the relay. These payouts stay in the relay until someone calls `sweep()`.
+contract KailuaProofRelay {
+    /// @notice The only address allowed to relay calls
+    address internal immutable OWNER;
+
+    constructor(address _owner) {
+        OWNER = _owner;
+    }
+
+    /// @notice Accepts payouts to the relay
+    receive() external payable {}
+
+    /// @notice Transfers the balance of the relay to its owner
+    function sweep() external {
+        (bool success,) = OWNER.call{value: address(this).balance}(hex"");
+        require(success, "KailuaProofRelay: sweep failed");
+    }
+
+    /// @notice Decompresses the calldata and forwards the call to its target
+    fallback() external payable {
+        require(msg.sender == OWNER, "KailuaProofRelay: caller is not the owner");
+        address target;
+        bytes memory payload;
+        /// @solidity memory-safe-assembly
+        assembly {
+            let data := mload(0x40)
+            let o := add(data, 0x20)
+            for { let i := 0 } lt(i, calldatasize()) { i := add(i, 1) } {
+                let c := byte(0, calldataload(i))
+                switch c
+                case 0 {
+                    i := add(i, 1)
+                    let d := byte(0, calldataload(i))
+                    let n := add(1, and(d, 0x7f))
+                    let fill := mul(iszero(iszero(and(d, 0x80))), not(0))
+                    for { let j := 0 } lt(j, n) { j := add(j, 0x20) } { mstore(add(o, j), fill) }
+                    o := add(o, n)
+                }
+                default {
+                    mstore8(o, c)
+                    o := add(o, 1)
+                }
+            }
+            let length := sub(o, add(data, 0x20))
+            if lt(length, 20) { revert(0, 0) }
+            target := shr(96, mload(add(data, 0x20)))
+            // reuse the bytes of the target address to store the length of the payload
+            payload := add(data, 20)
+            mstore(payload, sub(length, 20))
+            // runs may have been written up to a word past the end of the data
+            mstore(0x40, and(add(o, 0x3f), not(0x1f)))
+        }
+        (bool success, bytes memory result) = target.call{value: msg.value}(payload);
+        if (!success) {
+            assembly {
+                revert(add(result, 0x20), mload(result))
+            }
+        }
+    }
+}
@@ -39,6 +39,12 @@ abstract contract KailuaTournament is Clone, IDisputeGame {
     /// @notice The number of blocks a claim must cover
     uint256 internal immutable PROPOSAL_BLOCK_COUNT;
 
+    /// @notice The number of blocks between consecutive outputs committed to by a claim
+    uint256 internal immutable OUTPUT_BLOCK_SPAN;
+
+    /// @notice The number of outputs a claim must commit to, including its root claim
+    uint256 internal immutable PROPOSAL_OUTPUT_COUNT;
+
     /// @notice The number of blobs a claim must provide
     uint256 internal immutable PROPOSAL_BLOBS;
 
@@ -48,6 +54,15 @@ abstract contract KailuaTournament is Clone, IDisputeGame {
     /// @notice The dispute game factory
     IDisputeGameFactory internal immutable DISPUTE_GAME_FACTORY;
 
+    /// @notice The chain id of the L2 network
+    uint64 internal immutable L2_CHAIN_ID;
+
+    /// @notice The version of the proof journal expected from the fault proof program
+    uint8 public constant JOURNAL_VERSION = 1;
+
+    /// @notice The version of the encoding of output roots as blob field elements
+    uint8 public constant FIELD_ENCODING_VERSION = 0;
+
     /// @notice Returns the address of the Kailua Treasury used by tournament instances
     function treasury() public view returns (IKailuaTreasury treasury_) {
         treasury_ = KAILUA_TREASURY;
@@ -73,6 +88,11 @@ abstract contract KailuaTournament is Clone, IDisputeGame {
         proposalBlockCount_ = PROPOSAL_BLOCK_COUNT;
     }
 
+    /// @notice Returns the number of blocks between consecutive outputs committed to by this game
+    function outputBlockSpan() public view returns (uint256 outputBlockSpan_) {
+        outputBlockSpan_ = OUTPUT_BLOCK_SPAN;
+    }
+
     /// @notice Returns the number of blobs containing intermediate blob data
     function proposalBlobs() public view returns (uint256 proposalBlobs_) {
         proposalBlobs_ = PROPOSAL_BLOBS;
@@ -82,24 +102,36 @@ abstract contract KailuaTournament is Clone, IDisputeGame {
         factory_ = DISPUTE_GAME_FACTORY;
     }
 
+    /// @notice Returns the chain id of the L2 network committed to in proof journals
+    function l2ChainId() public view returns (uint64 l2ChainId_) {
+        l2ChainId_ = L2_CHAIN_ID;
+    }
+
     constructor(
         IKailuaTreasury _kailuaTreasury,
         IRiscZeroVerifier _verifierContract,
         bytes32 _imageId,
         bytes32 _configHash,
         uint256 _proposalBlockCount,
+        uint256 _outputBlockSpan,
         GameType _gameType,
-        IDisputeGameFactory _disputeGameFactory
+        IDisputeGameFactory _disputeGameFactory,
+        uint64 _l2ChainId
     ) {
         KAILUA_TREASURY = _kailuaTreasury;
         RISC_ZERO_VERIFIER = _verifierContract;
         FPVM_IMAGE_ID = _imageId;
         ROLLUP_CONFIG_HASH = _configHash;
+        // INVARIANT: Outputs must be committed to at regular intervals ending at the root claim
+        require(_outputBlockSpan > 0 && _proposalBlockCount % _outputBlockSpan == 0, "bad outputBlockSpan");
         PROPOSAL_BLOCK_COUNT = _proposalBlockCount;
-        PROPOSAL_BLOBS = (_proposalBlockCount / (1 << KailuaLib.FIELD_ELEMENTS_PER_BLOB_PO2))
-            + ((_proposalBlockCount % (1 << KailuaLib.FIELD_ELEMENTS_PER_BLOB_PO2)) == 0 ? 0 : 1);
+        OUTPUT_BLOCK_SPAN = _outputBlockSpan;
+        PROPOSAL_OUTPUT_COUNT = _proposalBlockCount / _outputBlockSpan;
+        PROPOSAL_BLOBS = (PROPOSAL_OUTPUT_COUNT / (1 << KailuaLib.FIELD_ELEMENTS_PER_BLOB_PO2))
+            + ((PROPOSAL_OUTPUT_COUNT % (1 << KailuaLib.FIELD_ELEMENTS_PER_BLOB_PO2)) == 0 ? 0 : 1);
         GAME_TYPE = _gameType;
         DISPUTE_GAME_FACTORY = _disputeGameFactory;
+        L2_CHAIN_ID = _l2ChainId;
     }
 
     /// @notice The blob hashes used to create the game
@@ -176,7 +208,7 @@ abstract contract KailuaTournament is Clone, IDisputeGame {
         if (uvo[2] > 0) {
             // Find the divergent blob index
             uint256 divergentBlobIndex = KailuaLib.blobIndex(uvo[2]);
-            if (uvo[2] == PROPOSAL_BLOCK_COUNT - 1) {
+            if (uvo[2] == PROPOSAL_OUTPUT_COUNT - 1) {
                 // If the only difference is the root claim, require all blobs to be equal.
                 divergentBlobIndex = PROPOSAL_BLOBS;
             }
@@ -189,7 +221,7 @@ abstract contract KailuaTournament is Clone, IDisputeGame {
                 }
             }
             // Update required precondition hash from proof if not at a boundary
-            if (KailuaLib.blobPosition(uvo[2]) != 0 && uvo[2] < PROPOSAL_BLOCK_COUNT - 1) {
+            if (KailuaLib.blobPosition(uvo[2]) != 0 && uvo[2] < PROPOSAL_OUTPUT_COUNT - 1) {
                 preconditionHash = sha256(
                     abi.encodePacked(
                         childContracts[0].proposalBlobHashes(divergentBlobIndex).raw(),
@@ -221,7 +253,7 @@ abstract contract KailuaTournament is Clone, IDisputeGame {
         }
 
         // Validate the claimed output roots.
-        if (uvo[2] == PROPOSAL_BLOCK_COUNT - 1) {
+        if (uvo[2] == PROPOSAL_OUTPUT_COUNT - 1) {
             require(proposedOutput[0] == childContracts[0].rootClaim().raw());
             require(proposedOutput[1] == childContracts[1].rootClaim().raw());
         } else {
@@ -249,22 +281,16 @@ abstract contract KailuaTournament is Clone, IDisputeGame {
 
         {
             // Construct the expected journal
-            uint64 claimBlockNumber = uint64(l2BlockNumber() + uvo[2] + 1);
-            bytes32 journalDigest = sha256(
-                abi.encodePacked(
-                    // The parent proposal's claim hash
-                    preconditionHash,
-                    // The L1 head hash containing the safe L2 chain data that may reproduce the L2 head hash.
-                    childContracts[1].l1Head().raw(),
-                    // The latest finalized L2 output root.
-                    acceptedOutput,
-                    // The L2 output root claim.
-                    computedOutput,
-                    // The L2 claim block number.
-                    claimBlockNumber,
-                    // The configuration hash for this game
-                    ROLLUP_CONFIG_HASH
-                )
+            uint64 claimBlockNumber = uint64(l2BlockNumber() + (uvo[2] + 1) * OUTPUT_BLOCK_SPAN);
+            bytes32 journalDigest = KailuaLib.journalDigest(
+                JOURNAL_VERSION,
+                preconditionHash,
+                childContracts[1].l1Head().raw(),
+                acceptedOutput,
+                computedOutput,
+                claimBlockNumber,
+                ROLLUP_CONFIG_HASH,
+                L2_CHAIN_ID
             );
 
             // reverts on failure
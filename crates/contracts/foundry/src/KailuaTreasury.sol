@@ -34,8 +34,10 @@ contract KailuaTreasury is KailuaTournament, IKailuaTreasury {
         bytes32 _imageId,
         bytes32 _configHash,
         uint256 _proposalBlockCount,
+        uint256 _outputBlockSpan,
         GameType _gameType,
-        IDisputeGameFactory _disputeGameFactory
+        IDisputeGameFactory _disputeGameFactory,
+        uint64 _l2ChainId
     )
         KailuaTournament(
             KailuaTreasury(this),
@@ -43,8 +45,10 @@ contract KailuaTreasury is KailuaTournament, IKailuaTreasury {
             _imageId,
             _configHash,
             _proposalBlockCount,
+            _outputBlockSpan,
             _gameType,
-            _disputeGameFactory
+            _disputeGameFactory,
+            _l2ChainId
         )
     {
         proposerOf[address(this)] = address(this);
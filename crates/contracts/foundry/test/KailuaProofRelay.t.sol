@@ -0,0 +1,141 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.24;
+
+import {Test} from "forge-std/Test.sol";
+import "../src/KailuaProofRelay.sol";
+import "../src/KailuaTournament.sol";
+
+/// @notice Records the calldata of the last call it received
+contract CalldataRecorder {
+    bytes public lastCall;
+
+    fallback() external payable {
+        lastCall = msg.data;
+    }
+}
+
+/// @notice Checks that the relay decompresses calldata compressed by `kailua_cli::relay`
+contract KailuaProofRelayTest is Test {
+    address constant TARGET = address(uint160(0x4200000000000000000000000000000000000042));
+
+    /// @notice The output of `compress_calldata` for the `prove` call built by `proveCalldata` to `TARGET`, which
+    ///         ends with a zero run token and has runs of 160 zero bytes and 140 0xff bytes split at 128 bytes.
+    bytes constant GOLDEN_COMPRESSED_PROVE =
+        hex"4200114208ba0b29001e01001e02001d1001001d014002575a0e9e593c0000f959f8c92f12db2869c3395a3b0502d05e"
+        hex"2516446f71f85b0a35acfbc15ff81a39ae7d344fd709f28e860000b4aa8c65c6b64bfe7fe36bd19b3f009e036b6384b5"
+        hex"eca791c62761152d0c79bb0604c104a5fb6f4eb0703f3154bb3db0001d02e0001d0460001d0174c101b42b290decd954"
+        hex"8b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563007f001f00ff008bb10effff405787fa12a823e0f2"
+        hex"b7631cc41b3ba8828b3321ca811111fa75cd3aa3bb5ace002a40001ee0001e01001e20001e30f652222313e28459528d"
+        hex"920b65115c16c04f3efc82aaedc97be59f3f377c0d3fa66cc928b5edb82af9bd49922954155a002e01001e20001e30f3"
+        hex"f7a9fe364faab93b216da50a3214154f22a0a2b415b23a84c8169e8b636ee36e1540171b6c0c960b71a7020d9f600700"
+        hex"2e40001ee0001e01001e20001e30c65a7bb8d6351c1cf70c95a316cc6a92839c986682d98bc35f958f4883f9d2a80175"
+        hex"b7a638427703f0dbe7bb9bbf987a002e01001e20001e30df6966c971051c3d54ec59162606531493a51404a002842f56"
+        hex"00009d7e5cf4a8c7d7b6990105719101dabeb77144f2a338000f";
+
+    KailuaProofRelay relay;
+
+    function setUp() public {
+        relay = new KailuaProofRelay(address(this));
+        vm.etch(TARGET, address(new CalldataRecorder()).code);
+    }
+
+    function word(uint256 i) internal pure returns (bytes32) {
+        return keccak256(abi.encode(i));
+    }
+
+    function fe(bytes32 hash) internal pure returns (bytes32) {
+        return bytes32(uint256(hash) & (type(uint256).max >> 2));
+    }
+
+    function run(uint8 value, uint256 length) internal pure returns (bytes memory result) {
+        result = new bytes(length);
+        for (uint256 i = 0; i < length; i++) {
+            result[i] = bytes1(value);
+        }
+    }
+
+    /// @notice Mirrors the `prove` call compressed in the tests of `kailua_cli::relay`
+    function proveCalldata() internal pure returns (bytes memory) {
+        bytes memory seal = bytes.concat(
+            hex"c101b42b", word(0), run(0x00, 160), run(0xff, 140), bytes2(word(1)), hex"ffff", word(2)
+        );
+        bytes[][2] memory commitments;
+        bytes[][2] memory proofs;
+        for (uint256 i = 0; i < 2; i++) {
+            commitments[i] = new bytes[](1);
+            commitments[i][0] = bytes.concat(word(6 + 2 * i), bytes16(word(7 + 2 * i)));
+            proofs[i] = new bytes[](1);
+            proofs[i][0] = bytes.concat(word(10 + 2 * i), bytes16(word(11 + 2 * i)));
+        }
+        return abi.encodeCall(
+            KailuaTournament.prove,
+            (
+                [uint64(1), 2, 4097],
+                seal,
+                fe(word(3)),
+                [fe(word(4)), fe(bytes32(type(uint256).max))],
+                fe(word(5)),
+                commitments,
+                proofs
+            )
+        );
+    }
+
+    function test_decompressProve() public {
+        bytes memory compressed = GOLDEN_COMPRESSED_PROVE;
+        // the last token is a zero run
+        assertEq(uint8(compressed[compressed.length - 2]), 0);
+        (bool success,) = address(relay).call(compressed);
+        assertTrue(success);
+        assertEq(CalldataRecorder(TARGET).lastCall(), proveCalldata());
+    }
+
+    function test_decompressRuns() public {
+        // runs of 128 zero and 0xff bytes fit a single token, following the compressed target address
+        (bool success,) = address(relay).call(hex"4200114201007f0180ff01");
+        assertTrue(success);
+        assertEq(
+            CalldataRecorder(TARGET).lastCall(),
+            bytes.concat(hex"01", run(0x00, 128), hex"01", run(0xff, 128), hex"01")
+        );
+    }
+
+    function test_decompressTrailingZero() public {
+        // a trailing zero byte without a run length decompresses to a single zero byte
+        (bool success,) = address(relay).call(hex"420011420102ff00");
+        assertTrue(success);
+        assertEq(CalldataRecorder(TARGET).lastCall(), hex"0102ff00");
+    }
+
+    function test_rejectsOtherCallers() public {
+        vm.prank(address(0xbad));
+        (bool success,) = address(relay).call(GOLDEN_COMPRESSED_PROVE);
+        assertFalse(success);
+    }
+
+    function test_sweep() public {
+        vm.deal(address(relay), 1 ether);
+        uint256 balance = address(this).balance;
+        // anyone may sweep payouts to the owner
+        vm.prank(address(0xbad));
+        relay.sweep();
+        assertEq(address(this).balance, balance + 1 ether);
+        assertEq(address(relay).balance, 0);
+    }
+
+    receive() external payable {}
+}
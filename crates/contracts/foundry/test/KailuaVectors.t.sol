@@ -0,0 +1,124 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.24;
+
+import {Test} from "forge-std/Test.sol";
+import "../src/KailuaGame.sol";
+
+/// @notice Checks the contracts against the golden vectors of `kailua_common::vectors`
+contract KailuaVectorsTest is Test {
+    bytes constant GOLDEN_JOURNAL_V1 =
+        hex"011111111111111111111111111111111111111111111111111111111111111111222222222222222222222222222222222222222222222222222222222222222233333333333333333333333333333333333333333333333333333333333333334444444444444444444444444444444444444444444444444444444444444444000000000012d6875555555555555555555555555555555555555555555555555555555555555555000000000000000a";
+
+    bytes32 constant GOLDEN_PRECONDITION_HASH = hex"d97c01a61ac2fc9039d811d6066e880b53635c2b582086fcdfbb91c4720f43cd";
+
+    bytes constant GOLDEN_EXTRA_DATA = hex"0000000000000708000000000000002a0000000000000003";
+
+    KailuaGame game;
+
+    function setUp() public {
+        game = new KailuaGame(
+            IKailuaTreasury(address(0x1)),
+            IRiscZeroVerifier(address(0x2)),
+            bytes32(0),
+            repeatByte(0x55),
+            1,
+            1,
+            GameType.wrap(1337),
+            IDisputeGameFactory(address(0x3)),
+            10,
+            0,
+            2,
+            0,
+            Duration.wrap(3600)
+        );
+    }
+
+    function repeatByte(uint8 value) internal pure returns (bytes32) {
+        return bytes32(uint256(value) * (type(uint256).max / 0xff));
+    }
+
+    /// @notice Calls the game with the immutable args of a clone created by the factory
+    function callClone(bytes4 selector) internal view returns (bytes memory result) {
+        bytes memory args = abi.encodePacked(address(0xc0ffee), repeatByte(0xaa), repeatByte(0xbb), GOLDEN_EXTRA_DATA);
+        bool success;
+        (success, result) = address(game).staticcall(abi.encodePacked(selector, args, uint16(args.length + 2)));
+        require(success, "clone call failed");
+    }
+
+    function test_journalDigest() public view {
+        assertEq(game.JOURNAL_VERSION(), 1);
+        bytes32 digest = KailuaLib.journalDigest(
+            game.JOURNAL_VERSION(),
+            repeatByte(0x11),
+            repeatByte(0x22),
+            repeatByte(0x33),
+            repeatByte(0x44),
+            1234567,
+            repeatByte(0x55),
+            10
+        );
+        assertEq(digest, sha256(GOLDEN_JOURNAL_V1));
+    }
+
+    function test_preconditionHash() public pure {
+        assertEq(sha256(abi.encodePacked(repeatByte(0x66), repeatByte(0x77))), GOLDEN_PRECONDITION_HASH);
+    }
+
+    function test_hashToFe() public pure {
+        bytes32[2][5] memory vectors = [
+            [bytes32(0), bytes32(0)],
+            [repeatByte(0xff), bytes32(0x3fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff)],
+            [
+                bytes32(0xc0ffee0000000000000000000000000000000000000000000000000000c0ffee),
+                bytes32(0x00ffee0000000000000000000000000000000000000000000000000000c0ffee)
+            ],
+            [
+                bytes32(0x73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000000),
+                bytes32(0x33eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000000)
+            ],
+            [
+                bytes32(0x73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001),
+                bytes32(0x33eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001)
+            ]
+        ];
+        for (uint256 i = 0; i < vectors.length; i++) {
+            assertEq(KailuaLib.hashToFe(vectors[i][0]), vectors[i][1]);
+            assertEq(KailuaLib.hashToFe(vectors[i][1]), vectors[i][1]);
+        }
+    }
+
+    function test_blobPositions() public pure {
+        uint256[3][5] memory vectors = [
+            [uint256(0), 0, 0],
+            [uint256(1), 0, 1],
+            [uint256(4095), 0, 4095],
+            [uint256(4096), 1, 0],
+            [uint256(8193), 2, 1]
+        ];
+        for (uint256 i = 0; i < vectors.length; i++) {
+            assertEq(KailuaLib.blobIndex(vectors[i][0]), vectors[i][1]);
+            assertEq(KailuaLib.blobPosition(vectors[i][0]), vectors[i][2]);
+        }
+    }
+
+    function test_extraDataOffsets() public view {
+        assertEq(abi.decode(callClone(game.extraData.selector), (bytes)), GOLDEN_EXTRA_DATA);
+        assertEq(abi.decode(callClone(game.l2BlockNumber.selector), (uint256)), 1800);
+        assertEq(abi.decode(callClone(game.parentGameIndex.selector), (uint64)), 42);
+        assertEq(abi.decode(callClone(game.duplicationCounter.selector), (uint64)), 3);
+    }
+}
@@ -0,0 +1,69 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The foundry build outputs of the deployable contracts, whose `immutableReferences` locate the
+//! immutable variables patched into the deployed bytecode.
+
+/// A contract's build output and the source it was compiled from
+#[derive(Clone, Copy, Debug)]
+pub struct Artifact {
+    /// The contract name
+    pub name: &'static str,
+    /// The source file path relative to the foundry project root
+    pub source: &'static str,
+    /// The foundry output json
+    pub json: &'static str,
+}
+
+impl Artifact {
+    /// Returns the fully qualified `path:name` identifier used by source verification tools
+    pub fn identifier(&self) -> String {
+        format!("{}:{}", self.source, self.name)
+    }
+}
+
+#[cfg(feature = "kailua-core")]
+pub const KAILUA_GAME: Artifact = Artifact {
+    name: "KailuaGame",
+    source: "src/KailuaGame.sol",
+    json: include_str!("../foundry/out/KailuaGame.sol/KailuaGame.json"),
+};
+
+#[cfg(feature = "kailua-core")]
+pub const KAILUA_TREASURY: Artifact = Artifact {
+    name: "KailuaTreasury",
+    source: "src/KailuaTreasury.sol",
+    json: include_str!("../foundry/out/KailuaTreasury.sol/KailuaTreasury.json"),
+};
+
+#[cfg(feature = "verifiers")]
+pub const RISC_ZERO_VERIFIER_ROUTER: Artifact = Artifact {
+    name: "RiscZeroVerifierRouter",
+    source: "src/vendor/FlatR0ImportV1.2.0.sol",
+    json: include_str!("../foundry/out/FlatR0ImportV1.2.0.sol/RiscZeroVerifierRouter.json"),
+};
+
+#[cfg(feature = "verifiers")]
+pub const RISC_ZERO_GROTH16_VERIFIER: Artifact = Artifact {
+    name: "RiscZeroGroth16Verifier",
+    source: "src/vendor/FlatR0ImportV1.2.0.sol",
+    json: include_str!("../foundry/out/FlatR0ImportV1.2.0.sol/RiscZeroGroth16Verifier.json"),
+};
+
+#[cfg(feature = "verifiers")]
+pub const RISC_ZERO_SET_VERIFIER: Artifact = Artifact {
+    name: "RiscZeroSetVerifier",
+    source: "src/vendor/FlatR0ImportV1.2.0.sol",
+    json: include_str!("../foundry/out/FlatR0ImportV1.2.0.sol/RiscZeroSetVerifier.json"),
+};
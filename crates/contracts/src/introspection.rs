@@ -0,0 +1,119 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers deriving common values from the on-chain state of Kailua games.
+
+use crate::{KailuaTournament, KailuaTreasury};
+use alloy::contract::Error;
+use alloy::network::Network;
+use alloy::primitives::U256;
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+
+/// The status of a game that has not been resolved yet
+pub const GAME_IN_PROGRESS: u8 = 0;
+/// The status of a game resolved against its proposer
+pub const GAME_CHALLENGER_WINS: u8 = 1;
+/// The status of a game resolved in favor of its proposer
+pub const GAME_DEFENDER_WINS: u8 = 2;
+
+/// Returns the seconds left as of `timestamp` on a challenge clock of `max_clock_duration`
+/// seconds that started at `created_at`
+pub fn remaining_clock(created_at: u64, max_clock_duration: u64, timestamp: u64) -> u64 {
+    max_clock_duration.saturating_sub(timestamp.saturating_sub(created_at))
+}
+
+/// The conditions that must all hold for a game to be resolvable
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Resolvability {
+    /// The game has not been resolved yet
+    pub in_progress: bool,
+    /// The parent game was resolved in favor of its proposer
+    pub parent_resolved: bool,
+    /// No time is left for challengers to contest the game
+    pub clock_expired: bool,
+    /// The game is the survivor of its parent's tournament
+    pub survivor: bool,
+}
+
+impl Resolvability {
+    pub fn is_resolvable(&self) -> bool {
+        self.in_progress && self.parent_resolved && self.clock_expired && self.survivor
+    }
+}
+
+impl<T: Transport + Clone, P: Provider<T, N>, N: Network>
+    KailuaTournament::KailuaTournamentInstance<T, P, N>
+{
+    pub async fn is_in_progress(&self) -> Result<bool, Error> {
+        Ok(self.status().call().await?._0 == GAME_IN_PROGRESS)
+    }
+
+    /// Returns the seconds left for challengers to contest this game as of `timestamp`, which is
+    /// zero once the game is resolved
+    pub async fn remaining_challenge_clock(&self, timestamp: u64) -> Result<u64, Error> {
+        if !self.is_in_progress().await? {
+            return Ok(0);
+        }
+        Ok(self
+            .getChallengerDuration(U256::from(timestamp))
+            .call()
+            .await?
+            .duration_)
+    }
+
+    /// Returns the time after which this game can no longer be contested, or None once resolved.
+    /// Proofs eliminating this game must land before then.
+    pub async fn proof_deadline(&self) -> Result<Option<u64>, Error> {
+        if !self.is_in_progress().await? {
+            return Ok(None);
+        }
+        let created_at = self.createdAt().call().await?._0;
+        let max_clock_duration = self
+            .getChallengerDuration(U256::from(created_at))
+            .call()
+            .await?
+            .duration_;
+        Ok(Some(created_at + max_clock_duration))
+    }
+
+    /// Returns which of the conditions to resolve this game hold as of `timestamp`
+    pub async fn resolvability(&self, timestamp: u64) -> Result<Resolvability, Error> {
+        let in_progress = self.is_in_progress().await?;
+        let parent =
+            KailuaTournament::new(self.parentGame().call().await?.parentGame_, self.provider());
+        let parent_resolved = parent.status().call().await?._0 == GAME_DEFENDER_WINS;
+        let clock_expired = self.remaining_challenge_clock(timestamp).await? == 0;
+        // Pruning reverts while any match remains unproven
+        let survivor = parent
+            .pruneChildren()
+            .call()
+            .await
+            .is_ok_and(|res| res.survivor == *self.address());
+        Ok(Resolvability {
+            in_progress,
+            parent_resolved,
+            clock_expired,
+            survivor,
+        })
+    }
+
+    /// Returns the participation bond the proposer of this game has paid into the treasury
+    pub async fn bond_at_stake(&self) -> Result<U256, Error> {
+        let treasury =
+            KailuaTreasury::new(self.treasury().call().await?.treasury_, self.provider());
+        let proposer = self.proposer().call().await?.proposer_;
+        Ok(treasury.paidBonds(proposer).call().await?._0)
+    }
+}
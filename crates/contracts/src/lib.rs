@@ -16,84 +16,124 @@
 
 use alloy::sol;
 
+pub mod artifacts;
+
+#[cfg(feature = "kailua-core")]
+pub mod introspection;
+
+#[cfg(feature = "kailua-core")]
 sol!(
     #[sol(rpc)]
     KailuaGame,
     "foundry/out/KailuaGame.sol/KailuaGame.json"
 );
 
+#[cfg(feature = "kailua-core")]
 sol!(
     #[sol(rpc)]
     KailuaTreasury,
     "foundry/out/KailuaTreasury.sol/KailuaTreasury.json"
 );
 
+#[cfg(feature = "kailua-core")]
 sol!(
     #[sol(rpc)]
     KailuaTournament,
     "foundry/out/KailuaTournament.sol/KailuaTournament.json"
 );
 
+#[cfg(feature = "verifiers")]
 sol!(
     #[sol(rpc)]
     IRiscZeroVerifier,
     "foundry/out/FlatR0ImportV1.2.0.sol/IRiscZeroVerifier.json"
 );
 
+#[cfg(feature = "verifiers")]
 sol!(
     #[sol(rpc)]
     RiscZeroVerifierRouter,
     "foundry/out/FlatR0ImportV1.2.0.sol/RiscZeroVerifierRouter.json"
 );
 
+#[cfg(feature = "verifiers")]
 sol!(
     #[sol(rpc)]
     RiscZeroSetVerifier,
     "foundry/out/FlatR0ImportV1.2.0.sol/RiscZeroSetVerifier.json"
 );
 
+#[cfg(feature = "verifiers")]
 sol!(
     #[sol(rpc)]
     RiscZeroGroth16Verifier,
     "foundry/out/FlatR0ImportV1.2.0.sol/RiscZeroGroth16Verifier.json"
 );
 
+#[cfg(feature = "verifiers")]
 sol!(
     #[sol(rpc)]
     RiscZeroMockVerifier,
     "foundry/out/FlatR0ImportV1.2.0.sol/RiscZeroMockVerifier.json"
 );
 
+#[cfg(feature = "kailua-core")]
 sol!(
     #[sol(rpc)]
     OwnableUpgradeable,
     "foundry/out/FlatOPImportV1.4.0.sol/OwnableUpgradeable.json"
 );
 
+#[cfg(feature = "kailua-core")]
+sol!(
+    #[sol(rpc)]
+    IDisputeGame,
+    "foundry/out/FlatOPImportV1.4.0.sol/IDisputeGame.json"
+);
+
+#[cfg(feature = "kailua-core")]
 sol!(
     #[sol(rpc)]
     IDisputeGameFactory,
     "foundry/out/FlatOPImportV1.4.0.sol/IDisputeGameFactory.json"
 );
 
+#[cfg(feature = "kailua-core")]
+sol!(
+    #[sol(rpc)]
+    DisputeGameFactory,
+    "foundry/out/FlatOPImportV1.4.0.sol/DisputeGameFactory.json"
+);
+
+#[cfg(feature = "safe")]
 sol!(
     #[sol(rpc)]
     Safe,
     "foundry/out/FlatOPImportV1.4.0.sol/Safe.json"
 );
 
+#[cfg(feature = "op-portal")]
 sol!(
     #[sol(rpc)]
     OptimismPortal2,
     "foundry/out/FlatOPImportV1.4.0.sol/OptimismPortal2.json"
 );
 
+#[cfg(feature = "op-portal")]
+sol!(
+    #[sol(rpc)]
+    SuperchainConfig,
+    "foundry/out/FlatOPImportV1.4.0.sol/SuperchainConfig.json"
+);
+
+#[cfg(feature = "op-portal")]
 sol!(
     #[sol(rpc)]
     SystemConfig,
     "foundry/out/FlatOPImportV1.4.0.sol/SystemConfig.json"
 );
 
+#[cfg(feature = "verifiers")]
 sol! {
     #[sol(rpc)]
     struct SetVerifierSeal {
@@ -103,3 +143,63 @@ sol! {
         bytes rootSeal;
     }
 }
+
+sol! {
+    #[sol(rpc)]
+    interface IMulticall3 {
+        struct Call3 {
+            /// Target contract to call.
+            address target;
+            /// Whether the batch may proceed if this call reverts.
+            bool allowFailure;
+            /// Calldata to send to the target.
+            bytes callData;
+        }
+
+        struct Result {
+            /// Whether the call succeeded.
+            bool success;
+            /// Data returned by the call.
+            bytes returnData;
+        }
+
+        function aggregate3(Call3[] calldata calls) external payable returns (Result[] memory returnData);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    interface IENSRegistry {
+        function resolver(bytes32 node) external view returns (address);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    interface IENSResolver {
+        function addr(bytes32 node) external view returns (address);
+    }
+}
+
+#[cfg(feature = "safe")]
+sol! {
+    #[sol(rpc)]
+    interface IMultiSendCallOnly {
+        /// Sends multiple transactions packed as (operation, to, value, dataLength, data).
+        function multiSend(bytes memory transactions) external payable;
+    }
+}
+
+#[cfg(feature = "safe")]
+sol! {
+    #[sol(rpc)]
+    interface ITimelockController {
+        function getMinDelay() external view returns (uint256);
+        function hasRole(bytes32 role, address account) external view returns (bool);
+        function isOperationReady(bytes32 id) external view returns (bool);
+        function isOperationDone(bytes32 id) external view returns (bool);
+        function hashOperationBatch(address[] calldata targets, uint256[] calldata values, bytes[] calldata payloads, bytes32 predecessor, bytes32 salt) external pure returns (bytes32);
+        function scheduleBatch(address[] calldata targets, uint256[] calldata values, bytes[] calldata payloads, bytes32 predecessor, bytes32 salt, uint256 delay) external;
+        function executeBatch(address[] calldata targets, uint256[] calldata values, bytes[] calldata payloads, bytes32 predecessor, bytes32 salt) external payable;
+    }
+}
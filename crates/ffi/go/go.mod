@@ -0,0 +1,3 @@
+module github.com/risc0/kailua/crates/ffi/go
+
+go 1.21
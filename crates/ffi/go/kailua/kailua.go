@@ -0,0 +1,136 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Package kailua binds the kailua-ffi C ABI for decoding proof journals and verifying proof files.
+package kailua
+
+/*
+#cgo CFLAGS: -I${SRCDIR}/../../include
+#cgo LDFLAGS: -L${SRCDIR}/../../../../target/release -lkailua_ffi -lm -ldl -lpthread
+#include "kailua.h"
+*/
+import "C"
+
+import (
+	"fmt"
+	"unsafe"
+)
+
+// ProofJournal holds the fields of a proof journal.
+type ProofJournal struct {
+	Version              uint8
+	PreconditionOutput   [32]byte
+	L1Head               [32]byte
+	AgreedL2OutputRoot   [32]byte
+	ClaimedL2OutputRoot  [32]byte
+	ClaimedL2BlockNumber uint64
+	ConfigHash           [32]byte
+	L2ChainId            uint64
+}
+
+func statusError(status C.KailuaStatus) error {
+	switch status {
+	case C.KAILUA_STATUS_OK:
+		return nil
+	case C.KAILUA_STATUS_NULL_POINTER:
+		return fmt.Errorf("kailua: null pointer")
+	case C.KAILUA_STATUS_INVALID_JOURNAL:
+		return fmt.Errorf("kailua: invalid journal")
+	case C.KAILUA_STATUS_INVALID_PROOF_FILE:
+		return fmt.Errorf("kailua: invalid proof file")
+	case C.KAILUA_STATUS_VERIFICATION_FAILED:
+		return fmt.Errorf("kailua: verification failed")
+	case C.KAILUA_STATUS_BUFFER_TOO_SMALL:
+		return fmt.Errorf("kailua: buffer too small")
+	default:
+		return fmt.Errorf("kailua: unknown status %d", status)
+	}
+}
+
+func bytesPtr(data []byte) *C.uint8_t {
+	if len(data) == 0 {
+		return nil
+	}
+	return (*C.uint8_t)(unsafe.Pointer(&data[0]))
+}
+
+func fromC(journal *C.KailuaProofJournal) ProofJournal {
+	return ProofJournal{
+		Version:              uint8(journal.version),
+		PreconditionOutput:   *(*[32]byte)(unsafe.Pointer(&journal.precondition_output)),
+		L1Head:               *(*[32]byte)(unsafe.Pointer(&journal.l1_head)),
+		AgreedL2OutputRoot:   *(*[32]byte)(unsafe.Pointer(&journal.agreed_l2_output_root)),
+		ClaimedL2OutputRoot:  *(*[32]byte)(unsafe.Pointer(&journal.claimed_l2_output_root)),
+		ClaimedL2BlockNumber: uint64(journal.claimed_l2_block_number),
+		ConfigHash:           *(*[32]byte)(unsafe.Pointer(&journal.config_hash)),
+		L2ChainId:            uint64(journal.l2_chain_id),
+	}
+}
+
+func (j *ProofJournal) toC() C.KailuaProofJournal {
+	var journal C.KailuaProofJournal
+	journal.version = C.uint8_t(j.Version)
+	*(*[32]byte)(unsafe.Pointer(&journal.precondition_output)) = j.PreconditionOutput
+	*(*[32]byte)(unsafe.Pointer(&journal.l1_head)) = j.L1Head
+	*(*[32]byte)(unsafe.Pointer(&journal.agreed_l2_output_root)) = j.AgreedL2OutputRoot
+	*(*[32]byte)(unsafe.Pointer(&journal.claimed_l2_output_root)) = j.ClaimedL2OutputRoot
+	journal.claimed_l2_block_number = C.uint64_t(j.ClaimedL2BlockNumber)
+	*(*[32]byte)(unsafe.Pointer(&journal.config_hash)) = j.ConfigHash
+	journal.l2_chain_id = C.uint64_t(j.L2ChainId)
+	return journal
+}
+
+// DecodeJournal decodes a packed proof journal of any supported version.
+func DecodeJournal(encoded []byte) (ProofJournal, error) {
+	var journal C.KailuaProofJournal
+	status := C.kailua_journal_decode(bytesPtr(encoded), C.uintptr_t(len(encoded)), &journal)
+	if err := statusError(status); err != nil {
+		return ProofJournal{}, err
+	}
+	return fromC(&journal), nil
+}
+
+// Encode packs the journal as committed to by the FPVM.
+func (j *ProofJournal) Encode() ([]byte, error) {
+	journal := j.toC()
+	out := make([]byte, 256)
+	var written C.uintptr_t
+	status := C.kailua_journal_encode(&journal, bytesPtr(out), C.uintptr_t(len(out)), &written)
+	if err := statusError(status); err != nil {
+		return nil, err
+	}
+	return out[:written], nil
+}
+
+// ProofFileJournal decodes the journal of the receipt in a proof file saved by kailua-host.
+func ProofFileJournal(data []byte) (ProofJournal, error) {
+	var journal C.KailuaProofJournal
+	status := C.kailua_proof_file_journal(bytesPtr(data), C.uintptr_t(len(data)), &journal)
+	if err := statusError(status); err != nil {
+		return ProofJournal{}, err
+	}
+	return fromC(&journal), nil
+}
+
+// VerifyProofFile verifies the receipt in a proof file against the image id and expected journal.
+func VerifyProofFile(data []byte, imageId [32]byte, expected ProofJournal) error {
+	journal := expected.toC()
+	status := C.kailua_proof_file_verify(
+		bytesPtr(data),
+		C.uintptr_t(len(data)),
+		(*[32]C.uint8_t)(unsafe.Pointer(&imageId)),
+		&journal,
+	)
+	return statusError(status)
+}
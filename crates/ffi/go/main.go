@@ -0,0 +1,55 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Prints the journal of a kailua-host proof file and verifies it against an image id.
+//
+// Usage: go run . <proof-file> <image-id-hex>
+package main
+
+import (
+	"encoding/hex"
+	"fmt"
+	"os"
+	"strings"
+
+	"github.com/risc0/kailua/crates/ffi/go/kailua"
+)
+
+func main() {
+	if len(os.Args) != 3 {
+		fmt.Fprintln(os.Stderr, "usage: go run . <proof-file> <image-id-hex>")
+		os.Exit(2)
+	}
+	data, err := os.ReadFile(os.Args[1])
+	if err != nil {
+		panic(err)
+	}
+	journal, err := kailua.ProofFileJournal(data)
+	if err != nil {
+		panic(err)
+	}
+	fmt.Printf("Claimed output 0x%x at block %d of chain %d\n",
+		journal.ClaimedL2OutputRoot, journal.ClaimedL2BlockNumber, journal.L2ChainId)
+
+	imageIdBytes, err := hex.DecodeString(strings.TrimPrefix(os.Args[2], "0x"))
+	if err != nil || len(imageIdBytes) != 32 {
+		panic("image id must be 32 hex-encoded bytes")
+	}
+	var imageId [32]byte
+	copy(imageId[:], imageIdBytes)
+	if err := kailua.VerifyProofFile(data, imageId, journal); err != nil {
+		panic(err)
+	}
+	fmt.Println("Proof verified.")
+}
@@ -0,0 +1,87 @@
+#ifndef KAILUA_H
+#define KAILUA_H
+
+/* Generated with cbindgen from crates/ffi. Do not edit manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The outcome of a call through the C ABI
+ */
+typedef enum KailuaStatus {
+  KAILUA_STATUS_OK = 0,
+  KAILUA_STATUS_NULL_POINTER = 1,
+  KAILUA_STATUS_INVALID_JOURNAL = 2,
+  KAILUA_STATUS_INVALID_PROOF_FILE = 3,
+  KAILUA_STATUS_VERIFICATION_FAILED = 4,
+  KAILUA_STATUS_BUFFER_TOO_SMALL = 5,
+} KailuaStatus;
+
+/**
+ * The fields of a proof journal
+ */
+typedef struct KailuaProofJournal {
+  uint8_t version;
+  uint8_t precondition_output[32];
+  uint8_t l1_head[32];
+  uint8_t agreed_l2_output_root[32];
+  uint8_t claimed_l2_output_root[32];
+  uint64_t claimed_l2_block_number;
+  uint8_t config_hash[32];
+  uint64_t l2_chain_id;
+} KailuaProofJournal;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Decodes a packed proof journal of any supported version.
+ *
+ * # Safety
+ * `encoded` must point to `len` readable bytes and `out` to a writable journal.
+ */
+KailuaStatus kailua_journal_decode(const uint8_t *encoded, uintptr_t len, struct KailuaProofJournal *out);
+
+/**
+ * Packs a proof journal as committed to by the FPVM, writing its length to `written`.
+ *
+ * # Safety
+ * `journal` must point to a readable journal, `out` to `capacity` writable bytes and `written`
+ * to a writable length.
+ */
+KailuaStatus kailua_journal_encode(const struct KailuaProofJournal *journal,
+                                   uint8_t *out,
+                                   uintptr_t capacity,
+                                   uintptr_t *written);
+
+/**
+ * Decodes the journal of the receipt in a proof file saved by `kailua-host`.
+ *
+ * # Safety
+ * `data` must point to `len` readable bytes and `out` to a writable journal.
+ */
+KailuaStatus kailua_proof_file_journal(const uint8_t *data,
+                                       uintptr_t len,
+                                       struct KailuaProofJournal *out);
+
+/**
+ * Verifies the receipt in a proof file against the FPVM image id and the expected journal.
+ *
+ * # Safety
+ * `data` must point to `len` readable bytes, `image_id` to 32 readable bytes and `expected` to a
+ * readable journal.
+ */
+KailuaStatus kailua_proof_file_verify(const uint8_t *data,
+                                      uintptr_t len,
+                                      const uint8_t (*image_id)[32],
+                                      const struct KailuaProofJournal *expected);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* KAILUA_H */
@@ -0,0 +1,177 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A C ABI over proof journal encoding, proof file parsing and receipt verification.
+//!
+//! The header in `include/kailua.h` is generated with `cbindgen` (see `just ffi-header`).
+
+use alloy_primitives::B256;
+use kailua_common::journal::ProofJournal;
+use kailua_sdk::receipt::{decode_proof_file, verify_receipt_journal};
+use std::slice;
+
+/// The outcome of a call through the C ABI
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KailuaStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidJournal = 2,
+    InvalidProofFile = 3,
+    VerificationFailed = 4,
+    BufferTooSmall = 5,
+}
+
+/// The fields of a proof journal
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KailuaProofJournal {
+    pub version: u8,
+    pub precondition_output: [u8; 32],
+    pub l1_head: [u8; 32],
+    pub agreed_l2_output_root: [u8; 32],
+    pub claimed_l2_output_root: [u8; 32],
+    pub claimed_l2_block_number: u64,
+    pub config_hash: [u8; 32],
+    pub l2_chain_id: u64,
+}
+
+impl From<ProofJournal> for KailuaProofJournal {
+    fn from(journal: ProofJournal) -> Self {
+        Self {
+            version: journal.version,
+            precondition_output: journal.precondition_output.0,
+            l1_head: journal.l1_head.0,
+            agreed_l2_output_root: journal.agreed_l2_output_root.0,
+            claimed_l2_output_root: journal.claimed_l2_output_root.0,
+            claimed_l2_block_number: journal.claimed_l2_block_number,
+            config_hash: journal.config_hash.0,
+            l2_chain_id: journal.l2_chain_id,
+        }
+    }
+}
+
+impl From<&KailuaProofJournal> for ProofJournal {
+    fn from(journal: &KailuaProofJournal) -> Self {
+        Self {
+            version: journal.version,
+            precondition_output: B256::from(journal.precondition_output),
+            l1_head: B256::from(journal.l1_head),
+            agreed_l2_output_root: B256::from(journal.agreed_l2_output_root),
+            claimed_l2_output_root: B256::from(journal.claimed_l2_output_root),
+            claimed_l2_block_number: journal.claimed_l2_block_number,
+            config_hash: B256::from(journal.config_hash),
+            l2_chain_id: journal.l2_chain_id,
+        }
+    }
+}
+
+/// Decodes a packed proof journal of any supported version.
+///
+/// # Safety
+/// `encoded` must point to `len` readable bytes and `out` to a writable journal.
+#[no_mangle]
+pub unsafe extern "C" fn kailua_journal_decode(
+    encoded: *const u8,
+    len: usize,
+    out: *mut KailuaProofJournal,
+) -> KailuaStatus {
+    if encoded.is_null() || out.is_null() {
+        return KailuaStatus::NullPointer;
+    }
+    match ProofJournal::decode_packed(slice::from_raw_parts(encoded, len)) {
+        Ok(journal) => {
+            *out = journal.into();
+            KailuaStatus::Ok
+        }
+        Err(_) => KailuaStatus::InvalidJournal,
+    }
+}
+
+/// Packs a proof journal as committed to by the FPVM, writing its length to `written`.
+///
+/// # Safety
+/// `journal` must point to a readable journal, `out` to `capacity` writable bytes and `written`
+/// to a writable length.
+#[no_mangle]
+pub unsafe extern "C" fn kailua_journal_encode(
+    journal: *const KailuaProofJournal,
+    out: *mut u8,
+    capacity: usize,
+    written: *mut usize,
+) -> KailuaStatus {
+    if journal.is_null() || out.is_null() || written.is_null() {
+        return KailuaStatus::NullPointer;
+    }
+    let encoded = ProofJournal::from(&*journal).encode_packed();
+    *written = encoded.len();
+    if capacity < encoded.len() {
+        return KailuaStatus::BufferTooSmall;
+    }
+    slice::from_raw_parts_mut(out, encoded.len()).copy_from_slice(&encoded);
+    KailuaStatus::Ok
+}
+
+/// Decodes the journal of the receipt in a proof file saved by `kailua-host`.
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `out` to a writable journal.
+#[no_mangle]
+pub unsafe extern "C" fn kailua_proof_file_journal(
+    data: *const u8,
+    len: usize,
+    out: *mut KailuaProofJournal,
+) -> KailuaStatus {
+    if data.is_null() || out.is_null() {
+        return KailuaStatus::NullPointer;
+    }
+    let Ok(receipt) = decode_proof_file(slice::from_raw_parts(data, len)) else {
+        return KailuaStatus::InvalidProofFile;
+    };
+    match ProofJournal::decode_packed(&receipt.journal.bytes) {
+        Ok(journal) => {
+            *out = journal.into();
+            KailuaStatus::Ok
+        }
+        Err(_) => KailuaStatus::InvalidJournal,
+    }
+}
+
+/// Verifies the receipt in a proof file against the FPVM image id and the expected journal.
+///
+/// # Safety
+/// `data` must point to `len` readable bytes, `image_id` to 32 readable bytes and `expected` to a
+/// readable journal.
+#[no_mangle]
+pub unsafe extern "C" fn kailua_proof_file_verify(
+    data: *const u8,
+    len: usize,
+    image_id: *const [u8; 32],
+    expected: *const KailuaProofJournal,
+) -> KailuaStatus {
+    if data.is_null() || image_id.is_null() || expected.is_null() {
+        return KailuaStatus::NullPointer;
+    }
+    let Ok(receipt) = decode_proof_file(slice::from_raw_parts(data, len)) else {
+        return KailuaStatus::InvalidProofFile;
+    };
+    match verify_receipt_journal(
+        &receipt,
+        B256::from(*image_id),
+        &ProofJournal::from(&*expected),
+    ) {
+        Ok(()) => KailuaStatus::Ok,
+        Err(_) => KailuaStatus::VerificationFailed,
+    }
+}
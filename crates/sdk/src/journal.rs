@@ -0,0 +1,74 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::network::Network;
+use alloy::primitives::{Address, B256};
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use kailua_common::errors::KailuaResult;
+use kailua_common::journal::ProofJournal;
+use kailua_contracts::KailuaTournament;
+use serde::{Deserialize, Serialize};
+
+pub use kailua_common::precondition::precondition_hash;
+
+/// The single-block transition a fault proof attests to within a game
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ProofClaim {
+    /// The precondition hash of the intermediate commitments, or zero for the root claim
+    pub precondition_output: B256,
+    /// The output root of the block preceding the claimed block
+    pub agreed_l2_output_root: B256,
+    pub claimed_l2_output_root: B256,
+    pub claimed_l2_block_number: u64,
+}
+
+/// Decodes a packed proof journal of any supported version
+pub fn decode_journal(encoded: &[u8]) -> KailuaResult<ProofJournal> {
+    ProofJournal::decode_packed(encoded)
+}
+
+/// Computes the journal a proof of the claim must commit to in order to be accepted by the game
+pub async fn expected_journal<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    provider: &P,
+    game: Address,
+    claim: &ProofClaim,
+) -> anyhow::Result<ProofJournal> {
+    let tournament = KailuaTournament::new(game, provider);
+    let l1_head = tournament.l1Head().call().await?.l1Head_.0.into();
+    let config_hash = tournament.configHash().call().await?.configHash_;
+    // Implementations predating journal versioning do not expose these
+    let version = tournament
+        .JOURNAL_VERSION()
+        .call()
+        .await
+        .map(|res| res._0)
+        .unwrap_or_default();
+    let l2_chain_id = tournament
+        .l2ChainId()
+        .call()
+        .await
+        .map(|res| res.l2ChainId_)
+        .unwrap_or_default();
+    Ok(ProofJournal {
+        version,
+        precondition_output: claim.precondition_output,
+        l1_head,
+        agreed_l2_output_root: claim.agreed_l2_output_root,
+        claimed_l2_output_root: claim.claimed_l2_output_root,
+        claimed_l2_block_number: claim.claimed_l2_block_number,
+        config_hash,
+        l2_chain_id,
+    })
+}
@@ -0,0 +1,33 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Client-side utilities for integrating with Kailua deployments.
+//!
+//! This crate covers what bridges, indexers and other integrators typically need without
+//! depending on the proposer and validator crates:
+//! - [outputs]: querying output roots finalized by resolved games
+//! - [journal]: decoding proof journals and computing the journal expected by a game
+//! - [receipt]: verifying that a receipt proves an expected journal
+//! - [proposal]: building the extra data and blob sidecar of a new proposal
+
+pub mod journal;
+pub mod outputs;
+pub mod proposal;
+pub mod receipt;
+
+pub use kailua_common::encoding::FieldEncoding;
+pub use kailua_common::journal::ProofJournal;
+
+/// The dispute game type of Kailua games
+pub const KAILUA_GAME_TYPE: u32 = 1337;
@@ -0,0 +1,119 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::KAILUA_GAME_TYPE;
+use alloy::network::Network;
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use kailua_contracts::{IDisputeGameFactory, KailuaTournament};
+use serde::{Deserialize, Serialize};
+
+/// The status of a game resolved in favor of its proposer
+pub const DEFENDER_WINS: u8 = 2;
+
+/// An output root finalized by a Kailua game resolved in favor of its proposer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResolvedOutput {
+    pub game_index: u64,
+    pub game_contract: Address,
+    /// The block number of the output finalized by the parent game
+    pub parent_l2_block_number: u64,
+    pub l2_block_number: u64,
+    pub output_root: B256,
+    pub resolved_at: u64,
+}
+
+impl ResolvedOutput {
+    /// Returns whether the block lies within the range of blocks sequenced by the game
+    pub fn covers(&self, block: u64) -> bool {
+        if self.parent_l2_block_number == self.l2_block_number {
+            block == self.l2_block_number
+        } else {
+            self.parent_l2_block_number < block && block <= self.l2_block_number
+        }
+    }
+}
+
+/// Returns the output finalized by the latest resolved Kailua game, if any
+pub async fn latest_resolved_output<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    provider: &P,
+    dispute_game_factory: Address,
+) -> anyhow::Result<Option<ResolvedOutput>> {
+    find_resolved_output(provider, dispute_game_factory, None).await
+}
+
+/// Returns the output finalized by the resolved Kailua game covering the block, if any
+pub async fn resolved_output_covering<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    provider: &P,
+    dispute_game_factory: Address,
+    block: u64,
+) -> anyhow::Result<Option<ResolvedOutput>> {
+    find_resolved_output(provider, dispute_game_factory, Some(block)).await
+}
+
+async fn find_resolved_output<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    provider: &P,
+    dispute_game_factory: Address,
+    block: Option<u64>,
+) -> anyhow::Result<Option<ResolvedOutput>> {
+    let dispute_game_factory = IDisputeGameFactory::new(dispute_game_factory, provider);
+    let game_count: u64 = dispute_game_factory
+        .gameCount()
+        .call()
+        .await?
+        .gameCount_
+        .to();
+    for game_index in (0..game_count).rev() {
+        let game = dispute_game_factory
+            .gameAtIndex(U256::from(game_index))
+            .call()
+            .await?;
+        if game.gameType_ != KAILUA_GAME_TYPE {
+            continue;
+        }
+        let tournament = KailuaTournament::new(game.proxy_, provider);
+        if tournament.status().call().await?._0 != DEFENDER_WINS {
+            continue;
+        }
+        let l2_block_number: u64 = tournament.l2BlockNumber().call().await?.l2BlockNumber_.to();
+        if block.is_some_and(|block| l2_block_number < block) {
+            // Resolved games extend each other, so earlier ones can not cover the block
+            return Ok(None);
+        }
+        let parent_address = tournament.parentGame().call().await?.parentGame_;
+        let parent_l2_block_number = if parent_address == game.proxy_ {
+            l2_block_number
+        } else {
+            KailuaTournament::new(parent_address, provider)
+                .l2BlockNumber()
+                .call()
+                .await?
+                .l2BlockNumber_
+                .to()
+        };
+        let output = ResolvedOutput {
+            game_index,
+            game_contract: game.proxy_,
+            parent_l2_block_number,
+            l2_block_number,
+            output_root: B256::from(tournament.rootClaim().call().await?.rootClaim_.0),
+            resolved_at: tournament.resolvedAt().call().await?._0,
+        };
+        if block.map_or(true, |block| output.covers(block)) {
+            return Ok(Some(output));
+        }
+    }
+    Ok(None)
+}
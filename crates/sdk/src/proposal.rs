@@ -0,0 +1,77 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::consensus::{Blob, BlobTransactionSidecar, EnvKzgSettings};
+use alloy::eips::eip4844::FIELD_ELEMENTS_PER_BLOB;
+use alloy::primitives::{Bytes, B256};
+use kailua_common::encoding::FieldEncoding;
+use kailua_common::vectors::encode_extra_data;
+
+/// The arguments of a `KailuaTreasury.propose` call along with its blob sidecar
+#[derive(Clone, Debug)]
+pub struct ProposalData {
+    pub root_claim: B256,
+    pub extra_data: Bytes,
+    pub sidecar: BlobTransactionSidecar,
+}
+
+/// Builds a proposal of the output root at the block extending the game at the parent index.
+///
+/// The intermediate outputs are those of every `proposalOutputCount` block between the parent's
+/// block and the proposed one (exclusive), and the duplication counter distinguishes proposals
+/// that would otherwise share the same extra data.
+pub fn build_proposal(
+    field_encoding: FieldEncoding,
+    output_root: B256,
+    l2_block_number: u64,
+    parent_index: u64,
+    dupe_counter: u64,
+    intermediate_outputs: &[B256],
+) -> anyhow::Result<ProposalData> {
+    let io_field_elements = intermediate_outputs
+        .iter()
+        .map(|output| field_encoding.output_to_fe(*output))
+        .collect::<Vec<_>>();
+    Ok(ProposalData {
+        root_claim: output_root,
+        extra_data: Bytes::from(encode_extra_data(
+            l2_block_number,
+            parent_index,
+            dupe_counter,
+        )),
+        sidecar: create_sidecar(&io_field_elements)?,
+    })
+}
+
+/// Packs the field elements into as few blobs as possible
+pub fn create_sidecar(io_field_elements: &[B256]) -> anyhow::Result<BlobTransactionSidecar> {
+    let settings = EnvKzgSettings::default();
+    let mut blobs = vec![];
+    let mut commitments = vec![];
+    let mut proofs = vec![];
+    for chunk in io_field_elements.chunks(FIELD_ELEMENTS_PER_BLOB as usize) {
+        let blob = Blob::right_padding_from(chunk.concat().as_slice());
+        let c_kzg_blob = c_kzg::Blob::from_bytes(blob.as_slice())?;
+        let commitment = c_kzg::KzgCommitment::blob_to_kzg_commitment(&c_kzg_blob, settings.get())?;
+        let proof = c_kzg::KzgProof::compute_blob_kzg_proof(
+            &c_kzg_blob,
+            &commitment.to_bytes(),
+            settings.get(),
+        )?;
+        blobs.push(blob);
+        commitments.push(commitment.to_bytes().into_inner().into());
+        proofs.push(proof.to_bytes().into_inner().into());
+    }
+    Ok(BlobTransactionSidecar::new(blobs, commitments, proofs))
+}
@@ -0,0 +1,68 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::journal::decode_journal;
+use alloy::primitives::B256;
+use anyhow::{bail, Context};
+use bincode::Options;
+use kailua_common::journal::ProofJournal;
+use risc0_zkvm::{Journal, Receipt};
+use serde::Deserialize;
+
+/// The encoding of proof files saved by `kailua-host`, mirroring `kailua_client::proof::Proof`
+#[derive(Deserialize)]
+enum ProofFile {
+    ZKVMReceipt(Box<Receipt>),
+    #[allow(dead_code)]
+    BoundlessSeal(Vec<u8>, Journal),
+}
+
+/// Decodes the receipt in a proof file saved by `kailua-host`
+pub fn decode_proof_file(data: &[u8]) -> anyhow::Result<Receipt> {
+    // bound allocations by the size of the file so that corrupted length prefixes fail cleanly
+    let options = bincode::options()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(data.len() as u64);
+    match options
+        .deserialize(data)
+        .context("Failed to decode proof file")?
+    {
+        ProofFile::ZKVMReceipt(receipt) => Ok(*receipt),
+        ProofFile::BoundlessSeal(..) => {
+            bail!("Proof file holds a Boundless seal, which is verified on-chain only")
+        }
+    }
+}
+
+/// Verifies the receipt against the FPVM image id and returns its decoded journal
+pub fn verify_receipt(receipt: &Receipt, image_id: B256) -> anyhow::Result<ProofJournal> {
+    receipt
+        .verify(image_id.0)
+        .context("Receipt verification failed")?;
+    decode_journal(&receipt.journal.bytes)
+}
+
+/// Verifies the receipt and checks that it commits to the expected journal
+pub fn verify_receipt_journal(
+    receipt: &Receipt,
+    image_id: B256,
+    expected: &ProofJournal,
+) -> anyhow::Result<()> {
+    verify_receipt(receipt, image_id)?;
+    if receipt.journal.bytes != expected.encode_packed() {
+        bail!("Receipt journal does not match the expected journal");
+    }
+    Ok(())
+}